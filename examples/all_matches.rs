@@ -0,0 +1,24 @@
+//! A custom detector built from the terminal identifier tables, reporting every matching
+//! terminal rather than only the first, and the quoted variables matched without their quotes.
+
+use terminal_relaunch::environment::SystemEnvironment;
+use terminal_relaunch::prelude::*;
+use terminal_relaunch::{get_possible_terminal_identifiers_for, quoted_detection_vars};
+
+fn main() {
+    let matches: Vec<_> = get_possible_terminal_identifiers_for(OperatingSystem::current())
+        .filter(|identifier| identifier.matches_in(&SystemEnvironment))
+        .map(|identifier| identifier.kind)
+        .collect();
+
+    if matches.is_empty() {
+        println!("No terminal identifiers matched.");
+    }
+    for kind in matches {
+        println!("Matched: {}", kind.verbose_format());
+    }
+
+    for var in quoted_detection_vars() {
+        println!("Quoted: {var}");
+    }
+}
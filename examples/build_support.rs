@@ -0,0 +1,19 @@
+//! A build script enabling `cfg(emoji_default)` when the target platform's default terminal renders emojis.
+//!
+//! Copy this into a `build.rs`, with `terminal_relaunch` (and the `build-support` feature) as a build dependency.
+
+use terminal_relaunch::build_support::{
+    capability_table_for, default_terminal_for, operating_system_for_target,
+};
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(emoji_default)");
+    println!("cargo:rerun-if-env-changed=CARGO_CFG_TARGET_OS");
+
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let terminal = default_terminal_for(operating_system_for_target(&target_os));
+
+    if capability_table_for(terminal).full_unicode {
+        println!("cargo:rustc-cfg=emoji_default");
+    }
+}
@@ -0,0 +1,94 @@
+//! The helper program relaunched by the end-to-end tests in `tests/real_relaunch.rs`.
+//!
+//! Started as `relaunch_probe <terminal-id> <result-file> [args..]`, it relaunches itself in the given terminal.
+//! Once relaunched, it writes its arguments, working directory, detected terminal and selected environment
+//! variables to the result file, and exits.
+//!
+//! # Notes
+//! The result file is written to a temporary file first, then renamed, so it's never read half written. Each
+//! line is a tab separated record, e.g. `arg\t<value>`, `cwd\t<path>` or `env\t<name>\t<value>`.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use terminal_relaunch::config::relaunch_config;
+use terminal_relaunch::{TerminalType, get_provider_for_terminal, has_been_relaunched};
+
+/// Environment variables recorded in the result file, for assertions and cleaning up the spawned window.
+const RECORDED_VARS: &[&str] = &[
+    "TERMINAL_RELAUNCH_E2E_TOKEN",
+    "KITTY_WINDOW_ID",
+    "KITTY_LISTEN_ON",
+    "WEZTERM_PANE",
+    "WT_SESSION",
+    "ITERM_SESSION_ID",
+    "LANG",
+    "LC_TIME",
+    "TZ",
+];
+
+/// Exit code when the terminal is unknown or has no provider.
+const EXIT_UNSUPPORTED: u8 = 2;
+/// Exit code when the terminal isn't installed.
+const EXIT_NOT_INSTALLED: u8 = 3;
+
+fn main() -> ExitCode {
+    let marker = relaunch_config().relaunched_argument();
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| *arg != marker)
+        .collect();
+    let [terminal_id, result_file, extra_args @ ..] = args.as_slice() else {
+        eprintln!("usage: relaunch_probe <terminal-id> <result-file> [args..]");
+        return ExitCode::FAILURE;
+    };
+
+    if has_been_relaunched() {
+        return match write_result(Path::new(result_file), extra_args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Failed to write {result_file}: {error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let Some(provider) = TerminalType::from_id(terminal_id).and_then(get_provider_for_terminal)
+    else {
+        eprintln!("No provider for terminal {terminal_id:?}.");
+        return ExitCode::from(EXIT_UNSUPPORTED);
+    };
+    if !provider.is_installed() {
+        eprintln!("{} is not installed.", provider.terminal_type());
+        return ExitCode::from(EXIT_NOT_INSTALLED);
+    }
+
+    match provider.relaunch_in_terminal() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!(
+                "Failed to relaunch in {}: {error}",
+                provider.terminal_type()
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Writes the result file of the relaunched probe.
+fn write_result(path: &Path, args: &[String]) -> std::io::Result<()> {
+    let mut lines = vec![
+        format!("terminal\t{}", terminal_relaunch::current_terminal().id()),
+        format!("cwd\t{}", std::env::current_dir()?.display()),
+    ];
+    lines.extend(args.iter().map(|arg| format!("arg\t{arg}")));
+    lines.extend(RECORDED_VARS.iter().filter_map(|name| {
+        std::env::var(name)
+            .ok()
+            .map(|value| format!("env\t{name}\t{value}"))
+    }));
+
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, lines.join("\n") + "\n")?;
+    std::fs::rename(partial, path)
+}
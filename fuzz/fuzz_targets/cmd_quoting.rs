@@ -0,0 +1,34 @@
+//! Quoting of the `cmd` command line used for network share working directories, where the program re-parses
+//! its arguments as `CommandLineToArgvW`, checked with a reference tokenizer.
+
+#![no_main]
+
+use std::path::Path;
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::terminal_providers::cmd_pushd_command_line;
+use terminal_relaunch_fuzz::windows_split;
+
+fuzz_target!(|data: (&str, &str, Vec<String>)| {
+    let (dir, exe, args) = data;
+    if [dir, exe]
+        .into_iter()
+        .chain(args.iter().map(String::as_str))
+        .any(|value| value.contains('\0'))
+    {
+        return;
+    }
+
+    let Some(command_line) = cmd_pushd_command_line(Path::new(dir), Path::new(exe), &args) else {
+        return;
+    };
+
+    // Everything is quoted, so `cmd` interprets nothing up to the program..
+    let program = command_line
+        .strip_prefix(&format!("pushd \"{dir}\" && "))
+        .expect("the directory is changed into first");
+
+    let mut expected = vec![exe.to_string()];
+    expected.extend(args);
+    assert_eq!(windows_split(program), expected);
+});
@@ -0,0 +1,34 @@
+//! Detection from the environment, e.g. `TERM`, `TERM_PROGRAM`, `COLORTERM` and `COLORFGBG`, simulated so the
+//! real environment is never read.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::OperatingSystem;
+use terminal_relaunch::simulation::{Scenario, simulate};
+use terminal_relaunch::theme::parse_colorfgbg;
+
+fuzz_target!(|data: (u8, Vec<(&str, &str)>)| {
+    let (os, vars) = data;
+    let os = [
+        OperatingSystem::Windows,
+        OperatingSystem::MacOS,
+        OperatingSystem::Linux,
+    ][usize::from(os % 3)];
+
+    let scenario = vars
+        .iter()
+        .fold(Scenario::new(os), |scenario, (name, value)| {
+            scenario.var(name, value)
+        });
+    let outcome = simulate(&scenario);
+    assert!(
+        outcome
+            .provider
+            .is_none_or(|provider| provider != outcome.terminal)
+    );
+
+    for (_, value) in vars {
+        let _ = parse_colorfgbg(value);
+    }
+});
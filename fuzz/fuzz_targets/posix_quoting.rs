@@ -0,0 +1,16 @@
+//! Quoting of arguments for POSIX shell command lines, re-parsed with a reference tokenizer.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::terminal_providers::posix_shell_command_line;
+use terminal_relaunch_fuzz::posix_split;
+
+fuzz_target!(|args: Vec<String>| {
+    // Arguments can't contain `NUL`, so no command line can either..
+    if args.iter().any(|arg| arg.contains('\0')) {
+        return;
+    }
+
+    assert_eq!(posix_split(&posix_shell_command_line(&args)), args);
+});
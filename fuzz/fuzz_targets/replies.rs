@@ -0,0 +1,21 @@
+//! Terminal replies to `XTGETTCAP`, `XTVERSION` and `OSC 11` queries, which anything able to write to the
+//! terminal (e.g. a remote program over `SSH`) can forge.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::query::{
+    parse_background_colour_reply, parse_xtgettcap_replies, parse_xtversion_reply,
+};
+
+fuzz_target!(|data: &[u8]| {
+    for (name, _) in parse_xtgettcap_replies(data) {
+        assert!(!name.is_empty());
+    }
+
+    if let Some(identity) = parse_xtversion_reply(data) {
+        assert!(!identity.is_empty());
+    }
+
+    let _ = parse_background_colour_reply(data);
+});
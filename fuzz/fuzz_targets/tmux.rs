@@ -0,0 +1,17 @@
+//! `tmux` output, i.e. `client_termfeatures`, `terminal-overrides` matched against a `TERM` and
+//! `allow-passthrough`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::tmux::{
+    parse_client_termfeatures, parse_passthrough, parse_terminal_overrides,
+};
+
+fuzz_target!(|data: (&str, &str)| {
+    let (output, term) = data;
+
+    let _ = parse_client_termfeatures(output);
+    let _ = parse_terminal_overrides(output, term);
+    let _ = parse_passthrough(output);
+});
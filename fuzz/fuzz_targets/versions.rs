@@ -0,0 +1,23 @@
+//! Version strings, from the environment (e.g. `TERM_PROGRAM_VERSION`), `mosh-server --version` output and
+//! version requirements.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::remote::parse_mosh_version;
+use terminal_relaunch::version::{TerminalVersion, VersionReq};
+
+fuzz_target!(|value: &str| {
+    // Parsed versions and requirements survive a round trip through their display..
+    if let Some(version) = TerminalVersion::parse(value) {
+        assert_eq!(TerminalVersion::parse(&version.to_string()), Some(version));
+    }
+    if let Some(version) = TerminalVersion::parse_dated(value) {
+        assert_eq!(version.patch, 0);
+    }
+    if let Ok(req) = value.parse::<VersionReq>() {
+        assert_eq!(req.to_string().parse::<VersionReq>(), Ok(req));
+    }
+
+    let _ = parse_mosh_version(value);
+});
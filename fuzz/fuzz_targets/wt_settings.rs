@@ -0,0 +1,12 @@
+//! `Windows Terminal` `settings.json` contents, which are user edited and often invalid.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use terminal_relaunch::fonts::wt_font_family_from_settings;
+
+fuzz_target!(|data: (&str, Option<&str>)| {
+    let (settings, profile_guid) = data;
+
+    let _ = wt_font_family_from_settings(settings, profile_guid);
+});
@@ -0,0 +1,119 @@
+//! Reference tokenizers the quoting fuzz targets re-parse command lines with, written from the specifications
+//! rather than from the quoting code they check.
+
+/// Characters a POSIX shell interprets when unquoted, which quoted command lines must never contain unquoted.
+const POSIX_METACHARACTERS: &[char] = &[
+    '|', '&', ';', '<', '>', '(', ')', '$', '`', '*', '?', '[', ']', '#', '~', '=', '%', '!', '{',
+    '}',
+];
+
+/// Splits a POSIX shell command line into words, as `sh` would without expansions.
+///
+/// # Panics
+/// If the command line has an unterminated quote, a trailing backslash, or an unquoted metacharacter, as any of
+/// them means quoting failed.
+#[must_use]
+pub fn posix_split(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().expect("unterminated single quote") {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().expect("unterminated double quote") {
+                        '"' => break,
+                        '\\' => match chars.next().expect("unterminated double quote") {
+                            c @ ('$' | '`' | '"' | '\\') => word.push(c),
+                            '\n' => {}
+                            c => word.extend(['\\', c]),
+                        },
+                        c @ ('$' | '`') => panic!("expansion `{c}` in double quotes"),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => match chars.next().expect("trailing backslash") {
+                '\n' => {}
+                c => word.get_or_insert_default().push(c),
+            },
+            c if POSIX_METACHARACTERS.contains(&c) => panic!("unquoted metacharacter `{c}`"),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+
+    words.extend(word);
+    words
+}
+
+/// Splits a `Windows` command line into arguments, as `CommandLineToArgvW` (and the `MSVC` runtime) would.
+///
+/// The program name is taken literally up to the next quote or whitespace, the arguments follow the backslash
+/// and quote rules, where `2n` backslashes before a quote are `n` backslashes and the quote toggles quoting,
+/// and `2n + 1` are `n` backslashes and a literal quote.
+#[must_use]
+pub fn windows_split(line: &str) -> Vec<String> {
+    let mut chars = line.chars().peekable();
+    let mut args = Vec::new();
+
+    // The program name..
+    let mut program = String::new();
+    if chars.next_if_eq(&'"').is_some() {
+        program.extend(chars.by_ref().take_while(|c| *c != '"'));
+    } else {
+        while let Some(c) = chars.next_if(|c| !matches!(c, ' ' | '\t')) {
+            program.push(c);
+        }
+    }
+    args.push(program);
+
+    let mut arg: Option<String> = None;
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' if !quoted => args.extend(arg.take()),
+            '\\' => {
+                let mut backslashes = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    backslashes += 1;
+                }
+                let arg = arg.get_or_insert_default();
+                if chars.next_if_eq(&'"').is_some() {
+                    arg.extend(std::iter::repeat_n('\\', backslashes / 2));
+                    if backslashes % 2 == 1 {
+                        arg.push('"');
+                    } else {
+                        quoted = !quoted;
+                    }
+                } else {
+                    arg.extend(std::iter::repeat_n('\\', backslashes));
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_default();
+                // A doubled quote in quotes is a literal quote..
+                if quoted && chars.next_if_eq(&'"').is_some() {
+                    arg.push('"');
+                } else {
+                    quoted = !quoted;
+                }
+            }
+            c => arg.get_or_insert_default().push(c),
+        }
+    }
+
+    args.extend(arg);
+    args
+}
@@ -0,0 +1,68 @@
+//! Pure lookups for build scripts, e.g. for baking a compile-time default from the capabilities of the
+//! target platform's default terminal.
+//!
+//! Everything in this module is deterministic: nothing reads the environment, registry or filesystem, and no
+//! detection statics are touched, so the results only depend on the arguments and the library version.
+//!
+//! # Notes
+//! Capabilities are the built-in table values, capability overrides and rules registered at runtime don't apply.
+//!
+//! # Example
+//! See `examples/build_support.rs`, a build script emitting `cargo:rustc-cfg=emoji_default`.
+//!
+//! ```
+//! use terminal_relaunch::build_support::{
+//!     capability_table_for, default_terminal_for, operating_system_for_target,
+//! };
+//!
+//! let os = operating_system_for_target("windows");
+//! let capabilities = capability_table_for(default_terminal_for(os));
+//! assert!(!capabilities.full_unicode);
+//! ```
+
+pub use crate::capabilities::TerminalCapabilities;
+use crate::{OperatingSystem, TerminalType};
+
+/// Returns the built-in capabilities of the given terminal type.
+#[inline]
+#[must_use]
+pub const fn capability_table_for(terminal: TerminalType) -> TerminalCapabilities {
+    TerminalCapabilities {
+        full_unicode: terminal.supports_full_unicode(),
+        rgb_ansi_colours: terminal.supports_rgb_ansi_colours(),
+        hyperlinks: terminal.supports_hyperlinks(),
+        score: terminal.capability_score(),
+    }
+}
+
+/// Returns the default terminal of the given operating system, see `get_default_terminal_for_os()`.
+#[inline]
+#[must_use]
+pub const fn default_terminal_for(os: OperatingSystem) -> TerminalType {
+    crate::get_default_terminal_for_os(os)
+}
+
+/// Returns the operating system for the given `target_os` value, e.g. `CARGO_CFG_TARGET_OS` in a build script.
+#[must_use]
+pub fn operating_system_for_target(target_os: &str) -> OperatingSystem {
+    match target_os {
+        "windows" => OperatingSystem::Windows,
+        "macos" => OperatingSystem::MacOS,
+        "linux" => OperatingSystem::Linux,
+        _ => OperatingSystem::Unknown,
+    }
+}
+
+/// Returns the stable identifier of the given terminal type, see `TerminalType::id()`.
+#[inline]
+#[must_use]
+pub const fn terminal_id(terminal: TerminalType) -> &'static str {
+    terminal.id()
+}
+
+/// Returns the terminal type with the given stable identifier, see `TerminalType::from_id()`.
+#[inline]
+#[must_use]
+pub fn terminal_from_id(id: &str) -> Option<TerminalType> {
+    TerminalType::from_id(id)
+}
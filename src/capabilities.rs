@@ -0,0 +1,1385 @@
+use std::fmt::Display;
+
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::TerminalType;
+use crate::environment::{Environment, SystemEnvironment};
+use crate::shell::Shell;
+use crate::version::{TerminalVersion, VersionReq};
+
+/// A partial set of terminal capabilities, where `None` means "leave as detected".
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartialCapabilities {
+    /// Overrides full unicode rendering support, if set.
+    pub full_unicode: Option<bool>,
+    /// Overrides RGB (ANSI) colour support, if set.
+    pub rgb_ansi_colours: Option<bool>,
+}
+
+impl PartialCapabilities {
+    /// Sets neither capability.
+    pub const NONE: Self = Self {
+        full_unicode: None,
+        rgb_ansi_colours: None,
+    };
+
+    /// Returns these capabilities, with those set by `upper` taking precedence.
+    #[inline]
+    #[must_use]
+    pub const fn overlay(self, upper: Self) -> Self {
+        Self {
+            full_unicode: match upper.full_unicode {
+                Some(supports) => Some(supports),
+                None => self.full_unicode,
+            },
+            rgb_ansi_colours: match upper.rgb_ansi_colours {
+                Some(supports) => Some(supports),
+                None => self.rgb_ansi_colours,
+            },
+        }
+    }
+}
+
+/// Represents who a capability hint comes from, see `set_capability_hint()`.
+///
+/// Capabilities are resolved through layers, from the lowest to the highest precedence, where the highest layer
+/// that sets a capability wins:
+///
+/// | Layer              | Set by                                                                          |
+/// |--------------------|---------------------------------------------------------------------------------|
+/// | Detection          | The built-in capability tables, the environment (e.g. `COLORTERM`) and probes.  |
+/// | Capability rules   | `register_capability_override()`, for specific terminals and versions.          |
+/// | `ParentProcess`    | Hints from a wrapping program, e.g. a test runner that captures output.         |
+/// | `User`             | Hints from the user, e.g. a configuration file or command line flag.            |
+/// | `Application`      | The application, including `set_unicode_support_override()` and                 |
+/// |                    | `set_rgb_ansi_override()`.                                                      |
+///
+/// Any hint also replaces the adjustments made to detection, e.g. for services or inside `tmux`.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::TerminalType;
+/// use terminal_relaunch::capabilities::{
+///     CapabilityRule, HintSource, PartialCapabilities, register_capability_override, resolve_full_unicode_support,
+///     set_capability_hint,
+/// };
+/// use terminal_relaunch::set_unicode_support_override;
+///
+/// let unicode = |value| PartialCapabilities { full_unicode: Some(value), ..PartialCapabilities::NONE };
+///
+/// // A rule enables full unicode in `xterm`..
+/// register_capability_override(CapabilityRule { terminal: TerminalType::Xterm, version: None, set: unicode(true) });
+/// assert!(resolve_full_unicode_support(TerminalType::Xterm, None));
+///
+/// // ..which the user's hint overrules, as the application's hint does in turn..
+/// set_capability_hint(HintSource::User, unicode(false));
+/// assert!(!resolve_full_unicode_support(TerminalType::Xterm, None));
+/// set_capability_hint(HintSource::Application, unicode(true));
+/// assert!(resolve_full_unicode_support(TerminalType::Xterm, None));
+///
+/// // ..and the global overrides are the application's hint..
+/// set_unicode_support_override(None);
+/// assert!(!resolve_full_unicode_support(TerminalType::Xterm, None));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HintSource {
+    /// A wrapping program, e.g. a test runner or file watcher, with the lowest precedence.
+    ParentProcess,
+    /// The user.
+    User,
+    /// The application, with the highest precedence.
+    Application,
+}
+
+impl HintSource {
+    /// Returns the name of the hint source.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ParentProcess => "Parent Process",
+            Self::User => "User",
+            Self::Application => "Application",
+        }
+    }
+}
+
+impl Display for HintSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Sets the capability hint of the given source, replacing its previous hint, see `HintSource` for how hints
+/// are resolved.
+///
+/// # Notes
+/// Hints set after `SUPPORTS_FULL_UNICODE` or `SUPPORTS_RGB_ANSI_COLOURS` have been read will not affect those
+/// statics.
+#[inline]
+pub fn set_capability_hint(source: HintSource, hint: PartialCapabilities) {
+    update_capability_hint(source, |current| *current = hint);
+}
+
+/// Updates the capability hint of the given source in place.
+pub(crate) fn update_capability_hint(
+    source: HintSource,
+    update: impl FnOnce(&mut PartialCapabilities),
+) {
+    update(
+        &mut crate::runtime::state()
+            .capability_hints
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)[source as usize],
+    );
+}
+
+/// Returns the capability hint of the given source.
+#[inline]
+#[must_use]
+pub fn capability_hint(source: HintSource) -> PartialCapabilities {
+    crate::runtime::state()
+        .capability_hints
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)[source as usize]
+}
+
+/// Removes the capability hints of every source, including the global overrides.
+#[inline]
+pub fn clear_capability_hints() {
+    *crate::runtime::state()
+        .capability_hints
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = [PartialCapabilities::NONE; 3];
+}
+
+/// Returns the capability hints of every source merged by precedence, see `HintSource`.
+#[must_use]
+pub fn resolved_capability_hints() -> PartialCapabilities {
+    HintSource::iter().fold(PartialCapabilities::NONE, |resolved, source| {
+        resolved.overlay(capability_hint(source))
+    })
+}
+
+/// A user supplied rule that adjusts the capabilities of a specific terminal, optionally
+/// limited to a range of terminal versions.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::TerminalType;
+/// use terminal_relaunch::capabilities::{
+///     CapabilityRule, PartialCapabilities, register_capability_override,
+/// };
+///
+/// // Kitty before 0.22 renders emojis incorrectly on some of our users machines..
+/// register_capability_override(CapabilityRule {
+///     terminal: TerminalType::Kitty,
+///     version: Some("<0.22".parse().unwrap()),
+///     set: PartialCapabilities {
+///         full_unicode: Some(false),
+///         ..Default::default()
+///     },
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CapabilityRule {
+    /// The terminal this rule applies to.
+    pub terminal: TerminalType,
+    /// The version requirement the terminal must meet, if `None` the rule applies to all versions.
+    pub version: Option<VersionReq>,
+    /// The capabilities to set when this rule applies.
+    pub set: PartialCapabilities,
+}
+
+impl CapabilityRule {
+    /// Returns `true` if this rule applies to the given terminal and version.
+    ///
+    /// # Notes
+    /// A rule with a version requirement never applies when the terminal version is unknown.
+    #[inline]
+    #[must_use]
+    pub fn applies_to(&self, terminal: TerminalType, version: Option<&TerminalVersion>) -> bool {
+        if self.terminal != terminal {
+            return false;
+        }
+
+        match (&self.version, version) {
+            (None, _) => true,
+            (Some(req), Some(version)) => req.matches(version),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Registers a capability override rule.
+///
+/// Rules are consulted after the built-in capability tables and before the capability hints (see `HintSource`),
+/// including the global overrides (e.g. `set_unicode_support_override`), the first matching rule in registration
+/// order wins.
+///
+/// # Notes
+/// *   Rules registered after `SUPPORTS_FULL_UNICODE` or `SUPPORTS_RGB_ANSI_COLOURS` have been read
+///     will not affect those statics.
+/// *   `doctor::DoctorReport` lists the registered rules, and which applies to the current terminal.
+/// *   With the `config` feature, rules can also be loaded from a config file, see `load_capability_rules()`.
+#[inline]
+pub fn register_capability_override(rule: CapabilityRule) {
+    crate::runtime::state()
+        .capability_rules
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .push(rule);
+}
+
+/// Removes all registered capability override rules.
+#[inline]
+pub fn clear_capability_overrides() {
+    crate::runtime::state()
+        .capability_rules
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clear();
+}
+
+/// Returns all currently registered capability override rules, in registration order.
+#[inline]
+#[must_use]
+pub fn capability_overrides() -> Vec<CapabilityRule> {
+    crate::runtime::state()
+        .capability_rules
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+/// The capability rules in a config file, see `load_capability_rules()`.
+#[cfg(feature = "config")]
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct CapabilityRulesFile {
+    /// The rules, in registration order.
+    capability_rules: Vec<CapabilityRule>,
+}
+
+/// Loads the capability rules from the `[[capability_rules]]` tables of the `TOML` config file at the given path,
+/// registering them in file order after any rules already registered, see `register_capability_override()`.
+///
+/// # Returns
+/// The loaded rules, none if the file has no `[[capability_rules]]` tables.
+///
+/// # Errors
+/// *   `RelaunchError::IOError` if the file can't be read.
+/// *   `RelaunchError::IOError` of kind `InvalidData` if the file isn't valid, e.g. a version requirement can't be
+///     parsed. No rules are registered in that case.
+///
+/// # Example
+/// ```rust,no_run
+/// use terminal_relaunch::capabilities::load_capability_rules;
+///
+/// // [[capability_rules]]
+/// // terminal = "Kitty"
+/// // version = "<0.22"
+/// // set = { full_unicode = false }
+/// let rules = load_capability_rules("terminal_relaunch.toml".as_ref()).unwrap();
+/// ```
+#[cfg(feature = "config")]
+pub fn load_capability_rules(
+    path: &std::path::Path,
+) -> crate::errors::TermResult<Vec<CapabilityRule>> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: CapabilityRulesFile = toml::from_str(&contents)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    crate::runtime::state()
+        .capability_rules
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .extend_from_slice(&file.capability_rules);
+
+    Ok(file.capability_rules)
+}
+
+/// Returns the first registered capability rule that applies to the given terminal and version, if any.
+#[inline]
+#[must_use]
+pub fn find_capability_rule(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+) -> Option<CapabilityRule> {
+    crate::runtime::state()
+        .capability_rules
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find(|rule| rule.applies_to(terminal, version))
+        .copied()
+}
+
+/// The first version of `Terminal.app` (`macOS 11`) rendering emojis and other wide characters correctly, see
+/// `version::apple_terminal_version_in()`.
+pub const MIN_APPLE_TERMINAL_UNICODE_VERSION: TerminalVersion = TerminalVersion::new(440, 0, 0);
+
+/// Returns the built-in full unicode support for the given terminal and version.
+///
+/// # Notes
+/// `Terminal.app` only renders full unicode since `MIN_APPLE_TERMINAL_UNICODE_VERSION`, so it's unsupported when
+/// the version is unknown, as `TerminalType::supports_full_unicode()`.
+#[inline]
+pub(crate) fn builtin_full_unicode_support(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+) -> bool {
+    if terminal == TerminalType::MacOS {
+        return version.is_some_and(|version| *version >= MIN_APPLE_TERMINAL_UNICODE_VERSION);
+    }
+
+    terminal.supports_full_unicode()
+}
+
+/// Resolves full unicode support for the given terminal and version, taking into account
+/// the built-in capability table, registered capability rules and the capability hints, in that order.
+#[inline]
+#[must_use]
+pub fn resolve_full_unicode_support(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+) -> bool {
+    if let Some(override_state) = crate::is_unicode_overridden() {
+        return override_state;
+    }
+
+    find_capability_rule(terminal, version)
+        .and_then(|rule| rule.set.full_unicode)
+        .unwrap_or_else(|| builtin_full_unicode_support(terminal, version))
+}
+
+/// Resolves RGB (ANSI) colour support for the given terminal and version, taking into account
+/// the built-in capability table, registered capability rules and the capability hints, in that order.
+#[inline]
+#[must_use]
+pub fn resolve_rgb_ansi_colour_support(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+) -> bool {
+    if let Some(override_state) = crate::is_rgb_ansi_overridden() {
+        return override_state;
+    }
+
+    find_capability_rule(terminal, version)
+        .and_then(|rule| rule.set.rgb_ansi_colours)
+        .unwrap_or_else(|| builtin_rgb_ansi_colour_support(terminal))
+}
+
+/// The capabilities of a terminal, see `resolve_capabilities()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerminalCapabilities {
+    /// If the terminal supports full unicode rendering.
+    pub full_unicode: bool,
+    /// If the terminal supports RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+    /// If the terminal supports `OSC 8` hyperlinks.
+    pub hyperlinks: bool,
+    /// The capability score of the terminal, see `TerminalType::capability_score()`.
+    pub score: u8,
+}
+
+impl TerminalCapabilities {
+    /// Returns the capabilities supported by these capabilities, but not by `other`.
+    ///
+    /// # Notes
+    /// The score is how much higher these capabilities score than `other`, or `0` if they don't.
+    ///
+    /// # Example
+    /// ```
+    /// use terminal_relaunch::capabilities::TerminalCapabilities;
+    ///
+    /// let cmd = TerminalCapabilities { rgb_ansi_colours: true, score: 20, ..Default::default() };
+    /// let wt = TerminalCapabilities { full_unicode: true, rgb_ansi_colours: true, hyperlinks: true, score: 45 };
+    ///
+    /// let gained = wt.difference(&cmd);
+    /// assert_eq!(gained, TerminalCapabilities { full_unicode: true, hyperlinks: true, score: 25, ..Default::default() });
+    /// assert_eq!(cmd.difference(&wt), TerminalCapabilities::default());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        Self {
+            full_unicode: self.full_unicode && !other.full_unicode,
+            rgb_ansi_colours: self.rgb_ansi_colours && !other.rgb_ansi_colours,
+            hyperlinks: self.hyperlinks && !other.hyperlinks,
+            score: self.score.saturating_sub(other.score),
+        }
+    }
+
+    /// Returns `true` if no capability is set, ignoring the score.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        !self.full_unicode && !self.rgb_ansi_colours && !self.hyperlinks
+    }
+}
+
+/// Resolves the capabilities of the given terminal and version.
+///
+/// # Notes
+/// Full unicode and RGB (ANSI) colour support are resolved as `resolve_full_unicode_support()` and
+/// `resolve_rgb_ansi_colour_support()`, hyperlink support and the score are the built-in values.
+#[inline]
+#[must_use]
+pub fn resolve_capabilities(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+) -> TerminalCapabilities {
+    TerminalCapabilities {
+        full_unicode: resolve_full_unicode_support(terminal, version),
+        rgb_ansi_colours: resolve_rgb_ansi_colour_support(terminal, version),
+        hyperlinks: terminal.supports_hyperlinks(),
+        score: terminal.capability_score(),
+    }
+}
+
+/// Represents how the capabilities of the current terminal are detected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum CapabilityDetection {
+    /// Capabilities are detected from the environment only.
+    #[default]
+    Environment,
+    /// Capabilities detected from the environment are confirmed by querying the terminal over the TTY,
+    /// e.g. using `XTGETTCAP`, which may take up to `PROBE_TIMEOUT`.
+    ///
+    /// # Notes
+    /// Requires the `tty-query` feature, otherwise this behaves like `Environment`.
+    Probe,
+}
+
+/// The maximum time to wait for the terminal to reply to a capability probe.
+pub const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Sets how the capabilities of the current terminal are detected.
+///
+/// # Notes
+/// The mode only affects `SUPPORTS_RGB_ANSI_COLOURS`, which is latched when the library is initialized, so it can
+/// only be staged before, see `runtime::init()`.
+///
+/// # Errors
+/// Returns `AlreadyInitialized` if the library has already been initialized, including by detecting the terminal.
+#[inline]
+pub fn set_capability_detection(
+    detection: CapabilityDetection,
+) -> Result<(), crate::runtime::AlreadyInitialized> {
+    crate::runtime::state().set_capability_detection(detection)
+}
+
+/// Returns how the capabilities of the current terminal are detected.
+#[inline]
+#[must_use]
+pub fn capability_detection() -> CapabilityDetection {
+    crate::runtime::state().capability_detection()
+}
+
+/// Resolves full unicode support for the current terminal, as `resolve_full_unicode_support()`.
+///
+/// # Notes
+/// Unless overridden, full unicode is unsupported when running as a service, see `service::is_service_context()`.
+#[must_use]
+pub fn resolve_current_full_unicode_support() -> bool {
+    resolve_full_unicode_support_for(
+        *crate::CURRENT_TERMINAL,
+        crate::CURRENT_TERMINAL_VERSION.as_ref(),
+        crate::service::is_service_context(),
+    )
+}
+
+/// Resolves full unicode support for a program in the given terminal, running as a service or not, see
+/// `resolve_current_full_unicode_support()`.
+pub(crate) fn resolve_full_unicode_support_for(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+    service_context: bool,
+) -> bool {
+    if crate::is_unicode_overridden().is_none() && service_context {
+        return false;
+    }
+
+    resolve_full_unicode_support(terminal, version)
+}
+
+/// Resolves RGB (ANSI) colour support for the current terminal, as `resolve_rgb_ansi_colour_support_in()` with
+/// the system environment, probing the terminal if `CapabilityDetection::Probe` is set.
+///
+/// # Notes
+/// *   Probing can only confirm support the environment didn't reveal (e.g. through SSH), as many capable
+///     terminals don't reply to probes at all.
+/// *   Unless overridden, RGB colours are unsupported when running as a service, see `service::is_service_context()`.
+#[must_use]
+pub fn resolve_current_rgb_ansi_colour_support() -> bool {
+    resolve_rgb_ansi_colour_support_for(
+        *crate::CURRENT_TERMINAL,
+        crate::CURRENT_TERMINAL_VERSION.as_ref(),
+        &SystemEnvironment,
+        crate::service::is_service_context(),
+        crate::tmux::tmux_features(),
+        probe_if_enabled,
+    )
+}
+
+/// Resolves RGB (ANSI) colour support for a program in the given terminal and environment, running as a service
+/// or not, see `resolve_current_rgb_ansi_colour_support()`.
+pub(crate) fn resolve_rgb_ansi_colour_support_for(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+    env: &(impl Environment + ?Sized),
+    service_context: bool,
+    tmux_features: Option<crate::tmux::TmuxFeatures>,
+    probe: impl FnOnce() -> bool,
+) -> bool {
+    if crate::is_rgb_ansi_overridden().is_none() && service_context {
+        return false;
+    }
+
+    resolve_rgb_ansi_colour_support_in(terminal, version, env, tmux_features, probe)
+}
+
+/// Probes the current terminal for RGB (ANSI) colour support, if `CapabilityDetection::Probe` is set.
+pub(crate) fn probe_if_enabled() -> bool {
+    capability_detection() == CapabilityDetection::Probe && probe_rgb_ansi_colour_support()
+}
+
+/// Resolves RGB (ANSI) colour support for the given terminal and version in the given environment, inside `tmux`
+/// passing through the given features, if any.
+///
+/// Support is first detected, from the built-in capability table, the environment (see `env_confirms_rgb_in()`)
+/// or by calling `probe` if neither confirms it. Registered capability rules and the capability hints then apply
+/// on top, as in `resolve_rgb_ansi_colour_support()`, so a rule disabling RGB colours is never overturned by what
+/// was detected.
+///
+/// # Notes
+/// Inside `tmux`, RGB colours are only detected if `tmux` passes them through, see `tmux::tmux_features()`, which
+/// is enough for `TerminalType::Tmux`, as the outer terminal is unknown.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use terminal_relaunch::TerminalType;
+/// use terminal_relaunch::capabilities::resolve_rgb_ansi_colour_support_in;
+///
+/// let truecolor = HashMap::from([("COLORTERM".to_string(), "truecolor".to_string())]);
+/// assert!(resolve_rgb_ansi_colour_support_in(TerminalType::Xterm, None, &truecolor, None, || false));
+/// assert!(resolve_rgb_ansi_colour_support_in(TerminalType::Xterm, None, &HashMap::<String, String>::new(), None, || true));
+/// assert!(!resolve_rgb_ansi_colour_support_in(TerminalType::Xterm, None, &HashMap::<String, String>::new(), None, || false));
+/// ```
+#[must_use]
+pub fn resolve_rgb_ansi_colour_support_in(
+    terminal: TerminalType,
+    version: Option<&TerminalVersion>,
+    env: &(impl Environment + ?Sized),
+    tmux_features: Option<crate::tmux::TmuxFeatures>,
+    probe: impl FnOnce() -> bool,
+) -> bool {
+    if let Some(override_state) = crate::is_rgb_ansi_overridden() {
+        return override_state;
+    }
+
+    let detected = match tmux_features {
+        Some(features) if !features.rgb => false,
+        Some(_) if terminal == TerminalType::Tmux => true,
+        _ => {
+            builtin_rgb_ansi_colour_support(terminal)
+                || env_confirms_rgb_in(terminal, env)
+                || probe()
+        }
+    };
+    find_capability_rule(terminal, version)
+        .and_then(|rule| rule.set.rgb_ansi_colours)
+        .unwrap_or(detected)
+}
+
+/// Probes the current terminal for RGB (ANSI) colour support.
+#[inline]
+fn probe_rgb_ansi_colour_support() -> bool {
+    #[cfg(feature = "tty-query")]
+    {
+        // Probing waits on the terminal, so the reply is only waited for once per process..
+        static PROBED_RGB_SUPPORT: std::sync::LazyLock<bool> = std::sync::LazyLock::new(|| {
+            crate::query::query_rgb_support(PROBE_TIMEOUT).unwrap_or(false)
+        });
+
+        *PROBED_RGB_SUPPORT
+    }
+
+    #[cfg(not(feature = "tty-query"))]
+    {
+        false
+    }
+}
+
+/// Represents what the default `Windows` console host does with VT sequences, see `console_vt_support()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConsoleVtSupport {
+    /// VT sequences can't be processed, only the console API and its 16 colours can be used.
+    Unsupported,
+    /// VT sequences are processed once the program enables VT processing for its console.
+    Available,
+    /// VT sequences are processed, as the hosting shell left VT processing enabled.
+    Enabled,
+}
+
+/// Returns what the default `Windows` console host does with VT sequences, for the given hosting shell (see
+/// `shell::host_shell()`) and `Windows` build (see `windows_build()`).
+///
+/// | Build                          | `PowerShell 7` | `CMD`, `Windows PowerShell` or unknown |
+/// |--------------------------------|----------------|----------------------------------------|
+/// | Before `MIN_WINDOWS_VT_BUILD`  | `Unsupported`  | `Unsupported`                          |
+/// | Since, or unknown              | `Enabled`      | `Available`                            |
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::MIN_WINDOWS_VT_BUILD;
+/// use terminal_relaunch::capabilities::{ConsoleVtSupport, console_vt_support};
+/// use terminal_relaunch::shell::Shell;
+///
+/// // `Windows 8.1`, whatever the shell..
+/// assert_eq!(console_vt_support(Some(Shell::PowerShell), Some(9600)), ConsoleVtSupport::Unsupported);
+/// assert_eq!(console_vt_support(Some(Shell::Cmd), Some(9600)), ConsoleVtSupport::Unsupported);
+///
+/// // `Windows 10`, where `pwsh` in the console host leaves VT processing on, unlike `CMD`..
+/// let build = Some(MIN_WINDOWS_VT_BUILD);
+/// assert_eq!(console_vt_support(Some(Shell::PowerShell), build), ConsoleVtSupport::Enabled);
+/// assert_eq!(console_vt_support(Some(Shell::Cmd), build), ConsoleVtSupport::Available);
+/// assert_eq!(console_vt_support(Some(Shell::WindowsPowerShell), build), ConsoleVtSupport::Available);
+/// assert_eq!(console_vt_support(None, None), ConsoleVtSupport::Available);
+/// ```
+#[inline]
+#[must_use]
+pub fn console_vt_support(shell: Option<Shell>, build: Option<u32>) -> ConsoleVtSupport {
+    if build.is_some_and(|build| build < crate::MIN_WINDOWS_VT_BUILD) {
+        ConsoleVtSupport::Unsupported
+    } else if shell.is_some_and(|shell| shell.enables_virtual_terminal()) {
+        ConsoleVtSupport::Enabled
+    } else {
+        ConsoleVtSupport::Available
+    }
+}
+
+/// Returns what the console host of the running `Windows` does with VT sequences, see `console_vt_support()`.
+#[inline]
+#[must_use]
+pub fn current_console_vt_support() -> ConsoleVtSupport {
+    console_vt_support(crate::shell::host_shell(), crate::windows_build())
+}
+
+/// Returns the built-in RGB (ANSI) colour support for the given terminal on the running platform.
+///
+/// # Notes
+/// The console host on legacy `Windows` builds cannot process VT sequences at all, so is limited to
+/// the 16 console colours, see `current_console_vt_support()`.
+#[inline]
+pub(crate) fn builtin_rgb_ansi_colour_support(terminal: TerminalType) -> bool {
+    builtin_rgb_ansi_colour_support_with(terminal, current_console_vt_support())
+}
+
+/// Returns the built-in RGB (ANSI) colour support for the given terminal, with the given console VT support.
+#[inline]
+fn builtin_rgb_ansi_colour_support_with(terminal: TerminalType, vt: ConsoleVtSupport) -> bool {
+    if terminal == TerminalType::WindowsCMD && vt == ConsoleVtSupport::Unsupported {
+        return false;
+    }
+
+    terminal.supports_rgb_ansi_colours()
+}
+
+/// Environment variable that disables colour output when set to a non-empty value, see <https://no-color.org>.
+pub const NO_COLOR_VAR: &str = "NO_COLOR";
+/// Environment variable that forces colour output, optionally at a level (`0`-`3`).
+pub const FORCE_COLOR_VAR: &str = "FORCE_COLOR";
+/// Environment variable set to `truecolor` or `24bit` by terminals (or users) to report RGB colour support.
+pub const COLORTERM_VAR: &str = "COLORTERM";
+
+/// Returns `true` if `COLORTERM` reports RGB colours, for terminals whose RGB support depends on how they
+/// were built or configured, i.e. `xterm` and `st`.
+///
+/// # Notes
+/// Other terminals ignore `COLORTERM`, as it's inherited by programs started from a terminal that sets it.
+#[must_use]
+pub fn colorterm_confirms_rgb_in(
+    terminal: TerminalType,
+    env: &(impl Environment + ?Sized),
+) -> bool {
+    matches!(terminal, TerminalType::Xterm | TerminalType::St)
+        && env.var(COLORTERM_VAR).is_some_and(|value| {
+            value.eq_ignore_ascii_case("truecolor") || value.eq_ignore_ascii_case("24bit")
+        })
+}
+
+/// Environment variable set by `ConEmu` (and `Cmder`, which bundles it) to `ON` when ANSI sequences are processed.
+pub const CONEMU_ANSI_VAR: &str = "ConEmuANSI";
+
+/// Returns `true` if the environment confirms RGB colours for terminals whose support depends on their
+/// configuration, i.e. `COLORTERM` for `xterm` and `st` (see `colorterm_confirms_rgb_in()`), `CONEMU_ANSI_VAR`
+/// being `ON` for `ConEmu`, and the terminal inside `Emacs` being `vterm` or `eat` (see
+/// `emacs::detect_emacs_terminal_in()`).
+#[must_use]
+pub fn env_confirms_rgb_in(terminal: TerminalType, env: &(impl Environment + ?Sized)) -> bool {
+    colorterm_confirms_rgb_in(terminal, env)
+        || (terminal == TerminalType::ConEmu
+            && env
+                .var(CONEMU_ANSI_VAR)
+                .is_some_and(|value| value.eq_ignore_ascii_case("ON")))
+        || (terminal == TerminalType::Emacs
+            && crate::emacs::detect_emacs_terminal_in(env).supports_rgb_ansi_colours())
+}
+
+/// Represents the number of colours the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColourDepth {
+    /// No colours should be used.
+    NoColour,
+    /// The 16 basic ANSI colours.
+    Ansi16,
+    /// The 256 colour ANSI palette.
+    Ansi256,
+    /// RGB (24-bit) colours.
+    TrueColour,
+}
+
+impl ColourDepth {
+    /// Returns the name of the colour depth.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NoColour => "No Colour",
+            Self::Ansi16 => "16 Colours",
+            Self::Ansi256 => "256 Colours",
+            Self::TrueColour => "True Colour",
+        }
+    }
+}
+
+impl std::fmt::Display for ColourDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the effective colour depth of the current terminal, see `resolve_colour_depth()`.
+///
+/// # Notes
+/// Inside `tmux`, the depth is capped to what `tmux` passes through, see `tmux::tmux_features()`, and in
+/// remote sessions to what the session passes through, see `remote::max_colour_depth()`. When running as a
+/// service, no colours are used unless `FORCE_COLOR` is set. No colours are used with plain output, see
+/// `output::plain_output()`.
+#[inline]
+#[must_use]
+pub fn colour_depth() -> ColourDepth {
+    if crate::output::plain_output() {
+        return ColourDepth::NoColour;
+    }
+    if crate::service::is_service_context() && SystemEnvironment.var(FORCE_COLOR_VAR).is_none() {
+        return ColourDepth::NoColour;
+    }
+
+    let depth = resolve_colour_depth(&SystemEnvironment, crate::supports_rgb_ansi_colours())
+        .min(crate::remote::max_colour_depth());
+
+    match crate::tmux::tmux_features() {
+        Some(features) => depth.min(features.max_colour_depth()),
+        None => depth,
+    }
+}
+
+/// Resolves the colour depth from the given environment, and whether RGB (ANSI) colours are supported.
+///
+/// In order of precedence:
+/// *   `FORCE_COLOR` set to `0`/`false` disables colours, `1`, `2` or `3` selects a depth, and any other
+///     value forces at least 16 colours.
+/// *   `NO_COLOR` set to a non-empty value disables colours.
+/// *   RGB (ANSI) colour support selects true colour.
+/// *   A `TERM` containing `256color` selects 256 colours, otherwise 16 colours.
+#[must_use]
+pub fn resolve_colour_depth(
+    env: &(impl Environment + ?Sized),
+    rgb_ansi_colours: bool,
+) -> ColourDepth {
+    let detected = if rgb_ansi_colours {
+        ColourDepth::TrueColour
+    } else if env
+        .var(crate::terminal_providers::TERM_VAR)
+        .is_some_and(|term| term.contains("256color"))
+    {
+        ColourDepth::Ansi256
+    } else {
+        ColourDepth::Ansi16
+    };
+
+    if let Some(force) = env.var(FORCE_COLOR_VAR) {
+        return match force.trim() {
+            "0" | "false" => ColourDepth::NoColour,
+            "1" => ColourDepth::Ansi16,
+            "2" => ColourDepth::Ansi256,
+            "3" => ColourDepth::TrueColour,
+            _ => detected,
+        };
+    }
+    if env.var(NO_COLOR_VAR).is_some_and(|value| !value.is_empty()) {
+        return ColourDepth::NoColour;
+    }
+
+    detected
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Barrier;
+
+    use strum::IntoEnumIterator;
+
+    use super::{
+        CapabilityRule, ConsoleVtSupport, HintSource, PartialCapabilities,
+        builtin_rgb_ansi_colour_support_with, capability_hint, capability_overrides,
+        clear_capability_hints, clear_capability_overrides, console_vt_support,
+        find_capability_rule, register_capability_override, resolve_full_unicode_support,
+        resolve_rgb_ansi_colour_support, resolve_rgb_ansi_colour_support_in,
+        resolved_capability_hints, set_capability_hint,
+    };
+    use crate::TerminalType;
+    use crate::doctor::DoctorReport;
+    use crate::info::TerminalInfo;
+    use crate::shell::Shell;
+    use crate::test_support::writing_globals;
+    use crate::tmux::TmuxFeatures;
+    use crate::version::{TerminalVersion, VersionReq};
+    use crate::{MIN_WINDOWS_TERMINAL_BUILD, MIN_WINDOWS_VT_BUILD};
+
+    /// Threads registering rules at once.
+    const REGISTRARS: usize = 4;
+    /// Rules registered by each thread.
+    const RULES_PER_REGISTRAR: u32 = 64;
+
+    fn rule(
+        terminal: TerminalType,
+        version: Option<&str>,
+        full_unicode: Option<bool>,
+    ) -> CapabilityRule {
+        CapabilityRule {
+            terminal,
+            version: version.map(|version| version.parse().unwrap()),
+            set: PartialCapabilities {
+                full_unicode,
+                ..PartialCapabilities::NONE
+            },
+        }
+    }
+
+    #[test]
+    fn rules_apply_over_the_builtin_tables_and_under_the_overrides() {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        let version = TerminalVersion::new(0, 21, 0);
+
+        assert!(resolve_full_unicode_support(
+            TerminalType::Kitty,
+            Some(&version)
+        ));
+        register_capability_override(rule(TerminalType::Kitty, Some("<0.22"), Some(false)));
+        assert!(!resolve_full_unicode_support(
+            TerminalType::Kitty,
+            Some(&version)
+        ));
+        // ..only the capabilities the rule sets are changed..
+        assert!(resolve_rgb_ansi_colour_support(
+            TerminalType::Kitty,
+            Some(&version)
+        ));
+
+        crate::set_unicode_support_override(Some(true));
+        let overridden = resolve_full_unicode_support(TerminalType::Kitty, Some(&version));
+        crate::set_unicode_support_override(None);
+        clear_capability_overrides();
+
+        assert!(overridden);
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins_in_registration_order() {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        let version = TerminalVersion::new(0, 21, 3);
+
+        register_capability_override(rule(TerminalType::Kitty, Some("0.21.3"), None));
+        register_capability_override(rule(TerminalType::Kitty, Some("<0.22"), Some(false)));
+        register_capability_override(rule(TerminalType::Kitty, None, Some(true)));
+        let first = find_capability_rule(TerminalType::Kitty, Some(&version));
+        // A rule setting nothing still wins, leaving the built-in support..
+        let unicode = resolve_full_unicode_support(TerminalType::Kitty, Some(&version));
+        let later =
+            find_capability_rule(TerminalType::Kitty, Some(&TerminalVersion::new(0, 21, 4)));
+        let report = DoctorReport::for_info_in(
+            TerminalInfo {
+                version: Some(version),
+                ..TerminalInfo::new(TerminalType::Kitty)
+            },
+            &std::collections::HashMap::<String, String>::new(),
+        );
+        clear_capability_overrides();
+
+        assert_eq!(first, Some(rule(TerminalType::Kitty, Some("0.21.3"), None)));
+        assert!(unicode);
+        assert_eq!(
+            later,
+            Some(rule(TerminalType::Kitty, Some("<0.22"), Some(false)))
+        );
+        assert_eq!(report.applied_rule(), first.as_ref());
+        assert_eq!(
+            report
+                .capability_rules
+                .iter()
+                .map(|status| status.applied)
+                .collect::<Vec<_>>(),
+            [true, false, false]
+        );
+    }
+
+    #[test]
+    fn version_requirements_match_at_their_boundaries() {
+        let version = |version: &str| TerminalVersion::parse(version).unwrap();
+        for (requirement, matching, other) in [
+            ("<0.22", ["0.21.99", "0.0.1"], ["0.22", "0.22.0"]),
+            (">=0.22", ["0.22", "1.0.0"], ["0.21.99", "0.0.0"]),
+            ("0.22", ["0.22.0", "0.22"], ["0.22.1", "0.21.0"]),
+            ("=0.22.1", ["0.22.1", "0.22.1"], ["0.22.0", "0.22.2"]),
+        ] {
+            let limited = rule(TerminalType::Kitty, Some(requirement), Some(false));
+            for matching in matching {
+                assert!(
+                    limited.applies_to(TerminalType::Kitty, Some(&version(matching))),
+                    "{requirement} {matching}"
+                );
+            }
+            for other in other {
+                assert!(
+                    !limited.applies_to(TerminalType::Kitty, Some(&version(other))),
+                    "{requirement} {other}"
+                );
+            }
+            // Rules with a version requirement never apply to unknown versions, or other terminals..
+            assert!(
+                !limited.applies_to(TerminalType::Kitty, None),
+                "{requirement}"
+            );
+            assert!(
+                !limited.applies_to(TerminalType::WezTerm, Some(&version(matching[0]))),
+                "{requirement}"
+            );
+        }
+
+        let any_version = rule(TerminalType::Kitty, None, Some(false));
+        assert!(any_version.applies_to(TerminalType::Kitty, None));
+        assert_eq!(
+            " >= 1.2 ".parse(),
+            Ok(VersionReq::AtLeast(TerminalVersion::new(1, 2, 0)))
+        );
+        assert!("~1.2".parse::<VersionReq>().is_err());
+        assert!("<".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn rules_registered_concurrently_are_all_kept_in_order() {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+
+        let barrier = Barrier::new(REGISTRARS * 2);
+        std::thread::scope(|scope| {
+            for registrar in 0..REGISTRARS {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    barrier.wait();
+                    for index in 0..RULES_PER_REGISTRAR {
+                        let version =
+                            TerminalVersion::new(u32::try_from(registrar).unwrap(), index, 0);
+                        register_capability_override(CapabilityRule {
+                            terminal: TerminalType::Kitty,
+                            version: Some(VersionReq::Exact(version)),
+                            set: PartialCapabilities::NONE,
+                        });
+                    }
+                });
+                // ..while others read the rules..
+                scope.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..RULES_PER_REGISTRAR {
+                        let rules = capability_overrides();
+                        assert!(rules.len() <= REGISTRARS * RULES_PER_REGISTRAR as usize);
+                        let _ = find_capability_rule(TerminalType::Kitty, None);
+                    }
+                });
+            }
+        });
+
+        let rules = capability_overrides();
+        clear_capability_overrides();
+
+        assert_eq!(rules.len(), REGISTRARS * RULES_PER_REGISTRAR as usize);
+        for registrar in 0..REGISTRARS {
+            let registered: Vec<u32> = rules
+                .iter()
+                .filter_map(|rule| match rule.version {
+                    Some(VersionReq::Exact(version)) if version.major as usize == registrar => {
+                        Some(version.minor)
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(registered, (0..RULES_PER_REGISTRAR).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn legacy_windows_builds_have_no_vt_and_16_colours() {
+        let shells = [
+            None,
+            Some(Shell::Cmd),
+            Some(Shell::WindowsPowerShell),
+            Some(Shell::PowerShell),
+        ];
+        for build in [9200, 9600, MIN_WINDOWS_VT_BUILD - 1] {
+            for shell in shells {
+                assert_eq!(
+                    console_vt_support(shell, Some(build)),
+                    ConsoleVtSupport::Unsupported,
+                    "{build} {shell:?}"
+                );
+            }
+        }
+        for build in [
+            Some(MIN_WINDOWS_VT_BUILD),
+            Some(MIN_WINDOWS_TERMINAL_BUILD),
+            Some(22631),
+            None,
+        ] {
+            assert_eq!(
+                console_vt_support(Some(Shell::PowerShell), build),
+                ConsoleVtSupport::Enabled
+            );
+            for shell in [None, Some(Shell::Cmd), Some(Shell::WindowsPowerShell)] {
+                assert_eq!(
+                    console_vt_support(shell, build),
+                    ConsoleVtSupport::Available,
+                    "{build:?} {shell:?}"
+                );
+            }
+        }
+
+        // Only the console host is limited to its 16 colours..
+        let rgb = |terminal, build| {
+            builtin_rgb_ansi_colour_support_with(terminal, console_vt_support(None, build))
+        };
+        assert!(!rgb(TerminalType::WindowsCMD, Some(9600)));
+        assert_eq!(
+            rgb(TerminalType::WindowsCMD, Some(MIN_WINDOWS_VT_BUILD)),
+            TerminalType::WindowsCMD.supports_rgb_ansi_colours()
+        );
+        for build in [Some(9600), Some(MIN_WINDOWS_VT_BUILD), None] {
+            assert!(rgb(TerminalType::WindowsTerminal, build));
+        }
+    }
+
+    #[test]
+    fn hint_precedence_is_exhaustive() {
+        let _globals = writing_globals();
+        let saved_rules = capability_overrides();
+        let saved_hints = HintSource::iter().map(capability_hint).collect::<Vec<_>>();
+        let values = [None, Some(false), Some(true)];
+
+        // Every value of every layer, for each capability independently of the other..
+        for rule_value in values {
+            for parent in values {
+                for user in values {
+                    for application in values {
+                        let layers = [rule_value, parent, user, application];
+                        let expected = layers.iter().rev().find_map(|value| *value);
+
+                        for (unicode, rgb) in [(true, false), (false, true), (true, true)] {
+                            let set = |value: Option<bool>| PartialCapabilities {
+                                full_unicode: value.filter(|_| unicode),
+                                rgb_ansi_colours: value.filter(|_| rgb),
+                            };
+                            clear_capability_overrides();
+                            if rule_value.is_some() {
+                                register_capability_override(CapabilityRule {
+                                    terminal: TerminalType::Xterm,
+                                    version: None,
+                                    set: set(rule_value),
+                                });
+                            }
+                            for (source, value) in
+                                HintSource::iter().zip([parent, user, application])
+                            {
+                                set_capability_hint(source, set(value));
+                            }
+
+                            // `xterm` supports neither by default, and a capability no layer sets stays as detected..
+                            let layers = format!("{layers:?} unicode: {unicode}, rgb: {rgb}");
+                            assert_eq!(
+                                resolve_full_unicode_support(TerminalType::Xterm, None),
+                                expected.filter(|_| unicode).unwrap_or(false),
+                                "{layers}"
+                            );
+                            assert_eq!(
+                                resolve_rgb_ansi_colour_support(TerminalType::Xterm, None),
+                                expected.filter(|_| rgb).unwrap_or(false),
+                                "{layers}"
+                            );
+                            // ..or as in the built-in tables, for terminals the rule doesn't apply to..
+                            assert_eq!(
+                                resolve_full_unicode_support(TerminalType::Kitty, None),
+                                [parent, user, application]
+                                    .iter()
+                                    .rev()
+                                    .find_map(|value| *value)
+                                    .filter(|_| unicode)
+                                    .unwrap_or(true),
+                                "{layers}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        clear_capability_overrides();
+        for rule in saved_rules {
+            register_capability_override(rule);
+        }
+        for (source, hint) in HintSource::iter().zip(saved_hints) {
+            set_capability_hint(source, hint);
+        }
+    }
+
+    #[test]
+    fn hints_merge_per_capability_and_the_global_overrides_are_the_application_layer() {
+        let _globals = writing_globals();
+        let saved_hints = HintSource::iter().map(capability_hint).collect::<Vec<_>>();
+        clear_capability_hints();
+
+        // Each capability is taken from the highest source setting it..
+        set_capability_hint(
+            HintSource::ParentProcess,
+            PartialCapabilities {
+                full_unicode: Some(false),
+                rgb_ansi_colours: Some(false),
+            },
+        );
+        set_capability_hint(
+            HintSource::User,
+            PartialCapabilities {
+                rgb_ansi_colours: Some(true),
+                ..PartialCapabilities::NONE
+            },
+        );
+        assert_eq!(
+            resolved_capability_hints(),
+            PartialCapabilities {
+                full_unicode: Some(false),
+                rgb_ansi_colours: Some(true),
+            }
+        );
+
+        // ..the global overrides only change their own capability of the application's hint..
+        crate::set_unicode_support_override(Some(true));
+        assert_eq!(
+            capability_hint(HintSource::Application),
+            PartialCapabilities {
+                full_unicode: Some(true),
+                ..PartialCapabilities::NONE
+            }
+        );
+        crate::set_rgb_ansi_override(Some(false));
+        assert_eq!(
+            resolved_capability_hints(),
+            PartialCapabilities {
+                full_unicode: Some(true),
+                rgb_ansi_colours: Some(false),
+            }
+        );
+        assert_eq!(crate::is_unicode_overridden(), Some(true));
+        assert_eq!(crate::is_rgb_ansi_overridden(), Some(false));
+
+        // ..and clearing them falls back to the lower sources, as does replacing a source's hint..
+        crate::set_unicode_support_override(None);
+        crate::set_rgb_ansi_override(None);
+        assert_eq!(
+            capability_hint(HintSource::Application),
+            PartialCapabilities::NONE
+        );
+        assert_eq!(crate::is_unicode_overridden(), Some(false));
+        set_capability_hint(HintSource::User, PartialCapabilities::NONE);
+        assert_eq!(crate::is_rgb_ansi_overridden(), Some(false));
+        clear_capability_hints();
+        assert_eq!(resolved_capability_hints(), PartialCapabilities::NONE);
+        assert_eq!(crate::is_unicode_overridden(), None);
+
+        for (source, hint) in HintSource::iter().zip(saved_hints) {
+            set_capability_hint(source, hint);
+        }
+    }
+
+    #[test]
+    fn rules_apply_over_the_probe_and_tmux() {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        let none = HashMap::<String, String>::new();
+        let resolve = |terminal, tmux: Option<TmuxFeatures>, probe: bool| {
+            resolve_rgb_ansi_colour_support_in(terminal, None, &none, tmux, || probe)
+        };
+        let passed_through = TmuxFeatures {
+            rgb: true,
+            ..TmuxFeatures::default()
+        };
+
+        // Detected from the probe, or what `tmux` passes through..
+        assert!(resolve(TerminalType::Xterm, None, true));
+        assert!(!resolve(TerminalType::Xterm, None, false));
+        assert!(resolve(TerminalType::Tmux, Some(passed_through), false));
+        assert!(!resolve(
+            TerminalType::Kitty,
+            Some(TmuxFeatures::default()),
+            true
+        ));
+
+        // ..while a rule has the last word over each..
+        register_capability_override(CapabilityRule {
+            terminal: TerminalType::Xterm,
+            version: None,
+            set: PartialCapabilities {
+                rgb_ansi_colours: Some(false),
+                ..PartialCapabilities::NONE
+            },
+        });
+        register_capability_override(CapabilityRule {
+            terminal: TerminalType::Tmux,
+            version: None,
+            set: PartialCapabilities {
+                rgb_ansi_colours: Some(false),
+                ..PartialCapabilities::NONE
+            },
+        });
+        register_capability_override(CapabilityRule {
+            terminal: TerminalType::Kitty,
+            version: None,
+            set: PartialCapabilities {
+                rgb_ansi_colours: Some(true),
+                ..PartialCapabilities::NONE
+            },
+        });
+        let probed_xterm = resolve(TerminalType::Xterm, None, true);
+        let passed_through_tmux = resolve(TerminalType::Tmux, Some(passed_through), true);
+        let kitty_in_tmux = resolve(TerminalType::Kitty, Some(TmuxFeatures::default()), false);
+        clear_capability_overrides();
+
+        assert!(!probed_xterm);
+        assert!(!passed_through_tmux);
+        assert!(kitty_in_tmux);
+    }
+
+    #[test]
+    fn rules_apply_over_the_environment() {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        let env = |vars: &[(&str, &str)]| -> HashMap<String, String> {
+            vars.iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect()
+        };
+        let cases = [
+            (TerminalType::Xterm, env(&[("COLORTERM", "truecolor")])),
+            (TerminalType::St, env(&[("COLORTERM", "24bit")])),
+            (TerminalType::ConEmu, env(&[("ConEmuANSI", "ON")])),
+        ];
+        let resolve = |terminal, env: &HashMap<String, String>| {
+            resolve_rgb_ansi_colour_support_in(terminal, None, env, None, || false)
+        };
+
+        for (terminal, env) in &cases {
+            assert!(resolve(*terminal, env), "{terminal}");
+        }
+        for (terminal, _) in &cases {
+            register_capability_override(CapabilityRule {
+                terminal: *terminal,
+                version: None,
+                set: PartialCapabilities {
+                    rgb_ansi_colours: Some(false),
+                    ..PartialCapabilities::NONE
+                },
+            });
+        }
+        let resolved: Vec<_> = cases
+            .iter()
+            .map(|(terminal, env)| resolve(*terminal, env))
+            .collect();
+        clear_capability_overrides();
+
+        assert_eq!(resolved, [false; 3]);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn rules_round_trip_through_the_config_file() {
+        use super::{CapabilityRulesFile, load_capability_rules};
+
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        let dir = crate::test_support::temp_dir("capability_rules");
+        let path = dir.join("config.toml");
+        let rules = vec![
+            rule(TerminalType::Kitty, Some("<0.22"), Some(false)),
+            rule(TerminalType::WezTerm, Some(">=20240203.0.0"), None),
+            rule(TerminalType::Alacritty, Some("0.13.2"), Some(true)),
+            rule(TerminalType::Xterm, None, Some(true)),
+        ];
+
+        let contents = toml::to_string(&CapabilityRulesFile {
+            capability_rules: rules.clone(),
+        })
+        .unwrap();
+        std::fs::write(&path, &contents).unwrap();
+        let loaded = load_capability_rules(&path).unwrap();
+        let registered = capability_overrides();
+        clear_capability_overrides();
+
+        std::fs::write(
+            &path,
+            "[[capability_rules]]\nterminal = \"Kitty\"\nversion = \"<0.22\"\nset = { full_unicode = false }\n",
+        )
+        .unwrap();
+        let handwritten = load_capability_rules(&path).unwrap();
+        clear_capability_overrides();
+        std::fs::write(
+            &path,
+            "[[capability_rules]]\nterminal = \"Kitty\"\nversion = \"~0.22\"\nset = {}\n",
+        )
+        .unwrap();
+        let invalid = load_capability_rules(&path);
+        let after_invalid = capability_overrides();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(contents.contains("version = \"<0.22.0\""), "{contents}");
+        assert_eq!(loaded, rules);
+        assert_eq!(registered, rules);
+        assert_eq!(handwritten, rules[..1]);
+        assert!(
+            matches!(&invalid, Err(crate::errors::RelaunchError::IOError(error)) if error.kind() == std::io::ErrorKind::InvalidData),
+            "{invalid:?}"
+        );
+        assert!(after_invalid.is_empty());
+    }
+}
@@ -0,0 +1,526 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Represents how the relaunched program should be opened in the new terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowMode {
+    /// Open in a new window.
+    #[default]
+    Window,
+    /// Open in a new tab of an existing window of the terminal.
+    Tab,
+    /// Split the current pane with a horizontal divider, opening the new pane below.
+    SplitHorizontal,
+    /// Split the current pane with a vertical divider, opening the new pane to the right.
+    SplitVertical,
+}
+
+impl WindowMode {
+    /// Returns the name of the window mode.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Window => "Window",
+            Self::Tab => "Tab",
+            Self::SplitHorizontal => "Horizontal Split",
+            Self::SplitVertical => "Vertical Split",
+        }
+    }
+
+    /// Returns `true` if this mode splits an existing pane.
+    #[inline]
+    #[must_use]
+    pub fn is_split(&self) -> bool {
+        matches!(self, Self::SplitHorizontal | Self::SplitVertical)
+    }
+}
+
+impl Display for WindowMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Represents which `Windows Terminal` window a relaunch opens in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WtWindowTarget {
+    /// A new window.
+    New,
+    /// The most recently used existing window (`wt -w 0`).
+    Existing,
+    /// The quake mode dropdown window (`wt -w _quake`).
+    Quake,
+}
+
+impl WtWindowTarget {
+    /// Returns the name of the window target.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::New => "New Window",
+            Self::Existing => "Existing Window",
+            Self::Quake => "Quake Window",
+        }
+    }
+}
+
+impl Display for WtWindowTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The position and size of a terminal window, see `RelaunchConfig::window_geometry()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowGeometry {
+    /// The horizontal position of the window, in pixels from the left of the virtual screen.
+    pub x: i32,
+    /// The vertical position of the window, in pixels from the top of the virtual screen.
+    pub y: i32,
+    /// The width of the window, in character cells.
+    pub cols: u16,
+    /// The height of the window, in character cells.
+    pub rows: u16,
+}
+
+/// The bounds of a console window and the size of its character cells, see `geometry_from_console_metrics()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConsoleWindowMetrics {
+    /// The left edge of the window, in pixels.
+    pub left: i32,
+    /// The top edge of the window, in pixels.
+    pub top: i32,
+    /// The right edge of the window, in pixels.
+    pub right: i32,
+    /// The bottom edge of the window, in pixels.
+    pub bottom: i32,
+    /// The width of a character cell, in pixels.
+    pub cell_width: i32,
+    /// The height of a character cell, in pixels.
+    pub cell_height: i32,
+}
+
+/// Returns the geometry of a console window with the given metrics, i.e. its position and how many character
+/// cells fit in it.
+///
+/// # Returns
+/// `None` if the window or its cells are empty, e.g. for a hidden console window.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::config::{ConsoleWindowMetrics, WindowGeometry, geometry_from_console_metrics};
+///
+/// // A console on a secondary monitor, left of the primary one..
+/// let metrics = ConsoleWindowMetrics { left: -1920, top: 120, right: -888, bottom: 736, cell_width: 8, cell_height: 16 };
+/// assert_eq!(
+///     geometry_from_console_metrics(metrics),
+///     Some(WindowGeometry { x: -1920, y: 120, cols: 129, rows: 38 }),
+/// );
+///
+/// let hidden = ConsoleWindowMetrics { right: -1920, bottom: 120, ..metrics };
+/// assert_eq!(geometry_from_console_metrics(hidden), None);
+/// assert_eq!(geometry_from_console_metrics(ConsoleWindowMetrics { cell_width: 0, ..metrics }), None);
+/// ```
+#[must_use]
+pub fn geometry_from_console_metrics(metrics: ConsoleWindowMetrics) -> Option<WindowGeometry> {
+    if metrics.cell_width <= 0 || metrics.cell_height <= 0 {
+        return None;
+    }
+
+    let cells =
+        |pixels: i32, cell: i32| u16::try_from(pixels / cell).ok().filter(|cells| *cells > 0);
+
+    Some(WindowGeometry {
+        x: metrics.left,
+        y: metrics.top,
+        cols: cells(metrics.right - metrics.left, metrics.cell_width)?,
+        rows: cells(metrics.bottom - metrics.top, metrics.cell_height)?,
+    })
+}
+
+/// Returns the geometry of the console window the program runs in, e.g. to open the relaunched terminal on the
+/// same monitor with `RelaunchConfig::window_geometry()`.
+///
+/// # Notes
+/// *   The size is approximate, as the window bounds include its borders and title bar.
+/// *   Always `None` on platforms other than `Windows`, or if the program has no console window, e.g. when
+///     running in a pseudo console.
+#[must_use]
+pub fn current_console_geometry() -> Option<WindowGeometry> {
+    #[cfg(target_os = "windows")]
+    {
+        console_geometry_with(crate::console::console_window_metrics)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+/// Returns the geometry of the console window with the metrics read by the given function, see
+/// `current_console_geometry()`.
+#[cfg(any(target_os = "windows", test))]
+fn console_geometry_with(
+    metrics: impl FnOnce() -> Option<ConsoleWindowMetrics>,
+) -> Option<WindowGeometry> {
+    metrics().and_then(geometry_from_console_metrics)
+}
+
+/// Configuration for how relaunching is performed.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::config::{RelaunchConfig, WindowMode, set_relaunch_config};
+///
+/// set_relaunch_config(RelaunchConfig::new().window_mode(WindowMode::Tab));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct RelaunchConfig {
+    /// How the relaunched program should be opened.
+    pub window_mode: WindowMode,
+    /// If `true`, relaunching fails when the terminal does not support `window_mode`, otherwise
+    /// the closest supported mode is used instead.
+    pub strict_window_mode: bool,
+    /// The namespace used to prefix the relaunch marker, if set.
+    pub marker_namespace: Option<String>,
+    /// Extra directories to search for `MacOS` application bundles, before the defaults.
+    pub extra_app_search_paths: Vec<PathBuf>,
+    /// If `true`, the user's remembered relaunch choice is consulted before relaunching (requires the `config` feature).
+    pub remember_choice: bool,
+    /// If `true`, the home directory of the user who invoked `sudo` is used for install probes and state files.
+    pub prefer_invoking_user_home: bool,
+    /// If `true`, relaunching is allowed under `sudo`, otherwise it is skipped.
+    pub allow_elevated_relaunch: bool,
+    /// If `true`, relaunching is allowed from a terminal inside `Emacs`, otherwise it is skipped.
+    pub allow_emacs_relaunch: bool,
+    /// If `true`, relaunching is skipped inside a terminal multiplexer (e.g. `tmux`), otherwise it's allowed.
+    pub skip_inside_multiplexer: bool,
+    /// Which `Windows Terminal` window to relaunch in, if set, otherwise an existing window is used if
+    /// `Windows Terminal` is already running, and a new window if not.
+    pub windows_terminal_window_target: Option<WtWindowTarget>,
+    /// The minimum time between relaunches for the same `marker_namespace`, if set (requires the `config` feature).
+    pub cooldown: Option<Duration>,
+    /// The position and size of a new relaunched terminal window, if set, for terminals that support it.
+    pub window_geometry: Option<WindowGeometry>,
+    /// The working directory relaunched programs start in, if set, see
+    /// `terminal_providers::launch_working_directory()`.
+    pub working_directory: Option<PathBuf>,
+}
+
+impl RelaunchConfig {
+    /// Creates a new default relaunch configuration.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            window_mode: WindowMode::Window,
+            strict_window_mode: false,
+            marker_namespace: None,
+            extra_app_search_paths: Vec::new(),
+            remember_choice: false,
+            prefer_invoking_user_home: true,
+            allow_elevated_relaunch: false,
+            allow_emacs_relaunch: false,
+            skip_inside_multiplexer: false,
+            windows_terminal_window_target: None,
+            cooldown: None,
+            window_geometry: None,
+            working_directory: None,
+        }
+    }
+
+    /// Sets how the relaunched program should be opened.
+    ///
+    /// # Notes
+    /// Tabs and splits require addressing a running instance of the terminal, which is only possible
+    /// for some terminals, and for splits, only from inside that terminal. Unsupported modes degrade
+    /// from a split to a tab, and from a tab to a window, unless `strict_window_mode` is set.
+    #[inline]
+    #[must_use]
+    pub const fn window_mode(mut self, mode: WindowMode) -> Self {
+        self.window_mode = mode;
+        self
+    }
+
+    /// Sets whether relaunching should fail when the window mode is unsupported, rather than degrading.
+    #[inline]
+    #[must_use]
+    pub const fn strict_window_mode(mut self, strict: bool) -> Self {
+        self.strict_window_mode = strict;
+        self
+    }
+
+    /// Sets the namespace used to prefix the relaunch marker, typically the application's package name.
+    ///
+    /// Without a namespace, every application using this library shares the same marker, so an application
+    /// started as a subprocess of another relaunched application would believe it was relaunched itself.
+    ///
+    /// # Example
+    /// ```rust
+    /// use terminal_relaunch::config::RelaunchConfig;
+    ///
+    /// let config = RelaunchConfig::new().marker_namespace(env!("CARGO_PKG_NAME"));
+    /// assert_eq!(config.relaunched_argument(), "--terminal_relaunch-relaunched-term");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn marker_namespace(mut self, namespace: &str) -> Self {
+        self.marker_namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Sets extra directories to search for `MacOS` application bundles, e.g. on a second volume.
+    ///
+    /// # Notes
+    /// These are searched after the directories in the `TERMINAL_RELAUNCH_APP_DIRS` environment variable and
+    /// before `/Applications` and `~/Applications`, see `terminal_providers::macos_app_search_paths()`.
+    #[inline]
+    #[must_use]
+    pub fn extra_app_search_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.extra_app_search_paths = paths;
+        self
+    }
+
+    /// Sets whether the user's remembered relaunch choice is consulted before relaunching.
+    ///
+    /// # Notes
+    /// Requires the `config` feature, see the `persistence` module. Choices are stored per `marker_namespace`,
+    /// so setting a namespace is recommended, a remembered `Never` skips relaunching entirely.
+    #[inline]
+    #[must_use]
+    pub const fn remember_choice(mut self, remember: bool) -> Self {
+        self.remember_choice = remember;
+        self
+    }
+
+    /// Sets whether the home directory of the user who invoked `sudo` is preferred over `HOME`, see `sudo::user_home()`.
+    #[inline]
+    #[must_use]
+    pub const fn prefer_invoking_user_home(mut self, prefer: bool) -> Self {
+        self.prefer_invoking_user_home = prefer;
+        self
+    }
+
+    /// Sets whether relaunching is allowed under `sudo`.
+    ///
+    /// # Notes
+    /// This is disabled by default, as the relaunched terminal would run as root, and may fail to connect
+    /// to the invoking user's display session.
+    #[inline]
+    #[must_use]
+    pub const fn allow_elevated_relaunch(mut self, allow: bool) -> Self {
+        self.allow_elevated_relaunch = allow;
+        self
+    }
+
+    /// Sets whether relaunching is allowed from a terminal inside `Emacs` (e.g. `vterm` or `term`).
+    ///
+    /// # Notes
+    /// This is disabled by default, as opening a terminal window out from under the editor is hostile to
+    /// `Emacs` users, who chose to run the program there.
+    #[inline]
+    #[must_use]
+    pub const fn allow_emacs_relaunch(mut self, allow: bool) -> Self {
+        self.allow_emacs_relaunch = allow;
+        self
+    }
+
+    /// Sets whether relaunching is skipped inside a terminal multiplexer, see `tmux::multiplexer()`.
+    ///
+    /// # Notes
+    /// This is disabled by default, as multiplexers often limit the features the outer terminal supports. Enable
+    /// it for programs whose users expect to stay in their multiplexer session, which the relaunched program
+    /// would be started outside of.
+    #[inline]
+    #[must_use]
+    pub const fn skip_inside_multiplexer(mut self, skip: bool) -> Self {
+        self.skip_inside_multiplexer = skip;
+        self
+    }
+
+    /// Sets which `Windows Terminal` window to relaunch in, e.g. `WtWindowTarget::Quake` to use a quake mode
+    /// dropdown window rather than opening a second instance.
+    ///
+    /// # Notes
+    /// Only applies to the `Window` and `Tab` window modes, splits always use the current window.
+    #[inline]
+    #[must_use]
+    pub const fn windows_terminal_window_target(mut self, target: WtWindowTarget) -> Self {
+        self.windows_terminal_window_target = Some(target);
+        self
+    }
+
+    /// Sets the minimum time between relaunches, e.g. for programs invoked repeatedly from a shell loop, where
+    /// each invocation would otherwise open a new terminal window.
+    ///
+    /// # Notes
+    /// Requires the `config` feature, the time of the last successful relaunch is stored per `marker_namespace`
+    /// in the state file (see the `persistence` module). Relaunches within the cooldown are skipped with
+    /// `SkipReason::CooldownActive`, capabilities are still reported as detected.
+    #[inline]
+    #[must_use]
+    pub const fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Sets the position and size of a new relaunched terminal window, e.g. from `current_console_geometry()`, so
+    /// it opens on the same monitor as the console it replaces.
+    ///
+    /// # Notes
+    /// Only applies to `Windows Terminal` when opening a new window, other terminals ignore the hints.
+    #[inline]
+    #[must_use]
+    pub const fn window_geometry(mut self, geometry: Option<WindowGeometry>) -> Self {
+        self.window_geometry = geometry;
+        self
+    }
+
+    /// Sets the working directory relaunched programs start in, rather than the working directory captured at
+    /// startup or when the relaunch is planned.
+    ///
+    /// # Notes
+    /// Set this when other threads may change the process working directory, see
+    /// `terminal_providers::launch_working_directory()`.
+    #[inline]
+    #[must_use]
+    pub fn working_directory(mut self, dir: Option<PathBuf>) -> Self {
+        self.working_directory = dir;
+        self
+    }
+
+    /// Returns the namespace used to store the remembered relaunch choice.
+    #[cfg(feature = "config")]
+    #[inline]
+    #[must_use]
+    pub fn choice_namespace(&self) -> &str {
+        self.marker_namespace
+            .as_deref()
+            .unwrap_or(crate::persistence::DEFAULT_NAMESPACE)
+    }
+
+    /// Returns the argument passed to relaunched programs to indicate a relaunch has occurred.
+    ///
+    /// # Returns
+    /// *   `--<namespace>-relaunched-term` if a marker namespace is set.
+    /// *   `RELAUNCHED_ARGUMENT` otherwise.
+    #[inline]
+    #[must_use]
+    pub fn relaunched_argument(&self) -> String {
+        match &self.marker_namespace {
+            Some(namespace) => format!("--{namespace}-relaunched-term"),
+            None => crate::RELAUNCHED_ARGUMENT.to_string(),
+        }
+    }
+}
+
+impl Default for RelaunchConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sets the global relaunch configuration used by the relaunch functions.
+///
+/// # Notes
+/// Staged for `runtime::init()` before initialization, and applied immediately after it.
+#[inline]
+pub fn set_relaunch_config(config: RelaunchConfig) {
+    *crate::runtime::state()
+        .relaunch_config
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = config;
+}
+
+/// Returns a copy of the current global relaunch configuration.
+#[inline]
+#[must_use]
+pub fn relaunch_config() -> RelaunchConfig {
+    crate::runtime::state()
+        .relaunch_config
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConsoleWindowMetrics, RelaunchConfig, WindowGeometry, console_geometry_with};
+
+    #[test]
+    fn console_geometry_from_injected_metrics() {
+        // A high DPI console with larger cells on a monitor above the primary one..
+        let metrics = ConsoleWindowMetrics {
+            left: 200,
+            top: -1440,
+            right: 1800,
+            bottom: -240,
+            cell_width: 16,
+            cell_height: 32,
+        };
+        assert_eq!(
+            console_geometry_with(|| Some(metrics)),
+            Some(WindowGeometry {
+                x: 200,
+                y: -1440,
+                cols: 100,
+                rows: 37,
+            })
+        );
+
+        // Partial cells are dropped, and windows too small for one cell, or without a console, give no hint..
+        let narrow = ConsoleWindowMetrics {
+            right: 215,
+            ..metrics
+        };
+        assert_eq!(console_geometry_with(|| Some(narrow)), None);
+        let zero_cells = ConsoleWindowMetrics {
+            cell_height: 0,
+            ..metrics
+        };
+        assert_eq!(console_geometry_with(|| Some(zero_cells)), None);
+        let huge = ConsoleWindowMetrics {
+            right: i32::MAX,
+            cell_width: 1,
+            ..metrics
+        };
+        assert_eq!(console_geometry_with(|| Some(huge)), None);
+        assert_eq!(console_geometry_with(|| None), None);
+
+        #[cfg(not(target_os = "windows"))]
+        assert_eq!(super::current_console_geometry(), None);
+    }
+
+    #[test]
+    fn no_geometry_hint_by_default() {
+        let geometry = Some(WindowGeometry {
+            x: 0,
+            y: 0,
+            cols: 80,
+            rows: 24,
+        });
+        assert_eq!(RelaunchConfig::new().window_geometry, None);
+        assert_eq!(RelaunchConfig::default().window_geometry, None);
+        assert_eq!(
+            RelaunchConfig::new()
+                .window_geometry(geometry)
+                .window_geometry,
+            geometry
+        );
+        assert_eq!(
+            RelaunchConfig::new()
+                .window_geometry(geometry)
+                .window_geometry(None)
+                .window_geometry,
+            None
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! A conformance suite for terminal providers, so authors of custom providers can check they behave like the
+//! built-in ones.
+//!
+//! Every check only inspects the provider, nothing is ever launched. The checks are:
+//!
+//! | Check                 | Passes if                                                                   |
+//! |-----------------------|-----------------------------------------------------------------------------|
+//! | `window-mode`         | `WindowMode::Window` is supported, as it's the fallback for every mode.     |
+//! | `installed-path`      | An `installed_path()` implies `is_installed()`.                             |
+//! | `installed-version`   | An `installed_version()` implies `is_installed()`.                          |
+//! | `dependency-stable`   | `missing_dependency()` returns the same value when called twice.            |
+//! | `unfit-stable`        | `unfit_reason()` returns the same value when called twice.                  |
+//! | `installation-stable` | `is_installed()` returns the same value when called twice.                  |
+//!
+//! # Notes
+//! The command a provider spawns isn't inspectable without relaunching, so it isn't checked here. The commands of
+//! the built-in providers are pinned by the golden files in `tests/fixtures/argv`, which are rewritten by running
+//! the tests with `TERMINAL_RELAUNCH_BLESS=1`.
+//!
+//! # Example
+//! ```
+//! use terminal_relaunch::conformance::run_built_in_conformance;
+//!
+//! for report in run_built_in_conformance() {
+//!     assert!(report.passed(), "{report}");
+//! }
+//! ```
+
+use std::fmt::Display;
+
+use strum::IntoEnumIterator;
+
+use crate::config::WindowMode;
+use crate::{TerminalProvider, TerminalType, get_built_in_provider_for_terminal};
+
+/// The outcome of a single conformance check.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CheckOutcome {
+    /// The provider conforms.
+    Passed,
+    /// The provider doesn't conform, for the given reason.
+    Failed(String),
+}
+
+/// A single conformance check run against a provider, see `run_provider_conformance()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConformanceCheck {
+    /// The name of the check, see the module documentation.
+    pub name: &'static str,
+    /// The outcome of the check.
+    pub outcome: CheckOutcome,
+}
+
+/// The results of running the conformance suite against a provider.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConformanceReport {
+    /// The terminal type of the provider.
+    pub terminal: TerminalType,
+    /// The checks run, in order.
+    pub checks: Vec<ConformanceCheck>,
+}
+
+impl ConformanceReport {
+    /// Returns `true` if every check passed.
+    #[inline]
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Returns the checks that failed.
+    #[inline]
+    pub fn failures(&self) -> impl Iterator<Item = &ConformanceCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome != CheckOutcome::Passed)
+    }
+}
+
+impl Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` conformance:", self.terminal)?;
+        for check in &self.checks {
+            match &check.outcome {
+                CheckOutcome::Passed => write!(f, "\n    {}: passed", check.name)?,
+                CheckOutcome::Failed(reason) => {
+                    write!(f, "\n    {}: failed, {reason}", check.name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs the conformance suite against the given provider.
+#[must_use]
+pub fn run_provider_conformance(provider: &dyn TerminalProvider) -> ConformanceReport {
+    let installed = provider.is_installed();
+    let check = |name, passed: bool, reason: &dyn Fn() -> String| ConformanceCheck {
+        name,
+        outcome: if passed {
+            CheckOutcome::Passed
+        } else {
+            CheckOutcome::Failed(reason())
+        },
+    };
+
+    let installed_path = provider.installed_path();
+    let installed_version = provider.installed_version();
+    let dependency = provider.missing_dependency();
+    let unfit_reason = provider.unfit_reason();
+
+    let checks = vec![
+        check(
+            "window-mode",
+            provider.supports_window_mode(WindowMode::Window),
+            &|| "`WindowMode::Window` is unsupported.".to_string(),
+        ),
+        check(
+            "installed-path",
+            installed_path.is_none() || installed,
+            &|| format!("reported path `{installed_path:?}` while not installed."),
+        ),
+        check(
+            "installed-version",
+            installed_version.is_none() || installed,
+            &|| format!("reported version `{installed_version:?}` while not installed."),
+        ),
+        check(
+            "dependency-stable",
+            dependency == provider.missing_dependency(),
+            &|| "`missing_dependency()` changed between calls.".to_string(),
+        ),
+        check(
+            "unfit-stable",
+            unfit_reason == provider.unfit_reason(),
+            &|| "`unfit_reason()` changed between calls.".to_string(),
+        ),
+        check(
+            "installation-stable",
+            installed == provider.is_installed(),
+            &|| "`is_installed()` changed between calls.".to_string(),
+        ),
+    ];
+
+    ConformanceReport {
+        terminal: provider.terminal_type(),
+        checks,
+    }
+}
+
+/// Runs the conformance suite against every built-in provider.
+#[must_use]
+pub fn run_built_in_conformance() -> Vec<ConformanceReport> {
+    TerminalType::iter()
+        .filter_map(get_built_in_provider_for_terminal)
+        .map(|provider| run_provider_conformance(provider.as_ref()))
+        .collect()
+}
@@ -0,0 +1,123 @@
+//! Minimal wrappers around the `Windows` console API, for consoles that can't process VT sequences.
+
+use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+use windows_sys::Win32::System::Console::{
+    CONSOLE_CURSOR_INFO, CONSOLE_SCREEN_BUFFER_INFO, COORD, FOREGROUND_BLUE, FOREGROUND_GREEN,
+    FOREGROUND_RED, FillConsoleOutputAttribute, FillConsoleOutputCharacterW,
+    GetConsoleScreenBufferInfo, GetStdHandle, STD_OUTPUT_HANDLE, SetConsoleCursorInfo,
+    SetConsoleCursorPosition, SetConsoleTextAttribute,
+};
+
+use crate::errors::TermResult;
+
+/// The default console attribute, light grey text on a black background.
+const DEFAULT_ATTRIBUTES: u16 = FOREGROUND_RED | FOREGROUND_GREEN | FOREGROUND_BLUE;
+
+/// Returns the last OS error if the given console API result indicates failure.
+fn check(result: i32) -> TermResult<()> {
+    if result == 0 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Clears the standard output console buffer, resets the text attributes, moves the cursor home and shows it.
+///
+/// # Errors
+/// Returns `RelaunchError::IOError` if standard output is not a console, or any console call fails.
+pub fn reset_console() -> TermResult<()> {
+    // SAFETY: `GetStdHandle` has no preconditions.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // SAFETY: `CONSOLE_SCREEN_BUFFER_INFO` is a plain C struct, fully initialised by the call before use.
+    let mut info: CONSOLE_SCREEN_BUFFER_INFO = unsafe { std::mem::zeroed() };
+    // SAFETY: `handle` is a valid console handle and `info` is a valid pointer.
+    check(unsafe { GetConsoleScreenBufferInfo(handle, &raw mut info) })?;
+
+    let origin = COORD { X: 0, Y: 0 };
+    let cells = u32::from(info.dwSize.X.unsigned_abs()) * u32::from(info.dwSize.Y.unsigned_abs());
+    let mut written = 0;
+
+    // SAFETY: `handle` is a valid console handle and `written` is a valid pointer, for all calls below.
+    unsafe {
+        check(FillConsoleOutputCharacterW(
+            handle,
+            u16::from(b' '),
+            cells,
+            origin,
+            &raw mut written,
+        ))?;
+        check(FillConsoleOutputAttribute(
+            handle,
+            DEFAULT_ATTRIBUTES,
+            cells,
+            origin,
+            &raw mut written,
+        ))?;
+        check(SetConsoleTextAttribute(handle, DEFAULT_ATTRIBUTES))?;
+        check(SetConsoleCursorPosition(handle, origin))?;
+
+        let cursor = CONSOLE_CURSOR_INFO {
+            dwSize: 25,
+            bVisible: 1,
+        };
+        check(SetConsoleCursorInfo(handle, &raw const cursor))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the bounds of the console window and the size of its character cells, if the program has a console
+/// window.
+pub fn console_window_metrics() -> Option<crate::config::ConsoleWindowMetrics> {
+    use windows_sys::Win32::Foundation::RECT;
+    use windows_sys::Win32::System::Console::{
+        CONSOLE_FONT_INFOEX, GetConsoleWindow, GetCurrentConsoleFontEx,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+    // SAFETY: `GetConsoleWindow` has no preconditions.
+    let window = unsafe { GetConsoleWindow() };
+    if window.is_null() {
+        return None;
+    }
+
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+    // SAFETY: `window` is a valid window handle and `rect` is a valid pointer.
+    if unsafe { GetWindowRect(window, &raw mut rect) } == 0 {
+        return None;
+    }
+
+    // SAFETY: `GetStdHandle` has no preconditions.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+        return None;
+    }
+
+    // SAFETY: `CONSOLE_FONT_INFOEX` is a plain C struct, fully initialised by the call before use.
+    let mut font: CONSOLE_FONT_INFOEX = unsafe { std::mem::zeroed() };
+    font.cbSize = u32::try_from(std::mem::size_of::<CONSOLE_FONT_INFOEX>()).ok()?;
+    // SAFETY: `handle` is a valid handle and `font` is a valid pointer with `cbSize` set, the call fails for
+    // non-console handles.
+    if unsafe { GetCurrentConsoleFontEx(handle, 0, &raw mut font) } == 0 {
+        return None;
+    }
+
+    Some(crate::config::ConsoleWindowMetrics {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+        cell_width: i32::from(font.dwFontSize.X),
+        cell_height: i32::from(font.dwFontSize.Y),
+    })
+}
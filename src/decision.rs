@@ -0,0 +1,921 @@
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+use crate::TerminalType;
+use crate::config::RelaunchConfig;
+use crate::environment::{Environment, SystemEnvironment};
+use crate::host::HostKind;
+use crate::remote::ProcessInfo;
+use crate::tmux::{Multiplexer, TmuxFeatures};
+use crate::version::TerminalVersion;
+
+/// Represents the reasons a relaunch may be skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SkipReason {
+    /// The program has already been relaunched by the library.
+    AlreadyRelaunched,
+    /// The current terminal already supports all preferred features.
+    AlreadyPreferred,
+    /// The only installed preferred terminal is the same type as the current terminal,
+    /// and cannot provide the features the current terminal is missing.
+    NoImprovementPossible,
+    /// The user chose to never relaunch, and the choice was remembered.
+    DeclinedByUser,
+    /// The platform's default terminal is already a preferred terminal, but the program was started in the
+    /// default console host anyway, e.g. from a stale shortcut.
+    DefaultAlreadyPreferredButNotActive,
+    /// The program is running in a remote or browser based session (e.g. over `SSH`, `mosh`, in
+    /// `GitHub Codespaces` or a `Jupyter` terminal), so there is no local terminal to relaunch in.
+    RemoteSession,
+    /// The program is running in a terminal inside `Emacs`, and relaunching from `Emacs` is not allowed, see
+    /// `RelaunchConfig::allow_emacs_relaunch`.
+    InsideEmacs,
+    /// The program is running inside a terminal multiplexer (e.g. `tmux`), and relaunches are skipped there, see
+    /// `RelaunchConfig::skip_inside_multiplexer`.
+    InsideMultiplexer,
+    /// The program's standard streams are not all terminals, e.g. when its output is piped or redirected to a
+    /// file, which a relaunch in a new window would break.
+    NotInteractive,
+    /// The program is running under `sudo`, and elevated relaunches are not allowed.
+    ElevatedContext,
+    /// The program is running as a background service (e.g. a `Windows` service or `launchd` daemon), so there
+    /// is no interactive session to relaunch in.
+    ServiceContext,
+    /// The program is running inside an installation flow (e.g. a post-install step in a `cmd /K` console), where
+    /// relaunching would interrupt the installer, see `installer::is_installer_context()`.
+    InstallerContext,
+    /// The program was relaunched recently, and the configured cooldown hasn't passed, see `RelaunchConfig::cooldown`.
+    CooldownActive,
+}
+
+impl SkipReason {
+    /// Returns a short description of the reason.
+    #[inline]
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::AlreadyRelaunched => "The program has already been relaunched.",
+            Self::AlreadyPreferred => {
+                "The current terminal already supports all preferred features."
+            }
+            Self::NoImprovementPossible => {
+                "No installed terminal would improve on the current terminal."
+            }
+            Self::DeclinedByUser => "The user chose to never relaunch.",
+            Self::ElevatedContext => "Relaunching is disabled when running under sudo.",
+            Self::CooldownActive => "The program was relaunched too recently.",
+            Self::ServiceContext => {
+                "There is no interactive session to relaunch in from a service."
+            }
+            Self::InstallerContext => "Relaunching is disabled inside an installer.",
+            Self::RemoteSession => {
+                "There is no local terminal to relaunch in from a remote session."
+            }
+            Self::InsideEmacs => "Relaunching is disabled inside Emacs.",
+            Self::InsideMultiplexer => "Relaunching is disabled inside a terminal multiplexer.",
+            Self::NotInteractive => "The program's standard streams are not all terminals.",
+            Self::DefaultAlreadyPreferredButNotActive => {
+                "The default terminal is already a preferred terminal, but was not used to start the program."
+            }
+        }
+    }
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+/// Represents the decision of whether to attempt a relaunch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelaunchDecision {
+    /// A relaunch should be attempted.
+    Attempt,
+    /// The relaunch should be skipped, for the given reason.
+    Skip(SkipReason),
+}
+
+impl RelaunchDecision {
+    /// Returns `true` if a relaunch should be attempted.
+    #[inline]
+    #[must_use]
+    pub fn should_attempt(&self) -> bool {
+        matches!(self, Self::Attempt)
+    }
+}
+
+/// The features the current terminal must support for a relaunch to be skipped as `SkipReason::AlreadyPreferred`,
+/// see `decide()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureRequirements {
+    /// If full unicode rendering is required.
+    pub full_unicode: bool,
+    /// If RGB (ANSI) colours are required.
+    pub rgb_ansi_colours: bool,
+}
+
+impl FeatureRequirements {
+    /// Requires every preferred feature, as `relaunch_decision()` does.
+    pub const PREFERRED: Self = Self {
+        full_unicode: true,
+        rgb_ansi_colours: true,
+    };
+
+    /// Returns `true` if a terminal with the given support satisfies every required feature.
+    #[inline]
+    #[must_use]
+    pub const fn satisfied_by(&self, full_unicode: bool, rgb_ansi_colours: bool) -> bool {
+        (full_unicode || !self.full_unicode) && (rgb_ansi_colours || !self.rgb_ansi_colours)
+    }
+}
+
+impl Default for FeatureRequirements {
+    fn default() -> Self {
+        Self::PREFERRED
+    }
+}
+
+/// The facts a relaunch decision is made from, see `decide()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DecisionInputs {
+    /// The current terminal.
+    pub terminal: TerminalType,
+    /// If the program has already been relaunched by the library.
+    pub already_relaunched: bool,
+    /// If the program is running as a background service.
+    pub service_context: bool,
+    /// If every standard stream is a terminal, see `startup::StartupState::is_interactive()`.
+    pub interactive: bool,
+    /// If the program is running inside an installation flow.
+    pub installer_context: bool,
+    /// If the program is running under `sudo`.
+    pub elevated: bool,
+    /// If the program is running in a remote or browser based session.
+    pub remote_session: bool,
+    /// The terminal multiplexer the program is running inside, if any, see `tmux::multiplexer()`.
+    pub multiplexer: Option<Multiplexer>,
+    /// If the current terminal supports full unicode rendering.
+    pub full_unicode: bool,
+    /// If the current terminal supports RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+    /// If the platform's default terminal is a preferred terminal, but the default console host is in use.
+    pub default_already_preferred_but_not_active: bool,
+    /// If the user's remembered choice is to never relaunch.
+    pub declined_by_user: bool,
+    /// If the configured cooldown since the last relaunch hasn't passed.
+    pub cooldown_active: bool,
+    /// The installed preferred terminals that could be relaunched in, in order of preference, `None` if they
+    /// haven't been probed.
+    pub installed_candidates: Option<Vec<TerminalType>>,
+}
+
+impl DecisionInputs {
+    /// Creates the inputs of the given terminal, missing every preferred feature and with no reason to skip, to
+    /// be adjusted by hand, e.g. for testing an application's configuration against `decide()`.
+    #[inline]
+    #[must_use]
+    pub const fn new(terminal: TerminalType) -> Self {
+        Self {
+            terminal,
+            already_relaunched: false,
+            service_context: false,
+            interactive: true,
+            installer_context: false,
+            elevated: false,
+            remote_session: false,
+            multiplexer: None,
+            full_unicode: false,
+            rgb_ansi_colours: false,
+            default_already_preferred_but_not_active: false,
+            declined_by_user: false,
+            cooldown_active: false,
+            installed_candidates: None,
+        }
+    }
+
+    /// Gathers the inputs for the current program and terminal.
+    ///
+    /// # Notes
+    /// *   This uses the effective capabilities of the current terminal, i.e. including capability rules and overrides,
+    ///     resolved at the time of the call rather than latched as `SUPPORTS_FULL_UNICODE`.
+    /// *   If the startup state was captured (see `startup::capture_startup_state()`), its terminal, capabilities and
+    ///     standard streams are used instead, so a late decision matches what the user saw at launch.
+    /// *   The installed terminals aren't probed, as probing is slow, the pipeline skips with
+    ///     `SkipReason::NoImprovementPossible` after probing them instead, see `pipeline::RelaunchPipeline`.
+    #[must_use]
+    pub fn current() -> Self {
+        let startup = crate::startup::startup_state();
+        let facts = SystemFacts::current();
+
+        let Some(startup) = startup else {
+            return Self::gather_in(
+                &SystemEnvironment,
+                &facts,
+                crate::capabilities::probe_if_enabled,
+            );
+        };
+        Self {
+            full_unicode: startup.full_unicode,
+            rgb_ansi_colours: startup.rgb_ansi_colours,
+            ..Self::gather_in(&SystemEnvironment, &facts, || false)
+        }
+    }
+
+    /// Gathers the inputs from the given environment and the given facts about the program, probing the terminal
+    /// for RGB colours with `probe` if nothing else confirms them.
+    ///
+    /// This is shared by `current()` and `simulation::simulate()`, so both gather the inputs the same way.
+    ///
+    /// # Notes
+    /// *   The capabilities are resolved as `capabilities::resolve_current_full_unicode_support()` and
+    ///     `capabilities::resolve_current_rgb_ansi_colour_support()`, for the terminal of the facts.
+    /// *   The installed terminals aren't probed, `installed_candidates` is `None`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// use terminal_relaunch::TerminalType;
+    /// use terminal_relaunch::decision::{DecisionInputs, SystemFacts};
+    ///
+    /// let env = HashMap::from([("SSH_CONNECTION".to_string(), "192.0.2.10 52022 192.0.2.20 22".to_string())]);
+    /// let inputs = DecisionInputs::gather_in(&env, &SystemFacts::new(TerminalType::Xterm), || false);
+    /// assert!(inputs.remote_session);
+    /// assert!(inputs.interactive);
+    /// ```
+    #[must_use]
+    pub fn gather_in(
+        env: &(impl Environment + ?Sized),
+        facts: &SystemFacts,
+        probe: impl FnOnce() -> bool,
+    ) -> Self {
+        let terminal = facts.terminal;
+        let version = facts.terminal_version.as_ref();
+        let multiplexer = crate::tmux::multiplexer_in(env);
+        let tmux_features = facts
+            .tmux_features
+            .filter(|_| multiplexer == Some(Multiplexer::Tmux));
+
+        Self {
+            terminal,
+            already_relaunched: facts.already_relaunched,
+            service_context: facts.service_context,
+            interactive: facts.interactive,
+            installer_context: crate::installer::installer_signal_in(env, &facts.ancestry)
+                .is_some(),
+            elevated: crate::sudo::is_elevated_in(env),
+            remote_session: crate::remote::classify_remote_session_in(env, &facts.ancestry)
+                .is_remote()
+                || (terminal == TerminalType::VSCode
+                    && crate::vscode::detect_vscode_flavor_in(env).is_remote())
+                || facts.non_terminal_host.is_some_and(|host| host.is_remote())
+                || terminal == TerminalType::Jupyter,
+            multiplexer,
+            full_unicode: crate::capabilities::resolve_full_unicode_support_for(
+                terminal,
+                version,
+                facts.service_context,
+            ),
+            rgb_ansi_colours: crate::capabilities::resolve_rgb_ansi_colour_support_for(
+                terminal,
+                version,
+                env,
+                facts.service_context,
+                tmux_features,
+                probe,
+            ),
+            default_already_preferred_but_not_active: facts
+                .default_already_preferred_but_not_active,
+            declined_by_user: facts.declined_by_user,
+            cooldown_active: facts.cooldown_active,
+            installed_candidates: None,
+        }
+    }
+}
+
+/// What `DecisionInputs::gather_in()` can't read from the environment, read from the system by
+/// `DecisionInputs::current()`, or given by a `simulation::Scenario`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SystemFacts {
+    /// The current terminal.
+    pub terminal: TerminalType,
+    /// The version of the current terminal, if known.
+    pub terminal_version: Option<TerminalVersion>,
+    /// If the program has already been relaunched by the library.
+    pub already_relaunched: bool,
+    /// If the program is running as a background service, see `service::is_service_context()`.
+    pub service_context: bool,
+    /// If every standard stream is a terminal, see `startup::StartupState::is_interactive()`.
+    pub interactive: bool,
+    /// The ancestors of the program, nearest first, see `remote::process_ancestry()`.
+    pub ancestry: Vec<ProcessInfo>,
+    /// The host running the program without a terminal, if any, see `host::non_terminal_host()`.
+    pub non_terminal_host: Option<HostKind>,
+    /// The features `tmux` passes through, if running inside it, see `tmux::tmux_features()`.
+    pub tmux_features: Option<TmuxFeatures>,
+    /// If the platform's default terminal is a preferred terminal, but the default console host is in use.
+    pub default_already_preferred_but_not_active: bool,
+    /// If the user's remembered choice is to never relaunch.
+    pub declined_by_user: bool,
+    /// If the configured cooldown since the last relaunch hasn't passed.
+    pub cooldown_active: bool,
+}
+
+impl SystemFacts {
+    /// Creates the facts of an interactive program in the given terminal, of unknown version, with nothing else
+    /// known about the system.
+    #[inline]
+    #[must_use]
+    pub const fn new(terminal: TerminalType) -> Self {
+        Self {
+            terminal,
+            terminal_version: None,
+            already_relaunched: false,
+            service_context: false,
+            interactive: true,
+            ancestry: Vec::new(),
+            non_terminal_host: None,
+            tmux_features: None,
+            default_already_preferred_but_not_active: false,
+            declined_by_user: false,
+            cooldown_active: false,
+        }
+    }
+
+    /// Reads the facts of the current program from the system.
+    ///
+    /// # Notes
+    /// If the startup state was captured (see `startup::capture_startup_state()`), its terminal and standard
+    /// streams are used.
+    #[must_use]
+    pub fn current() -> Self {
+        let startup = crate::startup::startup_state();
+
+        Self {
+            terminal: startup.map_or(*crate::CURRENT_TERMINAL, |state| state.terminal),
+            terminal_version: *crate::CURRENT_TERMINAL_VERSION,
+            already_relaunched: crate::has_been_relaunched(),
+            service_context: crate::service::is_service_context(),
+            interactive: startup.map_or_else(
+                || {
+                    std::io::stdin().is_terminal()
+                        && std::io::stdout().is_terminal()
+                        && std::io::stderr().is_terminal()
+                },
+                crate::startup::StartupState::is_interactive,
+            ),
+            ancestry: crate::remote::process_ancestry().to_vec(),
+            non_terminal_host: crate::host::non_terminal_host(),
+            tmux_features: crate::tmux::tmux_features(),
+            default_already_preferred_but_not_active: default_already_preferred_but_not_active(),
+            declined_by_user: declined_by_user(),
+            cooldown_active: cooldown_active(),
+        }
+    }
+}
+
+/// Decides whether a relaunch should be attempted for the current program and terminal.
+///
+/// # Notes
+/// This uses the effective capabilities of the current terminal, i.e. including capability rules and overrides, the
+/// current relaunch configuration (see `config::relaunch_config()`), and requires every preferred feature.
+#[inline]
+#[must_use]
+pub fn relaunch_decision() -> RelaunchDecision {
+    decide(
+        &DecisionInputs::current(),
+        &crate::config::relaunch_config(),
+        &FeatureRequirements::PREFERRED,
+    )
+}
+
+/// Decides whether a relaunch should be attempted from the given inputs, configuration and required features.
+///
+/// This is shared by `relaunch_decision()` and `simulation::simulate()`, so both always decide the same way. It's
+/// pure, so applications can check their policies against it with inputs built by hand (see `DecisionInputs::new()`).
+///
+/// # Returns
+/// *   `RelaunchDecision::Skip` with the reason of the first input that applies, in the order of the
+///     `DecisionInputs` fields, where:
+///     *   Not being interactive doesn't apply to `TerminalType::NonTerminalHost`, which never is.
+///     *   Being elevated only applies without `RelaunchConfig::allow_elevated_relaunch`, and the `Emacs` terminal
+///         only without `RelaunchConfig::allow_emacs_relaunch`.
+///     *   A multiplexer only applies with `RelaunchConfig::skip_inside_multiplexer`.
+///     *   The capabilities only apply when they satisfy the requirements.
+///     *   The installed candidates only apply when there is at least one, and all are the current terminal's type.
+/// *   `RelaunchDecision::Attempt` otherwise, whatever the terminal.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::TerminalType;
+/// use terminal_relaunch::config::RelaunchConfig;
+/// use terminal_relaunch::decision::{DecisionInputs, FeatureRequirements, RelaunchDecision, SkipReason, decide};
+/// use terminal_relaunch::tmux::Multiplexer;
+///
+/// // An application only needing RGB colours, started from `tmux`..
+/// let requirements = FeatureRequirements { full_unicode: false, rgb_ansi_colours: true };
+/// let inputs = DecisionInputs { multiplexer: Some(Multiplexer::Tmux), ..DecisionInputs::new(TerminalType::Tmux) };
+/// assert_eq!(decide(&inputs, &RelaunchConfig::new(), &requirements), RelaunchDecision::Attempt);
+///
+/// // ..isn't relaunched when configured to stay in the multiplexer, nor when the colours are passed through..
+/// let config = RelaunchConfig::new().skip_inside_multiplexer(true);
+/// assert_eq!(decide(&inputs, &config, &requirements), RelaunchDecision::Skip(SkipReason::InsideMultiplexer));
+/// let passed_through = DecisionInputs { rgb_ansi_colours: true, ..inputs.clone() };
+/// assert_eq!(
+///     decide(&passed_through, &RelaunchConfig::new(), &requirements),
+///     RelaunchDecision::Skip(SkipReason::AlreadyPreferred)
+/// );
+///
+/// // ..nor when the only installed preferred terminal is `tmux` itself..
+/// let only_itself = DecisionInputs { installed_candidates: Some(vec![TerminalType::Tmux]), ..inputs };
+/// assert_eq!(
+///     decide(&only_itself, &RelaunchConfig::new(), &requirements),
+///     RelaunchDecision::Skip(SkipReason::NoImprovementPossible)
+/// );
+/// ```
+#[must_use]
+pub fn decide(
+    inputs: &DecisionInputs,
+    config: &RelaunchConfig,
+    requirements: &FeatureRequirements,
+) -> RelaunchDecision {
+    if inputs.already_relaunched {
+        RelaunchDecision::Skip(SkipReason::AlreadyRelaunched)
+    } else if inputs.service_context {
+        RelaunchDecision::Skip(SkipReason::ServiceContext)
+    } else if !inputs.interactive && inputs.terminal != TerminalType::NonTerminalHost {
+        RelaunchDecision::Skip(SkipReason::NotInteractive)
+    } else if inputs.installer_context {
+        RelaunchDecision::Skip(SkipReason::InstallerContext)
+    } else if inputs.elevated && !config.allow_elevated_relaunch {
+        RelaunchDecision::Skip(SkipReason::ElevatedContext)
+    } else if inputs.remote_session {
+        RelaunchDecision::Skip(SkipReason::RemoteSession)
+    } else if inputs.terminal == TerminalType::Emacs && !config.allow_emacs_relaunch {
+        RelaunchDecision::Skip(SkipReason::InsideEmacs)
+    } else if inputs.multiplexer.is_some() && config.skip_inside_multiplexer {
+        RelaunchDecision::Skip(SkipReason::InsideMultiplexer)
+    } else if requirements.satisfied_by(inputs.full_unicode, inputs.rgb_ansi_colours) {
+        RelaunchDecision::Skip(SkipReason::AlreadyPreferred)
+    } else if inputs.default_already_preferred_but_not_active {
+        RelaunchDecision::Skip(SkipReason::DefaultAlreadyPreferredButNotActive)
+    } else if inputs.declined_by_user {
+        RelaunchDecision::Skip(SkipReason::DeclinedByUser)
+    } else if inputs.cooldown_active {
+        RelaunchDecision::Skip(SkipReason::CooldownActive)
+    } else if inputs
+        .installed_candidates
+        .as_ref()
+        .is_some_and(|candidates| only_same_type(candidates, inputs.terminal))
+    {
+        RelaunchDecision::Skip(SkipReason::NoImprovementPossible)
+    } else {
+        crate::logging::info!(
+            "Terminal `{}` is missing preferred features, relaunch should be attempted.",
+            inputs.terminal
+        );
+        RelaunchDecision::Attempt
+    }
+}
+
+/// Returns `true` if there is at least one candidate, and every candidate is the given terminal's type, whose
+/// installed version is unknown, so couldn't improve on it, as in `improves_on_current_terminal()`.
+#[inline]
+fn only_same_type(candidates: &[TerminalType], terminal: TerminalType) -> bool {
+    !candidates.is_empty() && candidates.iter().all(|candidate| *candidate == terminal)
+}
+
+/// Returns `true` if the program is running in the default `Windows` console host, while the platform's default
+/// terminal is a preferred terminal.
+#[inline]
+fn default_already_preferred_but_not_active() -> bool {
+    default_preferred_but_not_active(*crate::CURRENT_TERMINAL, crate::platform_default_terminal)
+}
+
+/// Returns `true` if the given terminal is the default `Windows` console host, while the platform's default
+/// terminal, only looked up if so, is a preferred terminal.
+#[inline]
+fn default_preferred_but_not_active(
+    terminal: TerminalType,
+    platform_default: impl FnOnce() -> Option<TerminalType>,
+) -> bool {
+    terminal == TerminalType::WindowsCMD
+        && platform_default().is_some_and(|default| default.is_preferred())
+}
+
+/// Returns `true` if remembering choices is enabled, and the user's remembered choice is to never relaunch.
+#[inline]
+fn declined_by_user() -> bool {
+    #[cfg(feature = "config")]
+    {
+        use crate::persistence::{RememberedDecision, load_remembered_choice};
+
+        let config = crate::config::relaunch_config();
+        config.remember_choice
+            && load_remembered_choice(config.choice_namespace()).decision
+                == RememberedDecision::Never
+    }
+
+    #[cfg(not(feature = "config"))]
+    {
+        false
+    }
+}
+
+/// Returns `true` if a cooldown is configured, and the last relaunch was within it.
+#[inline]
+fn cooldown_active() -> bool {
+    #[cfg(feature = "config")]
+    {
+        use crate::persistence::{cooldown_active_at, load_last_relaunch};
+
+        let config = crate::config::relaunch_config();
+        config.cooldown.is_some_and(|cooldown| {
+            cooldown_active_at(
+                load_last_relaunch(config.choice_namespace()),
+                std::time::SystemTime::now(),
+                cooldown,
+            )
+        })
+    }
+
+    #[cfg(not(feature = "config"))]
+    {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use proptest::prelude::*;
+    use strum::IntoEnumIterator;
+
+    use super::{
+        DecisionInputs, FeatureRequirements, Multiplexer, RelaunchConfig, RelaunchDecision,
+        SkipReason, SystemFacts, decide, default_preferred_but_not_active,
+    };
+    use crate::TerminalType;
+    use crate::environment::SystemEnvironment;
+    use crate::host::HostKind;
+    use crate::remote::ProcessInfo;
+    use crate::test_support::reading_globals;
+    use crate::tmux::TmuxFeatures;
+
+    /// Every skip reason, in the order `decide()` checks them.
+    const CHECK_ORDER: [SkipReason; 13] = [
+        SkipReason::AlreadyRelaunched,
+        SkipReason::ServiceContext,
+        SkipReason::NotInteractive,
+        SkipReason::InstallerContext,
+        SkipReason::ElevatedContext,
+        SkipReason::RemoteSession,
+        SkipReason::InsideEmacs,
+        SkipReason::InsideMultiplexer,
+        SkipReason::AlreadyPreferred,
+        SkipReason::DefaultAlreadyPreferredButNotActive,
+        SkipReason::DeclinedByUser,
+        SkipReason::CooldownActive,
+        SkipReason::NoImprovementPossible,
+    ];
+
+    /// Any terminal, favouring those `decide()` treats specially.
+    fn terminals() -> impl Strategy<Value = TerminalType> {
+        prop_oneof![
+            Just(TerminalType::Emacs),
+            Just(TerminalType::NonTerminalHost),
+            proptest::sample::select(TerminalType::iter().collect::<Vec<_>>()),
+        ]
+    }
+
+    prop_compose! {
+        fn inputs()(terminal in terminals())(
+            flags in proptest::array::uniform11(any::<bool>()),
+            multiplexer in proptest::option::of(proptest::sample::select(vec![
+                Multiplexer::Tmux,
+                Multiplexer::Screen,
+                Multiplexer::Zellij,
+            ])),
+            // Mostly the current terminal, so every candidate being it is common..
+            installed_candidates in proptest::option::of(proptest::collection::vec(
+                prop_oneof![3 => Just(terminal), 1 => terminals()],
+                0..3,
+            )),
+            terminal in Just(terminal),
+        ) -> DecisionInputs {
+            let [
+                already_relaunched,
+                service_context,
+                interactive,
+                installer_context,
+                elevated,
+                remote_session,
+                full_unicode,
+                rgb_ansi_colours,
+                default_already_preferred_but_not_active,
+                declined_by_user,
+                cooldown_active,
+            ] = flags;
+            DecisionInputs {
+                terminal,
+                already_relaunched,
+                service_context,
+                interactive,
+                installer_context,
+                elevated,
+                remote_session,
+                multiplexer,
+                full_unicode,
+                rgb_ansi_colours,
+                default_already_preferred_but_not_active,
+                declined_by_user,
+                cooldown_active,
+                installed_candidates,
+            }
+        }
+    }
+
+    prop_compose! {
+        fn configs()(
+            allow_elevated in any::<bool>(),
+            allow_emacs in any::<bool>(),
+            skip_multiplexer in any::<bool>(),
+        ) -> RelaunchConfig {
+            RelaunchConfig::new()
+                .allow_elevated_relaunch(allow_elevated)
+                .allow_emacs_relaunch(allow_emacs)
+                .skip_inside_multiplexer(skip_multiplexer)
+        }
+    }
+
+    prop_compose! {
+        fn requirements()(full_unicode in any::<bool>(), rgb_ansi_colours in any::<bool>()) -> FeatureRequirements {
+            FeatureRequirements { full_unicode, rgb_ansi_colours }
+        }
+    }
+
+    /// Returns `true` if the inputs explain skipping for the given reason, whatever the other inputs.
+    fn explains(
+        reason: SkipReason,
+        inputs: &DecisionInputs,
+        config: &RelaunchConfig,
+        requirements: FeatureRequirements,
+    ) -> bool {
+        match reason {
+            SkipReason::AlreadyRelaunched => inputs.already_relaunched,
+            SkipReason::ServiceContext => inputs.service_context,
+            SkipReason::NotInteractive => {
+                !inputs.interactive && inputs.terminal != TerminalType::NonTerminalHost
+            }
+            SkipReason::InstallerContext => inputs.installer_context,
+            SkipReason::ElevatedContext => inputs.elevated && !config.allow_elevated_relaunch,
+            SkipReason::RemoteSession => inputs.remote_session,
+            SkipReason::InsideEmacs => {
+                inputs.terminal == TerminalType::Emacs && !config.allow_emacs_relaunch
+            }
+            SkipReason::InsideMultiplexer => {
+                inputs.multiplexer.is_some() && config.skip_inside_multiplexer
+            }
+            SkipReason::AlreadyPreferred => {
+                requirements.satisfied_by(inputs.full_unicode, inputs.rgb_ansi_colours)
+            }
+            SkipReason::DefaultAlreadyPreferredButNotActive => {
+                inputs.default_already_preferred_but_not_active
+            }
+            SkipReason::DeclinedByUser => inputs.declined_by_user,
+            SkipReason::CooldownActive => inputs.cooldown_active,
+            SkipReason::NoImprovementPossible => {
+                inputs
+                    .installed_candidates
+                    .as_ref()
+                    .is_some_and(|candidates| {
+                        !candidates.is_empty()
+                            && candidates
+                                .iter()
+                                .all(|candidate| *candidate == inputs.terminal)
+                    })
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn never_attempts_when_already_relaunched(
+            inputs in inputs(),
+            config in configs(),
+            requirements in requirements(),
+        ) {
+            let relaunched = DecisionInputs { already_relaunched: true, ..inputs };
+            prop_assert_eq!(
+                decide(&relaunched, &config, &requirements),
+                RelaunchDecision::Skip(SkipReason::AlreadyRelaunched)
+            );
+        }
+
+        #[test]
+        fn never_attempts_when_requirements_are_satisfied(
+            inputs in inputs(),
+            config in configs(),
+            requirements in requirements(),
+        ) {
+            prop_assume!(requirements.satisfied_by(inputs.full_unicode, inputs.rgb_ansi_colours));
+            prop_assert!(!decide(&inputs, &config, &requirements).should_attempt());
+        }
+
+        #[test]
+        fn skips_for_the_first_reason_explained_by_the_inputs(
+            inputs in inputs(),
+            config in configs(),
+            requirements in requirements(),
+        ) {
+            let first = CHECK_ORDER
+                .into_iter()
+                .find(|reason| explains(*reason, &inputs, &config, requirements));
+            prop_assert_eq!(
+                decide(&inputs, &config, &requirements),
+                first.map_or(RelaunchDecision::Attempt, RelaunchDecision::Skip)
+            );
+        }
+    }
+
+    #[test]
+    fn no_improvement_is_only_decided_when_every_candidate_is_the_current_terminal() {
+        let decide_with = |candidates: Option<Vec<TerminalType>>| {
+            let inputs = DecisionInputs {
+                installed_candidates: candidates,
+                ..DecisionInputs::new(TerminalType::Alacritty)
+            };
+            decide(
+                &inputs,
+                &RelaunchConfig::new(),
+                &FeatureRequirements::PREFERRED,
+            )
+        };
+
+        assert_eq!(
+            decide_with(Some(vec![TerminalType::Alacritty; 2])),
+            RelaunchDecision::Skip(SkipReason::NoImprovementPossible)
+        );
+        for candidates in [
+            None,
+            Some(Vec::new()),
+            Some(vec![TerminalType::Alacritty, TerminalType::WezTerm]),
+        ] {
+            assert_eq!(
+                decide_with(candidates.clone()),
+                RelaunchDecision::Attempt,
+                "{candidates:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn default_preferred_but_not_active_matrix() {
+        for (terminal, default, expected) in [
+            // The stale shortcut, the console host while the default is `Windows Terminal`..
+            (
+                TerminalType::WindowsCMD,
+                Some(TerminalType::WindowsTerminal),
+                true,
+            ),
+            // ..but not when the default is the console host, unset or not installed..
+            (
+                TerminalType::WindowsCMD,
+                Some(TerminalType::WindowsCMD),
+                false,
+            ),
+            (TerminalType::WindowsCMD, None, false),
+            // ..or when not in the console host..
+            (
+                TerminalType::WindowsTerminal,
+                Some(TerminalType::WindowsTerminal),
+                false,
+            ),
+            (
+                TerminalType::ConEmu,
+                Some(TerminalType::WindowsTerminal),
+                false,
+            ),
+            (
+                TerminalType::Mintty,
+                Some(TerminalType::WindowsTerminal),
+                false,
+            ),
+        ] {
+            let not_active = default_preferred_but_not_active(terminal, || default);
+            assert_eq!(not_active, expected, "{terminal:?} {default:?}");
+
+            // ..which the console host, missing features, is skipped for..
+            let inputs = DecisionInputs {
+                full_unicode: terminal.supports_full_unicode(),
+                rgb_ansi_colours: terminal.supports_rgb_ansi_colours(),
+                default_already_preferred_but_not_active: not_active,
+                ..DecisionInputs::new(terminal)
+            };
+            let decision = decide(
+                &inputs,
+                &RelaunchConfig::new(),
+                &FeatureRequirements::PREFERRED,
+            );
+            if expected {
+                assert_eq!(
+                    decision,
+                    RelaunchDecision::Skip(SkipReason::DefaultAlreadyPreferredButNotActive)
+                );
+            } else {
+                assert_ne!(
+                    decision,
+                    RelaunchDecision::Skip(SkipReason::DefaultAlreadyPreferredButNotActive)
+                );
+            }
+        }
+
+        // ..and the default is only looked up from the console host..
+        assert!(!default_preferred_but_not_active(
+            TerminalType::WindowsTerminal,
+            || { unreachable!("looked up outside the console host") }
+        ));
+    }
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn current_inputs_are_gathered_from_the_system_facts() {
+        let _globals = reading_globals();
+        assert_eq!(
+            DecisionInputs::current(),
+            DecisionInputs::gather_in(
+                &SystemEnvironment,
+                &SystemFacts::current(),
+                crate::capabilities::probe_if_enabled
+            )
+        );
+    }
+
+    #[test]
+    fn facts_reach_the_inputs() {
+        let _globals = reading_globals();
+        let gather = |vars: &[(&str, &str)], facts: SystemFacts| {
+            DecisionInputs::gather_in(&env(vars), &facts, || false)
+        };
+        let wezterm = SystemFacts::new(TerminalType::WezTerm);
+
+        let plain = gather(&[], wezterm.clone());
+        assert!(plain.full_unicode && plain.rgb_ansi_colours && !plain.remote_session);
+
+        // Services have neither capability, whatever the terminal..
+        let service = gather(
+            &[],
+            SystemFacts {
+                service_context: true,
+                ..wezterm.clone()
+            },
+        );
+        assert!(service.service_context && !service.full_unicode && !service.rgb_ansi_colours);
+
+        // Remote hosts, ancestors and variables make the session remote..
+        for host in [HostKind::WinRm, HostKind::RemoteExecution] {
+            let hosted = SystemFacts {
+                non_terminal_host: Some(host),
+                ..wezterm.clone()
+            };
+            assert!(gather(&[], hosted).remote_session, "{host}");
+        }
+        let ise = SystemFacts {
+            non_terminal_host: Some(HostKind::PowerShellIse),
+            ..wezterm.clone()
+        };
+        assert!(!gather(&[], ise).remote_session);
+        let mosh = SystemFacts {
+            ancestry: vec![ProcessInfo {
+                pid: 7,
+                name: "mosh-server".to_string(),
+                path: None,
+            }],
+            ..wezterm.clone()
+        };
+        assert!(gather(&[], mosh).remote_session);
+        assert!(gather(&[("SSH_TTY", "/dev/pts/3")], wezterm.clone()).remote_session);
+
+        // ..and `tmux` limits RGB colours only when the environment is inside it..
+        let no_rgb = SystemFacts {
+            tmux_features: Some(TmuxFeatures::default()),
+            ..wezterm.clone()
+        };
+        let inside = gather(&[("TMUX", "/tmp/tmux-1000/default,1,0")], no_rgb.clone());
+        assert_eq!(inside.multiplexer, Some(Multiplexer::Tmux));
+        assert!(!inside.rgb_ansi_colours);
+        assert!(gather(&[], no_rgb).rgb_ansi_colours);
+    }
+}
@@ -0,0 +1,577 @@
+//! A diagnostic report of the detected terminal, the variables it was detected from, and the registered capability
+//! rules, e.g. for users to attach to bug reports.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::TerminalType;
+//! use terminal_relaunch::capabilities::{CapabilityRule, PartialCapabilities, register_capability_override};
+//! use terminal_relaunch::doctor::DoctorReport;
+//! use terminal_relaunch::info::TerminalInfo;
+//! use terminal_relaunch::version::TerminalVersion;
+//!
+//! for (version, unicode) in [("<0.22", Some(false)), ("0.21.3", Some(true))] {
+//!     register_capability_override(CapabilityRule {
+//!         terminal: TerminalType::Kitty,
+//!         version: Some(version.parse().unwrap()),
+//!         set: PartialCapabilities { full_unicode: unicode, ..Default::default() },
+//!     });
+//! }
+//!
+//! let env = HashMap::from([("TERM".to_string(), "xterm-kitty".to_string())]);
+//! let info = TerminalInfo { version: Some(TerminalVersion::new(0, 21, 3)), ..TerminalInfo::new(TerminalType::Kitty) };
+//! let report = DoctorReport::for_info_in(info, &env);
+//!
+//! // Both rules match, but the first registered applies..
+//! assert_eq!(report.applied_rule().map(|rule| rule.set.full_unicode), Some(Some(false)));
+//! assert_eq!(
+//!     report.to_string(),
+//!     "Terminal: Kitty 0.21.3, Enhanced Colours\n\
+//!      Full unicode: built-in on, rule off, Parent Process unset, User unset, Application unset\n\
+//!      RGB colours: built-in on, rule unset, Parent Process unset, User unset, Application unset\n\
+//!      TERM=xterm-kitty\n\
+//!      TERM_PROGRAM is unset\n\
+//!      TERM_PROGRAM_VERSION is unset\n\
+//!      COLORTERM is unset\n\
+//!      Remote session: none\n\
+//!      Sudo: no\n\
+//!      Capability rules:\n\
+//!      \x20 1. Kitty <0.22.0: full unicode off (applied)\n\
+//!      \x20 2. Kitty 0.21.3: full unicode on\n"
+//! );
+//! ```
+
+use std::fmt::Display;
+
+use crate::OperatingSystem;
+use strum::IntoEnumIterator;
+
+use crate::capabilities::{
+    COLORTERM_VAR, CapabilityRule, HintSource, PartialCapabilities, capability_hint,
+    capability_overrides,
+};
+use crate::environment::{Environment, QuotedVar, SystemEnvironment, quoted_vars_in};
+use crate::info::{EffectiveCapabilities, TerminalInfo};
+use crate::logging::sanitize_for_log;
+use crate::remote::RemoteSession;
+use crate::sudo::SudoUser;
+use crate::terminal_providers::{TERM_PROGRAM_VAR, TERM_VAR};
+use crate::version::TERM_PROGRAM_VERSION_VAR;
+
+/// The environment variables listed by `DoctorReport`, as most terminals are detected from them.
+pub const REPORTED_VARS: &[&str] = &[
+    TERM_VAR,
+    TERM_PROGRAM_VAR,
+    TERM_PROGRAM_VERSION_VAR,
+    COLORTERM_VAR,
+];
+
+/// A registered capability rule, and whether it's the rule applied to the reported terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CapabilityRuleStatus {
+    /// The registered rule.
+    pub rule: CapabilityRule,
+    /// If the rule is the first registered rule matching the reported terminal and version, so is the one applied.
+    pub applied: bool,
+}
+
+/// What each layer sets a capability to, from the lowest precedence to the highest, see `capabilities::HintSource`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CapabilityLayers {
+    /// The built-in support of the reported terminal, before detection from the environment.
+    pub built_in: bool,
+    /// What the applied capability rule sets, if anything, see `DoctorReport::applied_rule()`.
+    pub rule: Option<bool>,
+    /// What the hint of each source sets, if anything, in order of precedence.
+    pub hints: Vec<(HintSource, Option<bool>)>,
+}
+
+impl CapabilityLayers {
+    /// Returns the layers of a capability, taking it from the applied rule and each hint with `capability`.
+    fn new(
+        built_in: bool,
+        rule: Option<&CapabilityRule>,
+        capability: impl Fn(PartialCapabilities) -> Option<bool>,
+    ) -> Self {
+        Self {
+            built_in,
+            rule: rule.and_then(|rule| capability(rule.set)),
+            hints: HintSource::iter()
+                .map(|source| (source, capability(capability_hint(source))))
+                .collect(),
+        }
+    }
+}
+
+impl Display for CapabilityLayers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let setting = |value: Option<bool>| match value {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "unset",
+        };
+
+        write!(
+            f,
+            "built-in {}, rule {}",
+            setting(Some(self.built_in)),
+            setting(self.rule)
+        )?;
+        for (source, value) in &self.hints {
+            write!(f, ", {source} {}", setting(*value))?;
+        }
+        Ok(())
+    }
+}
+
+/// A diagnostic report of the detected terminal, see `DoctorReport::current()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorReport {
+    /// The reported terminal.
+    pub info: TerminalInfo,
+    /// The capabilities output uses in the reported terminal, see `TerminalInfo::effective_capabilities_in()`.
+    pub capabilities: EffectiveCapabilities,
+    /// What each layer sets full unicode support to.
+    pub full_unicode_layers: CapabilityLayers,
+    /// What each layer sets RGB (ANSI) colour support to.
+    pub rgb_ansi_colour_layers: CapabilityLayers,
+    /// The remote session the reported terminal is reached through, see `TerminalInfo::remote`.
+    pub remote: RemoteSession,
+    /// If the program runs translated under Rosetta (see `is_rosetta()`), `None` if unknown or not on `MacOS`.
+    pub rosetta: Option<bool>,
+    /// If the program runs as a background service (see `service::is_service_context()`), `None` if unknown.
+    pub service_context: Option<bool>,
+    /// The effective user, see `sudo::effective_user_in()`, `None` if unknown.
+    pub effective_user: Option<String>,
+    /// The user who invoked `sudo`, if running under `sudo`, see `sudo::invoking_user()`.
+    pub invoking_user: Option<SudoUser>,
+    /// The values of `REPORTED_VARS`, sanitised as `logging::sanitize_for_log()`, `None` if unset.
+    pub vars: Vec<(&'static str, Option<String>)>,
+    /// The `REPORTED_VARS` with quoted values, as compared after stripping their quotes, see
+    /// `environment::strip_env_quotes()`.
+    pub quoted_vars: Vec<QuotedVar>,
+    /// Every registered capability rule, in registration order.
+    pub capability_rules: Vec<CapabilityRuleStatus>,
+}
+
+impl DoctorReport {
+    /// Returns the report of the current terminal, and the current process.
+    #[must_use]
+    pub fn current() -> Self {
+        Self {
+            rosetta: (OperatingSystem::current() == OperatingSystem::MacOS).then(crate::is_rosetta),
+            service_context: Some(crate::service::is_service_context()),
+            ..Self::for_info_in(TerminalInfo::current(), &SystemEnvironment)
+        }
+    }
+
+    /// Returns the report of the given terminal, with the given environment.
+    ///
+    /// # Notes
+    /// *   The capability rules are read once, so the rule marked as applied is always one of those listed, even
+    ///     while rules are registered on other threads. Capability hints, e.g. `set_unicode_support_override()`,
+    ///     still take precedence over the applied rule, see `capabilities::HintSource`.
+    /// *   Facts about the current process rather than the terminal (e.g. `rosetta` and `service_context`) are left
+    ///     unknown, see `current()`.
+    #[must_use]
+    pub fn for_info_in(info: TerminalInfo, env: &(impl Environment + ?Sized)) -> Self {
+        let vars = REPORTED_VARS
+            .iter()
+            .map(|name| {
+                let value = env
+                    .var(name)
+                    .map(|value| sanitize_for_log(&value).into_owned());
+                (*name, value)
+            })
+            .collect();
+
+        let rules = capability_overrides();
+        let applied = rules
+            .iter()
+            .position(|rule| rule.applies_to(info.terminal, info.version.as_ref()));
+        let applied_rule = applied.map(|index| &rules[index]);
+        let full_unicode_layers = CapabilityLayers::new(
+            crate::capabilities::builtin_full_unicode_support(info.terminal, info.version.as_ref()),
+            applied_rule,
+            |set| set.full_unicode,
+        );
+        let rgb_ansi_colour_layers = CapabilityLayers::new(
+            crate::capabilities::builtin_rgb_ansi_colour_support(info.terminal),
+            applied_rule,
+            |set| set.rgb_ansi_colours,
+        );
+        let capability_rules = rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| CapabilityRuleStatus {
+                rule,
+                applied: applied == Some(index),
+            })
+            .collect();
+
+        Self {
+            info,
+            capabilities: info.effective_capabilities_in(env),
+            full_unicode_layers,
+            rgb_ansi_colour_layers,
+            remote: info.remote,
+            rosetta: None,
+            service_context: None,
+            effective_user: crate::sudo::effective_user_in(env),
+            invoking_user: crate::sudo::invoking_user_in(env),
+            vars,
+            quoted_vars: quoted_vars_in(env, REPORTED_VARS.iter().copied()),
+            capability_rules,
+        }
+    }
+
+    /// Returns the capability rule applied to the reported terminal, if any.
+    #[inline]
+    #[must_use]
+    pub fn applied_rule(&self) -> Option<&CapabilityRule> {
+        self.capability_rules
+            .iter()
+            .find(|status| status.applied)
+            .map(|status| &status.rule)
+    }
+}
+
+/// Writes the capabilities a rule sets, e.g. `full unicode off, RGB colours on`.
+fn write_rule_capabilities(
+    f: &mut std::fmt::Formatter<'_>,
+    set: PartialCapabilities,
+) -> std::fmt::Result {
+    let capabilities: Vec<String> = [
+        ("full unicode", set.full_unicode),
+        ("RGB colours", set.rgb_ansi_colours),
+    ]
+    .into_iter()
+    .filter_map(|(name, supports)| {
+        supports.map(|supports| format!("{name} {}", if supports { "on" } else { "off" }))
+    })
+    .collect();
+
+    if capabilities.is_empty() {
+        write!(f, "nothing")
+    } else {
+        write!(f, "{}", capabilities.join(", "))
+    }
+}
+
+/// Returns `"yes"` or `"no"`, for the report lines of facts.
+fn yes_no(fact: bool) -> &'static str {
+    if fact { "yes" } else { "no" }
+}
+
+impl Display for DoctorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let terminal: String = self
+            .info
+            .terminal
+            .segments_with(
+                self.info.version.as_ref(),
+                self.capabilities.full_unicode,
+                self.capabilities.rgb_ansi_colours,
+                false,
+            )
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+        writeln!(f, "Terminal: {terminal}")?;
+        writeln!(f, "Full unicode: {}", self.full_unicode_layers)?;
+        writeln!(f, "RGB colours: {}", self.rgb_ansi_colour_layers)?;
+        for (name, value) in &self.vars {
+            let Some(value) = value else {
+                writeln!(f, "{name} is unset")?;
+                continue;
+            };
+            write!(f, "{name}={value}")?;
+            if let Some(quoted) = self.quoted_vars.iter().find(|quoted| quoted.name == *name) {
+                write!(f, " (compared as {})", sanitize_for_log(&quoted.normalized))?;
+            }
+            writeln!(f)?;
+        }
+
+        match self.remote {
+            RemoteSession::None => writeln!(f, "Remote session: none")?,
+            remote => writeln!(f, "Remote session: {remote}")?,
+        }
+        if let Some(rosetta) = self.rosetta {
+            writeln!(f, "Rosetta: {}", yes_no(rosetta))?;
+        }
+        if let Some(service_context) = self.service_context {
+            writeln!(f, "Service context: {}", yes_no(service_context))?;
+        }
+        match &self.invoking_user {
+            Some(invoking) => {
+                let effective = self.effective_user.as_deref().unwrap_or("an unknown user");
+                write!(
+                    f,
+                    "Sudo: running as {}, invoked by {}",
+                    sanitize_for_log(effective),
+                    sanitize_for_log(&invoking.name)
+                )?;
+                if let Some(uid) = invoking.uid {
+                    write!(f, " (uid {uid})")?;
+                }
+                writeln!(f)?;
+            }
+            None => writeln!(f, "Sudo: no")?,
+        }
+
+        if self.capability_rules.is_empty() {
+            return writeln!(f, "Capability rules: none");
+        }
+        writeln!(f, "Capability rules:")?;
+        for (index, status) in self.capability_rules.iter().enumerate() {
+            write!(f, "  {}. {}", index + 1, status.rule.terminal.name())?;
+            if let Some(version) = status.rule.version {
+                write!(f, " {version}")?;
+            }
+            write!(f, ": ")?;
+            write_rule_capabilities(f, status.rule.set)?;
+            if status.applied {
+                write!(f, " (applied)")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use strum::IntoEnumIterator;
+
+    use super::{CapabilityLayers, DoctorReport};
+    use crate::capabilities::{
+        CapabilityRule, HintSource, PartialCapabilities, capability_hint, clear_capability_hints,
+        clear_capability_overrides, register_capability_override, set_capability_hint,
+    };
+    use crate::info::TerminalInfo;
+    use crate::remote::RemoteSession;
+    use crate::sudo::USER_VAR;
+    use crate::test_support::{reading_globals, writing_globals};
+    use crate::{OperatingSystem, TerminalType};
+
+    /// Returns the report of the given terminal in an empty environment.
+    fn report(info: TerminalInfo) -> DoctorReport {
+        DoctorReport::for_info_in(info, &HashMap::<String, String>::new())
+    }
+
+    #[test]
+    fn the_remote_session_is_reported() {
+        let _globals = reading_globals();
+        for (remote, line) in [
+            (RemoteSession::None, "Remote session: none"),
+            (RemoteSession::Ssh, "Remote session: SSH"),
+            (RemoteSession::Mosh, "Remote session: mosh"),
+        ] {
+            let report = report(TerminalInfo {
+                remote,
+                ..TerminalInfo::new(TerminalType::Xterm)
+            });
+            assert_eq!(report.remote, remote);
+            assert!(
+                report.to_string().lines().any(|report| report == line),
+                "{remote:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rosetta_is_reported_when_known() {
+        let _globals = reading_globals();
+        let unknown = report(TerminalInfo::new(TerminalType::MacOS));
+        assert_eq!(unknown.rosetta, None);
+        assert!(!unknown.to_string().contains("Rosetta"));
+
+        for (rosetta, line) in [(true, "Rosetta: yes"), (false, "Rosetta: no")] {
+            let known = DoctorReport {
+                rosetta: Some(rosetta),
+                ..unknown.clone()
+            };
+            assert!(known.to_string().lines().any(|report| report == line));
+        }
+
+        // ..and only reported by the current process on `MacOS`..
+        let current = DoctorReport::current();
+        assert_eq!(
+            current.rosetta.is_some(),
+            OperatingSystem::current() == OperatingSystem::MacOS
+        );
+    }
+
+    #[test]
+    fn the_service_context_is_reported_when_known() {
+        let _globals = reading_globals();
+        let unknown = report(TerminalInfo::new(TerminalType::Xterm));
+        assert_eq!(unknown.service_context, None);
+        assert!(!unknown.to_string().contains("Service context"));
+
+        for (service_context, line) in [
+            (true, "Service context: yes"),
+            (false, "Service context: no"),
+        ] {
+            let known = DoctorReport {
+                service_context: Some(service_context),
+                ..unknown.clone()
+            };
+            assert!(known.to_string().lines().any(|report| report == line));
+        }
+
+        // ..and always by the current process..
+        assert_eq!(
+            DoctorReport::current().service_context,
+            Some(crate::service::is_service_context())
+        );
+    }
+
+    #[test]
+    fn both_identities_are_reported_under_sudo() {
+        let _globals = reading_globals();
+        let report_in = |vars: &[(&str, &str)]| {
+            let env: HashMap<String, String> = vars
+                .iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect();
+            DoctorReport::for_info_in(TerminalInfo::new(TerminalType::Xterm), &env)
+        };
+        let sudo_line = |report: &DoctorReport| {
+            report
+                .to_string()
+                .lines()
+                .find(|line| line.starts_with("Sudo: "))
+                .map(str::to_string)
+        };
+
+        for (vars, line) in [
+            (
+                &[
+                    (USER_VAR, "root"),
+                    ("SUDO_USER", "alice"),
+                    ("SUDO_UID", "1000"),
+                ][..],
+                "Sudo: running as root, invoked by alice (uid 1000)",
+            ),
+            (
+                &[(USER_VAR, "root"), ("SUDO_USER", "alice")][..],
+                "Sudo: running as root, invoked by alice",
+            ),
+            (
+                &[("SUDO_USER", "alice"), ("SUDO_UID", "1000")][..],
+                "Sudo: running as an unknown user, invoked by alice (uid 1000)",
+            ),
+            // `sudo` as root, or not at all, changes nothing..
+            (&[(USER_VAR, "root"), ("SUDO_USER", "root")][..], "Sudo: no"),
+            (&[(USER_VAR, "alice")][..], "Sudo: no"),
+        ] {
+            let report = report_in(vars);
+            assert_eq!(sudo_line(&report).as_deref(), Some(line), "{vars:?}");
+        }
+
+        let report = report_in(&[
+            (USER_VAR, "root"),
+            ("SUDO_USER", "alice"),
+            ("SUDO_UID", "1000"),
+        ]);
+        assert_eq!(report.effective_user.as_deref(), Some("root"));
+        let invoking = report.invoking_user.unwrap();
+        assert_eq!(
+            (invoking.name.as_str(), invoking.uid),
+            ("alice", Some(1000))
+        );
+    }
+
+    #[test]
+    fn each_layer_is_reported_per_capability() {
+        let _globals = writing_globals();
+        let saved_hints: Vec<_> = HintSource::iter().map(capability_hint).collect();
+        clear_capability_overrides();
+        clear_capability_hints();
+
+        register_capability_override(CapabilityRule {
+            terminal: TerminalType::Xterm,
+            version: None,
+            set: PartialCapabilities {
+                rgb_ansi_colours: Some(true),
+                ..PartialCapabilities::NONE
+            },
+        });
+        set_capability_hint(
+            HintSource::ParentProcess,
+            PartialCapabilities {
+                full_unicode: Some(true),
+                rgb_ansi_colours: Some(false),
+            },
+        );
+        crate::set_unicode_support_override(Some(false));
+        let report = report(TerminalInfo::new(TerminalType::Xterm));
+
+        clear_capability_overrides();
+        clear_capability_hints();
+        for (source, hint) in HintSource::iter().zip(saved_hints) {
+            set_capability_hint(source, hint);
+        }
+
+        assert_eq!(
+            report.full_unicode_layers,
+            CapabilityLayers {
+                built_in: false,
+                rule: None,
+                hints: vec![
+                    (HintSource::ParentProcess, Some(true)),
+                    (HintSource::User, None),
+                    (HintSource::Application, Some(false)),
+                ],
+            }
+        );
+        let text = report.to_string();
+        assert!(text.contains(
+            "Full unicode: built-in off, rule unset, Parent Process on, User unset, Application off\n"
+        ));
+        assert!(text.contains(
+            "RGB colours: built-in off, rule on, Parent Process off, User unset, Application unset\n"
+        ));
+    }
+
+    #[test]
+    fn quoted_values_are_reported_as_compared() {
+        let _globals = reading_globals();
+        let env: HashMap<String, String> = [
+            ("TERM", "\"xterm-kitty\""),
+            ("TERM_PROGRAM", " 'WezTerm'\n"),
+            ("COLORTERM", "truecolor"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let report = DoctorReport::for_info_in(TerminalInfo::new(TerminalType::Kitty), &env);
+        assert_eq!(
+            report
+                .quoted_vars
+                .iter()
+                .map(|quoted| (quoted.name, quoted.normalized.as_str()))
+                .collect::<Vec<_>>(),
+            [("TERM", "xterm-kitty"), ("TERM_PROGRAM", "WezTerm")]
+        );
+
+        // Values compared as set are reported as before..
+        let text = report.to_string();
+        for line in [
+            "TERM=\"xterm-kitty\" (compared as xterm-kitty)",
+            "COLORTERM=truecolor",
+            "TERM_PROGRAM_VERSION is unset",
+        ] {
+            assert!(text.lines().any(|report| report == line), "{line}");
+        }
+        assert!(
+            text.lines().any(|line| line.starts_with("TERM_PROGRAM=")
+                && line.ends_with(" (compared as WezTerm)"))
+        );
+    }
+}
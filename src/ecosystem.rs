@@ -0,0 +1,131 @@
+//! Integration with the `anstream`/`anstyle` ecosystem, so applications can use this library's colour
+//! detection as the single source of truth.
+
+use crate::capabilities::{self, ColourDepth};
+
+/// Returns the `anstream::ColorChoice` matching the effective colour depth of the current terminal.
+///
+/// # Returns
+/// *   `ColorChoice::Never` if colours are disabled, e.g. by `NO_COLOR`.
+/// *   `ColorChoice::Always` on legacy `Windows` consoles, which need the console API rather than ANSI sequences.
+/// *   `ColorChoice::AlwaysAnsi` otherwise.
+#[must_use]
+pub fn color_choice() -> anstream::ColorChoice {
+    color_choice_for(capabilities::colour_depth(), crate::is_legacy_windows())
+}
+
+/// Returns the `anstream::ColorChoice` for the given colour depth, see `color_choice()`.
+#[inline]
+#[must_use]
+pub fn color_choice_for(depth: ColourDepth, legacy_windows: bool) -> anstream::ColorChoice {
+    match depth {
+        ColourDepth::NoColour => anstream::ColorChoice::Never,
+        _ if legacy_windows => anstream::ColorChoice::Always,
+        ColourDepth::Ansi16 | ColourDepth::Ansi256 | ColourDepth::TrueColour => {
+            anstream::ColorChoice::AlwaysAnsi
+        }
+    }
+}
+
+/// Returns the maximum ANSI colour level of the current terminal, as the numeric levels used across the
+/// ecosystem: `0` (none), `1` (16 colours), `2` (256 colours) or `3` (true colour).
+#[inline]
+#[must_use]
+pub fn max_ansi_level() -> u8 {
+    ansi_level_for(capabilities::colour_depth())
+}
+
+/// Returns the numeric ANSI colour level of the given colour depth, see `max_ansi_level()`.
+#[inline]
+#[must_use]
+pub const fn ansi_level_for(depth: ColourDepth) -> u8 {
+    match depth {
+        ColourDepth::NoColour => 0,
+        ColourDepth::Ansi16 => 1,
+        ColourDepth::Ansi256 => 2,
+        ColourDepth::TrueColour => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anstream::ColorChoice;
+
+    use super::{ansi_level_for, color_choice_for};
+    use crate::capabilities::{
+        COLORTERM_VAR, ColourDepth, FORCE_COLOR_VAR, NO_COLOR_VAR, resolve_colour_depth,
+    };
+
+    #[test]
+    fn each_depth_maps_to_a_choice_and_level() {
+        for (depth, level, choice) in [
+            (ColourDepth::NoColour, 0, ColorChoice::Never),
+            (ColourDepth::Ansi16, 1, ColorChoice::AlwaysAnsi),
+            (ColourDepth::Ansi256, 2, ColorChoice::AlwaysAnsi),
+            (ColourDepth::TrueColour, 3, ColorChoice::AlwaysAnsi),
+        ] {
+            assert_eq!(ansi_level_for(depth), level, "{depth:?}");
+            assert_eq!(color_choice_for(depth, false), choice, "{depth:?}");
+
+            // Legacy consoles use the console API for any colours..
+            let legacy = if depth == ColourDepth::NoColour {
+                ColorChoice::Never
+            } else {
+                ColorChoice::Always
+            };
+            assert_eq!(color_choice_for(depth, true), legacy, "{depth:?}");
+        }
+    }
+
+    #[test]
+    fn force_color_takes_precedence_over_no_color() {
+        for force in [
+            None,
+            Some("0"),
+            Some("false"),
+            Some("1"),
+            Some("2"),
+            Some("3"),
+            Some("yes"),
+        ] {
+            for no_color in [None, Some(""), Some("1")] {
+                for term in ["xterm", "xterm-256color"] {
+                    for rgb_ansi_colours in [false, true] {
+                        let mut env = HashMap::from([("TERM".to_string(), term.to_string())]);
+                        for (name, value) in [(FORCE_COLOR_VAR, force), (NO_COLOR_VAR, no_color)] {
+                            if let Some(value) = value {
+                                env.insert(name.to_string(), value.to_string());
+                            }
+                        }
+
+                        let detected = if rgb_ansi_colours {
+                            ColourDepth::TrueColour
+                        } else if term == "xterm-256color" {
+                            ColourDepth::Ansi256
+                        } else {
+                            ColourDepth::Ansi16
+                        };
+                        let expected = match (force, no_color) {
+                            (Some("0" | "false"), _) => ColourDepth::NoColour,
+                            (Some("1"), _) => ColourDepth::Ansi16,
+                            (Some("2"), _) => ColourDepth::Ansi256,
+                            (Some("3"), _) => ColourDepth::TrueColour,
+                            (Some(_), _) | (None, None | Some("")) => detected,
+                            (None, Some(_)) => ColourDepth::NoColour,
+                        };
+
+                        let depth = resolve_colour_depth(&env, rgb_ansi_colours);
+                        let case = format!("{force:?} {no_color:?} {term} {rgb_ansi_colours}");
+                        assert_eq!(depth, expected, "{case}");
+                    }
+                }
+            }
+        }
+
+        // `COLORTERM` isn't read here, it only confirms RGB support for some terminals..
+        let env = HashMap::from([(COLORTERM_VAR.to_string(), "truecolor".to_string())]);
+        assert_eq!(resolve_colour_depth(&env, false), ColourDepth::Ansi16);
+    }
+}
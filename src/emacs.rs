@@ -0,0 +1,119 @@
+//! Classification of the terminals inside `Emacs`, which all match the `Emacs` terminal identifier.
+//!
+//! Their capabilities differ wildly, `vterm` and `eat` render RGB colours while `term` and `ansi-term` only render
+//! 8 colours, so `TerminalType::Emacs` is conservative and RGB colours are confirmed per terminal, see
+//! `capabilities::env_confirms_rgb_in()`.
+//!
+//! Relaunching from `Emacs` is skipped by default, as opening a terminal window out from under the editor is
+//! hostile, see `RelaunchConfig::allow_emacs_relaunch`.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::emacs::{EmacsTerminal, detect_emacs_terminal_in};
+//!
+//! let env = |vars: &[(&str, &str)]| {
+//!     vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect::<HashMap<_, _>>()
+//! };
+//!
+//! assert_eq!(detect_emacs_terminal_in(&env(&[("INSIDE_EMACS", "vterm")])), EmacsTerminal::Vterm);
+//! assert_eq!(
+//!     detect_emacs_terminal_in(&env(&[("INSIDE_EMACS", "29.1,comint"), ("EMACS_VTERM_PATH", "/usr/share/emacs/vterm")])),
+//!     EmacsTerminal::Vterm
+//! );
+//! assert_eq!(detect_emacs_terminal_in(&env(&[("INSIDE_EMACS", "29.1,eat")])), EmacsTerminal::Eat);
+//! assert_eq!(detect_emacs_terminal_in(&env(&[("INSIDE_EMACS", "29.1,term:0.96")])), EmacsTerminal::Term);
+//! assert_eq!(detect_emacs_terminal_in(&env(&[("INSIDE_EMACS", "29.1,comint")])), EmacsTerminal::Other);
+//! assert_eq!(detect_emacs_terminal_in(&env(&[("INSIDE_EMACS", "t")])), EmacsTerminal::Other);
+//!
+//! assert!(EmacsTerminal::Vterm.supports_rgb_ansi_colours() && EmacsTerminal::Eat.supports_rgb_ansi_colours());
+//! assert!(!EmacsTerminal::Term.supports_rgb_ansi_colours());
+//! assert_eq!(EmacsTerminal::Term.to_string(), "term");
+//! ```
+
+use std::fmt::Display;
+
+use crate::TerminalType;
+use crate::environment::{Environment, SystemEnvironment};
+
+/// Environment variable `Emacs` sets for its subprocesses, e.g. `29.1,term:0.96`, `29.1,eat` or `vterm`.
+pub const INSIDE_EMACS_VAR: &str = "INSIDE_EMACS";
+
+/// Environment variable `vterm` sets to the directory of its package.
+pub const EMACS_VTERM_PATH_VAR: &str = "EMACS_VTERM_PATH";
+
+/// Represents the terminal inside `Emacs` the program runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EmacsTerminal {
+    /// `emacs-libvterm`, backed by `libvterm`.
+    Vterm,
+    /// `Eat`, the emulator written in `Emacs Lisp`.
+    Eat,
+    /// The built-in `term` and `ansi-term`.
+    Term,
+    /// Anything else, e.g. `M-x shell` or `eshell`, which aren't terminal emulators at all.
+    Other,
+}
+
+impl EmacsTerminal {
+    /// Returns the name of the terminal.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vterm => "vterm",
+            Self::Eat => "eat",
+            Self::Term => "term",
+            Self::Other => "Emacs",
+        }
+    }
+
+    /// Returns `true` if the terminal supports RGB (ANSI) colours.
+    #[inline]
+    #[must_use]
+    pub fn supports_rgb_ansi_colours(&self) -> bool {
+        matches!(self, Self::Vterm | Self::Eat)
+    }
+}
+
+impl Display for EmacsTerminal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the terminal inside `Emacs` the program runs in, if the current terminal is `Emacs`.
+#[inline]
+#[must_use]
+pub fn emacs_terminal() -> Option<EmacsTerminal> {
+    (*crate::CURRENT_TERMINAL == TerminalType::Emacs)
+        .then(|| detect_emacs_terminal_in(&SystemEnvironment))
+}
+
+/// Classifies the terminal inside `Emacs` from the given environment, assuming it is an `Emacs` terminal.
+///
+/// `vterm` is checked first, as `EMACS_VTERM_PATH_VAR` is kept by shells it runs that set `INSIDE_EMACS_VAR`
+/// themselves. Otherwise each comma separated part of `INSIDE_EMACS_VAR` is checked, ignoring versions.
+#[must_use]
+pub fn detect_emacs_terminal_in(env: &(impl Environment + ?Sized)) -> EmacsTerminal {
+    let inside_emacs = env.var(INSIDE_EMACS_VAR).unwrap_or_default();
+    let has_part = |name: &str| {
+        inside_emacs.split(',').any(|part| {
+            part.split(':')
+                .next()
+                .is_some_and(|part| part.trim() == name)
+        })
+    };
+
+    if env.var(EMACS_VTERM_PATH_VAR).is_some() || has_part("vterm") {
+        EmacsTerminal::Vterm
+    } else if has_part("eat") {
+        EmacsTerminal::Eat
+    } else if has_part("term") {
+        EmacsTerminal::Term
+    } else {
+        EmacsTerminal::Other
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::fmt::Display;
+
+/// Environment variable set by `MSYS2` (and `Git Bash`, which is built on it) to the active subsystem,
+/// e.g. `MINGW64`.
+pub const MSYSTEM_VAR: &str = "MSYSTEM";
+/// Environment variable holding the `Cygwin` runtime options.
+pub const CYGWIN_VAR: &str = "CYGWIN";
+
+/// A source of environment variables used when checking terminal signatures.
+///
+/// This allows terminal signatures to be checked against an environment other than the
+/// current process environment, e.g. a captured or mocked environment.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+/// use terminal_relaunch::TerminalSignature;
+///
+/// let env = HashMap::from([("TERM_PROGRAM".to_string(), "vscode".to_string())]);
+/// assert!(TerminalSignature::TermProgram("vscode").check_in(&env));
+/// ```
+///
+/// Values don't have to be valid unicode, e.g. socket paths containing raw bytes:
+/// ```rust
+/// # #[cfg(unix)] {
+/// use std::collections::HashMap;
+/// use std::ffi::OsString;
+/// use std::os::unix::ffi::OsStringExt;
+/// use terminal_relaunch::TerminalSignature;
+///
+/// let env = HashMap::from([
+///     (OsString::from("NVIM"), OsString::from_vec(b"/tmp/nvim\xff.sock".to_vec())),
+///     (OsString::from("TERM"), OsString::from_vec(b"foot-\xff".to_vec())),
+/// ]);
+/// assert!(TerminalSignature::EnvVarExists("NVIM").check_in(&env));
+/// assert!(TerminalSignature::TermVarPrefix("foot").check_in(&env));
+/// assert!(!TerminalSignature::TermVar("foot-").check_in(&env));
+/// # }
+/// ```
+pub trait Environment {
+    /// Returns the value of the given environment variable, if it is set and valid unicode.
+    #[must_use]
+    fn var(&self, name: &str) -> Option<String>;
+
+    /// Returns the value of the given environment variable, if it is set, whether or not it's valid unicode.
+    #[must_use]
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        self.var(name).map(OsString::from)
+    }
+}
+
+/// The environment of the current process.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemEnvironment;
+
+impl Environment for SystemEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        std::env::var_os(name)
+    }
+}
+
+impl<S: std::hash::BuildHasher> Environment for HashMap<String, String, S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+impl Environment for BTreeMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+impl<S: std::hash::BuildHasher> Environment for HashMap<OsString, OsString, S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.var_os(name)?.into_string().ok()
+    }
+
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        self.get(OsStr::new(name)).cloned()
+    }
+}
+
+impl Environment for BTreeMap<OsString, OsString> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.var_os(name)?.into_string().ok()
+    }
+
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        self.get(OsStr::new(name)).cloned()
+    }
+}
+
+/// Represents a `POSIX` layer running `Windows` programs in a `POSIX`-like environment, see `posix_layer()`.
+///
+/// Programs built for `Windows` still run as native processes, so `Windows` specific detection (e.g. the
+/// default terminal registry setting) describes the system, not the terminal the shell runs in.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use terminal_relaunch::environment::{PosixLayer, posix_layer_in};
+/// use terminal_relaunch::simulation::{Scenario, simulate};
+/// use terminal_relaunch::{TerminalSignature, TerminalType};
+///
+/// let git_bash = HashMap::from([("MSYSTEM".to_string(), "MINGW64".to_string())]);
+/// let cygwin = HashMap::from([("CYGWIN".to_string(), "winsymlinks:nativestrict".to_string())]);
+/// assert_eq!(posix_layer_in(&git_bash), Some(PosixLayer::Msys2));
+/// assert_eq!(posix_layer_in(&cygwin), Some(PosixLayer::Cygwin));
+/// assert_eq!(posix_layer_in(&HashMap::<String, String>::new()), None);
+///
+/// // The registry delegation is never checked inside these environments..
+/// assert!(!TerminalSignature::WindowsConsoleDelegationSet.check_in(&git_bash));
+///
+/// // Neither are reported as `CMD/PowerShell` or `Windows Terminal`, even when their `TERM` isn't `mintty`'s..
+/// for (name, value) in [("MSYSTEM", "MSYS"), ("MSYSTEM", "UCRT64"), ("CYGWIN", "")] {
+///     let shell = Scenario::windows_cmd_with_wt_installed().var(name, value).var("TERM", "cygwin");
+///     let result = simulate(&shell);
+///     assert_eq!(result.terminal, TerminalType::Mintty, "{name}={value}");
+///     assert!(!result.decision.should_attempt());
+/// }
+///
+/// // Terminals with their own signatures are still detected..
+/// let vscode = Scenario::windows_cmd_with_wt_installed().var("MSYSTEM", "MINGW64").var("TERM_PROGRAM", "vscode");
+/// assert_eq!(simulate(&vscode).terminal, TerminalType::VSCode);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PosixLayer {
+    /// `MSYS2`, including `Git Bash`, see `MSYSTEM_VAR`.
+    Msys2,
+    /// `Cygwin`, see `CYGWIN_VAR`.
+    Cygwin,
+}
+
+impl PosixLayer {
+    /// Returns the name of the `POSIX` layer.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Msys2 => "MSYS2",
+            Self::Cygwin => "Cygwin",
+        }
+    }
+}
+
+impl Display for PosixLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the `POSIX` layer the current program runs in, if any, see `posix_layer_in()`.
+#[inline]
+#[must_use]
+pub fn posix_layer() -> Option<PosixLayer> {
+    posix_layer_in(&SystemEnvironment)
+}
+
+/// Returns the `POSIX` layer of the given environment, if any.
+///
+/// # Returns
+/// *   `PosixLayer::Msys2` if `MSYSTEM` is set to a subsystem.
+/// *   `PosixLayer::Cygwin` if `CYGWIN` is set, even when empty, as its runtime options may be.
+/// *   `None` otherwise.
+#[must_use]
+pub fn posix_layer_in(env: &(impl Environment + ?Sized)) -> Option<PosixLayer> {
+    if env
+        .var_os(MSYSTEM_VAR)
+        .is_some_and(|subsystem| !subsystem.is_empty())
+    {
+        Some(PosixLayer::Msys2)
+    } else if env.var_os(CYGWIN_VAR).is_some() {
+        Some(PosixLayer::Cygwin)
+    } else {
+        None
+    }
+}
+
+/// Environment variable set by `WSL` to the name of the running distribution, e.g. `Ubuntu`.
+pub const WSL_DISTRO_NAME_VAR: &str = "WSL_DISTRO_NAME";
+/// Environment variable set by `WSL 2` to the socket of the `Windows` interop server.
+pub const WSL_INTEROP_VAR: &str = "WSL_INTEROP";
+
+/// Returns `true` if the current program runs inside `WSL`, see `is_wsl_in()`.
+///
+/// # Notes
+/// `/proc/version` is read once per process, and never on platforms other than `Linux`.
+#[must_use]
+pub fn is_wsl() -> bool {
+    static PROC_VERSION: std::sync::LazyLock<Option<String>> = std::sync::LazyLock::new(|| {
+        if cfg!(target_os = "linux") {
+            std::fs::read_to_string("/proc/version").ok()
+        } else {
+            None
+        }
+    });
+
+    cfg!(target_os = "linux") && is_wsl_in(&SystemEnvironment, PROC_VERSION.as_deref())
+}
+
+/// Returns `true` if the given environment and kernel version (the contents of `/proc/version`) are those of
+/// `WSL`.
+///
+/// # Returns
+/// *   `true` if `WSL_DISTRO_NAME_VAR` is set to a distribution, or `WSL_INTEROP_VAR` is set.
+/// *   `true` if the kernel version names a `Microsoft` kernel, e.g. `microsoft-standard-WSL2`, as the variables
+///     aren't passed through e.g. `sudo`.
+/// *   `false` otherwise.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use terminal_relaunch::environment::is_wsl_in;
+///
+/// let env = |name: &str, value: &str| HashMap::from([(name.to_string(), value.to_string())]);
+/// let none = HashMap::<String, String>::new();
+/// let wsl2 = "Linux version 5.15.167.4-microsoft-standard-WSL2 (root@f9c826d3017f) (gcc (GCC) 11.2.0) #1 SMP";
+/// let wsl1 = "Linux version 4.4.0-19041-Microsoft (Microsoft@Microsoft.com) (gcc version 5.4.0 (GCC) ) #1237-Microsoft";
+/// let native = "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-115) (x86_64-linux-gnu-gcc-13) #45-Ubuntu SMP";
+///
+/// // From the environment..
+/// assert!(is_wsl_in(&env("WSL_DISTRO_NAME", "Ubuntu"), None));
+/// assert!(is_wsl_in(&env("WSL_INTEROP", "/run/WSL/1234_interop"), None));
+/// assert!(!is_wsl_in(&env("WSL_DISTRO_NAME", ""), None));
+///
+/// // From the kernel, e.g. under `sudo`..
+/// assert!(is_wsl_in(&none, Some(wsl2)));
+/// assert!(is_wsl_in(&none, Some(wsl1)));
+/// assert!(!is_wsl_in(&none, Some(native)));
+/// assert!(!is_wsl_in(&none, None));
+/// ```
+#[must_use]
+pub fn is_wsl_in(env: &(impl Environment + ?Sized), proc_version: Option<&str>) -> bool {
+    env.var_os(WSL_DISTRO_NAME_VAR)
+        .is_some_and(|distro| !distro.is_empty())
+        || env.var_os(WSL_INTEROP_VAR).is_some()
+        || proc_version.is_some_and(|version| version.to_ascii_lowercase().contains("microsoft"))
+}
+
+/// Environment variables selecting the user's locale, propagated to relaunched programs, see `propagated_env()`.
+pub const LOCALE_VARS: &[&str] = &[
+    "LANG",
+    "LANGUAGE",
+    "LC_ALL",
+    "LC_CTYPE",
+    "LC_NUMERIC",
+    "LC_TIME",
+    "LC_COLLATE",
+    "LC_MONETARY",
+    "LC_MESSAGES",
+    "LC_PAPER",
+    "LC_NAME",
+    "LC_ADDRESS",
+    "LC_TELEPHONE",
+    "LC_MEASUREMENT",
+    "LC_IDENTIFICATION",
+];
+/// Environment variable selecting the user's time zone, propagated to relaunched programs.
+pub const TZ_VAR: &str = "TZ";
+
+/// Returns the environment variables of the current program propagated to relaunched programs, see
+/// `propagated_env_in()`.
+#[inline]
+#[must_use]
+pub fn propagated_env() -> Vec<(&'static str, String)> {
+    propagated_env_in(&SystemEnvironment)
+}
+
+/// Returns the environment variables of the given environment propagated to relaunched programs, i.e. those of
+/// `LOCALE_VARS` and `TZ_VAR` that are set, in that order.
+///
+/// # Notes
+/// *   These are always propagated, as launchers like `open` start the terminal with `launchd`'s environment, so
+///     the relaunched program would format numbers and dates differently than before the relaunch.
+/// *   Values that aren't valid unicode or contain control characters are skipped, as locale names and time zones
+///     never do, and the values are exported in shell commands typed into some terminals, e.g. `ITerm2`.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use terminal_relaunch::environment::propagated_env_in;
+///
+/// let env = HashMap::from([
+///     ("TZ".to_string(), "Europe/Berlin".to_string()),
+///     ("LC_TIME".to_string(), "en_GB.UTF-8".to_string()),
+///     ("LANG".to_string(), "de_DE.UTF-8".to_string()),
+///     ("HOME".to_string(), "/Users/me".to_string()),
+/// ]);
+/// assert_eq!(
+///     propagated_env_in(&env),
+///     [("LANG", "de_DE.UTF-8".to_string()), ("LC_TIME", "en_GB.UTF-8".to_string()), ("TZ", "Europe/Berlin".to_string())]
+/// );
+/// assert!(propagated_env_in(&HashMap::<String, String>::new()).is_empty());
+/// assert!(propagated_env_in(&HashMap::from([("TZ".to_string(), "UTC\x1b]0;title\x07".to_string())])).is_empty());
+/// ```
+#[must_use]
+pub fn propagated_env_in(env: &(impl Environment + ?Sized)) -> Vec<(&'static str, String)> {
+    LOCALE_VARS
+        .iter()
+        .chain([&TZ_VAR])
+        .filter_map(|name| env.var(name).map(|value| (*name, value)))
+        .filter(|(_, value)| !value.contains(char::is_control))
+        .collect()
+}
+
+/// Strips one pair of matching surrounding quotes (`"` or `'`) from an environment variable value, ignoring
+/// surrounding whitespace, e.g. `"vscode"` exported literally by a `direnv` or `dotenv` file quoted twice.
+///
+/// # Returns
+/// *   The value between the quotes, if the value is quoted.
+/// *   The value unchanged otherwise, including values with a single or mismatched quote.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::environment::strip_env_quotes;
+///
+/// assert_eq!(strip_env_quotes(b"\"vscode\""), b"vscode");
+/// assert_eq!(strip_env_quotes(b" 'xterm-kitty'\n"), b"xterm-kitty");
+/// assert_eq!(strip_env_quotes(b"\"'WezTerm'\""), b"'WezTerm'");
+/// assert_eq!(strip_env_quotes(b"\"vscode'"), b"\"vscode'");
+/// assert_eq!(strip_env_quotes(b"\""), b"\"");
+/// assert_eq!(strip_env_quotes(b" vscode"), b" vscode");
+/// ```
+#[must_use]
+pub fn strip_env_quotes(value: &[u8]) -> &[u8] {
+    match value.trim_ascii() {
+        [quote @ (b'"' | b'\''), inner @ .., last] if last == quote => inner,
+        _ => value,
+    }
+}
+
+/// An environment variable with a quoted value, as compared after stripping its quotes, see `strip_env_quotes()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuotedVar {
+    /// The name of the variable.
+    pub name: &'static str,
+    /// The value of the variable, as set.
+    pub raw: String,
+    /// The value of the variable, as compared.
+    pub normalized: String,
+}
+
+impl Display for QuotedVar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}={} (compared as {})",
+            self.name, self.raw, self.normalized
+        )
+    }
+}
+
+/// Returns the given environment variables with quoted values, in the given order.
+///
+/// # Notes
+/// Values that aren't valid unicode are skipped, see `Environment::var()`.
+#[must_use]
+pub fn quoted_vars_in(
+    env: &(impl Environment + ?Sized),
+    names: impl IntoIterator<Item = &'static str>,
+) -> Vec<QuotedVar> {
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let raw = env.var(name)?;
+            let normalized = strip_env_quotes(raw.as_bytes());
+            if normalized.len() == raw.len() {
+                return None;
+            }
+            let normalized = String::from_utf8_lossy(normalized).into_owned();
+            Some(QuotedVar {
+                name,
+                raw,
+                normalized,
+            })
+        })
+        .collect()
+}
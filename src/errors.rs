@@ -3,6 +3,9 @@ use std::process::ExitStatus;
 use thiserror::Error;
 
 use crate::TerminalType;
+use crate::config::WindowMode;
+use crate::decision::SkipReason;
+use crate::pipeline::UnfitReason;
 
 /// Errors that can occur during terminal relaunch operations.
 #[derive(Error, Debug)]
@@ -10,12 +13,48 @@ pub enum RelaunchError {
     /// No alternative terminal was found to relaunch in.
     #[error("No alternative terminal found to relaunch in.")]
     NoAlternativeTerminalFound,
+    /// No alternative terminal was found inside `WSL`, where a terminal on the `Windows` host is needed instead,
+    /// see `environment::is_wsl()`.
+    #[error(
+        "No alternative terminal found inside WSL, a terminal on the Windows host is required."
+    )]
+    WslHostTerminalRequired,
+    /// The relaunch was skipped.
+    #[error("Relaunch skipped: {0}")]
+    Skipped(SkipReason),
     /// The terminal provider is unsupported on this platform.
     #[error("The terminal provider for {0} is unsupported on this platform.")]
     UnsupportedTerminalProvider(TerminalType),
+    /// The terminal does not support the requested window mode, and strict window mode is enabled.
+    #[error("The terminal {0} does not support opening a {1}.")]
+    UnsupportedWindowMode(TerminalType, WindowMode),
+    /// The terminal is installed, but can't be used for the launch, e.g. it can't run the relaunched program.
+    #[error("The terminal {0} can't be used: {1}")]
+    UnfitTerminal(TerminalType, UnfitReason),
+    /// A program required to relaunch in the terminal (e.g. `osascript`) could not be found.
+    #[error("The program `{1}` required to launch {0} could not be found.")]
+    MissingDependency(TerminalType, &'static str),
+    /// No program to open URLs with could be found, e.g. on a headless `Linux` system.
+    #[error("No program to open URLs with could be found.")]
+    NoUrlOpener,
     /// An error occured when trying to relaunch in the specified terminal.
     #[error("Failed to launch terminal `{0}`. Exit status: {1:?}")]
     FailedToLaunchTerminal(TerminalType, ExitStatus),
+    /// A path (e.g. a long network share path) can't be passed to the terminal in any form it understands.
+    #[error("The path `{path}` can't be passed to {0}.", path = .1.display())]
+    UnrepresentablePath(TerminalType, std::path::PathBuf),
+    /// The terminal did not reply to a query in time.
+    #[error("The terminal did not reply to the query in time.")]
+    QueryTimedOut,
+    /// Another terminal query is running, queries must not run concurrently.
+    #[error("Another terminal query is already running.")]
+    QueryInProgress,
+    /// Querying the terminal is unsupported on this platform.
+    #[error("Querying the terminal is unsupported on this platform.")]
+    QueryUnsupported,
+    /// Waiting for the relaunched program to be ready is unsupported on this platform.
+    #[error("Relaunch rendezvous is unsupported on this platform.")]
+    RendezvousUnsupported,
     /// An I/O error occurred.
     #[error("I/O error occurred: {0:?}")]
     IOError(#[from] std::io::Error),
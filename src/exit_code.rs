@@ -0,0 +1,434 @@
+//! Stable exit codes for command line tools built on the library, so scripts wrapping them can tell outcomes apart
+//! without parsing output.
+//!
+//! The codes are defined once here, so tools and third-party wrappers share them, and the help text listing them is
+//! generated from `CliExitCode` (see `CliExitCode::help_section()`), so it can't drift from the codes returned.
+//!
+//! | Code | Variant                          | Meaning                                                  |
+//! |------|----------------------------------|----------------------------------------------------------|
+//! | `0`  | `CliExitCode::AlreadyFine`       | The current terminal is already fine, nothing was done.  |
+//! | `1`  | `CliExitCode::InternalError`     | An unexpected error occurred.                            |
+//! | `10` | `CliExitCode::Relaunched`        | The program was relaunched in another terminal.          |
+//! | `20` | `CliExitCode::RelaunchDeclined`  | A relaunch was skipped, by the user or the environment.  |
+//! | `30` | `CliExitCode::NoTerminal`        | No terminal is available to relaunch in.                 |
+//! | `40` | `CliExitCode::InfoPrinted`       | Detection information was printed, nothing was launched. |
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::decision::SkipReason;
+//! use terminal_relaunch::errors::RelaunchError;
+//! use terminal_relaunch::exit_code::CliExitCode;
+//!
+//! // Every skip reason maps to a code, a new reason fails to compile here until it's mapped..
+//! let expected = |reason: SkipReason| match reason {
+//!     SkipReason::AlreadyPreferred | SkipReason::AlreadyRelaunched => CliExitCode::AlreadyFine,
+//!     SkipReason::NoImprovementPossible => CliExitCode::NoTerminal,
+//!     SkipReason::DeclinedByUser
+//!     | SkipReason::DefaultAlreadyPreferredButNotActive
+//!     | SkipReason::RemoteSession
+//!     | SkipReason::InsideEmacs
+//!     | SkipReason::InsideMultiplexer
+//!     | SkipReason::NotInteractive
+//!     | SkipReason::ElevatedContext
+//!     | SkipReason::ServiceContext
+//!     | SkipReason::InstallerContext
+//!     | SkipReason::CooldownActive => CliExitCode::RelaunchDeclined,
+//! };
+//! for reason in [
+//!     SkipReason::AlreadyRelaunched,
+//!     SkipReason::AlreadyPreferred,
+//!     SkipReason::NoImprovementPossible,
+//!     SkipReason::DeclinedByUser,
+//!     SkipReason::DefaultAlreadyPreferredButNotActive,
+//!     SkipReason::RemoteSession,
+//!     SkipReason::InsideEmacs,
+//!     SkipReason::InsideMultiplexer,
+//!     SkipReason::NotInteractive,
+//!     SkipReason::ElevatedContext,
+//!     SkipReason::ServiceContext,
+//!     SkipReason::InstallerContext,
+//!     SkipReason::CooldownActive,
+//! ] {
+//!     assert_eq!(CliExitCode::for_skip_reason(reason), expected(reason), "{reason:?}");
+//!     assert_eq!(CliExitCode::for_error(&RelaunchError::Skipped(reason)), expected(reason), "{reason:?}");
+//! }
+//!
+//! // ..as do relaunch results..
+//! for (result, code) in [
+//!     (Ok(true), CliExitCode::Relaunched),
+//!     (Ok(false), CliExitCode::AlreadyFine),
+//!     (Err(RelaunchError::NoAlternativeTerminalFound), CliExitCode::NoTerminal),
+//!     (Err(RelaunchError::WslHostTerminalRequired), CliExitCode::NoTerminal),
+//!     (Err(RelaunchError::NoUrlOpener), CliExitCode::InternalError),
+//!     (Err(std::io::Error::other("spawn failed").into()), CliExitCode::InternalError),
+//! ] {
+//!     assert_eq!(CliExitCode::for_result(&result), code, "{result:?}");
+//! }
+//!
+//! // ..and every code round trips, and is listed in the help text..
+//! let help = CliExitCode::help_section();
+//! for code in CliExitCode::all() {
+//!     assert_eq!(CliExitCode::from_code(code.code()), Some(code));
+//!     assert!(help.contains(&format!("{:>4}  {}", code.code(), code.description())), "{help}");
+//! }
+//! assert_eq!(CliExitCode::from_code(2), None);
+//! ```
+
+use std::fmt::{Display, Write};
+
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::decision::SkipReason;
+use crate::errors::{RelaunchError, TermResult};
+
+/// Represents the exit code of a command line tool built on the library, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum CliExitCode {
+    /// The current terminal is already fine, nothing was done.
+    AlreadyFine = 0,
+    /// An unexpected error occurred, e.g. spawning the terminal failed.
+    InternalError = 1,
+    /// The program was relaunched in another terminal.
+    Relaunched = 10,
+    /// A relaunch was skipped, by the user's choice or because of the environment (e.g. a remote session).
+    RelaunchDeclined = 20,
+    /// No terminal is available to relaunch in.
+    NoTerminal = 30,
+    /// Detection information was printed, nothing was launched.
+    InfoPrinted = 40,
+}
+
+impl CliExitCode {
+    /// Returns every exit code, in ascending order.
+    #[inline]
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::iter()
+    }
+
+    /// Returns the numeric exit code.
+    #[inline]
+    #[must_use]
+    pub const fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the exit code with the given numeric code, if any, e.g. for a script wrapping a tool.
+    #[inline]
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::iter().find(|exit_code| exit_code.code() == code)
+    }
+
+    /// Returns a short description of the exit code.
+    #[inline]
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::AlreadyFine => "The current terminal is already fine, nothing was done.",
+            Self::InternalError => "An unexpected error occurred.",
+            Self::Relaunched => "The program was relaunched in another terminal.",
+            Self::RelaunchDeclined => {
+                "A relaunch was skipped, by the user's choice or the environment."
+            }
+            Self::NoTerminal => "No terminal is available to relaunch in.",
+            Self::InfoPrinted => "Detection information was printed, nothing was launched.",
+        }
+    }
+
+    /// Returns the exit code of a relaunch skipped for the given reason.
+    ///
+    /// # Returns
+    /// *   `AlreadyFine` if the terminal already supports all preferred features, or the program was relaunched.
+    /// *   `NoTerminal` if no installed terminal would improve on the current terminal.
+    /// *   `RelaunchDeclined` otherwise.
+    #[must_use]
+    pub fn for_skip_reason(reason: SkipReason) -> Self {
+        match reason {
+            SkipReason::AlreadyPreferred | SkipReason::AlreadyRelaunched => Self::AlreadyFine,
+            SkipReason::NoImprovementPossible => Self::NoTerminal,
+            SkipReason::DeclinedByUser
+            | SkipReason::DefaultAlreadyPreferredButNotActive
+            | SkipReason::RemoteSession
+            | SkipReason::InsideEmacs
+            | SkipReason::InsideMultiplexer
+            | SkipReason::NotInteractive
+            | SkipReason::ElevatedContext
+            | SkipReason::ServiceContext
+            | SkipReason::InstallerContext
+            | SkipReason::CooldownActive => Self::RelaunchDeclined,
+        }
+    }
+
+    /// Returns the exit code of a relaunch that failed with the given error.
+    ///
+    /// # Returns
+    /// *   The code of the skip reason for `RelaunchError::Skipped`, see `for_skip_reason()`.
+    /// *   `NoTerminal` if no alternative terminal was found.
+    /// *   `InternalError` otherwise.
+    #[must_use]
+    pub fn for_error(error: &RelaunchError) -> Self {
+        match error {
+            RelaunchError::Skipped(reason) => Self::for_skip_reason(*reason),
+            RelaunchError::NoAlternativeTerminalFound | RelaunchError::WslHostTerminalRequired => {
+                Self::NoTerminal
+            }
+            _ => Self::InternalError,
+        }
+    }
+
+    /// Returns the exit code of a relaunch result, e.g. from `relaunch_if_available()`.
+    #[must_use]
+    pub fn for_result(result: &TermResult<bool>) -> Self {
+        match result {
+            Ok(true) => Self::Relaunched,
+            Ok(false) => Self::AlreadyFine,
+            Err(error) => Self::for_error(error),
+        }
+    }
+
+    /// Returns the section of a tool's help text listing every exit code, generated from the codes themselves.
+    #[must_use]
+    pub fn help_section() -> String {
+        let mut help = String::from("EXIT CODES:");
+        for code in Self::iter() {
+            // Writing to a `String` can't fail..
+            let _ = write!(help, "\n{:>4}  {}", code.code(), code.description());
+        }
+        help
+    }
+}
+
+impl Display for CliExitCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl From<CliExitCode> for std::process::ExitCode {
+    fn from(code: CliExitCode) -> Self {
+        Self::from(code.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::process::ExitStatus;
+
+    use super::CliExitCode;
+    use crate::config::WindowMode;
+    use crate::decision::SkipReason;
+    use crate::errors::RelaunchError;
+    use crate::pipeline::UnfitReason;
+    use crate::{RelaunchAttemptSummary, TerminalType};
+
+    /// Every skip reason, with the code it maps to.
+    const SKIP_REASONS: &[(SkipReason, CliExitCode)] = &[
+        (SkipReason::AlreadyRelaunched, CliExitCode::AlreadyFine),
+        (SkipReason::AlreadyPreferred, CliExitCode::AlreadyFine),
+        (SkipReason::NoImprovementPossible, CliExitCode::NoTerminal),
+        (SkipReason::DeclinedByUser, CliExitCode::RelaunchDeclined),
+        (
+            SkipReason::DefaultAlreadyPreferredButNotActive,
+            CliExitCode::RelaunchDeclined,
+        ),
+        (SkipReason::RemoteSession, CliExitCode::RelaunchDeclined),
+        (SkipReason::InsideEmacs, CliExitCode::RelaunchDeclined),
+        (SkipReason::InsideMultiplexer, CliExitCode::RelaunchDeclined),
+        (SkipReason::NotInteractive, CliExitCode::RelaunchDeclined),
+        (SkipReason::ElevatedContext, CliExitCode::RelaunchDeclined),
+        (SkipReason::ServiceContext, CliExitCode::RelaunchDeclined),
+        (SkipReason::InstallerContext, CliExitCode::RelaunchDeclined),
+        (SkipReason::CooldownActive, CliExitCode::RelaunchDeclined),
+    ];
+
+    fn failed_status() -> ExitStatus {
+        #[cfg(unix)]
+        {
+            std::os::unix::process::ExitStatusExt::from_raw(1 << 8)
+        }
+        #[cfg(windows)]
+        {
+            std::os::windows::process::ExitStatusExt::from_raw(1)
+        }
+    }
+
+    /// Returns an error of every kind, with the code it maps to.
+    fn errors() -> Vec<(RelaunchError, CliExitCode)> {
+        let errors = vec![
+            (
+                RelaunchError::NoAlternativeTerminalFound,
+                CliExitCode::NoTerminal,
+            ),
+            (
+                RelaunchError::WslHostTerminalRequired,
+                CliExitCode::NoTerminal,
+            ),
+            (
+                RelaunchError::Skipped(SkipReason::RemoteSession),
+                CliExitCode::RelaunchDeclined,
+            ),
+            (
+                RelaunchError::UnsupportedTerminalProvider(TerminalType::Unknown),
+                CliExitCode::InternalError,
+            ),
+            (
+                RelaunchError::UnsupportedWindowMode(TerminalType::Hyper, WindowMode::Tab),
+                CliExitCode::InternalError,
+            ),
+            (
+                RelaunchError::UnfitTerminal(TerminalType::Hyper, UnfitReason::CannotHostCommand),
+                CliExitCode::InternalError,
+            ),
+            (
+                RelaunchError::MissingDependency(TerminalType::ITerm2, "osascript"),
+                CliExitCode::InternalError,
+            ),
+            (RelaunchError::NoUrlOpener, CliExitCode::InternalError),
+            (
+                RelaunchError::FailedToLaunchTerminal(TerminalType::Kitty, failed_status()),
+                CliExitCode::InternalError,
+            ),
+            (
+                RelaunchError::UnrepresentablePath(
+                    TerminalType::WindowsTerminal,
+                    PathBuf::from("C:\\"),
+                ),
+                CliExitCode::InternalError,
+            ),
+            (RelaunchError::QueryTimedOut, CliExitCode::InternalError),
+            (RelaunchError::QueryInProgress, CliExitCode::InternalError),
+            (RelaunchError::QueryUnsupported, CliExitCode::InternalError),
+            (
+                RelaunchError::RendezvousUnsupported,
+                CliExitCode::InternalError,
+            ),
+            (
+                std::io::Error::other("spawn failed").into(),
+                CliExitCode::InternalError,
+            ),
+        ];
+
+        // A new kind of error fails to compile here until it's added above..
+        for (error, _) in &errors {
+            match error {
+                RelaunchError::NoAlternativeTerminalFound
+                | RelaunchError::WslHostTerminalRequired
+                | RelaunchError::Skipped(_)
+                | RelaunchError::UnsupportedTerminalProvider(_)
+                | RelaunchError::UnsupportedWindowMode(..)
+                | RelaunchError::UnfitTerminal(..)
+                | RelaunchError::MissingDependency(..)
+                | RelaunchError::NoUrlOpener
+                | RelaunchError::FailedToLaunchTerminal(..)
+                | RelaunchError::UnrepresentablePath(..)
+                | RelaunchError::QueryTimedOut
+                | RelaunchError::QueryInProgress
+                | RelaunchError::QueryUnsupported
+                | RelaunchError::RendezvousUnsupported
+                | RelaunchError::IOError(_) => {}
+            }
+        }
+        errors
+    }
+
+    #[test]
+    fn codes_are_stable() {
+        let codes: Vec<_> = CliExitCode::all().map(|code| (code, code.code())).collect();
+        assert_eq!(
+            codes,
+            [
+                (CliExitCode::AlreadyFine, 0),
+                (CliExitCode::InternalError, 1),
+                (CliExitCode::Relaunched, 10),
+                (CliExitCode::RelaunchDeclined, 20),
+                (CliExitCode::NoTerminal, 30),
+                (CliExitCode::InfoPrinted, 40),
+            ]
+        );
+        for code in 0..=u8::MAX {
+            assert_eq!(
+                CliExitCode::from_code(code).map(|exit_code| exit_code.code()),
+                codes
+                    .iter()
+                    .find(|(_, known)| *known == code)
+                    .map(|(_, known)| *known)
+            );
+        }
+        assert_eq!(
+            std::process::ExitCode::from(CliExitCode::NoTerminal),
+            std::process::ExitCode::from(30)
+        );
+    }
+
+    #[test]
+    fn every_outcome_maps_to_its_code() {
+        for (reason, code) in SKIP_REASONS {
+            assert_eq!(CliExitCode::for_skip_reason(*reason), *code, "{reason:?}");
+            assert_eq!(
+                CliExitCode::for_error(&RelaunchError::Skipped(*reason)),
+                *code,
+                "{reason:?}"
+            );
+        }
+        for (error, code) in errors() {
+            assert_eq!(CliExitCode::for_error(&error), code, "{error:?}");
+            let result = Err(error);
+            assert_eq!(CliExitCode::for_result(&result), code, "{result:?}");
+        }
+        assert_eq!(CliExitCode::for_result(&Ok(true)), CliExitCode::Relaunched);
+        assert_eq!(
+            CliExitCode::for_result(&Ok(false)),
+            CliExitCode::AlreadyFine
+        );
+    }
+
+    #[test]
+    fn attempt_summaries_map_to_the_code_of_their_error_then_skip_reason() {
+        let summary = |skip_reason, error: Option<RelaunchError>| RelaunchAttemptSummary {
+            attempted: error.is_some(),
+            skip_reason,
+            error,
+            terminal: TerminalType::Xterm,
+            full_unicode: false,
+            rgb_ansi_colours: false,
+        };
+
+        assert_eq!(summary(None, None).exit_code(), CliExitCode::AlreadyFine);
+        for (reason, code) in SKIP_REASONS {
+            assert_eq!(
+                summary(Some(*reason), None).exit_code(),
+                *code,
+                "{reason:?}"
+            );
+
+            // ..an error takes precedence over the reason..
+            for (error, error_code) in errors() {
+                assert_eq!(
+                    summary(Some(*reason), Some(error)).exit_code(),
+                    error_code,
+                    "{reason:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn help_section_lists_every_code() {
+        assert_eq!(
+            CliExitCode::help_section(),
+            "EXIT CODES:\n\
+            \x20  0  The current terminal is already fine, nothing was done.\n\
+            \x20  1  An unexpected error occurred.\n\
+            \x20 10  The program was relaunched in another terminal.\n\
+            \x20 20  A relaunch was skipped, by the user's choice or the environment.\n\
+            \x20 30  No terminal is available to relaunch in.\n\
+            \x20 40  Detection information was printed, nothing was launched."
+        );
+        for code in CliExitCode::all() {
+            assert_eq!(code.to_string(), code.description());
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Advisory detection of emoji rendering risks caused by the terminal's font configuration.
+//!
+//! Nothing here affects the relaunch decision, it's intended for applications that want to warn the user
+//! rather than assume e.g. `Windows Terminal` always renders emoji correctly.
+
+use std::fmt::Display;
+
+use crate::TerminalType;
+
+/// Font used by `Windows Terminal` when no font is configured.
+pub const WT_DEFAULT_FONT: &str = "Cascadia Mono";
+
+/// Environment variable containing the GUID of the active `Windows Terminal` profile.
+pub const WT_PROFILE_ID_VAR: &str = "WT_PROFILE_ID";
+
+/// Represents how likely emoji are to render incorrectly (e.g. as boxes) in the current terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EmojiRisk {
+    /// Emoji are expected to render correctly.
+    Low,
+    /// Not enough is known about the terminal's font to tell.
+    Unknown,
+    /// Emoji are likely to render incorrectly.
+    High,
+}
+
+impl EmojiRisk {
+    /// Returns the name of the risk level.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Unknown => "Unknown",
+            Self::High => "High",
+        }
+    }
+}
+
+impl Display for EmojiRisk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the emoji rendering risk of the current terminal, see `emoji_rendering_risk_for()`.
+#[inline]
+#[must_use]
+pub fn emoji_rendering_risk() -> EmojiRisk {
+    let font = if *crate::CURRENT_TERMINAL == TerminalType::WindowsTerminal {
+        wt_font_family()
+    } else {
+        None
+    };
+
+    emoji_rendering_risk_for(*crate::CURRENT_TERMINAL, font.as_deref())
+}
+
+/// Returns the emoji rendering risk of the given terminal using the given font family, if known.
+///
+/// # Notes
+/// *   Terminals without full unicode support are always `High` risk.
+/// *   For `Windows Terminal`, fonts shipped with Windows fall back to colour emoji, patched `Nerd Fonts`
+///     commonly don't, and other fonts are `Unknown`.
+/// *   Other terminals are `Low` risk, as they handle emoji fallback themselves.
+#[must_use]
+pub fn emoji_rendering_risk_for(terminal: TerminalType, font: Option<&str>) -> EmojiRisk {
+    if !terminal.supports_full_unicode() {
+        return EmojiRisk::High;
+    }
+    if terminal != TerminalType::WindowsTerminal {
+        return EmojiRisk::Low;
+    }
+
+    let Some(font) = font else {
+        return EmojiRisk::Unknown;
+    };
+    let font = font.to_ascii_lowercase();
+
+    if [
+        "cascadia",
+        "consolas",
+        "segoe",
+        "lucida console",
+        "courier new",
+    ]
+    .iter()
+    .any(|known| font.starts_with(known))
+    {
+        EmojiRisk::Low
+    } else if font.contains("nerd font") || font.ends_with(" nf") || font.ends_with(" nfm") {
+        EmojiRisk::High
+    } else {
+        EmojiRisk::Unknown
+    }
+}
+
+/// Returns the font family of the active `Windows Terminal` profile, read from its `settings.json`.
+///
+/// # Notes
+/// The active profile is found from `WT_PROFILE_ID`, falling back to the default profile. Returns `None`
+/// when not on `Windows`, without the `wt-settings` feature, or if the settings could not be read.
+#[must_use]
+pub fn wt_font_family() -> Option<String> {
+    #[cfg(all(target_os = "windows", feature = "wt-settings"))]
+    {
+        let profile = std::env::var(WT_PROFILE_ID_VAR).ok();
+
+        wt_settings_paths()
+            .into_iter()
+            .find_map(|path| std::fs::read_to_string(path).ok())
+            .and_then(|settings| wt_font_family_from_settings(&settings, profile.as_deref()))
+    }
+
+    #[cfg(not(all(target_os = "windows", feature = "wt-settings")))]
+    {
+        None
+    }
+}
+
+/// Returns the possible locations of the `Windows Terminal` `settings.json`, packaged installs first.
+#[cfg(all(target_os = "windows", feature = "wt-settings"))]
+fn wt_settings_paths() -> Vec<std::path::PathBuf> {
+    let Some(local_app_data) = std::env::var_os("LOCALAPPDATA").map(std::path::PathBuf::from)
+    else {
+        return Vec::new();
+    };
+
+    let packages = local_app_data.join("Packages");
+    vec![
+        packages.join("Microsoft.WindowsTerminal_8wekyb3d8bbwe\\LocalState\\settings.json"),
+        packages.join("Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe\\LocalState\\settings.json"),
+        local_app_data.join("Microsoft\\Windows Terminal\\settings.json"),
+    ]
+}
+
+/// Returns the font family of a profile from the contents of a `Windows Terminal` `settings.json`.
+///
+/// Both the current (`profiles.defaults` and `profiles.list`, `font.face`) and older (a plain `profiles`
+/// array, `fontFace`) schemas are supported, profile settings override the defaults.
+///
+/// # Returns
+/// *   The font of the profile with the given GUID, or of the default profile if `None` or not found.
+/// *   `WT_DEFAULT_FONT` if no font is configured.
+/// *   `None` if the settings could not be parsed.
+#[cfg(feature = "wt-settings")]
+#[must_use]
+pub fn wt_font_family_from_settings(settings: &str, profile_guid: Option<&str>) -> Option<String> {
+    use serde_json::Value;
+
+    fn font_of(profile: &Value) -> Option<&str> {
+        profile
+            .get("font")
+            .and_then(|font| font.get("face"))
+            .or_else(|| profile.get("fontFace"))
+            .and_then(Value::as_str)
+    }
+    fn find_profile<'a>(profiles: &'a [Value], guid: &str) -> Option<&'a Value> {
+        profiles.iter().find(|profile| {
+            profile
+                .get("guid")
+                .and_then(Value::as_str)
+                .is_some_and(|id| id.eq_ignore_ascii_case(guid))
+        })
+    }
+
+    let settings: Value = serde_json::from_str(&strip_json_comments(settings)).ok()?;
+
+    let (defaults, profiles) = match settings.get("profiles") {
+        Some(Value::Array(list)) => (None, list.as_slice()),
+        Some(profiles) => (
+            profiles.get("defaults"),
+            profiles
+                .get("list")
+                .and_then(Value::as_array)
+                .map_or(&[][..], Vec::as_slice),
+        ),
+        None => (None, &[][..]),
+    };
+
+    let default_guid = settings.get("defaultProfile").and_then(Value::as_str);
+    let profile = profile_guid
+        .and_then(|guid| find_profile(profiles, guid))
+        .or_else(|| default_guid.and_then(|guid| find_profile(profiles, guid)));
+
+    let font = profile
+        .and_then(font_of)
+        .or_else(|| defaults.and_then(font_of))
+        .unwrap_or(WT_DEFAULT_FONT);
+    Some(font.to_string())
+}
+
+/// Removes `//` and `/* */` comments from JSON, which `Windows Terminal` allows in its settings.
+#[cfg(feature = "wt-settings")]
+fn strip_json_comments(json: &str) -> String {
+    let mut stripped = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|next| *next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+
+    stripped
+}
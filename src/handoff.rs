@@ -0,0 +1,585 @@
+//! A handoff from a launcher to the program it relaunched, describing the terminal it was relaunched in and the
+//! capabilities it was expected to have, so the relaunched program can verify them.
+//!
+//! A relaunch may land in a terminal that doesn't live up to its promise, e.g. `Kitty` configured to set `TERM` to
+//! `linux`, so RGB colours never materialise. Relaunches pass the handoff to the relaunched program as
+//! `HANDOFF_ARGUMENT`, which reads it with `read_relaunch_handoff()` and checks it with
+//! `verify_relaunch_expectations()`, so it can warn or adjust its rendering.
+//!
+//! # Notes
+//! *   The relaunched program must ignore `HANDOFF_ARGUMENT` when parsing its arguments, as with the relaunch marker.
+//! *   With the `rendezvous` feature, the handoff also carries the rendezvous of
+//!     `rendezvous::relaunch_with_rendezvous()`, which `read_relaunch_handoff()` signals.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::TerminalType;
+//! use terminal_relaunch::capabilities::{TerminalCapabilities, resolve_capabilities};
+//! use terminal_relaunch::handoff::{RelaunchHandoff, RelaunchVerification, verify_handoff_in};
+//!
+//! let env = |vars: &[(&str, &str)]| {
+//!     vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect::<HashMap<_, _>>()
+//! };
+//!
+//! // The launcher passes the target and its capabilities..
+//! let handoff = RelaunchHandoff::new(TerminalType::WezTerm, resolve_capabilities(TerminalType::WezTerm, None));
+//! assert_eq!(RelaunchHandoff::parse_argument(&handoff.argument()), Some(handoff.clone()));
+//!
+//! // ..which are met when the environment, or the terminal itself, confirms them..
+//! let wezterm = env(&[("TERM_PROGRAM", "WezTerm"), ("TERM", "xterm-256color")]);
+//! let truecolor = env(&[("TERM_PROGRAM", "WezTerm"), ("COLORTERM", "truecolor")]);
+//! assert_eq!(verify_handoff_in(&handoff, &wezterm, || None), RelaunchVerification::Met);
+//! assert_eq!(verify_handoff_in(&handoff, &wezterm, || Some(true)), RelaunchVerification::Met);
+//! assert_eq!(verify_handoff_in(&handoff, &truecolor, || Some(false)), RelaunchVerification::Met);
+//!
+//! // ..degraded when either denies them..
+//! let rgb = TerminalCapabilities { rgb_ansi_colours: true, ..Default::default() };
+//! let linux = env(&[("TERM_PROGRAM", "WezTerm"), ("TERM", "linux")]);
+//! assert_eq!(verify_handoff_in(&handoff, &linux, || None), RelaunchVerification::Degraded(rgb));
+//! assert_eq!(verify_handoff_in(&handoff, &wezterm, || Some(false)), RelaunchVerification::Degraded(rgb));
+//! assert_eq!(verify_handoff_in(&handoff, &linux, || Some(true)), RelaunchVerification::Met);
+//!
+//! // ..and not met at all outside the target terminal..
+//! let vscode = env(&[("TERM_PROGRAM", "vscode"), ("COLORTERM", "truecolor")]);
+//! assert_eq!(verify_handoff_in(&handoff, &vscode, || Some(true)), RelaunchVerification::NotInTarget);
+//!
+//! // Nothing is probed if nothing is expected of RGB colours..
+//! let unicode_only = RelaunchHandoff::new(
+//!     TerminalType::WezTerm,
+//!     TerminalCapabilities { full_unicode: true, ..Default::default() },
+//! );
+//! assert_eq!(verify_handoff_in(&unicode_only, &linux, || unreachable!()), RelaunchVerification::Met);
+//! ```
+
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use crate::capabilities::{COLORTERM_VAR, TerminalCapabilities};
+use crate::environment::{Environment, SystemEnvironment};
+use crate::terminal_providers::TERM_VAR;
+use crate::{OperatingSystem, TerminalProvider, TerminalType};
+
+/// Argument passed to the relaunched program with the handoff, as
+/// `--relaunch-handoff=<terminal>:<capabilities>[:<rendezvous>]`, e.g.
+/// `--relaunch-handoff=wezterm:unicode,rgb,hyperlinks`, see `RelaunchHandoff::argument()`.
+pub const HANDOFF_ARGUMENT: &str = "--relaunch-handoff";
+
+/// Values of `TERM` selecting terminal descriptions without RGB colours, which programs honour whatever the terminal
+/// supports.
+pub const NO_RGB_TERMS: [&str; 5] = ["dumb", "linux", "vt100", "vt220", "ansi"];
+
+thread_local! {
+    /// Handoff to pass to relaunches on this thread, if set.
+    static PENDING_HANDOFF: RefCell<Option<RelaunchHandoff>> = const { RefCell::new(None) };
+}
+
+/// What a launcher expected of the terminal it relaunched the program in, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelaunchHandoff {
+    /// The terminal the program was relaunched in.
+    pub target: TerminalType,
+    /// The capabilities the terminal was expected to have, see `capabilities::resolve_capabilities()`.
+    pub expected_capabilities: TerminalCapabilities,
+    /// The path of the rendezvous the launcher waits on, if any, see `rendezvous::relaunch_with_rendezvous()`.
+    pub rendezvous: Option<PathBuf>,
+}
+
+impl RelaunchHandoff {
+    /// Creates a handoff for a relaunch in the given terminal, expected to have the given capabilities.
+    #[inline]
+    #[must_use]
+    pub const fn new(target: TerminalType, expected_capabilities: TerminalCapabilities) -> Self {
+        Self {
+            target,
+            expected_capabilities,
+            rendezvous: None,
+        }
+    }
+
+    /// Sets the path of the rendezvous the launcher waits on, see `rendezvous::Rendezvous::path()`.
+    #[inline]
+    #[must_use]
+    pub fn with_rendezvous(mut self, path: impl Into<PathBuf>) -> Self {
+        self.rendezvous = Some(path.into());
+        self
+    }
+
+    /// Creates a handoff for a relaunch by the given provider, expecting the capabilities of its installed version.
+    ///
+    /// # Notes
+    /// With the `rendezvous` feature, the handoff carries the rendezvous of `rendezvous::relaunch_with_rendezvous()`
+    /// when called from it.
+    #[must_use]
+    pub fn for_provider(provider: &dyn TerminalProvider) -> Self {
+        let target = provider.terminal_type();
+        let version = provider.installed_version();
+
+        Self {
+            #[cfg(feature = "rendezvous")]
+            rendezvous: crate::rendezvous::pending_path(),
+            ..Self::new(
+                target,
+                crate::capabilities::resolve_capabilities(target, version.as_ref()),
+            )
+        }
+    }
+
+    /// Returns the argument passing this handoff to the relaunched program, see `HANDOFF_ARGUMENT`.
+    ///
+    /// # Notes
+    /// *   The score isn't passed, it's the built-in score of the target, see `TerminalType::capability_score()`.
+    /// *   The rendezvous is passed last, so its path may contain any character.
+    #[must_use]
+    pub fn argument(&self) -> String {
+        let capabilities = [
+            (self.expected_capabilities.full_unicode, "unicode"),
+            (self.expected_capabilities.rgb_ansi_colours, "rgb"),
+            (self.expected_capabilities.hyperlinks, "hyperlinks"),
+        ]
+        .into_iter()
+        .filter_map(|(expected, name)| expected.then_some(name))
+        .collect::<Vec<_>>()
+        .join(",");
+
+        match &self.rendezvous {
+            Some(path) => format!(
+                "{HANDOFF_ARGUMENT}={}:{capabilities}:{}",
+                self.target.id(),
+                path.display()
+            ),
+            None => format!("{HANDOFF_ARGUMENT}={}:{capabilities}", self.target.id()),
+        }
+    }
+
+    /// Parses a handoff from its argument, see `argument()`.
+    ///
+    /// # Returns
+    /// *   `Some(handoff)` if the argument is a handoff for a known terminal, unknown capabilities are ignored.
+    /// *   `None` otherwise.
+    #[must_use]
+    pub fn parse_argument(argument: &str) -> Option<Self> {
+        let value = argument.strip_prefix(HANDOFF_ARGUMENT)?.strip_prefix('=')?;
+        let (target, rest) = value.split_once(':')?;
+        let target = TerminalType::from_id(target)?;
+        let (capabilities, rendezvous) = match rest.split_once(':') {
+            Some((capabilities, path)) => (capabilities, Some(PathBuf::from(path))),
+            None => (rest, None),
+        };
+
+        let mut expected = TerminalCapabilities {
+            score: target.capability_score(),
+            ..TerminalCapabilities::default()
+        };
+        for capability in capabilities.split(',') {
+            match capability {
+                "unicode" => expected.full_unicode = true,
+                "rgb" => expected.rgb_ansi_colours = true,
+                "hyperlinks" => expected.hyperlinks = true,
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            rendezvous,
+            ..Self::new(target, expected)
+        })
+    }
+}
+
+/// Runs the given function with relaunches on this thread passing the given handoff.
+pub(crate) fn with_pending_handoff<T>(handoff: RelaunchHandoff, f: impl FnOnce() -> T) -> T {
+    let previous = PENDING_HANDOFF.with(|cell| cell.replace(Some(handoff)));
+    let result = f();
+    PENDING_HANDOFF.with(|cell| cell.replace(previous));
+
+    result
+}
+
+/// Returns the handoff argument to pass to relaunches on this thread, if any.
+pub(crate) fn pending_argument() -> Option<String> {
+    PENDING_HANDOFF.with(|cell| cell.borrow().as_ref().map(RelaunchHandoff::argument))
+}
+
+/// Returns the handoff passed to this program by its launcher, if any.
+///
+/// # Notes
+/// With the `rendezvous` feature, this signals the launcher waiting on the rendezvous of the handoff that the
+/// program is ready, once, see `rendezvous::signal_relaunch_ready()`. A launcher that stopped waiting is only logged.
+#[must_use]
+pub fn read_relaunch_handoff() -> Option<RelaunchHandoff> {
+    let handoff = passed_handoff();
+
+    #[cfg(feature = "rendezvous")]
+    if let Some(path) = handoff
+        .as_ref()
+        .and_then(|handoff| handoff.rendezvous.as_deref())
+        && let Err(error) = crate::rendezvous::signal_ready_once(path)
+    {
+        crate::logging::warning!("Could not signal the relaunch rendezvous: {error}");
+    }
+
+    handoff
+}
+
+/// Returns the handoff passed to this program by its launcher, if any, without signalling its rendezvous.
+pub(crate) fn passed_handoff() -> Option<RelaunchHandoff> {
+    std::env::args_os().find_map(|arg| arg.to_str().and_then(RelaunchHandoff::parse_argument))
+}
+
+/// Represents the result of verifying a handoff, see `verify_relaunch_expectations()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelaunchVerification {
+    /// The program runs in the expected terminal, with the expected capabilities, as far as can be told.
+    Met,
+    /// The program runs in the expected terminal, but without the given expected capabilities.
+    Degraded(TerminalCapabilities),
+    /// The program doesn't run in the expected terminal, e.g. the terminal handed the program to another one.
+    NotInTarget,
+}
+
+impl RelaunchVerification {
+    /// Returns a short description of the verification result.
+    #[inline]
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Met => "The terminal has the expected capabilities.",
+            Self::Degraded(_) => "The terminal lacks some of the expected capabilities.",
+            Self::NotInTarget => "The program doesn't run in the expected terminal.",
+        }
+    }
+}
+
+impl Display for RelaunchVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+/// Verifies the handoff passed to this program by its launcher, see `verify_handoff_in()`.
+///
+/// # Notes
+/// *   Returns `RelaunchVerification::Met` if no handoff was passed, e.g. the program wasn't relaunched, as nothing
+///     was expected of the terminal, see `read_relaunch_handoff()`.
+/// *   With the `tty-query` feature, the terminal is probed for RGB colours if the environment doesn't confirm them,
+///     which may take up to `capabilities::PROBE_TIMEOUT`.
+#[must_use]
+pub fn verify_relaunch_expectations() -> RelaunchVerification {
+    let Some(handoff) = read_relaunch_handoff() else {
+        return RelaunchVerification::Met;
+    };
+
+    let verification =
+        verify_handoff_in(&handoff, &SystemEnvironment, probe_rgb_ansi_colour_support);
+    if verification != RelaunchVerification::Met {
+        crate::logging::warning!("Relaunched in `{}`: {verification}", handoff.target);
+    }
+    verification
+}
+
+/// Verifies the given handoff in the given environment, probing the terminal for RGB colours with `probe`.
+///
+/// # Returns
+/// *   `RelaunchVerification::NotInTarget` if no identifier of the target matches the environment, and it isn't
+///     detected from it either, see `identifiers_for()`.
+/// *   `RelaunchVerification::Degraded` with the expected RGB colours, if they're not confirmed. `COLORTERM` set to
+///     `truecolor` or `24bit` confirms them, otherwise the probe decides if it got a reply (`Some`), or `TERM` if
+///     it didn't (`None`), denying them if it's any of `NO_RGB_TERMS`.
+/// *   `RelaunchVerification::Met` otherwise.
+///
+/// # Notes
+/// Only RGB colours are verified, full unicode and hyperlinks can't be told from the environment, and `probe` is
+/// only called if RGB colours are expected and the environment doesn't confirm them.
+#[must_use]
+pub fn verify_handoff_in(
+    handoff: &RelaunchHandoff,
+    env: &(impl Environment + ?Sized),
+    probe: impl FnOnce() -> Option<bool>,
+) -> RelaunchVerification {
+    let os = OperatingSystem::current();
+    let in_target = crate::identifiers_for(handoff.target)
+        .any(|identifier| identifier.applicable_on(os) && identifier.matches_in(env))
+        || crate::detect_terminal(env, os, false) == handoff.target;
+    if !in_target {
+        return RelaunchVerification::NotInTarget;
+    }
+
+    let expected = handoff.expected_capabilities;
+    let rgb_ansi_colours = !expected.rgb_ansi_colours
+        || env.var(COLORTERM_VAR).is_some_and(|value| {
+            value.eq_ignore_ascii_case("truecolor") || value.eq_ignore_ascii_case("24bit")
+        })
+        || probe().unwrap_or_else(|| {
+            !env.var(TERM_VAR).is_some_and(|term| {
+                NO_RGB_TERMS
+                    .iter()
+                    .any(|no_rgb| term.trim().eq_ignore_ascii_case(no_rgb))
+            })
+        });
+
+    let missing = expected.difference(&TerminalCapabilities {
+        rgb_ansi_colours,
+        ..expected
+    });
+    if missing.is_empty() {
+        RelaunchVerification::Met
+    } else {
+        RelaunchVerification::Degraded(missing)
+    }
+}
+
+/// Probes the current terminal for RGB (ANSI) colour support.
+///
+/// # Returns
+/// *   `Some(supported)` if the terminal replied, see `query::query_rgb_support()`.
+/// *   `None` if it didn't, or the `tty-query` feature is disabled.
+fn probe_rgb_ansi_colour_support() -> Option<bool> {
+    #[cfg(feature = "tty-query")]
+    {
+        crate::query::query_rgb_support(crate::capabilities::PROBE_TIMEOUT).ok()
+    }
+
+    #[cfg(not(feature = "tty-query"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::collections::HashMap;
+
+    use strum::IntoEnumIterator;
+
+    use super::{
+        HANDOFF_ARGUMENT, RelaunchHandoff, RelaunchVerification, pending_argument,
+        verify_handoff_in, with_pending_handoff,
+    };
+    use crate::capabilities::{TerminalCapabilities, resolve_capabilities};
+    use crate::config::WindowMode;
+    use crate::pipeline::RelaunchPipeline;
+    use crate::recording::RecordingProvider;
+    use crate::terminal_providers::with_window_mode;
+    use crate::test_support::reading_globals;
+    use crate::{TerminalProvider, TerminalType};
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    fn rgb() -> TerminalCapabilities {
+        TerminalCapabilities {
+            rgb_ansi_colours: true,
+            ..TerminalCapabilities::default()
+        }
+    }
+
+    #[test]
+    fn handoffs_round_trip_through_their_argument() {
+        for target in TerminalType::iter() {
+            for bits in 0..8_u8 {
+                let expected = TerminalCapabilities {
+                    full_unicode: bits & 1 != 0,
+                    rgb_ansi_colours: bits & 2 != 0,
+                    hyperlinks: bits & 4 != 0,
+                    score: target.capability_score(),
+                };
+                let handoff = RelaunchHandoff::new(target, expected);
+                let argument = handoff.argument();
+                assert!(argument.starts_with(&format!("{HANDOFF_ARGUMENT}=")));
+                assert_eq!(RelaunchHandoff::parse_argument(&argument), Some(handoff));
+            }
+        }
+
+        // Rendezvous paths may contain the separators..
+        for path in [
+            "/tmp/terminal-relaunch-1-0-00000000.sock",
+            r"\\.\pipe\terminal-relaunch-1-0-00000000",
+            r"C:\Users\a:b,c\rendezvous",
+        ] {
+            let handoff = RelaunchHandoff::new(TerminalType::Kitty, rgb()).with_rendezvous(path);
+            let parsed = RelaunchHandoff::parse_argument(&handoff.argument()).unwrap();
+            assert_eq!(parsed.rendezvous, handoff.rendezvous, "{path}");
+        }
+
+        // Unknown capabilities are ignored, anything else that isn't a handoff is rejected..
+        assert_eq!(
+            RelaunchHandoff::parse_argument("--relaunch-handoff=wezterm:rgb,sixel"),
+            Some(RelaunchHandoff::new(
+                TerminalType::WezTerm,
+                TerminalCapabilities {
+                    score: TerminalType::WezTerm.capability_score(),
+                    ..rgb()
+                }
+            ))
+        );
+        for argument in [
+            "--relaunch-handoff=not-a-terminal:rgb",
+            "--relaunch-handoff=wezterm",
+            "--relaunch-handoff:wezterm:rgb",
+            "--relaunch-handoffs=wezterm:rgb",
+            "wezterm:rgb",
+            "",
+        ] {
+            assert_eq!(
+                RelaunchHandoff::parse_argument(argument),
+                None,
+                "{argument:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn verification_outcomes_from_the_environment_and_the_probe() {
+        let handoff = RelaunchHandoff::new(
+            TerminalType::WezTerm,
+            resolve_capabilities(TerminalType::WezTerm, None),
+        );
+        assert!(handoff.expected_capabilities.rgb_ansi_colours);
+
+        let wezterm = env(&[("TERM_PROGRAM", "WezTerm"), ("TERM", "xterm-256color")]);
+        let truecolor = env(&[("TERM_PROGRAM", "WezTerm"), ("COLORTERM", "24BIT")]);
+        let no_rgb_term = env(&[("TERM_PROGRAM", "WezTerm"), ("TERM", " Linux ")]);
+        let elsewhere = env(&[("TERM_PROGRAM", "vscode"), ("COLORTERM", "truecolor")]);
+        let degraded = RelaunchVerification::Degraded(rgb());
+
+        // (environment, probe reply, outcome, if the probe is asked)..
+        for (name, vars, reply, expected, probed) in [
+            ("env", &wezterm, None, RelaunchVerification::Met, true),
+            ("env", &wezterm, Some(true), RelaunchVerification::Met, true),
+            ("env", &wezterm, Some(false), degraded, true),
+            (
+                "truecolor",
+                &truecolor,
+                Some(false),
+                RelaunchVerification::Met,
+                false,
+            ),
+            ("no rgb", &no_rgb_term, None, degraded, true),
+            ("no rgb", &no_rgb_term, Some(false), degraded, true),
+            (
+                "no rgb",
+                &no_rgb_term,
+                Some(true),
+                RelaunchVerification::Met,
+                true,
+            ),
+            (
+                "elsewhere",
+                &elsewhere,
+                Some(true),
+                RelaunchVerification::NotInTarget,
+                false,
+            ),
+        ] {
+            let asked = Cell::new(false);
+            let verification = verify_handoff_in(&handoff, vars, || {
+                asked.set(true);
+                reply
+            });
+            assert_eq!(verification, expected, "{name} {reply:?}");
+            assert_eq!(asked.get(), probed, "{name} {reply:?}");
+        }
+
+        // Only RGB colours are verified, so a handoff without them is met wherever the target is found..
+        let unicode_only = RelaunchHandoff::new(
+            TerminalType::WezTerm,
+            TerminalCapabilities {
+                full_unicode: true,
+                hyperlinks: true,
+                ..TerminalCapabilities::default()
+            },
+        );
+        assert_eq!(
+            verify_handoff_in(&unicode_only, &no_rgb_term, || unreachable!()),
+            RelaunchVerification::Met
+        );
+        assert_eq!(
+            verify_handoff_in(&unicode_only, &elsewhere, || unreachable!()),
+            RelaunchVerification::NotInTarget
+        );
+        for verification in [
+            RelaunchVerification::Met,
+            degraded,
+            RelaunchVerification::NotInTarget,
+        ] {
+            assert_eq!(verification.to_string(), verification.description());
+        }
+    }
+
+    #[test]
+    fn pending_handoffs_only_apply_inside_their_scope() {
+        let handoff = RelaunchHandoff::new(TerminalType::Kitty, rgb());
+        assert_eq!(pending_argument(), None);
+        with_pending_handoff(handoff.clone(), || {
+            assert_eq!(pending_argument(), Some(handoff.argument()));
+
+            // ..the innermost handoff applies, and the outer one is restored..
+            let nested =
+                RelaunchHandoff::new(TerminalType::WezTerm, TerminalCapabilities::default());
+            with_pending_handoff(nested.clone(), || {
+                assert_eq!(pending_argument(), Some(nested.argument()));
+            });
+            assert_eq!(pending_argument(), Some(handoff.argument()));
+
+            // ..and other threads don't pass it..
+            std::thread::spawn(|| assert_eq!(pending_argument(), None))
+                .join()
+                .unwrap();
+        });
+        assert_eq!(pending_argument(), None);
+    }
+
+    #[test]
+    fn pipelines_hand_the_target_and_its_capabilities_to_the_relaunched_program() {
+        let _globals = reading_globals();
+        let recorder = RecordingProvider::new(TerminalType::WezTerm);
+        let relaunched = RelaunchPipeline::without_decision()
+            .with_current_terminal(TerminalType::Unknown)
+            .with_providers({
+                let recorder = recorder.clone();
+                move |terminal| {
+                    (terminal == TerminalType::WezTerm)
+                        .then(|| Box::new(recorder.clone()) as Box<dyn TerminalProvider>)
+                }
+            })
+            .into_result()
+            .unwrap();
+        assert!(relaunched);
+
+        // The relaunched program reads the handoff from its arguments, and verifies it in its terminal..
+        let launch = recorder.last_launch().unwrap();
+        let handoffs: Vec<_> = launch
+            .args
+            .iter()
+            .filter_map(|arg| RelaunchHandoff::parse_argument(arg))
+            .collect();
+        let expected = RelaunchHandoff::new(
+            TerminalType::WezTerm,
+            resolve_capabilities(TerminalType::WezTerm, None),
+        );
+        assert_eq!(handoffs, [expected]);
+        assert_eq!(
+            verify_handoff_in(&handoffs[0], &env(&[("TERM_PROGRAM", "WezTerm")]), || None),
+            RelaunchVerification::Met
+        );
+
+        // ..and relaunches outside a pipeline don't pass one..
+        with_window_mode(WindowMode::Window, || recorder.relaunch_in_terminal()).unwrap();
+        let launch = recorder.last_launch().unwrap();
+        assert!(
+            !launch
+                .args
+                .iter()
+                .any(|arg| arg.starts_with(HANDOFF_ARGUMENT))
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Detection of `Windows` hosts that run programs without a terminal, e.g. the `PowerShell ISE` or `WinRM` remote
+//! execution, where there is no terminal to detect capabilities of.
+//!
+//! These hosts read the program's output through pipes and display it as plain text, so escape sequences are shown
+//! literally. They inherit variables such as `TERM` or `WT_SESSION` from wherever they were started, so detection
+//! from the environment would claim a capable terminal. Instead, they are detected from none of the standard
+//! handles being a console, together with the process ancestry, and reported as `TerminalType::NonTerminalHost`.
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::TerminalType;
+//! use terminal_relaunch::config::RelaunchConfig;
+//! use terminal_relaunch::decision::{DecisionInputs, FeatureRequirements, RelaunchDecision, SkipReason, decide};
+//! use terminal_relaunch::host::{HostKind, StdHandleKind, classify_non_terminal_host_in};
+//! use terminal_relaunch::remote::ProcessInfo;
+//!
+//! let process = |name: &str| ProcessInfo { pid: 4242, name: name.to_string(), path: None };
+//! let piped = [StdHandleKind::Redirected; 3];
+//! // Inherited from the `Windows Terminal` the host was started from..
+//! let env = std::collections::HashMap::from([("WT_SESSION".to_string(), "0b7e6a7c".to_string())]);
+//!
+//! let outcome = |handles: [StdHandleKind; 3], ancestry: &[ProcessInfo]| {
+//!     let host = classify_non_terminal_host_in(&env, ancestry, handles);
+//!     let terminal = host.map_or(TerminalType::WindowsTerminal, |_| TerminalType::NonTerminalHost);
+//!     let inputs = DecisionInputs {
+//!         interactive: handles.iter().all(|handle| *handle == StdHandleKind::Console),
+//!         remote_session: host.is_some_and(|host| host.is_remote()),
+//!         full_unicode: terminal.supports_full_unicode(),
+//!         rgb_ansi_colours: terminal.supports_rgb_ansi_colours(),
+//!         ..DecisionInputs::new(terminal)
+//!     };
+//!     let decision = decide(&inputs, &RelaunchConfig::new(), &FeatureRequirements::PREFERRED);
+//!     (host, terminal, decision)
+//! };
+//!
+//! // The `PowerShell ISE` has no terminal, opening a real one helps most..
+//! let ise = [process("powershell.exe"), process("powershell_ise.exe"), process("explorer.exe")];
+//! assert_eq!(
+//!     outcome(piped, &ise),
+//!     (Some(HostKind::PowerShellIse), TerminalType::NonTerminalHost, RelaunchDecision::Attempt),
+//! );
+//!
+//! // `WinRM` has no terminal either, but also no user on this machine to open one for..
+//! let winrm = [process("cmd.exe"), process("winrshost.exe"), process("svchost.exe")];
+//! assert_eq!(
+//!     outcome(piped, &winrm),
+//!     (
+//!         Some(HostKind::WinRm),
+//!         TerminalType::NonTerminalHost,
+//!         RelaunchDecision::Skip(SkipReason::RemoteSession),
+//!     ),
+//! );
+//!
+//! // Plain redirection in a real terminal keeps the detected terminal, which isn't relaunched from..
+//! let terminal = [process("pwsh.exe"), process("WindowsTerminal.exe")];
+//! assert_eq!(
+//!     outcome(piped, &terminal),
+//!     (None, TerminalType::WindowsTerminal, RelaunchDecision::Skip(SkipReason::NotInteractive)),
+//! );
+//!
+//! // As is any host with a console attached..
+//! let console = [StdHandleKind::Console, StdHandleKind::Redirected, StdHandleKind::Redirected];
+//! assert_eq!(outcome(console, &ise).0, None);
+//! ```
+
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use crate::environment::Environment;
+use crate::remote::ProcessInfo;
+
+/// Executable name of the `PowerShell ISE`.
+pub const POWERSHELL_ISE_PROCESS: &str = "powershell_ise.exe";
+
+/// Executable names of the `WinRM` hosts, for `winrs` and `PowerShell` remoting respectively.
+pub const WINRM_HOST_PROCESSES: [&str; 2] = ["winrshost.exe", "wsmprovhost.exe"];
+
+/// Environment variable set by `PowerShell` for its children when started with `-ExecutionPolicy`, as remote
+/// execution agents (e.g. RMM tools) do to run their scripts.
+pub const EXECUTION_POLICY_PREFERENCE_VAR: &str = "PSExecutionPolicyPreference";
+
+/// Represents what a standard handle of the process is connected to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StdHandleKind {
+    /// A console.
+    Console,
+    /// A pipe or file.
+    Redirected,
+    /// Nothing, e.g. for programs started from a GUI without standard handles.
+    Missing,
+}
+
+/// Represents a host running the program without a terminal, see `non_terminal_host()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HostKind {
+    /// The legacy `PowerShell ISE`.
+    PowerShellIse,
+    /// `WinRM` remote execution, i.e. `winrs` or `PowerShell` remoting.
+    WinRm,
+    /// A remote execution agent (e.g. an RMM tool), running the program from `PowerShell` with an execution
+    /// policy override, see `EXECUTION_POLICY_PREFERENCE_VAR`.
+    RemoteExecution,
+}
+
+impl HostKind {
+    /// Returns the name of the host.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PowerShellIse => "PowerShell ISE",
+            Self::WinRm => "WinRM",
+            Self::RemoteExecution => "Remote Execution",
+        }
+    }
+
+    /// Returns `true` if the host runs the program on behalf of a remote user, so there is no local user to open
+    /// a terminal for.
+    #[inline]
+    #[must_use]
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Self::WinRm | Self::RemoteExecution)
+    }
+}
+
+impl Display for HostKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The host running the program without a terminal, detected once per process.
+static NON_TERMINAL_HOST: LazyLock<Option<HostKind>> = LazyLock::new(detect_non_terminal_host);
+
+/// Returns the host running the program without a terminal, if any.
+///
+/// # Notes
+/// Always `None` on platforms other than `Windows`.
+#[inline]
+#[must_use]
+pub fn non_terminal_host() -> Option<HostKind> {
+    *NON_TERMINAL_HOST
+}
+
+/// Classifies the host running the program from the given environment, process ancestry (nearest ancestor first)
+/// and standard handles (input, output and error).
+///
+/// # Returns
+/// *   `None` if any standard handle is a console, as the program then runs in a terminal (e.g. with its output
+///     redirected), whatever its ancestors are.
+/// *   The nearest `PowerShell ISE` or `WinRM` ancestor otherwise, see `POWERSHELL_ISE_PROCESS` and
+///     `WINRM_HOST_PROCESSES`.
+/// *   `HostKind::RemoteExecution` if there is neither, but `EXECUTION_POLICY_PREFERENCE_VAR` is set.
+/// *   `None` otherwise, e.g. for plain redirection.
+#[must_use]
+pub fn classify_non_terminal_host_in(
+    env: &(impl Environment + ?Sized),
+    ancestry: &[ProcessInfo],
+    handles: [StdHandleKind; 3],
+) -> Option<HostKind> {
+    if handles.contains(&StdHandleKind::Console) {
+        return None;
+    }
+
+    let host = ancestry.iter().find_map(|process| {
+        if process.name.eq_ignore_ascii_case(POWERSHELL_ISE_PROCESS) {
+            Some(HostKind::PowerShellIse)
+        } else if WINRM_HOST_PROCESSES
+            .iter()
+            .any(|name| process.name.eq_ignore_ascii_case(name))
+        {
+            Some(HostKind::WinRm)
+        } else {
+            None
+        }
+    });
+
+    host.or_else(|| {
+        env.var(EXECUTION_POLICY_PREFERENCE_VAR)
+            .is_some_and(|policy| !policy.is_empty())
+            .then_some(HostKind::RemoteExecution)
+    })
+}
+
+/// Detects the host running the program without a terminal, see `non_terminal_host()`.
+fn detect_non_terminal_host() -> Option<HostKind> {
+    #[cfg(windows)]
+    {
+        let host = classify_non_terminal_host_in(
+            &crate::environment::SystemEnvironment,
+            crate::remote::process_ancestry(),
+            std_handle_kinds(),
+        );
+        if let Some(host) = host {
+            crate::logging::info!("Running in {host} without a terminal.");
+        }
+        host
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// Returns what the standard input, output and error handles are connected to.
+#[cfg(windows)]
+fn std_handle_kinds() -> [StdHandleKind; 3] {
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    [STD_INPUT_HANDLE, STD_OUTPUT_HANDLE, STD_ERROR_HANDLE].map(|std_handle| {
+        // SAFETY: `GetStdHandle` has no preconditions.
+        let handle = unsafe { GetStdHandle(std_handle) };
+        if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+            return StdHandleKind::Missing;
+        }
+
+        let mut mode = 0;
+        // SAFETY: `handle` is a valid handle and `mode` is a valid pointer, the call fails for non-console handles.
+        if unsafe { GetConsoleMode(handle, &raw mut mode) } == 0 {
+            StdHandleKind::Redirected
+        } else {
+            StdHandleKind::Console
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        EXECUTION_POLICY_PREFERENCE_VAR, HostKind, StdHandleKind, classify_non_terminal_host_in,
+    };
+    use crate::TerminalType;
+    use crate::config::RelaunchConfig;
+    use crate::decision::{
+        DecisionInputs, FeatureRequirements, RelaunchDecision, SkipReason, decide,
+    };
+    use crate::remote::ProcessInfo;
+
+    const PIPED: [StdHandleKind; 3] = [StdHandleKind::Redirected; 3];
+
+    fn ancestry(names: &[&str]) -> Vec<ProcessInfo> {
+        (1..)
+            .zip(names)
+            .map(|(pid, name)| ProcessInfo {
+                pid,
+                name: (*name).to_string(),
+                path: None,
+            })
+            .collect()
+    }
+
+    /// Inherited from the `Windows Terminal` the host was started from..
+    fn inherited_env() -> HashMap<String, String> {
+        HashMap::from([
+            ("WT_SESSION".to_string(), "0b7e6a7c".to_string()),
+            ("TERM".to_string(), "xterm-256color".to_string()),
+        ])
+    }
+
+    /// Returns the host, terminal and decision for the given process, as `DecisionInputs::gather()` would.
+    fn outcome(
+        env: &HashMap<String, String>,
+        names: &[&str],
+        handles: [StdHandleKind; 3],
+    ) -> (Option<HostKind>, TerminalType, RelaunchDecision) {
+        let host = classify_non_terminal_host_in(env, &ancestry(names), handles);
+        let terminal = host.map_or(TerminalType::WindowsTerminal, |_| {
+            TerminalType::NonTerminalHost
+        });
+        let inputs = DecisionInputs {
+            interactive: handles
+                .iter()
+                .all(|handle| *handle == StdHandleKind::Console),
+            remote_session: host.is_some_and(|host| host.is_remote()),
+            full_unicode: terminal.supports_full_unicode(),
+            rgb_ansi_colours: terminal.supports_rgb_ansi_colours(),
+            ..DecisionInputs::new(terminal)
+        };
+        let decision = decide(
+            &inputs,
+            &RelaunchConfig::new(),
+            &FeatureRequirements::PREFERRED,
+        );
+        (host, terminal, decision)
+    }
+
+    #[test]
+    fn ise_winrm_and_redirection_have_distinct_outcomes() {
+        let env = inherited_env();
+
+        let ise = outcome(
+            &env,
+            &["powershell.exe", "powershell_ise.exe", "explorer.exe"],
+            PIPED,
+        );
+        assert_eq!(
+            ise,
+            (
+                Some(HostKind::PowerShellIse),
+                TerminalType::NonTerminalHost,
+                RelaunchDecision::Attempt
+            )
+        );
+
+        for host in ["winrshost.exe", "wsmprovhost.exe", "WinRSHost.EXE"] {
+            assert_eq!(
+                outcome(&env, &["cmd.exe", host, "svchost.exe"], PIPED),
+                (
+                    Some(HostKind::WinRm),
+                    TerminalType::NonTerminalHost,
+                    RelaunchDecision::Skip(SkipReason::RemoteSession)
+                ),
+                "{host}"
+            );
+        }
+
+        let redirected = outcome(&env, &["pwsh.exe", "WindowsTerminal.exe"], PIPED);
+        assert_eq!(
+            redirected,
+            (
+                None,
+                TerminalType::WindowsTerminal,
+                RelaunchDecision::Skip(SkipReason::NotInteractive)
+            )
+        );
+    }
+
+    #[test]
+    fn any_console_handle_means_a_terminal() {
+        let env = inherited_env();
+        let ise = ancestry(&["powershell.exe", "powershell_ise.exe"]);
+
+        for console in 0..3 {
+            let mut handles = PIPED;
+            handles[console] = StdHandleKind::Console;
+            assert_eq!(
+                classify_non_terminal_host_in(&env, &ise, handles),
+                None,
+                "{handles:?}"
+            );
+        }
+
+        // Missing handles, as for programs started from a GUI, aren't consoles..
+        let missing = [StdHandleKind::Missing; 3];
+        assert_eq!(
+            classify_non_terminal_host_in(&env, &ise, missing),
+            Some(HostKind::PowerShellIse)
+        );
+        let mixed = [
+            StdHandleKind::Missing,
+            StdHandleKind::Redirected,
+            StdHandleKind::Redirected,
+        ];
+        assert_eq!(
+            classify_non_terminal_host_in(&env, &ise, mixed),
+            Some(HostKind::PowerShellIse)
+        );
+    }
+
+    #[test]
+    fn nearest_host_wins_then_the_execution_policy() {
+        let mut env = inherited_env();
+
+        // A script run remotely that opens the `PowerShell ISE` is in the `ISE`..
+        let nested = ancestry(&["powershell_ise.exe", "wsmprovhost.exe"]);
+        assert_eq!(
+            classify_non_terminal_host_in(&env, &nested, PIPED),
+            Some(HostKind::PowerShellIse)
+        );
+        let nested = ancestry(&["wsmprovhost.exe", "powershell_ise.exe"]);
+        assert_eq!(
+            classify_non_terminal_host_in(&env, &nested, PIPED),
+            Some(HostKind::WinRm)
+        );
+
+        // Remote execution agents are only recognised from the execution policy they run `PowerShell` with..
+        let agent = ancestry(&["powershell.exe", "AgentService.exe", "services.exe"]);
+        assert_eq!(classify_non_terminal_host_in(&env, &agent, PIPED), None);
+        env.insert(EXECUTION_POLICY_PREFERENCE_VAR.to_string(), String::new());
+        assert_eq!(classify_non_terminal_host_in(&env, &agent, PIPED), None);
+        env.insert(
+            EXECUTION_POLICY_PREFERENCE_VAR.to_string(),
+            "Bypass".to_string(),
+        );
+        assert_eq!(
+            classify_non_terminal_host_in(&env, &agent, PIPED),
+            Some(HostKind::RemoteExecution)
+        );
+        assert!(HostKind::RemoteExecution.is_remote());
+        assert!(!HostKind::PowerShellIse.is_remote());
+
+        // ..which doesn't override a known host..
+        assert_eq!(
+            classify_non_terminal_host_in(&env, &ancestry(&["powershell_ise.exe"]), PIPED),
+            Some(HostKind::PowerShellIse)
+        );
+    }
+}
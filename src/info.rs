@@ -0,0 +1,220 @@
+//! The detected terminal together with its effective capabilities, i.e. what output should actually use.
+//!
+//! `TerminalType`'s capability methods (e.g. `TerminalType::supports_full_unicode()`) and `verbose_format()`
+//! describe the intrinsic capabilities of a terminal type, so never change at runtime. `TerminalInfo` also
+//! applies the overrides and capability rules (see `set_unicode_support_override()` and `capabilities`),
+//! `NO_COLOR`/`FORCE_COLOR`, and whether output is written to a terminal at all.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::capabilities::ColourDepth;
+//! use terminal_relaunch::info::TerminalInfo;
+//! use terminal_relaunch::{TerminalType, set_rgb_ansi_override, set_unicode_support_override};
+//!
+//! let no_color = HashMap::from([("NO_COLOR".to_string(), "1".to_string())]);
+//! let plain = HashMap::new();
+//!
+//! for terminal in [TerminalType::Kitty, TerminalType::Xterm] {
+//!     let intrinsic = (terminal.supports_full_unicode(), terminal.supports_rgb_ansi_colours());
+//!     let verbose = terminal.verbose_format();
+//!
+//!     for overridden in [None, Some(true), Some(false)] {
+//!         set_unicode_support_override(overridden);
+//!         set_rgb_ansi_override(overridden);
+//!
+//!         for stream_terminal in [true, false] {
+//!             for env in [&plain, &no_color] {
+//!                 let info = TerminalInfo { stream_terminal, ..TerminalInfo::new(terminal) };
+//!                 let effective = info.effective_capabilities_in(env);
+//!
+//!                 // The intrinsic capabilities never change..
+//!                 assert_eq!((terminal.supports_full_unicode(), terminal.supports_rgb_ansi_colours()), intrinsic);
+//!                 assert_eq!(terminal.verbose_format(), verbose);
+//!
+//!                 let rgb = overridden.unwrap_or(intrinsic.1) && stream_terminal && env.is_empty();
+//!                 assert_eq!(effective.full_unicode, overridden.unwrap_or(intrinsic.0));
+//!                 assert_eq!(effective.rgb_ansi_colours, rgb);
+//!                 if !stream_terminal || !env.is_empty() {
+//!                     assert_eq!(effective.colour_depth, ColourDepth::NoColour);
+//!                 }
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! set_unicode_support_override(Some(true));
+//! set_rgb_ansi_override(Some(false));
+//! assert_eq!(TerminalInfo::new(TerminalType::Xterm).effective_format(), "xterm (xterm), Full Unicode");
+//! ```
+
+use crate::capabilities::{self, ColourDepth, FORCE_COLOR_VAR};
+use crate::environment::{Environment, SystemEnvironment};
+use crate::output::InfoSegment;
+use crate::remote::RemoteSession;
+use crate::version::TerminalVersion;
+use crate::{CURRENT_TERMINAL_VERSION, TerminalType};
+
+/// The capabilities output should use, see `TerminalInfo::effective_capabilities()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectiveCapabilities {
+    /// If full unicode (e.g. emojis) should be rendered.
+    pub full_unicode: bool,
+    /// If RGB (ANSI) colours should be used, i.e. `colour_depth` is `ColourDepth::TrueColour`.
+    pub rgb_ansi_colours: bool,
+    /// The colour depth output should use.
+    pub colour_depth: ColourDepth,
+}
+
+/// A terminal, its version, whether output is written to it, and the remote session it's reached through, see
+/// `TerminalInfo::current()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerminalInfo {
+    /// The terminal type.
+    pub terminal: TerminalType,
+    /// The version of the terminal, if known.
+    pub version: Option<TerminalVersion>,
+    /// If output is written to a terminal, see `output::user_channel()`.
+    pub stream_terminal: bool,
+    /// The remote session the terminal is reached through, if any, see `remote::remote_session()`.
+    pub remote: RemoteSession,
+}
+
+impl TerminalInfo {
+    /// Creates the information of the given terminal, with an unknown version, written to as a local terminal.
+    #[inline]
+    #[must_use]
+    pub const fn new(terminal: TerminalType) -> Self {
+        Self {
+            terminal,
+            version: None,
+            stream_terminal: true,
+            remote: RemoteSession::None,
+        }
+    }
+
+    /// Returns the information of the current terminal.
+    #[must_use]
+    pub fn current() -> Self {
+        Self {
+            terminal: crate::current_terminal(),
+            version: *CURRENT_TERMINAL_VERSION,
+            stream_terminal: crate::output::user_channel().is_some(),
+            remote: crate::remote::remote_session(),
+        }
+    }
+
+    /// Returns the capabilities output should use, see `effective_capabilities_in()`.
+    #[inline]
+    #[must_use]
+    pub fn effective_capabilities(&self) -> EffectiveCapabilities {
+        self.effective_capabilities_in(&SystemEnvironment)
+    }
+
+    /// Returns the capabilities output should use, with the given environment.
+    ///
+    /// # Notes
+    /// *   Full unicode and RGB (ANSI) colour support are resolved as `capabilities::resolve_full_unicode_support()`
+    ///     and `capabilities::resolve_rgb_ansi_colour_support()`, i.e. honouring the overrides and capability rules.
+    /// *   The colour depth is resolved as `capabilities::resolve_colour_depth()`, so `NO_COLOR` disables colours
+    ///     even when RGB colours are overridden as supported.
+    /// *   No colours are used when output isn't written to a terminal, unless `FORCE_COLOR` is set. Unicode is
+    ///     unaffected, as it's only a matter of rendering.
+    /// *   Neither full unicode nor colours are used with plain output, see `output::plain_output_in()`.
+    #[must_use]
+    pub fn effective_capabilities_in(
+        &self,
+        env: &(impl Environment + ?Sized),
+    ) -> EffectiveCapabilities {
+        if crate::output::plain_output_in(env) {
+            return EffectiveCapabilities {
+                full_unicode: false,
+                rgb_ansi_colours: false,
+                colour_depth: ColourDepth::NoColour,
+            };
+        }
+
+        let version = self.version.as_ref();
+        let rgb_ansi_colours =
+            capabilities::resolve_rgb_ansi_colour_support(self.terminal, version)
+                || (crate::is_rgb_ansi_overridden().is_none()
+                    && capabilities::env_confirms_rgb_in(self.terminal, env));
+
+        let colour_depth = if self.stream_terminal || env.var(FORCE_COLOR_VAR).is_some() {
+            capabilities::resolve_colour_depth(env, rgb_ansi_colours)
+        } else {
+            ColourDepth::NoColour
+        };
+
+        EffectiveCapabilities {
+            full_unicode: capabilities::resolve_full_unicode_support(self.terminal, version),
+            rgb_ansi_colours: colour_depth == ColourDepth::TrueColour,
+            colour_depth,
+        }
+    }
+
+    /// Returns a verbose formatted string of the terminal type and its effective capabilities.
+    ///
+    /// # Notes
+    /// This is the plain text of `effective_segments()`, unlike `TerminalType::verbose_format()` which lists the
+    /// intrinsic capabilities.
+    #[inline]
+    #[must_use]
+    pub fn effective_format(&self) -> String {
+        self.effective_segments()
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect()
+    }
+
+    /// Returns the terminal type, its version if known, and its effective capabilities as segments, as
+    /// `TerminalType::format_segments()`.
+    #[inline]
+    #[must_use]
+    pub fn effective_segments(&self) -> Vec<InfoSegment> {
+        self.segments(false)
+    }
+
+    /// Returns the terminal type, its version if known, and every capability as segments, as
+    /// `effective_segments()`, but listing the capabilities output won't use as `SegmentKind::CapabilityMissing`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use terminal_relaunch::info::TerminalInfo;
+    /// use terminal_relaunch::output::SegmentKind;
+    /// use terminal_relaunch::version::TerminalVersion;
+    /// use terminal_relaunch::{TerminalType, set_rgb_ansi_override, set_unicode_support_override};
+    ///
+    /// set_unicode_support_override(Some(true));
+    /// set_rgb_ansi_override(Some(false));
+    ///
+    /// let info = TerminalInfo { version: Some(TerminalVersion::new(3, 4, 0)), ..TerminalInfo::new(TerminalType::Tmux) };
+    /// let segments = info.status_segments();
+    /// let text: String = segments.iter().map(|segment| segment.text.as_str()).collect();
+    /// assert_eq!(text, "tmux 3.4.0 (tmux), Full Unicode, Enhanced Colours");
+    /// assert!(segments.iter().any(|segment| segment.kind == SegmentKind::Version && segment.text == "3.4.0"));
+    /// assert_eq!(
+    ///     segments.last().map(|segment| segment.kind),
+    ///     Some(SegmentKind::CapabilityMissing)
+    /// );
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn status_segments(&self) -> Vec<InfoSegment> {
+        self.segments(true)
+    }
+
+    /// Returns the segments of the terminal with its effective capabilities, see `TerminalType::segments_with()`.
+    fn segments(&self, include_missing: bool) -> Vec<InfoSegment> {
+        let capabilities = self.effective_capabilities();
+        self.terminal.segments_with(
+            self.version.as_ref(),
+            capabilities.full_unicode,
+            capabilities.rgb_ansi_colours,
+            include_missing,
+        )
+    }
+}
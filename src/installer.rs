@@ -0,0 +1,397 @@
+//! Detection of running inside an installation flow, e.g. a post-install step an installer runs in a `cmd /K`
+//! console, where relaunching would interrupt the installer and leave an orphaned window behind.
+//!
+//! Detection is heuristic, so packagers can mark their installation steps explicitly with
+//! `TERMINAL_RELAUNCH_INSTALLER=1`, see `INSTALLER_VAR`.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::TerminalType;
+//! use terminal_relaunch::config::RelaunchConfig;
+//! use terminal_relaunch::decision::{DecisionInputs, FeatureRequirements, RelaunchDecision, SkipReason, decide};
+//! use terminal_relaunch::installer::{InstallerSignal, installer_signal_in};
+//! use terminal_relaunch::remote::ProcessInfo;
+//!
+//! let process = |name: &str| ProcessInfo { pid: 4242, name: name.to_string(), path: None };
+//! let env = |name: &str, value: &str| HashMap::from([(name.to_string(), value.to_string())]);
+//! let none = HashMap::<String, String>::new();
+//! let console = [process("cmd.exe"), process("explorer.exe")];
+//!
+//! // Each signal..
+//! assert_eq!(installer_signal_in(&env("TERMINAL_RELAUNCH_INSTALLER", "1"), &console), Some(InstallerSignal::ExplicitVar));
+//! assert_eq!(installer_signal_in(&env("__COMPAT_LAYER", "ElevateCreateProcess"), &console), Some(InstallerSignal::CompatLayer));
+//! assert_eq!(installer_signal_in(&env("__COMPAT_LAYER", "RunAsInvoker Installer"), &console), Some(InstallerSignal::CompatLayer));
+//! for installer in ["msiexec.exe", "MyApp-Setup.exe", "setup64.exe", "unins000.exe", "vs_installer.exe", "Install.exe"] {
+//!     let ancestry = [process("cmd.exe"), process(installer), process("explorer.exe")];
+//!     assert_eq!(installer_signal_in(&none, &ancestry), Some(InstallerSignal::InstallerAncestor), "{installer}");
+//! }
+//!
+//! // ..and what isn't one..
+//! assert_eq!(installer_signal_in(&none, &console), None);
+//! assert_eq!(installer_signal_in(&env("TERMINAL_RELAUNCH_INSTALLER", "0"), &console), None);
+//! assert_eq!(installer_signal_in(&env("__COMPAT_LAYER", "RunAsInvoker"), &console), None);
+//! assert_eq!(installer_signal_in(&none, &[process("cargo.exe"), process("setuptools")]), None);
+//!
+//! // An installer context skips the relaunch, unless there are stronger reasons to..
+//! let ancestry = [process("cmd.exe"), process("msiexec.exe")];
+//! let inputs = DecisionInputs {
+//!     installer_context: installer_signal_in(&none, &ancestry).is_some(),
+//!     ..DecisionInputs::new(TerminalType::WindowsCMD)
+//! };
+//! let decide = |inputs: &DecisionInputs| decide(inputs, &RelaunchConfig::new(), &FeatureRequirements::PREFERRED);
+//! assert_eq!(decide(&inputs), RelaunchDecision::Skip(SkipReason::InstallerContext));
+//! let relaunched = DecisionInputs { already_relaunched: true, ..inputs };
+//! assert_eq!(decide(&relaunched), RelaunchDecision::Skip(SkipReason::AlreadyRelaunched));
+//! assert_eq!(decide(&DecisionInputs::new(TerminalType::WindowsCMD)), RelaunchDecision::Attempt);
+//! ```
+
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use crate::environment::Environment;
+use crate::remote::ProcessInfo;
+
+/// Environment variable packagers set to `1` for the installation steps they run, so relaunching is skipped.
+pub const INSTALLER_VAR: &str = "TERMINAL_RELAUNCH_INSTALLER";
+
+/// Environment variable listing the `Windows` compatibility layers applied to the process, space separated.
+pub const COMPAT_LAYER_VAR: &str = "__COMPAT_LAYER";
+
+/// Compatibility layers `Windows` applies to installers, see `COMPAT_LAYER_VAR`.
+pub const INSTALLER_COMPAT_LAYERS: [&str; 2] = ["Installer", "ElevateCreateProcess"];
+
+/// Executable name of the `Windows Installer`.
+pub const MSIEXEC_PROCESS: &str = "msiexec.exe";
+
+/// Represents the signal an installer context was detected from, see `installer_signal()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InstallerSignal {
+    /// `INSTALLER_VAR` is set to `1`.
+    ExplicitVar,
+    /// An installer compatibility layer is applied, see `INSTALLER_COMPAT_LAYERS`.
+    CompatLayer,
+    /// An ancestor is an installer, see `is_installer_process()`.
+    InstallerAncestor,
+}
+
+impl InstallerSignal {
+    /// Returns the name of the signal.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ExplicitVar => "Explicit Variable",
+            Self::CompatLayer => "Compatibility Layer",
+            Self::InstallerAncestor => "Installer Ancestor",
+        }
+    }
+}
+
+impl Display for InstallerSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The installer signal of the program, detected once per process.
+static INSTALLER_SIGNAL: LazyLock<Option<InstallerSignal>> = LazyLock::new(detect_installer_signal);
+
+/// Returns `true` if the program runs inside an installation flow, see `installer_signal()`.
+#[inline]
+#[must_use]
+pub fn is_installer_context() -> bool {
+    installer_signal().is_some()
+}
+
+/// Returns the signal the program was detected to run inside an installation flow from, if any.
+#[inline]
+#[must_use]
+pub fn installer_signal() -> Option<InstallerSignal> {
+    *INSTALLER_SIGNAL
+}
+
+/// Returns `true` if the given executable name is that of an installer, i.e. `msiexec.exe`, or an executable
+/// named like `setup.exe`, `MyApp-Setup.exe`, `vs_installer.exe` or `unins000.exe`.
+#[must_use]
+pub fn is_installer_process(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let Some(stem) = name.strip_suffix(".exe") else {
+        return false;
+    };
+
+    name == MSIEXEC_PROCESS
+        || stem.starts_with("setup")
+        || stem.ends_with("setup")
+        || stem.contains("install")
+        || stem.starts_with("unins")
+}
+
+/// Classifies whether the given environment and process ancestry (nearest ancestor first) belong to an
+/// installation flow.
+///
+/// # Returns
+/// *   `InstallerSignal::ExplicitVar` if `INSTALLER_VAR` is `1`.
+/// *   `InstallerSignal::CompatLayer` if `COMPAT_LAYER_VAR` lists any of `INSTALLER_COMPAT_LAYERS`.
+/// *   `InstallerSignal::InstallerAncestor` if any ancestor is an installer, see `is_installer_process()`.
+/// *   `None` otherwise.
+#[must_use]
+pub fn installer_signal_in(
+    env: &(impl Environment + ?Sized),
+    ancestry: &[ProcessInfo],
+) -> Option<InstallerSignal> {
+    if env
+        .var(INSTALLER_VAR)
+        .is_some_and(|value| value.trim() == "1")
+    {
+        return Some(InstallerSignal::ExplicitVar);
+    }
+
+    let compat_layer = env.var(COMPAT_LAYER_VAR).is_some_and(|layers| {
+        layers.split_whitespace().any(|layer| {
+            INSTALLER_COMPAT_LAYERS
+                .iter()
+                .any(|installer_layer| layer.eq_ignore_ascii_case(installer_layer))
+        })
+    });
+    if compat_layer {
+        return Some(InstallerSignal::CompatLayer);
+    }
+
+    ancestry
+        .iter()
+        .any(|process| is_installer_process(&process.name))
+        .then_some(InstallerSignal::InstallerAncestor)
+}
+
+/// Detects the installer signal of the program, see `installer_signal()`.
+fn detect_installer_signal() -> Option<InstallerSignal> {
+    let signal = installer_signal_in(
+        &crate::environment::SystemEnvironment,
+        crate::remote::process_ancestry(),
+    );
+    if let Some(signal) = signal {
+        crate::logging::info!("Running inside an installer ({signal}), relaunching is disabled.");
+    }
+    signal
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        COMPAT_LAYER_VAR, INSTALLER_VAR, InstallerSignal, installer_signal_in, is_installer_process,
+    };
+    use crate::config::RelaunchConfig;
+    use crate::decision::{
+        DecisionInputs, FeatureRequirements, RelaunchDecision, SkipReason, decide,
+    };
+    use crate::remote::ProcessInfo;
+    use crate::simulation::{Scenario, simulate};
+    use crate::test_support::reading_globals;
+    use crate::{OperatingSystem, TerminalType};
+
+    fn ancestry(names: &[&str]) -> Vec<ProcessInfo> {
+        (1..)
+            .zip(names)
+            .map(|(pid, name)| ProcessInfo {
+                pid,
+                name: (*name).to_string(),
+                path: None,
+            })
+            .collect()
+    }
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn installer_processes() {
+        for name in [
+            "msiexec.exe",
+            "MSIEXEC.EXE",
+            "setup.exe",
+            "Setup64.exe",
+            "MyApp-Setup.exe",
+            "myapp_setup.exe",
+            "vs_installer.exe",
+            "Install.exe",
+            "InstallShield.exe",
+            "unins000.exe",
+            "Uninstall.exe",
+        ] {
+            assert!(is_installer_process(name), "{name}");
+        }
+
+        for name in [
+            "cmd.exe",
+            "explorer.exe",
+            "cargo.exe",
+            "setuptools",
+            "setup",
+            "install",
+            "msiexec",
+            "upset.exe.bak",
+            "settings.exe",
+            "",
+        ] {
+            assert!(!is_installer_process(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn each_signal_alone_and_their_precedence() {
+        let console = ancestry(&["cmd.exe", "explorer.exe"]);
+        let installer = ancestry(&["cmd.exe", "MyApp-Setup.exe", "explorer.exe"]);
+        let explicit = (INSTALLER_VAR, "1");
+        let compat = (COMPAT_LAYER_VAR, "RunAsInvoker Installer");
+
+        // Each signal on its own..
+        assert_eq!(
+            installer_signal_in(&env(&[explicit]), &console),
+            Some(InstallerSignal::ExplicitVar)
+        );
+        assert_eq!(
+            installer_signal_in(&env(&[(INSTALLER_VAR, " 1\n")]), &console),
+            Some(InstallerSignal::ExplicitVar)
+        );
+        assert_eq!(
+            installer_signal_in(&env(&[compat]), &console),
+            Some(InstallerSignal::CompatLayer)
+        );
+        assert_eq!(
+            installer_signal_in(
+                &env(&[(COMPAT_LAYER_VAR, "elevatecreateprocess")]),
+                &console
+            ),
+            Some(InstallerSignal::CompatLayer)
+        );
+        assert_eq!(
+            installer_signal_in(&env(&[]), &installer),
+            Some(InstallerSignal::InstallerAncestor)
+        );
+
+        // ..values that aren't one..
+        for vars in [
+            [(INSTALLER_VAR, "0")],
+            [(INSTALLER_VAR, "true")],
+            [(INSTALLER_VAR, "")],
+            [(COMPAT_LAYER_VAR, "RunAsInvoker")],
+            [(COMPAT_LAYER_VAR, "InstallerDetection")],
+            [(COMPAT_LAYER_VAR, "")],
+        ] {
+            assert_eq!(installer_signal_in(&env(&vars), &console), None, "{vars:?}");
+        }
+        assert_eq!(installer_signal_in(&env(&[]), &[]), None);
+
+        // ..and the explicit variable first, then the compatibility layer, then the ancestry..
+        assert_eq!(
+            installer_signal_in(&env(&[explicit, compat]), &installer),
+            Some(InstallerSignal::ExplicitVar)
+        );
+        assert_eq!(
+            installer_signal_in(&env(&[compat]), &installer),
+            Some(InstallerSignal::CompatLayer)
+        );
+        assert_eq!(
+            installer_signal_in(&env(&[(INSTALLER_VAR, "0")]), &installer),
+            Some(InstallerSignal::InstallerAncestor)
+        );
+    }
+
+    #[test]
+    fn installer_context_in_the_combined_decision() {
+        let decide = |inputs: &DecisionInputs| {
+            decide(
+                inputs,
+                &RelaunchConfig::new(),
+                &FeatureRequirements::PREFERRED,
+            )
+        };
+        let inputs = DecisionInputs {
+            installer_context: installer_signal_in(
+                &env(&[]),
+                &ancestry(&["cmd.exe", "msiexec.exe"]),
+            )
+            .is_some(),
+            ..DecisionInputs::new(TerminalType::WindowsCMD)
+        };
+        let skipped = |reason| RelaunchDecision::Skip(reason);
+
+        assert_eq!(decide(&inputs), skipped(SkipReason::InstallerContext));
+        assert_eq!(
+            decide(&DecisionInputs::new(TerminalType::WindowsCMD)),
+            RelaunchDecision::Attempt
+        );
+
+        // Earlier reasons still win..
+        for (earlier, reason) in [
+            (
+                DecisionInputs {
+                    already_relaunched: true,
+                    ..inputs.clone()
+                },
+                SkipReason::AlreadyRelaunched,
+            ),
+            (
+                DecisionInputs {
+                    service_context: true,
+                    ..inputs.clone()
+                },
+                SkipReason::ServiceContext,
+            ),
+            (
+                DecisionInputs {
+                    interactive: false,
+                    ..inputs.clone()
+                },
+                SkipReason::NotInteractive,
+            ),
+        ] {
+            assert_eq!(decide(&earlier), skipped(reason));
+        }
+
+        // ..while it wins over later ones..
+        for later in [
+            DecisionInputs {
+                elevated: true,
+                ..inputs.clone()
+            },
+            DecisionInputs {
+                remote_session: true,
+                ..inputs.clone()
+            },
+            DecisionInputs {
+                cooldown_active: true,
+                ..inputs.clone()
+            },
+        ] {
+            assert_eq!(decide(&later), skipped(SkipReason::InstallerContext));
+        }
+
+        // ..including in a simulation, which has no ancestry, so only reads the environment..
+        let _globals = reading_globals();
+        let scenario = Scenario::windows_cmd_with_wt_installed();
+        assert_eq!(simulate(&scenario).decision, RelaunchDecision::Attempt);
+        for (name, value) in [(INSTALLER_VAR, "1"), (COMPAT_LAYER_VAR, "Installer")] {
+            let outcome = simulate(&scenario.clone().var(name, value));
+            assert_eq!(
+                outcome.decision,
+                skipped(SkipReason::InstallerContext),
+                "{name}"
+            );
+            assert_eq!(outcome.provider, None, "{name}");
+        }
+        let linux = Scenario::new(OperatingSystem::Linux).var(INSTALLER_VAR, "1");
+        assert_eq!(
+            simulate(&linux).decision,
+            skipped(SkipReason::InstallerContext)
+        );
+    }
+}
@@ -6,17 +6,17 @@
 //! ## Quick Start
 //!
 //! ```rust,no_run
-//! use terminal_relaunch::{relaunch_if_available_and_exit, CURRENT_TERMINAL};
+//! use terminal_relaunch::prelude::*;
 //!
 //! fn main() {
-//!     // Check if we should attempt to relaunch
-//!     match relaunch_if_available_and_exit() {
-//!         Ok(()) => println!("Terminal features met!"),
-//!         Err(e) => eprintln!("Terminal could not relaunch: {e:?}"),
+//!     // Relaunch in a better terminal if possible, otherwise continue in this one
+//!     let summary = relaunch_if_available_or_continue();
+//!     if summary.is_degraded() {
+//!         eprintln!("Running with reduced terminal features..");
 //!     }
 //!
 //!     // Continue with your application..
-//!     println!("Terminal information: {}", CURRENT_TERMINAL.verbose_format())
+//!     println!("Terminal information: {}", TerminalInfo::current().effective_format())
 //! }
 //! ```
 //!
@@ -25,71 +25,182 @@
 //! ### Detect Current Terminal
 //!
 //! ```rust
-//! use terminal_relaunch::CURRENT_TERMINAL;
+//! use terminal_relaunch::prelude::*;
 //!
-//! println!("Terminal: {}", CURRENT_TERMINAL.verbose_format());
+//! println!("Terminal: {}", TerminalInfo::current().effective_format());
 //! ```
 //!
 //! ### Check Feature Support
 //!
 //! ```rust
-//! use terminal_relaunch::{SUPPORTS_FULL_UNICODE, SUPPORTS_RGB_ANSI_COLOURS};
+//! use terminal_relaunch::prelude::*;
 //!
-//! if *SUPPORTS_FULL_UNICODE {
+//! if supports_full_unicode() {
 //!     println!("✨ Unicode emojis work!");
 //! }
 //!
-//! if *SUPPORTS_RGB_ANSI_COLOURS {
+//! if supports_rgb_ansi_colours() {
 //!     println!("\x1b[38;2;255;0;0mRGB colors work!\x1b[0m");
 //! }
 //! ```
 //!
+//! ## Threading
+//!
+//! Every public function can be called from any thread:
+//!
+//! - Detection (e.g. `current_terminal()`, `supports_full_unicode()`) runs once per process, on first use, and is
+//!   shared by every thread.
+//! - Overrides, capability hints and rules, and the relaunch configuration (e.g. `set_unicode_support_override()`,
+//!   `config::set_relaunch_config()`) are process-wide, and seen by every thread once set.
+//! - Relaunches (e.g. `relaunch_if_available()`, `pipeline::RelaunchPipeline`) resolve the working directory of the
+//!   relaunched program when they're planned, from `config::RelaunchConfig::working_directory`, the directory
+//!   captured by `startup::capture_startup_state()`, or the current directory, in that order. Providers only use
+//!   that directory, see `terminal_providers::launch_working_directory()`, so other threads changing the process
+//!   working directory can't redirect a planned relaunch.
+//! - `open_terminal_here()`, `open_in_new_window_of_current_terminal()` and
+//!   `rendezvous::relaunch_with_rendezvous()` only affect relaunches made by the calling thread, as does the handoff
+//!   a `pipeline::RelaunchPipeline` passes to the program it relaunches, see `handoff`.
+//!
+//! Programs whose threads change the working directory should call `startup::capture_startup_state()` at the top
+//! of `main`, or set `config::RelaunchConfig::working_directory`.
+//!
 //! ## Supported Terminals
 //!
+//! See `support::render_support_matrix()` for how each terminal is detected and what it supports.
+//!
 //! ### Detection
 //!
 //! - `Windows` Specific:
 //!     - `Windows Terminal`
-//!     - `CMD/PowerShell`
+//!     - `CMD/PowerShell` (including which shell hosts the program, see `shell::host_shell()`)
+//!     - `ConEmu` (including `Cmder`)
+//!     - `mintty` (including `Git Bash` and `MSYS2`)
+//!     - Hosts without a terminal (`PowerShell ISE`, `WinRM`)
 //! - `MacOS` Specific:
 //!     - `Terminal.app`
 //!     - `ITerm2`
 //!     - `Kitty`
 //!     - `Ghostty`
+//! - `Linux` Specific:
+//!     - `Ptyxis`
+//!     - `Konsole`
+//!     - `GNOME Terminal`
+//!     - `Tilix`
+//!     - `xterm`
+//!     - `foot`
+//!     - `Terminator`
+//!     - `Xfce Terminal`
+//!     - `rxvt-unicode`
+//!     - `st`
 //! - Other terminals:
 //!     - `Alacritty`
 //!     - `WezTerm`
+//!     - `Hyper`
+//!     - `Tabby`
+//!     - `Contour`
 //! - Editor terminals:
 //!     - `VSCode`
+//!     - `JetBrains` IDEs (e.g. `IntelliJ IDEA`, `CLion`, `RustRover`)
+//!     - `Zed`
 //!     - `NVIM`
+//!     - `Emacs` (`vterm`, `eat` and `term`), never relaunched from unless `RelaunchConfig::allow_emacs_relaunch` is set
+//!     - `Jupyter` (`JupyterLab` and notebook terminals), never relaunched from, as they are served to a browser
+//! - Terminal multiplexers:
+//!     - `tmux`, when the outer terminal can't be identified
+//!     - `GNU Screen`
 //! - Generic Linux Terminals
+//! - `WSL`, where relaunching needs a terminal on the `Windows` host (`RelaunchError::WslHostTerminalRequired`)
 //!
 //! ### Relaunching
-//! 
-//! - `Windows Terminal`
+//!
+//! - `Windows Terminal` (including `Windows Terminal Preview`)
 //! - `ITerm2`
 //! - `Ghostty`
 //! - `Kitty`
 //! - `Alacritty`
 //! - `WezTerm`
+//! - `Ptyxis`
+//! - `Hyper`, for interactive shells only (see `open_terminal_here()`), as it can't run the relaunched program
+//!
+//! The relaunched program keeps the user's locale and time zone (`LANG`, `LANGUAGE`, `LC_*` and `TZ`), even through
+//! launchers like `open` that start the terminal with their own environment, see `environment::propagated_env()`.
 
 #![warn(clippy::pedantic)]
 
+#[cfg(feature = "build-support")]
+pub mod build_support;
+pub mod capabilities;
+pub mod config;
+#[cfg(feature = "test-util")]
+pub mod conformance;
+#[cfg(target_os = "windows")]
+mod console;
+pub mod decision;
+pub mod doctor;
+#[cfg(feature = "anstyle")]
+pub mod ecosystem;
+pub mod emacs;
+pub mod environment;
 pub mod errors;
+pub mod exit_code;
+pub mod fonts;
+#[cfg(feature = "handoff")]
+pub mod handoff;
+pub mod host;
+pub mod info;
+pub mod installer;
 pub mod logging;
+pub mod output;
+#[cfg(feature = "config")]
+pub mod persistence;
+pub mod pipeline;
+pub mod prelude;
+#[cfg(feature = "tty-query")]
+pub mod query;
+#[cfg(any(feature = "test-util", test))]
+pub mod recording;
+pub mod registry;
+pub mod remote;
+#[cfg(feature = "rendezvous")]
+pub mod rendezvous;
+pub mod runtime;
+pub mod service;
+pub mod shell;
+pub mod simulation;
+pub mod startup;
+pub mod status;
+pub mod sudo;
+pub mod support;
+pub mod symbols;
 pub mod terminal_providers;
+#[cfg(test)]
+mod test_support;
+pub mod theme;
+pub mod tmux;
+pub mod version;
+pub mod vscode;
+
+pub use runtime::init;
 
 use std::fmt::Display;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
-use std::sync::atomic;
 
 use strum::{EnumIter, IntoEnumIterator};
 
+use crate::capabilities::HintSource;
+use crate::config::WindowMode;
+use crate::decision::SkipReason;
+use crate::environment::{Environment, SystemEnvironment};
 use crate::terminal_providers::AlacrittyProvider;
 use crate::terminal_providers::GhosttyProvider;
+use crate::terminal_providers::HyperProvider;
 use crate::terminal_providers::KittyProvider;
+use crate::terminal_providers::PtyxisProvider;
 use crate::terminal_providers::TERM_VAR;
 use crate::terminal_providers::WezTermProvider;
+use crate::version::TerminalVersion;
 use crate::{
     errors::{RelaunchError, TermResult},
     terminal_providers::{ITerm2Provider, TERM_PROGRAM_VAR, WindowsTerminalProvider},
@@ -97,6 +208,7 @@ use crate::{
 
 /// Represents the different types of terminals we can identify.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum TerminalType {
     /// Unable to identify the terminal.
@@ -104,7 +216,7 @@ pub enum TerminalType {
 
     // Windows terminals..
     #[default]
-    /// Default `Windows` terminal (`cmd.exe` or `powershell.exe`).
+    /// Default `Windows` terminal (`cmd.exe`, `powershell.exe` or `pwsh.exe`), see `shell::host_shell()` for which.
     WindowsCMD,
     /// `Windows Terminal`. (terminal app from Microsoft Store `wt.exe`)
     WindowsTerminal,
@@ -118,16 +230,12 @@ pub enum TerminalType {
     Kitty,
     /// Third party `MacOS` terminal `Ghostty`.
     Ghostty,
-    /// Third party `MacOS` terminal (`iTerm2`, `Alacritty`, etc).
-    /// We just assume that if we are on `MacOS` and not in the default terminal,
-    /// then we are in a third party terminal.
-    ///
-    /// **TODO**: Improve detection for specific third party terminals.
+    /// Any other third party `MacOS` terminal.
+    /// We just assume that if we are on `MacOS` and neither in the default terminal nor a terminal identified
+    /// by `TERMINAL_IDENTIFIERS`, then we are in a third party terminal.
     ThirdPartyMacOSTerminal,
 
-    /// Default `Linux` terminal (`GNOME Terminal`, `Konsole`, etc).
-    ///
-    /// **TODO**: Improve detection for specific Linux terminals.
+    /// Any other `Linux` terminal, when none identified by `TERMINAL_IDENTIFIERS` is found.
     LinuxTerminal,
 
     // Cross platform editor terminals..
@@ -139,40 +247,193 @@ pub enum TerminalType {
     VSCode,
     /// `NVim` terminal (e.g. `nvim-qt`, `neovide`, etc).
     Nvim,
+
+    // Terminals added since are appended, keeping the discriminants and ordering of the others stable..
+    /// `KDE` terminal `Konsole`.
+    Konsole,
+    /// `GNOME` terminal `GNOME Terminal`.
+    GnomeTerminal,
+    /// Classic `X11` terminal `xterm`, which renders neither RGB colours nor emojis in its default build.
+    Xterm,
+    /// `Wayland` terminal `foot`.
+    Foot,
+    /// Tiling `VTE` based terminal `Tilix`.
+    Tilix,
+    /// Tiling `VTE` based terminal `Terminator`.
+    Terminator,
+    /// `XFCE` terminal `xfce4-terminal`.
+    XfceTerminal,
+    /// `rxvt-unicode` (`urxvt`), which renders neither RGB colours nor emojis.
+    Urxvt,
+    /// Suckless terminal `st`, which renders neither RGB colours nor emojis in its default build.
+    St,
+    /// `Electron` based terminal `Hyper`.
+    Hyper,
+    /// `Electron` based terminal `Tabby` (formerly `Terminus`).
+    Tabby,
+    /// A `Windows` host running the program without a terminal (e.g. the `PowerShell ISE`), see `host`.
+    NonTerminalHost,
+    /// `Contour`, a modern terminal with `Sixel` image support.
+    Contour,
+    /// `tmux`, when the outer terminal could not be identified, see `tmux::multiplexer()`.
+    Tmux,
+    /// `GNU Screen`, which renders neither RGB colours nor emojis unless specially configured.
+    GnuScreen,
+    /// `ConEmu` (including `Cmder`, which bundles it), which supports RGB colours when its ANSI processing is
+    /// enabled, see `capabilities::env_confirms_rgb_in()`.
+    ConEmu,
+    /// `mintty`, the terminal of `Git Bash`, `MSYS2` and `Cygwin`.
+    Mintty,
+    /// `GNOME` terminal `Ptyxis`, the default terminal of `GNOME` 47+ on `Fedora` and `Ubuntu`.
+    Ptyxis,
+    /// A terminal inside `Emacs` (e.g. `vterm`, `eat` or `term`), see `emacs::emacs_terminal()`.
+    Emacs,
+    /// `JetBrains` IDE embedded terminal (`JediTerm`), e.g. in `IntelliJ IDEA`, `CLion` or `RustRover`.
+    JetBrains,
+    /// `Zed` editor embedded terminal.
+    Zed,
+    /// `JupyterLab` or `Jupyter Notebook` web terminal (`xterm.js`), usually served from a remote machine.
+    Jupyter,
 }
 
 impl TerminalType {
     /// Returns the name of the terminal type.
     #[inline]
     #[must_use]
-    pub fn name(&self) -> &'static str {
+    pub const fn name(&self) -> &'static str {
         match self {
             Self::Unknown => "Unknown",
             Self::WindowsCMD => "Windows CMD",
             Self::WindowsTerminal => "Windows Terminal",
+            Self::NonTerminalHost => "Non-Terminal Host",
+            Self::ConEmu => "ConEmu",
+            Self::Mintty => "mintty",
             Self::MacOS => "MacOS Terminal",
             Self::ITerm2 => "iTerm2",
             Self::Kitty => "Kitty",
             Self::Ghostty => "Ghostty",
             Self::ThirdPartyMacOSTerminal => "Third Party MacOS Terminal",
             Self::LinuxTerminal => "Linux Terminal",
+            Self::Ptyxis => "Ptyxis",
+            Self::Konsole => "Konsole",
+            Self::GnomeTerminal => "GNOME Terminal",
+            Self::Tilix => "Tilix",
+            Self::Xterm => "xterm",
+            Self::Foot => "foot",
+            Self::Terminator => "Terminator",
+            Self::XfceTerminal => "Xfce Terminal",
+            Self::Urxvt => "rxvt-unicode",
+            Self::St => "st",
             Self::Alacritty => "Alacritty",
             Self::WezTerm => "WezTerm",
+            Self::Hyper => "Hyper",
+            Self::Tabby => "Tabby",
+            Self::Contour => "Contour",
             Self::VSCode => "VSCode Terminal",
+            Self::JetBrains => "JetBrains Terminal",
+            Self::Zed => "Zed Terminal",
             Self::Nvim => "NVIM Terminal",
+            Self::Emacs => "Emacs Terminal",
+            Self::Jupyter => "Jupyter Terminal",
+            Self::Tmux => "tmux",
+            Self::GnuScreen => "GNU Screen",
+        }
+    }
+
+    /// Returns the stable identifier of the terminal type, e.g. `"windows-terminal"`.
+    ///
+    /// # Notes
+    /// Unlike `name()`, identifiers never change between releases, so they are safe to store or compare against,
+    /// see `from_id()`.
+    #[inline]
+    #[must_use]
+    pub const fn id(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::WindowsCMD => "windows-cmd",
+            Self::WindowsTerminal => "windows-terminal",
+            Self::NonTerminalHost => "non-terminal-host",
+            Self::ConEmu => "conemu",
+            Self::Mintty => "mintty",
+            Self::MacOS => "macos-terminal",
+            Self::ITerm2 => "iterm2",
+            Self::Kitty => "kitty",
+            Self::Ghostty => "ghostty",
+            Self::ThirdPartyMacOSTerminal => "macos-third-party",
+            Self::LinuxTerminal => "linux-terminal",
+            Self::Ptyxis => "ptyxis",
+            Self::Konsole => "konsole",
+            Self::GnomeTerminal => "gnome-terminal",
+            Self::Tilix => "tilix",
+            Self::Xterm => "xterm",
+            Self::Foot => "foot",
+            Self::Terminator => "terminator",
+            Self::XfceTerminal => "xfce-terminal",
+            Self::Urxvt => "urxvt",
+            Self::St => "st",
+            Self::Alacritty => "alacritty",
+            Self::WezTerm => "wezterm",
+            Self::Hyper => "hyper",
+            Self::Tabby => "tabby",
+            Self::Contour => "contour",
+            Self::VSCode => "vscode",
+            Self::JetBrains => "jetbrains",
+            Self::Zed => "zed",
+            Self::Nvim => "nvim",
+            Self::Emacs => "emacs",
+            Self::Jupyter => "jupyter",
+            Self::Tmux => "tmux",
+            Self::GnuScreen => "gnu-screen",
         }
     }
 
+    /// Returns the terminal type with the given stable identifier, see `id()`.
+    #[must_use]
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::iter().find(|terminal_type| terminal_type.id() == id)
+    }
+
     /// Returns the executable name of the terminal type, if known.
     #[inline]
     #[must_use]
-    pub fn exec_name(&self) -> Option<&'static str> {
+    pub const fn exec_name(&self) -> Option<&'static str> {
         match self {
             Self::WindowsCMD => Some("cmd.exe"),
             Self::MacOS => Some("Terminal.app"),
             Self::WindowsTerminal => Some("wt.exe"),
+            Self::ConEmu => Some("ConEmu64.exe"),
+            Self::Mintty => Some("mintty.exe"),
             Self::VSCode => Some("Code.exe"),
+            Self::Emacs => Some("emacs"),
+            Self::Jupyter => Some("jupyter"),
             Self::ITerm2 => Some("iTerm2.app"),
+            Self::Ptyxis => Some("ptyxis"),
+            Self::Konsole => Some("konsole"),
+            Self::GnomeTerminal => Some("gnome-terminal"),
+            Self::Tilix => Some("tilix"),
+            Self::Xterm => Some("xterm"),
+            Self::Foot => Some("foot"),
+            Self::Terminator => Some("terminator"),
+            Self::XfceTerminal => Some("xfce4-terminal"),
+            Self::Urxvt => Some("urxvt"),
+            Self::St => Some("st"),
+            Self::Contour => Some("contour"),
+            Self::Tmux => Some("tmux"),
+            Self::GnuScreen => Some("screen"),
+            Self::Hyper => Some(if cfg!(target_os = "windows") {
+                "Hyper.exe"
+            } else if cfg!(target_os = "macos") {
+                "Hyper.app"
+            } else {
+                "hyper"
+            }),
+            Self::Tabby => Some(if cfg!(target_os = "windows") {
+                "Tabby.exe"
+            } else if cfg!(target_os = "macos") {
+                "Tabby.app"
+            } else {
+                "tabby"
+            }),
             _ => None,
         }
     }
@@ -180,18 +441,42 @@ impl TerminalType {
     /// Returns the target operating system this terminal runs on.
     #[inline]
     #[must_use]
-    pub fn target_os(&self) -> TargetOperatingSystem {
+    pub const fn target_os(&self) -> TargetOperatingSystem {
         match self {
-            Self::WindowsCMD | Self::WindowsTerminal => TargetOperatingSystem::Windows,
+            Self::WindowsCMD
+            | Self::WindowsTerminal
+            | Self::NonTerminalHost
+            | Self::ConEmu
+            | Self::Mintty => TargetOperatingSystem::Windows,
             Self::MacOS
             | Self::ITerm2
             | Self::Ghostty
             | Self::Kitty
             | Self::ThirdPartyMacOSTerminal => TargetOperatingSystem::MacOS,
-            Self::VSCode | Self::Nvim | Self::Alacritty | Self::WezTerm => {
-                TargetOperatingSystem::Any
-            }
-            Self::LinuxTerminal => TargetOperatingSystem::Linux,
+            Self::VSCode
+            | Self::JetBrains
+            | Self::Zed
+            | Self::Nvim
+            | Self::Emacs
+            | Self::Jupyter
+            | Self::Alacritty
+            | Self::WezTerm
+            | Self::Hyper
+            | Self::Tabby
+            | Self::Contour
+            | Self::Tmux
+            | Self::GnuScreen
+            | Self::Xterm => TargetOperatingSystem::Any,
+            Self::LinuxTerminal
+            | Self::Ptyxis
+            | Self::Konsole
+            | Self::GnomeTerminal
+            | Self::Tilix
+            | Self::Foot
+            | Self::Terminator
+            | Self::XfceTerminal
+            | Self::Urxvt
+            | Self::St => TargetOperatingSystem::Linux,
             Self::Unknown => TargetOperatingSystem::Invalid,
         }
     }
@@ -199,70 +484,327 @@ impl TerminalType {
     /// Returns `true` if the terminal supports RGB (ANSI) colours.
     #[inline]
     #[must_use]
-    pub fn supports_rgb_ansi_colours(&self) -> bool {
+    pub const fn supports_rgb_ansi_colours(&self) -> bool {
         match self {
-            Self::Unknown | Self::MacOS => false,
+            Self::Unknown
+            | Self::NonTerminalHost
+            | Self::MacOS
+            | Self::Xterm
+            | Self::Urxvt
+            | Self::St
+            | Self::Emacs
+            | Self::Tmux
+            | Self::GnuScreen
+            | Self::ConEmu => false,
             Self::WindowsCMD
             | Self::WindowsTerminal
+            | Self::Mintty
             | Self::VSCode
+            | Self::JetBrains
+            | Self::Zed
             | Self::Nvim
             | Self::ITerm2
             | Self::ThirdPartyMacOSTerminal
             | Self::Alacritty
             | Self::WezTerm
+            | Self::Hyper
+            | Self::Tabby
+            | Self::Contour
             | Self::Kitty
             | Self::Ghostty
-            | Self::LinuxTerminal => true,
+            | Self::LinuxTerminal
+            | Self::Ptyxis
+            | Self::Konsole
+            | Self::GnomeTerminal
+            | Self::Tilix
+            | Self::Foot
+            | Self::Terminator
+            | Self::XfceTerminal
+            | Self::Jupyter => true,
         }
     }
 
     /// Returns `true` if the terminal supports full unicode rendering (e.g. emojis, etc.).
+    ///
+    /// # Notes
+    /// Modern versions of `Terminal.app` (`TerminalType::MacOS`) do, which this can't tell without the version, see
+    /// `capabilities::resolve_full_unicode_support()`.
     #[inline]
     #[must_use]
-    pub fn supports_full_unicode(&self) -> bool {
+    pub const fn supports_full_unicode(&self) -> bool {
         match self {
-            Self::Unknown | Self::WindowsCMD | Self::MacOS => false,
+            Self::Unknown
+            | Self::WindowsCMD
+            | Self::NonTerminalHost
+            | Self::MacOS
+            | Self::Xterm
+            | Self::Urxvt
+            | Self::St
+            | Self::Jupyter
+            | Self::GnuScreen => false,
             Self::WindowsTerminal
+            | Self::Mintty
             | Self::VSCode
+            | Self::JetBrains
+            | Self::Zed
             | Self::Nvim
             | Self::ITerm2
             | Self::ThirdPartyMacOSTerminal
             | Self::Alacritty
             | Self::WezTerm
+            | Self::Hyper
+            | Self::Tabby
+            | Self::Contour
+            | Self::Kitty
+            | Self::Ghostty
+            | Self::LinuxTerminal
+            | Self::Ptyxis
+            | Self::Konsole
+            | Self::GnomeTerminal
+            | Self::Tilix
+            | Self::Foot
+            | Self::Terminator
+            | Self::XfceTerminal
+            | Self::Emacs
+            | Self::Tmux
+            | Self::ConEmu => true,
+        }
+    }
+
+    /// Returns `true` if the terminal supports `OSC 8` hyperlinks.
+    #[inline]
+    #[must_use]
+    pub const fn supports_hyperlinks(&self) -> bool {
+        match self {
+            Self::Unknown
+            | Self::WindowsCMD
+            | Self::NonTerminalHost
+            | Self::MacOS
+            | Self::JetBrains
+            | Self::Zed
+            | Self::Nvim
+            | Self::Emacs
+            | Self::Jupyter
+            | Self::ThirdPartyMacOSTerminal
+            | Self::LinuxTerminal
+            | Self::Xterm
+            | Self::Urxvt
+            | Self::St
+            | Self::Tmux
+            | Self::GnuScreen
+            | Self::ConEmu => false,
+            Self::WindowsTerminal
+            | Self::Mintty
+            | Self::VSCode
+            | Self::ITerm2
+            | Self::Alacritty
+            | Self::WezTerm
+            | Self::Hyper
+            | Self::Tabby
+            | Self::Contour
+            | Self::Kitty
+            | Self::Ghostty
+            | Self::Ptyxis
+            | Self::Konsole
+            | Self::GnomeTerminal
+            | Self::Tilix
+            | Self::Foot
+            | Self::Terminator
+            | Self::XfceTerminal => true,
+        }
+    }
+
+    /// Returns `true` if the terminal displays inline images out of the box, through `Sixel`, the `Kitty`
+    /// graphics protocol or `iTerm2` inline images.
+    ///
+    /// # Notes
+    /// Terminals needing a setting or build option for images (e.g. `VSCode` or `xterm`) are reported as `false`.
+    #[inline]
+    #[must_use]
+    pub const fn supports_images(&self) -> bool {
+        match self {
+            Self::WindowsTerminal
+            | Self::Mintty
+            | Self::ITerm2
+            | Self::WezTerm
+            | Self::Contour
             | Self::Kitty
             | Self::Ghostty
-            | Self::LinuxTerminal => true,
+            | Self::Konsole
+            | Self::Foot => true,
+            Self::Unknown
+            | Self::WindowsCMD
+            | Self::NonTerminalHost
+            | Self::ConEmu
+            | Self::MacOS
+            | Self::VSCode
+            | Self::JetBrains
+            | Self::Zed
+            | Self::Nvim
+            | Self::Emacs
+            | Self::Jupyter
+            | Self::ThirdPartyMacOSTerminal
+            | Self::Alacritty
+            | Self::Hyper
+            | Self::Tabby
+            | Self::LinuxTerminal
+            | Self::Ptyxis
+            | Self::GnomeTerminal
+            | Self::Tilix
+            | Self::Terminator
+            | Self::XfceTerminal
+            | Self::Xterm
+            | Self::Urxvt
+            | Self::St
+            | Self::Tmux
+            | Self::GnuScreen => false,
+        }
+    }
+
+    /// Returns the capability score of the terminal, used to rank terminals by capability richness.
+    ///
+    /// Scores are an explicit table, not derived from the capability methods, and are stable across releases
+    /// unless documented otherwise. The weighting is:
+    /// *   `+20` for RGB (ANSI) colour support.
+    /// *   `+20` for full unicode rendering support.
+    /// *   `+0..=9` for protocol extras (e.g. hyperlinks, image protocols), used as tiebreakers.
+    #[inline]
+    #[must_use]
+    pub const fn capability_score(&self) -> u8 {
+        match self {
+            Self::Unknown
+            | Self::NonTerminalHost
+            | Self::MacOS
+            | Self::Xterm
+            | Self::Urxvt
+            | Self::St
+            | Self::GnuScreen => 0,
+            Self::WindowsCMD | Self::Emacs | Self::Jupyter | Self::Tmux => 20,
+            Self::ConEmu => 21,
+            Self::LinuxTerminal => 40,
+            Self::JetBrains | Self::Zed | Self::Nvim | Self::ThirdPartyMacOSTerminal => 41,
+            Self::VSCode | Self::Hyper | Self::Tabby | Self::Mintty => 43,
+            Self::Alacritty
+            | Self::Ptyxis
+            | Self::Konsole
+            | Self::GnomeTerminal
+            | Self::Tilix
+            | Self::Terminator
+            | Self::XfceTerminal => 44,
+            Self::WindowsTerminal => 45,
+            Self::ITerm2 | Self::WezTerm | Self::Kitty | Self::Contour => 48,
+            Self::Foot => 46,
+            Self::Ghostty => 49,
         }
     }
 
     /// Returns `true` if the terminal is a preferred terminal type (i.e. supports all features).
     #[inline]
     #[must_use]
-    pub fn is_preferred(&self) -> bool {
+    pub const fn is_preferred(&self) -> bool {
         self.supports_full_unicode() && self.supports_rgb_ansi_colours()
     }
 
     /// Returns a verbose formatted string of the terminal type and supported features.
+    ///
+    /// # Notes
+    /// This is the plain text of `format_segments()`, listing the intrinsic capabilities of the terminal type, so
+    /// it's unaffected by overrides. Use `info::TerminalInfo::effective_format()` to describe what output will use.
     #[inline]
     #[must_use]
     pub fn verbose_format(&self) -> String {
-        let unicode = if self.supports_full_unicode() {
-            ", Full Unicode"
-        } else {
-            ""
-        };
-        let rgb = if self.supports_rgb_ansi_colours() {
-            ", Enhanced Colours"
-        } else {
-            ""
-        };
-        let exec_name = if let Some(exec_name) = self.exec_name() {
-            format!(" ({exec_name})")
-        } else {
-            String::new()
-        };
+        self.format_segments()
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect()
+    }
+
+    /// Returns the terminal type and supported features as segments, so UIs can style each piece,
+    /// e.g. `Windows Terminal (wt), Full Unicode, Enhanced Colours`.
+    #[inline]
+    #[must_use]
+    pub fn format_segments(&self) -> Vec<output::InfoSegment> {
+        self.segments_with(
+            None,
+            self.supports_full_unicode(),
+            self.supports_rgb_ansi_colours(),
+            false,
+        )
+    }
+
+    /// Returns the terminal type and every feature as segments, as `format_segments()`, but listing the features
+    /// the terminal lacks as `SegmentKind::CapabilityMissing`, e.g. for UIs colouring each feature by support.
+    ///
+    /// # Example
+    /// ```rust
+    /// use terminal_relaunch::TerminalType;
+    /// use terminal_relaunch::output::SegmentKind;
+    ///
+    /// let kinds: Vec<SegmentKind> = TerminalType::Tmux
+    ///     .status_segments()
+    ///     .into_iter()
+    ///     .filter(|segment| segment.kind != SegmentKind::Separator)
+    ///     .map(|segment| segment.kind)
+    ///     .collect();
+    /// assert_eq!(kinds, [
+    ///     SegmentKind::Name,
+    ///     SegmentKind::ExecName,
+    ///     SegmentKind::CapabilityPresent,
+    ///     SegmentKind::CapabilityMissing,
+    /// ]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn status_segments(&self) -> Vec<output::InfoSegment> {
+        self.segments_with(
+            None,
+            self.supports_full_unicode(),
+            self.supports_rgb_ansi_colours(),
+            true,
+        )
+    }
+
+    /// Returns the terminal type, its version and the given capabilities as segments, see `format_segments()`.
+    ///
+    /// # Notes
+    /// The capabilities the terminal lacks are only listed with `include_missing`.
+    pub(crate) fn segments_with(
+        self,
+        version: Option<&TerminalVersion>,
+        full_unicode: bool,
+        rgb_ansi_colours: bool,
+        include_missing: bool,
+    ) -> Vec<output::InfoSegment> {
+        use output::{InfoSegment, SegmentKind};
+
+        let mut segments = vec![InfoSegment::new(self.name(), SegmentKind::Name)];
+        if let Some(version) = version {
+            segments.push(InfoSegment::new(" ", SegmentKind::Separator));
+            segments.push(InfoSegment::new(version.to_string(), SegmentKind::Version));
+        }
+        if let Some(exec_name) = self.exec_name() {
+            segments.push(InfoSegment::new(" (", SegmentKind::Separator));
+            segments.push(InfoSegment::new(exec_name, SegmentKind::ExecName));
+            segments.push(InfoSegment::new(")", SegmentKind::Separator));
+        }
+
+        let capabilities = [
+            (full_unicode, "Full Unicode"),
+            (rgb_ansi_colours, "Enhanced Colours"),
+        ];
+        for (supported, capability) in capabilities {
+            let kind = if supported {
+                SegmentKind::CapabilityPresent
+            } else if include_missing {
+                SegmentKind::CapabilityMissing
+            } else {
+                continue;
+            };
+            segments.push(InfoSegment::new(", ", SegmentKind::Separator));
+            segments.push(InfoSegment::new(capability, kind));
+        }
 
-        format!("{}{}{}{}", self.name(), exec_name, unicode, rgb)
+        segments
     }
 }
 
@@ -304,6 +846,17 @@ impl OperatingSystem {
         }
     }
 
+    /// Returns `true` if this is `Linux` running inside `WSL`, see `environment::is_wsl()`.
+    ///
+    /// # Notes
+    /// `Linux` terminals are rarely installed inside `WSL`, so relaunching usually needs a terminal on the
+    /// `Windows` host, see `RelaunchError::WslHostTerminalRequired`.
+    #[inline]
+    #[must_use]
+    pub fn is_wsl(&self) -> bool {
+        *self == Self::Linux && environment::is_wsl()
+    }
+
     /// Returns the name of the operating system.
     /// # Example
     /// * `Self::Windows` => `"Windows"`
@@ -338,6 +891,65 @@ impl OperatingSystem {
     }
 }
 
+/// Returns `true` if the current process is an `x86_64` build running translated under Rosetta on `MacOS`.
+#[must_use]
+pub fn is_rosetta() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("/usr/sbin/sysctl")
+            .args(["-in", "sysctl.proc_translated"])
+            .output()
+            .is_ok_and(|output| output.stdout.trim_ascii() == b"1")
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        false
+    }
+}
+
+/// Minimum `Windows 10` build (1511) where the console host supports virtual terminal (VT) sequences.
+pub const MIN_WINDOWS_VT_BUILD: u32 = 10586;
+/// Minimum `Windows 10` build (1903) that `Windows Terminal` can be installed on.
+pub const MIN_WINDOWS_TERMINAL_BUILD: u32 = 18362;
+
+/// Returns the build number of the running `Windows` version (e.g. `19045`), read from the registry.
+///
+/// # Returns
+/// *   `Some(build)` when running on `Windows` and the build number could be read.
+/// *   `None` otherwise.
+#[must_use]
+pub fn windows_build() -> Option<u32> {
+    #[cfg(target_os = "windows")]
+    {
+        static WINDOWS_BUILD: LazyLock<Option<u32>> = LazyLock::new(|| {
+            use winreg::RegKey;
+            use winreg::enums::HKEY_LOCAL_MACHINE;
+
+            let current_version = RegKey::predef(HKEY_LOCAL_MACHINE)
+                .open_subkey(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion")
+                .ok()?;
+            let build: String = current_version.get_value("CurrentBuildNumber").ok()?;
+            build.trim().parse().ok()
+        });
+
+        *WINDOWS_BUILD
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+/// Returns `true` if running on a `Windows` build too old for the console host to support
+/// virtual terminal (VT) sequences, e.g. `Windows 8.1`.
+#[inline]
+#[must_use]
+pub fn is_legacy_windows() -> bool {
+    windows_build().is_some_and(|build| build < MIN_WINDOWS_VT_BUILD)
+}
+
 /// Represents a target operating system for a terminal signature.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
@@ -381,30 +993,191 @@ impl Display for TargetOperatingSystem {
 /// Represents a kind of 'signature' that can be used to identify which terminal we are running in.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TerminalSignature {
-    /// An environment variable that must exist.
+    /// An environment variable that must exist, with any value, including values that aren't valid unicode.
     EnvVarExists(&'static str),
     /// An environment variable that must exist, and have a specific value.
+    ///
+    /// Compared byte-wise, ignoring ASCII case, so a value that isn't valid unicode never matches. One pair of
+    /// matching surrounding quotes is ignored (see `environment::strip_env_quotes()`), as left by `direnv` or
+    /// `dotenv` files quoting a value twice, e.g. `TERM_PROGRAM='"vscode"'`.
     EnvVar(&'static str, &'static str),
-    /// The environment variable `TERM_PROGRAM` must have a specific value.
+    /// The environment variable `TERM_PROGRAM` must have a specific value, compared as `EnvVar`, but resilient to
+    /// values exported by misbehaving profile scripts or wrappers.
+    ///
+    /// # Notes
+    /// *   Surrounding whitespace is ignored, as is one pair of matching surrounding quotes.
+    /// *   A value that looks like a path is compared by its final component, or its enclosing `.app` bundle,
+    ///     ignoring a `.app` or `.exe` suffix on either side.
+    /// *   Use `TermProgramExact` for identifiers that need the exact value, whose quotes aren't stripped.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use terminal_relaunch::TerminalSignature;
+    ///
+    /// let matches = |signature: TerminalSignature, value: &str| {
+    ///     signature.check_in(&HashMap::from([("TERM_PROGRAM".to_string(), value.to_string())]))
+    /// };
+    ///
+    /// // Values collected from user reports..
+    /// for (expected, value) in [
+    ///     ("Apple_Terminal", "Apple_Terminal "),
+    ///     ("Apple_Terminal", "\tapple_terminal\n"),
+    ///     ("vscode", "  vscode"),
+    ///     ("vscode", "\"vscode\""),
+    ///     ("WezTerm", "'WezTerm' "),
+    ///     ("ghostty", "\"/Applications/Ghostty.app\""),
+    ///     ("iTerm.app", "/Applications/iTerm.app/Contents/MacOS/iTerm2"),
+    ///     ("ghostty", "/Applications/Ghostty.app/Contents/MacOS/ghostty"),
+    ///     ("ghostty", "/Applications/Ghostty.app"),
+    ///     ("ghostty", "/usr/bin/ghostty "),
+    ///     ("WezTerm", r"C:\Program Files\WezTerm\wezterm.exe"),
+    ///     ("WezTerm", "/Applications/WezTerm.app/Contents/MacOS/wezterm-gui"),
+    ///     ("Tabby", r"C:\Users\me\AppData\Local\Programs\Tabby\Tabby.exe"),
+    /// ] {
+    ///     assert!(matches(TerminalSignature::TermProgram(expected), value), "{value:?}");
+    ///     assert!(!matches(TerminalSignature::TermProgramExact(expected), value), "{value:?}");
+    /// }
+    ///
+    /// // Other programs, and directories named after the terminal, don't match..
+    /// for (expected, value) in [
+    ///     ("vscode", "vscode-insiders"),
+    ///     ("vscode", "/usr/share/vscode/bin/code"),
+    ///     ("ghostty", "/opt/ghostty-tools/bin/launcher"),
+    ///     ("Tabby", "Tabby Terminal"),
+    ///     ("Apple_Terminal", ""),
+    ///     ("vscode", "\"vscode'"),
+    ///     ("vscode", "\"\"vscode\"\""),
+    /// ] {
+    ///     assert!(!matches(TerminalSignature::TermProgram(expected), value), "{value:?}");
+    /// }
+    ///
+    /// assert!(matches(TerminalSignature::TermProgramExact("Apple_Terminal"), "apple_terminal"));
+    /// assert!(!matches(TerminalSignature::TermProgramExact("Apple_Terminal"), "\"Apple_Terminal\""));
+    /// ```
     TermProgram(&'static str),
-    /// The environment variable `TERM` must have a specific value.
+    /// The environment variable `TERM_PROGRAM` must have a specific value, compared byte-wise, ignoring ASCII case
+    /// only, i.e. quotes aren't stripped as for `EnvVar`.
+    TermProgramExact(&'static str),
+    /// The environment variable `TERM` must have a specific value, compared as `EnvVar`.
     TermVar(&'static str),
-    /// Returns `true` if the windows console delegation is set to a value in the windows registry.
+    /// The environment variable `TERM` must start with a specific value, e.g. `foot` also matching `foot-extra`.
+    ///
+    /// Compared byte-wise, ignoring ASCII case and quotes as `EnvVar`, so only the prefix has to be valid unicode.
+    TermVarPrefix(&'static str),
+    /// An ancestor of the current process must have a specific executable name, see `remote::process_ancestry()`.
+    AncestorProcess(&'static str),
+    /// Returns `true` if the windows console delegation selects `Windows Terminal` in the windows registry, see
+    /// `delegation_selects_windows_terminal()`.
+    ///
+    /// Never met inside a `POSIX` layer (see `environment::posix_layer_in()`), whose shells don't run in the
+    /// delegated terminal.
     WindowsConsoleDelegationSet,
 
     /// Returns `true` if any of the given terminal signatures are met (I.e. `OR` logic).
     Any(&'static [TerminalSignature]),
 }
 
-/// Checks if Windows console delegation is set in the registry.
-/// If this is _NOT_ set, it means the default console host is being used (cmd)
-/// otherwise, it is being delegated to another terminal (e.g. Windows Terminal).
+impl Display for TerminalSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EnvVarExists(var) => write!(f, "{var} set"),
+            Self::EnvVar(var, value) => write!(f, "{var}={value}"),
+            Self::TermProgram(value) => write!(f, "{TERM_PROGRAM_VAR}={value}"),
+            Self::TermProgramExact(value) => write!(f, "{TERM_PROGRAM_VAR}={value} (exact)"),
+            Self::TermVar(value) => write!(f, "{TERM_VAR}={value}"),
+            Self::TermVarPrefix(prefix) => write!(f, "{TERM_VAR}={prefix}*"),
+            Self::AncestorProcess(name) => write!(f, "{name} ancestor"),
+            Self::WindowsConsoleDelegationSet => write!(f, "console delegation"),
+            Self::Any(sigs) => {
+                for (index, sig) in sigs.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " or ")?;
+                    }
+                    write!(f, "{sig}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Delegation GUIDs of the default terminal application setting on `Windows 11`, and the terminal they select.
+///
+/// # Notes
+/// The "Let Windows decide" GUID (all zeroes) is not included, as it doesn't identify a terminal.
+pub const WINDOWS_DELEGATION_TERMINALS: &[(&str, TerminalType)] = &[
+    (
+        "{B23D10C0-E52E-411E-9D5B-C09FDF709C7D}",
+        TerminalType::WindowsCMD,
+    ),
+    // Windows Terminal..
+    (
+        "{E12CFF52-A866-4C77-9A90-F570A7AA2C6B}",
+        TerminalType::WindowsTerminal,
+    ),
+    // Windows Terminal Preview..
+    (
+        "{86633F1F-6454-40EC-89CE-DA4EBA977EE2}",
+        TerminalType::WindowsTerminal,
+    ),
+];
+
+/// The "Let Windows decide" delegation GUID, which doesn't select a terminal, see `WINDOWS_DELEGATION_TERMINALS`.
+pub const WINDOWS_DELEGATION_LET_WINDOWS_DECIDE: &str = "{00000000-0000-0000-0000-000000000000}";
+
+/// Returns the terminal selected by the given `DelegationTerminal` GUID, if known.
 #[inline]
 #[must_use]
-fn check_for_windows_registry_delegation() -> bool {
+pub fn terminal_for_delegation_guid(guid: &str) -> Option<TerminalType> {
+    WINDOWS_DELEGATION_TERMINALS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(guid.trim()))
+        .map(|(_, terminal)| *terminal)
+}
+
+/// Returns `true` if the given `DelegationTerminal` GUID selects `Windows Terminal`, see
+/// `WINDOWS_DELEGATION_TERMINALS`.
+///
+/// # Notes
+/// The `DelegationConsole` GUID isn't considered, as some `Windows` updates roll the default terminal back to the
+/// console host without resetting it, leaving the two GUIDs different while neither selects `Windows Terminal`.
+/// The console host, "Let Windows decide" and unknown GUIDs don't select `Windows Terminal`, so detection falls back
+/// to `WT_SESSION`.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::delegation_selects_windows_terminal;
+///
+/// // Windows Terminal and Windows Terminal Preview..
+/// assert!(delegation_selects_windows_terminal("{E12CFF52-A866-4C77-9A90-F570A7AA2C6B}"));
+/// assert!(delegation_selects_windows_terminal("{86633f1f-6454-40ec-89ce-da4eba977ee2}"));
+///
+/// // ..but not the console host, "Let Windows decide" or anything unknown..
+/// assert!(!delegation_selects_windows_terminal("{B23D10C0-E52E-411E-9D5B-C09FDF709C7D}"));
+/// assert!(!delegation_selects_windows_terminal("{00000000-0000-0000-0000-000000000000}"));
+/// assert!(!delegation_selects_windows_terminal("{2EACA947-7F5F-4CFA-BA87-8F7FBEEFBE69}"));
+/// assert!(!delegation_selects_windows_terminal(""));
+///
+/// // ..including after a rollback to the console host, where `DelegationConsole` still differs..
+/// let (console, terminal) = ("{2EACA947-7F5F-4CFA-BA87-8F7FBEEFBE69}", "{B23D10C0-E52E-411E-9D5B-C09FDF709C7D}");
+/// assert_ne!(console, terminal);
+/// assert!(!delegation_selects_windows_terminal(terminal));
+/// ```
+#[inline]
+#[must_use]
+pub fn delegation_selects_windows_terminal(delegation_terminal: &str) -> bool {
+    terminal_for_delegation_guid(delegation_terminal) == Some(TerminalType::WindowsTerminal)
+}
+
+/// Reads the `DelegationConsole` and `DelegationTerminal` GUIDs from the registry, if set.
+#[inline]
+#[must_use]
+fn read_windows_delegation() -> Option<(String, String)> {
     #[cfg(not(target_os = "windows"))]
     {
-        return false;
+        None
     }
 
     #[cfg(target_os = "windows")]
@@ -412,26 +1185,144 @@ fn check_for_windows_registry_delegation() -> bool {
         use winreg::RegKey;
         use winreg::enums::HKEY_CURRENT_USER;
 
-        let Ok(console) = RegKey::predef(HKEY_CURRENT_USER).open_subkey("Console") else {
-            return false;
-        };
-        let Ok(startup) = console.open_subkey("%%Startup") else {
-            return false;
-        };
+        let console = RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey("Console")
+            .ok()?;
+        let startup = console.open_subkey("%%Startup").ok()?;
 
-        let Ok(delegation_console): Result<String, _> =
-            startup.get_value::<String, _>("DelegationConsole")
-        else {
-            return false;
-        };
-        let Ok(delegation_terminal): Result<String, _> =
-            startup.get_value::<String, _>("DelegationTerminal")
-        else {
-            return false;
-        };
+        let delegation_console = startup.get_value::<String, _>("DelegationConsole").ok()?;
+        let delegation_terminal = startup.get_value::<String, _>("DelegationTerminal").ok()?;
+
+        Some((delegation_console, delegation_terminal))
+    }
+}
+
+/// Returns `true` if console delegation selects `Windows Terminal`, as read by the given registry check, which is
+/// never called inside a `POSIX` layer (see `environment::posix_layer_in()`), whose shells don't run in the console
+/// the delegation applies to.
+fn console_delegation_set_in(
+    env: &(impl Environment + ?Sized),
+    registry_delegation: impl FnOnce() -> bool,
+) -> bool {
+    environment::posix_layer_in(env).is_none() && registry_delegation()
+}
+
+/// Checks if Windows console delegation selects `Windows Terminal` in the registry.
+/// If this is _NOT_ set, or set to the console host, the default console host is being used (cmd)
+/// otherwise, it is being delegated to Windows Terminal, see `delegation_selects_windows_terminal()`.
+#[inline]
+#[must_use]
+fn check_for_windows_registry_delegation() -> bool {
+    registry_delegation_selects_windows_terminal(read_windows_delegation())
+}
+
+/// Returns `true` if the given `DelegationConsole` and `DelegationTerminal` GUIDs, as read by
+/// `read_windows_delegation()`, select `Windows Terminal`.
+fn registry_delegation_selects_windows_terminal(delegation: Option<(String, String)>) -> bool {
+    delegation.is_some_and(|(delegation_console, delegation_terminal)| {
+        let selected = delegation_selects_windows_terminal(&delegation_terminal);
+        if !selected && delegation_console != delegation_terminal {
+            logging::info!(
+                "Console delegation is set ({}, {}) but doesn't select Windows Terminal.",
+                logging::sanitize_for_log(&delegation_console),
+                logging::sanitize_for_log(&delegation_terminal)
+            );
+        }
+        selected
+    })
+}
+
+/// Returns the terminal the platform is configured to open console programs in by default, if known
+/// and installed.
+///
+/// # Notes
+/// Only `Windows 11` has a default terminal application setting, this returns `None` on other platforms,
+/// or when set to "Let Windows decide".
+#[must_use]
+pub fn platform_default_terminal() -> Option<TerminalType> {
+    let (_, delegation_terminal) = read_windows_delegation()?;
+
+    default_terminal_for_delegation(&delegation_terminal, |terminal| {
+        get_provider_for_terminal(terminal).is_some_and(|provider| provider.is_installed())
+    })
+}
+
+/// Returns the terminal selected by the given `DelegationTerminal` GUID, if known and installed, the console
+/// host always being installed.
+fn default_terminal_for_delegation(
+    delegation_terminal: &str,
+    is_installed: impl Fn(TerminalType) -> bool,
+) -> Option<TerminalType> {
+    let terminal = terminal_for_delegation_guid(delegation_terminal)?;
 
-        delegation_console != delegation_terminal
+    (terminal == TerminalType::WindowsCMD || is_installed(terminal)).then_some(terminal)
+}
+
+/// Suffixes of application bundles and executables, ignored when comparing `TERM_PROGRAM` paths.
+const PROGRAM_SUFFIXES: [&[u8]; 2] = [b".app", b".exe"];
+
+/// Returns `true` if a `TERM_PROGRAM` value matches the expected value, see `TerminalSignature::TermProgram`.
+fn term_program_matches(value: &[u8], expected: &[u8]) -> bool {
+    let value = value.trim_ascii();
+    if value.eq_ignore_ascii_case(expected) {
+        return true;
+    }
+    if !value.contains(&b'/') && !value.contains(&b'\\') {
+        return false;
+    }
+
+    let expected = strip_program_suffix(expected);
+    let components: Vec<_> = value
+        .split(|byte| matches!(byte, b'/' | b'\\'))
+        .filter(|component| !component.is_empty())
+        .collect();
+
+    components.iter().enumerate().any(|(index, component)| {
+        let stripped = strip_program_suffix(component);
+        let candidate = index + 1 == components.len()
+            || component[stripped.len()..].eq_ignore_ascii_case(b".app");
+
+        candidate && stripped.eq_ignore_ascii_case(expected)
+    })
+}
+
+/// Returns `true` if the value of an environment variable matches, as set or after stripping its quotes (see
+/// `environment::strip_env_quotes()`), logging values only matching once stripped so the environment can be fixed.
+fn unquoted_var_matches(
+    env: &(impl Environment + ?Sized),
+    var: &str,
+    matches: impl Fn(&[u8]) -> bool,
+) -> bool {
+    let Some(value) = env.var_os(var) else {
+        return false;
+    };
+    let value = value.as_encoded_bytes();
+    if matches(value) {
+        return true;
+    }
+
+    let stripped = environment::strip_env_quotes(value);
+    if stripped.len() == value.len() || !matches(stripped) {
+        return false;
     }
+    logging::warning!(
+        "`{var}` is set to a quoted value ({}), matched without its quotes, check the files exporting it (e.g. `.envrc` or `.env`).",
+        logging::sanitize_for_log(&String::from_utf8_lossy(value))
+    );
+    true
+}
+
+/// Strips a `.app` or `.exe` suffix from a program name, ignoring ASCII case.
+fn strip_program_suffix(name: &[u8]) -> &[u8] {
+    PROGRAM_SUFFIXES
+        .iter()
+        .find_map(|suffix| {
+            let split = name.len().checked_sub(suffix.len())?;
+            name[split..]
+                .eq_ignore_ascii_case(suffix)
+                .then_some(&name[..split])
+        })
+        .unwrap_or(name)
 }
 
 impl TerminalSignature {
@@ -439,22 +1330,93 @@ impl TerminalSignature {
     #[inline]
     #[must_use]
     pub fn check(&self) -> bool {
+        self.check_in(&SystemEnvironment)
+    }
+
+    /// Checks if the terminal signature is met in the given environment.
+    ///
+    /// # Notes
+    /// Only signatures for which `accesses_system()` returns `true` read anything other than the
+    /// given environment, e.g. the Windows registry.
+    #[inline]
+    #[must_use]
+    pub fn check_in(&self, env: &(impl Environment + ?Sized)) -> bool {
         match self {
-            Self::EnvVarExists(var_name) => std::env::var(var_name).is_ok(),
+            Self::EnvVarExists(var_name) => env.var_os(var_name).is_some(),
             Self::EnvVar(var, value) => {
-                matches!(std::env::var(var).ok().as_deref(),
-                    Some(v) if v.eq_ignore_ascii_case(value))
-            }
-            Self::TermProgram(var_value) => {
-                matches!(std::env::var(TERM_PROGRAM_VAR).ok().as_deref(),
-                    Some(v) if v.eq_ignore_ascii_case(var_value))
+                unquoted_var_matches(env, var, |v| v.eq_ignore_ascii_case(value.as_bytes()))
             }
-            Self::TermVar(var_value) => {
-                matches!(std::env::var(TERM_VAR).ok().as_deref(),
-                    Some(v) if v.eq_ignore_ascii_case(var_value))
+            Self::TermProgram(value) => unquoted_var_matches(env, TERM_PROGRAM_VAR, |v| {
+                term_program_matches(v, value.as_bytes())
+            }),
+            Self::TermProgramExact(value) => env
+                .var_os(TERM_PROGRAM_VAR)
+                .is_some_and(|v| v.as_encoded_bytes().eq_ignore_ascii_case(value.as_bytes())),
+            Self::TermVar(value) => Self::EnvVar(TERM_VAR, value).check_in(env),
+            Self::TermVarPrefix(prefix) => unquoted_var_matches(env, TERM_VAR, |v| {
+                v.get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
+            }),
+            Self::AncestorProcess(name) => remote::process_ancestry()
+                .iter()
+                .any(|process| process.name == *name),
+            Self::WindowsConsoleDelegationSet => {
+                console_delegation_set_in(env, check_for_windows_registry_delegation)
             }
-            Self::WindowsConsoleDelegationSet => check_for_windows_registry_delegation(),
-            Self::Any(sigs) => sigs.iter().any(TerminalSignature::check),
+            Self::Any(sigs) => sigs.iter().any(|sig| sig.check_in(env)),
+        }
+    }
+
+    /// Checks if the terminal signature is met in the given environment, without reading from the system.
+    ///
+    /// # Notes
+    /// Signatures for which `accesses_system()` returns `true` are treated as unmet, so an `Any` signature is only
+    /// met by its signatures reading the environment, e.g. `WT_SESSION` for `Windows Terminal`.
+    #[must_use]
+    pub fn check_env_only_in(&self, env: &(impl Environment + ?Sized)) -> bool {
+        match self {
+            Self::AncestorProcess(_) | Self::WindowsConsoleDelegationSet => false,
+            Self::Any(sigs) => sigs.iter().any(|sig| sig.check_env_only_in(env)),
+            _ => self.check_in(env),
+        }
+    }
+
+    /// Returns `true` if checking this signature reads from the system beyond the environment
+    /// (e.g. the Windows registry or process ancestry), rather than being a pure function of the environment.
+    #[inline]
+    #[must_use]
+    pub fn accesses_system(&self) -> bool {
+        match self {
+            Self::AncestorProcess(_) | Self::WindowsConsoleDelegationSet => true,
+            Self::Any(sigs) => sigs.iter().any(TerminalSignature::accesses_system),
+            Self::EnvVarExists(_)
+            | Self::EnvVar(..)
+            | Self::TermProgram(_)
+            | Self::TermProgramExact(_)
+            | Self::TermVar(_)
+            | Self::TermVarPrefix(_) => false,
+        }
+    }
+
+    /// Returns `true` if this signature can be met on the given operating system.
+    ///
+    /// # Notes
+    /// Inapplicable signatures always evaluate to `false`, so should be reported as "not applicable"
+    /// rather than as failed checks, e.g. `WindowsConsoleDelegationSet` on anything but `Windows`.
+    /// An `Any` signature is applicable if any of its signatures are.
+    #[inline]
+    #[must_use]
+    pub fn applicable_on(&self, os: OperatingSystem) -> bool {
+        match self {
+            Self::WindowsConsoleDelegationSet => os == OperatingSystem::Windows,
+            Self::Any(sigs) => sigs.iter().any(|sig| sig.applicable_on(os)),
+            Self::AncestorProcess(_)
+            | Self::EnvVarExists(_)
+            | Self::EnvVar(..)
+            | Self::TermProgram(_)
+            | Self::TermProgramExact(_)
+            | Self::TermVar(_)
+            | Self::TermVarPrefix(_) => true,
         }
     }
 }
@@ -471,6 +1433,35 @@ pub struct TerminalIdentifier {
     pub signatures: &'static [TerminalSignature],
 }
 
+impl TerminalIdentifier {
+    /// Returns `true` if this identifier can match on the given operating system, i.e. it targets the
+    /// operating system and all of its signatures are applicable on it.
+    #[inline]
+    #[must_use]
+    pub fn applicable_on(&self, os: OperatingSystem) -> bool {
+        os.compatible_with_target(self.target_os)
+            && self.signatures.iter().all(|sig| sig.applicable_on(os))
+    }
+
+    /// Returns `true` if all of this identifier's signatures are met in the given environment.
+    ///
+    /// # Notes
+    /// An identifier with no signatures never matches, rather than vacuously matching everything.
+    #[inline]
+    #[must_use]
+    pub fn matches_in(&self, env: &(impl Environment + ?Sized)) -> bool {
+        !self.signatures.is_empty() && self.signatures.iter().all(|sig| sig.check_in(env))
+    }
+
+    /// Returns `true` if all of this identifier's signatures are met in the given environment, without reading
+    /// from the system, see `TerminalSignature::check_env_only_in()`.
+    #[inline]
+    #[must_use]
+    pub fn matches_env_only_in(&self, env: &(impl Environment + ?Sized)) -> bool {
+        !self.signatures.is_empty() && self.signatures.iter().all(|sig| sig.check_env_only_in(env))
+    }
+}
+
 // Identification..
 
 /// Returns the default terminal type for a given operating system.
@@ -493,34 +1484,189 @@ pub fn get_all_terminal_identifiers() -> impl Iterator<Item = &'static TerminalI
         .chain(terminal_providers::FINAL_TERMINAL_IDENTIFIERS)
 }
 
+/// Returns an iterator over the terminal identifiers for the given terminal type, in detection order.
+#[inline]
+pub fn identifiers_for(kind: TerminalType) -> impl Iterator<Item = &'static TerminalIdentifier> {
+    get_all_terminal_identifiers().filter(move |identifier| identifier.kind == kind)
+}
+
 /// Returns an iterator over possible terminal identifiers for the given operating system.
+///
+/// # Notes
+/// Identifiers with any signature inapplicable on the operating system are excluded, see
+/// `TerminalIdentifier::applicable_on()`.
 #[inline]
 pub fn get_possible_terminal_identifiers_for(
     os: OperatingSystem,
 ) -> impl Iterator<Item = &'static TerminalIdentifier> {
-    get_all_terminal_identifiers()
-        .filter(move |identifier| os.compatible_with_target(identifier.target_os))
+    get_all_terminal_identifiers().filter(move |identifier| identifier.applicable_on(os))
+}
+
+/// Returns the environment variables whose values terminal identifiers compare, in detection order.
+///
+/// # Notes
+/// Quoted values of these variables are compared without their quotes, see `quoted_detection_vars()`.
+#[must_use]
+pub fn compared_detection_vars() -> Vec<&'static str> {
+    fn collect(signature: &TerminalSignature, vars: &mut Vec<&'static str>) {
+        let var = match signature {
+            TerminalSignature::EnvVar(var, _) => *var,
+            TerminalSignature::TermProgram(_) | TerminalSignature::TermProgramExact(_) => {
+                TERM_PROGRAM_VAR
+            }
+            TerminalSignature::TermVar(_) | TerminalSignature::TermVarPrefix(_) => TERM_VAR,
+            TerminalSignature::Any(signatures) => {
+                signatures
+                    .iter()
+                    .for_each(|signature| collect(signature, vars));
+                return;
+            }
+            TerminalSignature::EnvVarExists(_)
+            | TerminalSignature::AncestorProcess(_)
+            | TerminalSignature::WindowsConsoleDelegationSet => return,
+        };
+        if !vars.contains(&var) {
+            vars.push(var);
+        }
+    }
+
+    let mut vars = Vec::new();
+    for identifier in get_all_terminal_identifiers() {
+        identifier
+            .signatures
+            .iter()
+            .for_each(|signature| collect(signature, &mut vars));
+    }
+    vars
+}
+
+/// Returns the variables compared by terminal identifiers that are set to quoted values, with the values they are
+/// compared as, e.g. for a diagnostics report, see `compared_detection_vars()`.
+#[inline]
+#[must_use]
+pub fn quoted_detection_vars() -> Vec<environment::QuotedVar> {
+    quoted_detection_vars_in(&SystemEnvironment)
+}
+
+/// Returns the variables compared by terminal identifiers that are set to quoted values in the given environment,
+/// see `quoted_detection_vars()`.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use terminal_relaunch::environment::QuotedVar;
+/// use terminal_relaunch::quoted_detection_vars_in;
+///
+/// let env = HashMap::from([
+///     ("TERM_PROGRAM".to_string(), "\"vscode\"".to_string()),
+///     ("TERM".to_string(), "xterm-256color".to_string()),
+///     ("TERMINAL_EMULATOR".to_string(), "'JetBrains-JediTerm'".to_string()),
+///     ("EDITOR".to_string(), "'vim'".to_string()),
+/// ]);
+/// let quoted = quoted_detection_vars_in(&env);
+/// assert_eq!(quoted.len(), 2);
+/// assert!(quoted.contains(&QuotedVar {
+///     name: "TERM_PROGRAM",
+///     raw: "\"vscode\"".to_string(),
+///     normalized: "vscode".to_string(),
+/// }));
+/// assert!(quoted.iter().any(|var| var.to_string() == "TERMINAL_EMULATOR='JetBrains-JediTerm' (compared as JetBrains-JediTerm)"));
+/// ```
+#[must_use]
+pub fn quoted_detection_vars_in(env: &(impl Environment + ?Sized)) -> Vec<environment::QuotedVar> {
+    environment::quoted_vars_in(env, compared_detection_vars())
 }
 
 /// Attempts to identify the current terminal type based on a list of known terminal identification signatures.
+///
+/// # Notes
+/// *   Hosts running the program without a terminal are reported as `TerminalType::NonTerminalHost` whatever the
+///     environment, see `host::non_terminal_host()`.
+/// *   Inside `tmux`, the outer terminal of the client is reported if it could be identified, see
+///     `tmux::client_terminal()`, as the environment may describe another terminal.
 #[inline]
 #[must_use]
 pub fn find_current_terminal() -> TerminalType {
-    let current_os = OperatingSystem::current();
+    if host::non_terminal_host().is_some() {
+        return TerminalType::NonTerminalHost;
+    }
+    if let Some(client) = tmux::client_terminal() {
+        return client.terminal;
+    }
+    detect_terminal(&SystemEnvironment, OperatingSystem::current(), true)
+}
+
+/// Detects the terminal from the given environment, as if running on the given operating system.
+///
+/// # Notes
+/// *   Signatures that read from the system (see `TerminalSignature::accesses_system()`) are only checked if
+///     `system_access` is `true`, otherwise they are unmet, so identifiers can still match through the
+///     environment, see `TerminalSignature::check_env_only_in()`.
+/// *   Inside `tmux`, `TerminalType::Tmux` is returned rather than the default terminal if no identifier matched.
+/// *   Inside a `POSIX` layer on `Windows` (see `environment::posix_layer_in()`), `TerminalType::Mintty` is returned
+///     rather than the default terminal, as `MSYS2`, `Git Bash` and `Cygwin` all start their shells in `mintty`.
+pub(crate) fn detect_terminal(
+    env: &(impl Environment + ?Sized),
+    os: OperatingSystem,
+    system_access: bool,
+) -> TerminalType {
+    debug_assert!(
+        !has_ambiguous_terminal_identifiers(),
+        "Terminal identifiers for different terminal types have identical signatures."
+    );
+
+    for identifier in get_possible_terminal_identifiers_for(os) {
+        if identifier.signatures.is_empty() {
+            logging::warning!(
+                "Skipping {} identifier with no signatures, as it would match any terminal.",
+                identifier.kind
+            );
+            continue;
+        }
 
-    for identifier in get_possible_terminal_identifiers_for(current_os) {
         // Check all signatures
-        if identifier.signatures.iter().all(TerminalSignature::check) {
+        let matches = if system_access {
+            identifier.matches_in(env)
+        } else {
+            identifier.matches_env_only_in(env)
+        };
+        if matches {
             return identifier.kind;
         }
     }
 
+    if tmux::multiplexer_in(env) == Some(tmux::Multiplexer::Tmux) {
+        logging::info!("No terminal signatures matched inside tmux, reporting tmux itself.");
+        return TerminalType::Tmux;
+    }
+
+    if os == OperatingSystem::Windows
+        && let Some(layer) = environment::posix_layer_in(env)
+    {
+        logging::info!("No terminal signatures matched inside {layer}, reporting mintty.");
+        return TerminalType::Mintty;
+    }
+
     logging::info!(
         "No terminal signatures matched for current terminal, falling back to default terminal for OS."
     );
 
-    // If no terminal matched, return the default terminal for the current OS
-    get_default_terminal_for_os(current_os)
+    // If no terminal matched, return the default terminal for the OS
+    get_default_terminal_for_os(os)
+}
+
+/// Returns `true` if any two terminal identifiers for different terminal types have identical signatures,
+/// which would make detection depend on the order of the identifier tables.
+#[must_use]
+fn has_ambiguous_terminal_identifiers() -> bool {
+    let identifiers: Vec<_> = get_all_terminal_identifiers().collect();
+
+    identifiers.iter().enumerate().any(|(index, a)| {
+        identifiers[index + 1..]
+            .iter()
+            .any(|b| a.kind != b.kind && a.target_os == b.target_os && a.signatures == b.signatures)
+    })
 }
 
 /// Returns an iterator over possible preferred terminals for the given operating system.
@@ -531,105 +1677,250 @@ pub fn get_preferred_terminals_for_os(os: OperatingSystem) -> impl Iterator<Item
     })
 }
 
-/// Returns `true` if the current program has been relaunched by the library in a new terminal already.
-#[inline]
+/// Environment variable listing the current desktop environments, colon separated, e.g. `ubuntu:GNOME`.
+pub const XDG_CURRENT_DESKTOP_VAR: &str = "XDG_CURRENT_DESKTOP";
+
+/// The terminals of desktop environments, preferred over other terminals on those desktops, see
+/// `get_preferred_terminals_in()`.
+pub const DESKTOP_TERMINALS: &[(&str, &[TerminalType])] = &[
+    (
+        "GNOME",
+        &[TerminalType::Ptyxis, TerminalType::GnomeTerminal],
+    ),
+    ("KDE", &[TerminalType::Konsole]),
+    ("XFCE", &[TerminalType::XfceTerminal]),
+];
+
+/// Returns the preferred terminals for the given operating system, with the terminals of the desktop environment
+/// in the given environment first, see `DESKTOP_TERMINALS`.
+///
+/// # Notes
+/// Desktops are read from `XDG_CURRENT_DESKTOP`, ignoring ASCII case. The order is otherwise that of
+/// `get_preferred_terminals_for_os()`.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use terminal_relaunch::{OperatingSystem, TerminalType, get_preferred_terminals_in};
+///
+/// let desktop = |desktop: &str| HashMap::from([("XDG_CURRENT_DESKTOP".to_string(), desktop.to_string())]);
+///
+/// let gnome = get_preferred_terminals_in(&desktop("ubuntu:GNOME"), OperatingSystem::Linux);
+/// assert_eq!(gnome[..2], [TerminalType::Ptyxis, TerminalType::GnomeTerminal]);
+/// let kde = get_preferred_terminals_in(&desktop("KDE"), OperatingSystem::Linux);
+/// assert_eq!(kde[0], TerminalType::Konsole);
+///
+/// // Every preferred terminal is still listed once..
+/// let mut sorted = gnome.clone();
+/// sorted.sort();
+/// assert_eq!(sorted, get_preferred_terminals_in(&HashMap::<String, String>::new(), OperatingSystem::Linux));
+///
+/// // Desktop terminals for another operating system are never listed..
+/// let windows = get_preferred_terminals_in(&desktop("GNOME"), OperatingSystem::Windows);
+/// assert!(!windows.contains(&TerminalType::Ptyxis));
+/// ```
 #[must_use]
-pub fn has_been_relaunched() -> bool {
-    std::env::args().any(|arg| arg == RELAUNCHED_ARGUMENT)
+pub fn get_preferred_terminals_in(
+    env: &(impl Environment + ?Sized),
+    os: OperatingSystem,
+) -> Vec<TerminalType> {
+    let desktops = env.var(XDG_CURRENT_DESKTOP_VAR).unwrap_or_default();
+    let mut terminals: Vec<TerminalType> = desktops
+        .split(':')
+        .filter_map(|desktop| {
+            DESKTOP_TERMINALS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(desktop.trim()))
+        })
+        .flat_map(|(_, terminals)| terminals.iter().copied())
+        .filter(|terminal| {
+            terminal.is_preferred() && os.compatible_with_target(terminal.target_os())
+        })
+        .collect();
+    terminals.dedup();
+
+    let others: Vec<TerminalType> = get_preferred_terminals_for_os(os)
+        .filter(|terminal| !terminals.contains(terminal))
+        .collect();
+    terminals.extend(others);
+    terminals
 }
 
-/// Constant indicating no override for is active.
-const NO_OVERRIDE: u8 = 0;
-/// Constant indicating override is true.
-const OVERRIDE_TRUE: u8 = 1;
-/// Constant indicating override is false.
-const OVERRIDE_FALSE: u8 = 2;
-
-/// Global override for unicode support detection.
-static OVERRIDE_UNICODE_SUPPORT: atomic::AtomicU8 = atomic::AtomicU8::new(NO_OVERRIDE);
-/// Global override for unicode support detection.
-static OVERRIDE_RGB_ANSI_COLOURS: atomic::AtomicU8 = atomic::AtomicU8::new(NO_OVERRIDE);
-
-/// Stores the override value into the given atomic target.
+/// Compares two terminals by their capability richness, see `TerminalType::capability_score()`.
 #[inline]
-fn store_override(target: &atomic::AtomicU8, supports: Option<bool>) {
-    match supports {
-        Some(true) => target.store(OVERRIDE_TRUE, atomic::Ordering::SeqCst),
-        Some(false) => target.store(OVERRIDE_FALSE, atomic::Ordering::SeqCst),
-        None => target.store(NO_OVERRIDE, atomic::Ordering::SeqCst),
-    }
+#[must_use]
+pub fn compare_terminals(a: TerminalType, b: TerminalType) -> std::cmp::Ordering {
+    a.capability_score().cmp(&b.capability_score())
+}
+
+/// Returns the most capable terminal available, out of the current terminal and the installed
+/// terminals for the current operating system.
+///
+/// # Notes
+/// When terminals are ranked equally, the current terminal is preferred.
+#[must_use]
+pub fn best_available_terminal() -> Option<TerminalType> {
+    let current_os = OperatingSystem::current();
+    let installed = TerminalType::iter().filter(|terminal_type| {
+        current_os.compatible_with_target(terminal_type.target_os())
+            && get_provider_for_terminal(*terminal_type).is_some_and(|p| p.is_installed())
+    });
+
+    std::iter::once(*CURRENT_TERMINAL)
+        .filter(|terminal_type| *terminal_type != TerminalType::Unknown)
+        .chain(installed)
+        .reduce(|best, candidate| {
+            if compare_terminals(candidate, best).is_gt() {
+                candidate
+            } else {
+                best
+            }
+        })
 }
 
-/// Reads the override value from the given atomic target.
+/// Returns `true` if the current program has been relaunched by the library in a new terminal already.
+///
+/// # Notes
+/// If a marker namespace is configured (see `RelaunchConfig::marker_namespace`), only the namespaced
+/// marker is recognised, otherwise the legacy `RELAUNCHED_ARGUMENT` is.
 #[inline]
-fn read_override(target: &atomic::AtomicU8) -> Option<bool> {
-    match target.load(atomic::Ordering::SeqCst) {
-        OVERRIDE_TRUE => Some(true),
-        OVERRIDE_FALSE => Some(false),
-        _ => None,
-    }
+#[must_use]
+pub fn has_been_relaunched() -> bool {
+    let marker = config::relaunch_config().relaunched_argument();
+    std::env::args().any(|arg| arg == marker)
 }
 
 /// Overrides the detected full unicode support for the current terminal.
 ///
 /// # Notes
-/// If `supports` is `None`, the override is cleared and automatic detection is used again.
+/// *   This is the `HintSource::Application` capability hint, see `capabilities::set_capability_hint()`.
+/// *   If `supports` is `None`, the override is cleared and automatic detection (or hints from other sources)
+///     is used again.
 #[inline]
 pub fn set_unicode_support_override(supports: Option<bool>) {
-    store_override(&OVERRIDE_UNICODE_SUPPORT, supports);
+    capabilities::update_capability_hint(HintSource::Application, |hint| {
+        hint.full_unicode = supports;
+    });
 }
 
 /// Overrides the detected RGB (ANSI) colour support for the current terminal.
 ///
 /// # Notes
-/// If `supports` is `None`, the override is cleared and automatic detection is used again.
+/// *   This is the `HintSource::Application` capability hint, see `capabilities::set_capability_hint()`.
+/// *   If `supports` is `None`, the override is cleared and automatic detection (or hints from other sources)
+///     is used again.
 #[inline]
 pub fn set_rgb_ansi_override(supports: Option<bool>) {
-    store_override(&OVERRIDE_RGB_ANSI_COLOURS, supports);
+    capabilities::update_capability_hint(HintSource::Application, |hint| {
+        hint.rgb_ansi_colours = supports;
+    });
 }
 
-/// Reads the current override for full unicode support detection.
+/// Reads the current override for full unicode support detection, i.e. the capability hints of every source
+/// merged by precedence, see `capabilities::HintSource`.
 /// # Returns
 /// *   `Some(overriden_state)` if full unicode support is overridden.
 /// *   `None` if no override is set and automatic detection should be used.
 #[inline]
 #[must_use]
 pub fn is_unicode_overridden() -> Option<bool> {
-    read_override(&OVERRIDE_UNICODE_SUPPORT)
+    capabilities::resolved_capability_hints().full_unicode
 }
 
-/// Reads the current override for rgb (ANSI) colour support detection.
+/// Reads the current override for rgb (ANSI) colour support detection, i.e. the capability hints of every
+/// source merged by precedence, see `capabilities::HintSource`.
 /// # Returns
-/// *   `Some(overriden_state)` if full unicode support is overridden.
+/// *   `Some(overriden_state)` if rgb (ANSI) colour support is overridden.
 /// *   `None` if no override is set and automatic detection should be used.
 #[inline]
 #[must_use]
 pub fn is_rgb_ansi_overridden() -> Option<bool> {
-    read_override(&OVERRIDE_RGB_ANSI_COLOURS)
+    capabilities::resolved_capability_hints().rgb_ansi_colours
 }
 
 /// The current terminal type detected at runtime.
-pub static CURRENT_TERMINAL: LazyLock<TerminalType> = LazyLock::new(find_current_terminal);
+///
+/// # Notes
+/// Detected the first time it's read, and latched for the lifetime of the process. Detecting it initializes the
+/// library, see `runtime::init()`.
+pub static CURRENT_TERMINAL: LazyLock<TerminalType> = LazyLock::new(|| runtime::state().terminal());
+
+/// The version of the current terminal detected at runtime, if it could be determined.
+///
+/// # Notes
+/// Detected the first time it's read, and latched for the lifetime of the process.
+pub static CURRENT_TERMINAL_VERSION: LazyLock<Option<TerminalVersion>> =
+    LazyLock::new(version::find_current_terminal_version);
 
 /// If the current terminal supports full unicode rendering.
-pub static SUPPORTS_FULL_UNICODE: LazyLock<bool> = LazyLock::new(|| {
-    if let Some(override_state) = is_unicode_overridden() {
-        override_state
-    } else {
-        CURRENT_TERMINAL.supports_full_unicode()
-    }
-});
+///
+/// # Notes
+/// Resolved the first time it's read, and latched for the lifetime of the process, so later overrides and
+/// capability rules are ignored (e.g. when tests in the same process set different overrides).
+/// Use `supports_full_unicode()` to respect them.
+pub static SUPPORTS_FULL_UNICODE: LazyLock<bool> =
+    LazyLock::new(capabilities::resolve_current_full_unicode_support);
 
 /// If the current terminal supports full RGB (ANSI) colours.
-pub static SUPPORTS_RGB_ANSI_COLOURS: LazyLock<bool> = LazyLock::new(|| {
-    if let Some(override_state) = is_unicode_overridden() {
-        override_state
-    } else {
-        CURRENT_TERMINAL.supports_rgb_ansi_colours()
-    }
-});
+///
+/// # Notes
+/// Resolved the first time it's read, and latched for the lifetime of the process, so later overrides and
+/// capability rules are ignored (e.g. when tests in the same process set different overrides).
+/// Use `supports_rgb_ansi_colours()` to respect them.
+pub static SUPPORTS_RGB_ANSI_COLOURS: LazyLock<bool> =
+    LazyLock::new(capabilities::resolve_current_rgb_ansi_colour_support);
+
+/// Returns the current terminal type, see `CURRENT_TERMINAL`.
+#[inline]
+#[must_use]
+pub fn current_terminal() -> TerminalType {
+    *CURRENT_TERMINAL
+}
+
+/// Returns `true` if the current terminal supports full unicode rendering.
+///
+/// Unlike `SUPPORTS_FULL_UNICODE`, this is resolved on every call, so it respects the current overrides
+/// and capability rules.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::{set_unicode_support_override, supports_full_unicode};
+///
+/// set_unicode_support_override(Some(false));
+/// assert!(!supports_full_unicode());
+///
+/// set_unicode_support_override(Some(true));
+/// assert!(supports_full_unicode());
+/// ```
+#[inline]
+#[must_use]
+pub fn supports_full_unicode() -> bool {
+    capabilities::resolve_current_full_unicode_support()
+}
+
+/// Returns `true` if the current terminal supports RGB (ANSI) colours.
+///
+/// Unlike `SUPPORTS_RGB_ANSI_COLOURS`, this is resolved on every call, so it respects the current overrides
+/// and capability rules. The terminal is only probed once per process, see `capabilities::CapabilityDetection`.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::{set_rgb_ansi_override, supports_rgb_ansi_colours};
+///
+/// set_rgb_ansi_override(Some(false));
+/// assert!(!supports_rgb_ansi_colours());
+///
+/// set_rgb_ansi_override(Some(true));
+/// assert!(supports_rgb_ansi_colours());
+/// ```
+#[inline]
+#[must_use]
+pub fn supports_rgb_ansi_colours() -> bool {
+    capabilities::resolve_current_rgb_ansi_colour_support()
+}
 
-/// Argument passed to relaunched terminals to indicate a relaunch has occurred.
+/// Argument passed to relaunched terminals to indicate a relaunch has occurred, when no marker namespace is configured.
 pub const RELAUNCHED_ARGUMENT: &str = "--relaunched-term";
 
 /// A trait for terminal providers that can supply terminal types, check installation status and relaunch the
@@ -643,53 +1934,172 @@ pub trait TerminalProvider {
     #[must_use]
     fn is_installed(&self) -> bool;
 
+    /// Returns the resolved path of the installed terminal (e.g. the application bundle or binary),
+    /// if it is installed and its location is known.
+    ///
+    /// # Notes
+    /// *   Providers should look the path up once and launch that exact path (see `terminal_providers::Resolved`),
+    ///     as a provider is selected and launched from the same instance.
+    /// *   The path is checked to still exist immediately before relaunching, so a terminal removed or replaced
+    ///     after `is_installed()` was checked is skipped rather than failing the whole relaunch.
+    #[must_use]
+    fn installed_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns `true` if the terminal can open the relaunched program using the given window mode
+    /// from the current context.
+    #[must_use]
+    fn supports_window_mode(&self, mode: WindowMode) -> bool {
+        mode == WindowMode::Window
+    }
+
+    /// Returns the version of the installed terminal, if it is installed and its version can be determined.
+    #[must_use]
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        None
+    }
+
+    /// Returns the name of a program required to relaunch in this terminal that could not be found, if any,
+    /// e.g. `osascript` in minimal `MacOS` environments.
+    ///
+    /// # Notes
+    /// Providers with a missing dependency are skipped when searching for alternative terminals.
+    #[must_use]
+    fn missing_dependency(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns why the installed terminal can't be used for the current launch, if it can't, e.g.
+    /// `UnfitReason::CannotHostCommand` for a terminal that can't run the relaunched program.
+    ///
+    /// # Notes
+    /// *   Unfit providers are skipped when searching for alternative terminals, as if not installed.
+    /// *   `terminal_providers::launches_program()` tells relaunches from interactive shells opened by
+    ///     `open_terminal_here()`.
+    /// *   A provider only finding out when launching should return `RelaunchError::UnfitTerminal` from
+    ///     `relaunch_in_terminal()` instead, so the next preferred terminal is tried.
+    #[must_use]
+    fn unfit_reason(&self) -> Option<pipeline::UnfitReason> {
+        None
+    }
+
     /// Attempts to relaunch the current program in the terminal provided by this provider,
     /// with the given arguments, if installed.
+    ///
+    /// Inside `open_terminal_here()`, an interactive shell is opened in the requested directory instead.
     /// # Errors
     /// Returns an `std::io::Error` if any I/O operations fail.
     fn relaunch_in_terminal(&self) -> TermResult<()>;
 }
 
 /// Returns `true` if we should attempt to find and relaunch in a preferred terminal.
+///
+/// # Notes
+/// See `decision::relaunch_decision()` for the reason a relaunch would be skipped.
 #[inline]
 #[must_use]
 pub fn should_attempt_relaunch() -> bool {
-    !has_been_relaunched() && !CURRENT_TERMINAL.is_preferred()
+    decision::relaunch_decision().should_attempt()
+}
+
+/// Returns `true` if relaunching in the given provider's terminal would improve on the current terminal.
+///
+/// A terminal of a different type is always an improvement, as only preferred terminals are searched. A terminal
+/// of the same type as the current terminal (e.g. an older `Kitty` lacking a capability) is only an improvement if
+/// its installed version is known, and has the full preferred capabilities for that version.
+#[inline]
+#[must_use]
+pub fn improves_on_current_terminal(provider: &dyn TerminalProvider) -> bool {
+    improves_on_terminal(provider, *CURRENT_TERMINAL)
+}
+
+/// Returns `true` if relaunching in the given provider's terminal would improve on the given terminal, see
+/// `improves_on_current_terminal()`.
+#[must_use]
+pub(crate) fn improves_on_terminal(provider: &dyn TerminalProvider, current: TerminalType) -> bool {
+    let terminal_type = provider.terminal_type();
+    if terminal_type != current {
+        return true;
+    }
+
+    let Some(installed_version) = provider.installed_version() else {
+        logging::info!(
+            "`{terminal_type}` is the current terminal and its installed version is unknown, skipping."
+        );
+        return false;
+    };
+
+    capabilities::resolve_full_unicode_support(terminal_type, Some(&installed_version))
+        && capabilities::resolve_rgb_ansi_colour_support(terminal_type, Some(&installed_version))
 }
 
 /// Returns an alternative preferred terminal provider, if one is found and installed.
 #[inline]
 #[must_use]
 pub fn find_alternative_terminal() -> Option<Box<dyn TerminalProvider>> {
+    find_alternative_terminals().next()
+}
+
+/// Returns an iterator over all installed alternative preferred terminal providers, in order of preference.
+///
+/// # Notes
+/// *   Installation is checked lazily, as the iterator is advanced.
+/// *   Inside `WSL`, only terminals installed in the distribution are found, not those of the `Windows` host, see
+///     `OperatingSystem::is_wsl()`.
+#[inline]
+pub fn find_alternative_terminals() -> impl Iterator<Item = Box<dyn TerminalProvider>> {
     let current_os = OperatingSystem::current();
 
-    for terminal_type in get_preferred_terminals_for_os(current_os) {
-        logging::info!(
-            "Testing if preferred terminal `{}` is installed.",
-            terminal_type.name()
-        );
-        if let Some(provider) = get_provider_for_terminal(terminal_type)
-            && provider.is_installed()
-        {
-            logging::info!("`{}` is installed!", terminal_type.name());
-            return Some(provider);
-        }
-    }
+    get_preferred_terminals_in(&SystemEnvironment, current_os)
+        .into_iter()
+        .filter_map(|terminal_type| {
+            pipeline::probe_terminal(terminal_type, *CURRENT_TERMINAL, &get_provider_for_terminal).1
+        })
+}
 
-    None
+/// Returns the error for when no alternative terminal is installed.
+///
+/// # Returns
+/// *   `RelaunchError::WslHostTerminalRequired` inside `WSL`, see `OperatingSystem::is_wsl()`.
+/// *   `RelaunchError::NoAlternativeTerminalFound` otherwise.
+#[inline]
+pub(crate) fn no_alternative_terminal_error() -> RelaunchError {
+    if OperatingSystem::current().is_wsl() {
+        RelaunchError::WslHostTerminalRequired
+    } else {
+        RelaunchError::NoAlternativeTerminalFound
+    }
 }
 
 /// Returns a terminal provider for the given terminal type, if available.
+///
+/// # Notes
+/// A provider registered for the terminal type is returned over the built-in one, see
+/// `registry::register_provider()`.
 #[inline]
 #[must_use]
 pub fn get_provider_for_terminal(terminal_type: TerminalType) -> Option<Box<dyn TerminalProvider>> {
+    registry::registered_provider(terminal_type)
+        .or_else(|| get_built_in_provider_for_terminal(terminal_type))
+}
+
+/// Returns the built-in terminal provider for the given terminal type, if there is one, ignoring registered
+/// providers.
+#[inline]
+#[must_use]
+pub fn get_built_in_provider_for_terminal(
+    terminal_type: TerminalType,
+) -> Option<Box<dyn TerminalProvider>> {
     match terminal_type {
-        TerminalType::WindowsTerminal => Some(Box::new(WindowsTerminalProvider)),
-        TerminalType::ITerm2 => Some(Box::new(ITerm2Provider)),
-        TerminalType::Ghostty => Some(Box::new(GhosttyProvider)),
-        TerminalType::Kitty => Some(Box::new(KittyProvider)),
-        TerminalType::Alacritty => Some(Box::new(AlacrittyProvider)),
-        TerminalType::WezTerm => Some(Box::new(WezTermProvider)),
+        TerminalType::WindowsTerminal => Some(Box::new(WindowsTerminalProvider::default())),
+        TerminalType::ITerm2 => Some(Box::new(ITerm2Provider::default())),
+        TerminalType::Ghostty => Some(Box::new(GhosttyProvider::default())),
+        TerminalType::Kitty => Some(Box::new(KittyProvider::default())),
+        TerminalType::Alacritty => Some(Box::new(AlacrittyProvider::default())),
+        TerminalType::WezTerm => Some(Box::new(WezTermProvider::default())),
+        TerminalType::Ptyxis => Some(Box::new(PtyxisProvider::default())),
+        TerminalType::Hyper => Some(Box::new(HyperProvider::default())),
         _ => None,
     }
 }
@@ -707,15 +2117,83 @@ pub fn get_provider_for_terminal(terminal_type: TerminalType) -> Option<Box<dyn
 ///
 /// # Returns
 /// *   `Ok(())` if the relaunch was successful, if `Ok(())` is returned, the current instance should exit.
+/// *   `Err(RelaunchError::Skipped)` if no installed terminal improves on the current terminal, see
+///     `pipeline::RelaunchPipeline::skip_reason()`.
 /// *   `Err(RelaunchError)` if no preferred terminal is found or if the relaunch fails.
-#[inline]
 pub fn try_relaunch_in_preferred_terminal() -> TermResult<()> {
-    if let Some(provider) = find_alternative_terminal() {
-        provider.relaunch_in_terminal()
-    } else {
-        logging::warning!("No alternative preferred terminal found for relaunch.");
-        Err(RelaunchError::NoAlternativeTerminalFound)
+    let mut pipeline = pipeline::RelaunchPipeline::without_decision();
+    pipeline.by_ref().for_each(drop);
+    if let Some(reason) = pipeline.skip_reason() {
+        return Err(RelaunchError::Skipped(reason));
     }
+
+    pipeline.into_result().map(|_| ())
+}
+
+/// Opens the current program in a new window of the current terminal, e.g. to give a full-screen TUI a dedicated
+/// window when started from a busy shell.
+///
+/// # Notes
+/// *   This ignores the preferred terminals and capabilities entirely, selecting the provider of the current terminal.
+/// *   The relaunch marker is passed as usual, so `has_been_relaunched()` is `true` in the new window.
+/// *   The configured window mode is ignored, a new window is always opened.
+///
+/// # Errors
+/// *   `RelaunchError::UnsupportedTerminalProvider` if the current terminal has no provider, e.g. `LinuxTerminal`.
+/// *   Any error from the provider's `relaunch_in_terminal()`.
+pub fn open_in_new_window_of_current_terminal() -> TermResult<()> {
+    open_in_new_window_of(*CURRENT_TERMINAL)
+}
+
+/// Opens the current program in a new window of the given terminal, see `open_in_new_window_of_current_terminal()`.
+fn open_in_new_window_of(terminal: TerminalType) -> TermResult<()> {
+    let provider = get_provider_for_terminal(terminal)
+        .ok_or(RelaunchError::UnsupportedTerminalProvider(terminal))?;
+
+    terminal_providers::with_window_mode(WindowMode::Window, || provider.relaunch_in_terminal())
+}
+
+/// Opens the best installed terminal with an interactive shell in the given directory, without relaunching the
+/// current program, e.g. for an "Open terminal here" action.
+///
+/// # Notes
+/// *   Terminals are selected in the preferred order (see `get_preferred_terminals_in()`), the first installed
+///     terminal with a provider is used, even if it's the current terminal.
+/// *   The configured window mode is ignored, a new window is always opened.
+/// *   No relaunch marker is passed, and no relaunch is recorded.
+///
+/// # Errors
+/// *   `RelaunchError::NoAlternativeTerminalFound` if no preferred terminal is installed.
+/// *   `RelaunchError::WslHostTerminalRequired` instead inside `WSL`, see `OperatingSystem::is_wsl()`.
+/// *   `RelaunchError::IOError` if the directory can't be made absolute.
+/// *   Any error from the provider's `relaunch_in_terminal()`.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::path::Path;
+///
+/// use terminal_relaunch::open_terminal_here;
+///
+/// let terminal = open_terminal_here(Path::new("/tmp")).expect("Failed to open a terminal");
+/// println!("Opened {terminal}..");
+/// ```
+pub fn open_terminal_here(dir: &Path) -> TermResult<TerminalType> {
+    let dir = std::path::absolute(dir)?;
+    let provider = get_preferred_terminals_in(&SystemEnvironment, OperatingSystem::current())
+        .into_iter()
+        .filter_map(get_provider_for_terminal)
+        .find(|provider| {
+            provider.is_installed()
+                && provider.missing_dependency().is_none()
+                && terminal_providers::with_shell_in(&dir, || provider.unfit_reason()).is_none()
+        })
+        .ok_or_else(no_alternative_terminal_error)?;
+
+    terminal_providers::with_shell_in(&dir, || {
+        terminal_providers::with_window_mode(WindowMode::Window, || provider.relaunch_in_terminal())
+    })?;
+
+    Ok(provider.terminal_type())
 }
 
 /// Attempts to relaunch the current program in a preferred terminal, if we have not already relaunched the application,
@@ -737,14 +2215,132 @@ pub fn try_relaunch_in_preferred_terminal() -> TermResult<()> {
 /// *   `Err(RelaunchError)` if no preferred terminal is found or if the relaunch fails.
 #[inline]
 pub fn relaunch_if_available() -> TermResult<bool> {
-    // Check if we should attempt to relaunch
-    if should_attempt_relaunch() {
-        try_relaunch_in_preferred_terminal()?;
-        Ok(true)
-    } else {
-        // No relaunch needed
-        Ok(false)
+    pipeline::RelaunchPipeline::new().into_result()
+}
+
+/// A summary of a relaunch attempt that did not exit the current process, see `relaunch_if_available_or_continue()`.
+#[derive(Debug)]
+pub struct RelaunchAttemptSummary {
+    /// If a relaunch was attempted.
+    pub attempted: bool,
+    /// Why the relaunch was skipped, if it was.
+    pub skip_reason: Option<SkipReason>,
+    /// The error that occurred if the relaunch was attempted and failed.
+    pub error: Option<RelaunchError>,
+    /// The current terminal.
+    pub terminal: TerminalType,
+    /// If the current terminal supports full unicode rendering.
+    pub full_unicode: bool,
+    /// If the current terminal supports RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+}
+
+impl RelaunchAttemptSummary {
+    /// Returns `true` if the program is continuing in a terminal missing preferred features.
+    #[inline]
+    #[must_use]
+    pub fn is_degraded(&self) -> bool {
+        !(self.full_unicode && self.rgb_ansi_colours)
+    }
+
+    /// Returns the exit code of the attempt for command line tools, see `exit_code::CliExitCode`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use terminal_relaunch::decision::SkipReason;
+    /// use terminal_relaunch::errors::RelaunchError;
+    /// use terminal_relaunch::exit_code::CliExitCode;
+    /// use terminal_relaunch::{RelaunchAttemptSummary, TerminalType};
+    ///
+    /// let summary = |skip_reason, error: Option<RelaunchError>| RelaunchAttemptSummary {
+    ///     attempted: error.is_some(),
+    ///     skip_reason,
+    ///     error,
+    ///     terminal: TerminalType::Xterm,
+    ///     full_unicode: false,
+    ///     rgb_ansi_colours: false,
+    /// };
+    ///
+    /// for (summary, code) in [
+    ///     (summary(None, None), CliExitCode::AlreadyFine),
+    ///     (summary(Some(SkipReason::AlreadyPreferred), None), CliExitCode::AlreadyFine),
+    ///     (summary(Some(SkipReason::RemoteSession), None), CliExitCode::RelaunchDeclined),
+    ///     (summary(Some(SkipReason::NoImprovementPossible), None), CliExitCode::NoTerminal),
+    ///     (summary(None, Some(RelaunchError::NoAlternativeTerminalFound)), CliExitCode::NoTerminal),
+    ///     (summary(None, Some(RelaunchError::NoUrlOpener)), CliExitCode::InternalError),
+    /// ] {
+    ///     assert_eq!(summary.exit_code(), code, "{summary:?}");
+    /// }
+    /// ```
+    #[must_use]
+    pub fn exit_code(&self) -> exit_code::CliExitCode {
+        match (&self.error, self.skip_reason) {
+            (Some(error), _) => exit_code::CliExitCode::for_error(error),
+            (None, Some(reason)) => exit_code::CliExitCode::for_skip_reason(reason),
+            (None, None) => exit_code::CliExitCode::AlreadyFine,
+        }
+    }
+}
+
+/// Attempts to relaunch the current program in a preferred terminal, if we have not already relaunched the application,
+/// and if the current terminal does not meet the preferred terminal requirements, i.e. full unicode and RGB (ANSI) colour support.
+/// and an alternative preferred terminal is found and installed.
+///
+/// If the relaunch is successful, this function will exit the current process. Otherwise, any failure is logged and
+/// recorded in the returned summary, so the program can continue in the current terminal.
+///
+/// # Notes
+/// This is the recommended way to relaunch, use `relaunch_if_available_and_exit()` if relaunch failures
+/// should be handled as errors instead.
+#[must_use]
+pub fn relaunch_if_available_or_continue() -> RelaunchAttemptSummary {
+    match relaunch_or_continue_with(pipeline::RelaunchPipeline::new()) {
+        ControlFlow::Break(()) => std::process::exit(0),
+        ControlFlow::Continue(summary) => summary,
+    }
+}
+
+/// Runs the given relaunch pipeline as `relaunch_if_available_or_continue()`, without exiting the current process.
+///
+/// # Returns
+/// *   `ControlFlow::Break(())` if the program was relaunched, and the current instance should exit.
+/// *   `ControlFlow::Continue(summary)` otherwise, with any failure logged and recorded in the summary.
+pub fn relaunch_or_continue_with(
+    mut pipeline: pipeline::RelaunchPipeline,
+) -> ControlFlow<(), RelaunchAttemptSummary> {
+    let mut summary = RelaunchAttemptSummary {
+        attempted: true,
+        skip_reason: None,
+        error: None,
+        terminal: *CURRENT_TERMINAL,
+        full_unicode: supports_full_unicode(),
+        rgb_ansi_colours: supports_rgb_ansi_colours(),
+    };
+
+    for step in pipeline.by_ref() {
+        if let pipeline::PipelineStep::Decided {
+            decision: decision::RelaunchDecision::Skip(_),
+            ..
+        } = step
+        {
+            summary.attempted = false;
+        }
+    }
+    summary.skip_reason = pipeline.skip_reason();
+
+    match pipeline.into_result() {
+        Ok(true) => return ControlFlow::Break(()),
+        Ok(false) => {}
+        Err(error) => {
+            logging::error!(
+                "Relaunch failed, continuing in the current terminal: {}",
+                logging::sanitize_for_log(&error.to_string())
+            );
+            summary.error = Some(error);
+        }
     }
+
+    ControlFlow::Continue(summary)
 }
 
 /// Attempts to relaunch the current program in a preferred terminal, if we have not already relaunched the application,
@@ -789,3 +2385,509 @@ pub fn relaunch_if_available_and_exit_with(exit_code: i32) -> TermResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{RelaunchConfig, WindowMode, relaunch_config, set_relaunch_config};
+    use crate::exit_code::CliExitCode;
+    use crate::pipeline::RelaunchPipeline;
+    use crate::recording::RecordingProvider;
+    use crate::registry::ScopedRegistry;
+    use crate::test_support::reading_globals;
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
+    use std::ops::ControlFlow;
+
+    use strum::IntoEnumIterator;
+
+    use super::{
+        Environment, OperatingSystem, RelaunchAttemptSummary, RelaunchError, TerminalProvider,
+        TerminalSignature, TerminalType, WINDOWS_DELEGATION_LET_WINDOWS_DECIDE,
+        WINDOWS_DELEGATION_TERMINALS, compare_terminals, console_delegation_set_in,
+        default_terminal_for_delegation, detect_terminal, get_built_in_provider_for_terminal,
+        open_in_new_window_of, registry_delegation_selects_windows_terminal,
+        relaunch_or_continue_with, terminal_for_delegation_guid,
+    };
+
+    #[test]
+    fn terminal_types_keep_their_discriminants_and_ordering() {
+        // The original variants, whose discriminants and ordering are relied on, e.g. when serialised..
+        let original = [
+            TerminalType::Unknown,
+            TerminalType::WindowsCMD,
+            TerminalType::WindowsTerminal,
+            TerminalType::MacOS,
+            TerminalType::ITerm2,
+            TerminalType::Kitty,
+            TerminalType::Ghostty,
+            TerminalType::ThirdPartyMacOSTerminal,
+            TerminalType::LinuxTerminal,
+            TerminalType::WezTerm,
+            TerminalType::Alacritty,
+            TerminalType::VSCode,
+            TerminalType::Nvim,
+        ];
+        for (discriminant, terminal) in original.into_iter().enumerate() {
+            assert_eq!(terminal as usize, discriminant, "{terminal:?}");
+        }
+        assert!(original.is_sorted());
+        assert!(TerminalType::Konsole > TerminalType::Nvim);
+    }
+
+    #[test]
+    fn ranking_order_is_frozen() {
+        // Every terminal, from least to most capable, with terminals ranked equally grouped together..
+        let ranking: &[&[TerminalType]] = &[
+            &[
+                TerminalType::Unknown,
+                TerminalType::NonTerminalHost,
+                TerminalType::MacOS,
+                TerminalType::Xterm,
+                TerminalType::Urxvt,
+                TerminalType::St,
+                TerminalType::GnuScreen,
+            ],
+            &[
+                TerminalType::WindowsCMD,
+                TerminalType::Emacs,
+                TerminalType::Jupyter,
+                TerminalType::Tmux,
+            ],
+            &[TerminalType::ConEmu],
+            &[TerminalType::LinuxTerminal],
+            &[
+                TerminalType::JetBrains,
+                TerminalType::Zed,
+                TerminalType::Nvim,
+                TerminalType::ThirdPartyMacOSTerminal,
+            ],
+            &[
+                TerminalType::VSCode,
+                TerminalType::Hyper,
+                TerminalType::Tabby,
+                TerminalType::Mintty,
+            ],
+            &[
+                TerminalType::Alacritty,
+                TerminalType::Ptyxis,
+                TerminalType::Konsole,
+                TerminalType::GnomeTerminal,
+                TerminalType::Tilix,
+                TerminalType::Terminator,
+                TerminalType::XfceTerminal,
+            ],
+            &[TerminalType::WindowsTerminal],
+            &[TerminalType::Foot],
+            &[
+                TerminalType::ITerm2,
+                TerminalType::WezTerm,
+                TerminalType::Kitty,
+                TerminalType::Contour,
+            ],
+            &[TerminalType::Ghostty],
+        ];
+
+        // ..which ranks every current variant, so a new one must be placed explicitly..
+        let ranked: Vec<TerminalType> = ranking
+            .iter()
+            .flat_map(|tier| tier.iter().copied())
+            .collect();
+        assert_eq!(ranked.len(), TerminalType::iter().count());
+        for terminal in TerminalType::iter() {
+            assert!(ranked.contains(&terminal), "{terminal:?} is not ranked");
+        }
+
+        for (index, tier) in ranking.iter().enumerate() {
+            for a in *tier {
+                for b in *tier {
+                    assert_eq!(compare_terminals(*a, *b), Ordering::Equal, "{a:?} {b:?}");
+                }
+                for lower in ranking[..index].iter().flat_map(|tier| tier.iter()) {
+                    assert_eq!(
+                        compare_terminals(*a, *lower),
+                        Ordering::Greater,
+                        "{a:?} {lower:?}"
+                    );
+                    assert_eq!(
+                        compare_terminals(*lower, *a),
+                        Ordering::Less,
+                        "{lower:?} {a:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn delegation_guids_select_their_terminals() {
+        for (guid, terminal) in WINDOWS_DELEGATION_TERMINALS {
+            // Compared ignoring case and surrounding whitespace, as read from the registry..
+            for read in [
+                guid.to_string(),
+                guid.to_ascii_lowercase(),
+                format!(" {guid}\n"),
+            ] {
+                assert_eq!(
+                    terminal_for_delegation_guid(&read),
+                    Some(*terminal),
+                    "{read:?}"
+                );
+            }
+            assert_eq!(
+                WINDOWS_DELEGATION_TERMINALS
+                    .iter()
+                    .filter(|(other, _)| other.eq_ignore_ascii_case(guid))
+                    .count(),
+                1,
+                "{guid}"
+            );
+        }
+        for guid in [
+            WINDOWS_DELEGATION_LET_WINDOWS_DECIDE,
+            "{2EACA947-7F5F-4CFA-BA87-8F7FBEEFBE69}",
+            "E12CFF52-A866-4C77-9A90-F570A7AA2C6B",
+            "",
+        ] {
+            assert_eq!(terminal_for_delegation_guid(guid), None, "{guid:?}");
+        }
+
+        // The default is only reported if installed, except for the console host, which always is..
+        let (cmd, wt, preview) = (
+            "{B23D10C0-E52E-411E-9D5B-C09FDF709C7D}",
+            "{E12CFF52-A866-4C77-9A90-F570A7AA2C6B}",
+            "{86633F1F-6454-40EC-89CE-DA4EBA977EE2}",
+        );
+        for (guid, installed, expected) in [
+            (cmd, false, Some(TerminalType::WindowsCMD)),
+            (cmd, true, Some(TerminalType::WindowsCMD)),
+            (wt, true, Some(TerminalType::WindowsTerminal)),
+            (wt, false, None),
+            (preview, true, Some(TerminalType::WindowsTerminal)),
+            (preview, false, None),
+            (WINDOWS_DELEGATION_LET_WINDOWS_DECIDE, true, None),
+        ] {
+            assert_eq!(
+                default_terminal_for_delegation(guid, |_| installed),
+                expected,
+                "{guid} {installed}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_delegation_to_windows_terminal_is_reported() {
+        let (cmd, wt_console, wt, preview) = (
+            "{B23D10C0-E52E-411E-9D5B-C09FDF709C7D}",
+            "{2EACA947-7F5F-4CFA-BA87-8F7FBEEFBE69}",
+            "{E12CFF52-A866-4C77-9A90-F570A7AA2C6B}",
+            "{86633f1f-6454-40ec-89ce-da4eba977ee2}",
+        );
+        let delegation = |console: &str, terminal: &str| {
+            registry_delegation_selects_windows_terminal(Some((
+                console.to_string(),
+                terminal.to_string(),
+            )))
+        };
+
+        assert!(!registry_delegation_selects_windows_terminal(None));
+        assert!(delegation(wt_console, wt));
+        assert!(delegation(wt_console, preview));
+        assert!(delegation(cmd, wt));
+
+        // The console host, "Let Windows decide" and unknown terminals are never `Windows Terminal`..
+        let decide = WINDOWS_DELEGATION_LET_WINDOWS_DECIDE;
+        for (console, terminal) in [
+            (cmd, cmd),
+            (decide, decide),
+            (cmd, decide),
+            (wt_console, decide),
+            (wt, wt_console),
+            (wt_console, ""),
+        ] {
+            assert!(!delegation(console, terminal), "{console} {terminal}");
+        }
+
+        // ..including the rollback to the console host, which leaves the two GUIDs different..
+        assert_ne!(wt_console, cmd);
+        assert!(!delegation(wt_console, cmd));
+
+        // ..where detection falls back to `WT_SESSION`..
+        let env = |vars: &[(&str, &str)]| -> HashMap<String, String> {
+            vars.iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect()
+        };
+        let cmd_env = env(&[("COMSPEC", r"C:\Windows\system32\cmd.exe")]);
+        assert!(!console_delegation_set_in(&cmd_env, || delegation(
+            wt_console, cmd
+        )));
+        assert_eq!(
+            detect_terminal(&cmd_env, OperatingSystem::Windows, false),
+            TerminalType::WindowsCMD
+        );
+        let wt_env = env(&[
+            ("COMSPEC", r"C:\Windows\system32\cmd.exe"),
+            ("WT_SESSION", "0b7e6a7c"),
+        ]);
+        assert_eq!(
+            detect_terminal(&wt_env, OperatingSystem::Windows, false),
+            TerminalType::WindowsTerminal
+        );
+    }
+
+    #[test]
+    fn new_windows_open_in_the_given_terminal_only() {
+        let _guard = crate::test_support::writing_globals();
+        let config = relaunch_config();
+        set_relaunch_config(RelaunchConfig::new().window_mode(WindowMode::Tab));
+
+        // Every terminal with a provider is "installed", so a preferred terminal search would find one..
+        let recorders: Vec<RecordingProvider> = TerminalType::iter()
+            .filter(|terminal| get_built_in_provider_for_terminal(*terminal).is_some())
+            .map(RecordingProvider::new)
+            .collect();
+        let mut scope = ScopedRegistry::new();
+        for recorder in &recorders {
+            scope.register(recorder.clone());
+        }
+
+        // ..but only the given terminal is launched, in a new window, with the relaunch marker..
+        for recorder in &recorders {
+            let terminal = recorder.terminal_type();
+            open_in_new_window_of(terminal).unwrap();
+
+            let launches: Vec<_> = recorders
+                .iter()
+                .flat_map(RecordingProvider::launches)
+                .collect();
+            assert_eq!(launches.len(), 1, "{terminal}");
+            let launch = &launches[0];
+            assert_eq!(launch.terminal, terminal);
+            assert_eq!(launch.window_mode, WindowMode::Window, "{terminal}");
+            assert_eq!(
+                launch.args.first(),
+                Some(&RelaunchConfig::new().relaunched_argument()),
+                "{terminal}"
+            );
+            recorder.clear();
+        }
+
+        // ..and terminals without a provider can't be opened, rather than falling back to another..
+        for terminal in [
+            TerminalType::LinuxTerminal,
+            TerminalType::WindowsCMD,
+            TerminalType::Unknown,
+        ] {
+            assert!(matches!(
+                open_in_new_window_of(terminal),
+                Err(RelaunchError::UnsupportedTerminalProvider(unsupported)) if unsupported == terminal
+            ));
+        }
+        assert!(
+            recorders
+                .iter()
+                .all(|recorder| recorder.launches().is_empty())
+        );
+
+        drop(scope);
+        set_relaunch_config(config);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signatures_read_values_that_are_not_unicode() {
+        use std::collections::HashMap;
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let env = |vars: &[(&str, &[u8])]| -> HashMap<OsString, OsString> {
+            vars.iter()
+                .map(|(name, value)| (OsString::from(name), OsString::from_vec(value.to_vec())))
+                .collect()
+        };
+
+        // Presence is checked regardless of the encoding..
+        let nvim = env(&[("NVIM", b"/run/user/1000/nvim.\xff\xfe.0")]);
+        assert!(TerminalSignature::EnvVarExists("NVIM").check_in(&nvim));
+        assert!(!TerminalSignature::EnvVarExists("VIM").check_in(&nvim));
+        assert_eq!(
+            detect_terminal(&nvim, OperatingSystem::Linux, false),
+            TerminalType::Nvim
+        );
+
+        // ..values are compared byte-wise, ignoring ASCII case, so invalid bytes never equal the expected value..
+        let term = env(&[("TERM", b"FOOT-\xff")]);
+        assert!(TerminalSignature::TermVarPrefix("foot").check_in(&term));
+        assert!(!TerminalSignature::TermVar("foot-").check_in(&term));
+        assert!(!TerminalSignature::TermVar("foot-\u{fffd}").check_in(&term));
+        assert!(TerminalSignature::EnvVar("TERM", "foot-").check_in(&env(&[("TERM", b"Foot-")])));
+
+        // ..as are `TERM_PROGRAM` values, quoted or given as a path with invalid bytes in its directories..
+        let program = |value: &[u8]| env(&[("TERM_PROGRAM", value)]);
+        assert!(TerminalSignature::TermProgram("WezTerm").check_in(&program(b"/opt/\xff/wezterm")));
+        assert!(TerminalSignature::TermProgram("WezTerm").check_in(&program(b"\"WEZTERM\"")));
+        assert!(!TerminalSignature::TermProgram("WezTerm").check_in(&program(b"wezterm\xff")));
+        assert!(!TerminalSignature::TermProgramExact("WezTerm").check_in(&program(b"\xffWezTerm")));
+
+        // ..while `var()` treats such a value as unset..
+        assert_eq!(Environment::var(&nvim, "NVIM"), None);
+        assert!(Environment::var_os(&nvim, "NVIM").is_some());
+    }
+
+    #[test]
+    fn posix_layers_skip_the_registry_and_are_never_cmd_or_wt() {
+        use std::cell::Cell;
+
+        let env = |vars: &[(&str, &str)]| -> HashMap<String, String> {
+            vars.iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect()
+        };
+        let layers = [
+            ("MSYSTEM", "MINGW64"),
+            ("MSYSTEM", "UCRT64"),
+            ("MSYSTEM", "MSYS"),
+            ("CYGWIN", "winsymlinks:nativestrict"),
+            ("CYGWIN", ""),
+        ];
+
+        for layer in layers {
+            let vars = env(&[
+                layer,
+                ("TERM", "xterm-256color"),
+                ("COMSPEC", r"C:\Windows\system32\cmd.exe"),
+            ]);
+
+            // The registry isn't read, even when it would select `Windows Terminal`..
+            let read = Cell::new(false);
+            let delegated = console_delegation_set_in(&vars, || {
+                read.set(true);
+                true
+            });
+            assert!(!delegated && !read.get(), "{layer:?}");
+
+            // ..and neither `CMD` nor `Windows Terminal` is reported..
+            let terminal = detect_terminal(&vars, OperatingSystem::Windows, false);
+            assert_eq!(terminal, TerminalType::Mintty, "{layer:?}");
+        }
+
+        // Outside of them, the registry decides..
+        for (vars, selected) in [(env(&[]), true), (env(&[("MSYSTEM", "")]), false)] {
+            let read = Cell::new(false);
+            let delegated = console_delegation_set_in(&vars, || {
+                read.set(true);
+                selected
+            });
+            assert_eq!((delegated, read.get()), (selected, true), "{vars:?}");
+        }
+        let cmd = env(&[("COMSPEC", r"C:\Windows\system32\cmd.exe")]);
+        assert_eq!(
+            detect_terminal(&cmd, OperatingSystem::Windows, false),
+            TerminalType::WindowsCMD
+        );
+
+        // ..while shells of a layer opened in `Windows Terminal` are in it..
+        let wt = env(&[("MSYSTEM", "MINGW64"), ("WT_SESSION", "0b7e6a7c")]);
+        assert_eq!(
+            detect_terminal(&wt, OperatingSystem::Windows, false),
+            TerminalType::WindowsTerminal
+        );
+    }
+
+    /// A provider whose relaunches fail with the given error kind.
+    struct FailingProvider(TerminalType, std::io::ErrorKind);
+
+    impl TerminalProvider for FailingProvider {
+        fn terminal_type(&self) -> TerminalType {
+            self.0
+        }
+
+        fn is_installed(&self) -> bool {
+            true
+        }
+
+        fn relaunch_in_terminal(&self) -> crate::errors::TermResult<()> {
+            Err(std::io::Error::from(self.1).into())
+        }
+    }
+
+    /// Runs `relaunch_or_continue_with()` with the first two preferred terminals failing with the given error
+    /// kinds, if any, and the others recorded if `recorded`, returning the number of recorded launches.
+    fn relaunch_or_continue_failing(
+        failures: [Option<std::io::ErrorKind>; 2],
+        recorded: bool,
+    ) -> (ControlFlow<(), RelaunchAttemptSummary>, usize) {
+        let candidates: Vec<_> =
+            super::get_preferred_terminals_for_os(OperatingSystem::current()).collect();
+        let recorders: Vec<_> = candidates
+            .iter()
+            .map(|&terminal| RecordingProvider::new(terminal))
+            .collect();
+        let pipeline = RelaunchPipeline::without_decision()
+            .with_current_terminal(TerminalType::Unknown)
+            .with_providers({
+                let (candidates, recorders) = (candidates.clone(), recorders.clone());
+                move |terminal| {
+                    let index = candidates
+                        .iter()
+                        .position(|&candidate| candidate == terminal)?;
+                    match failures.get(index).copied().flatten() {
+                        Some(kind) => Some(Box::new(FailingProvider(terminal, kind))),
+                        None if recorded => Some(Box::new(recorders[index].clone())),
+                        None => None,
+                    }
+                }
+            });
+
+        let flow = relaunch_or_continue_with(pipeline);
+        let launched = recorders.iter().map(|recorder| recorder.launches().len());
+        (flow, launched.sum())
+    }
+
+    #[test]
+    fn relaunches_break_and_failures_continue_with_a_summary() {
+        use std::io::ErrorKind;
+
+        let _globals = reading_globals();
+
+        // A relaunch breaks, so the wrapper exits..
+        let (flow, launched) = relaunch_or_continue_failing([None, None], true);
+        assert!(flow.is_break());
+        assert_eq!(launched, 1);
+
+        // ..as does one after terminals that vanished when launched..
+        let (flow, launched) =
+            relaunch_or_continue_failing([Some(ErrorKind::NotFound), None], true);
+        assert!(flow.is_break());
+        assert_eq!(launched, 1);
+
+        // ..while no terminal at all continues with the error..
+        let (flow, launched) = relaunch_or_continue_failing([None, None], false);
+        let ControlFlow::Continue(summary) = flow else {
+            panic!("relaunched without a terminal");
+        };
+        assert!(summary.attempted && summary.skip_reason.is_none());
+        assert!(matches!(
+            summary.error,
+            Some(
+                RelaunchError::NoAlternativeTerminalFound | RelaunchError::WslHostTerminalRequired
+            )
+        ));
+        assert_eq!(summary.exit_code(), CliExitCode::NoTerminal);
+        assert_eq!(launched, 0);
+
+        // ..as does any other failure, without trying the next terminal..
+        let (flow, launched) =
+            relaunch_or_continue_failing([Some(ErrorKind::PermissionDenied), None], true);
+        let ControlFlow::Continue(summary) = flow else {
+            panic!("relaunched after a failure");
+        };
+        assert!(summary.attempted && summary.skip_reason.is_none());
+        assert!(matches!(
+            &summary.error,
+            Some(RelaunchError::IOError(error)) if error.kind() == ErrorKind::PermissionDenied
+        ));
+        assert_eq!(summary.terminal, super::current_terminal());
+        assert_eq!(launched, 0);
+    }
+}
@@ -1,3 +1,8 @@
+use std::borrow::Cow;
+
+/// Maximum number of characters of a value kept by `sanitize_for_log()`.
+pub const MAX_LOGGED_VALUE_LEN: usize = 256;
+
 #[allow(unused_macros)]
 macro_rules! info {
     ($($arg:tt)+) => {
@@ -5,6 +10,11 @@ macro_rules! info {
         {
             log::info!($($arg)+);
         }
+        // Keep the arguments used without logging, so they don't cause unused warnings..
+        #[cfg(not(feature = "logging"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
     };
 }
 
@@ -15,6 +25,11 @@ macro_rules! warning {
         {
             log::warn!($($arg)+);
         }
+        // Keep the arguments used without logging, so they don't cause unused warnings..
+        #[cfg(not(feature = "logging"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
     };
 }
 
@@ -25,6 +40,11 @@ macro_rules! error {
         {
             log::error!($($arg)+);
         }
+        // Keep the arguments used without logging, so they don't cause unused warnings..
+        #[cfg(not(feature = "logging"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
     };
 }
 
@@ -35,6 +55,11 @@ macro_rules! debug {
         {
             log::debug!($($arg)+);
         }
+        // Keep the arguments used without logging, so they don't cause unused warnings..
+        #[cfg(not(feature = "logging"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
     };
 }
 
@@ -45,8 +70,89 @@ macro_rules! trace {
         {
             log::trace!($($arg)+);
         }
+        // Keep the arguments used without logging, so they don't cause unused warnings..
+        #[cfg(not(feature = "logging"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
     };
 }
 
 #[allow(unused_imports)]
 pub(crate) use {debug, error, info, trace, warning};
+
+/// Sanitizes a value read from the environment (or derived from it) before it's logged or shown in a message.
+///
+/// # Notes
+/// *   `C0` and `C1` control characters are removed, so values can't inject escape sequences into the
+///     user's terminal or forge log lines.
+/// *   Values longer than `MAX_LOGGED_VALUE_LEN` characters are truncated and suffixed with `...`.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::logging::sanitize_for_log;
+///
+/// assert_eq!(sanitize_for_log("xterm-256color"), "xterm-256color");
+/// assert_eq!(sanitize_for_log("evil\x1b]0;title\x07"), "evil]0;title");
+/// ```
+#[must_use]
+pub fn sanitize_for_log(value: &str) -> Cow<'_, str> {
+    let too_long = value
+        .chars()
+        .filter(|c| !c.is_control())
+        .nth(MAX_LOGGED_VALUE_LEN)
+        .is_some();
+    if !too_long && !value.chars().any(char::is_control) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut sanitized: String = value
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_LOGGED_VALUE_LEN)
+        .collect();
+    if too_long {
+        sanitized.push_str("...");
+    }
+    Cow::Owned(sanitized)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{MAX_LOGGED_VALUE_LEN, sanitize_for_log};
+
+    #[test]
+    fn control_characters_are_stripped() {
+        for (value, sanitized) in [
+            ("\x1b]0;pwned\x07", "]0;pwned"),
+            ("\x1b[2J", "[2J"),
+            ("xterm\r\nfake=1", "xtermfake=1"),
+            ("\u{9b}31mred", "31mred"),
+            ("tab\tand\0nul\x7fdel", "tabandnuldel"),
+            ("d\u{e9}grad\u{e9} \u{2728}", "d\u{e9}grad\u{e9} \u{2728}"),
+        ] {
+            assert_eq!(sanitize_for_log(value), sanitized, "{value:?}");
+        }
+
+        // Clean values aren't copied..
+        assert!(matches!(
+            sanitize_for_log("xterm-256color"),
+            Cow::Borrowed("xterm-256color")
+        ));
+    }
+
+    #[test]
+    fn long_values_are_truncated_by_characters() {
+        let limit = "\u{e9}".repeat(MAX_LOGGED_VALUE_LEN);
+        assert_eq!(sanitize_for_log(&limit), limit);
+
+        let long = format!("{limit}\u{e9}\u{e9}");
+        assert_eq!(sanitize_for_log(&long), format!("{limit}..."));
+
+        // ..counting the characters kept, not those stripped..
+        let escaped = format!("\x1b{}\x1b", "a".repeat(MAX_LOGGED_VALUE_LEN));
+        assert_eq!(sanitize_for_log(&escaped), "a".repeat(MAX_LOGGED_VALUE_LEN));
+    }
+}
@@ -0,0 +1,779 @@
+//! Helpers for writing to the current terminal using only sequences it understands.
+
+use std::borrow::Cow;
+use std::io::{IsTerminal, Write};
+use std::process::Command;
+use std::sync::atomic;
+
+use crate::environment::{Environment, SystemEnvironment};
+use crate::errors::{RelaunchError, TermResult};
+use crate::{CURRENT_TERMINAL, OperatingSystem, TerminalType};
+
+/// Represents a standard output stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StdStream {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
+}
+
+impl StdStream {
+    /// Returns the name of the stream.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+
+    /// Returns `true` if the stream is a terminal.
+    #[inline]
+    #[must_use]
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            Self::Stdout => std::io::stdout().is_terminal(),
+            Self::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+impl std::fmt::Display for StdStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the stream user-facing output should be written to, so it never ends up in piped data.
+///
+/// # Returns
+/// *   `Some(StdStream::Stdout)` if standard output is a terminal.
+/// *   `Some(StdStream::Stderr)` if only standard error is a terminal, e.g. in `myapp | jq`.
+/// *   `None` if neither is a terminal, so there is no user to write to.
+#[inline]
+#[must_use]
+pub fn user_channel() -> Option<StdStream> {
+    user_channel_for(
+        StdStream::Stdout.is_terminal(),
+        StdStream::Stderr.is_terminal(),
+    )
+}
+
+/// Returns the stream user-facing output should be written to, given which streams are terminals,
+/// see `user_channel()`.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::output::{StdStream, user_channel_for};
+///
+/// assert_eq!(user_channel_for(true, true), Some(StdStream::Stdout));
+/// assert_eq!(user_channel_for(true, false), Some(StdStream::Stdout));
+/// assert_eq!(user_channel_for(false, true), Some(StdStream::Stderr));
+/// assert_eq!(user_channel_for(false, false), None);
+/// ```
+#[inline]
+#[must_use]
+pub fn user_channel_for(stdout_terminal: bool, stderr_terminal: bool) -> Option<StdStream> {
+    if stdout_terminal {
+        Some(StdStream::Stdout)
+    } else if stderr_terminal {
+        Some(StdStream::Stderr)
+    } else {
+        None
+    }
+}
+
+/// A writer for user-facing output, writing to the stream selected by `user_channel()`.
+pub(crate) struct UserChannel {
+    stream: StdStream,
+}
+
+impl UserChannel {
+    /// Opens the user channel, or returns `None` if there is no interactive stream.
+    #[inline]
+    pub(crate) fn open() -> Option<Self> {
+        user_channel().map(|stream| Self { stream })
+    }
+}
+
+impl Write for UserChannel {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.stream {
+            StdStream::Stdout => std::io::stdout().write(buf),
+            StdStream::Stderr => std::io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.stream {
+            StdStream::Stdout => std::io::stdout().flush(),
+            StdStream::Stderr => std::io::stderr().flush(),
+        }
+    }
+}
+
+/// Environment variable selecting `OutputStyle::PlainAscii` when set to `1`, unless the application chose a style.
+pub const PLAIN_OUTPUT_VAR: &str = "TERMINAL_RELAUNCH_PLAIN";
+
+/// How text generated by the crate (e.g. links, status messages and formatted terminal information) is styled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputStyle {
+    /// Styled from the detected capabilities, unless `PLAIN_OUTPUT_VAR` selects `OutputStyle::PlainAscii`.
+    #[default]
+    Auto,
+    /// Unadorned ASCII, for screen readers, braille displays and log captures: no escape sequences (e.g. hyperlinks
+    /// or colours), no emoji or symbols, and no other non-ASCII characters.
+    PlainAscii,
+    /// Styled from the detected capabilities, ignoring `PLAIN_OUTPUT_VAR`.
+    Rich,
+}
+
+impl OutputStyle {
+    /// Returns the name of the output style.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::PlainAscii => "plain-ascii",
+            Self::Rich => "rich",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Sets how text generated by the crate is styled, e.g. `OutputStyle::PlainAscii` for an accessibility setting.
+///
+/// # Notes
+/// Every function generating text consults the style when called, i.e. `format_link()`, `reset_terminal_state()`,
+/// the messages of `status::ensure_good_terminal()`, `capabilities::colour_depth()` (and so the `ecosystem`
+/// helpers), and `info::TerminalInfo::effective_capabilities()` (and so `effective_format()`).
+///
+/// # Example
+/// ```
+/// use strum::IntoEnumIterator;
+/// use terminal_relaunch::capabilities::{ColourDepth, colour_depth};
+/// use terminal_relaunch::info::TerminalInfo;
+/// use terminal_relaunch::output::{OutputStyle, format_link, set_output_style};
+/// use terminal_relaunch::status::install_suggestion_for;
+/// use terminal_relaunch::{OperatingSystem, TerminalType};
+///
+/// set_output_style(OutputStyle::PlainAscii);
+///
+/// let mut texts = vec![format_link("https://example.com/dégradé", "Dégradé \u{2728}")];
+/// for terminal in TerminalType::iter() {
+///     texts.push(TerminalInfo::new(terminal).effective_format());
+///     texts.push(terminal.verbose_format());
+/// }
+/// for os in [OperatingSystem::Windows, OperatingSystem::MacOS, OperatingSystem::Linux] {
+///     texts.extend(install_suggestion_for(os).map(|suggestion| suggestion.link()));
+/// }
+///
+/// for text in texts {
+///     assert!(text.is_ascii() && !text.contains('\x1b'), "{text:?}");
+/// }
+/// assert_eq!(colour_depth(), ColourDepth::NoColour);
+///
+/// set_output_style(OutputStyle::Auto);
+/// ```
+#[inline]
+pub fn set_output_style(style: OutputStyle) {
+    crate::runtime::state()
+        .output_style
+        .store(style as u8, atomic::Ordering::SeqCst);
+}
+
+/// Returns the output style chosen by the application, see `set_output_style()`.
+#[inline]
+#[must_use]
+pub fn output_style() -> OutputStyle {
+    match crate::runtime::state()
+        .output_style
+        .load(atomic::Ordering::SeqCst)
+    {
+        style if style == OutputStyle::PlainAscii as u8 => OutputStyle::PlainAscii,
+        style if style == OutputStyle::Rich as u8 => OutputStyle::Rich,
+        _ => OutputStyle::Auto,
+    }
+}
+
+/// Returns `true` if text generated by the crate should be plain ASCII, see `plain_output_in()`.
+#[inline]
+#[must_use]
+pub fn plain_output() -> bool {
+    plain_output_in(&SystemEnvironment)
+}
+
+/// Returns `true` if text generated by the crate should be plain ASCII, with the given environment.
+///
+/// # Returns
+/// *   `true` if the style is `OutputStyle::PlainAscii`, or `OutputStyle::Auto` and `PLAIN_OUTPUT_VAR` is `1`.
+/// *   `false` otherwise.
+#[inline]
+#[must_use]
+pub fn plain_output_in(env: &(impl Environment + ?Sized)) -> bool {
+    match output_style() {
+        OutputStyle::PlainAscii => true,
+        OutputStyle::Rich => false,
+        OutputStyle::Auto => env.var(PLAIN_OUTPUT_VAR).as_deref() == Some("1"),
+    }
+}
+
+/// Converts text to plain ASCII, as used for `OutputStyle::PlainAscii`.
+///
+/// # Notes
+/// *   Escape sequences (e.g. `OSC 8` hyperlinks and `SGR` colours) are removed, keeping the text they style.
+/// *   Other control characters are removed, except newlines and tabs.
+/// *   Other non-ASCII characters (e.g. emoji, or accented letters in a localised `MessageCatalog`) are replaced
+///     with `?`.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::output::{format_link_for, to_plain_ascii};
+///
+/// assert_eq!(to_plain_ascii("Running in Kitty."), "Running in Kitty.");
+/// assert_eq!(to_plain_ascii("\x1b[1;31mDégradé\x1b[0m \u{2728}\n"), "D?grad? ?\n");
+///
+/// let link = format_link_for(true, "https://sw.kovidgoyal.net/kitty", "Kitty");
+/// assert_eq!(to_plain_ascii(&link), "Kitty");
+/// ```
+#[must_use]
+pub fn to_plain_ascii(text: &str) -> Cow<'_, str> {
+    if text
+        .chars()
+        .all(|c| c.is_ascii() && (!c.is_ascii_control() || c == '\n' || c == '\t'))
+    {
+        return Cow::Borrowed(text);
+    }
+
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // `CSI`, ended by a final byte..
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // `OSC`, `DCS` and other strings, ended by `BEL` or `ST`..
+                Some(']' | 'P' | '_' | '^' | 'X') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' | '\t' => plain.push(c),
+            c if c.is_control() => {}
+            c if c.is_ascii() => plain.push(c),
+            _ => plain.push('?'),
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// Sequence resetting the terminal state on VT-capable terminals: leaves the alternate screen, soft resets
+/// the terminal (`DECSTR`), resets all text attributes (`SGR 0`), shows the cursor, clears the screen and
+/// moves the cursor home.
+pub const VT_RESET_SEQUENCE: &str = "\x1b[?1049l\x1b[!p\x1b[0m\x1b[?25h\x1b[2J\x1b[H";
+
+/// Returns the sequence to reset the state of the current terminal, if it understands VT sequences.
+///
+/// # Returns
+/// *   `Some(VT_RESET_SEQUENCE)` for the default `Windows` console host when the hosting shell left VT processing
+///     enabled, see `capabilities::current_console_vt_support()`.
+/// *   `None` for the default `Windows` console host otherwise, which needs the console API instead, see
+///     `reset_terminal_state()`.
+/// *   `Some(VT_RESET_SEQUENCE)` otherwise.
+#[inline]
+#[must_use]
+pub fn reset_sequence() -> Option<&'static str> {
+    reset_sequence_with(
+        *CURRENT_TERMINAL,
+        crate::capabilities::current_console_vt_support,
+    )
+}
+
+/// Returns the sequence to reset the state of the given terminal, with the console VT support, only read for the
+/// default `Windows` console host, see `reset_sequence()`.
+#[inline]
+fn reset_sequence_with(
+    terminal: TerminalType,
+    console_vt_support: impl FnOnce() -> crate::capabilities::ConsoleVtSupport,
+) -> Option<&'static str> {
+    if terminal == TerminalType::WindowsCMD
+        && console_vt_support() == crate::capabilities::ConsoleVtSupport::Enabled
+    {
+        return Some(VT_RESET_SEQUENCE);
+    }
+    reset_sequence_for(terminal)
+}
+
+/// Returns the sequence to reset the state of the given terminal, see `reset_sequence()`.
+#[inline]
+#[must_use]
+pub fn reset_sequence_for(terminal: TerminalType) -> Option<&'static str> {
+    (terminal != TerminalType::WindowsCMD).then_some(VT_RESET_SEQUENCE)
+}
+
+/// Resets whatever state previous output left the current terminal in (e.g. stray text attributes, or the
+/// alternate screen of a crashed previous run) and clears the screen.
+///
+/// # Notes
+/// *   The sequence is written to the stream selected by `user_channel()`, nothing is done if neither standard
+///     output nor standard error is a terminal.
+/// *   The default `Windows` console host is reset through the console API.
+/// *   Nothing is done with plain output, see `plain_output()`.
+///
+/// # Errors
+/// Returns `RelaunchError::IOError` if writing to the terminal, or any console API call fails.
+pub fn reset_terminal_state() -> TermResult<()> {
+    if plain_output() {
+        return Ok(());
+    }
+    let Some(mut channel) = UserChannel::open() else {
+        return Ok(());
+    };
+
+    let Some(sequence) = reset_sequence() else {
+        #[cfg(target_os = "windows")]
+        return crate::console::reset_console();
+
+        #[cfg(not(target_os = "windows"))]
+        return Ok(());
+    };
+
+    channel.write_all(sequence.as_bytes())?;
+    channel.flush()?;
+    Ok(())
+}
+
+/// Formats a link to the given URL for the current terminal.
+///
+/// # Returns
+/// *   The plain ASCII text of `"label (url)"` with plain output, see `plain_output()`.
+/// *   An `OSC 8` hyperlink showing `label` if the current terminal supports hyperlinks and there is a stream to
+///     write it to, see `user_channel()`, e.g. standard error in `myapp | jq`.
+/// *   `"label (url)"` otherwise, or just the URL if the label is the URL.
+#[must_use]
+pub fn format_link(url: &str, label: &str) -> String {
+    if plain_output() {
+        return to_plain_ascii(&format_link_for(false, url, label)).into_owned();
+    }
+
+    format_link_for(
+        CURRENT_TERMINAL.supports_hyperlinks() && user_channel().is_some(),
+        url,
+        label,
+    )
+}
+
+/// Formats a link to the given URL as an `OSC 8` hyperlink if `hyperlinks` is `true`, see `format_link()`.
+#[must_use]
+pub fn format_link_for(hyperlinks: bool, url: &str, label: &str) -> String {
+    if hyperlinks {
+        format!("\x1b]8;;{url}\x1b\\{label}\x1b]8;;\x1b\\")
+    } else if label.is_empty() || label == url {
+        url.to_string()
+    } else {
+        format!("{label} ({url})")
+    }
+}
+
+/// Returns the command that opens the given URL in the user's default application, if an opener exists.
+///
+/// # Notes
+/// *   `Windows` uses `rundll32 url.dll,FileProtocolHandler`, avoiding `cmd` interpreting characters such as `&`.
+/// *   `MacOS` uses `open`.
+/// *   Other platforms use `xdg-open`, which is not installed on headless systems.
+#[must_use]
+pub fn url_opener_command(url: &str) -> Option<Command> {
+    url_opener_command_in(OperatingSystem::current(), url, &SystemEnvironment)
+}
+
+/// Returns the command that opens the given URL on the given operating system, looking up the opener in the
+/// given environment's `PATH`, see `url_opener_command()`.
+fn url_opener_command_in(
+    os: OperatingSystem,
+    url: &str,
+    env: &(impl Environment + ?Sized),
+) -> Option<Command> {
+    let mut command = match os {
+        OperatingSystem::Windows => {
+            let mut command = Command::new("rundll32");
+            command.arg("url.dll,FileProtocolHandler");
+            command
+        }
+        OperatingSystem::MacOS => Command::new("open"),
+        OperatingSystem::Linux | OperatingSystem::Unknown => Command::new(
+            crate::terminal_providers::find_executable_in("xdg-open", env)?,
+        ),
+    };
+
+    command.arg(url);
+    Some(command)
+}
+
+/// Opens the given URL in the user's default application (e.g. their browser), waiting for the opener to exit.
+///
+/// # Errors
+/// *   `RelaunchError::NoUrlOpener` if no program to open URLs with could be found.
+/// *   `RelaunchError::IOError` if the opener could not be run, or exited unsuccessfully.
+pub fn open_url(url: &str) -> TermResult<()> {
+    let mut command = url_opener_command(url).ok_or(RelaunchError::NoUrlOpener)?;
+
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("URL opener exited unsuccessfully: {status}")).into())
+    }
+}
+
+/// Represents what a piece of formatted terminal information describes, so UIs can style each piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SegmentKind {
+    /// The name of the terminal.
+    Name,
+    /// The name of the terminal's executable.
+    ExecName,
+    /// The version of the terminal.
+    Version,
+    /// A capability the terminal supports.
+    CapabilityPresent,
+    /// A capability the terminal lacks, only listed by `TerminalType::status_segments()` and
+    /// `info::TerminalInfo::status_segments()`.
+    CapabilityMissing,
+    /// Punctuation between the other segments.
+    Separator,
+}
+
+/// A piece of formatted terminal information, see `TerminalType::format_segments()`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InfoSegment {
+    /// The text of the segment.
+    pub text: String,
+    /// What the segment describes.
+    pub kind: SegmentKind,
+}
+
+impl InfoSegment {
+    /// Creates a new segment of the given kind.
+    #[inline]
+    #[must_use]
+    pub fn new(text: impl Into<String>, kind: SegmentKind) -> Self {
+        Self {
+            text: text.into(),
+            kind,
+        }
+    }
+}
+
+impl std::fmt::Display for InfoSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::process::Command;
+    use strum::IntoEnumIterator;
+
+    use super::{
+        OutputStyle, SegmentKind, VT_RESET_SEQUENCE, format_link, format_link_for, output_style,
+        reset_sequence_for, reset_sequence_with, set_output_style, url_opener_command_in,
+    };
+    use crate::{OperatingSystem, TerminalType};
+
+    /// The verbose strings before they were joined from segments, which must never change.
+    const VERBOSE_FORMATS: &[(TerminalType, &str)] = &[
+        (TerminalType::Unknown, "Unknown"),
+        (
+            TerminalType::WindowsCMD,
+            "Windows CMD (cmd.exe), Enhanced Colours",
+        ),
+        (
+            TerminalType::WindowsTerminal,
+            "Windows Terminal (wt.exe), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::MacOS, "MacOS Terminal (Terminal.app)"),
+        (
+            TerminalType::ITerm2,
+            "iTerm2 (iTerm2.app), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::Kitty, "Kitty, Full Unicode, Enhanced Colours"),
+        (
+            TerminalType::Ghostty,
+            "Ghostty, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::ThirdPartyMacOSTerminal,
+            "Third Party MacOS Terminal, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::LinuxTerminal,
+            "Linux Terminal, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::WezTerm,
+            "WezTerm, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Alacritty,
+            "Alacritty, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::VSCode,
+            "VSCode Terminal (Code.exe), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Nvim,
+            "NVIM Terminal, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Konsole,
+            "Konsole (konsole), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::GnomeTerminal,
+            "GNOME Terminal (gnome-terminal), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::Xterm, "xterm (xterm)"),
+        (
+            TerminalType::Foot,
+            "foot (foot), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Tilix,
+            "Tilix (tilix), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Terminator,
+            "Terminator (terminator), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::XfceTerminal,
+            "Xfce Terminal (xfce4-terminal), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::Urxvt, "rxvt-unicode (urxvt)"),
+        (TerminalType::St, "st (st)"),
+        (
+            TerminalType::Hyper,
+            "Hyper (hyper), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Tabby,
+            "Tabby (tabby), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::NonTerminalHost, "Non-Terminal Host"),
+        (
+            TerminalType::Contour,
+            "Contour (contour), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::Tmux, "tmux (tmux), Full Unicode"),
+        (TerminalType::GnuScreen, "GNU Screen (screen)"),
+        (TerminalType::ConEmu, "ConEmu (ConEmu64.exe), Full Unicode"),
+        (
+            TerminalType::Mintty,
+            "mintty (mintty.exe), Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Ptyxis,
+            "Ptyxis (ptyxis), Full Unicode, Enhanced Colours",
+        ),
+        (TerminalType::Emacs, "Emacs Terminal (emacs), Full Unicode"),
+        (
+            TerminalType::JetBrains,
+            "JetBrains Terminal, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Zed,
+            "Zed Terminal, Full Unicode, Enhanced Colours",
+        ),
+        (
+            TerminalType::Jupyter,
+            "Jupyter Terminal (jupyter), Enhanced Colours",
+        ),
+    ];
+
+    #[test]
+    fn joined_segments_reproduce_the_verbose_strings() {
+        assert_eq!(VERBOSE_FORMATS.len(), TerminalType::iter().count());
+        for (terminal, expected) in VERBOSE_FORMATS {
+            let joined: String = terminal
+                .format_segments()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            assert_eq!(joined, *expected, "{terminal:?}");
+            assert_eq!(terminal.verbose_format(), *expected, "{terminal:?}");
+        }
+    }
+
+    #[test]
+    fn status_segments_only_add_the_missing_capabilities() {
+        for terminal in TerminalType::iter() {
+            let status = terminal.status_segments();
+            assert_eq!(
+                status
+                    .iter()
+                    .filter(|segment| segment.kind == SegmentKind::CapabilityMissing)
+                    .count(),
+                usize::from(!terminal.supports_full_unicode())
+                    + usize::from(!terminal.supports_rgb_ansi_colours()),
+                "{terminal:?}"
+            );
+
+            let mut present = status.clone();
+            // Each missing capability is preceded by its separator..
+            while let Some(index) = present
+                .iter()
+                .position(|segment| segment.kind == SegmentKind::CapabilityMissing)
+            {
+                present.drain(index - 1..=index);
+            }
+            assert_eq!(present, terminal.format_segments(), "{terminal:?}");
+            assert!(
+                status
+                    .iter()
+                    .all(|segment| segment.kind != SegmentKind::Version)
+            );
+        }
+    }
+
+    #[test]
+    fn reset_sequences_per_terminal_class() {
+        use crate::capabilities::ConsoleVtSupport;
+
+        // The console host only takes the sequence when its shell left VT processing on..
+        for (vt, expected) in [
+            (ConsoleVtSupport::Unsupported, None),
+            (ConsoleVtSupport::Available, None),
+            (ConsoleVtSupport::Enabled, Some(VT_RESET_SEQUENCE)),
+        ] {
+            assert_eq!(
+                reset_sequence_with(TerminalType::WindowsCMD, || vt),
+                expected,
+                "{vt:?}"
+            );
+        }
+        assert_eq!(reset_sequence_for(TerminalType::WindowsCMD), None);
+
+        // ..while every other terminal, from full featured to 16 colours, takes it regardless..
+        for terminal in
+            TerminalType::iter().filter(|terminal| *terminal != TerminalType::WindowsCMD)
+        {
+            assert_eq!(
+                reset_sequence_with(terminal, || unreachable!("read for {terminal:?}")),
+                Some(VT_RESET_SEQUENCE)
+            );
+            assert_eq!(reset_sequence_for(terminal), Some(VT_RESET_SEQUENCE));
+        }
+
+        // ..which leaves the alternate screen, resets attributes and shows the cursor before clearing..
+        let steps = [
+            "\x1b[?1049l",
+            "\x1b[!p",
+            "\x1b[0m",
+            "\x1b[?25h",
+            "\x1b[2J",
+            "\x1b[H",
+        ];
+        assert_eq!(VT_RESET_SEQUENCE, steps.concat());
+        assert!(
+            !VT_RESET_SEQUENCE.contains("\x1bc"),
+            "a full reset (`RIS`) drops the scrollback"
+        );
+    }
+
+    #[test]
+    fn links_fall_back_to_plain_text() {
+        let url = "https://aka.ms/terminal";
+        assert_eq!(
+            format_link_for(true, url, "Windows Terminal"),
+            "\x1b]8;;https://aka.ms/terminal\x1b\\Windows Terminal\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            format_link_for(false, url, "Windows Terminal"),
+            "Windows Terminal (https://aka.ms/terminal)"
+        );
+        // A missing or repeated label isn't printed twice..
+        assert_eq!(format_link_for(false, url, ""), url);
+        assert_eq!(format_link_for(false, url, url), url);
+
+        // ..and plain output never emits a hyperlink, nor anything but `ASCII`..
+        let _guard = crate::test_support::writing_globals();
+        let style = output_style();
+        set_output_style(OutputStyle::PlainAscii);
+        let plain = format_link(
+            "https://example.com/d\u{e9}grad\u{e9}",
+            "D\u{e9}grad\u{e9} \u{2728}",
+        );
+        set_output_style(style);
+        assert!(plain.is_ascii(), "{plain:?}");
+        assert!(!plain.contains('\x1b'), "{plain:?}");
+        assert!(plain.contains("https://example.com/"), "{plain:?}");
+    }
+
+    #[test]
+    fn url_openers_per_platform() {
+        fn argv(command: &Command) -> Vec<String> {
+            std::iter::once(command.get_program())
+                .chain(command.get_args())
+                .map(|arg| arg.to_str().unwrap().to_string())
+                .collect()
+        }
+
+        // The `URL` is a single argument, never interpreted by a shell..
+        let url = "https://example.com/search?q=a&b=\"c d\"";
+        let no_path = HashMap::<String, String>::new();
+        assert_eq!(
+            argv(&url_opener_command_in(OperatingSystem::Windows, url, &no_path).unwrap()),
+            ["rundll32", "url.dll,FileProtocolHandler", url]
+        );
+        assert_eq!(
+            argv(&url_opener_command_in(OperatingSystem::MacOS, url, &no_path).unwrap()),
+            ["open", url]
+        );
+
+        // ..and headless systems without `xdg-open` have no opener..
+        let root = crate::test_support::temp_dir("url_openers");
+        let with_path = HashMap::from([("PATH".to_string(), root.to_str().unwrap().to_string())]);
+        for os in [OperatingSystem::Linux, OperatingSystem::Unknown] {
+            assert!(url_opener_command_in(os, url, &no_path).is_none());
+            assert!(url_opener_command_in(os, url, &with_path).is_none());
+        }
+
+        let xdg_open = root.join("xdg-open");
+        std::fs::write(&xdg_open, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&xdg_open, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let command = url_opener_command_in(OperatingSystem::Linux, url, &with_path).unwrap();
+        assert_eq!(argv(&command), [xdg_open.to_str().unwrap(), url]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
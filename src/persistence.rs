@@ -0,0 +1,472 @@
+//! Persistence of the user's relaunch choice across invocations, enabled by `RelaunchConfig::remember_choice`.
+//!
+//! Choices are stored per application namespace (see `RelaunchConfig::marker_namespace`) in a small `TOML`
+//! state file, under `$XDG_STATE_HOME/terminal_relaunch/<namespace>.toml` (`~/.local/state` if unset),
+//! `~/Library/Application Support/terminal_relaunch` on `MacOS`, or `%LOCALAPPDATA%\terminal_relaunch` on `Windows`.
+//!
+//! The state file also records the time of the last successful relaunch, for `RelaunchConfig::cooldown`.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::TerminalType;
+use crate::errors::TermResult;
+use crate::logging;
+
+/// Namespace used for the state file when no marker namespace is configured.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Represents what the user chose to do about relaunching.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RememberedDecision {
+    /// Always relaunch without asking.
+    Always,
+    /// Never relaunch.
+    Never,
+    /// Ask the user each time.
+    #[default]
+    Ask,
+}
+
+/// The user's remembered relaunch choice.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(default)]
+pub struct RememberedChoice {
+    /// What the user chose to do about relaunching.
+    pub decision: RememberedDecision,
+    /// The terminal the user chose to relaunch in, if any.
+    pub terminal: Option<TerminalType>,
+}
+
+/// Returns the directory the state files are stored in, if it can be determined.
+#[must_use]
+pub fn state_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        crate::sudo::user_home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| crate::sudo::user_home().map(|home| home.join(".local").join("state")))
+    };
+
+    base.map(|base| base.join("terminal_relaunch"))
+}
+
+/// Returns the path of the state file for the given namespace, if the state directory can be determined.
+#[inline]
+#[must_use]
+pub fn state_file(namespace: &str) -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(format!("{namespace}.toml")))
+}
+
+/// The contents of a state file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct StateFile {
+    /// The user's remembered relaunch choice.
+    #[serde(flatten)]
+    choice: RememberedChoice,
+    /// The time of the last successful relaunch, in milliseconds since the unix epoch.
+    last_relaunch_ms: Option<u64>,
+}
+
+impl StateFile {
+    /// Returns the time of the last successful relaunch, if one was recorded.
+    fn last_relaunch(self) -> Option<SystemTime> {
+        self.last_relaunch_ms
+            .map(|ms| SystemTime::UNIX_EPOCH + Duration::from_millis(ms))
+    }
+}
+
+/// Loads the state file for the given namespace.
+///
+/// # Notes
+/// A missing, unreadable or corrupt state file results in the default state, with a warning logged for
+/// anything but a missing file.
+fn load_state(namespace: &str) -> StateFile {
+    state_file(namespace).map_or_else(StateFile::default, |path| read_state(&path))
+}
+
+/// Reads the state file at the given path, see `load_state()`.
+fn read_state(path: &Path) -> StateFile {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return StateFile::default();
+        }
+        Err(error) => {
+            logging::warning!(
+                "Failed to read relaunch state from `{}`: {error}",
+                logging::sanitize_for_log(&path.to_string_lossy())
+            );
+            return StateFile::default();
+        }
+    };
+
+    toml::from_str(&contents).unwrap_or_else(|error| {
+        logging::warning!(
+            "Ignoring corrupt relaunch state in `{}`: {}",
+            logging::sanitize_for_log(&path.to_string_lossy()),
+            logging::sanitize_for_log(&error.to_string())
+        );
+        StateFile::default()
+    })
+}
+
+/// Stores the state file for the given namespace.
+///
+/// # Notes
+/// The state file is written to a temporary file and renamed into place, so concurrent instances never
+/// read a partially written file, the last write wins.
+fn store_state(namespace: &str, state: StateFile) -> TermResult<()> {
+    let path = state_file(namespace).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Could not determine the state directory.",
+        )
+    })?;
+
+    write_state(&path, state)
+}
+
+/// Writes the state file at the given path, see `store_state()`.
+fn write_state(path: &Path, state: StateFile) -> TermResult<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let contents = toml::to_string(&state).map_err(std::io::Error::other)?;
+    let temp_path = path.with_extension(format!("toml.{}.tmp", std::process::id()));
+
+    let result = std::fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    Ok(result?)
+}
+
+/// Loads the remembered choice for the given namespace.
+///
+/// # Notes
+/// A missing, unreadable or corrupt state file results in the default choice (`RememberedDecision::Ask`),
+/// with a warning logged for anything but a missing file.
+#[inline]
+#[must_use]
+pub fn load_remembered_choice(namespace: &str) -> RememberedChoice {
+    load_state(namespace).choice
+}
+
+/// Stores the remembered choice for the given namespace, keeping the time of the last relaunch.
+///
+/// # Errors
+/// Returns `RelaunchError::IOError` if the state directory can't be determined or the file can't be written.
+pub fn store_remembered_choice(namespace: &str, choice: RememberedChoice) -> TermResult<()> {
+    store_state(
+        namespace,
+        StateFile {
+            choice,
+            ..load_state(namespace)
+        },
+    )
+}
+
+/// Loads the time of the last successful relaunch for the given namespace, if one was recorded.
+#[must_use]
+pub fn load_last_relaunch(namespace: &str) -> Option<SystemTime> {
+    load_state(namespace).last_relaunch()
+}
+
+/// Records the time of a successful relaunch for the given namespace, keeping the remembered choice.
+///
+/// # Errors
+/// Returns `RelaunchError::IOError` if the state directory can't be determined or the file can't be written.
+pub fn record_relaunch(namespace: &str, at: SystemTime) -> TermResult<()> {
+    store_state(namespace, with_relaunch_at(load_state(namespace), at))
+}
+
+/// Returns the given state with the time of the last successful relaunch set to the given time.
+fn with_relaunch_at(state: StateFile, at: SystemTime) -> StateFile {
+    let last_relaunch_ms = at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()
+        .and_then(|since| u64::try_from(since.as_millis()).ok());
+
+    StateFile {
+        last_relaunch_ms,
+        ..state
+    }
+}
+
+/// Returns `true` if a relaunch at `now` would be within the cooldown of the last relaunch.
+///
+/// # Notes
+/// A last relaunch in the future (e.g. after the clock was changed) never blocks relaunching.
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use terminal_relaunch::persistence::cooldown_active_at;
+///
+/// let last = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+/// let cooldown = Duration::from_secs(10);
+///
+/// assert!(cooldown_active_at(Some(last), last + Duration::from_millis(9_999), cooldown));
+/// assert!(!cooldown_active_at(Some(last), last + cooldown, cooldown));
+/// assert!(!cooldown_active_at(Some(last), last - Duration::from_secs(1), cooldown));
+/// assert!(!cooldown_active_at(None, last, cooldown));
+/// ```
+#[inline]
+#[must_use]
+pub fn cooldown_active_at(
+    last_relaunch: Option<SystemTime>,
+    now: SystemTime,
+    cooldown: Duration,
+) -> bool {
+    last_relaunch
+        .and_then(|last| now.duration_since(last).ok())
+        .is_some_and(|elapsed| elapsed < cooldown)
+}
+
+/// Removes the remembered choice for the given namespace, so the user is asked again.
+///
+/// # Errors
+/// Returns `RelaunchError::IOError` if the state file exists but could not be removed.
+pub fn clear_remembered_choice(namespace: &str) -> TermResult<()> {
+    let Some(path) = state_file(namespace) else {
+        return Ok(());
+    };
+
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        RememberedChoice, RememberedDecision, StateFile, cooldown_active_at, read_state,
+        with_relaunch_at, write_state,
+    };
+    use crate::TerminalType;
+
+    #[test]
+    fn state_survives_read_modify_write() {
+        let dir = crate::test_support::temp_dir("persistence_cycle");
+        let path = dir.join("nested").join("myapp.toml");
+        assert_eq!(read_state(&path), StateFile::default());
+
+        // The choice is stored, creating the directory..
+        let choice = RememberedChoice {
+            decision: RememberedDecision::Always,
+            terminal: Some(TerminalType::WezTerm),
+        };
+        write_state(
+            &path,
+            StateFile {
+                choice,
+                ..read_state(&path)
+            },
+        )
+        .unwrap();
+        assert_eq!(read_state(&path).choice, choice);
+
+        // ..then a relaunch is recorded, keeping it..
+        let at = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let last_relaunch_ms = Some(1_700_000_000_123);
+        write_state(
+            &path,
+            StateFile {
+                last_relaunch_ms,
+                ..read_state(&path)
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            read_state(&path),
+            StateFile {
+                choice,
+                last_relaunch_ms
+            }
+        );
+        assert_eq!(
+            read_state(&path)
+                .last_relaunch_ms
+                .map(|ms| SystemTime::UNIX_EPOCH + Duration::from_millis(ms)),
+            Some(at)
+        );
+
+        // ..and changing the choice keeps the relaunch, leaving no temporary files behind..
+        let never = RememberedChoice {
+            decision: RememberedDecision::Never,
+            terminal: None,
+        };
+        write_state(
+            &path,
+            StateFile {
+                choice: never,
+                ..read_state(&path)
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            read_state(&path),
+            StateFile {
+                choice: never,
+                last_relaunch_ms
+            }
+        );
+        let files: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, ["myapp.toml"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_or_unreadable_state_degrades_to_ask() {
+        let dir = crate::test_support::temp_dir("persistence_corrupt");
+        let path = dir.join("myapp.toml");
+
+        for contents in [
+            "decision = \"sometimes\"",
+            "decision = 1",
+            "terminal = \"NotATerminal\"",
+            "last_relaunch_ms = -5",
+            "decision = \"always\"\nnot toml",
+            "\u{0}\u{1}",
+        ] {
+            std::fs::write(&path, contents).unwrap();
+            let state = read_state(&path);
+            assert_eq!(state, StateFile::default(), "{contents:?}");
+            assert_eq!(state.choice.decision, RememberedDecision::Ask);
+        }
+
+        // Missing or unknown keys are tolerated, as written by other versions..
+        std::fs::write(&path, "decision = \"never\"\nextra = true\n").unwrap();
+        assert_eq!(read_state(&path).choice.decision, RememberedDecision::Never);
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(read_state(&path), StateFile::default());
+
+        // ..and a state file that can't be read as a file, or written, is ignored or reported..
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir(&path).unwrap();
+        assert_eq!(read_state(&path), StateFile::default());
+        assert!(write_state(&path, StateFile::default()).is_err());
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            1,
+            "the temporary file was removed"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cooldown_boundary_from_the_state_file() {
+        let dir = crate::test_support::temp_dir("persistence_cooldown");
+        let path = dir.join("myapp.toml");
+        let cooldown = Duration::from_secs(30);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let active_after = |ago: Duration| {
+            write_state(&path, with_relaunch_at(read_state(&path), now - ago)).unwrap();
+            cooldown_active_at(read_state(&path).last_relaunch(), now, cooldown)
+        };
+
+        // Relaunches within the cooldown are skipped, up to the millisecond before it passes..
+        assert!(active_after(Duration::ZERO));
+        assert!(active_after(Duration::from_secs(1)));
+        assert!(active_after(Duration::from_millis(29_999)));
+
+        // ..then allowed from the moment it passes..
+        assert!(!active_after(cooldown));
+        assert!(!active_after(cooldown + Duration::from_millis(1)));
+        assert!(!active_after(Duration::from_hours(1)));
+
+        // ..as they are with no relaunch recorded, one recorded in the future, or a corrupt state file..
+        std::fs::remove_file(&path).unwrap();
+        assert!(!cooldown_active_at(
+            read_state(&path).last_relaunch(),
+            now,
+            cooldown
+        ));
+        std::fs::write(&path, "last_relaunch_ms = 1800000000000\n").unwrap();
+        assert!(!cooldown_active_at(
+            read_state(&path).last_relaunch(),
+            now,
+            cooldown
+        ));
+        std::fs::write(&path, "last_relaunch_ms = \"soon\"\n").unwrap();
+        assert!(!cooldown_active_at(
+            read_state(&path).last_relaunch(),
+            now,
+            cooldown
+        ));
+
+        // ..while a timestamp written by hand is honoured, keeping the remembered choice when recorded over..
+        let recent = 1_700_000_000_000_u64 - 5_000;
+        std::fs::write(
+            &path,
+            format!("decision = \"always\"\nlast_relaunch_ms = {recent}\n"),
+        )
+        .unwrap();
+        assert!(cooldown_active_at(
+            read_state(&path).last_relaunch(),
+            now,
+            cooldown
+        ));
+        assert!(!cooldown_active_at(
+            read_state(&path).last_relaunch(),
+            now + Duration::from_secs(25),
+            cooldown
+        ));
+        write_state(&path, with_relaunch_at(read_state(&path), now)).unwrap();
+        assert_eq!(
+            read_state(&path).choice.decision,
+            RememberedDecision::Always
+        );
+        assert_eq!(read_state(&path).last_relaunch(), Some(now));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,1276 @@
+//! An inspectable, step-by-step form of the relaunch process, e.g. for showing progress in a GUI wrapper.
+//!
+//! The one-shot functions (`relaunch_if_available()`, `try_relaunch_in_preferred_terminal()`) drive a
+//! `RelaunchPipeline` to completion, so stepping through it behaves exactly the same.
+//!
+//! # Example
+//! ```rust,no_run
+//! use terminal_relaunch::config::RelaunchConfig;
+//! use terminal_relaunch::pipeline::relaunch_pipeline;
+//!
+//! let mut pipeline = relaunch_pipeline(RelaunchConfig::new());
+//! for step in pipeline.by_ref() {
+//!     println!("{step}");
+//! }
+//!
+//! if pipeline.into_result().unwrap_or(false) {
+//!     std::process::exit(0);
+//! }
+//! ```
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::capabilities::{TerminalCapabilities, resolve_capabilities};
+use crate::config::RelaunchConfig;
+use crate::decision::{RelaunchDecision, SkipReason};
+use crate::environment::SystemEnvironment;
+use crate::errors::{RelaunchError, TermResult};
+use crate::output::{InfoSegment, SegmentKind};
+use crate::{
+    CURRENT_TERMINAL, CURRENT_TERMINAL_VERSION, OperatingSystem, TerminalProvider, TerminalType,
+    get_preferred_terminals_in, get_provider_for_terminal, improves_on_terminal, logging,
+};
+
+/// Represents why an installed terminal can't be used for a launch, see `TerminalProvider::unfit_reason()`.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "test-util")] {
+/// use terminal_relaunch::errors::{RelaunchError, TermResult};
+/// use terminal_relaunch::pipeline::{LaunchOutcome, PipelineStep, ProbeOutcome, RelaunchPipeline, UnfitReason};
+/// use terminal_relaunch::recording::RecordingProvider;
+/// use terminal_relaunch::{TerminalProvider, TerminalType};
+///
+/// /// A terminal only finding out it can't run the program when launching..
+/// struct LateUnfit;
+///
+/// impl TerminalProvider for LateUnfit {
+///     fn terminal_type(&self) -> TerminalType { TerminalType::WezTerm }
+///     fn is_installed(&self) -> bool { true }
+///     fn relaunch_in_terminal(&self) -> TermResult<()> {
+///         Err(RelaunchError::UnfitTerminal(TerminalType::WezTerm, UnfitReason::CannotHostCommand))
+///     }
+/// }
+///
+/// let unfit = RecordingProvider::new(TerminalType::Alacritty).with_unfit_reason(UnfitReason::CannotHostCommand);
+/// let fallback = RecordingProvider::new(TerminalType::Hyper);
+/// let pipeline = RelaunchPipeline::without_decision().with_providers({
+///     let (unfit, fallback) = (unfit.clone(), fallback.clone());
+///     move |terminal| match terminal {
+///         TerminalType::Alacritty => Some(Box::new(unfit.clone()) as Box<dyn TerminalProvider>),
+///         TerminalType::WezTerm => Some(Box::new(LateUnfit)),
+///         TerminalType::Hyper => Some(Box::new(fallback.clone())),
+///         _ => None,
+///     }
+/// });
+///
+/// // Unfit terminals are skipped, whether found when probing or launching, rather than failing the relaunch..
+/// let steps: Vec<_> = pipeline.collect();
+/// let unfit_reason = UnfitReason::CannotHostCommand;
+/// assert!(steps.contains(&PipelineStep::Probed { terminal: TerminalType::Alacritty, outcome: ProbeOutcome::Unfit(unfit_reason) }));
+/// assert!(steps.contains(&PipelineStep::Launched { terminal: TerminalType::WezTerm, outcome: LaunchOutcome::Unfit(unfit_reason) }));
+/// assert_eq!(steps.last(), Some(&PipelineStep::Launched { terminal: TerminalType::Hyper, outcome: LaunchOutcome::Launched }));
+/// assert!(unfit.launches().is_empty());
+/// assert_eq!(fallback.launches().len(), 1);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnfitReason {
+    /// The terminal can be opened, but not told to run a program, e.g. `Hyper`, so relaunching in it would open
+    /// an empty window. Interactive shells (see `open_terminal_here()`) can still be opened.
+    CannotHostCommand,
+}
+
+impl UnfitReason {
+    /// Returns a short description of the reason.
+    #[inline]
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::CannotHostCommand => "The terminal can't run the relaunched program.",
+        }
+    }
+}
+
+impl Display for UnfitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+/// Represents the outcome of checking a preferred terminal as a relaunch candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProbeOutcome {
+    /// The terminal has no provider, so can't be relaunched in.
+    NoProvider,
+    /// The terminal is not installed.
+    NotInstalled,
+    /// The terminal is installed, but a program required to launch it could not be found.
+    MissingDependency(&'static str),
+    /// The terminal is installed, but can't be used for the launch.
+    Unfit(UnfitReason),
+    /// The terminal is installed, but would not improve on the current terminal.
+    NoImprovement,
+    /// The terminal was selected to relaunch in.
+    Selected,
+}
+
+/// Represents the outcome of launching a selected terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LaunchOutcome {
+    /// The program was relaunched, the current instance should exit.
+    Launched,
+    /// The terminal was removed or replaced since it was selected, the next candidate is tried.
+    Disappeared,
+    /// The terminal could not be found when launching, the next candidate is tried.
+    NotFound,
+    /// The terminal turned out to be unfit when launching, the next candidate is tried.
+    Unfit(UnfitReason),
+    /// The launch failed, see `RelaunchPipeline::into_result()` for the error.
+    Failed,
+}
+
+/// A single step of the relaunch process, see `RelaunchPipeline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PipelineStep {
+    /// The current terminal was detected and checked against the preferred requirements.
+    Decided {
+        /// The current terminal.
+        terminal: TerminalType,
+        /// Whether a relaunch should be attempted.
+        decision: RelaunchDecision,
+    },
+    /// A preferred terminal was checked as a relaunch candidate.
+    Probed {
+        /// The candidate terminal.
+        terminal: TerminalType,
+        /// The outcome of the check.
+        outcome: ProbeOutcome,
+    },
+    /// The selected terminal was launched.
+    Launched {
+        /// The selected terminal.
+        terminal: TerminalType,
+        /// The outcome of the launch.
+        outcome: LaunchOutcome,
+    },
+    /// Every preferred terminal was checked without a successful relaunch.
+    Exhausted,
+}
+
+impl Display for PipelineStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decided {
+                terminal,
+                decision: RelaunchDecision::Attempt,
+            } => write!(f, "Detected `{terminal}`, a relaunch will be attempted."),
+            Self::Decided {
+                terminal,
+                decision: RelaunchDecision::Skip(reason),
+            } => write!(f, "Detected `{terminal}`, skipping relaunch: {reason}"),
+            Self::Probed { terminal, outcome } => match outcome {
+                ProbeOutcome::NoProvider => write!(f, "`{terminal}` can't be relaunched in."),
+                ProbeOutcome::NotInstalled => write!(f, "`{terminal}` is not installed."),
+                ProbeOutcome::MissingDependency(dependency) => {
+                    write!(
+                        f,
+                        "`{terminal}` requires `{dependency}`, which could not be found."
+                    )
+                }
+                ProbeOutcome::Unfit(reason) => write!(f, "`{terminal}` can't be used: {reason}"),
+                ProbeOutcome::NoImprovement => {
+                    write!(f, "`{terminal}` would not improve on the current terminal.")
+                }
+                ProbeOutcome::Selected => write!(f, "Selected `{terminal}`."),
+            },
+            Self::Launched { terminal, outcome } => match outcome {
+                LaunchOutcome::Launched => write!(f, "Relaunched in `{terminal}`."),
+                LaunchOutcome::Disappeared => {
+                    write!(f, "`{terminal}` disappeared before relaunch.")
+                }
+                LaunchOutcome::NotFound => write!(f, "`{terminal}` could not be found."),
+                LaunchOutcome::Unfit(reason) => write!(f, "`{terminal}` can't be used: {reason}"),
+                LaunchOutcome::Failed => write!(f, "Failed to relaunch in `{terminal}`."),
+            },
+            Self::Exhausted => write!(f, "No preferred terminal could be relaunched in."),
+        }
+    }
+}
+
+/// The relaunch process as an iterator, where each call to `next()` performs a single step.
+///
+/// The steps are, in order:
+/// *   Deciding whether to relaunch (detecting the current terminal and checking its capabilities).
+/// *   Checking each preferred terminal for the current platform, until one is selected.
+/// *   Launching the selected terminal, falling back to checking the next preferred terminal if it's missing or
+///     unfit, see `UnfitReason`.
+///
+/// The pipeline ends after a skip decision, a launch (successful or not), or once every preferred terminal
+/// has been checked. If the only installed candidates were older installs of the current terminal, lacking the
+/// same capabilities, the relaunch is skipped with `SkipReason::NoImprovementPossible`, see `skip_reason()`.
+pub struct RelaunchPipeline {
+    decided: bool,
+    current: TerminalType,
+    no_improvement: bool,
+    skip_reason: Option<SkipReason>,
+    working_directory: PathBuf,
+    candidates: std::vec::IntoIter<TerminalType>,
+    providers: Box<ProviderLookup>,
+    selected: Option<Box<dyn TerminalProvider>>,
+    result: Option<TermResult<bool>>,
+}
+
+/// Returns the provider for a terminal type, see `RelaunchPipeline::with_providers()`.
+pub type ProviderLookup = dyn Fn(TerminalType) -> Option<Box<dyn TerminalProvider>>;
+
+impl RelaunchPipeline {
+    /// Creates a new pipeline using the current relaunch configuration.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            decided: false,
+            current: *CURRENT_TERMINAL,
+            no_improvement: false,
+            skip_reason: None,
+            working_directory: crate::terminal_providers::launch_working_directory(),
+            candidates: get_preferred_terminals_in(&SystemEnvironment, OperatingSystem::current())
+                .into_iter(),
+            providers: Box::new(get_provider_for_terminal),
+            selected: None,
+            result: None,
+        }
+    }
+
+    /// Creates a new pipeline that skips the decision step, and always searches for a terminal to relaunch in.
+    #[must_use]
+    pub fn without_decision() -> Self {
+        Self {
+            decided: true,
+            ..Self::new()
+        }
+    }
+
+    /// Replaces how the provider of each candidate is found, `get_provider_for_terminal()` by default.
+    ///
+    /// # Notes
+    /// Intended for custom providers, or for recording providers in tests (see `recording::RecordingProvider` and
+    /// `upgrade_preview_for()`), without registering them globally (see `registry::register_provider()`).
+    #[must_use]
+    pub fn with_providers(
+        mut self,
+        providers: impl Fn(TerminalType) -> Option<Box<dyn TerminalProvider>> + 'static,
+    ) -> Self {
+        self.providers = Box::new(providers);
+        self
+    }
+
+    /// Replaces the terminal that candidates must improve on, the current terminal by default, e.g. for testing the
+    /// selection as if running in another terminal, see `improves_on_current_terminal()`.
+    #[must_use]
+    pub fn with_current_terminal(mut self, terminal: TerminalType) -> Self {
+        self.current = terminal;
+        self
+    }
+
+    /// Replaces the working directory the relaunched program starts in, see `working_directory()`.
+    #[must_use]
+    pub fn with_working_directory(mut self, dir: PathBuf) -> Self {
+        self.working_directory = dir;
+        self
+    }
+
+    /// Returns the working directory the relaunched program starts in.
+    ///
+    /// # Notes
+    /// This is resolved when the pipeline is created (see `terminal_providers::launch_working_directory()`), so
+    /// other threads changing the process working directory afterwards don't affect the relaunch.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "test-util")] {
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use terminal_relaunch::config::{RelaunchConfig, set_relaunch_config};
+    /// use terminal_relaunch::pipeline::RelaunchPipeline;
+    /// use terminal_relaunch::recording::RecordingProvider;
+    /// use terminal_relaunch::registry::ScopedRegistry;
+    /// use terminal_relaunch::{OperatingSystem, get_preferred_terminals_for_os};
+    ///
+    /// // Every preferred terminal is installed, and records the directory it would launch in..
+    /// let recorders: Vec<RecordingProvider> = get_preferred_terminals_for_os(OperatingSystem::current())
+    ///     .map(RecordingProvider::new)
+    ///     .collect();
+    /// let mut scope = ScopedRegistry::new();
+    /// for recorder in &recorders {
+    ///     scope.register(recorder.clone());
+    /// }
+    /// let launched = || {
+    ///     recorders.iter().flat_map(RecordingProvider::launches).map(|launch| launch.working_directory).collect::<Vec<_>>()
+    /// };
+    /// let pipeline = RelaunchPipeline::without_decision;
+    ///
+    /// let temp = std::env::temp_dir();
+    /// let requested = temp.join(format!("relaunch-requested-{}", std::process::id()));
+    /// let elsewhere: Vec<_> = (0..4).map(|i| temp.join(format!("relaunch-elsewhere-{}-{i}", std::process::id()))).collect();
+    /// for dir in elsewhere.iter().chain([&requested]) {
+    ///     std::fs::create_dir_all(dir).unwrap();
+    /// }
+    ///
+    /// // Another thread keeps changing the process working directory while relaunches are planned..
+    /// let stop = Arc::new(AtomicBool::new(false));
+    /// let worker = std::thread::spawn({
+    ///     let (stop, elsewhere) = (Arc::clone(&stop), elsewhere.clone());
+    ///     move || {
+    ///         for dir in elsewhere.iter().cycle().take_while(|_| !stop.load(Ordering::Relaxed)) {
+    ///             std::env::set_current_dir(dir).unwrap();
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// set_relaunch_config(RelaunchConfig::new().working_directory(Some(requested.clone())));
+    /// for _ in 0..100 {
+    ///     let mut pipeline = pipeline();
+    ///     assert_eq!(pipeline.working_directory(), requested.as_path());
+    ///     assert!(pipeline.plan().is_some());
+    ///     std::thread::yield_now();
+    ///     assert!(pipeline.into_result().unwrap());
+    /// }
+    /// stop.store(true, Ordering::Relaxed);
+    /// worker.join().unwrap();
+    /// assert_eq!(launched(), vec![requested.clone(); 100]);
+    ///
+    /// // Otherwise, the directory is resolved when the pipeline is created, not when it launches..
+    /// set_relaunch_config(RelaunchConfig::new());
+    /// std::env::set_current_dir(&requested).unwrap();
+    /// let planned = pipeline();
+    /// std::env::set_current_dir(&elsewhere[0]).unwrap();
+    /// assert!(planned.into_result().unwrap());
+    /// assert_eq!(launched().last(), Some(&requested));
+    ///
+    /// std::env::set_current_dir(&temp).unwrap();
+    /// for dir in elsewhere.iter().chain([&requested]) {
+    ///     std::fs::remove_dir(dir).unwrap();
+    /// }
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn working_directory(&self) -> &Path {
+        &self.working_directory
+    }
+
+    /// Runs the steps up to selecting a terminal to relaunch in, without launching it.
+    ///
+    /// # Returns
+    /// *   The provider that the next step launches, continuing the pipeline launches it.
+    /// *   `None` if the pipeline finished without selecting a terminal, e.g. after a skip decision.
+    pub fn plan(&mut self) -> Option<&dyn TerminalProvider> {
+        while self.selected.is_none() && self.next().is_some() {}
+        self.selected.as_deref()
+    }
+
+    /// Returns why the relaunch was skipped, if it was, by the decision step or because no installed terminal
+    /// improves on the current terminal.
+    #[inline]
+    #[must_use]
+    pub fn skip_reason(&self) -> Option<SkipReason> {
+        self.skip_reason
+    }
+
+    /// Returns `true` once the pipeline has finished, i.e. `next()` will return `None`.
+    #[inline]
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Runs any remaining steps, and returns the result of the relaunch process.
+    ///
+    /// # Errors
+    /// Returns a `RelaunchError` if no preferred terminal is found or if the relaunch fails.
+    ///
+    /// # Returns
+    /// *   `Ok(true)` if the relaunch was successful, if this is returned, the current instance should exit.
+    /// *   `Ok(false)` if the relaunch was skipped, and program execution can continue as normal.
+    /// *   `Err(RelaunchError)` if no preferred terminal is found or if the relaunch fails.
+    pub fn into_result(mut self) -> TermResult<bool> {
+        self.by_ref().for_each(drop);
+        self.result.unwrap_or(Ok(false))
+    }
+
+    /// Performs the decision step.
+    fn decide(&mut self) -> PipelineStep {
+        self.decided = true;
+
+        let decision = crate::decision::relaunch_decision();
+        if let RelaunchDecision::Skip(reason) = decision {
+            self.skip_reason = Some(reason);
+            self.result = Some(Ok(false));
+        }
+
+        PipelineStep::Decided {
+            terminal: self.current,
+            decision,
+        }
+    }
+
+    /// Performs the launch step for the selected provider.
+    fn launch(&mut self, provider: &dyn TerminalProvider) -> PipelineStep {
+        let terminal = provider.terminal_type();
+
+        // The terminal may have been removed or replaced (e.g. by an update) since it was found..
+        if let Some(path) = provider.installed_path()
+            && !path.exists()
+        {
+            logging::warning!(
+                "`{terminal}` disappeared before relaunch, trying the next preferred terminal."
+            );
+            return PipelineStep::Launched {
+                terminal,
+                outcome: LaunchOutcome::Disappeared,
+            };
+        }
+
+        let relaunch =
+            crate::terminal_providers::with_working_directory(&self.working_directory, || {
+                #[cfg(feature = "handoff")]
+                {
+                    let handoff = crate::handoff::RelaunchHandoff::for_provider(provider);
+                    crate::handoff::with_pending_handoff(handoff, || {
+                        provider.relaunch_in_terminal()
+                    })
+                }
+                #[cfg(not(feature = "handoff"))]
+                {
+                    provider.relaunch_in_terminal()
+                }
+            });
+        let outcome = match relaunch {
+            Ok(()) => {
+                record_relaunch();
+                self.result = Some(Ok(true));
+                LaunchOutcome::Launched
+            }
+            Err(RelaunchError::IOError(error)) if error.kind() == std::io::ErrorKind::NotFound => {
+                logging::warning!(
+                    "`{terminal}` could not be found during relaunch, trying the next preferred terminal."
+                );
+                LaunchOutcome::NotFound
+            }
+            Err(RelaunchError::UnfitTerminal(_, reason)) => {
+                logging::warning!(
+                    "`{terminal}` can't be used for the relaunch, trying the next preferred terminal."
+                );
+                LaunchOutcome::Unfit(reason)
+            }
+            Err(error) => {
+                self.result = Some(Err(error));
+                LaunchOutcome::Failed
+            }
+        };
+
+        PipelineStep::Launched { terminal, outcome }
+    }
+
+    /// Finishes the pipeline once every preferred terminal has been checked.
+    fn exhaust(&mut self) -> PipelineStep {
+        // The only installed candidates were instances of the current terminal lacking the same capabilities, so a
+        // relaunch can't help, rather than failing..
+        if self.no_improvement {
+            logging::warning!("No installed terminal would improve on the current terminal.");
+            self.skip_reason = Some(SkipReason::NoImprovementPossible);
+            self.result = Some(Ok(false));
+        } else {
+            logging::warning!("No alternative preferred terminal found for relaunch.");
+            self.result = Some(Err(crate::no_alternative_terminal_error()));
+        }
+
+        PipelineStep::Exhausted
+    }
+}
+
+impl Default for RelaunchPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for RelaunchPipeline {
+    type Item = PipelineStep;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished() {
+            return None;
+        }
+        if !self.decided {
+            return Some(self.decide());
+        }
+        if let Some(provider) = self.selected.take() {
+            return Some(self.launch(provider.as_ref()));
+        }
+
+        let Some(terminal) = self.candidates.next() else {
+            return Some(self.exhaust());
+        };
+        let (outcome, provider) = probe_terminal(terminal, self.current, &self.providers);
+        self.no_improvement |= outcome == ProbeOutcome::NoImprovement;
+        self.selected = provider;
+
+        Some(PipelineStep::Probed { terminal, outcome })
+    }
+}
+
+/// Applies the given configuration (see `config::set_relaunch_config()`), and returns a pipeline for the relaunch process.
+#[must_use]
+pub fn relaunch_pipeline(config: RelaunchConfig) -> RelaunchPipeline {
+    crate::config::set_relaunch_config(config);
+    RelaunchPipeline::new()
+}
+
+/// A comparison of the current terminal and the terminal a relaunch would select, see `upgrade_preview()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpgradePreview {
+    /// The current terminal.
+    pub current: TerminalType,
+    /// The capabilities of the current terminal.
+    pub current_capabilities: TerminalCapabilities,
+    /// The terminal a relaunch would select.
+    pub target: TerminalType,
+    /// The capabilities of the selected terminal, for its installed version if known.
+    pub target_capabilities: TerminalCapabilities,
+    /// The capabilities the selected terminal has, but the current terminal lacks.
+    pub gained: TerminalCapabilities,
+    /// The capabilities the current terminal has, but the selected terminal lacks.
+    pub lost: TerminalCapabilities,
+}
+
+impl UpgradePreview {
+    /// Returns the comparison as segments, the current terminal and its capabilities, then the selected terminal
+    /// and its capabilities, as `TerminalType::format_segments()`.
+    #[must_use]
+    pub fn segments(&self) -> Vec<InfoSegment> {
+        let mut segments = terminal_segments(self.current, self.current_capabilities);
+        segments.push(InfoSegment::new(" -> ", SegmentKind::Separator));
+        segments.extend(terminal_segments(self.target, self.target_capabilities));
+        segments
+    }
+
+    /// Returns the comparison as a single line of plain text, e.g.
+    /// `"Windows CMD (cmd.exe), Enhanced Colours -> Windows Terminal (wt.exe), Full Unicode, Enhanced Colours, Hyperlinks"`.
+    #[inline]
+    #[must_use]
+    pub fn summary(&self) -> String {
+        self.segments()
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect()
+    }
+}
+
+/// Returns the segments of a terminal with the given capabilities, for `UpgradePreview::segments()`.
+fn terminal_segments(
+    terminal: TerminalType,
+    capabilities: TerminalCapabilities,
+) -> Vec<InfoSegment> {
+    let mut segments = terminal.segments_with(
+        None,
+        capabilities.full_unicode,
+        capabilities.rgb_ansi_colours,
+        false,
+    );
+    if capabilities.hyperlinks {
+        segments.push(InfoSegment::new(", ", SegmentKind::Separator));
+        segments.push(InfoSegment::new(
+            "Hyperlinks",
+            SegmentKind::CapabilityPresent,
+        ));
+    }
+    segments
+}
+
+/// Returns a comparison of the current terminal and the terminal `try_relaunch_in_preferred_terminal()` would
+/// select, without launching it, e.g. for a confirmation prompt.
+///
+/// # Returns
+/// `None` if no terminal would be selected.
+#[inline]
+#[must_use]
+pub fn upgrade_preview() -> Option<UpgradePreview> {
+    upgrade_preview_for(RelaunchPipeline::without_decision())
+}
+
+/// Returns a comparison of the current terminal and the terminal the given pipeline would select, without
+/// launching it, see `RelaunchPipeline::plan()`.
+///
+/// # Notes
+/// The terminal is selected by the pipeline itself, so a relaunch with the same pipeline always launches the
+/// previewed terminal first. It may still fall back to the next candidate, if the previewed terminal
+/// disappears before it's launched.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "test-util")] {
+/// use terminal_relaunch::pipeline::{LaunchOutcome, PipelineStep, RelaunchPipeline, upgrade_preview_for};
+/// use terminal_relaunch::recording::RecordingProvider;
+/// use terminal_relaunch::{TerminalProvider, TerminalType};
+///
+/// // Installed terminals, which only record their launches..
+/// type Installed = &'static [(TerminalType, Option<&'static str>)];
+/// let pipeline = |installed: Installed| {
+///     RelaunchPipeline::without_decision().with_providers(move |terminal| {
+///         let (terminal, dependency) = installed.iter().find(|(kind, _)| *kind == terminal)?;
+///         let recorder = RecordingProvider::new(*terminal);
+///         let recorder = dependency.map_or(recorder.clone(), |dependency| recorder.with_missing_dependency(dependency));
+///         Some(Box::new(recorder) as Box<dyn TerminalProvider>)
+///     })
+/// };
+///
+/// let sets: [Installed; 4] = [
+///     &[],
+///     &[(TerminalType::Alacritty, None)],
+///     &[(TerminalType::Alacritty, None), (TerminalType::WezTerm, None)],
+///     &[(TerminalType::WezTerm, Some("wezterm-gui")), (TerminalType::Alacritty, None)],
+/// ];
+/// for installed in sets {
+///     let preview = upgrade_preview_for(pipeline(installed));
+///     let launched = pipeline(installed).find_map(|step| match step {
+///         PipelineStep::Launched { terminal, outcome: LaunchOutcome::Launched } => Some(terminal),
+///         _ => None,
+///     });
+///     assert_eq!(preview.map(|preview| preview.target), launched);
+///
+///     let Some(preview) = preview else { continue };
+///     let (current, target) = (preview.current_capabilities, preview.target_capabilities);
+///     for (gained, lost, current, target) in [
+///         (preview.gained.full_unicode, preview.lost.full_unicode, current.full_unicode, target.full_unicode),
+///         (preview.gained.rgb_ansi_colours, preview.lost.rgb_ansi_colours, current.rgb_ansi_colours, target.rgb_ansi_colours),
+///         (preview.gained.hyperlinks, preview.lost.hyperlinks, current.hyperlinks, target.hyperlinks),
+///     ] {
+///         assert_eq!(gained, target && !current);
+///         assert_eq!(lost, current && !target);
+///     }
+///     assert!(preview.summary().starts_with(preview.current.name()));
+///     assert!(preview.summary().contains(&format!(" -> {}", preview.target.name())));
+/// }
+/// assert_eq!(upgrade_preview_for(pipeline(&[])), None);
+/// # }
+/// ```
+#[must_use]
+pub fn upgrade_preview_for(mut pipeline: RelaunchPipeline) -> Option<UpgradePreview> {
+    let provider = pipeline.plan()?;
+
+    let current = *CURRENT_TERMINAL;
+    let current_capabilities = resolve_capabilities(current, CURRENT_TERMINAL_VERSION.as_ref());
+    let target = provider.terminal_type();
+    let target_capabilities = resolve_capabilities(target, provider.installed_version().as_ref());
+
+    Some(UpgradePreview {
+        current,
+        current_capabilities,
+        target,
+        target_capabilities,
+        gained: target_capabilities.difference(&current_capabilities),
+        lost: current_capabilities.difference(&target_capabilities),
+    })
+}
+
+/// Checks if the given preferred terminal can be relaunched in from the given current terminal, returning its
+/// provider if it was selected.
+pub(crate) fn probe_terminal(
+    terminal: TerminalType,
+    current: TerminalType,
+    providers: &dyn Fn(TerminalType) -> Option<Box<dyn TerminalProvider>>,
+) -> (ProbeOutcome, Option<Box<dyn TerminalProvider>>) {
+    logging::info!("Testing if preferred terminal `{terminal}` is installed.");
+    let Some(provider) = providers(terminal) else {
+        return (ProbeOutcome::NoProvider, None);
+    };
+    if !provider.is_installed() {
+        return (ProbeOutcome::NotInstalled, None);
+    }
+    logging::info!("`{terminal}` is installed!");
+
+    if let Some(dependency) = provider.missing_dependency() {
+        logging::warning!("`{terminal}` requires `{dependency}`, which could not be found.");
+        return (ProbeOutcome::MissingDependency(dependency), None);
+    }
+    if let Some(reason) = provider.unfit_reason() {
+        logging::info!("`{terminal}` can't be used for the relaunch: {reason}");
+        return (ProbeOutcome::Unfit(reason), None);
+    }
+    if !improves_on_terminal(provider.as_ref(), current) {
+        return (ProbeOutcome::NoImprovement, None);
+    }
+
+    (ProbeOutcome::Selected, Some(provider))
+}
+
+/// Records the time of a successful relaunch, if a cooldown is configured.
+fn record_relaunch() {
+    #[cfg(feature = "config")]
+    {
+        let config = crate::config::relaunch_config();
+        if config.cooldown.is_some()
+            && let Err(error) = crate::persistence::record_relaunch(
+                config.choice_namespace(),
+                std::time::SystemTime::now(),
+            )
+        {
+            logging::warning!(
+                "Failed to record relaunch time: {}",
+                logging::sanitize_for_log(&error.to_string())
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::{LaunchOutcome, PipelineStep, ProbeOutcome, RelaunchPipeline};
+    use crate::capabilities::{
+        CapabilityRule, PartialCapabilities, clear_capability_overrides,
+        register_capability_override,
+    };
+    use crate::decision::{RelaunchDecision, SkipReason};
+    use crate::errors::{RelaunchError, TermResult};
+    use crate::recording::RecordingProvider;
+    use crate::terminal_providers::Resolved;
+    use crate::test_support::{reading_globals, writing_globals};
+    use crate::version::TerminalVersion;
+    use crate::{OperatingSystem, TerminalProvider, TerminalType, current_terminal};
+
+    /// A mock probe, finding the paths in turn on each lookup, and the last one from then on.
+    #[derive(Clone)]
+    struct FlippingProbe {
+        paths: Arc<Vec<Option<PathBuf>>>,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl FlippingProbe {
+        fn new(paths: Vec<Option<PathBuf>>) -> Self {
+            Self {
+                paths: Arc::new(paths),
+                lookups: Arc::default(),
+            }
+        }
+
+        fn lookup(&self) -> Option<PathBuf> {
+            let lookup = self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.paths[lookup.min(self.paths.len() - 1)].clone()
+        }
+    }
+
+    /// A provider resolving its path with a `FlippingProbe` as the built-in providers do, recording the paths it
+    /// launched.
+    struct ProbedProvider {
+        terminal: TerminalType,
+        probe: FlippingProbe,
+        path: Resolved<PathBuf>,
+        launched: Arc<Mutex<Vec<PathBuf>>>,
+    }
+
+    impl TerminalProvider for ProbedProvider {
+        fn terminal_type(&self) -> TerminalType {
+            self.terminal
+        }
+
+        fn is_installed(&self) -> bool {
+            self.installed_path().is_some()
+        }
+
+        fn installed_path(&self) -> Option<PathBuf> {
+            self.path.get_or_resolve(|| self.probe.lookup()).cloned()
+        }
+
+        fn relaunch_in_terminal(&self) -> TermResult<()> {
+            let path = self.installed_path().expect("launched while not installed");
+            self.launched.lock().unwrap().push(path);
+            Ok(())
+        }
+    }
+
+    /// The first two candidates of a pipeline that would improve on the current terminal.
+    fn candidates() -> (TerminalType, TerminalType) {
+        let mut candidates = crate::get_preferred_terminals_for_os(OperatingSystem::current())
+            .filter(|terminal| *terminal != current_terminal());
+        (candidates.next().unwrap(), candidates.next().unwrap())
+    }
+
+    /// A pipeline where the given candidates are found with their probes, and every other candidate is missing.
+    fn pipeline(
+        probes: Vec<(TerminalType, FlippingProbe)>,
+        launched: &Arc<Mutex<Vec<PathBuf>>>,
+    ) -> RelaunchPipeline {
+        let launched = Arc::clone(launched);
+        RelaunchPipeline::without_decision().with_providers(move |terminal| {
+            let (_, probe) = probes
+                .iter()
+                .find(|(candidate, _)| *candidate == terminal)?;
+            Some(Box::new(ProbedProvider {
+                terminal,
+                probe: probe.clone(),
+                path: Resolved::default(),
+                launched: Arc::clone(&launched),
+            }))
+        })
+    }
+
+    /// Creates an empty file to stand in for an installed terminal.
+    fn installed_file(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("terminal-relaunch-{name}-{}", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        path
+    }
+
+    #[test]
+    fn launches_the_path_resolved_at_selection() {
+        let _globals = reading_globals();
+        let (first, _) = candidates();
+        let (selected, replaced) = (installed_file("selected"), installed_file("replaced"));
+        let probe = FlippingProbe::new(vec![Some(selected.clone()), Some(replaced.clone())]);
+        let launched = Arc::default();
+
+        let relaunched = pipeline(vec![(first, probe.clone())], &launched).into_result();
+
+        assert!(relaunched.unwrap());
+        assert_eq!(*launched.lock().unwrap(), std::slice::from_ref(&selected));
+        assert_eq!(probe.lookups.load(Ordering::SeqCst), 1);
+        for path in [selected, replaced] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn disappeared_terminal_falls_through_to_the_next_candidate() {
+        let _globals = reading_globals();
+        let (first, second) = candidates();
+        let (removed, fallback) = (installed_file("removed"), installed_file("fallback"));
+        // The lookup would no longer find the removed terminal, but the resolved path is checked rather than
+        // looked up again..
+        let probes = vec![
+            (first, FlippingProbe::new(vec![Some(removed.clone()), None])),
+            (second, FlippingProbe::new(vec![Some(fallback.clone())])),
+        ];
+        let launched = Arc::default();
+        let mut pipeline = pipeline(probes, &launched);
+
+        let mut steps = Vec::new();
+        for step in pipeline.by_ref() {
+            if step
+                == (PipelineStep::Probed {
+                    terminal: first,
+                    outcome: ProbeOutcome::Selected,
+                })
+            {
+                std::fs::remove_file(&removed).unwrap();
+            }
+            steps.push(step);
+        }
+
+        let launch_steps: Vec<_> = steps
+            .iter()
+            .filter(|step| matches!(step, PipelineStep::Launched { .. }))
+            .copied()
+            .collect();
+        assert_eq!(
+            launch_steps,
+            [
+                PipelineStep::Launched {
+                    terminal: first,
+                    outcome: LaunchOutcome::Disappeared,
+                },
+                PipelineStep::Launched {
+                    terminal: second,
+                    outcome: LaunchOutcome::Launched,
+                },
+            ]
+        );
+        assert!(pipeline.into_result().unwrap());
+        assert_eq!(*launched.lock().unwrap(), std::slice::from_ref(&fallback));
+        std::fs::remove_file(fallback).unwrap();
+    }
+
+    /// A provider of an installed terminal with the given version, recording if it was launched.
+    struct VersionedProvider {
+        terminal: TerminalType,
+        version: Option<TerminalVersion>,
+        launched: Arc<Mutex<Vec<TerminalType>>>,
+    }
+
+    impl TerminalProvider for VersionedProvider {
+        fn terminal_type(&self) -> TerminalType {
+            self.terminal
+        }
+
+        fn is_installed(&self) -> bool {
+            true
+        }
+
+        fn installed_version(&self) -> Option<TerminalVersion> {
+            self.version
+        }
+
+        fn relaunch_in_terminal(&self) -> TermResult<()> {
+            self.launched.lock().unwrap().push(self.terminal);
+            Ok(())
+        }
+    }
+
+    /// What a pipeline run by `from_old_wezterm()` did.
+    struct Run {
+        probes: Vec<(TerminalType, ProbeOutcome)>,
+        skip_reason: Option<SkipReason>,
+        result: TermResult<bool>,
+        launched: Vec<TerminalType>,
+    }
+
+    /// Runs a pipeline from an old `WezTerm` lacking full unicode (as `WezTerm <1.0` is registered as lacking it),
+    /// with the given terminals installed.
+    fn from_old_wezterm(installed: Vec<(TerminalType, Option<TerminalVersion>)>) -> Run {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        register_capability_override(CapabilityRule {
+            terminal: TerminalType::WezTerm,
+            version: Some("<1.0".parse().unwrap()),
+            set: PartialCapabilities {
+                full_unicode: Some(false),
+                ..PartialCapabilities::NONE
+            },
+        });
+
+        let launched = Arc::new(Mutex::new(Vec::new()));
+        let mut pipeline = RelaunchPipeline::without_decision()
+            .with_current_terminal(TerminalType::WezTerm)
+            .with_providers({
+                let launched = Arc::clone(&launched);
+                move |terminal| {
+                    let (_, version) = installed
+                        .iter()
+                        .find(|(installed, _)| *installed == terminal)?;
+                    Some(Box::new(VersionedProvider {
+                        terminal,
+                        version: *version,
+                        launched: Arc::clone(&launched),
+                    }))
+                }
+            });
+        let probes = pipeline
+            .by_ref()
+            .filter_map(|step| match step {
+                PipelineStep::Probed { terminal, outcome } => Some((terminal, outcome)),
+                _ => None,
+            })
+            .collect();
+        let skip_reason = pipeline.skip_reason();
+        let result = pipeline.into_result();
+        clear_capability_overrides();
+
+        let launched = launched.lock().unwrap().clone();
+        Run {
+            probes,
+            skip_reason,
+            result,
+            launched,
+        }
+    }
+
+    #[test]
+    fn same_type_older_install_is_skipped_without_an_error() {
+        let Run {
+            probes,
+            skip_reason,
+            result,
+            launched,
+        } = from_old_wezterm(vec![(
+            TerminalType::WezTerm,
+            Some(TerminalVersion::new(0, 9, 0)),
+        )]);
+
+        assert!(probes.contains(&(TerminalType::WezTerm, ProbeOutcome::NoImprovement)));
+        assert_eq!(skip_reason, Some(SkipReason::NoImprovementPossible));
+        assert!(!result.unwrap());
+        assert!(launched.is_empty());
+    }
+
+    #[test]
+    fn same_type_newer_install_is_relaunched_in() {
+        let Run {
+            probes,
+            skip_reason,
+            result,
+            launched,
+        } = from_old_wezterm(vec![(
+            TerminalType::WezTerm,
+            Some(TerminalVersion::new(1, 2, 0)),
+        )]);
+
+        assert!(probes.contains(&(TerminalType::WezTerm, ProbeOutcome::Selected)));
+        assert_eq!(skip_reason, None);
+        assert!(result.unwrap());
+        assert_eq!(launched, [TerminalType::WezTerm]);
+    }
+
+    #[test]
+    fn different_type_install_is_relaunched_in_over_an_older_same_type_install() {
+        let Run {
+            probes,
+            skip_reason,
+            result,
+            launched,
+        } = from_old_wezterm(vec![
+            (TerminalType::WezTerm, Some(TerminalVersion::new(0, 9, 0))),
+            (TerminalType::Alacritty, None),
+        ]);
+
+        assert!(probes.contains(&(TerminalType::Alacritty, ProbeOutcome::Selected)));
+        assert_eq!(skip_reason, None);
+        assert!(result.unwrap());
+        assert_eq!(launched, [TerminalType::Alacritty]);
+    }
+
+    #[test]
+    fn nothing_installed_is_an_error_rather_than_a_skip() {
+        let Run {
+            skip_reason,
+            result,
+            launched,
+            ..
+        } = from_old_wezterm(Vec::new());
+
+        assert_eq!(skip_reason, None);
+        assert!(matches!(
+            result,
+            Err(RelaunchError::NoAlternativeTerminalFound)
+        ));
+        assert!(launched.is_empty());
+    }
+
+    #[test]
+    fn terminals_missing_osascript_are_passed_over() {
+        let _globals = reading_globals();
+        let (first, second) = candidates();
+        let run = |installed: &[RecordingProvider]| {
+            let installed = installed.to_vec();
+            let mut pipeline =
+                RelaunchPipeline::without_decision().with_providers(move |terminal| {
+                    let recorder = installed
+                        .iter()
+                        .find(|recorder| recorder.terminal_type() == terminal)?;
+                    Some(Box::new(recorder.clone()) as Box<dyn TerminalProvider>)
+                });
+            let steps: Vec<PipelineStep> = pipeline.by_ref().collect();
+            (steps, pipeline.into_result())
+        };
+        let missing_osascript = RecordingProvider::new(first).with_missing_dependency("osascript");
+        let recorders = [missing_osascript.clone(), RecordingProvider::new(second)];
+        let launched = || {
+            recorders
+                .iter()
+                .flat_map(RecordingProvider::launches)
+                .map(|launch| launch.terminal)
+                .collect::<Vec<_>>()
+        };
+
+        // The preferred terminal needing `osascript` is skipped for the next, which doesn't..
+        let (steps, result) = run(&recorders);
+        let missing = PipelineStep::Probed {
+            terminal: first,
+            outcome: ProbeOutcome::MissingDependency("osascript"),
+        };
+        assert!(steps.contains(&missing));
+        assert_eq!(
+            missing.to_string(),
+            format!("`{first}` requires `osascript`, which could not be found.")
+        );
+        assert!(steps.contains(&PipelineStep::Launched {
+            terminal: second,
+            outcome: LaunchOutcome::Launched,
+        }));
+        assert!(result.unwrap());
+        assert_eq!(launched(), [second]);
+
+        // ..and if it's the only one installed, nothing is launched..
+        recorders[1].clear();
+        let (steps, result) = run(&[missing_osascript]);
+        assert!(steps.contains(&missing));
+        assert_eq!(steps.last(), Some(&PipelineStep::Exhausted));
+        assert!(result.is_err());
+        assert!(launched().is_empty());
+    }
+
+    /// How a candidate of `scripted_run()` behaves, candidates without a script have no provider.
+    #[derive(Debug, Clone, Copy)]
+    enum Script {
+        NotInstalled,
+        Launches,
+        NotFound,
+        Fails,
+    }
+
+    /// A provider behaving as its script says, recording the launches it was asked for.
+    struct ScriptedProvider {
+        terminal: TerminalType,
+        script: Script,
+        launched: Arc<Mutex<Vec<TerminalType>>>,
+    }
+
+    impl TerminalProvider for ScriptedProvider {
+        fn terminal_type(&self) -> TerminalType {
+            self.terminal
+        }
+
+        fn is_installed(&self) -> bool {
+            !matches!(self.script, Script::NotInstalled)
+        }
+
+        fn relaunch_in_terminal(&self) -> TermResult<()> {
+            self.launched.lock().unwrap().push(self.terminal);
+            match self.script {
+                Script::NotInstalled => unreachable!("launched while not installed"),
+                Script::Launches => Ok(()),
+                Script::NotFound => Err(std::io::Error::from(std::io::ErrorKind::NotFound).into()),
+                Script::Fails => Err(std::io::Error::other("spawn failed").into()),
+            }
+        }
+    }
+
+    /// Runs a pipeline without the decision step, with the preferred candidates at the given indices scripted.
+    fn scripted_run(
+        scripts: &[(usize, Script)],
+    ) -> (
+        Vec<TerminalType>,
+        Vec<PipelineStep>,
+        TermResult<bool>,
+        Vec<TerminalType>,
+    ) {
+        let candidates: Vec<_> =
+            crate::get_preferred_terminals_for_os(OperatingSystem::current()).collect();
+        let scripts: Vec<_> = scripts
+            .iter()
+            .map(|(index, script)| (candidates[*index], *script))
+            .collect();
+
+        let launched = Arc::new(Mutex::new(Vec::new()));
+        let mut pipeline = RelaunchPipeline::without_decision()
+            .with_current_terminal(TerminalType::Unknown)
+            .with_providers({
+                let launched = Arc::clone(&launched);
+                move |terminal| {
+                    let (_, script) = scripts.iter().find(|(scripted, _)| *scripted == terminal)?;
+                    Some(Box::new(ScriptedProvider {
+                        terminal,
+                        script: *script,
+                        launched: Arc::clone(&launched),
+                    }))
+                }
+            });
+        let steps = pipeline.by_ref().collect();
+        let result = pipeline.into_result();
+
+        let launched = launched.lock().unwrap().clone();
+        (candidates, steps, result, launched)
+    }
+
+    fn probed(terminal: TerminalType, outcome: ProbeOutcome) -> PipelineStep {
+        PipelineStep::Probed { terminal, outcome }
+    }
+
+    fn launched(terminal: TerminalType, outcome: LaunchOutcome) -> PipelineStep {
+        PipelineStep::Launched { terminal, outcome }
+    }
+
+    #[test]
+    fn steps_of_the_main_scenarios() {
+        let _globals = reading_globals();
+
+        // The first preferred terminal is installed, and launched..
+        let (c, steps, result, launches) =
+            scripted_run(&[(0, Script::Launches), (1, Script::Launches)]);
+        assert_eq!(
+            steps,
+            [
+                probed(c[0], ProbeOutcome::Selected),
+                launched(c[0], LaunchOutcome::Launched),
+            ]
+        );
+        assert!(result.unwrap());
+        assert_eq!(launches, [c[0]]);
+
+        // ..candidates without a provider, not installed or not found on launch are passed over..
+        let (c, steps, result, launches) = scripted_run(&[
+            (1, Script::NotInstalled),
+            (2, Script::NotFound),
+            (3, Script::Launches),
+        ]);
+        assert_eq!(
+            steps,
+            [
+                probed(c[0], ProbeOutcome::NoProvider),
+                probed(c[1], ProbeOutcome::NotInstalled),
+                probed(c[2], ProbeOutcome::Selected),
+                launched(c[2], LaunchOutcome::NotFound),
+                probed(c[3], ProbeOutcome::Selected),
+                launched(c[3], LaunchOutcome::Launched),
+            ]
+        );
+        assert!(result.unwrap());
+        assert_eq!(launches, [c[2], c[3]]);
+
+        // ..a failed launch ends the pipeline with its error, without trying the next candidate..
+        let (c, steps, result, launches) =
+            scripted_run(&[(0, Script::Fails), (1, Script::Launches)]);
+        assert_eq!(
+            steps,
+            [
+                probed(c[0], ProbeOutcome::Selected),
+                launched(c[0], LaunchOutcome::Failed),
+            ]
+        );
+        assert!(matches!(result, Err(RelaunchError::IOError(_))));
+        assert_eq!(launches, [c[0]]);
+
+        // ..and without an installed candidate, every one is probed before the pipeline is exhausted..
+        let (c, steps, result, launches) = scripted_run(&[(0, Script::NotInstalled)]);
+        let mut expected = vec![probed(c[0], ProbeOutcome::NotInstalled)];
+        expected.extend(
+            c[1..]
+                .iter()
+                .map(|terminal| probed(*terminal, ProbeOutcome::NoProvider)),
+        );
+        expected.push(PipelineStep::Exhausted);
+        assert_eq!(steps, expected);
+        assert!(matches!(
+            result,
+            Err(RelaunchError::NoAlternativeTerminalFound)
+        ));
+        assert!(launches.is_empty());
+    }
+
+    #[test]
+    fn skipped_decision_is_the_only_step() {
+        let _globals = reading_globals();
+        let mut pipeline = RelaunchPipeline::new().with_providers(|_| {
+            panic!("a candidate was probed");
+        });
+
+        let Some(PipelineStep::Decided { terminal, decision }) = pipeline.next() else {
+            panic!("the decision isn't the first step");
+        };
+        assert_eq!(terminal, current_terminal());
+        if let RelaunchDecision::Skip(reason) = decision {
+            assert_eq!(pipeline.next(), None);
+            assert_eq!(pipeline.skip_reason(), Some(reason));
+            assert!(!pipeline.into_result().unwrap());
+        }
+    }
+}
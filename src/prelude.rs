@@ -0,0 +1,61 @@
+//! The commonly used API surface, intended to be glob imported.
+//!
+//! This module is the stability boundary of the library: items re-exported here keep their paths and
+//! signatures across minor releases, while items only reachable through their own modules may be moved
+//! or refined. Removing an item, or changing its signature, is a breaking change.
+//!
+//! # Example
+//! ```rust,no_run
+//! use terminal_relaunch::prelude::*;
+//!
+//! let summary = relaunch_if_available_or_continue();
+//! if summary.is_degraded() {
+//!     eprintln!("Running with reduced terminal features..");
+//! }
+//!
+//! println!("Terminal information: {}", TerminalInfo::current().effective_format());
+//! ```
+//!
+//! # Signatures
+//! The signatures below are checked on every test run, so changing a prelude item fails to compile here,
+//! and has to be a deliberate decision.
+//! ```rust
+//! use terminal_relaunch::prelude::*;
+//!
+//! let _: fn() -> RelaunchAttemptSummary = relaunch_if_available_or_continue;
+//! let _: fn() -> TermResult<bool> = relaunch_if_available;
+//! let _: fn() -> TermResult<()> = relaunch_if_available_and_exit;
+//! let _: fn(i32) -> TermResult<()> = relaunch_if_available_and_exit_with;
+//! let _: fn() -> bool = should_attempt_relaunch;
+//! let _: fn() -> TerminalType = current_terminal;
+//! let _: fn() -> bool = supports_full_unicode;
+//! let _: fn() -> bool = supports_rgb_ansi_colours;
+//! let _: fn() -> ColourDepth = colour_depth;
+//! let _: fn() -> RelaunchDecision = relaunch_decision;
+//! let _: fn(RelaunchConfig) = set_relaunch_config;
+//! let _: fn() -> RelaunchConfig = RelaunchConfig::new;
+//! let _: fn(RelaunchConfig, WindowMode) -> RelaunchConfig = RelaunchConfig::window_mode;
+//! let _: fn(&TerminalType) -> &'static str = TerminalType::name;
+//! let _: fn(&TerminalType) -> String = TerminalType::verbose_format;
+//! let _: fn(&TerminalType) -> bool = TerminalType::is_preferred;
+//! let _: fn() -> TerminalInfo = TerminalInfo::current;
+//! let _: fn(&TerminalInfo) -> EffectiveCapabilities = TerminalInfo::effective_capabilities;
+//! let _: fn(&TerminalInfo) -> String = TerminalInfo::effective_format;
+//! let _: fn(&RelaunchAttemptSummary) -> bool = RelaunchAttemptSummary::is_degraded;
+//! let _: fn(&RelaunchDecision) -> bool = RelaunchDecision::should_attempt;
+//! let _: fn() -> OperatingSystem = OperatingSystem::current;
+//! let _: fn(OutputStyle) = set_output_style;
+//! ```
+
+pub use crate::capabilities::{ColourDepth, colour_depth};
+pub use crate::config::{RelaunchConfig, WindowMode, set_relaunch_config};
+pub use crate::decision::{RelaunchDecision, SkipReason, relaunch_decision};
+pub use crate::errors::{RelaunchError, TermResult};
+pub use crate::info::{EffectiveCapabilities, TerminalInfo};
+pub use crate::output::{OutputStyle, set_output_style};
+pub use crate::{
+    OperatingSystem, RelaunchAttemptSummary, TerminalProvider, TerminalType, current_terminal,
+    relaunch_if_available, relaunch_if_available_and_exit, relaunch_if_available_and_exit_with,
+    relaunch_if_available_or_continue, should_attempt_relaunch, supports_full_unicode,
+    supports_rgb_ansi_colours,
+};
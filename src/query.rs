@@ -0,0 +1,977 @@
+//! Querying the terminal directly over the TTY, for capabilities that can't be reliably detected from
+//! the environment (e.g. through SSH, where most environment variables are not forwarded).
+//!
+//! Queries put the controlling terminal into raw mode, write a request, and read the reply until it is
+//! complete or the timeout expires. Every query is followed by a primary device attributes (`DA1`) request,
+//! which all terminals answer, so terminals that ignore the query don't cost the full timeout.
+//!
+//! Applications that already enabled raw mode can query safely, the mode is left untouched (see `QuerySession`).
+//! Console queries on `Windows` are unsupported.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::errors::{RelaunchError, TermResult};
+
+pub use tty::SystemTty;
+
+/// Escape character.
+const ESC: u8 = 0x1b;
+/// The `BEL` control character, also terminating some replies.
+const BEL: u8 = 0x07;
+/// The 8-bit string terminator (`ST`), the equivalent of `ESC \`.
+const ST_8BIT: u8 = 0x9c;
+/// Primary device attributes (`DA1`) request.
+const DA1_REQUEST: &[u8] = b"\x1b[c";
+/// Terminal name and version (`XTVERSION`) request.
+const XTVERSION_REQUEST: &[u8] = b"\x1b[>0q";
+/// How long to wait for each chunk of a late reply when draining input.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// Set while a `QuerySession` is open, as concurrent queries would read each other's replies.
+static QUERY_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// The connection to a terminal used by a `QuerySession`, implemented by `SystemTty` for the controlling terminal.
+pub trait TtyTransport {
+    /// Returns `true` if the terminal is already in raw mode, e.g. enabled by the application.
+    ///
+    /// # Errors
+    /// Returns a `RelaunchError` if the terminal mode could not be read.
+    fn is_raw_mode(&self) -> TermResult<bool>;
+
+    /// Puts the terminal into raw mode, remembering the current mode.
+    ///
+    /// # Errors
+    /// Returns a `RelaunchError` if the terminal mode could not be changed.
+    fn enable_raw_mode(&mut self) -> TermResult<()>;
+
+    /// Restores the mode remembered by `enable_raw_mode()`.
+    ///
+    /// # Errors
+    /// Returns a `RelaunchError` if the terminal mode could not be changed.
+    fn restore_mode(&mut self) -> TermResult<()>;
+
+    /// Writes and flushes the given bytes to the terminal.
+    ///
+    /// # Errors
+    /// Returns a `RelaunchError` if the terminal could not be written.
+    fn write_all(&mut self, bytes: &[u8]) -> TermResult<()>;
+
+    /// Reads the bytes available from the terminal into the given buffer, waiting at most `timeout` for them.
+    ///
+    /// # Returns
+    /// The number of bytes read, `0` if none arrived in time.
+    ///
+    /// # Errors
+    /// Returns a `RelaunchError` if the terminal could not be read.
+    fn read_timeout(&mut self, buffer: &mut [u8], timeout: Duration) -> TermResult<usize>;
+}
+
+/// Runs several queries under a single raw mode switch, restoring the terminal mode when dropped.
+///
+/// # Notes
+/// *   If the terminal is already in raw mode (e.g. the application enabled it through `crossterm` or `termion`),
+///     the mode is left untouched, so it can't fight the application's own save and restore.
+/// *   Input arriving while waiting for a reply is consumed with it. If a query timed out, any late reply is
+///     drained once when the session is dropped, so it doesn't reach the application as input.
+/// *   Only one session may be open at a time, opening another returns `RelaunchError::QueryInProgress`.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use terminal_relaunch::errors::{RelaunchError, TermResult};
+/// use terminal_relaunch::query::{QuerySession, TtyTransport};
+///
+/// /// A terminal replying to every request with `DA1` only.
+/// #[derive(Default)]
+/// struct MockTty {
+///     raw: bool,
+///     pending: Vec<u8>,
+/// }
+///
+/// impl TtyTransport for MockTty {
+///     fn is_raw_mode(&self) -> TermResult<bool> {
+///         Ok(self.raw)
+///     }
+///     fn enable_raw_mode(&mut self) -> TermResult<()> {
+///         self.raw = true;
+///         Ok(())
+///     }
+///     fn restore_mode(&mut self) -> TermResult<()> {
+///         self.raw = false;
+///         Ok(())
+///     }
+///     fn write_all(&mut self, _bytes: &[u8]) -> TermResult<()> {
+///         self.pending.extend_from_slice(b"\x1b[?62;22c");
+///         Ok(())
+///     }
+///     fn read_timeout(&mut self, buffer: &mut [u8], _timeout: Duration) -> TermResult<usize> {
+///         let len = self.pending.len().min(buffer.len());
+///         buffer[..len].copy_from_slice(&self.pending[..len]);
+///         self.pending.drain(..len);
+///         Ok(len)
+///     }
+/// }
+///
+/// let timeout = Duration::from_millis(100);
+///
+/// // The application already enabled raw mode, so the session leaves the mode alone..
+/// let mut session = QuerySession::with_transport(MockTty { raw: true, ..MockTty::default() })?;
+/// assert!(!session.changed_mode());
+/// assert_eq!(session.identity(timeout)?, None);
+/// assert_eq!(session.background_colour(timeout)?, None);
+/// assert!(!session.rgb_support(timeout)?);
+///
+/// // Queries can't run concurrently..
+/// assert!(matches!(
+///     QuerySession::with_transport(MockTty::default()),
+///     Err(RelaunchError::QueryInProgress)
+/// ));
+/// drop(session);
+///
+/// let session = QuerySession::with_transport(MockTty::default())?;
+/// assert!(session.changed_mode());
+/// # Ok::<(), RelaunchError>(())
+/// ```
+pub struct QuerySession<T: TtyTransport> {
+    transport: T,
+    changed_mode: bool,
+    timed_out: bool,
+}
+
+impl QuerySession<SystemTty> {
+    /// Opens a session on the controlling terminal.
+    ///
+    /// # Errors
+    /// *   `RelaunchError::QueryUnsupported` if terminal queries are unsupported on this platform.
+    /// *   `RelaunchError::QueryInProgress` if another session is open.
+    /// *   `RelaunchError::IOError` if the controlling terminal could not be opened or its mode changed.
+    pub fn open() -> TermResult<Self> {
+        Self::with_transport(SystemTty::open()?)
+    }
+}
+
+impl<T: TtyTransport> QuerySession<T> {
+    /// Opens a session on the given transport, putting it into raw mode unless it already is.
+    ///
+    /// # Errors
+    /// *   `RelaunchError::QueryInProgress` if another session is open.
+    /// *   Any error from reading or changing the transport's mode.
+    pub fn with_transport(transport: T) -> TermResult<Self> {
+        if QUERY_IN_PROGRESS
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(RelaunchError::QueryInProgress);
+        }
+
+        // Created before changing the mode, so the flag is released if that fails..
+        let mut session = Self {
+            transport,
+            changed_mode: false,
+            timed_out: false,
+        };
+        if !session.transport.is_raw_mode()? {
+            session.transport.enable_raw_mode()?;
+            session.changed_mode = true;
+        }
+
+        Ok(session)
+    }
+
+    /// Returns `true` if the session put the terminal into raw mode, i.e. it wasn't already.
+    #[inline]
+    #[must_use]
+    pub fn changed_mode(&self) -> bool {
+        self.changed_mode
+    }
+
+    /// Writes the given request, followed by a `DA1` request, and returns the raw reply.
+    ///
+    /// # Errors
+    /// *   `RelaunchError::QueryTimedOut` if the terminal did not reply in time.
+    /// *   Any error from the transport.
+    pub fn query(&mut self, request: &[u8], timeout: Duration) -> TermResult<Vec<u8>> {
+        let mut full_request = request.to_vec();
+        full_request.extend_from_slice(DA1_REQUEST);
+        self.transport.write_all(&full_request)?;
+
+        let deadline = Instant::now() + timeout;
+        let mut reply = Vec::new();
+        let mut buffer = [0u8; 256];
+
+        while !contains_da1_reply(&reply) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                self.timed_out = true;
+                return Err(RelaunchError::QueryTimedOut);
+            }
+
+            let read = self.transport.read_timeout(&mut buffer, remaining)?;
+            reply.extend_from_slice(&buffer[..read]);
+        }
+
+        Ok(reply)
+    }
+
+    /// Queries the given `terminfo` capabilities using `XTGETTCAP`, see `xtgettcap()`.
+    ///
+    /// # Errors
+    /// See `QuerySession::query()`.
+    pub fn xtgettcap(
+        &mut self,
+        caps: &[&str],
+        timeout: Duration,
+    ) -> TermResult<HashMap<String, Option<String>>> {
+        // One request per capability, as some terminals only reply to the first of a combined request..
+        let mut request = Vec::new();
+        for cap in caps {
+            request.extend_from_slice(b"\x1bP+q");
+            request.extend_from_slice(hex_encode(cap).as_bytes());
+            request.extend_from_slice(b"\x1b\\");
+        }
+
+        let reply = self.query(&request, timeout)?;
+        let replies = parse_xtgettcap_replies(&reply);
+
+        Ok(caps
+            .iter()
+            .map(|cap| (cap.to_string(), replies.get(*cap).cloned().flatten()))
+            .collect())
+    }
+
+    /// Returns `true` if the terminal confirms RGB colour support, see `query_rgb_support()`.
+    ///
+    /// # Errors
+    /// See `QuerySession::query()`.
+    pub fn rgb_support(&mut self, timeout: Duration) -> TermResult<bool> {
+        let replies = self.xtgettcap(&["RGB", "Tc"], timeout)?;
+        Ok(replies.values().any(Option::is_some))
+    }
+
+    /// Queries the terminal's background colour, see `query_background_colour()`.
+    ///
+    /// # Errors
+    /// See `QuerySession::query()`.
+    pub fn background_colour(&mut self, timeout: Duration) -> TermResult<Option<(u8, u8, u8)>> {
+        let reply = self.query(b"\x1b]11;?\x1b\\", timeout)?;
+        Ok(parse_background_colour_reply(&reply))
+    }
+
+    /// Queries the terminal's name and version, see `query_identity()`.
+    ///
+    /// # Errors
+    /// See `QuerySession::query()`.
+    pub fn identity(&mut self, timeout: Duration) -> TermResult<Option<String>> {
+        let reply = self.query(XTVERSION_REQUEST, timeout)?;
+        Ok(parse_xtversion_reply(&reply))
+    }
+}
+
+impl<T: TtyTransport> Drop for QuerySession<T> {
+    fn drop(&mut self) {
+        if self.timed_out {
+            let mut buffer = [0u8; 256];
+            while self
+                .transport
+                .read_timeout(&mut buffer, DRAIN_TIMEOUT)
+                .is_ok_and(|read| read > 0)
+            {}
+        }
+        if self.changed_mode {
+            let _ = self.transport.restore_mode();
+        }
+
+        QUERY_IN_PROGRESS.store(false, Ordering::Release);
+    }
+}
+
+/// Writes the given request to the controlling terminal, followed by a `DA1` request, and returns the raw reply.
+///
+/// # Notes
+/// Opens a `QuerySession` for the single query, use one directly to batch several queries.
+///
+/// # Errors
+/// *   `RelaunchError::QueryUnsupported` if terminal queries are unsupported on this platform.
+/// *   `RelaunchError::QueryInProgress` if another query is running.
+/// *   `RelaunchError::QueryTimedOut` if the terminal did not reply in time.
+/// *   `RelaunchError::IOError` if the controlling terminal could not be opened, read or written.
+pub fn query_terminal(request: &[u8], timeout: Duration) -> TermResult<Vec<u8>> {
+    QuerySession::open()?.query(request, timeout)
+}
+
+/// Returns `true` if the given reply contains a complete `DA1` reply, i.e. `ESC [ ? ... c`.
+fn contains_da1_reply(reply: &[u8]) -> bool {
+    reply.windows(3).enumerate().any(|(start, window)| {
+        window == [ESC, b'[', b'?']
+            && reply[start + 3..]
+                .iter()
+                .find(|byte| !(byte.is_ascii_digit() || **byte == b';'))
+                .is_some_and(|byte| *byte == b'c')
+    })
+}
+
+/// Queries the terminal for the given `terminfo` capabilities using `XTGETTCAP` (`DCS + q`).
+///
+/// # Returns
+/// A map containing every requested capability, with:
+/// *   `Some(value)` if the terminal reported the capability, where boolean capabilities have an empty value.
+/// *   `None` if the terminal reported the capability as invalid, or did not reply for it.
+///
+/// # Errors
+/// See `query_terminal()`.
+pub fn xtgettcap(caps: &[&str], timeout: Duration) -> TermResult<HashMap<String, Option<String>>> {
+    QuerySession::open()?.xtgettcap(caps, timeout)
+}
+
+/// Parses all `XTGETTCAP` replies in the given bytes.
+///
+/// Valid replies (`DCS 1 + r name=value ST`, with hex encoded names and values, possibly several separated
+/// by `;`) map to `Some(value)`, and invalid replies (`DCS 0 + r name ST`) map to `None`. Invalid replies
+/// without a name, and anything that fails to decode, are ignored.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::query::parse_xtgettcap_replies;
+///
+/// let replies = parse_xtgettcap_replies(b"\x1bP1+r524742=\x1b\\\x1bP0+r5463\x1b\\\x1bP1+r+A=31\x1b\\\x1b[?62c");
+/// assert_eq!(replies.get("RGB"), Some(&Some(String::new())));
+/// assert_eq!(replies.get("Tc"), Some(&None));
+/// // Signs aren't hex digits..
+/// assert_eq!(replies.len(), 2);
+/// ```
+#[must_use]
+pub fn parse_xtgettcap_replies(bytes: &[u8]) -> HashMap<String, Option<String>> {
+    let mut replies = HashMap::new();
+
+    for payload in dcs_payloads(bytes) {
+        let (valid, entries) = if let Some(entries) = payload.strip_prefix(b"1+r") {
+            (true, entries)
+        } else if let Some(entries) = payload.strip_prefix(b"0+r") {
+            (false, entries)
+        } else {
+            continue;
+        };
+
+        for entry in entries.split(|byte| *byte == b';') {
+            let mut parts = entry.splitn(2, |byte| *byte == b'=');
+            let Some(name) = parts.next().and_then(hex_decode) else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+
+            let value = match parts.next() {
+                Some(value) => match hex_decode(value) {
+                    Some(value) => value,
+                    None => continue,
+                },
+                None => String::new(),
+            };
+
+            replies.insert(name, valid.then_some(value));
+        }
+    }
+
+    replies
+}
+
+/// Returns the payloads of all device control strings (`ESC P ... ST`) in the given bytes.
+///
+/// # Notes
+/// The string terminator may be `ESC \`, the 8-bit `ST` (`0x9c`), or `BEL`, which some terminals (and
+/// multiplexers passing replies through) use as they do for `OSC` replies.
+fn dcs_payloads(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = bytes;
+
+    std::iter::from_fn(move || {
+        let start = rest.windows(2).position(|window| window == [ESC, b'P'])? + 2;
+        let (length, terminator) =
+            rest[start..]
+                .iter()
+                .enumerate()
+                .find_map(|(index, byte)| match *byte {
+                    ESC if rest.get(start + index + 1) == Some(&b'\\') => Some((index, 2)),
+                    BEL | ST_8BIT => Some((index, 1)),
+                    _ => None,
+                })?;
+
+        let payload = &rest[start..start + length];
+        rest = &rest[start + length + terminator..];
+        Some(payload)
+    })
+}
+
+/// Encodes a string as uppercase hex, as used by `XTGETTCAP`.
+fn hex_encode(value: &str) -> String {
+    use std::fmt::Write;
+
+    value.bytes().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02X}");
+        hex
+    })
+}
+
+/// Decodes a hex encoded string, as used by `XTGETTCAP`.
+fn hex_decode(value: &[u8]) -> Option<String> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+
+    // `from_str_radix()` also accepts a sign, e.g. `+A`..
+    if !value.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    let bytes = value
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Returns `true` if the terminal confirms RGB (24-bit) colour support via the `RGB` or `Tc` capabilities.
+///
+/// # Errors
+/// See `query_terminal()`.
+pub fn query_rgb_support(timeout: Duration) -> TermResult<bool> {
+    QuerySession::open()?.rgb_support(timeout)
+}
+
+/// Queries the terminal's background colour using `OSC 11`, as 8-bit `(red, green, blue)` components.
+///
+/// # Returns
+/// *   `Some(colour)` if the terminal reported its background colour.
+/// *   `None` if the terminal ignored the query.
+///
+/// # Errors
+/// See `query_terminal()`.
+pub fn query_background_colour(timeout: Duration) -> TermResult<Option<(u8, u8, u8)>> {
+    QuerySession::open()?.background_colour(timeout)
+}
+
+/// Queries the terminal's name and version using `XTVERSION`, e.g. `"kitty(0.39.1)"`.
+///
+/// # Returns
+/// *   `Some(identity)` if the terminal reported its name.
+/// *   `None` if the terminal ignored the query.
+///
+/// # Errors
+/// See `query_terminal()`.
+pub fn query_identity(timeout: Duration) -> TermResult<Option<String>> {
+    QuerySession::open()?.identity(timeout)
+}
+
+/// Parses an `XTVERSION` reply (`DCS > | name ST`) from the given bytes.
+#[must_use]
+pub fn parse_xtversion_reply(bytes: &[u8]) -> Option<String> {
+    dcs_payloads(bytes)
+        .find_map(|payload| payload.strip_prefix(b">|"))
+        .and_then(|name| String::from_utf8(name.to_vec()).ok())
+        .filter(|name| !name.is_empty())
+}
+
+/// Parses an `OSC 11` reply (`OSC 11 ; rgb:RRRR/GGGG/BBBB`, terminated by `BEL` or `ST`) from the given bytes.
+///
+/// Components may have 1 to 4 hex digits, and are scaled to 8 bits.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::query::parse_background_colour_reply;
+///
+/// assert_eq!(parse_background_colour_reply(b"\x1b]11;rgb:ffff/8080/0\x07\xff\x1b[?62c"), Some((255, 128, 0)));
+/// assert_eq!(parse_background_colour_reply(b"\x1b]11;rgb:+ff/0/0\x1b\\"), None);
+/// ```
+#[must_use]
+pub fn parse_background_colour_reply(bytes: &[u8]) -> Option<(u8, u8, u8)> {
+    let start = bytes
+        .windows(4)
+        .position(|window| window == [ESC, b']', b'1', b'1'])?;
+    let reply = bytes[start + 4..].strip_prefix(b";rgb:")?;
+    // Only the colour has to be text, whatever follows it (e.g. the `DA1` reply)..
+    let end = reply
+        .iter()
+        .position(|byte| matches!(*byte, BEL | ESC))
+        .unwrap_or(reply.len());
+    let colour = std::str::from_utf8(&reply[..end]).ok()?;
+
+    let mut components = colour.split('/').map(|component| {
+        if component.is_empty()
+            || component.len() > 4
+            || !component.bytes().all(|byte| byte.is_ascii_hexdigit())
+        {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1u32 << (4 * component.len())) - 1;
+        u8::try_from(value * 255 / max).ok()
+    });
+
+    let colour = (
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+    components.next().is_none().then_some(colour)
+}
+
+#[cfg(unix)]
+mod tty {
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::Duration;
+
+    use super::TtyTransport;
+    use crate::errors::TermResult;
+
+    /// The controlling terminal (`/dev/tty`).
+    pub struct SystemTty {
+        tty: File,
+        original: Option<libc::termios>,
+    }
+
+    impl SystemTty {
+        /// Opens the controlling terminal.
+        ///
+        /// # Errors
+        /// Returns `RelaunchError::IOError` if the controlling terminal could not be opened.
+        pub fn open() -> TermResult<Self> {
+            let tty = File::options().read(true).write(true).open("/dev/tty")?;
+            Ok(Self {
+                tty,
+                original: None,
+            })
+        }
+
+        /// Returns the current terminal mode.
+        fn attributes(&self) -> TermResult<libc::termios> {
+            // SAFETY: `termios` is a plain C struct, fully initialised by `tcgetattr` before use.
+            let mut attributes: libc::termios = unsafe { std::mem::zeroed() };
+            // SAFETY: the file descriptor is valid and open, and `attributes` is a valid pointer.
+            if unsafe { libc::tcgetattr(self.tty.as_raw_fd(), &raw mut attributes) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(attributes)
+        }
+
+        /// Sets the terminal mode.
+        fn set_attributes(&self, attributes: &libc::termios) -> TermResult<()> {
+            // SAFETY: the file descriptor is valid and open, and `attributes` is a valid pointer.
+            if unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, attributes) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(())
+        }
+    }
+
+    impl TtyTransport for SystemTty {
+        fn is_raw_mode(&self) -> TermResult<bool> {
+            // Raw mode libraries disable canonical input and echo, which queries need..
+            let attributes = self.attributes()?;
+            Ok(attributes.c_lflag & (libc::ICANON | libc::ECHO) == 0)
+        }
+
+        fn enable_raw_mode(&mut self) -> TermResult<()> {
+            let original = self.attributes()?;
+            let mut raw = original;
+            // SAFETY: `raw` is a valid, initialised `termios`.
+            unsafe { libc::cfmakeraw(&raw mut raw) };
+            self.set_attributes(&raw)?;
+
+            self.original = Some(original);
+            Ok(())
+        }
+
+        fn restore_mode(&mut self) -> TermResult<()> {
+            match self.original.take() {
+                Some(original) => self.set_attributes(&original),
+                None => Ok(()),
+            }
+        }
+
+        fn write_all(&mut self, bytes: &[u8]) -> TermResult<()> {
+            self.tty.write_all(bytes)?;
+            self.tty.flush()?;
+            Ok(())
+        }
+
+        fn read_timeout(&mut self, buffer: &mut [u8], timeout: Duration) -> TermResult<usize> {
+            let mut poll_fd = libc::pollfd {
+                fd: self.tty.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+            // SAFETY: `poll_fd` is a valid pointer to a single `pollfd`.
+            let ready = unsafe { libc::poll(&raw mut poll_fd, 1, timeout_ms.max(1)) };
+            match ready {
+                0 => Ok(0),
+                ..0 => {
+                    let error = std::io::Error::last_os_error();
+                    // Interrupted polls are retried by the caller until its deadline..
+                    if error.kind() == std::io::ErrorKind::Interrupted {
+                        Ok(0)
+                    } else {
+                        Err(error.into())
+                    }
+                }
+                _ => Ok(self.tty.read(buffer)?),
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod tty {
+    use std::time::Duration;
+
+    use super::TtyTransport;
+    use crate::errors::{RelaunchError, TermResult};
+
+    /// The controlling terminal, terminal queries are currently only supported on unix platforms.
+    pub struct SystemTty {
+        _private: (),
+    }
+
+    impl SystemTty {
+        /// Opens the controlling terminal.
+        ///
+        /// # Errors
+        /// Always returns `RelaunchError::QueryUnsupported`.
+        pub fn open() -> TermResult<Self> {
+            Err(RelaunchError::QueryUnsupported)
+        }
+    }
+
+    impl TtyTransport for SystemTty {
+        fn is_raw_mode(&self) -> TermResult<bool> {
+            Err(RelaunchError::QueryUnsupported)
+        }
+
+        fn enable_raw_mode(&mut self) -> TermResult<()> {
+            Err(RelaunchError::QueryUnsupported)
+        }
+
+        fn restore_mode(&mut self) -> TermResult<()> {
+            Err(RelaunchError::QueryUnsupported)
+        }
+
+        fn write_all(&mut self, _bytes: &[u8]) -> TermResult<()> {
+            Err(RelaunchError::QueryUnsupported)
+        }
+
+        fn read_timeout(&mut self, _buffer: &mut [u8], _timeout: Duration) -> TermResult<usize> {
+            Err(RelaunchError::QueryUnsupported)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DA1_REQUEST, DRAIN_TIMEOUT, QuerySession, TtyTransport, XTVERSION_REQUEST,
+        parse_xtgettcap_replies, parse_xtversion_reply,
+    };
+    use crate::errors::{RelaunchError, TermResult};
+    use crate::test_support::writing_globals;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Replies as `kitty` sends them to `XTGETTCAP` for `TN`, `RGB`, `Tc`, `colors` and `Smulx`, one `DCS` per
+    /// capability with uppercase hex, followed by its `DA1` reply.
+    const KITTY_REPLIES: &[u8] = b"\x1bP1+r544E=787465726D2D6B69747479\x1b\\\
+        \x1bP0+r524742\x1b\\\
+        \x1bP1+r5463=\x1b\\\
+        \x1bP1+r636F6C6F7273=323536\x1b\\\
+        \x1bP1+r536D756C78=1B5B343A25703125646D\x1b\\\
+        \x1b[?62;52;c";
+
+    /// Replies as `foot` sends them to `XTGETTCAP` for `TN`, `RGB`, `Tc` and the unknown `Ms`, echoing the
+    /// lowercase hex of the request, followed by its `DA1` reply.
+    const FOOT_REPLIES: &[u8] = b"\x1bP1+r544e=666f6f74\x1b\\\
+        \x1bP1+r524742=\x1b\\\
+        \x1bP1+r5463=\x1b\\\
+        \x1bP0+r4d73\x1b\\\
+        \x1b[?62;4;22;28c";
+
+    #[test]
+    fn kitty_replies_are_parsed() {
+        let replies = parse_xtgettcap_replies(KITTY_REPLIES);
+        assert_eq!(replies.len(), 5);
+        assert_eq!(replies["TN"].as_deref(), Some("xterm-kitty"));
+        assert_eq!(replies["RGB"], None);
+        assert_eq!(replies["Tc"].as_deref(), Some(""));
+        assert_eq!(replies["colors"].as_deref(), Some("256"));
+        assert_eq!(replies["Smulx"].as_deref(), Some("\x1b[4:%p1%dm"));
+    }
+
+    #[test]
+    fn foot_replies_are_parsed() {
+        let replies = parse_xtgettcap_replies(FOOT_REPLIES);
+        assert_eq!(replies.len(), 4);
+        assert_eq!(replies["TN"].as_deref(), Some("foot"));
+        assert_eq!(replies["RGB"].as_deref(), Some(""));
+        assert_eq!(replies["Tc"].as_deref(), Some(""));
+        assert_eq!(replies["Ms"], None);
+    }
+
+    #[test]
+    fn several_capabilities_in_one_reply_are_parsed() {
+        let replies =
+            parse_xtgettcap_replies(b"\x1bP1+r544E=666F6F74;636F6C6F7273=323536;5463\x1b\\");
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies["TN"].as_deref(), Some("foot"));
+        assert_eq!(replies["colors"].as_deref(), Some("256"));
+        assert_eq!(replies["Tc"].as_deref(), Some(""));
+
+        // An unsupported reply may also list several capabilities..
+        let replies = parse_xtgettcap_replies(b"\x1bP0+r524742;4D73\x1b\\");
+        assert_eq!(
+            (replies["RGB"].clone(), replies["Ms"].clone()),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn malformed_entries_are_ignored() {
+        let replies = parse_xtgettcap_replies(
+            b"\x1bP1+r+A=31\x1b\\\x1bP1+r544=31\x1b\\\x1bP1+r=31\x1b\\\x1bP1+r5463=ZZ\x1b\\\x1bP2+r5463\x1b\\",
+        );
+        assert!(replies.is_empty(), "{replies:?}");
+        // ..as is an unterminated reply..
+        assert!(parse_xtgettcap_replies(b"\x1bP1+r5463=").is_empty());
+    }
+
+    #[test]
+    fn every_string_terminator_is_accepted() {
+        for terminator in [&b"\x1b\\"[..], b"\x07", b"\x9c"] {
+            let bytes = [
+                &b"\x1bP1+r5463="[..],
+                terminator,
+                b"\x1bP0+r524742",
+                terminator,
+                b"\x1b[?62c",
+            ]
+            .concat();
+            let replies = parse_xtgettcap_replies(&bytes);
+            assert_eq!(replies.len(), 2, "{terminator:?}");
+            assert_eq!(replies["Tc"].as_deref(), Some(""), "{terminator:?}");
+            assert_eq!(replies["RGB"], None, "{terminator:?}");
+        }
+
+        // Terminators may be mixed, e.g. when a multiplexer rewrites some replies..
+        let replies =
+            parse_xtgettcap_replies(b"\x1bP1+r5463=\x07\x1bP1+r524742=\x9c\x1bP0+r4D73\x1b\\");
+        assert_eq!(replies.len(), 3);
+        assert_eq!(
+            parse_xtversion_reply(b"\x1bP>|foot(1.20.2)\x9c"),
+            Some("foot(1.20.2)".to_string())
+        );
+    }
+
+    /// What a `MockTty` was asked to do.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Event {
+        EnableRaw,
+        Restore,
+        Write(Vec<u8>),
+        Drained(usize),
+    }
+
+    /// A mock terminal, replying to each write with the next scripted reply, and recording what it was asked to do.
+    struct MockTty {
+        raw: bool,
+        fail_raw_mode: bool,
+        replies: VecDeque<&'static [u8]>,
+        pending: Vec<u8>,
+        late: bool,
+        events: Arc<Mutex<Vec<Event>>>,
+    }
+
+    impl MockTty {
+        fn new(raw: bool, replies: &[&'static [u8]]) -> (Self, Arc<Mutex<Vec<Event>>>) {
+            let events = Arc::default();
+            let tty = Self {
+                raw,
+                fail_raw_mode: false,
+                replies: replies.iter().copied().collect(),
+                pending: Vec::new(),
+                late: false,
+                events: Arc::clone(&events),
+            };
+            (tty, events)
+        }
+
+        fn record(&self, event: Event) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl TtyTransport for MockTty {
+        fn is_raw_mode(&self) -> TermResult<bool> {
+            Ok(self.raw)
+        }
+
+        fn enable_raw_mode(&mut self) -> TermResult<()> {
+            if self.fail_raw_mode {
+                return Err(std::io::Error::other("not a terminal").into());
+            }
+            self.raw = true;
+            self.record(Event::EnableRaw);
+            Ok(())
+        }
+
+        fn restore_mode(&mut self) -> TermResult<()> {
+            self.raw = false;
+            self.record(Event::Restore);
+            Ok(())
+        }
+
+        fn write_all(&mut self, bytes: &[u8]) -> TermResult<()> {
+            self.record(Event::Write(bytes.to_vec()));
+            if let Some(reply) = self.replies.pop_front() {
+                self.pending.extend_from_slice(reply);
+            }
+            Ok(())
+        }
+
+        fn read_timeout(&mut self, buffer: &mut [u8], timeout: Duration) -> TermResult<usize> {
+            let draining = timeout == DRAIN_TIMEOUT;
+            // Late replies only arrive once the query waiting for them timed out..
+            if self.late && !draining {
+                std::thread::sleep(timeout.min(Duration::from_millis(5)));
+                return Ok(0);
+            }
+
+            let len = self.pending.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&self.pending[..len]);
+            self.pending.drain(..len);
+            if draining {
+                self.record(Event::Drained(len));
+            }
+            Ok(len)
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn sessions_leave_raw_mode_set_by_the_application_alone() {
+        let _globals = writing_globals();
+        let (tty, events) = MockTty::new(true, &[b"\x1bP>|WezTerm 20240203\x1b\\\x1b[?62c"]);
+
+        let mut session = QuerySession::with_transport(tty).unwrap();
+        assert!(!session.changed_mode());
+        assert_eq!(
+            session.identity(TIMEOUT).unwrap(),
+            Some("WezTerm 20240203".to_string())
+        );
+        drop(session);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [Event::Write([XTVERSION_REQUEST, DA1_REQUEST].concat())]
+        );
+    }
+
+    #[test]
+    fn sessions_batch_queries_under_one_mode_switch() {
+        let _globals = writing_globals();
+        let (tty, events) = MockTty::new(
+            false,
+            &[
+                b"\x1bP>|foot(1.20.2)\x1b\\\x1b[?62;4c",
+                b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\\x1b[?62;4c",
+                b"\x1bP1+r524742=\x1b\\\x1bP0+r5463\x1b\\\x1b[?62;4c",
+            ],
+        );
+
+        let mut session = QuerySession::with_transport(tty).unwrap();
+        assert!(session.changed_mode());
+        assert_eq!(
+            session.identity(TIMEOUT).unwrap(),
+            Some("foot(1.20.2)".to_string())
+        );
+        assert_eq!(
+            session.background_colour(TIMEOUT).unwrap(),
+            Some((0x1e, 0x1e, 0x2e))
+        );
+        assert!(session.rgb_support(TIMEOUT).unwrap());
+        drop(session);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.first(), Some(&Event::EnableRaw));
+        assert_eq!(events.last(), Some(&Event::Restore));
+        let modes = events
+            .iter()
+            .filter(|event| matches!(event, Event::EnableRaw | Event::Restore))
+            .count();
+        assert_eq!(modes, 2, "{events:?}");
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::Write(_)))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn late_replies_are_drained_once_the_session_ends() {
+        let _globals = writing_globals();
+        // The reply arrives after the query gave up on it..
+        let (mut tty, events) =
+            MockTty::new(false, &[b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62c"]);
+        tty.late = true;
+
+        let mut session = QuerySession::with_transport(tty).unwrap();
+        assert!(matches!(
+            session.background_colour(TIMEOUT),
+            Err(RelaunchError::QueryTimedOut)
+        ));
+        drop(session);
+
+        let events = events.lock().unwrap();
+        let drained: usize = events
+            .iter()
+            .map(|event| match event {
+                Event::Drained(len) => *len,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(drained, b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62c".len());
+        // ..before the mode is restored, so it isn't echoed..
+        assert_eq!(events.last(), Some(&Event::Restore));
+    }
+
+    #[test]
+    fn one_session_at_a_time() {
+        let _globals = writing_globals();
+        let (tty, _) = MockTty::new(false, &[]);
+        let session = QuerySession::with_transport(tty).unwrap();
+
+        let (other, other_events) = MockTty::new(false, &[]);
+        assert!(matches!(
+            QuerySession::with_transport(other),
+            Err(RelaunchError::QueryInProgress)
+        ));
+        assert!(other_events.lock().unwrap().is_empty());
+        drop(session);
+
+        // A session failing to change the mode doesn't keep others out..
+        let (mut failing, _) = MockTty::new(false, &[]);
+        failing.fail_raw_mode = true;
+        assert!(matches!(
+            QuerySession::with_transport(failing),
+            Err(RelaunchError::IOError(_))
+        ));
+        let (tty, _) = MockTty::new(true, &[]);
+        assert!(QuerySession::with_transport(tty).is_ok());
+    }
+}
@@ -0,0 +1,288 @@
+//! A recording terminal provider, so applications can check in their own tests which terminal they would relaunch
+//! in, and how, without any terminal installed.
+//!
+//! A `RecordingProvider` claims to be an installed terminal of the given type, and records the launch it was asked
+//! for rather than launching anything. Registered for the terminals a relaunch may select (see
+//! `registry::ScopedRegistry`), it intercepts every relaunch the library would perform.
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::config::{RelaunchConfig, WindowMode, set_relaunch_config};
+//! use terminal_relaunch::pipeline::RelaunchPipeline;
+//! use terminal_relaunch::recording::RecordingProvider;
+//! use terminal_relaunch::registry::{ScopedRegistry, registered_provider};
+//! use terminal_relaunch::{OperatingSystem, current_terminal, get_preferred_terminals_for_os};
+//!
+//! set_relaunch_config(RelaunchConfig::new().window_mode(WindowMode::Tab));
+//!
+//! // Every terminal a relaunch may select is "installed"..
+//! let recorders: Vec<RecordingProvider> = get_preferred_terminals_for_os(OperatingSystem::current())
+//!     .map(RecordingProvider::new)
+//!     .collect();
+//! let mut scope = ScopedRegistry::new();
+//! for recorder in &recorders {
+//!     scope.register(recorder.clone());
+//! }
+//!
+//! // ..so the relaunch is intercepted by the first that improves on the current terminal..
+//! assert!(RelaunchPipeline::without_decision().into_result().unwrap());
+//! let launches: Vec<_> = recorders.iter().flat_map(RecordingProvider::launches).collect();
+//! assert_eq!(launches.len(), 1);
+//! let launch = &launches[0];
+//! assert_ne!(launch.terminal, current_terminal());
+//! assert_eq!(launch.window_mode, WindowMode::Tab);
+//! assert_eq!(launch.program, Some(std::env::current_exe().unwrap()));
+//! assert_eq!(launch.args.first(), Some(&RelaunchConfig::new().relaunched_argument()));
+//! assert_eq!(launch.working_directory, std::env::current_dir().unwrap());
+//!
+//! // ..and nothing is left registered once the scope ends..
+//! drop(scope);
+//! assert!(registered_provider(launch.terminal).is_none());
+//! ```
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::WindowMode;
+use crate::errors::{RelaunchError, TermResult};
+use crate::pipeline::UnfitReason;
+use crate::{TerminalProvider, TerminalType};
+
+/// A launch recorded by a `RecordingProvider`, as the provider of a real terminal would have performed it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordedLaunch {
+    /// The terminal type of the provider.
+    pub terminal: TerminalType,
+    /// The window mode the program is opened in, after falling back from unsupported modes.
+    pub window_mode: WindowMode,
+    /// The working directory the program starts in, see `terminal_providers::launch_working_directory()`.
+    pub working_directory: PathBuf,
+    /// The program to launch, or `None` for an interactive shell, see `open_terminal_here()`.
+    pub program: Option<PathBuf>,
+    /// The arguments of the program, starting with the relaunch marker.
+    pub args: Vec<String>,
+    /// The environment variables propagated to the program, see `environment::propagated_env()`.
+    pub env: Vec<(&'static str, String)>,
+}
+
+/// A provider claiming to be an installed terminal, which records launches instead of performing them.
+///
+/// # Notes
+/// Clones share their recorded launches, so a clone can be registered while the original is inspected.
+#[derive(Debug, Clone)]
+pub struct RecordingProvider {
+    terminal: TerminalType,
+    missing_dependency: Option<&'static str>,
+    unfit_reason: Option<UnfitReason>,
+    launches: Arc<Mutex<Vec<RecordedLaunch>>>,
+}
+
+impl RecordingProvider {
+    /// Creates a recording provider claiming to be an installed terminal of the given type.
+    #[inline]
+    #[must_use]
+    pub fn new(terminal: TerminalType) -> Self {
+        Self {
+            terminal,
+            missing_dependency: None,
+            unfit_reason: None,
+            launches: Arc::default(),
+        }
+    }
+
+    /// Sets a program the terminal requires that can't be found, see `TerminalProvider::missing_dependency()`.
+    #[inline]
+    #[must_use]
+    pub fn with_missing_dependency(mut self, dependency: &'static str) -> Self {
+        self.missing_dependency = Some(dependency);
+        self
+    }
+
+    /// Sets why the terminal can't be used for relaunches, see `TerminalProvider::unfit_reason()`.
+    ///
+    /// # Notes
+    /// Interactive shells (see `open_terminal_here()`) are still recorded.
+    #[inline]
+    #[must_use]
+    pub fn with_unfit_reason(mut self, reason: UnfitReason) -> Self {
+        self.unfit_reason = Some(reason);
+        self
+    }
+
+    /// Returns the launches recorded so far, in order.
+    #[must_use]
+    pub fn launches(&self) -> Vec<RecordedLaunch> {
+        self.launches
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the most recently recorded launch, if any.
+    #[must_use]
+    pub fn last_launch(&self) -> Option<RecordedLaunch> {
+        self.launches
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .last()
+            .cloned()
+    }
+
+    /// Removes the launches recorded so far.
+    pub fn clear(&self) {
+        self.launches
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl TerminalProvider for RecordingProvider {
+    fn terminal_type(&self) -> TerminalType {
+        self.terminal
+    }
+
+    fn is_installed(&self) -> bool {
+        true
+    }
+
+    fn supports_window_mode(&self, _mode: WindowMode) -> bool {
+        true
+    }
+
+    fn missing_dependency(&self) -> Option<&'static str> {
+        self.missing_dependency
+    }
+
+    fn unfit_reason(&self) -> Option<UnfitReason> {
+        self.unfit_reason
+            .filter(|_| crate::terminal_providers::launches_program())
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        if let Some(dependency) = self.missing_dependency {
+            return Err(RelaunchError::MissingDependency(self.terminal, dependency));
+        }
+        if let Some(reason) = self.unfit_reason() {
+            return Err(RelaunchError::UnfitTerminal(self.terminal, reason));
+        }
+
+        let window_mode = crate::terminal_providers::resolve_window_mode(self)?;
+        let (program, working_directory) = crate::terminal_providers::get_launch_params();
+        let (program, args) = program.map_or((None, Vec::new()), |(exe, args)| (Some(exe), args));
+
+        self.launches
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(RecordedLaunch {
+                terminal: self.terminal,
+                window_mode,
+                working_directory,
+                program,
+                args,
+                env: crate::environment::propagated_env(),
+            });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::RecordingProvider;
+    use crate::config::{RelaunchConfig, WindowMode, relaunch_config, set_relaunch_config};
+    use crate::errors::RelaunchError;
+    use crate::pipeline::UnfitReason;
+    use crate::terminal_providers::{with_shell_in, with_window_mode};
+    use crate::test_support::reading_globals;
+    use crate::{TerminalProvider, TerminalType};
+
+    #[test]
+    fn launches_are_recorded_in_order_and_shared_by_clones() {
+        let _globals = reading_globals();
+        let recorder = RecordingProvider::new(TerminalType::Kitty);
+        let registered = recorder.clone();
+        assert!(registered.is_installed());
+        assert!(registered.supports_window_mode(WindowMode::SplitVertical));
+        assert_eq!(recorder.last_launch(), None);
+
+        with_window_mode(WindowMode::Tab, || registered.relaunch_in_terminal()).unwrap();
+        with_shell_in(Path::new("/srv/work"), || registered.relaunch_in_terminal()).unwrap();
+
+        let launches = recorder.launches();
+        assert_eq!(launches.len(), 2);
+        let (program, shell) = (&launches[0], &launches[1]);
+        assert_eq!(program.terminal, TerminalType::Kitty);
+        assert_eq!(program.window_mode, WindowMode::Tab);
+        assert_eq!(program.program, Some(std::env::current_exe().unwrap()));
+        assert_eq!(
+            program.args.first(),
+            Some(&relaunch_config().relaunched_argument())
+        );
+        assert_eq!(program.env, crate::environment::propagated_env());
+
+        // ..while a shell has no program or arguments, and starts in the given directory..
+        assert_eq!(shell.program, None);
+        assert!(shell.args.is_empty());
+        assert_eq!(shell.working_directory, Path::new("/srv/work"));
+        assert_eq!(recorder.last_launch().as_ref(), Some(shell));
+
+        registered.clear();
+        assert!(recorder.launches().is_empty());
+    }
+
+    #[test]
+    fn missing_dependencies_and_unfit_terminals_record_nothing() {
+        let _globals = reading_globals();
+        let missing =
+            RecordingProvider::new(TerminalType::ITerm2).with_missing_dependency("osascript");
+        assert_eq!(missing.missing_dependency(), Some("osascript"));
+        assert!(matches!(
+            missing.relaunch_in_terminal(),
+            Err(RelaunchError::MissingDependency(
+                TerminalType::ITerm2,
+                "osascript"
+            ))
+        ));
+        assert!(with_shell_in(Path::new("/"), || missing.relaunch_in_terminal()).is_err());
+        assert!(missing.launches().is_empty());
+
+        // Terminals unfit for relaunches can still open a shell..
+        let unfit = RecordingProvider::new(TerminalType::Hyper)
+            .with_unfit_reason(UnfitReason::CannotHostCommand);
+        assert_eq!(unfit.unfit_reason(), Some(UnfitReason::CannotHostCommand));
+        assert!(matches!(
+            unfit.relaunch_in_terminal(),
+            Err(RelaunchError::UnfitTerminal(
+                TerminalType::Hyper,
+                UnfitReason::CannotHostCommand
+            ))
+        ));
+        assert!(unfit.launches().is_empty());
+        with_shell_in(Path::new("/"), || {
+            assert_eq!(unfit.unfit_reason(), None);
+            unfit.relaunch_in_terminal()
+        })
+        .unwrap();
+        assert_eq!(unfit.launches().len(), 1);
+    }
+
+    #[test]
+    fn the_configured_window_mode_is_recorded() {
+        let _globals = crate::test_support::writing_globals();
+        let config = relaunch_config();
+        set_relaunch_config(RelaunchConfig::new().window_mode(WindowMode::SplitHorizontal));
+
+        // Recorders support every mode, so the configured one is recorded as is..
+        let recorder = RecordingProvider::new(TerminalType::WezTerm);
+        recorder.relaunch_in_terminal().unwrap();
+        assert_eq!(
+            recorder.last_launch().map(|launch| launch.window_mode),
+            Some(WindowMode::SplitHorizontal)
+        );
+
+        set_relaunch_config(config);
+    }
+}
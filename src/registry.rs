@@ -0,0 +1,265 @@
+//! Registration of custom terminal providers, returned by `get_provider_for_terminal()` over the built-in ones, e.g.
+//! for a terminal the library doesn't support, or to intercept relaunches in tests.
+//!
+//! The most recent registration for a terminal type wins, so a registration can temporarily replace another, and
+//! `ScopedRegistry` removes its registrations when dropped, so tests don't leak providers into each other.
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::errors::TermResult;
+//! use terminal_relaunch::registry::{ScopedRegistry, register_provider, registered_provider, unregister_provider};
+//! use terminal_relaunch::{TerminalProvider, TerminalType, get_provider_for_terminal};
+//!
+//! /// A terminal the library has no provider for..
+//! struct Contour(bool);
+//!
+//! impl TerminalProvider for Contour {
+//!     fn terminal_type(&self) -> TerminalType { TerminalType::Contour }
+//!     fn is_installed(&self) -> bool { self.0 }
+//!     fn relaunch_in_terminal(&self) -> TermResult<()> { Ok(()) }
+//! }
+//!
+//! assert!(get_provider_for_terminal(TerminalType::Contour).is_none());
+//! let registration = register_provider(Contour(true));
+//! assert!(get_provider_for_terminal(TerminalType::Contour).is_some_and(|provider| provider.is_installed()));
+//!
+//! // Replaced for the lifetime of a scope..
+//! {
+//!     let mut scope = ScopedRegistry::new();
+//!     scope.register(Contour(false));
+//!     assert!(registered_provider(TerminalType::Contour).is_some_and(|provider| !provider.is_installed()));
+//! }
+//! assert!(registered_provider(TerminalType::Contour).is_some_and(|provider| provider.is_installed()));
+//!
+//! unregister_provider(registration);
+//! assert!(get_provider_for_terminal(TerminalType::Contour).is_none());
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use crate::config::WindowMode;
+use crate::errors::TermResult;
+use crate::pipeline::UnfitReason;
+use crate::version::TerminalVersion;
+use crate::{TerminalProvider, TerminalType};
+
+/// A registered provider, shared by every lookup of its terminal type.
+pub(crate) type SharedProvider = Arc<dyn TerminalProvider + Send + Sync>;
+
+/// Identifies a provider registration, see `register_provider()`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ProviderRegistration(u64);
+
+/// Registers a provider for its terminal type, returned by `get_provider_for_terminal()` until it's unregistered.
+///
+/// # Notes
+/// The most recent registration for a terminal type wins, over earlier registrations and the built-in provider.
+pub fn register_provider(
+    provider: impl TerminalProvider + Send + Sync + 'static,
+) -> ProviderRegistration {
+    let state = crate::runtime::state();
+    let id = state.next_registration.fetch_add(1, Ordering::Relaxed);
+    crate::logging::info!("Registered a provider for `{}`.", provider.terminal_type());
+    state
+        .providers
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .push((id, Arc::new(provider)));
+
+    ProviderRegistration(id)
+}
+
+/// Removes the given provider registration, restoring the provider registered before it, or the built-in one.
+// Taken by value, so a registration can't be removed twice..
+#[allow(clippy::needless_pass_by_value)]
+pub fn unregister_provider(registration: ProviderRegistration) {
+    crate::runtime::state()
+        .providers
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .retain(|(id, _)| *id != registration.0);
+}
+
+/// Returns the most recently registered provider for the given terminal type, if any.
+#[must_use]
+pub fn registered_provider(terminal_type: TerminalType) -> Option<Box<dyn TerminalProvider>> {
+    crate::runtime::state()
+        .providers
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .rev()
+        .find(|(_, provider)| provider.terminal_type() == terminal_type)
+        .map(|(_, provider)| {
+            Box::new(Registered(Arc::clone(provider))) as Box<dyn TerminalProvider>
+        })
+}
+
+/// Provider registrations removed when dropped, e.g. for the duration of a test.
+#[derive(Debug, Default)]
+pub struct ScopedRegistry {
+    registrations: Vec<ProviderRegistration>,
+}
+
+impl ScopedRegistry {
+    /// Creates a new scope without registrations.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a provider for the lifetime of the scope, see `register_provider()`.
+    pub fn register(
+        &mut self,
+        provider: impl TerminalProvider + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.registrations.push(register_provider(provider));
+        self
+    }
+}
+
+impl Drop for ScopedRegistry {
+    fn drop(&mut self) {
+        for registration in self.registrations.drain(..) {
+            unregister_provider(registration);
+        }
+    }
+}
+
+/// A registered provider, as returned by `registered_provider()`.
+struct Registered(SharedProvider);
+
+impl TerminalProvider for Registered {
+    fn terminal_type(&self) -> TerminalType {
+        self.0.terminal_type()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.0.is_installed()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.0.installed_path()
+    }
+
+    fn supports_window_mode(&self, mode: WindowMode) -> bool {
+        self.0.supports_window_mode(mode)
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.0.installed_version()
+    }
+
+    fn missing_dependency(&self) -> Option<&'static str> {
+        self.0.missing_dependency()
+    }
+
+    fn unfit_reason(&self) -> Option<UnfitReason> {
+        self.0.unfit_reason()
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        self.0.relaunch_in_terminal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScopedRegistry, register_provider, registered_provider, unregister_provider};
+    use crate::pipeline::UnfitReason;
+    use crate::recording::RecordingProvider;
+    use crate::test_support::writing_globals;
+    use crate::{TerminalType, get_provider_for_terminal};
+
+    /// Returns the missing dependency of the provider returned for the given terminal type, to tell them apart.
+    fn provided_dependency(terminal: TerminalType) -> Option<&'static str> {
+        get_provider_for_terminal(terminal)
+            .unwrap()
+            .missing_dependency()
+    }
+
+    #[test]
+    fn the_latest_registration_wins_until_it_is_removed() {
+        let _globals = writing_globals();
+        assert!(registered_provider(TerminalType::Kitty).is_none());
+
+        let first = register_provider(
+            RecordingProvider::new(TerminalType::Kitty).with_missing_dependency("first"),
+        );
+        let second = register_provider(
+            RecordingProvider::new(TerminalType::Kitty).with_missing_dependency("second"),
+        );
+        let other = register_provider(RecordingProvider::new(TerminalType::WezTerm));
+        assert_ne!(first, second);
+        assert_eq!(provided_dependency(TerminalType::Kitty), Some("second"));
+
+        // Removing the latest restores the one before it, in any order..
+        unregister_provider(second);
+        assert_eq!(provided_dependency(TerminalType::Kitty), Some("first"));
+        unregister_provider(other);
+        assert_eq!(provided_dependency(TerminalType::Kitty), Some("first"));
+
+        // ..and then the built-in provider..
+        unregister_provider(first);
+        assert!(registered_provider(TerminalType::Kitty).is_none());
+        assert_eq!(provided_dependency(TerminalType::Kitty), None);
+        assert!(get_provider_for_terminal(TerminalType::Contour).is_none());
+    }
+
+    #[test]
+    fn scopes_only_remove_their_own_registrations() {
+        let _globals = writing_globals();
+        let outer = register_provider(
+            RecordingProvider::new(TerminalType::Contour).with_missing_dependency("outer"),
+        );
+        {
+            let mut scope = ScopedRegistry::new();
+            scope
+                .register(
+                    RecordingProvider::new(TerminalType::Contour).with_missing_dependency("scoped"),
+                )
+                .register(RecordingProvider::new(TerminalType::Foot));
+            assert_eq!(provided_dependency(TerminalType::Contour), Some("scoped"));
+            assert!(registered_provider(TerminalType::Foot).is_some());
+
+            // A registration made outside the scope while it's alive outlives it..
+            let during = register_provider(RecordingProvider::new(TerminalType::Konsole));
+            drop(scope);
+            assert!(registered_provider(TerminalType::Konsole).is_some());
+            unregister_provider(during);
+        }
+        assert_eq!(provided_dependency(TerminalType::Contour), Some("outer"));
+        assert!(registered_provider(TerminalType::Foot).is_none());
+
+        unregister_provider(outer);
+        assert!(registered_provider(TerminalType::Contour).is_none());
+    }
+
+    #[test]
+    fn registered_providers_are_forwarded_to() {
+        let _globals = writing_globals();
+        let recorder = RecordingProvider::new(TerminalType::Hyper)
+            .with_unfit_reason(UnfitReason::CannotHostCommand);
+        let mut scope = ScopedRegistry::new();
+        scope.register(recorder.clone());
+
+        let provider = registered_provider(TerminalType::Hyper).unwrap();
+        assert_eq!(provider.terminal_type(), TerminalType::Hyper);
+        assert!(provider.is_installed());
+        assert_eq!(provider.installed_path(), None);
+        assert_eq!(
+            provider.unfit_reason(),
+            Some(UnfitReason::CannotHostCommand)
+        );
+        assert!(provider.relaunch_in_terminal().is_err());
+
+        crate::terminal_providers::with_shell_in(std::path::Path::new("/"), || {
+            provider.relaunch_in_terminal()
+        })
+        .unwrap();
+        assert_eq!(recorder.launches().len(), 1);
+    }
+}
@@ -0,0 +1,420 @@
+//! Detection of remote sessions (`SSH` and `mosh`), which have no local terminal to relaunch in.
+//!
+//! `mosh` re-implements the terminal on the client side and scrubs most of the environment, so it can't be
+//! reliably identified from environment variables alone, instead it's found from a `mosh-server` ancestor process.
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::LazyLock;
+
+use crate::capabilities::{CapabilityDetection, ColourDepth, capability_detection};
+use crate::environment::{Environment, SystemEnvironment};
+use crate::version::TerminalVersion;
+
+/// Environment variables set by the `SSH` server in the sessions it starts.
+pub const SSH_VARS: [&str; 3] = ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"];
+
+/// Name of the `mosh` server process, which the program is a descendant of in `mosh` sessions.
+pub const MOSH_SERVER_PROCESS: &str = "mosh-server";
+
+/// First `mosh` version with RGB (24-bit) colour support.
+pub const MOSH_RGB_VERSION: TerminalVersion = TerminalVersion::new(1, 4, 0);
+
+/// Maximum number of ancestor processes walked by `process_ancestry()`.
+const MAX_ANCESTRY_DEPTH: usize = 64;
+
+/// Represents the kind of remote session the program is running in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RemoteSession {
+    /// The program is running locally.
+    #[default]
+    None,
+    /// The program is running in an `SSH` session.
+    Ssh,
+    /// The program is running in a `mosh` session.
+    Mosh,
+}
+
+impl RemoteSession {
+    /// Returns the name of the remote session kind.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "Local",
+            Self::Ssh => "SSH",
+            Self::Mosh => "mosh",
+        }
+    }
+
+    /// Returns `true` if the session is remote, so there is no local terminal to relaunch in.
+    #[inline]
+    #[must_use]
+    pub fn is_remote(&self) -> bool {
+        *self != Self::None
+    }
+
+    /// Returns the highest colour depth the session passes through to the client terminal.
+    ///
+    /// # Notes
+    /// `mosh` is capped to 256 colours unless its version is known to support RGB colours, see `MOSH_RGB_VERSION`.
+    #[inline]
+    #[must_use]
+    pub fn max_colour_depth(&self, mosh_version: Option<&TerminalVersion>) -> ColourDepth {
+        match self {
+            Self::Mosh if mosh_version.is_none_or(|version| *version < MOSH_RGB_VERSION) => {
+                ColourDepth::Ansi256
+            }
+            _ => ColourDepth::TrueColour,
+        }
+    }
+}
+
+impl Display for RemoteSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An ancestor of the current process, see `process_ancestry()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessInfo {
+    /// The process id.
+    pub pid: u32,
+    /// The name of the process' executable.
+    pub name: String,
+    /// The path to the process' executable, if it could be determined.
+    pub path: Option<PathBuf>,
+}
+
+/// The ancestors of the current process, walked once per process.
+static PROCESS_ANCESTRY: LazyLock<Vec<ProcessInfo>> = LazyLock::new(walk_process_ancestry);
+
+/// The remote session the program is running in, detected once per process.
+static REMOTE_SESSION: LazyLock<RemoteSession> =
+    LazyLock::new(|| classify_remote_session_in(&SystemEnvironment, &PROCESS_ANCESTRY));
+
+/// The version of the `mosh` server the program is running under, queried once per process.
+static MOSH_VERSION: LazyLock<Option<TerminalVersion>> = LazyLock::new(query_mosh_server_version);
+
+/// Returns the remote session the program is running in.
+#[inline]
+#[must_use]
+pub fn remote_session() -> RemoteSession {
+    *REMOTE_SESSION
+}
+
+/// Classifies the remote session from the given environment and process ancestry (nearest ancestor first).
+///
+/// A `mosh-server` ancestor takes precedence over the `SSH` variables, as `mosh` sessions are started over `SSH`.
+#[must_use]
+pub fn classify_remote_session_in(
+    env: &(impl Environment + ?Sized),
+    ancestry: &[ProcessInfo],
+) -> RemoteSession {
+    if ancestry
+        .iter()
+        .any(|process| process.name == MOSH_SERVER_PROCESS)
+    {
+        RemoteSession::Mosh
+    } else if SSH_VARS
+        .iter()
+        .any(|var| env.var(var).is_some_and(|value| !value.is_empty()))
+    {
+        RemoteSession::Ssh
+    } else {
+        RemoteSession::None
+    }
+}
+
+/// Returns the ancestors of the current process, nearest first.
+///
+/// # Notes
+/// Read from `/proc` on `Linux`, `ps` on other unix platforms and a process snapshot on `Windows`, empty if
+/// the ancestry could not be read.
+#[inline]
+#[must_use]
+pub fn process_ancestry() -> &'static [ProcessInfo] {
+    &PROCESS_ANCESTRY
+}
+
+/// Returns the highest colour depth the current remote session passes through.
+///
+/// # Notes
+/// The `mosh` server version is only queried if capability probing is enabled, see `capabilities::set_capability_detection()`.
+#[inline]
+#[must_use]
+pub fn max_colour_depth() -> ColourDepth {
+    match remote_session() {
+        RemoteSession::Mosh => RemoteSession::Mosh.max_colour_depth(MOSH_VERSION.as_ref()),
+        session => session.max_colour_depth(None),
+    }
+}
+
+/// Parses the version from the output of `mosh-server --version`, e.g. `"mosh-server (mosh 1.4.0) [build mosh 1.4.0]"`.
+#[must_use]
+pub fn parse_mosh_version(output: &str) -> Option<TerminalVersion> {
+    let start = output.find("(mosh ")? + "(mosh ".len();
+    let version = &output[start..];
+    let end = version.find(')').unwrap_or(version.len());
+
+    TerminalVersion::parse(&version[..end])
+}
+
+/// Queries the version of the `mosh-server` ancestor, if capability probing is enabled and its path is known.
+fn query_mosh_server_version() -> Option<TerminalVersion> {
+    if capability_detection() != CapabilityDetection::Probe {
+        return None;
+    }
+
+    let path = PROCESS_ANCESTRY
+        .iter()
+        .find(|process| process.name == MOSH_SERVER_PROCESS)?
+        .path
+        .as_ref()?;
+    let output = Command::new(path).arg("--version").output().ok()?;
+
+    parse_mosh_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| parse_mosh_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Walks the ancestors of the current process, see `process_ancestry()`.
+fn walk_process_ancestry() -> Vec<ProcessInfo> {
+    let mut ancestry = Vec::new();
+    let mut pid = std::process::id();
+
+    while ancestry.len() < MAX_ANCESTRY_DEPTH {
+        let Some(parent) = parent_process(pid) else {
+            break;
+        };
+        if parent.pid <= 1 || parent.pid == pid {
+            break;
+        }
+
+        pid = parent.pid;
+        ancestry.push(parent);
+    }
+
+    ancestry
+}
+
+/// Returns the parent of the given process, read from `/proc`.
+#[cfg(target_os = "linux")]
+fn parent_process(pid: u32) -> Option<ProcessInfo> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The name may contain spaces and parentheses, so the fields are parsed from after the last `)`..
+    let parent_pid = stat[stat.rfind(')')? + 1..]
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+
+    let name = std::fs::read_to_string(format!("/proc/{parent_pid}/comm")).ok()?;
+    Some(ProcessInfo {
+        pid: parent_pid,
+        name: name.trim_end().to_string(),
+        path: std::fs::read_link(format!("/proc/{parent_pid}/exe")).ok(),
+    })
+}
+
+/// Returns the parent of the given process, read from `ps`.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn parent_process(pid: u32) -> Option<ProcessInfo> {
+    fn ps(pid: u32, field: &str) -> Option<String> {
+        let output = Command::new("ps")
+            .arg("-o")
+            .arg(format!("{field}="))
+            .arg("-p")
+            .arg(pid.to_string())
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    let parent_pid = ps(pid, "ppid")?.parse().ok()?;
+    let command = PathBuf::from(ps(parent_pid, "comm")?);
+
+    Some(ProcessInfo {
+        pid: parent_pid,
+        name: command.file_name()?.to_string_lossy().into_owned(),
+        path: command.is_absolute().then_some(command),
+    })
+}
+
+/// Returns the parent of the given process, read from a process snapshot.
+///
+/// # Notes
+/// Process ids are reused on `Windows`, so a parent that exited may be mistaken for an unrelated process.
+#[cfg(windows)]
+fn parent_process(pid: u32) -> Option<ProcessInfo> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
+        TH32CS_SNAPPROCESS,
+    };
+
+    // SAFETY: `CreateToolhelp32Snapshot` has no preconditions.
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    // SAFETY: `PROCESSENTRY32W` is a plain C struct, `dwSize` is set before use as required.
+    let mut entry: PROCESSENTRY32W = unsafe { std::mem::zeroed() };
+    entry.dwSize = u32::try_from(size_of::<PROCESSENTRY32W>()).unwrap_or_default();
+
+    // SAFETY: `snapshot` is a valid snapshot handle and `entry` is a valid pointer, for all calls below.
+    unsafe {
+        let mut found = Process32FirstW(snapshot, &raw mut entry);
+        while found != 0 {
+            let len = entry
+                .szExeFile
+                .iter()
+                .position(|c| *c == 0)
+                .unwrap_or(entry.szExeFile.len());
+            entries.push((
+                entry.th32ProcessID,
+                entry.th32ParentProcessID,
+                String::from_utf16_lossy(&entry.szExeFile[..len]),
+            ));
+            found = Process32NextW(snapshot, &raw mut entry);
+        }
+        CloseHandle(snapshot);
+    }
+
+    let parent_pid = entries
+        .iter()
+        .find(|(process_pid, ..)| *process_pid == pid)
+        .map(|(_, parent_pid, _)| *parent_pid)?;
+    let (_, _, name) = entries
+        .into_iter()
+        .find(|(process_pid, ..)| *process_pid == parent_pid)?;
+
+    Some(ProcessInfo {
+        pid: parent_pid,
+        name,
+        path: None,
+    })
+}
+
+/// Returns the parent of the given process, unsupported on this platform.
+#[cfg(not(any(unix, windows)))]
+fn parent_process(_pid: u32) -> Option<ProcessInfo> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::{
+        MOSH_RGB_VERSION, MOSH_SERVER_PROCESS, ProcessInfo, RemoteSession, SSH_VARS,
+        classify_remote_session_in, parse_mosh_version,
+    };
+    use crate::capabilities::ColourDepth;
+    use crate::version::TerminalVersion;
+
+    fn process(pid: u32, name: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            path: Some(PathBuf::from("/usr/bin").join(name)),
+        }
+    }
+
+    #[test]
+    fn sessions_are_classified_from_the_environment_and_ancestry() {
+        let local = [process(20, "bash"), process(10, "alacritty")];
+        let mosh = [
+            process(30, "bash"),
+            process(20, MOSH_SERVER_PROCESS),
+            process(10, "systemd"),
+        ];
+        // A similarly named process isn't `mosh`..
+        let not_mosh = [process(20, "mosh-server-wrapper"), process(10, "mosh")];
+
+        let no_vars = HashMap::<String, String>::new();
+        let mut sessions = vec![
+            (no_vars.clone(), &local[..], RemoteSession::None),
+            (no_vars.clone(), &[][..], RemoteSession::None),
+            (no_vars.clone(), &not_mosh[..], RemoteSession::None),
+            (no_vars.clone(), &mosh[..], RemoteSession::Mosh),
+        ];
+        for var in SSH_VARS {
+            let ssh = HashMap::from([(var.to_string(), "10.0.0.2 50000 10.0.0.1 22".to_string())]);
+            let empty = HashMap::from([(var.to_string(), String::new())]);
+            sessions.extend([
+                (ssh.clone(), &local[..], RemoteSession::Ssh),
+                // ..and `mosh`, started over `SSH`, takes precedence..
+                (ssh, &mosh[..], RemoteSession::Mosh),
+                (empty, &local[..], RemoteSession::None),
+            ]);
+        }
+
+        for (env, ancestry, expected) in sessions {
+            let session = classify_remote_session_in(&env, ancestry);
+            assert_eq!(session, expected, "{env:?} {ancestry:?}");
+            assert_eq!(session.is_remote(), expected != RemoteSession::None);
+        }
+    }
+
+    #[test]
+    fn mosh_is_capped_to_256_colours_unless_known_good() {
+        assert_eq!(
+            RemoteSession::Mosh.max_colour_depth(None),
+            ColourDepth::Ansi256
+        );
+        assert_eq!(
+            RemoteSession::Mosh.max_colour_depth(Some(&TerminalVersion::new(1, 3, 2))),
+            ColourDepth::Ansi256
+        );
+        assert_eq!(
+            RemoteSession::Mosh.max_colour_depth(Some(&MOSH_RGB_VERSION)),
+            ColourDepth::TrueColour
+        );
+        for session in [RemoteSession::None, RemoteSession::Ssh] {
+            assert_eq!(session.max_colour_depth(None), ColourDepth::TrueColour);
+        }
+    }
+
+    #[test]
+    fn mosh_server_versions_are_parsed() {
+        for (output, version) in [
+            (
+                "mosh-server (mosh 1.4.0) [build mosh 1.4.0]\nCopyright 2012 Keith Winstein\n",
+                Some(TerminalVersion::new(1, 4, 0)),
+            ),
+            (
+                "mosh-server (mosh 1.3.2) [build mosh-1.3.2]\n",
+                Some(TerminalVersion::new(1, 3, 2)),
+            ),
+            (
+                "mosh-server (mosh 1.4.0",
+                Some(TerminalVersion::new(1, 4, 0)),
+            ),
+            ("mosh-server: unknown option -- -\n", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_mosh_version(output), version, "{output:?}");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn ancestry_starts_at_the_parent_process() {
+        let parent = std::os::unix::process::parent_id();
+        if parent > 1 {
+            assert_eq!(
+                super::process_ancestry().first().map(|process| process.pid),
+                Some(parent)
+            );
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! A rendezvous between a launcher and the program it relaunched, so the launcher can wait until the relaunched
+//! program is ready (e.g. to send it a file to open), rather than sleeping and hoping.
+//!
+//! The launcher creates a uniquely named local socket before relaunching (see `relaunch_with_rendezvous()`), and
+//! passes its path to the relaunched program in the handoff, see `handoff::RelaunchHandoff`. The relaunched program
+//! signals it's ready when it reads the handoff with `handoff::read_relaunch_handoff()`, or calls
+//! `signal_relaunch_ready()`, which connects to the socket and writes a single byte.
+//!
+//! # Notes
+//! *   Uses `Unix` domain sockets on unix platforms, and named pipes on `Windows`, other platforms are unsupported.
+//! *   The socket is removed when the `Rendezvous` is dropped, whether the relaunched program signalled, crashed or
+//!     never started.
+//!
+//! # Example
+//! ```rust
+//! # #[cfg(any(unix, windows))] {
+//! use std::time::Duration;
+//!
+//! use terminal_relaunch::rendezvous::{Rendezvous, signal_ready_at};
+//!
+//! let rendezvous = Rendezvous::create().unwrap();
+//! let path = rendezvous.path().to_path_buf();
+//!
+//! // Usually signalled by the relaunched program with `signal_relaunch_ready()`..
+//! let child = std::thread::spawn({
+//!     let path = path.clone();
+//!     move || signal_ready_at(&path)
+//! });
+//! assert!(rendezvous.wait_ready(Duration::from_secs(5)).unwrap());
+//! child.join().unwrap().unwrap();
+//!
+//! // A program that never signals times out..
+//! let silent = Rendezvous::create().unwrap();
+//! assert!(!silent.wait_ready(Duration::from_millis(50)).unwrap());
+//!
+//! // The sockets are removed on drop, however the wait ended..
+//! let silent_path = silent.path().to_path_buf();
+//! drop((rendezvous, silent));
+//! assert!(!path.exists() && !silent_path.exists());
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[allow(unused_imports)]
+use crate::errors::RelaunchError;
+use crate::errors::TermResult;
+
+/// Byte written by the relaunched program to signal it's ready.
+const READY_BYTE: u8 = 1;
+
+/// How often the socket is checked for a connection while waiting.
+#[cfg(any(unix, windows))]
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Paths of the rendezvous this program already signalled, so each is only signalled once.
+static SIGNALLED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// Path of the rendezvous socket to pass to relaunches on this thread, if set.
+    static PENDING_RENDEZVOUS: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// A local socket the relaunched program signals readiness on, see the module documentation.
+#[derive(Debug)]
+pub struct Rendezvous {
+    /// The path of the socket.
+    path: PathBuf,
+    /// The listening socket.
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    /// The server end of the named pipe, in non-blocking mode.
+    #[cfg(windows)]
+    pipe: std::fs::File,
+}
+
+impl Rendezvous {
+    /// Creates a uniquely named rendezvous socket, in the temporary directory on unix platforms, or a named pipe on
+    /// `Windows`.
+    ///
+    /// # Errors
+    /// *   `RelaunchError::RendezvousUnsupported` on platforms other than unix and `Windows`.
+    /// *   `RelaunchError::IOError` if the socket could not be created.
+    pub fn create() -> TermResult<Self> {
+        #[cfg(unix)]
+        {
+            let path = std::env::temp_dir().join(format!("{}.sock", unique_name()));
+
+            let listener = std::os::unix::net::UnixListener::bind(&path)?;
+            let rendezvous = Self { path, listener };
+            // Created first, so the socket is removed on drop if this fails..
+            rendezvous.listener.set_nonblocking(true)?;
+
+            Ok(rendezvous)
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+            use std::os::windows::io::FromRawHandle;
+
+            use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+            use windows_sys::Win32::Storage::FileSystem::{
+                FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_INBOUND,
+            };
+            use windows_sys::Win32::System::Pipes::{
+                CreateNamedPipeW, PIPE_NOWAIT, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_TYPE_BYTE,
+            };
+
+            let path = PathBuf::from(format!(r"\\.\pipe\{}", unique_name()));
+            let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+
+            // SAFETY: The name is null terminated, and the returned handle is owned by the file below..
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    wide.as_ptr(),
+                    PIPE_ACCESS_INBOUND | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                    PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_NOWAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    0,
+                    1,
+                    0,
+                    std::ptr::null(),
+                )
+            };
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            // SAFETY: The handle is valid, and owned by nothing else..
+            let pipe = unsafe { std::fs::File::from_raw_handle(handle) };
+
+            Ok(Self { path, pipe })
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        {
+            Err(RelaunchError::RendezvousUnsupported)
+        }
+    }
+
+    /// Returns the path of the socket.
+    #[inline]
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Blocks until the relaunched program signals it's ready, or the timeout expires.
+    ///
+    /// # Returns
+    /// *   `Ok(true)` if the relaunched program signalled it's ready.
+    /// *   `Ok(false)` if the timeout expired first, e.g. the relaunched program crashed or never started.
+    ///
+    /// # Errors
+    /// *   `RelaunchError::RendezvousUnsupported` on platforms other than unix and `Windows`.
+    /// *   `RelaunchError::IOError` if waiting on the socket failed.
+    pub fn wait_ready(&self, timeout: Duration) -> TermResult<bool> {
+        #[cfg(unix)]
+        {
+            use std::io::Read;
+
+            let deadline = std::time::Instant::now() + timeout;
+            loop {
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                match self.listener.accept() {
+                    Ok((mut stream, _)) => {
+                        stream.set_nonblocking(false)?;
+                        stream.set_read_timeout(Some(remaining.max(POLL_INTERVAL)))?;
+
+                        let mut byte = [0];
+                        // Anything else connecting to the socket is ignored..
+                        if matches!(stream.read(&mut byte), Ok(1)) && byte[0] == READY_BYTE {
+                            return Ok(true);
+                        }
+                    }
+                    Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                        if remaining.is_zero() {
+                            return Ok(false);
+                        }
+                        std::thread::sleep(remaining.min(POLL_INTERVAL));
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+            }
+        }
+
+        #[cfg(windows)]
+        {
+            use std::io::Read;
+            use std::os::windows::io::AsRawHandle;
+
+            use windows_sys::Win32::Foundation::{
+                ERROR_NO_DATA, ERROR_PIPE_CONNECTED, ERROR_PIPE_LISTENING,
+            };
+            use windows_sys::Win32::System::Pipes::{ConnectNamedPipe, DisconnectNamedPipe};
+
+            let handle = self.pipe.as_raw_handle();
+            let deadline = std::time::Instant::now() + timeout;
+            loop {
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+
+                // In non-blocking mode, this reports whether a client connected, rather than waiting for one..
+                // SAFETY: The handle is the server end of a named pipe, owned by `self`..
+                let listening = if unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } != 0 {
+                    false
+                } else {
+                    let error = std::io::Error::last_os_error();
+                    match error
+                        .raw_os_error()
+                        .and_then(|code| u32::try_from(code).ok())
+                    {
+                        Some(ERROR_PIPE_CONNECTED | ERROR_NO_DATA) => false,
+                        Some(ERROR_PIPE_LISTENING) => true,
+                        _ => return Err(error.into()),
+                    }
+                };
+
+                if !listening {
+                    let mut byte = [0];
+                    match (&self.pipe).read(&mut byte) {
+                        Ok(1) if byte[0] == READY_BYTE => return Ok(true),
+                        // Connected, but nothing written yet..
+                        Err(error)
+                            if error
+                                .raw_os_error()
+                                .and_then(|code| u32::try_from(code).ok())
+                                == Some(ERROR_NO_DATA) => {}
+                        // Anything else connecting to the pipe is ignored, and the pipe listens again..
+                        Ok(_) | Err(_) => {
+                            // SAFETY: As above..
+                            unsafe { DisconnectNamedPipe(handle) };
+                        }
+                    }
+                }
+
+                if remaining.is_zero() {
+                    return Ok(false);
+                }
+                std::thread::sleep(remaining.min(POLL_INTERVAL));
+            }
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = timeout;
+            Err(RelaunchError::RendezvousUnsupported)
+        }
+    }
+}
+
+impl Drop for Rendezvous {
+    fn drop(&mut self) {
+        // Named pipes disappear with their last handle, sockets have to be removed..
+        #[cfg(not(windows))]
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Returns a name for a new rendezvous, unique to this process and the time.
+#[cfg(any(unix, windows))]
+fn unique_name() -> String {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Distinguishes rendezvous created by the same process.
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    format!(
+        "terminal-relaunch-{}-{}-{nanos:08x}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    )
+}
+
+/// Relaunches the current program in a preferred terminal as `try_relaunch_in_preferred_terminal()`, passing it
+/// a new rendezvous to wait on with `Rendezvous::wait_ready()`.
+///
+/// # Notes
+/// The rendezvous travels in the handoff (see `handoff::RelaunchHandoff`), which the relaunched program signals when
+/// reading it with `handoff::read_relaunch_handoff()`.
+///
+/// # Errors
+/// *   Any error from `Rendezvous::create()`.
+/// *   Any error from `try_relaunch_in_preferred_terminal()`, the rendezvous is removed.
+pub fn relaunch_with_rendezvous() -> TermResult<Rendezvous> {
+    with_rendezvous(crate::try_relaunch_in_preferred_terminal)
+}
+
+/// Performs the given relaunch (e.g. of a configured `pipeline::RelaunchPipeline`) passing a new rendezvous, see
+/// `relaunch_with_rendezvous()`.
+///
+/// # Errors
+/// *   Any error from `Rendezvous::create()`.
+/// *   Any error from the relaunch, the rendezvous is removed.
+pub fn with_rendezvous(relaunch: impl FnOnce() -> TermResult<()>) -> TermResult<Rendezvous> {
+    let rendezvous = Rendezvous::create()?;
+
+    let previous = PENDING_RENDEZVOUS.with(|cell| cell.replace(Some(rendezvous.path.clone())));
+    let result = relaunch();
+    PENDING_RENDEZVOUS.with(|cell| cell.replace(previous));
+
+    result.map(|()| rendezvous)
+}
+
+/// Returns the path of the rendezvous to pass to relaunches on this thread, if any.
+pub(crate) fn pending_path() -> Option<PathBuf> {
+    PENDING_RENDEZVOUS.with(|cell| cell.borrow().clone())
+}
+
+/// Returns the path of the rendezvous passed to this program by its launcher in the handoff, if any.
+#[must_use]
+pub fn relaunch_rendezvous_path() -> Option<PathBuf> {
+    crate::handoff::passed_handoff().and_then(|handoff| handoff.rendezvous)
+}
+
+/// Signals the launcher that this relaunched program is ready, if it passed a rendezvous.
+///
+/// # Notes
+/// `handoff::read_relaunch_handoff()` already signals, so this is only needed by programs that don't read the
+/// handoff. The launcher is only signalled once, however often this is called.
+///
+/// # Returns
+/// *   `Ok(true)` if the launcher was signalled, now or before.
+/// *   `Ok(false)` if no rendezvous was passed, e.g. the program wasn't relaunched.
+///
+/// # Errors
+/// Any error from `signal_ready_at()`, e.g. if the launcher stopped waiting.
+pub fn signal_relaunch_ready() -> TermResult<bool> {
+    let Some(path) = relaunch_rendezvous_path() else {
+        return Ok(false);
+    };
+
+    signal_ready_once(&path).map(|()| true)
+}
+
+/// Signals readiness on the rendezvous at the given path, unless this program already did.
+///
+/// # Errors
+/// Any error from `signal_ready_at()`, the rendezvous may be signalled again after one.
+pub(crate) fn signal_ready_once(path: &Path) -> TermResult<()> {
+    let mut signalled = SIGNALLED
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if signalled.iter().any(|signalled| signalled == path) {
+        return Ok(());
+    }
+
+    signal_ready_at(path)?;
+    signalled.push(path.to_path_buf());
+    Ok(())
+}
+
+/// Signals readiness on the rendezvous socket at the given path.
+///
+/// # Errors
+/// *   `RelaunchError::RendezvousUnsupported` on platforms other than unix and `Windows`.
+/// *   `RelaunchError::IOError` if the socket could not be connected to or written, e.g. it was removed.
+pub fn signal_ready_at(path: &Path) -> TermResult<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+
+        let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+        stream.write_all(&[READY_BYTE])?;
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        use std::io::Write;
+
+        let mut pipe = std::fs::OpenOptions::new().write(true).open(path)?;
+        pipe.write_all(&[READY_BYTE])?;
+
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        Err(RelaunchError::RendezvousUnsupported)
+    }
+}
@@ -0,0 +1,313 @@
+//! Explicit, one-time initialization of the library's global state.
+//!
+//! Every global the library reads (the detected terminal, the relaunch configuration, capability hints and rules,
+//! the capability detection mode, the output style, registered providers and the startup state) lives in a single
+//! `RuntimeState`, created on first use. Setters called before `init()` stage their values, `init()` applies a whole
+//! configuration over them, then performs detection, guaranteeing the configuration is seen by the statics.
+//!
+//! Detecting the terminal (e.g. reading `CURRENT_TERMINAL`) before `init()` initializes the state implicitly with
+//! the values staged so far, so `init()` racing the first detection either applies its configuration before any
+//! thread detects, or returns `AlreadyInitialized`.
+//!
+//! # Mutability after initialization
+//!
+//! | Setting                                               | After `init()`                                   |
+//! |-------------------------------------------------------|--------------------------------------------------|
+//! | `config::set_relaunch_config()`                       | Applies.                                         |
+//! | Overrides and hints, e.g. `set_unicode_support_override()` | Applies, but the capability statics are latched. |
+//! | `capabilities::register_capability_override()`        | Applies, but the capability statics are latched. |
+//! | `output::set_output_style()`                          | Applies.                                         |
+//! | `registry::register_provider()`                       | Applies.                                         |
+//! | `startup::capture_startup_state()`                    | Applies.                                         |
+//! | `capabilities::set_capability_detection()`            | Errors with `AlreadyInitialized`.                |
+//! | Detection (`CURRENT_TERMINAL`, versions)              | Immutable for the lifetime of the process.       |
+//!
+//! Calling `init()` a second time returns `AlreadyInitialized`.
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::capabilities::{CapabilityDetection, set_capability_detection};
+//! use terminal_relaunch::runtime::{AlreadyInitialized, RuntimeConfig, init, is_initialized};
+//!
+//! // Staged before initialization..
+//! set_capability_detection(CapabilityDetection::Environment).unwrap();
+//! let _guard = init(RuntimeConfig { unicode_override: Some(true), ..Default::default() }).unwrap();
+//! assert!(is_initialized() && *terminal_relaunch::SUPPORTS_FULL_UNICODE);
+//!
+//! // ..and rejected after, as it only affects the latched statics..
+//! assert_eq!(set_capability_detection(CapabilityDetection::Probe), Err(AlreadyInitialized));
+//! assert_eq!(init(RuntimeConfig::default()).err(), Some(AlreadyInitialized));
+//! ```
+
+use std::sync::atomic::{AtomicU8, AtomicU64};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use crate::TerminalType;
+use crate::capabilities::{CapabilityDetection, CapabilityRule, PartialCapabilities};
+use crate::config::RelaunchConfig;
+use crate::output::OutputStyle;
+use crate::registry::SharedProvider;
+use crate::startup::StartupState;
+
+/// The configuration applied by `init()`, over the values staged by the setters.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    /// The relaunch configuration, if set, otherwise the staged configuration is kept.
+    pub relaunch: Option<RelaunchConfig>,
+    /// Overrides full unicode support detection, if set, otherwise the staged override is kept.
+    pub unicode_override: Option<bool>,
+    /// Overrides RGB (ANSI) colour support detection, if set, otherwise the staged override is kept.
+    pub rgb_ansi_override: Option<bool>,
+    /// Capability rules to register, in order, after the staged rules, see
+    /// `capabilities::register_capability_override()`.
+    pub capability_rules: Vec<CapabilityRule>,
+    /// How the capabilities of the current terminal are detected, if set, otherwise the staged mode is kept.
+    pub capability_detection: Option<CapabilityDetection>,
+    /// How text generated by the crate is styled, if set, otherwise the staged style is kept.
+    pub output_style: Option<OutputStyle>,
+}
+
+/// Error returned by `init()`, and by setters that can't apply after initialization, when the library has already
+/// been initialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The terminal relaunch library has already been initialized.")]
+pub struct AlreadyInitialized;
+
+/// Guard returned by a successful `init()`.
+///
+/// With the `test-util` feature enabled, `InitGuard::tear_down()` resets the configurable global state, so tests
+/// can each initialize the library. Dropping the guard does nothing.
+#[derive(Debug)]
+pub struct InitGuard {
+    _private: (),
+}
+
+impl InitGuard {
+    /// Resets the configurable global state (overrides, capability rules and relaunch configuration), and allows
+    /// `init()` to be called again.
+    ///
+    /// # Notes
+    /// Detection results remain latched, see `reset_all_global_state()`.
+    #[cfg(feature = "test-util")]
+    pub fn tear_down(self) {
+        let state = state();
+        let mut phase = state.lock_phase();
+        state.reset_configurable();
+        *phase = Phase::Staging;
+    }
+}
+
+/// Whether the state is still staging values, see `RuntimeState::phase`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Setters stage values for `init()`, or the first detection.
+    Staging,
+    /// `init()` has been called, or the terminal detected.
+    Initialized,
+}
+
+/// The library's global state, see `state()`.
+pub(crate) struct RuntimeState {
+    /// Whether the state is initialized, held by `init()` while applying its configuration, and by the first
+    /// detection, so neither sees the other half done.
+    phase: Mutex<Phase>,
+    /// The detected terminal, see `CURRENT_TERMINAL`.
+    terminal: OnceLock<TerminalType>,
+    /// The relaunch configuration, see `config::set_relaunch_config()`.
+    pub(crate) relaunch_config: RwLock<RelaunchConfig>,
+    /// The capability hints, indexed by `HintSource`, see `capabilities::set_capability_hint()`.
+    pub(crate) capability_hints: RwLock<[PartialCapabilities; 3]>,
+    /// The registered capability rules, in registration order, see `capabilities::register_capability_override()`.
+    pub(crate) capability_rules: RwLock<Vec<CapabilityRule>>,
+    /// The capability detection mode, see `capabilities::set_capability_detection()`.
+    capability_detection: AtomicU8,
+    /// The output style chosen by the application, see `output::set_output_style()`.
+    pub(crate) output_style: AtomicU8,
+    /// The registered providers with their registration ids, in registration order, see
+    /// `registry::register_provider()`.
+    pub(crate) providers: RwLock<Vec<(u64, SharedProvider)>>,
+    /// The id of the next provider registration.
+    pub(crate) next_registration: AtomicU64,
+    /// The captured startup state, see `startup::capture_startup_state()`.
+    ///
+    /// Snapshots are leaked so references to them can be handed out, a refresh leaks the previous one.
+    pub(crate) startup: RwLock<Option<&'static StartupState>>,
+}
+
+/// The library's global state.
+static STATE: OnceLock<RuntimeState> = OnceLock::new();
+
+/// Returns the library's global state, creating it with the defaults on first use.
+#[inline]
+pub(crate) fn state() -> &'static RuntimeState {
+    STATE.get_or_init(RuntimeState::new)
+}
+
+impl RuntimeState {
+    /// Creates the default state, staging values.
+    fn new() -> Self {
+        Self {
+            phase: Mutex::new(Phase::Staging),
+            terminal: OnceLock::new(),
+            relaunch_config: RwLock::new(RelaunchConfig::new()),
+            capability_hints: RwLock::new([PartialCapabilities::NONE; 3]),
+            capability_rules: RwLock::new(Vec::new()),
+            capability_detection: AtomicU8::new(CapabilityDetection::Environment as u8),
+            output_style: AtomicU8::new(OutputStyle::Auto as u8),
+            providers: RwLock::new(Vec::new()),
+            next_registration: AtomicU64::new(0),
+            startup: RwLock::new(None),
+        }
+    }
+
+    /// Locks the phase.
+    fn lock_phase(&self) -> std::sync::MutexGuard<'_, Phase> {
+        self.phase
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Returns the detected terminal, detecting it the first time, which initializes the state.
+    pub(crate) fn terminal(&self) -> TerminalType {
+        *self.terminal.get_or_init(|| {
+            *self.lock_phase() = Phase::Initialized;
+            crate::find_current_terminal()
+        })
+    }
+
+    /// Returns the capability detection mode.
+    pub(crate) fn capability_detection(&self) -> CapabilityDetection {
+        if self
+            .capability_detection
+            .load(std::sync::atomic::Ordering::SeqCst)
+            == CapabilityDetection::Probe as u8
+        {
+            CapabilityDetection::Probe
+        } else {
+            CapabilityDetection::Environment
+        }
+    }
+
+    /// Stages the capability detection mode.
+    ///
+    /// # Errors
+    /// Returns `AlreadyInitialized` if the state is initialized.
+    pub(crate) fn set_capability_detection(
+        &self,
+        detection: CapabilityDetection,
+    ) -> Result<(), AlreadyInitialized> {
+        let phase = self.lock_phase();
+        if *phase == Phase::Initialized {
+            return Err(AlreadyInitialized);
+        }
+
+        self.store_capability_detection(detection);
+        Ok(())
+    }
+
+    /// Stores the capability detection mode, whatever the phase.
+    fn store_capability_detection(&self, detection: CapabilityDetection) {
+        self.capability_detection
+            .store(detection as u8, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Applies the given configuration over the staged values, with the phase locked.
+    fn apply(&self, config: RuntimeConfig) {
+        if let Some(relaunch) = config.relaunch {
+            crate::config::set_relaunch_config(relaunch);
+        }
+        if let Some(supports) = config.unicode_override {
+            crate::set_unicode_support_override(Some(supports));
+        }
+        if let Some(supports) = config.rgb_ansi_override {
+            crate::set_rgb_ansi_override(Some(supports));
+        }
+        for rule in config.capability_rules {
+            crate::capabilities::register_capability_override(rule);
+        }
+        if let Some(detection) = config.capability_detection {
+            self.store_capability_detection(detection);
+        }
+        if let Some(style) = config.output_style {
+            crate::output::set_output_style(style);
+        }
+    }
+
+    /// Resets the configurable values to their defaults.
+    #[cfg(feature = "test-util")]
+    fn reset_configurable(&self) {
+        *self
+            .relaunch_config
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = RelaunchConfig::new();
+        *self
+            .capability_hints
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = [PartialCapabilities::NONE; 3];
+        self.capability_rules
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clear();
+    }
+}
+
+/// Initializes the library with the given configuration, then performs terminal detection.
+///
+/// This should be called once, at the start of `main`, before any other threads read the detection statics.
+///
+/// # Errors
+/// Returns `AlreadyInitialized` if the library has already been initialized, including implicitly by detecting the
+/// terminal first, in which case the configuration is not applied.
+pub fn init(config: RuntimeConfig) -> Result<InitGuard, AlreadyInitialized> {
+    let state = state();
+    {
+        let mut phase = state.lock_phase();
+        if *phase == Phase::Initialized {
+            return Err(AlreadyInitialized);
+        }
+
+        state.apply(config);
+        *phase = Phase::Initialized;
+    }
+
+    // Latch detection with the configuration applied..
+    std::sync::LazyLock::force(&crate::CURRENT_TERMINAL);
+    std::sync::LazyLock::force(&crate::SUPPORTS_FULL_UNICODE);
+    std::sync::LazyLock::force(&crate::SUPPORTS_RGB_ANSI_COLOURS);
+
+    Ok(InitGuard { _private: () })
+}
+
+/// Returns `true` if the library is initialized, i.e. `init()` has been called or the terminal detected.
+#[inline]
+#[must_use]
+pub fn is_initialized() -> bool {
+    *state().lock_phase() == Phase::Initialized
+}
+
+/// Resets all resettable global state, so tests sharing a process don't depend on each other's order.
+///
+/// # Notes
+/// *   Clears the overrides, capability rules, capability detection mode, output style, registered providers,
+///     relaunch configuration and captured startup state, and allows `init()` to be called again.
+/// *   Detection results latched in statics (e.g. `CURRENT_TERMINAL`, `SUPPORTS_FULL_UNICODE`) are process-lifetime,
+///     and can't be reset. Tests should read the capability getters (e.g. `supports_full_unicode()`) instead.
+#[cfg(feature = "test-util")]
+pub fn reset_all_global_state() {
+    let state = state();
+    let mut phase = state.lock_phase();
+
+    state.reset_configurable();
+    state.store_capability_detection(CapabilityDetection::Environment);
+    crate::output::set_output_style(OutputStyle::Auto);
+    state
+        .providers
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clear();
+    *state
+        .startup
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+    *phase = Phase::Staging;
+}
@@ -0,0 +1,200 @@
+//! Detection of running as a background service (a `Windows` service, `launchd` daemon or `systemd` unit), where
+//! there is no interactive session to relaunch in.
+//!
+//! Spawning a terminal from `Windows` session 0 either fails or shows a window on the secure desktop, so relaunching
+//! is skipped and the capabilities are reported as unsupported.
+
+use std::sync::LazyLock;
+
+use crate::environment::Environment;
+
+/// Environment variable set by `launchd` for the jobs it starts.
+pub const XPC_SERVICE_NAME_VAR: &str = "XPC_SERVICE_NAME";
+
+/// Environment variable set by `systemd` for the units it starts.
+pub const INVOCATION_ID_VAR: &str = "INVOCATION_ID";
+
+/// If the program is running as a service, detected once per process.
+static SERVICE_CONTEXT: LazyLock<bool> = LazyLock::new(detect_service_context);
+
+/// Returns `true` if the program is running as a background service, without an interactive session.
+///
+/// # Notes
+/// *   On `Windows`, this is the case in session 0 or outside the interactive window station (`WinSta0`).
+/// *   Elsewhere, see `is_service_context_in()`.
+#[inline]
+#[must_use]
+pub fn is_service_context() -> bool {
+    *SERVICE_CONTEXT
+}
+
+/// Returns `true` if the given environment belongs to a `launchd` job or `systemd` unit, and there is no controlling TTY.
+///
+/// # Notes
+/// Interactive programs started from e.g. `Terminal.app` or a `systemd` user session may also have these variables
+/// set, so they are only trusted without a controlling TTY. `XPC_SERVICE_NAME=0` is set for interactive sessions.
+#[must_use]
+pub fn is_service_context_in(env: &(impl Environment + ?Sized), has_controlling_tty: bool) -> bool {
+    if has_controlling_tty {
+        return false;
+    }
+
+    let launchd_job = env
+        .var(XPC_SERVICE_NAME_VAR)
+        .is_some_and(|name| !name.is_empty() && name != "0");
+    let systemd_unit = env.var(INVOCATION_ID_VAR).is_some_and(|id| !id.is_empty());
+
+    launchd_job || systemd_unit
+}
+
+/// Detects if the program is running as a service, see `is_service_context()`.
+fn detect_service_context() -> bool {
+    #[cfg(windows)]
+    {
+        let service = is_windows_service(in_session_zero, in_interactive_window_station);
+        if service {
+            crate::logging::info!("Running as a Windows service, relaunching is disabled.");
+        }
+        service
+    }
+
+    #[cfg(not(windows))]
+    {
+        is_service_context_in(
+            &crate::environment::SystemEnvironment,
+            has_controlling_tty(),
+        )
+    }
+}
+
+/// Returns `true` if the process is running in session 0 or outside the interactive window station, checking each
+/// with the given probe.
+#[cfg(any(windows, test))]
+fn is_windows_service(
+    in_session_zero: impl FnOnce() -> bool,
+    in_interactive_window_station: impl FnOnce() -> bool,
+) -> bool {
+    in_session_zero() || !in_interactive_window_station()
+}
+
+/// Returns `true` if the given window station name is the interactive window station, `WinSta0`.
+#[cfg(any(windows, test))]
+fn is_interactive_window_station(name: &str) -> bool {
+    name.eq_ignore_ascii_case("WinSta0")
+}
+
+/// Returns `true` if the process has a controlling TTY.
+#[cfg(not(windows))]
+fn has_controlling_tty() -> bool {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .is_ok()
+}
+
+/// Returns `true` if the process is running in session 0, where services run.
+#[cfg(windows)]
+fn in_session_zero() -> bool {
+    use windows_sys::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+    use windows_sys::Win32::System::Threading::GetCurrentProcessId;
+
+    let mut session_id = u32::MAX;
+    // SAFETY: `GetCurrentProcessId` has no preconditions, and `session_id` is a valid pointer.
+    let result = unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &raw mut session_id) };
+
+    result != 0 && session_id == 0
+}
+
+/// Returns `true` if the process' window station is the interactive one (`WinSta0`), or it can't be determined.
+#[cfg(windows)]
+fn in_interactive_window_station() -> bool {
+    use windows_sys::Win32::System::StationsAndDesktops::{
+        GetProcessWindowStation, GetUserObjectInformationW, UOI_NAME,
+    };
+
+    // SAFETY: `GetProcessWindowStation` has no preconditions, the returned handle must not be closed.
+    let station = unsafe { GetProcessWindowStation() };
+    if station.is_null() {
+        return true;
+    }
+
+    let mut name = [0u16; 64];
+    let mut needed = 0;
+    // SAFETY: `station` is a valid window station handle, and `name` is valid for the given length in bytes.
+    let result = unsafe {
+        GetUserObjectInformationW(
+            station,
+            UOI_NAME,
+            name.as_mut_ptr().cast(),
+            u32::try_from(size_of_val(&name)).unwrap_or_default(),
+            &raw mut needed,
+        )
+    };
+    if result == 0 {
+        return true;
+    }
+
+    let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+    is_interactive_window_station(&String::from_utf16_lossy(&name[..len]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        INVOCATION_ID_VAR, XPC_SERVICE_NAME_VAR, is_interactive_window_station,
+        is_service_context_in, is_windows_service,
+    };
+
+    #[test]
+    fn launchd_and_systemd_signals_need_no_controlling_tty() {
+        for (vars, service) in [
+            (&[][..], false),
+            (&[(XPC_SERVICE_NAME_VAR, "com.example.daemon")][..], true),
+            (
+                &[(INVOCATION_ID_VAR, "4f1c0b9e2d7a4a6e9c3b5d8f0a1e2c3d")][..],
+                true,
+            ),
+            // `launchd` sets `0` for interactive sessions, and empty values don't count..
+            (&[(XPC_SERVICE_NAME_VAR, "0")][..], false),
+            (&[(XPC_SERVICE_NAME_VAR, "")][..], false),
+            (&[(INVOCATION_ID_VAR, "")][..], false),
+            (
+                &[(XPC_SERVICE_NAME_VAR, "0"), (INVOCATION_ID_VAR, "4f1c0b9e")][..],
+                true,
+            ),
+        ] {
+            let env: HashMap<String, String> = vars
+                .iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect();
+            assert_eq!(is_service_context_in(&env, false), service, "{vars:?}");
+            // ..and a controlling TTY means an interactive program, whatever is set..
+            assert!(!is_service_context_in(&env, true), "{vars:?}");
+        }
+    }
+
+    #[test]
+    fn windows_signals_each_mean_a_service() {
+        assert!(!is_windows_service(|| false, || true));
+        assert!(is_windows_service(|| true, || true));
+        assert!(is_windows_service(|| false, || false));
+        // The window station isn't checked in session 0..
+        assert!(is_windows_service(
+            || true,
+            || panic!("window station checked in session 0")
+        ));
+
+        for (name, interactive) in [
+            ("WinSta0", true),
+            ("winsta0", true),
+            ("Service-0x0-3e7$", false),
+            ("WinSta1", false),
+            ("", false),
+        ] {
+            assert_eq!(is_interactive_window_station(name), interactive, "{name:?}");
+        }
+    }
+}
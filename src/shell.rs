@@ -0,0 +1,159 @@
+//! Detection of the shell hosting the program on `Windows`, e.g. `PowerShell 7` rather than `CMD`.
+//!
+//! `TerminalType::WindowsCMD` covers every program running in the default console host, whichever shell it was
+//! started from. The shell matters for what the console does with VT sequences, see
+//! `capabilities::console_vt_support()`, as `PowerShell 7` leaves VT processing enabled for the programs it runs,
+//! while `CMD` and `Windows PowerShell` don't.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::remote::ProcessInfo;
+//! use terminal_relaunch::shell::{Shell, classify_host_shell_in};
+//!
+//! let process = |name: &str| ProcessInfo { pid: 4242, name: name.to_string(), path: None };
+//! let system_modules = r"C:\Program Files\WindowsPowerShell\Modules;C:\WINDOWS\system32\WindowsPowerShell\v1.0\Modules";
+//! let pwsh_modules = format!(r"C:\Users\me\Documents\PowerShell\Modules;C:\Program Files\PowerShell\7\Modules;{system_modules}");
+//! let env = |modules: &str| HashMap::from([("PSModulePath".to_string(), modules.to_string())]);
+//!
+//! // The nearest shell ancestor wins, e.g. `CMD` started from `PowerShell 7`, whose module path it inherits..
+//! let ancestry = [process("cargo.exe"), process("cmd.exe"), process("pwsh.exe"), process("WindowsTerminal.exe")];
+//! assert_eq!(classify_host_shell_in(&env(&pwsh_modules), &ancestry), Some(Shell::Cmd));
+//! assert_eq!(classify_host_shell_in(&env(&pwsh_modules), &ancestry[2..]), Some(Shell::PowerShell));
+//! let ancestry = [process("POWERSHELL.EXE"), process("explorer.exe")];
+//! assert_eq!(classify_host_shell_in(&env(system_modules), &ancestry), Some(Shell::WindowsPowerShell));
+//!
+//! // Without a shell ancestor, only `PowerShell 7` can be told apart by its module path..
+//! let ancestry = [process("explorer.exe")];
+//! assert_eq!(classify_host_shell_in(&env(&pwsh_modules), &ancestry), Some(Shell::PowerShell));
+//! assert_eq!(classify_host_shell_in(&env(system_modules), &ancestry), None);
+//! assert_eq!(classify_host_shell_in(&HashMap::<String, String>::new(), &[]), None);
+//!
+//! assert_eq!(Shell::PowerShell.to_string(), "PowerShell 7");
+//! assert_eq!(Shell::Cmd.exec_name(), "cmd.exe");
+//! ```
+
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::environment::Environment;
+use crate::remote::ProcessInfo;
+
+/// Environment variable listing the `PowerShell` module directories, extended by each `PowerShell` for its
+/// children.
+pub const PS_MODULE_PATH_VAR: &str = "PSModulePath";
+
+/// Represents a shell hosting the program on `Windows`, see `host_shell()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shell {
+    /// The legacy command prompt, `cmd.exe`.
+    Cmd,
+    /// `Windows PowerShell` (5.1 and earlier), bundled with `Windows`.
+    WindowsPowerShell,
+    /// `PowerShell` 6 and later, installed separately as `pwsh.exe`.
+    PowerShell,
+}
+
+impl Shell {
+    /// Returns the name of the shell.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cmd => "CMD",
+            Self::WindowsPowerShell => "Windows PowerShell",
+            Self::PowerShell => "PowerShell 7",
+        }
+    }
+
+    /// Returns the executable name of the shell.
+    #[inline]
+    #[must_use]
+    pub fn exec_name(&self) -> &'static str {
+        match self {
+            Self::Cmd => "cmd.exe",
+            Self::WindowsPowerShell => "powershell.exe",
+            Self::PowerShell => "pwsh.exe",
+        }
+    }
+
+    /// Returns `true` if the shell leaves VT processing enabled in the console for the programs it runs.
+    #[inline]
+    #[must_use]
+    pub fn enables_virtual_terminal(&self) -> bool {
+        matches!(self, Self::PowerShell)
+    }
+}
+
+impl Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The shell hosting the program, detected once per process.
+static HOST_SHELL: LazyLock<Option<Shell>> = LazyLock::new(detect_host_shell);
+
+/// Returns the shell hosting the program, if known.
+///
+/// # Notes
+/// Always `None` on platforms other than `Windows`.
+#[inline]
+#[must_use]
+pub fn host_shell() -> Option<Shell> {
+    *HOST_SHELL
+}
+
+/// Classifies the shell hosting the program from the given environment and process ancestry (nearest ancestor
+/// first).
+///
+/// # Returns
+/// *   The nearest ancestor that is a shell, see `Shell::exec_name()`.
+/// *   `Shell::PowerShell` if there is none, but `PS_MODULE_PATH_VAR` lists a `PowerShell` directory (e.g.
+///     `C:\Program Files\PowerShell\7\Modules`), which only `PowerShell 7` adds.
+/// *   `None` otherwise, as `CMD` and `Windows PowerShell` can't be told apart by their module path, which lists
+///     the `WindowsPowerShell` directories in both.
+#[must_use]
+pub fn classify_host_shell_in(
+    env: &(impl Environment + ?Sized),
+    ancestry: &[ProcessInfo],
+) -> Option<Shell> {
+    let shell = ancestry.iter().find_map(|process| {
+        Shell::iter().find(|shell| process.name.eq_ignore_ascii_case(shell.exec_name()))
+    });
+
+    shell.or_else(|| {
+        env.var(PS_MODULE_PATH_VAR)
+            .is_some_and(|modules| {
+                modules.split(';').any(|path| {
+                    path.split(['\\', '/'])
+                        .any(|component| component.eq_ignore_ascii_case("PowerShell"))
+                })
+            })
+            .then_some(Shell::PowerShell)
+    })
+}
+
+/// Detects the shell hosting the program, see `host_shell()`.
+fn detect_host_shell() -> Option<Shell> {
+    #[cfg(windows)]
+    {
+        let shell = classify_host_shell_in(
+            &crate::environment::SystemEnvironment,
+            crate::remote::process_ancestry(),
+        );
+        if let Some(shell) = shell {
+            crate::logging::info!("Hosted by {shell}.");
+        }
+        shell
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
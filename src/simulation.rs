@@ -0,0 +1,435 @@
+//! Simulation of what the library would decide on another machine, e.g. for documenting an application's relaunch
+//! behaviour without access to every platform.
+//!
+//! Simulations never read the real environment, registry or filesystem, the terminal is detected from the scenario's
+//! environment and the relaunch decision is made by the same code as `decision::relaunch_decision()`.
+//!
+//! # Notes
+//! Capability overrides and rules registered in the current process (see `capabilities`) still apply, as they are
+//! part of the application's configuration.
+//!
+//! # Example
+//! ```
+//! use terminal_relaunch::TerminalType;
+//! use terminal_relaunch::decision::RelaunchDecision;
+//! use terminal_relaunch::simulation::{Scenario, simulate};
+//!
+//! let outcome = simulate(&Scenario::windows_cmd_with_wt_installed());
+//! assert_eq!(outcome.terminal, TerminalType::WindowsCMD);
+//! assert_eq!(outcome.decision, RelaunchDecision::Attempt);
+//! assert_eq!(outcome.provider, Some(TerminalType::WindowsTerminal));
+//!
+//! let command = outcome.command.unwrap();
+//! assert_eq!((command.program.to_str(), command.args), (Some("app"), vec!["--relaunched-term".to_string()]));
+//! ```
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::config::RelaunchConfig;
+use crate::decision::{DecisionInputs, FeatureRequirements, RelaunchDecision, SystemFacts, decide};
+use crate::errors::{RelaunchError, TermResult};
+use crate::pipeline::{ProbeOutcome, UnfitReason, probe_terminal};
+use crate::remote::ProcessInfo;
+use crate::terminal_providers::relaunch_args;
+use crate::tmux::TmuxFeatures;
+use crate::version::TerminalVersion;
+use crate::{OperatingSystem, TerminalProvider, TerminalType};
+
+/// A machine to simulate, see `simulate()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    /// The environment variables of the simulated process.
+    pub env: HashMap<String, String>,
+    /// The operating system of the simulated machine.
+    pub os: OperatingSystem,
+    /// The terminals installed on the simulated machine.
+    pub installed: Vec<TerminalType>,
+    /// The relaunch configuration of the simulated application.
+    pub config: RelaunchConfig,
+    /// The path of the simulated application, `app` by default.
+    pub program: PathBuf,
+    /// The arguments the simulated application was started with.
+    pub args: Vec<String>,
+    /// The ancestors of the simulated process, nearest first, e.g. a `mosh-server`.
+    pub ancestry: Vec<ProcessInfo>,
+    /// The features `tmux` passes through, if the simulated process runs inside `tmux`, `None` if unknown, in
+    /// which case RGB colours aren't limited by `tmux`.
+    pub tmux_features: Option<TmuxFeatures>,
+}
+
+impl Scenario {
+    /// Creates a new scenario for the given operating system, with an empty environment and no installed terminals.
+    #[inline]
+    #[must_use]
+    pub fn new(os: OperatingSystem) -> Self {
+        Self {
+            env: HashMap::new(),
+            os,
+            installed: Vec::new(),
+            config: RelaunchConfig::new(),
+            program: PathBuf::from("app"),
+            args: Vec::new(),
+            ancestry: Vec::new(),
+            tmux_features: None,
+        }
+    }
+
+    /// Sets the path of the simulated application, and the arguments it was started with.
+    #[inline]
+    #[must_use]
+    pub fn program(mut self, program: impl Into<PathBuf>, args: &[&str]) -> Self {
+        self.program = program.into();
+        self.args = args.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Sets an environment variable of the simulated process.
+    #[inline]
+    #[must_use]
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.env.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds an ancestor of the simulated process, after those added before, i.e. further from the process.
+    #[inline]
+    #[must_use]
+    pub fn ancestor(mut self, name: &str) -> Self {
+        self.ancestry.push(ProcessInfo {
+            pid: 0,
+            name: name.to_string(),
+            path: None,
+        });
+        self
+    }
+
+    /// Sets the features `tmux` passes through in the simulated process, if it runs inside `tmux`.
+    #[inline]
+    #[must_use]
+    pub fn tmux_features(mut self, features: TmuxFeatures) -> Self {
+        self.tmux_features = Some(features);
+        self
+    }
+
+    /// Marks the given terminal as installed on the simulated machine.
+    #[inline]
+    #[must_use]
+    pub fn installed(mut self, terminal: TerminalType) -> Self {
+        self.installed.push(terminal);
+        self
+    }
+
+    /// Sets the relaunch configuration of the simulated application.
+    #[inline]
+    #[must_use]
+    pub fn config(mut self, config: RelaunchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// A program started from `cmd.exe` on `Windows`, with `Windows Terminal` installed.
+    #[must_use]
+    pub fn windows_cmd_with_wt_installed() -> Self {
+        Self::new(OperatingSystem::Windows)
+            .var("COMSPEC", r"C:\Windows\system32\cmd.exe")
+            .installed(TerminalType::WindowsTerminal)
+    }
+
+    /// A program started from `Terminal.app` on `MacOS`, with no third party terminals installed.
+    #[must_use]
+    pub fn macos_apple_terminal_no_thirdparty() -> Self {
+        Self::new(OperatingSystem::MacOS)
+            .var("TERM_PROGRAM", "Apple_Terminal")
+            .var("TERM", "xterm-256color")
+    }
+
+    /// A program started over `SSH` on `Linux`, with `Kitty` installed on the remote machine.
+    #[must_use]
+    pub fn ssh_linux() -> Self {
+        Self::new(OperatingSystem::Linux)
+            .var("SSH_CONNECTION", "192.0.2.10 52022 192.0.2.20 22")
+            .var("TERM", "xterm-256color")
+            .installed(TerminalType::Kitty)
+    }
+}
+
+/// The command a simulated relaunch would run in the selected terminal, see `SimulationOutcome::command`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlannedCommand {
+    /// The terminal the program would be relaunched in.
+    pub terminal: TerminalType,
+    /// The program relaunched, i.e. `Scenario::program`.
+    pub program: PathBuf,
+    /// The arguments the program would be relaunched with, the relaunch marker (see
+    /// `RelaunchConfig::relaunched_argument()`) followed by `Scenario::args`.
+    pub args: Vec<String>,
+    /// The working directory the program would be relaunched in, `None` for the directory it was started in, see
+    /// `RelaunchConfig::working_directory`.
+    pub working_directory: Option<PathBuf>,
+}
+
+/// The result of a simulation, see `simulate()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationOutcome {
+    /// The detected terminal.
+    pub terminal: TerminalType,
+    /// If the detected terminal supports full unicode rendering.
+    pub full_unicode: bool,
+    /// If the detected terminal supports RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+    /// Whether a relaunch would be attempted, and if not, why.
+    pub decision: RelaunchDecision,
+    /// The terminal that would be relaunched in, if a relaunch would be attempted and one is installed.
+    pub provider: Option<TerminalType>,
+    /// The command that would be run in the terminal relaunched in, if any.
+    pub command: Option<PlannedCommand>,
+}
+
+/// A provider for a terminal of a simulated machine, which is installed if the scenario says so, and never reads
+/// from the system.
+struct SimulatedProvider {
+    /// The provider of the real machine, consulted for what doesn't depend on the system.
+    provider: Box<dyn TerminalProvider>,
+    /// If the terminal is installed on the simulated machine.
+    installed: bool,
+}
+
+impl TerminalProvider for SimulatedProvider {
+    fn terminal_type(&self) -> TerminalType {
+        self.provider.terminal_type()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.installed
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        None
+    }
+
+    fn unfit_reason(&self) -> Option<UnfitReason> {
+        self.provider.unfit_reason()
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        Err(RelaunchError::UnsupportedTerminalProvider(
+            self.terminal_type(),
+        ))
+    }
+}
+
+/// Simulates detection and the relaunch decision for the given scenario.
+///
+/// # Notes
+/// *   The inputs are gathered by `decision::DecisionInputs::gather_in()`, as for the real machine, from the
+///     scenario's environment, ancestry and `tmux` features. Other state only available on the real machine is
+///     assumed absent (see `decision::SystemFacts::new()`), i.e. the program hasn't been relaunched, isn't running
+///     as a service, is interactive, the user hasn't declined a relaunch, no cooldown is active, and the `Windows`
+///     default terminal is `cmd.exe`.
+/// *   Terminals are probed as by `pipeline::RelaunchPipeline`, but dependencies are assumed present and the
+///     installed version is unknown, so a terminal of the same type as the detected terminal is never selected, as
+///     in `improves_on_current_terminal()`, and is decided as `SkipReason::NoImprovementPossible` if it's the only
+///     installed candidate.
+/// *   Signatures reading from the system are unmet, but identifiers can still match through the environment, see
+///     `TerminalSignature::check_env_only_in()`.
+/// *   The version of the detected terminal is only read for `Terminal.app`, whose full unicode support depends on
+///     it, see `version::apple_terminal_version_in()`.
+#[must_use]
+pub fn simulate(scenario: &Scenario) -> SimulationOutcome {
+    let env = &scenario.env;
+    let terminal = crate::detect_terminal(env, scenario.os, false);
+    let version =
+        crate::version::apple_terminal_version_in(env).filter(|_| terminal == TerminalType::MacOS);
+    let facts = SystemFacts {
+        terminal_version: version,
+        ancestry: scenario.ancestry.clone(),
+        tmux_features: scenario.tmux_features,
+        ..SystemFacts::new(terminal)
+    };
+
+    let candidates = crate::get_preferred_terminals_in(env, scenario.os);
+    let inputs = DecisionInputs {
+        installed_candidates: Some(
+            candidates
+                .iter()
+                .copied()
+                .filter(|candidate| {
+                    scenario.installed.contains(candidate)
+                        && crate::get_provider_for_terminal(*candidate).is_some()
+                })
+                .collect(),
+        ),
+        ..DecisionInputs::gather_in(env, &facts, || false)
+    };
+    let decision = decide(&inputs, &scenario.config, &FeatureRequirements::PREFERRED);
+
+    let providers = |candidate: TerminalType| {
+        crate::get_provider_for_terminal(candidate).map(|provider| {
+            Box::new(SimulatedProvider {
+                provider,
+                installed: scenario.installed.contains(&candidate),
+            }) as Box<dyn TerminalProvider>
+        })
+    };
+    let provider = decision
+        .should_attempt()
+        .then(|| {
+            candidates.into_iter().find(|candidate| {
+                probe_terminal(*candidate, terminal, &providers).0 == ProbeOutcome::Selected
+            })
+        })
+        .flatten();
+
+    let command = provider.map(|provider| PlannedCommand {
+        terminal: provider,
+        program: scenario.program.clone(),
+        args: relaunch_args(
+            scenario.config.relaunched_argument(),
+            None,
+            scenario.args.iter().cloned(),
+        ),
+        working_directory: scenario.config.working_directory.clone(),
+    });
+
+    SimulationOutcome {
+        terminal,
+        full_unicode: inputs.full_unicode,
+        rgb_ansi_colours: inputs.rgb_ansi_colours,
+        decision,
+        provider,
+        command,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{PlannedCommand, Scenario, SimulationOutcome, simulate};
+    use crate::capabilities::{
+        CapabilityRule, PartialCapabilities, clear_capability_overrides,
+        register_capability_override,
+    };
+    use crate::config::RelaunchConfig;
+    use crate::decision::{RelaunchDecision, SkipReason};
+    use crate::test_support::{reading_globals, writing_globals};
+    use crate::tmux::TmuxFeatures;
+    use crate::{OperatingSystem, TerminalType};
+
+    /// Simulates the scenario, holding the global state steady for the capabilities.
+    fn outcome(scenario: &Scenario) -> SimulationOutcome {
+        let _globals = reading_globals();
+        simulate(scenario)
+    }
+
+    #[test]
+    fn windows_terminal_is_detected_from_its_environment_only() {
+        let wt = outcome(
+            &Scenario::new(OperatingSystem::Windows).var("WT_SESSION", "5a9e8d8c-7c02-4d39"),
+        );
+        assert_eq!(wt.terminal, TerminalType::WindowsTerminal);
+        assert_eq!(
+            wt.decision,
+            RelaunchDecision::Skip(SkipReason::AlreadyPreferred)
+        );
+        assert_eq!(wt.command, None);
+
+        // The registry delegation isn't read, so `cmd.exe` is assumed without the variable..
+        let cmd = outcome(&Scenario::windows_cmd_with_wt_installed());
+        assert_eq!(cmd.terminal, TerminalType::WindowsCMD);
+    }
+
+    #[test]
+    fn planned_command_has_the_marker_arguments_and_working_directory() {
+        let config = RelaunchConfig {
+            marker_namespace: Some("myapp".to_string()),
+            working_directory: Some(PathBuf::from("/home/me/project")),
+            ..RelaunchConfig::new()
+        };
+        let scenario = Scenario::windows_cmd_with_wt_installed()
+            .program(r"C:\Tools\myapp.exe", &["serve", "--port=8080"])
+            .config(config);
+
+        assert_eq!(
+            outcome(&scenario).command,
+            Some(PlannedCommand {
+                terminal: TerminalType::WindowsTerminal,
+                program: PathBuf::from(r"C:\Tools\myapp.exe"),
+                args: vec![
+                    "--myapp-relaunched-term".to_string(),
+                    "serve".to_string(),
+                    "--port=8080".to_string(),
+                ],
+                working_directory: Some(PathBuf::from("/home/me/project")),
+            })
+        );
+    }
+
+    #[test]
+    fn terminals_are_probed_as_by_the_pipeline() {
+        // `Hyper` can't run the relaunched program, so is passed over for the next installed terminal..
+        let apple = Scenario::macos_apple_terminal_no_thirdparty().installed(TerminalType::Hyper);
+        let hyper_only = outcome(&apple);
+        assert_eq!(hyper_only.decision, RelaunchDecision::Attempt);
+        assert_eq!((hyper_only.provider, hyper_only.command), (None, None));
+
+        let with_kitty = outcome(&apple.installed(TerminalType::Kitty));
+        assert_eq!(with_kitty.provider, Some(TerminalType::Kitty));
+        assert_eq!(
+            with_kitty.command.map(|command| command.terminal),
+            Some(TerminalType::Kitty)
+        );
+
+        // Terminals without a provider are never selected, even if installed..
+        let konsole = outcome(
+            &Scenario::new(OperatingSystem::Linux)
+                .var("TERM", "xterm-256color")
+                .installed(TerminalType::Konsole),
+        );
+        assert_eq!(konsole.provider, None);
+    }
+
+    #[test]
+    fn rules_apply_over_the_simulated_environment() {
+        let _globals = writing_globals();
+        clear_capability_overrides();
+        let scenario = Scenario::new(OperatingSystem::Linux)
+            .var("XTERM_VERSION", "XTerm(390)")
+            .var("COLORTERM", "truecolor");
+        let confirmed = simulate(&scenario);
+
+        register_capability_override(CapabilityRule {
+            terminal: TerminalType::Xterm,
+            version: None,
+            set: PartialCapabilities {
+                rgb_ansi_colours: Some(false),
+                ..PartialCapabilities::NONE
+            },
+        });
+        let disabled = simulate(&scenario);
+        clear_capability_overrides();
+
+        assert_eq!(confirmed.terminal, TerminalType::Xterm);
+        assert!(confirmed.rgb_ansi_colours);
+        assert!(!disabled.rgb_ansi_colours);
+    }
+
+    #[test]
+    fn ancestry_and_tmux_features_are_simulated() {
+        let mosh = Scenario::ssh_linux().ancestor("mosh-server");
+        assert_eq!(
+            outcome(&mosh).decision,
+            RelaunchDecision::Skip(SkipReason::RemoteSession)
+        );
+
+        // `tmux` not passing RGB colours through has the last word over `COLORTERM`..
+        let tmux = Scenario::new(OperatingSystem::Linux)
+            .var("TMUX", "/tmp/tmux-1000/default,1,0")
+            .var("TERM_PROGRAM", "WezTerm")
+            .var("COLORTERM", "truecolor");
+        assert!(outcome(&tmux).rgb_ansi_colours);
+        let no_rgb = tmux.tmux_features(TmuxFeatures::default());
+        assert!(!outcome(&no_rgb).rgb_ansi_colours);
+    }
+}
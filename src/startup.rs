@@ -0,0 +1,134 @@
+//! A snapshot of the terminal state at process start, for programs that make the relaunch decision late.
+//!
+//! A program that re-execs itself (e.g. after an update check) may have consumed or redirected its standard
+//! streams by the time it decides whether to relaunch. Capturing the state at the top of `main` lets later
+//! decisions use what the user saw at launch, see `capture_startup_state()`.
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::startup::{capture_startup_state, startup_state};
+//!
+//! fn main() {
+//!     let state = capture_startup_state();
+//!     assert_eq!(startup_state(), Some(state));
+//!
+//!     // Continue with your application..
+//! }
+//! ```
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use crate::TerminalType;
+
+/// The terminal state captured by `capture_startup_state()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StartupState {
+    /// If stdin was a terminal.
+    pub stdin_terminal: bool,
+    /// If stdout was a terminal.
+    pub stdout_terminal: bool,
+    /// If stderr was a terminal.
+    pub stderr_terminal: bool,
+    /// The detected terminal.
+    pub terminal: TerminalType,
+    /// If the detected terminal supported full unicode rendering.
+    pub full_unicode: bool,
+    /// If the detected terminal supported RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+    /// The working directory, if it could be read, which relaunched programs start in by default, see
+    /// `terminal_providers::launch_working_directory()`.
+    pub working_directory: Option<PathBuf>,
+}
+
+impl StartupState {
+    /// Captures the current terminal state.
+    ///
+    /// # Notes
+    /// The capabilities are resolved with the overrides and capability rules set at the time of the call.
+    #[must_use]
+    pub fn capture() -> Self {
+        Self {
+            stdin_terminal: std::io::stdin().is_terminal(),
+            stdout_terminal: std::io::stdout().is_terminal(),
+            stderr_terminal: std::io::stderr().is_terminal(),
+            terminal: crate::current_terminal(),
+            full_unicode: crate::supports_full_unicode(),
+            rgb_ansi_colours: crate::supports_rgb_ansi_colours(),
+            working_directory: std::env::current_dir().ok(),
+        }
+    }
+
+    /// Returns `true` if every standard stream was a terminal, i.e. the program was started interactively.
+    #[inline]
+    #[must_use]
+    pub fn is_interactive(&self) -> bool {
+        self.stdin_terminal && self.stdout_terminal && self.stderr_terminal
+    }
+}
+
+/// Captures the terminal state, if it hasn't been captured yet, and returns the snapshot.
+///
+/// Call this at the top of `main`, before any input is read, the standard streams are redirected, or any thread
+/// changes the working directory. Once captured, relaunch decisions use the snapshot's terminal and capabilities
+/// (see `decision::DecisionInputs::current()`), and relaunched programs start in its working directory.
+///
+/// # Notes
+/// With the `macros` feature enabled, `terminal_relaunch::main!` calls this before the body of `main`.
+pub fn capture_startup_state() -> &'static StartupState {
+    if let Some(state) = startup_state() {
+        return state;
+    }
+
+    let mut slot = crate::runtime::state()
+        .startup
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    // Another thread may have captured the state while waiting for the lock..
+    slot.get_or_insert_with(|| Box::leak(Box::new(StartupState::capture())))
+}
+
+/// Captures the terminal state again, replacing any earlier snapshot, and returns the new snapshot.
+pub fn refresh_startup_state() -> &'static StartupState {
+    let state = Box::leak(Box::new(StartupState::capture()));
+    *crate::runtime::state()
+        .startup
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(state);
+    state
+}
+
+/// Returns the captured startup state, or `None` if `capture_startup_state()` hasn't been called.
+#[inline]
+#[must_use]
+pub fn startup_state() -> Option<&'static StartupState> {
+    *crate::runtime::state()
+        .startup
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Wraps `main`, capturing the startup state (see `startup::capture_startup_state()`) before its body runs.
+///
+/// # Example
+/// ```rust
+/// terminal_relaunch::main! {
+///     fn main() {
+///         // The snapshot is captured before any user code runs..
+///         assert!(terminal_relaunch::startup::startup_state().is_some());
+///     }
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[macro_export]
+macro_rules! main {
+    ($(#[$attr:meta])* $vis:vis fn main() $(-> $ret:ty)? $body:block) => {
+        $(#[$attr])*
+        $vis fn main() $(-> $ret)? {
+            $crate::startup::capture_startup_state();
+            $body
+        }
+    };
+}
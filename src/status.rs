@@ -0,0 +1,425 @@
+use crate::decision::SkipReason;
+use crate::errors::RelaunchError;
+use crate::{
+    MIN_WINDOWS_TERMINAL_BUILD, OperatingSystem, TerminalType, relaunch_if_available_or_continue,
+};
+
+/// A suggestion of a preferred terminal the user could install.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InstallSuggestion {
+    /// The suggested terminal.
+    pub terminal: TerminalType,
+    /// Where the suggested terminal can be installed from.
+    pub url: &'static str,
+}
+
+impl InstallSuggestion {
+    /// Opens the install page of the suggested terminal, e.g. when the user accepts an "open the install page" prompt.
+    ///
+    /// # Errors
+    /// See `output::open_url()`.
+    #[inline]
+    pub fn open(&self) -> crate::errors::TermResult<()> {
+        crate::output::open_url(self.url)
+    }
+
+    /// Returns a link to the install page of the suggested terminal, formatted for the current terminal.
+    #[inline]
+    #[must_use]
+    pub fn link(&self) -> String {
+        crate::output::format_link(self.url, self.url)
+    }
+}
+
+/// Returns the terminal we suggest installing for the given operating system, if any.
+///
+/// # Notes
+/// Nothing is suggested on `Windows` builds too old to install `Windows Terminal`.
+#[inline]
+#[must_use]
+pub fn install_suggestion_for(os: OperatingSystem) -> Option<InstallSuggestion> {
+    install_suggestion_for_build(os, crate::windows_build())
+}
+
+/// Returns the terminal we suggest installing for the given operating system, on the given `Windows` build.
+fn install_suggestion_for_build(
+    os: OperatingSystem,
+    build: Option<u32>,
+) -> Option<InstallSuggestion> {
+    match os {
+        OperatingSystem::Windows
+            if build.is_some_and(|build| build < MIN_WINDOWS_TERMINAL_BUILD) =>
+        {
+            None
+        }
+        OperatingSystem::Windows => Some(InstallSuggestion {
+            terminal: TerminalType::WindowsTerminal,
+            url: "https://aka.ms/terminal",
+        }),
+        OperatingSystem::MacOS => Some(InstallSuggestion {
+            terminal: TerminalType::ITerm2,
+            url: "https://iterm2.com",
+        }),
+        OperatingSystem::Linux => Some(InstallSuggestion {
+            terminal: TerminalType::WezTerm,
+            url: "https://wezterm.org",
+        }),
+        OperatingSystem::Unknown => None,
+    }
+}
+
+/// The message templates used to explain the terminal status to the user.
+///
+/// Templates may contain the placeholders `{terminal}`, `{missing}`, `{suggestion}`, `{url}`, `{default}` and `{error}`,
+/// which are replaced when the message is built. All strings can be replaced, e.g. for localisation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageCatalog {
+    /// Used when the current terminal supports all preferred features.
+    pub supported: String,
+    /// Used when the current terminal is missing features, `{missing}` is one of the `missing_*` strings.
+    pub degraded: String,
+    /// Appended to `degraded` when there is a terminal we can suggest installing.
+    pub install_suggestion: String,
+    /// Appended to `degraded` when a relaunch was attempted, but failed.
+    pub relaunch_failed: String,
+    /// Appended to `degraded` when the platform's default terminal (`{default}`) is already a preferred terminal,
+    /// but was not used to start the program.
+    pub default_not_active: String,
+    /// Describes missing full unicode support.
+    pub missing_unicode: String,
+    /// Describes missing RGB (ANSI) colour support.
+    pub missing_colours: String,
+    /// Describes missing full unicode and RGB (ANSI) colour support.
+    pub missing_unicode_and_colours: String,
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self {
+            supported: "Running in {terminal}: all terminal features are supported.".to_string(),
+            degraded: "Running in {terminal}: {missing} will look degraded.".to_string(),
+            install_suggestion: " Install {suggestion} for the best experience: {url}".to_string(),
+            relaunch_failed: " Could not open a better terminal: {error}".to_string(),
+            default_not_active: " {default} is already your default terminal, reopen the program from it or check the shortcut used to start it.".to_string(),
+            missing_unicode: "emoji and symbols".to_string(),
+            missing_colours: "colors".to_string(),
+            missing_unicode_and_colours: "emoji and colors".to_string(),
+        }
+    }
+}
+
+/// The status of the current terminal, as returned by `ensure_good_terminal()`.
+#[derive(Debug)]
+pub struct TerminalStatus {
+    /// The current terminal.
+    pub terminal: TerminalType,
+    /// If the current terminal supports full unicode rendering.
+    pub full_unicode: bool,
+    /// If the current terminal supports RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+    /// Why a relaunch was not attempted, if it was skipped.
+    pub skip_reason: Option<SkipReason>,
+    /// The error that occurred if a relaunch was attempted and failed.
+    pub error: Option<RelaunchError>,
+    /// A terminal the user could install for a better experience, if the current terminal is missing features.
+    pub suggestion: Option<InstallSuggestion>,
+    /// A ready to print explanation of the status, plain ASCII with the default `MessageCatalog` or plain output
+    /// (see `output::plain_output()`).
+    pub message: String,
+}
+
+impl TerminalStatus {
+    /// Returns `true` if the current terminal supports all preferred features.
+    #[inline]
+    #[must_use]
+    pub fn is_supported(&self) -> bool {
+        self.full_unicode && self.rgb_ansi_colours
+    }
+}
+
+/// Relaunches the current program in a preferred terminal and exits if possible, otherwise returns the
+/// status of the current terminal with a ready to print explanation, using the default `MessageCatalog`.
+///
+/// # Example
+/// ```rust,no_run
+/// let status = terminal_relaunch::status::ensure_good_terminal();
+/// if !status.is_supported() {
+///     eprintln!("{}", status.message);
+/// }
+/// ```
+#[inline]
+#[must_use]
+pub fn ensure_good_terminal() -> TerminalStatus {
+    ensure_good_terminal_with(&MessageCatalog::default())
+}
+
+/// Relaunches the current program in a preferred terminal and exits if possible, otherwise returns the
+/// status of the current terminal with a ready to print explanation, built from the given message catalog.
+#[must_use]
+pub fn ensure_good_terminal_with(catalog: &MessageCatalog) -> TerminalStatus {
+    let summary = relaunch_if_available_or_continue();
+
+    let mut status = TerminalStatus {
+        terminal: summary.terminal,
+        full_unicode: summary.full_unicode,
+        rgb_ansi_colours: summary.rgb_ansi_colours,
+        skip_reason: summary.skip_reason,
+        error: summary.error,
+        suggestion: None,
+        message: String::new(),
+    };
+    if !status.is_supported()
+        && status.skip_reason != Some(SkipReason::DefaultAlreadyPreferredButNotActive)
+    {
+        status.suggestion = install_suggestion_for(OperatingSystem::current())
+            .filter(|suggestion| suggestion.terminal != status.terminal);
+    }
+    status.message = build_message(&status, catalog);
+    if crate::output::plain_output() {
+        status.message = crate::output::to_plain_ascii(&status.message).into_owned();
+    }
+
+    status
+}
+
+/// Builds the explanation message for the given status from the message catalog.
+fn build_message(status: &TerminalStatus, catalog: &MessageCatalog) -> String {
+    let missing = match (status.full_unicode, status.rgb_ansi_colours) {
+        (true, true) => {
+            return catalog
+                .supported
+                .replace("{terminal}", status.terminal.name());
+        }
+        (false, true) => &catalog.missing_unicode,
+        (true, false) => &catalog.missing_colours,
+        (false, false) => &catalog.missing_unicode_and_colours,
+    };
+
+    let mut message = catalog.degraded.replace("{missing}", missing);
+    if let Some(error) = &status.error {
+        message.push_str(&catalog.relaunch_failed.replace(
+            "{error}",
+            &crate::logging::sanitize_for_log(&error.to_string()),
+        ));
+    }
+    if status.skip_reason == Some(SkipReason::DefaultAlreadyPreferredButNotActive)
+        && let Some(default) = crate::platform_default_terminal()
+    {
+        message.push_str(
+            &catalog
+                .default_not_active
+                .replace("{default}", default.name()),
+        );
+    }
+    if let Some(suggestion) = &status.suggestion {
+        message.push_str(
+            &catalog
+                .install_suggestion
+                .replace("{suggestion}", suggestion.terminal.name())
+                .replace("{url}", suggestion.url),
+        );
+    }
+
+    message.replace("{terminal}", status.terminal.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        InstallSuggestion, MessageCatalog, TerminalStatus, build_message, install_suggestion_for,
+        install_suggestion_for_build,
+    };
+    use crate::decision::SkipReason;
+    use crate::errors::RelaunchError;
+    use crate::{MIN_WINDOWS_TERMINAL_BUILD, MIN_WINDOWS_VT_BUILD};
+    use crate::{OperatingSystem, TerminalType};
+
+    /// The status of a terminal with the given support, with the suggestion for the given platform.
+    fn status(
+        terminal: TerminalType,
+        full_unicode: bool,
+        rgb_ansi_colours: bool,
+        os: OperatingSystem,
+    ) -> TerminalStatus {
+        TerminalStatus {
+            terminal,
+            full_unicode,
+            rgb_ansi_colours,
+            skip_reason: None,
+            error: None,
+            suggestion: install_suggestion_for(os),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn default_messages_for_the_main_scenarios() {
+        let catalog = MessageCatalog::default();
+        for (status, expected) in [
+            (
+                TerminalStatus {
+                    suggestion: None,
+                    ..status(TerminalType::Kitty, true, true, OperatingSystem::Linux)
+                },
+                "Running in Kitty: all terminal features are supported.",
+            ),
+            (
+                status(
+                    TerminalType::WindowsCMD,
+                    false,
+                    false,
+                    OperatingSystem::Windows,
+                ),
+                "Running in Windows CMD: emoji and colors will look degraded. Install Windows Terminal for the best experience: https://aka.ms/terminal",
+            ),
+            (
+                status(TerminalType::MacOS, false, true, OperatingSystem::MacOS),
+                "Running in MacOS Terminal: emoji and symbols will look degraded. Install iTerm2 for the best experience: https://iterm2.com",
+            ),
+            (
+                status(TerminalType::Xterm, true, false, OperatingSystem::Linux),
+                "Running in xterm: colors will look degraded. Install WezTerm for the best experience: https://wezterm.org",
+            ),
+            (
+                TerminalStatus {
+                    error: Some(RelaunchError::NoAlternativeTerminalFound),
+                    ..status(
+                        TerminalType::WindowsCMD,
+                        false,
+                        false,
+                        OperatingSystem::Windows,
+                    )
+                },
+                "Running in Windows CMD: emoji and colors will look degraded. Could not open a better terminal: No alternative terminal found to relaunch in. Install Windows Terminal for the best experience: https://aka.ms/terminal",
+            ),
+            (
+                TerminalStatus {
+                    skip_reason: Some(SkipReason::RemoteSession),
+                    suggestion: None,
+                    ..status(
+                        TerminalType::Unknown,
+                        false,
+                        false,
+                        OperatingSystem::Unknown,
+                    )
+                },
+                "Running in Unknown: emoji and colors will look degraded.",
+            ),
+        ] {
+            let message = build_message(&status, &catalog);
+            assert_eq!(message, expected);
+            assert!(message.is_ascii(), "{message}");
+        }
+    }
+
+    #[test]
+    fn every_string_comes_from_the_catalog() {
+        let catalog = MessageCatalog {
+            supported: "{terminal} ok".to_string(),
+            degraded: "{terminal} fehlt {missing}.".to_string(),
+            install_suggestion: " Installiere {suggestion}: {url}".to_string(),
+            relaunch_failed: " Fehler: {error}".to_string(),
+            default_not_active: " {default}".to_string(),
+            missing_unicode: "Unicode".to_string(),
+            missing_colours: "Farben".to_string(),
+            missing_unicode_and_colours: "Unicode und Farben".to_string(),
+        };
+        let linux = |full_unicode, rgb_ansi_colours| {
+            build_message(
+                &status(
+                    TerminalType::Xterm,
+                    full_unicode,
+                    rgb_ansi_colours,
+                    OperatingSystem::Linux,
+                ),
+                &catalog,
+            )
+        };
+
+        assert_eq!(linux(true, true), "xterm ok");
+        assert_eq!(
+            linux(false, true),
+            "xterm fehlt Unicode. Installiere WezTerm: https://wezterm.org"
+        );
+        assert_eq!(
+            linux(true, false),
+            "xterm fehlt Farben. Installiere WezTerm: https://wezterm.org"
+        );
+        let failed = TerminalStatus {
+            error: Some(RelaunchError::NoUrlOpener),
+            suggestion: None,
+            ..status(TerminalType::Xterm, false, false, OperatingSystem::Linux)
+        };
+        assert_eq!(
+            build_message(&failed, &catalog),
+            "xterm fehlt Unicode und Farben. Fehler: No program to open URLs with could be found."
+        );
+    }
+
+    #[test]
+    fn suggestions_per_platform() {
+        let suggested = |os| install_suggestion_for(os).map(|suggestion| suggestion.terminal);
+        assert_eq!(
+            suggested(OperatingSystem::MacOS),
+            Some(TerminalType::ITerm2)
+        );
+        assert_eq!(
+            suggested(OperatingSystem::Linux),
+            Some(TerminalType::WezTerm)
+        );
+        assert_eq!(suggested(OperatingSystem::Unknown), None);
+        // Not running on `Windows`, so its build is unknown and never too old..
+        assert_eq!(
+            install_suggestion_for(OperatingSystem::Windows),
+            Some(InstallSuggestion {
+                terminal: TerminalType::WindowsTerminal,
+                url: "https://aka.ms/terminal",
+            })
+        );
+    }
+
+    #[test]
+    fn windows_terminal_is_only_suggested_on_builds_that_can_install_it() {
+        let suggested = |build| {
+            install_suggestion_for_build(OperatingSystem::Windows, build)
+                .map(|suggestion| suggestion.terminal)
+        };
+        for (build, expected) in [
+            // `Windows 8.1`, and `Windows 10` before VT and `Windows Terminal`..
+            (Some(9600), None),
+            (Some(MIN_WINDOWS_VT_BUILD - 1), None),
+            (Some(MIN_WINDOWS_VT_BUILD), None),
+            (Some(MIN_WINDOWS_TERMINAL_BUILD - 1), None),
+            // ..and since, or when the build is unknown..
+            (
+                Some(MIN_WINDOWS_TERMINAL_BUILD),
+                Some(TerminalType::WindowsTerminal),
+            ),
+            (Some(22631), Some(TerminalType::WindowsTerminal)),
+            (None, Some(TerminalType::WindowsTerminal)),
+        ] {
+            assert_eq!(suggested(build), expected, "{build:?}");
+        }
+
+        // ..which only matters on `Windows`..
+        assert_eq!(
+            install_suggestion_for_build(OperatingSystem::Linux, Some(9600)),
+            install_suggestion_for(OperatingSystem::Linux)
+        );
+
+        // ..so legacy builds get the degraded message without a suggestion..
+        let legacy = TerminalStatus {
+            suggestion: install_suggestion_for_build(OperatingSystem::Windows, Some(9600)),
+            ..status(
+                TerminalType::WindowsCMD,
+                false,
+                false,
+                OperatingSystem::Windows,
+            )
+        };
+        assert_eq!(
+            build_message(&legacy, &MessageCatalog::default()),
+            "Running in Windows CMD: emoji and colors will look degraded."
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Awareness of running under `sudo`, where `HOME` may belong to root while the display session belongs to
+//! the invoking user.
+
+use std::path::PathBuf;
+
+use crate::environment::{Environment, SystemEnvironment};
+
+/// Environment variable containing the name of the user who invoked `sudo`.
+pub const SUDO_USER_VAR: &str = "SUDO_USER";
+/// Environment variable containing the user ID of the user who invoked `sudo`.
+pub const SUDO_UID_VAR: &str = "SUDO_UID";
+/// Environment variable containing the name of the effective user, e.g. `root` under `sudo`.
+pub const USER_VAR: &str = if cfg!(windows) { "USERNAME" } else { "USER" };
+
+/// The user who invoked `sudo`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SudoUser {
+    /// The user name.
+    pub name: String,
+    /// The user ID, if known.
+    pub uid: Option<u32>,
+    /// The user's home directory, if it could be found.
+    pub home: Option<PathBuf>,
+}
+
+/// Returns the user who invoked `sudo`, if running under `sudo`.
+#[inline]
+#[must_use]
+pub fn invoking_user() -> Option<SudoUser> {
+    invoking_user_in(&SystemEnvironment)
+}
+
+/// Returns the user who invoked `sudo` from the given environment, see `invoking_user()`.
+///
+/// # Notes
+/// `sudo` as root (`SUDO_USER=root`) is not treated as running under `sudo`, as nothing changes identity.
+#[must_use]
+pub fn invoking_user_in(env: &(impl Environment + ?Sized)) -> Option<SudoUser> {
+    let name = env.var(SUDO_USER_VAR).filter(|name| is_sudo_user(name))?;
+    let uid = env
+        .var(SUDO_UID_VAR)
+        .and_then(|uid| uid.trim().parse().ok());
+    let home = home_of(&name);
+
+    Some(SudoUser { name, uid, home })
+}
+
+/// Returns the name of the effective user from the given environment, e.g. `root` under `sudo`, if set.
+#[must_use]
+pub fn effective_user_in(env: &(impl Environment + ?Sized)) -> Option<String> {
+    env.var(USER_VAR).filter(|name| !name.is_empty())
+}
+
+/// Returns `true` if the program is running under `sudo`, see `invoking_user()`.
+#[inline]
+#[must_use]
+pub fn is_elevated() -> bool {
+    is_elevated_in(&SystemEnvironment)
+}
+
+/// Returns `true` if the given environment is running under `sudo`, without looking up the invoking user.
+#[inline]
+#[must_use]
+pub fn is_elevated_in(env: &(impl Environment + ?Sized)) -> bool {
+    env.var(SUDO_USER_VAR)
+        .is_some_and(|name| is_sudo_user(&name))
+}
+
+/// Returns `true` if the given `SUDO_USER` value is a user other than root.
+#[inline]
+fn is_sudo_user(name: &str) -> bool {
+    !name.is_empty() && name != "root"
+}
+
+/// Returns the home directory of the given user, if it can be found.
+fn home_of(name: &str) -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        return Some(PathBuf::from("/Users").join(name)).filter(|home| home.is_dir());
+    }
+
+    home_in_passwd(name, &std::fs::read_to_string("/etc/passwd").ok()?)
+}
+
+/// Returns the home directory of the given user from the contents of a `passwd` file.
+fn home_in_passwd(name: &str, passwd: &str) -> Option<PathBuf> {
+    // `name:password:uid:gid:gecos:home:shell`..
+    passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 6 && fields[0] == name)
+        .map(|fields| PathBuf::from(fields[5]))
+}
+
+/// Returns the home directory of the current user.
+///
+/// # Notes
+/// Under `sudo`, the invoking user's home is preferred unless disabled by
+/// `RelaunchConfig::prefer_invoking_user_home`, otherwise `HOME` (`USERPROFILE` on `Windows`) is used.
+#[must_use]
+pub fn user_home() -> Option<PathBuf> {
+    user_home_in(
+        &SystemEnvironment,
+        crate::config::relaunch_config().prefer_invoking_user_home,
+    )
+}
+
+/// Returns the home directory of the current user from the given environment, see `user_home()`.
+fn user_home_in(
+    env: &(impl Environment + ?Sized),
+    prefer_invoking_user_home: bool,
+) -> Option<PathBuf> {
+    if prefer_invoking_user_home
+        && let Some(home) = invoking_user_in(env).and_then(|user| user.home)
+    {
+        return Some(home);
+    }
+
+    let var = if cfg!(target_os = "windows") {
+        "USERPROFILE"
+    } else {
+        "HOME"
+    };
+    env.var_os(var)
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::{
+        SUDO_UID_VAR, SUDO_USER_VAR, home_in_passwd, home_of, invoking_user_in, is_elevated_in,
+        user_home_in,
+    };
+    use crate::config::RelaunchConfig;
+    use crate::decision::{RelaunchDecision, SkipReason};
+    use crate::simulation::{Scenario, simulate};
+    use crate::{OperatingSystem, TerminalType};
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn sudo_variables_identify_the_invoking_user() {
+        let user =
+            invoking_user_in(&env(&[(SUDO_USER_VAR, "alice"), (SUDO_UID_VAR, " 1000 ")])).unwrap();
+        assert_eq!(user.name, "alice");
+        assert_eq!(user.uid, Some(1000));
+
+        // A missing or malformed uid still identifies the user..
+        for uid in [None, Some(""), Some("-1"), Some("alice")] {
+            let mut vars = vec![(SUDO_USER_VAR, "alice")];
+            vars.extend(uid.map(|uid| (SUDO_UID_VAR, uid)));
+            let user = invoking_user_in(&env(&vars)).unwrap();
+            assert_eq!((user.name.as_str(), user.uid), ("alice", None), "{uid:?}");
+            assert!(is_elevated_in(&env(&vars)));
+        }
+
+        // ..but no, an empty or root `SUDO_USER` isn't running under `sudo`, whatever the uid..
+        for vars in [
+            env(&[]),
+            env(&[(SUDO_UID_VAR, "1000")]),
+            env(&[(SUDO_USER_VAR, ""), (SUDO_UID_VAR, "1000")]),
+            env(&[(SUDO_USER_VAR, "root"), (SUDO_UID_VAR, "0")]),
+        ] {
+            assert_eq!(invoking_user_in(&vars), None, "{vars:?}");
+            assert!(!is_elevated_in(&vars), "{vars:?}");
+        }
+    }
+
+    #[test]
+    fn passwd_entries_give_the_home_directory() {
+        let passwd = "\
+root:x:0:0:root:/root:/bin/bash
+alice:x:1000:1000:Alice,,,:/home/alice:/bin/zsh
+alicia:x:1001:1001::/srv/alicia:/bin/sh
+broken:x:1002
+";
+        assert_eq!(
+            home_in_passwd("alice", passwd),
+            Some(PathBuf::from("/home/alice"))
+        );
+        assert_eq!(
+            home_in_passwd("alicia", passwd),
+            Some(PathBuf::from("/srv/alicia"))
+        );
+        assert_eq!(home_in_passwd("ali", passwd), None);
+        assert_eq!(home_in_passwd("broken", passwd), None);
+        assert_eq!(home_in_passwd("bob", passwd), None);
+    }
+
+    #[test]
+    fn home_switches_to_the_invoking_user_only_when_preferred() {
+        let home_var = if cfg!(target_os = "windows") {
+            "USERPROFILE"
+        } else {
+            "HOME"
+        };
+
+        // Not under `sudo`, or the invoking user's home can't be found, `HOME` is used either way..
+        for vars in [
+            env(&[(home_var, "/root")]),
+            env(&[
+                (home_var, "/root"),
+                (SUDO_USER_VAR, "no-such-user-terminal-relaunch"),
+            ]),
+        ] {
+            for prefer in [false, true] {
+                assert_eq!(
+                    user_home_in(&vars, prefer),
+                    Some(PathBuf::from("/root")),
+                    "{vars:?}, {prefer}"
+                );
+            }
+        }
+        assert_eq!(user_home_in(&env(&[(home_var, "")]), true), None);
+        assert_eq!(user_home_in(&env(&[]), false), None);
+
+        // ..and a user of this machine whose home is found is preferred unless disabled..
+        let Some((name, home)) = std::fs::read_to_string("/etc/passwd")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split(':').next())
+            .filter(|name| !name.is_empty() && *name != "root")
+            .find_map(|name| Some((name.to_string(), home_of(name)?)))
+        else {
+            return;
+        };
+        let vars = env(&[(home_var, "/root"), (SUDO_USER_VAR, &name)]);
+        assert_eq!(invoking_user_in(&vars).unwrap().home, Some(home.clone()));
+        assert_eq!(user_home_in(&vars, true), Some(home));
+        assert_eq!(user_home_in(&vars, false), Some(PathBuf::from("/root")));
+    }
+
+    #[test]
+    fn relaunching_under_sudo_is_skipped_unless_allowed() {
+        let scenario = || {
+            Scenario::new(OperatingSystem::Linux)
+                .var("TERM", "xterm")
+                .var(SUDO_USER_VAR, "alice")
+                .var(SUDO_UID_VAR, "1000")
+                .installed(TerminalType::Kitty)
+        };
+
+        assert_eq!(
+            simulate(&scenario()).decision,
+            RelaunchDecision::Skip(SkipReason::ElevatedContext)
+        );
+        let allowed =
+            simulate(&scenario().config(RelaunchConfig::new().allow_elevated_relaunch(true)));
+        assert_ne!(
+            allowed.decision,
+            RelaunchDecision::Skip(SkipReason::ElevatedContext)
+        );
+
+        // `sudo` as root changes nothing..
+        let as_root = simulate(&scenario().var(SUDO_USER_VAR, "root"));
+        assert_eq!(as_root.decision, allowed.decision);
+    }
+}
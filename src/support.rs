@@ -0,0 +1,164 @@
+//! The support matrix of every terminal, rendered from the detection and capability tables, so it never drifts
+//! from what the library actually does.
+//!
+//! `SUPPORT_MATRIX.md` at the root of the repository is rendered by `render_support_matrix()`, and checked against
+//! it by `tests/support_matrix.rs`, so every table change is reflected there. After changing a table, regenerate it
+//! with `UPDATE_SUPPORT_MATRIX=1 cargo test --test support_matrix -- --ignored regenerate_matrix`.
+//!
+//! # Example
+//! ```rust
+//! use strum::IntoEnumIterator;
+//! use terminal_relaunch::TerminalType;
+//! use terminal_relaunch::support::{SupportEntry, render_support_matrix, support_matrix};
+//!
+//! let kitty = SupportEntry::new(TerminalType::Kitty);
+//! assert_eq!(kitty.detection, ["TERM=xterm-kitty"]);
+//! assert!(kitty.relaunch && kitty.rgb_ansi_colours && kitty.full_unicode && kitty.hyperlinks && kitty.images);
+//!
+//! // Terminals found without an identifier list how..
+//! let cmd = SupportEntry::new(TerminalType::WindowsCMD);
+//! assert_eq!(cmd.detection, ["default on Windows"]);
+//! assert!(!cmd.relaunch);
+//!
+//! // Every terminal but `Unknown` has a row..
+//! assert_eq!(support_matrix().len(), TerminalType::iter().count() - 1);
+//! let matrix = render_support_matrix();
+//! assert!(matrix.contains("| Kitty | `kitty` | MacOS | `TERM=xterm-kitty` | ✓ | ✓ | ✓ | ✓ | ✓ |"));
+//! assert!(matrix.contains("| Third Party MacOS Terminal | `macos-third-party` | MacOS | Not detected |"));
+//! ```
+
+use std::fmt::Write;
+
+use strum::IntoEnumIterator;
+
+use crate::{
+    OperatingSystem, TerminalType, get_built_in_provider_for_terminal, get_default_terminal_for_os,
+    identifiers_for,
+};
+
+/// How terminals without an identifier of their own are detected, as done by `detect_terminal()` and
+/// `find_current_terminal()`.
+pub const DETECTION_FALLBACKS: &[(TerminalType, &str)] = &[
+    (TerminalType::NonTerminalHost, "no console handles"),
+    (TerminalType::Mintty, "POSIX layer fallback"),
+    (TerminalType::Tmux, "inside tmux fallback"),
+];
+
+/// The support of a single terminal, a row of `support_matrix()`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SupportEntry {
+    /// The terminal type.
+    pub terminal: TerminalType,
+    /// How the terminal is detected, one description per identifier or fallback, in detection order.
+    pub detection: Vec<String>,
+    /// If the program can be relaunched in the terminal, i.e. it has a built-in terminal provider able to run the
+    /// relaunched program, see `TerminalProvider::unfit_reason()`.
+    pub relaunch: bool,
+    /// If the terminal supports RGB (ANSI) colours.
+    pub rgb_ansi_colours: bool,
+    /// If the terminal supports full unicode rendering.
+    pub full_unicode: bool,
+    /// If the terminal supports `OSC 8` hyperlinks.
+    pub hyperlinks: bool,
+    /// If the terminal displays inline images.
+    pub images: bool,
+}
+
+impl SupportEntry {
+    /// Creates the support entry of the given terminal from the live tables.
+    ///
+    /// # Notes
+    /// *   Each identifier is described by its signatures, joined with `+` as they must all match.
+    /// *   Fallbacks follow the identifiers, see `DETECTION_FALLBACKS` and `get_default_terminal_for_os()`.
+    #[must_use]
+    pub fn new(terminal: TerminalType) -> Self {
+        let identifiers = identifiers_for(terminal).map(|identifier| {
+            identifier
+                .signatures
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" + ")
+        });
+        let fallbacks = DETECTION_FALLBACKS
+            .iter()
+            .filter(|(kind, _)| *kind == terminal)
+            .map(|(_, fallback)| (*fallback).to_string());
+        let defaults = [
+            OperatingSystem::Windows,
+            OperatingSystem::MacOS,
+            OperatingSystem::Linux,
+        ]
+        .into_iter()
+        .filter(|os| get_default_terminal_for_os(*os) == terminal)
+        .map(|os| format!("default on {os}"));
+
+        Self {
+            terminal,
+            detection: identifiers.chain(fallbacks).chain(defaults).collect(),
+            relaunch: get_built_in_provider_for_terminal(terminal)
+                .is_some_and(|provider| provider.unfit_reason().is_none()),
+            rgb_ansi_colours: terminal.supports_rgb_ansi_colours(),
+            full_unicode: terminal.supports_full_unicode(),
+            hyperlinks: terminal.supports_hyperlinks(),
+            images: terminal.supports_images(),
+        }
+    }
+}
+
+/// Returns the support entries of every terminal but `TerminalType::Unknown`, in preference order.
+#[must_use]
+pub fn support_matrix() -> Vec<SupportEntry> {
+    TerminalType::iter()
+        .filter(|terminal| *terminal != TerminalType::Unknown)
+        .map(SupportEntry::new)
+        .collect()
+}
+
+/// Renders `support_matrix()` as a markdown document, the contents of `SUPPORT_MATRIX.md`.
+#[must_use]
+pub fn render_support_matrix() -> String {
+    let mark = |supported: bool| if supported { "✓" } else { "✗" };
+
+    let mut markdown = String::from(
+        "# Support Matrix\n\
+         \n\
+         <!-- Generated by `support::render_support_matrix()`, do not edit. Regenerate with\n\
+         `UPDATE_SUPPORT_MATRIX=1 cargo test --test support_matrix -- --ignored regenerate_matrix`. -->\n\
+         \n\
+         Capabilities are intrinsic to each terminal, before overrides, capability rules and the environment \
+         (e.g. `NO_COLOR`) are applied.\n\
+         \n\
+         | Terminal | Id | OS | Detection | Relaunch | Truecolor | Unicode | Hyperlinks | Images |\n\
+         |----------|----|----|-----------|----------|-----------|---------|------------|--------|\n",
+    );
+    for entry in support_matrix() {
+        let detection = if entry.detection.is_empty() {
+            "Not detected".to_string()
+        } else {
+            entry
+                .detection
+                .iter()
+                .map(|description| format!("`{description}`"))
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+        // Writing to a `String` never fails..
+        let _ = writeln!(
+            markdown,
+            "| {} | `{}` | {} | {} | {} | {} | {} | {} | {} |",
+            entry.terminal,
+            entry.terminal.id(),
+            entry.terminal.target_os(),
+            detection,
+            mark(entry.relaunch),
+            mark(entry.rgb_ansi_colours),
+            mark(entry.full_unicode),
+            mark(entry.hyperlinks),
+            mark(entry.images),
+        );
+    }
+
+    markdown
+}
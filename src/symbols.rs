@@ -0,0 +1,238 @@
+//! Symbols for progress and status output (check marks, spinners, box drawing, etc.), with ASCII fallbacks for
+//! terminals without full unicode support.
+//!
+//! The style is resolved from the effective unicode support, see `SymbolStyle::current()`, so it honours the
+//! overrides, capability rules and plain output. Resolve a `SymbolSet` once, rather than calling the functions
+//! for every symbol, when printing many.
+//!
+//! # Example
+//! ```rust
+//! use terminal_relaunch::output::{OutputStyle, set_output_style};
+//! use terminal_relaunch::set_unicode_support_override;
+//! use terminal_relaunch::symbols::{self, BoxChars, SymbolSet, SymbolStyle};
+//!
+//! assert_eq!(
+//!     SymbolSet::for_style(SymbolStyle::Unicode),
+//!     SymbolSet {
+//!         check: "✓",
+//!         cross: "✗",
+//!         bullet: "•",
+//!         ellipsis: "…",
+//!         spinner_frames: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+//!         box_drawing: BoxChars {
+//!             horizontal: "─",
+//!             vertical: "│",
+//!             top_left: "┌",
+//!             top_right: "┐",
+//!             bottom_left: "└",
+//!             bottom_right: "┘",
+//!         },
+//!     },
+//! );
+//! assert_eq!(
+//!     SymbolSet::for_style(SymbolStyle::Ascii),
+//!     SymbolSet {
+//!         check: "+",
+//!         cross: "x",
+//!         bullet: "*",
+//!         ellipsis: "...",
+//!         spinner_frames: &["|", "/", "-", "\\"],
+//!         box_drawing: BoxChars {
+//!             horizontal: "-",
+//!             vertical: "|",
+//!             top_left: "+",
+//!             top_right: "+",
+//!             bottom_left: "+",
+//!             bottom_right: "+",
+//!         },
+//!     },
+//! );
+//!
+//! set_unicode_support_override(Some(true));
+//! assert_eq!(symbols::check(), "✓");
+//! assert_eq!(SymbolSet::current(), SymbolSet::for_style(SymbolStyle::Unicode));
+//!
+//! // Plain output wins over unicode support..
+//! set_output_style(OutputStyle::PlainAscii);
+//! assert_eq!(symbols::check(), "+");
+//! assert_eq!(symbols::spinner_frames(), ["|", "/", "-", "\\"]);
+//!
+//! set_output_style(OutputStyle::Auto);
+//! set_unicode_support_override(Some(false));
+//! assert_eq!(symbols::box_drawing(), SymbolSet::for_style(SymbolStyle::Ascii).box_drawing);
+//! ```
+
+use std::fmt::Display;
+
+/// Represents which symbols to use, see `SymbolStyle::current()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SymbolStyle {
+    /// Unicode symbols, e.g. `✓`.
+    Unicode,
+    /// Plain ASCII symbols, e.g. `+`.
+    Ascii,
+}
+
+impl SymbolStyle {
+    /// Returns the style to use in the current terminal.
+    ///
+    /// # Returns
+    /// *   `SymbolStyle::Ascii` with plain output, see `output::plain_output()`.
+    /// *   `SymbolStyle::Unicode` if the current terminal supports full unicode, see `supports_full_unicode()`.
+    /// *   `SymbolStyle::Ascii` otherwise.
+    #[inline]
+    #[must_use]
+    pub fn current() -> Self {
+        if !crate::output::plain_output() && crate::supports_full_unicode() {
+            Self::Unicode
+        } else {
+            Self::Ascii
+        }
+    }
+
+    /// Returns the name of the style.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Unicode => "Unicode",
+            Self::Ascii => "ASCII",
+        }
+    }
+}
+
+impl Display for SymbolStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Characters for drawing boxes, see `box_drawing()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoxChars {
+    /// A horizontal line.
+    pub horizontal: &'static str,
+    /// A vertical line.
+    pub vertical: &'static str,
+    /// The top left corner.
+    pub top_left: &'static str,
+    /// The top right corner.
+    pub top_right: &'static str,
+    /// The bottom left corner.
+    pub bottom_left: &'static str,
+    /// The bottom right corner.
+    pub bottom_right: &'static str,
+}
+
+/// Every symbol of a style, see `SymbolSet::current()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolSet {
+    /// A check mark, for success.
+    pub check: &'static str,
+    /// A cross, for failure.
+    pub cross: &'static str,
+    /// A list bullet.
+    pub bullet: &'static str,
+    /// An ellipsis, for truncated or ongoing text.
+    pub ellipsis: &'static str,
+    /// The frames of a spinner, in order.
+    pub spinner_frames: &'static [&'static str],
+    /// Characters for drawing boxes.
+    pub box_drawing: BoxChars,
+}
+
+impl SymbolSet {
+    /// The unicode symbols.
+    const UNICODE: Self = Self {
+        check: "✓",
+        cross: "✗",
+        bullet: "•",
+        ellipsis: "…",
+        spinner_frames: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+        box_drawing: BoxChars {
+            horizontal: "─",
+            vertical: "│",
+            top_left: "┌",
+            top_right: "┐",
+            bottom_left: "└",
+            bottom_right: "┘",
+        },
+    };
+
+    /// The plain ASCII symbols.
+    const ASCII: Self = Self {
+        check: "+",
+        cross: "x",
+        bullet: "*",
+        ellipsis: "...",
+        spinner_frames: &["|", "/", "-", "\\"],
+        box_drawing: BoxChars {
+            horizontal: "-",
+            vertical: "|",
+            top_left: "+",
+            top_right: "+",
+            bottom_left: "+",
+            bottom_right: "+",
+        },
+    };
+
+    /// Returns the symbols of the given style, whatever the current terminal supports.
+    #[inline]
+    #[must_use]
+    pub const fn for_style(style: SymbolStyle) -> Self {
+        match style {
+            SymbolStyle::Unicode => Self::UNICODE,
+            SymbolStyle::Ascii => Self::ASCII,
+        }
+    }
+
+    /// Returns the symbols to use in the current terminal, see `SymbolStyle::current()`.
+    #[inline]
+    #[must_use]
+    pub fn current() -> Self {
+        Self::for_style(SymbolStyle::current())
+    }
+}
+
+/// Returns a check mark for the current terminal, see `SymbolSet::current()`.
+#[inline]
+#[must_use]
+pub fn check() -> &'static str {
+    SymbolSet::current().check
+}
+
+/// Returns a cross for the current terminal, see `SymbolSet::current()`.
+#[inline]
+#[must_use]
+pub fn cross() -> &'static str {
+    SymbolSet::current().cross
+}
+
+/// Returns a list bullet for the current terminal, see `SymbolSet::current()`.
+#[inline]
+#[must_use]
+pub fn bullet() -> &'static str {
+    SymbolSet::current().bullet
+}
+
+/// Returns an ellipsis for the current terminal, see `SymbolSet::current()`.
+#[inline]
+#[must_use]
+pub fn ellipsis() -> &'static str {
+    SymbolSet::current().ellipsis
+}
+
+/// Returns the spinner frames for the current terminal, see `SymbolSet::current()`.
+#[inline]
+#[must_use]
+pub fn spinner_frames() -> &'static [&'static str] {
+    SymbolSet::current().spinner_frames
+}
+
+/// Returns the box drawing characters for the current terminal, see `SymbolSet::current()`.
+#[inline]
+#[must_use]
+pub fn box_drawing() -> BoxChars {
+    SymbolSet::current().box_drawing
+}
@@ -1,14 +1,16 @@
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::{
     TargetOperatingSystem, TerminalIdentifier, TerminalProvider, TerminalSignature as TermSig,
-    TerminalType, errors::TermResult,
+    TerminalType, errors::TermResult, version::TerminalVersion,
 };
 
-use crate::RELAUNCHED_ARGUMENT;
-#[allow(unused_imports)]
+use crate::config::{WindowGeometry, WindowMode, WtWindowTarget, relaunch_config};
+use crate::environment::{Environment, SystemEnvironment};
 use crate::errors::RelaunchError;
+use crate::pipeline::UnfitReason;
 
 /// Common environment variable `TERM_PROGRAM` used in terminal identification.
 pub const TERM_PROGRAM_VAR: &str = "TERM_PROGRAM";
@@ -16,12 +18,66 @@ pub const TERM_PROGRAM_VAR: &str = "TERM_PROGRAM";
 pub const TERM_VAR: &str = "TERM";
 
 /// A list of known terminal identifiers with their associated signatures.
+///
+/// # Notes
+/// The identifier tables are plain data, checking their signatures has no side effects, and only reads
+/// from the system beyond the environment where `TerminalSignature::accesses_system()` says so.
+/// Identifiers may be added, reordered or refined between minor releases.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::simulation::{Scenario, simulate};
+/// use terminal_relaunch::{OperatingSystem, TerminalType};
+///
+/// // `WezTerm` is found from `TERM_PROGRAM`, or its own variables when `TERM_PROGRAM` was replaced..
+/// let term_program = Scenario::new(OperatingSystem::Linux).var("TERM_PROGRAM", "WezTerm");
+/// let pane_only = Scenario::new(OperatingSystem::Linux).var("WEZTERM_PANE", "0");
+/// assert_eq!(simulate(&term_program).terminal, TerminalType::WezTerm);
+/// assert_eq!(simulate(&pane_only).terminal, TerminalType::WezTerm);
+/// ```
 pub const TERMINAL_IDENTIFIERS: &[TerminalIdentifier] = &[
+    TerminalIdentifier {
+        kind: TerminalType::Emacs,
+        target_os: TargetOperatingSystem::Any,
+        // Checked first, as terminals inside `Emacs` inherit the variables of the terminal `Emacs` runs in, and
+        // set `TERM` to values (e.g. `xterm-256color` or `screen`) matched by other identifiers..
+        signatures: &[TermSig::EnvVarExists("INSIDE_EMACS")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Jupyter,
+        target_os: TargetOperatingSystem::Any,
+        // Checked early, as `Jupyter` inherits the variables of the terminal its server was started from..
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVarExists("JPY_PARENT_PID"),
+            TermSig::EnvVarExists("JUPYTER_SERVER_ROOT"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::GnuScreen,
+        target_os: TargetOperatingSystem::Any,
+        // Checked first, as `GNU Screen` degrades the terminal it runs in, whose variables are inherited..
+        signatures: &[
+            TermSig::EnvVarExists("STY"),
+            TermSig::TermVarPrefix("screen"),
+        ],
+    },
     TerminalIdentifier {
         kind: TerminalType::VSCode,
         target_os: TargetOperatingSystem::Any,
         signatures: &[TermSig::TermProgram("vscode")],
     },
+    TerminalIdentifier {
+        kind: TerminalType::JetBrains,
+        target_os: TargetOperatingSystem::Any,
+        // `TERM` is a plain `xterm-256color`, and `TERM_PROGRAM` may be leaked from the terminal the IDE was
+        // started from..
+        signatures: &[TermSig::EnvVar("TERMINAL_EMULATOR", "JetBrains-JediTerm")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Zed,
+        target_os: TargetOperatingSystem::Any,
+        signatures: &[TermSig::TermProgram("zed")],
+    },
     TerminalIdentifier {
         kind: TerminalType::Nvim,
         target_os: TargetOperatingSystem::Any,
@@ -30,7 +86,24 @@ pub const TERMINAL_IDENTIFIERS: &[TerminalIdentifier] = &[
     TerminalIdentifier {
         kind: TerminalType::ITerm2,
         target_os: TargetOperatingSystem::Any,
-        signatures: &[TermSig::EnvVarExists("ITERM_SESSION_ID")],
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVarExists("ITERM_SESSION_ID"),
+            TermSig::TermProgram("iTerm.app"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::ConEmu,
+        target_os: TargetOperatingSystem::Windows,
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVarExists("ConEmuANSI"),
+            TermSig::EnvVarExists("ConEmuPID"),
+            TermSig::EnvVarExists("ConEmuBuild"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Mintty,
+        target_os: TargetOperatingSystem::Windows,
+        signatures: &[TermSig::TermProgram("mintty")],
     },
     TerminalIdentifier {
         kind: TerminalType::Alacritty,
@@ -40,7 +113,41 @@ pub const TERMINAL_IDENTIFIERS: &[TerminalIdentifier] = &[
     TerminalIdentifier {
         kind: TerminalType::WezTerm,
         target_os: TargetOperatingSystem::Any,
-        signatures: &[TermSig::TermProgram("WezTerm")],
+        // `TERM_PROGRAM` is replaced by `tmux` and lost over some ssh setups, but these are kept..
+        signatures: &[TermSig::Any(&[
+            TermSig::TermProgram("WezTerm"),
+            TermSig::EnvVarExists("WEZTERM_PANE"),
+            TermSig::EnvVarExists("WEZTERM_UNIX_SOCKET"),
+            TermSig::EnvVarExists("WEZTERM_EXECUTABLE"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Hyper,
+        target_os: TargetOperatingSystem::Any,
+        // `HYPER_VERSION` is only set by recent releases..
+        signatures: &[TermSig::Any(&[
+            TermSig::TermProgram("Hyper"),
+            TermSig::EnvVarExists("HYPER_VERSION"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Tabby,
+        target_os: TargetOperatingSystem::Any,
+        signatures: &[TermSig::TermProgram("Tabby")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Tabby,
+        target_os: TargetOperatingSystem::Any,
+        signatures: &[TermSig::EnvVarExists("TABBY_CONFIG_DIRECTORY")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Contour,
+        target_os: TargetOperatingSystem::Any,
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVar("TERMINAL_NAME", "contour"),
+            TermSig::EnvVarExists("TERMINAL_VERSION_STRING"),
+            TermSig::TermVar("contour"),
+        ])],
     },
     TerminalIdentifier {
         kind: TerminalType::Kitty,
@@ -52,6 +159,90 @@ pub const TERMINAL_IDENTIFIERS: &[TerminalIdentifier] = &[
         target_os: TargetOperatingSystem::MacOS,
         signatures: &[TermSig::TermProgram("ghostty")],
     },
+    TerminalIdentifier {
+        kind: TerminalType::Ptyxis,
+        target_os: TargetOperatingSystem::Linux,
+        // Also `VTE` based, so it's checked before the other `VTE` terminals, whose variables may be inherited..
+        signatures: &[TermSig::EnvVarExists("PTYXIS_VERSION")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Konsole,
+        target_os: TargetOperatingSystem::Linux,
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVarExists("KONSOLE_VERSION"),
+            TermSig::EnvVarExists("KONSOLE_DBUS_SERVICE"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Tilix,
+        target_os: TargetOperatingSystem::Linux,
+        // `Tilix` is also `VTE` based, and may inherit the `GNOME Terminal` variables, so it's checked first..
+        signatures: &[TermSig::EnvVarExists("TILIX_ID")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Terminator,
+        target_os: TargetOperatingSystem::Linux,
+        // Also `VTE` based, `TERMINATOR_DBUS_NAME` is only set by newer versions..
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVarExists("TERMINATOR_UUID"),
+            TermSig::EnvVarExists("TERMINATOR_DBUS_NAME"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::GnomeTerminal,
+        target_os: TargetOperatingSystem::Linux,
+        // RGB colours are supported since 3.12, older releases are no longer shipped by any distribution..
+        signatures: &[TermSig::Any(&[
+            TermSig::EnvVarExists("GNOME_TERMINAL_SCREEN"),
+            TermSig::EnvVarExists("GNOME_TERMINAL_SERVICE"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Foot,
+        target_os: TargetOperatingSystem::Linux,
+        // Matched exactly or by `foot-`, so e.g. `footx` isn't mistaken for `foot`..
+        signatures: &[TermSig::Any(&[
+            TermSig::TermVar("foot"),
+            TermSig::TermVarPrefix("foot-"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::XfceTerminal,
+        target_os: TargetOperatingSystem::Linux,
+        signatures: &[TermSig::AncestorProcess("xfce4-terminal")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::XfceTerminal,
+        target_os: TargetOperatingSystem::Linux,
+        // `xfce4-terminal` sets no variables of its own, so without its process this relies on its defaults
+        // on an `XFCE` desktop, which other terminals override or are detected from before..
+        signatures: &[
+            TermSig::EnvVar("XDG_CURRENT_DESKTOP", "XFCE"),
+            TermSig::TermVar("xterm-256color"),
+            TermSig::EnvVar("COLORTERM", "truecolor"),
+        ],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Urxvt,
+        target_os: TargetOperatingSystem::Linux,
+        // Also matches `rxvt` itself, which has the same limitations..
+        signatures: &[TermSig::TermVarPrefix("rxvt")],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::St,
+        target_os: TargetOperatingSystem::Linux,
+        // Matched exactly or by `st-`, so e.g. `stterm-256color` isn't mistaken for `st`..
+        signatures: &[TermSig::Any(&[
+            TermSig::TermVar("st"),
+            TermSig::TermVarPrefix("st-"),
+        ])],
+    },
+    TerminalIdentifier {
+        kind: TerminalType::Xterm,
+        target_os: TargetOperatingSystem::Any,
+        // Inherited by terminals started from `xterm`, so it's checked after their own signatures..
+        signatures: &[TermSig::EnvVarExists("XTERM_VERSION")],
+    },
 ];
 
 /// A list of terminal identifiers to check last, typically for terminals that may be falsely detected when
@@ -65,6 +256,16 @@ pub const FINAL_TERMINAL_IDENTIFIERS: &[TerminalIdentifier] = &[
             TermSig::EnvVarExists("WT_SESSION"),
         ])],
     },
+    TerminalIdentifier {
+        kind: TerminalType::Mintty,
+        target_os: TargetOperatingSystem::Windows,
+        // `MSYSTEM` is also set by `MSYS` shells opened in other terminals, so it's only checked after them, with
+        // the `TERM` `mintty` sets by default..
+        signatures: &[
+            TermSig::EnvVarExists("MSYSTEM"),
+            TermSig::TermVarPrefix("xterm"),
+        ],
+    },
     TerminalIdentifier {
         kind: TerminalType::MacOS,
         target_os: TargetOperatingSystem::MacOS,
@@ -79,7 +280,7 @@ macro_rules! for_target {
 
         #[cfg(not(target_os = $target_os))]
         {
-            false
+            Default::default()
         }
     }};
     ($self:expr, $target_os: literal, $code:block) => {{
@@ -97,306 +298,4290 @@ macro_rules! for_target {
 
 // Providers..
 
+/// An installation of a terminal, looked up on first use and kept for the lifetime of its provider, so the
+/// provider launches the exact installation it was selected with rather than looking it up again.
+///
+/// # Notes
+/// A terminal removed or replaced since (e.g. by an update) is then noticed by the pipeline, which checks the
+/// kept path still exists before launching, see `pipeline::LaunchOutcome::Disappeared`.
+///
+/// # Example
+/// ```rust
+/// use std::cell::Cell;
+/// use std::path::PathBuf;
+///
+/// use terminal_relaunch::terminal_providers::Resolved;
+///
+/// // A lookup finding another path each time, e.g. while the terminal is being updated..
+/// let lookups = Cell::new(0);
+/// let lookup = || {
+///     lookups.set(lookups.get() + 1);
+///     Some(PathBuf::from(format!("/opt/kitty-{}", lookups.get())))
+/// };
+///
+/// let resolved = Resolved::default();
+/// assert_eq!(resolved.get_or_resolve(lookup), Some(&PathBuf::from("/opt/kitty-1")));
+/// assert_eq!(resolved.get_or_resolve(lookup), Some(&PathBuf::from("/opt/kitty-1")));
+/// assert_eq!(lookups.get(), 1);
+/// ```
+#[derive(Debug)]
+pub struct Resolved<T>(std::sync::OnceLock<Option<T>>);
+
+impl<T> Resolved<T> {
+    /// Returns the kept installation, looking it up with the given function on first use.
+    #[inline]
+    pub fn get_or_resolve(&self, lookup: impl FnOnce() -> Option<T>) -> Option<&T> {
+        self.0.get_or_init(lookup).as_ref()
+    }
+}
+
+impl<T> Default for Resolved<T> {
+    fn default() -> Self {
+        Self(std::sync::OnceLock::new())
+    }
+}
+
 /// Retrieves the current executable path, working directory, and command-line arguments.
 #[inline]
 #[must_use]
 fn get_relaunch_params() -> (PathBuf, PathBuf, Vec<String>) {
     let current_exe = std::env::current_exe().expect("Failed to get current executable path");
-    let current_wd = std::env::current_dir().expect("Failed to get current working directory");
-    let args: Vec<String> = [RELAUNCHED_ARGUMENT.to_string()]
-        .into_iter()
-        .chain(std::env::args().skip(1))
-        .collect();
+    let current_wd = launch_working_directory();
+    let marker = relaunch_config().relaunched_argument();
+    #[cfg(feature = "handoff")]
+    let handoff = crate::handoff::pending_argument();
+    #[cfg(not(feature = "handoff"))]
+    let handoff = None;
 
+    let args = relaunch_args(marker, handoff, std::env::args().skip(1));
     (current_exe, current_wd, args)
 }
 
-/// Terminal provider for `Windows Terminal`.
-pub struct WindowsTerminalProvider;
+/// Returns the arguments a program is relaunched with, the relaunch marker and any pending handoff followed by the
+/// program's own arguments.
+pub(crate) fn relaunch_args(
+    marker: String,
+    handoff: Option<String>,
+    args: impl IntoIterator<Item = String>,
+) -> Vec<String> {
+    [Some(marker), handoff]
+        .into_iter()
+        .flatten()
+        .chain(args)
+        .collect()
+}
 
-impl TerminalProvider for WindowsTerminalProvider {
-    fn terminal_type(&self) -> TerminalType {
-        TerminalType::WindowsTerminal
+/// A program to launch with its arguments, see `get_launch_params()`.
+pub(crate) type LaunchProgram = (PathBuf, Vec<String>);
+
+/// Retrieves the program to launch and the working directory to launch it in.
+///
+/// # Returns
+/// *   `(None, dir)` inside `with_shell_in()`, i.e. an interactive shell should be opened in `dir`.
+/// *   The current executable, with its arguments and the relaunch marker, see `get_relaunch_params()`.
+#[must_use]
+pub(crate) fn get_launch_params() -> (Option<LaunchProgram>, PathBuf) {
+    if let Some(dir) = SHELL_DIRECTORY.with(|cell| cell.borrow().clone()) {
+        return (None, dir);
     }
 
-    fn is_installed(&self) -> bool {
-        for_target!("windows", {
-            /// Path to Windows Terminal install in registry.
-            const WINDOWS_TERMINAL_INSTALL_PATH: &str =
-                r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\wt.exe";
+    let (curr_exe, curr_wd, args) = get_relaunch_params();
+    (Some((curr_exe, args)), curr_wd)
+}
 
-            use winreg::RegKey;
-            use winreg::enums::HKEY_CURRENT_USER;
+/// Returns the arguments running the given program, preceded by the given arguments (e.g. `-e`), or nothing
+/// for an interactive shell.
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn program_args(prefix: &[&str], program: Option<&LaunchProgram>) -> Vec<OsString> {
+    let Some((exe, args)) = program else {
+        return Vec::new();
+    };
 
-            let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-            hkcu.open_subkey(WINDOWS_TERMINAL_INSTALL_PATH).is_ok()
-        })
+    prefix
+        .iter()
+        .map(OsString::from)
+        .chain([exe.as_os_str().to_owned()])
+        .chain(args.iter().map(OsString::from))
+        .collect()
+}
+
+/// Returns the arguments running the given program as `program_args()`, through `env_wrapper_args()` so it keeps
+/// the given variables (see `environment::propagated_env()`), for launchers that don't pass ours on.
+#[cfg(any(target_os = "macos", test))]
+fn program_args_with_env(
+    prefix: &[&str],
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> Vec<OsString> {
+    let mut args = program_args(prefix, program);
+    if program.is_some() {
+        let wrapper = env_wrapper_args(vars);
+        args.splice(prefix.len()..prefix.len(), wrapper);
     }
+    args
+}
 
-    fn relaunch_in_terminal(&self) -> TermResult<()> {
-        for_target!(self, "windows", {
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
-
-            Command::new("wt")
-                .arg("new-tab")
-                .arg("--startingDirectory")
-                .arg(curr_wd)
-                .arg("--")
-                .arg(curr_exe)
-                .args(&args)
-                .spawn()?;
+/// Environment variable `HOMEBREW_PREFIX`, exported by `brew shellenv`.
+pub const HOMEBREW_PREFIX_VAR: &str = "HOMEBREW_PREFIX";
 
-            Ok(())
-        })
+/// Default `Homebrew` prefixes, for Apple Silicon (`/opt/homebrew`) and Intel (`/usr/local`) installs.
+const DEFAULT_HOMEBREW_PREFIXES: &[&str] = &["/opt/homebrew", "/usr/local"];
+
+/// Returns the `Homebrew` prefixes to search for terminals installed as formulae.
+///
+/// # Notes
+/// The `HOMEBREW_PREFIX` environment variable is returned first if set, followed by both default prefixes.
+/// Both defaults are always searched regardless of the build architecture, as an `x86_64` build running
+/// under Rosetta can still launch terminals installed by an `arm64` Homebrew, and vice versa.
+#[inline]
+#[must_use]
+pub fn homebrew_prefixes() -> Vec<PathBuf> {
+    homebrew_prefixes_in(&SystemEnvironment)
+}
+
+/// Returns the `Homebrew` prefixes to search with the given environment, see `homebrew_prefixes()`.
+#[must_use]
+pub fn homebrew_prefixes_in(env: &(impl Environment + ?Sized)) -> Vec<PathBuf> {
+    let mut prefixes: Vec<PathBuf> = env
+        .var_os(HOMEBREW_PREFIX_VAR)
+        .filter(|prefix| !prefix.is_empty())
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+
+    for default in DEFAULT_HOMEBREW_PREFIXES.iter().map(PathBuf::from) {
+        if !prefixes.contains(&default) {
+            prefixes.push(default);
+        }
     }
+
+    prefixes
 }
 
-/// Escapes a string for safe embedding in a shell single-quoted string.
-#[allow(dead_code)]
-fn shell_escape(s: &str) -> String {
-    format!("'{}'", s.replace('\'', r"'\''"))
+/// Returns the path of a binary installed by a `Homebrew` formula (e.g. `"kitty"`), if found in any prefix.
+#[cfg(target_os = "macos")]
+fn find_homebrew_binary(binary: &str) -> Option<PathBuf> {
+    find_binary_in_prefixes(binary, &homebrew_prefixes())
 }
 
-/// Escapes a list of arguments for safe embedding in a shell command.
-#[allow(dead_code)]
-fn shell_escape_args(args: &[String]) -> String {
-    args.iter()
-        .map(|a| shell_escape(a))
-        .collect::<Vec<_>>()
-        .join(" ")
+/// Returns the path of the given binary in the `bin` directory of the first prefix containing it.
+#[cfg(any(target_os = "macos", test))]
+fn find_binary_in_prefixes(binary: &str, prefixes: &[PathBuf]) -> Option<PathBuf> {
+    prefixes
+        .iter()
+        .map(|prefix| prefix.join("bin").join(binary))
+        .find(|path| path.is_file())
 }
 
-/// Terminal provider for `ITerm2`.
-pub struct ITerm2Provider;
+/// Returns the path of the given executable if found in any directory of the `PATH` environment variable.
+#[inline]
+#[must_use]
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    find_executable_in(name, &SystemEnvironment)
+}
 
-impl TerminalProvider for ITerm2Provider {
-    fn terminal_type(&self) -> TerminalType {
-        TerminalType::ITerm2
+/// Returns the path of the given executable if found in any directory of the `PATH` variable of the given
+/// environment.
+#[must_use]
+pub fn find_executable_in(name: &str, env: &(impl Environment + ?Sized)) -> Option<PathBuf> {
+    let paths = env.var_os("PATH")?;
+
+    std::env::split_paths(&paths)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join(name))
+        .find(|path| is_executable(path))
+}
+
+/// Returns `true` if the given path is a file that can be executed.
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
     }
 
-    fn is_installed(&self) -> bool {
-        for_target!("macos", {
-            std::path::Path::new("/Applications/iTerm.app").exists()
-        })
+    #[cfg(not(unix))]
+    {
+        path.is_file()
     }
+}
 
-    fn relaunch_in_terminal(&self) -> TermResult<()> {
-        for_target!(self, "macos", {
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
-
-            let quoted_wd = shell_escape(&curr_wd.to_string_lossy());
-            let quoted_exe = shell_escape(&curr_exe.to_string_lossy());
-            let quoted_args = shell_escape_args(&args);
-
-            let cmd = format!("cd {quoted_wd}; exec {quoted_exe} {quoted_args}");
-
-            let script = format!(
-                r#"
-tell application "iTerm"
-    activate
-    repeat 50 times
-        if (count of windows) > 0 then exit repeat
-        delay 0.1
-    end repeat
-    if (count of windows) > 0 then
-        tell current session of current window
-            write text "{cmd}"
-        end tell
-    else
-        error "no window" number 20
-    end if
-end tell
-"#
-            );
+/// Environment variable containing extra, colon separated directories to search for `MacOS` application bundles.
+pub const APP_DIRS_VAR: &str = "TERMINAL_RELAUNCH_APP_DIRS";
 
-            let res = Command::new("osascript")
-                .arg("-e")
-                .arg(script)
-                .spawn()?
-                .wait()?;
+/// Returns the directories to search for `MacOS` application bundles, in search order.
+///
+/// # Notes
+/// The directories in `TERMINAL_RELAUNCH_APP_DIRS` are returned first (ignoring empty segments), followed by
+/// `RelaunchConfig::extra_app_search_paths`, then `/Applications`, its firmlinked data volume form
+/// `/System/Volumes/Data/Applications`, and `~/Applications`.
+#[inline]
+#[must_use]
+pub fn macos_app_search_paths() -> Vec<PathBuf> {
+    macos_app_search_paths_in(&SystemEnvironment)
+}
 
-            if res.success() {
-                Ok(())
-            } else {
-                crate::logging::error!("ITerm2 launch exited unsuccessfully!");
-                Err(RelaunchError::FailedToLaunchTerminal(
-                    self.terminal_type(),
-                    res.clone(),
-                ))
-            }
+/// Returns the directories to search for `MacOS` application bundles, in search order, with
+/// `TERMINAL_RELAUNCH_APP_DIRS` read from the given environment, see `macos_app_search_paths()`.
+#[must_use]
+pub fn macos_app_search_paths_in(env: &(impl Environment + ?Sized)) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = env
+        .var_os(APP_DIRS_VAR)
+        .map(|dirs| {
+            std::env::split_paths(&dirs)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .collect()
         })
+        .unwrap_or_default();
+    paths.extend(relaunch_config().extra_app_search_paths);
+
+    let home_applications = crate::sudo::user_home().map(|home| home.join("Applications"));
+    for default in [
+        Some(PathBuf::from("/Applications")),
+        Some(PathBuf::from("/System/Volumes/Data/Applications")),
+        home_applications,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if !paths.contains(&default) {
+            paths.push(default);
+        }
     }
+
+    paths
 }
 
-/// Terminal provider for `Ghostty`.
-pub struct GhosttyProvider;
+/// Returns the path of the installed `MacOS` application bundle of the given terminal (e.g. `"kitty.app"`), if
+/// found in any of the `macos_app_search_paths()`.
+///
+/// # Notes
+/// Bundle names are compared case-insensitively, and the returned path uses the name as it is on disk,
+/// e.g. `"alacritty.app"` finds `/Applications/Alacritty.app`.
+#[cfg(target_os = "macos")]
+fn find_macos_app(terminal: TerminalType) -> Option<PathBuf> {
+    find_app_in_dirs(macos_bundle_name(terminal)?, &macos_app_search_paths())
+}
 
-impl TerminalProvider for GhosttyProvider {
-    fn terminal_type(&self) -> TerminalType {
-        TerminalType::Ghostty
-    }
+/// Returns the name of the `MacOS` application bundle of the given terminal, if it's looked up as one.
+#[cfg(any(target_os = "macos", test))]
+const fn macos_bundle_name(terminal: TerminalType) -> Option<&'static str> {
+    Some(match terminal {
+        TerminalType::ITerm2 => "iTerm.app",
+        TerminalType::Ghostty => "Ghostty.app",
+        TerminalType::Kitty => "kitty.app",
+        TerminalType::Alacritty => "Alacritty.app",
+        TerminalType::WezTerm => "WezTerm.app",
+        TerminalType::Hyper => "Hyper.app",
+        _ => return None,
+    })
+}
 
-    fn is_installed(&self) -> bool {
-        for_target!("macos", {
-            std::path::Path::new("/Applications/Ghostty.app").exists()
-        })
-    }
+/// Returns the path of the application bundle with the given name in the first of the given directories having it.
+#[cfg(any(target_os = "macos", test))]
+fn find_app_in_dirs(bundle_name: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    dirs.iter()
+        .find_map(|dir| find_entry_ignoring_case(dir, bundle_name))
+}
 
-    fn relaunch_in_terminal(&self) -> TermResult<()> {
-        for_target!(self, "macos", {
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
-
-            Command::new("open")
-                .arg("-na")
-                .arg("Ghostty")
-                .arg("--args")
-                .arg("-e")
-                .arg(curr_exe)
-                .args(args)
-                .current_dir(curr_wd)
-                .spawn()?;
+/// Returns the path of the entry in the given directory whose name matches the given name case-insensitively.
+#[cfg(any(target_os = "macos", test))]
+fn find_entry_ignoring_case(dir: &Path, name: &str) -> Option<PathBuf> {
+    // `MacOS` volumes are usually case-insensitive, so checking the exact path would accept the wrong casing..
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Some(dir.join(name)).filter(|path| path.exists());
+    };
 
-            Ok(())
+    entries
+        .filter_map(Result::ok)
+        .find(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(name)
         })
-    }
+        .map(|entry| entry.path())
 }
 
-/// Terminal provider for `Kitty`.
-pub struct KittyProvider;
+thread_local! {
+    /// Window mode used instead of the configured window mode for relaunches on this thread, if set.
+    static WINDOW_MODE_OVERRIDE: std::cell::Cell<Option<WindowMode>> = const { std::cell::Cell::new(None) };
+}
 
-impl TerminalProvider for KittyProvider {
-    fn terminal_type(&self) -> TerminalType {
-        TerminalType::Kitty
+thread_local! {
+    /// Directory to open an interactive shell in instead of relaunching the current program on this thread, if set.
+    static SHELL_DIRECTORY: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+thread_local! {
+    /// Working directory of relaunches on this thread, resolved when they were planned, if set.
+    static WORKING_DIRECTORY: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Runs the given function with relaunches on this thread starting in the given working directory, see
+/// `launch_working_directory()`.
+pub(crate) fn with_working_directory<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = WORKING_DIRECTORY.with(|cell| cell.replace(Some(dir.to_path_buf())));
+    let result = f();
+    WORKING_DIRECTORY.with(|cell| cell.replace(previous));
+
+    result
+}
+
+/// Returns the working directory a relaunched program should start in.
+///
+/// # Returns
+/// *   The working directory of the `RelaunchPipeline` launching, from inside a provider's `relaunch_in_terminal()`,
+///     see `RelaunchPipeline::working_directory()`.
+/// *   `RelaunchConfig::working_directory`, if set.
+/// *   The working directory captured by `startup::capture_startup_state()`, if captured.
+/// *   The current working directory otherwise.
+///
+/// # Notes
+/// The process working directory is shared by every thread, so it may have been changed between planning a
+/// relaunch and launching it. Custom providers should start the relaunched program in this directory, rather
+/// than reading the current working directory themselves.
+///
+/// # Panics
+/// If none of the above are set, and the current working directory can't be read, e.g. it was removed.
+#[must_use]
+pub fn launch_working_directory() -> PathBuf {
+    if let Some(dir) = WORKING_DIRECTORY.with(|cell| cell.borrow().clone()) {
+        return dir;
     }
 
-    fn is_installed(&self) -> bool {
-        for_target!("macos", {
-            std::path::Path::new("/Applications/kitty.app").exists()
+    relaunch_config()
+        .working_directory
+        .or_else(|| {
+            crate::startup::startup_state().and_then(|state| state.working_directory.clone())
         })
-    }
+        .unwrap_or_else(|| {
+            std::env::current_dir().expect("Failed to get current working directory")
+        })
+}
 
-    fn relaunch_in_terminal(&self) -> TermResult<()> {
-        for_target!(self, "macos", {
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
-
-            Command::new("open")
-                .arg("-na")
-                .arg("kitty")
-                .arg("--args")
-                .arg(curr_exe)
-                .args(args)
-                .current_dir(curr_wd)
-                .spawn()?;
+/// Runs the given function with relaunches on this thread opening an interactive shell in the given directory,
+/// rather than the current program, see `open_terminal_here()`.
+pub(crate) fn with_shell_in<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = SHELL_DIRECTORY.with(|cell| cell.replace(Some(dir.to_path_buf())));
+    let result = f();
+    SHELL_DIRECTORY.with(|cell| cell.replace(previous));
 
-            Ok(())
-        })
-    }
+    result
 }
 
-/// Terminal provider for `Alacritty`.
-pub struct AlacrittyProvider;
+/// Returns `true` if relaunches on this thread run the current program, rather than opening an interactive shell
+/// (see `open_terminal_here()`), e.g. for `TerminalProvider::unfit_reason()`.
+#[inline]
+#[must_use]
+pub fn launches_program() -> bool {
+    SHELL_DIRECTORY.with(|cell| cell.borrow().is_none())
+}
 
-impl TerminalProvider for AlacrittyProvider {
-    fn terminal_type(&self) -> TerminalType {
-        TerminalType::Alacritty
-    }
+/// Runs the given function with relaunches on this thread using the given window mode, regardless of
+/// the global relaunch configuration.
+pub(crate) fn with_window_mode<T>(mode: WindowMode, f: impl FnOnce() -> T) -> T {
+    let previous = WINDOW_MODE_OVERRIDE.with(|cell| cell.replace(Some(mode)));
+    let result = f();
+    WINDOW_MODE_OVERRIDE.with(|cell| cell.set(previous));
 
-    fn is_installed(&self) -> bool {
-        #[cfg(target_os = "macos")]
-        {
-            std::path::Path::new("/Applications/alacritty.app").exists()
-        }
+    result
+}
 
-        #[cfg(target_os = "windows")]
-        {
-            Command::new("alacritty")
-                .arg("--version")
-                .output()
-                .map(|output| output.status.success())
-                .unwrap_or(false)
-        }
+/// Resolves the window mode to relaunch with for the given provider, from the global relaunch configuration.
+///
+/// Unsupported modes degrade from a split to a tab, and from a tab to a window, unless strict window mode is set.
+#[cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    feature = "test-util"
+))]
+pub(crate) fn resolve_window_mode(provider: &dyn TerminalProvider) -> TermResult<WindowMode> {
+    let config = relaunch_config();
+    let requested = WINDOW_MODE_OVERRIDE
+        .with(std::cell::Cell::get)
+        .unwrap_or(config.window_mode);
 
-        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-        {
-            false
-        }
+    if provider.supports_window_mode(requested) {
+        return Ok(requested);
+    }
+    if config.strict_window_mode {
+        return Err(RelaunchError::UnsupportedWindowMode(
+            provider.terminal_type(),
+            requested,
+        ));
     }
 
-    fn relaunch_in_terminal(&self) -> TermResult<()> {
-        #[cfg(target_os = "windows")]
-        {
-            use std::os::windows::process::CommandExt;
+    let fallback = if requested.is_split() && provider.supports_window_mode(WindowMode::Tab) {
+        WindowMode::Tab
+    } else {
+        WindowMode::Window
+    };
+    crate::logging::info!(
+        "`{}` does not support opening a {requested}, falling back to a {fallback}.",
+        provider.terminal_type()
+    );
 
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
+    Ok(fallback)
+}
 
-            Command::new("cmd")
-                .current_dir(curr_wd)
-                .arg("/C")
-                .arg("alacritty")
-                .creation_flags(0x8 | 0x200)
-                .arg("-e")
-                .arg(curr_exe)
-                .args(args)
-                .spawn()?;
+/// Reads the version of an installed terminal from its application bundle (`CFBundleShortVersionString`),
+/// or for a binary, from the output of `--version`.
+fn read_installed_version(path: &Path) -> Option<TerminalVersion> {
+    let output = if path.is_dir() {
+        Command::new("defaults")
+            .arg("read")
+            .arg(path.join("Contents").join("Info"))
+            .arg("CFBundleShortVersionString")
+            .output()
+    } else {
+        Command::new(path).arg("--version").output()
+    }
+    .ok()?;
 
-            Ok(())
-        }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .find_map(TerminalVersion::parse)
+}
 
-        #[cfg(target_os = "macos")]
-        {
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
+/// Returns the error used when a terminal is no longer installed at the time of relaunch.
+fn not_installed_error(terminal_type: TerminalType) -> RelaunchError {
+    RelaunchError::IOError(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("`{terminal_type}` is no longer installed."),
+    ))
+}
 
-            Command::new("open")
-                .arg("-na")
-                .arg("alacritty")
-                .arg("--args")
-                .arg("-e")
-                .arg(curr_exe)
-                .args(args)
-                .current_dir(curr_wd)
-                .spawn()?;
+/// Maximum length of a `Windows` path without the verbatim (`\\?\`) prefix.
+const WINDOWS_MAX_PATH: usize = 260;
 
-            Ok(())
-        }
+/// Removes the verbatim prefix (`\\?\`, or `\\?\UNC\` for network shares) from a `Windows` path, if the
+/// resulting path fits in `MAX_PATH`.
+///
+/// `std::env::current_exe()` and canonicalized paths may be verbatim, which `wt` and `cmd` don't understand.
+/// Longer paths are returned unchanged, as they can only be represented in verbatim form.
+///
+/// # Notes
+/// Path support per provider on `Windows`:
+///
+/// | Path                    | `Windows Terminal`                    | `Alacritty`                           |
+/// |-------------------------|---------------------------------------|---------------------------------------|
+/// | Verbatim, `< MAX_PATH`  | Prefix stripped.                      | Prefix stripped.                      |
+/// | Verbatim, `>= MAX_PATH` | Passed unchanged.                     | Passed unchanged.                     |
+/// | UNC working directory   | Changed into by `cmd` with `pushd`.   | Passed with `--working-directory`.    |
+///
+/// # Example
+/// ```
+/// use std::path::{Path, PathBuf};
+///
+/// use terminal_relaunch::terminal_providers::strip_verbatim_prefix;
+///
+/// assert_eq!(strip_verbatim_prefix(Path::new(r"\\?\C:\tools")), PathBuf::from(r"C:\tools"));
+/// assert_eq!(strip_verbatim_prefix(Path::new(r"\\?\UNC\srv\share")), PathBuf::from(r"\\srv\share"));
+/// assert_eq!(strip_verbatim_prefix(Path::new(r"\\?\Volume{1}\dir")), PathBuf::from(r"\\?\Volume{1}\dir"));
+///
+/// let long = format!(r"\\?\C:\{}", "a".repeat(300));
+/// assert_eq!(strip_verbatim_prefix(Path::new(&long)), PathBuf::from(&long));
+/// ```
+#[must_use]
+pub fn strip_verbatim_prefix(path: &Path) -> PathBuf {
+    let Some(value) = path.to_str() else {
+        return path.to_path_buf();
+    };
 
-        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-        {
-            Err(RelaunchError::UnsupportedTerminalProvider(
-                self.terminal_type(),
-            ))
-        }
+    let stripped = if let Some(share) = value.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{share}")
+    } else if let Some(local) = value
+        .strip_prefix(r"\\?\")
+        .filter(|local| local.get(1..3) == Some(r":\"))
+    {
+        local.to_string()
+    } else {
+        return path.to_path_buf();
+    };
+
+    if stripped.encode_utf16().count() < WINDOWS_MAX_PATH {
+        PathBuf::from(stripped)
+    } else {
+        path.to_path_buf()
     }
 }
 
-/// Terminal provider for `WezTerm`.
-pub struct WezTermProvider;
-
-impl TerminalProvider for WezTermProvider {
+/// Returns `true` if the given path is a `Windows` network share (UNC) path, e.g. `\\server\share\dir`,
+/// including its verbatim form.
+#[must_use]
+pub fn is_unc_path(path: &Path) -> bool {
+    path.to_str().is_some_and(|value| {
+        value.starts_with(r"\\?\UNC\")
+            || (value.starts_with(r"\\")
+                && !value.starts_with(r"\\?\")
+                && !value.starts_with(r"\\.\"))
+    })
+}
+
+/// Builds a `cmd` command line changing into the given directory with `pushd` (which maps a temporary drive
+/// for network shares), then running the given program, if any.
+///
+/// # Returns
+/// `None` if the directory is verbatim (`cmd` can't change into it), or anything contains a character `cmd`
+/// would interpret even when quoted (`"`, `%` or a line break).
+///
+/// # Notes
+/// The program parses its arguments as `CommandLineToArgvW`, where backslashes before a closing quote escape
+/// it, so trailing backslashes of the arguments are doubled. The program name is taken literally up to the
+/// closing quote, so is quoted unchanged.
+#[cfg(any(target_os = "windows", feature = "test-util", test))]
+fn pushd_command_line(dir: &Path, program: Option<&LaunchProgram>) -> Option<String> {
+    fn quote(value: &str) -> Option<String> {
+        (!value.contains(['"', '%', '\r', '\n'])).then(|| format!("\"{value}\""))
+    }
+
+    fn quote_argument(value: &str) -> Option<String> {
+        let trailing = value.len() - value.trim_end_matches('\\').len();
+        quote(&format!("{value}{}", "\\".repeat(trailing)))
+    }
+
+    let dir = dir.to_str()?;
+    if dir.starts_with(r"\\?\") {
+        return None;
+    }
+
+    let mut command_line = format!("pushd {}", quote(dir)?);
+    if let Some((exe, args)) = program {
+        command_line.push_str(" && ");
+        command_line.push_str(&quote(exe.to_str()?)?);
+        for arg in args {
+            command_line.push(' ');
+            command_line.push_str(&quote_argument(arg)?);
+        }
+    }
+
+    Some(command_line)
+}
+
+/// Normalises a working directory for use with `wt --startingDirectory`.
+///
+/// Arguments containing spaces are quoted when spawned, so a trailing backslash escapes the closing quote
+/// and `wt` receives a mangled directory, also swallowing the following `--` separator. A single trailing
+/// separator is stripped, except for drive roots (e.g. `C:\`) where it is doubled instead.
+fn wt_starting_directory(path: &Path) -> OsString {
+    let Some(dir) = path.to_str() else {
+        return path.as_os_str().to_owned();
+    };
+    let Some(stripped) = dir.strip_suffix(['\\', '/']) else {
+        return dir.into();
+    };
+
+    // Drive roots, including the verbatim form (e.g. `\\?\C:\`)..
+    let drive = stripped.strip_prefix(r"\\?\").unwrap_or(stripped);
+    let is_drive_root = drive.len() == 2
+        && drive.ends_with(':')
+        && drive.starts_with(|c: char| c.is_ascii_alphabetic());
+
+    if is_drive_root {
+        format!("{dir}\\").into()
+    } else {
+        stripped.into()
+    }
+}
+
+/// How a working directory reaches the relaunched program, see `cwd_argument()`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CwdArg {
+    /// Passed to the terminal as the value of its directory flag, see `CwdArg::flag()`.
+    Value(OsString),
+    /// Quoted for a POSIX shell command typed into the terminal.
+    Shell(ShellQuoted),
+    /// Changed into by a `cmd /c` wrapper with `pushd`, as the terminal rejects network shares.
+    Pushd(PathBuf),
+    /// Not passed to the terminal, only set as the working directory of the spawned process.
+    CurrentDir(PathBuf),
+}
+
+impl CwdArg {
+    /// Returns the `--flag=value` argument for the given flag, or `None` if the directory isn't passed as a value.
+    ///
+    /// The joined form is always used, so a directory starting with `-` is never parsed as a flag.
+    #[must_use]
+    pub fn flag(&self, flag: &str) -> Option<OsString> {
+        let Self::Value(value) = self else {
+            return None;
+        };
+
+        let mut argument = OsString::from(flag);
+        argument.push("=");
+        argument.push(value);
+        Some(argument)
+    }
+}
+
+/// Returns how the given working directory is passed when relaunching in the given terminal.
+///
+/// # Notes
+/// | Terminal                          | Strategy                                                              |
+/// |-----------------------------------|-----------------------------------------------------------------------|
+/// | `Windows Terminal`                | `--startingDirectory=`, or `pushd` for network shares.                |
+/// | `Kitty`, `WezTerm`                | `--directory=` / `--cwd=`.                                            |
+/// | `Alacritty`                       | `--working-directory=` for network shares, otherwise the process cwd. |
+/// | `ITerm2`                          | Quoted in the shell command typed into the new session.               |
+/// | Anything else                     | The process cwd.                                                      |
+///
+/// A relative directory starting with `-` is prefixed with `./`.
+///
+/// # Errors
+/// `RelaunchError::UnrepresentablePath` for `ITerm2` if the directory contains a control character, e.g. a line
+/// break would submit the typed command early and an escape sequence would be interpreted by the terminal, or
+/// isn't valid unicode, as `osascript` decodes its arguments as `UTF-8`.
+///
+/// # Example
+/// ```
+/// use std::ffi::OsString;
+/// use std::path::{Path, PathBuf};
+///
+/// use terminal_relaunch::TerminalType;
+/// use terminal_relaunch::terminal_providers::{CwdArg, ShellQuoted, cwd_argument};
+///
+/// let flag = |terminal, dir: &str, flag| cwd_argument(terminal, Path::new(dir)).unwrap().flag(flag);
+///
+/// for (terminal, name) in [(TerminalType::Kitty, "--directory"), (TerminalType::WezTerm, "--cwd")] {
+///     assert_eq!(flag(terminal, "/tmp/-foo", name), Some(OsString::from(format!("{name}=/tmp/-foo"))));
+///     assert_eq!(flag(terminal, "-foo", name), Some(OsString::from(format!("{name}=./-foo"))));
+///     assert_eq!(flag(terminal, "/tmp/a\nb", name), Some(OsString::from(format!("{name}=/tmp/a\nb"))));
+///     assert_eq!(flag(terminal, "/tmp/with space", name), Some(OsString::from(format!("{name}=/tmp/with space"))));
+/// }
+///
+/// assert_eq!(
+///     flag(TerminalType::WindowsTerminal, r"C:\-foo\", "--startingDirectory"),
+///     Some(OsString::from(r"--startingDirectory=C:\-foo")),
+/// );
+/// assert_eq!(
+///     cwd_argument(TerminalType::WindowsTerminal, Path::new(r"\\srv\share")).unwrap(),
+///     CwdArg::Pushd(PathBuf::from(r"\\srv\share")),
+/// );
+///
+/// for dir in ["/tmp/-foo", "/tmp/a\nb", "/tmp/with space"] {
+///     let process_cwd = cwd_argument(TerminalType::Ghostty, Path::new(dir)).unwrap();
+///     assert_eq!(process_cwd, CwdArg::CurrentDir(PathBuf::from(dir)));
+///     assert_eq!(cwd_argument(TerminalType::Alacritty, Path::new(dir)).unwrap(), process_cwd);
+/// }
+///
+/// let shell = |dir: &str| cwd_argument(TerminalType::ITerm2, Path::new(dir));
+/// assert_eq!(shell("/tmp/-foo").unwrap(), CwdArg::Shell(ShellQuoted::quote("/tmp/-foo")));
+/// assert_eq!(shell("/tmp/it's here").unwrap(), CwdArg::Shell(ShellQuoted::quote("/tmp/it's here")));
+/// assert!(shell("/tmp/a\nb").is_err());
+/// assert!(shell("/tmp/\x1b]0;title\x07").is_err());
+/// ```
+pub fn cwd_argument(terminal: TerminalType, path: &Path) -> TermResult<CwdArg> {
+    let value = if path.is_relative() && path.as_os_str().as_encoded_bytes().starts_with(b"-") {
+        Path::new(".").join(path)
+    } else {
+        path.to_path_buf()
+    };
+
+    Ok(match terminal {
+        TerminalType::WindowsTerminal if is_unc_path(path) => CwdArg::Pushd(path.to_path_buf()),
+        TerminalType::WindowsTerminal => CwdArg::Value(wt_starting_directory(&value)),
+        TerminalType::Kitty | TerminalType::WezTerm | TerminalType::Ptyxis => {
+            CwdArg::Value(value.into_os_string())
+        }
+        TerminalType::Alacritty if is_unc_path(path) => CwdArg::Value(value.into_os_string()),
+        TerminalType::ITerm2 => match path.to_str() {
+            Some(dir) if !dir.contains(char::is_control) => CwdArg::Shell(ShellQuoted::quote(dir)),
+            _ => {
+                return Err(RelaunchError::UnrepresentablePath(
+                    terminal,
+                    path.to_path_buf(),
+                ));
+            }
+        },
+        _ => CwdArg::CurrentDir(path.to_path_buf()),
+    })
+}
+
+/// A source of the executables registered under `App Paths` in the `Windows` registry, so lookups can be mocked.
+pub trait AppPathRegistry {
+    /// Returns the registered path of the given executable name (e.g. `wt.exe`), or the name itself if it's
+    /// registered without a path.
+    #[must_use]
+    fn app_path(&self, exe_name: &str) -> Option<PathBuf>;
+}
+
+/// The `App Paths` of the current user, read from the registry on `Windows`, and empty on other platforms.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemAppPathRegistry;
+
+impl AppPathRegistry for SystemAppPathRegistry {
+    fn app_path(&self, exe_name: &str) -> Option<PathBuf> {
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = exe_name;
+            None
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use winreg::RegKey;
+            use winreg::enums::HKEY_CURRENT_USER;
+
+            let key = RegKey::predef(HKEY_CURRENT_USER)
+                .open_subkey(format!(
+                    r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\{exe_name}"
+                ))
+                .ok()?;
+            let path = key
+                .get_value::<String, _>("")
+                .ok()
+                .map(|path| path.trim().trim_matches('"').to_string())
+                .filter(|path| !path.is_empty())
+                .unwrap_or_else(|| exe_name.to_string());
+            Some(PathBuf::from(path))
+        }
+    }
+}
+
+impl<S: std::hash::BuildHasher> AppPathRegistry for std::collections::HashMap<String, PathBuf, S> {
+    fn app_path(&self, exe_name: &str) -> Option<PathBuf> {
+        self.get(exe_name).cloned()
+    }
+}
+
+/// Package family name of `Windows Terminal`, naming its directory of app execution aliases.
+pub const WINDOWS_TERMINAL_PACKAGE: &str = "Microsoft.WindowsTerminal_8wekyb3d8bbwe";
+/// Package family name of `Windows Terminal Preview`, naming its directory of app execution aliases.
+pub const WINDOWS_TERMINAL_PREVIEW_PACKAGE: &str = "Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe";
+
+/// Represents a release channel of `Windows Terminal`, installed as separate packages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowsTerminalChannel {
+    /// The stable release, see `WINDOWS_TERMINAL_PACKAGE`.
+    Stable,
+    /// The preview release, see `WINDOWS_TERMINAL_PREVIEW_PACKAGE`.
+    Preview,
+}
+
+impl WindowsTerminalChannel {
+    /// Returns the name of the channel.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stable => "Windows Terminal",
+            Self::Preview => "Windows Terminal Preview",
+        }
+    }
+
+    /// Returns the package family name of the channel.
+    #[inline]
+    #[must_use]
+    pub fn package(&self) -> &'static str {
+        match self {
+            Self::Stable => WINDOWS_TERMINAL_PACKAGE,
+            Self::Preview => WINDOWS_TERMINAL_PREVIEW_PACKAGE,
+        }
+    }
+}
+
+impl std::fmt::Display for WindowsTerminalChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An installed `Windows Terminal`, and how to launch it, see `WindowsTerminalInstall::find()`.
+///
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+/// use std::path::PathBuf;
+///
+/// use terminal_relaunch::terminal_providers::{
+///     WINDOWS_TERMINAL_PREVIEW_PACKAGE, WindowsTerminalChannel, WindowsTerminalInstall,
+/// };
+///
+/// let local_app_data = std::env::temp_dir().join(format!("wt-install-{}", std::process::id()));
+/// let env = HashMap::from([("LOCALAPPDATA".to_string(), local_app_data.display().to_string())]);
+/// let no_registry = HashMap::<String, PathBuf>::new();
+///
+/// // Nothing registered or aliased..
+/// assert_eq!(WindowsTerminalInstall::find_in(&no_registry, &env), None);
+///
+/// // The stable release registered under `App Paths` is launched through the `wt` alias, as before..
+/// let stable = HashMap::from([(
+///     "wt.exe".to_string(),
+///     PathBuf::from(r"C:\Users\me\AppData\Local\Microsoft\WindowsApps\Microsoft.WindowsTerminal_8wekyb3d8bbwe\wt.exe"),
+/// )]);
+/// let install = WindowsTerminalInstall::find_in(&stable, &env).unwrap();
+/// assert_eq!(install.channel, WindowsTerminalChannel::Stable);
+/// assert_eq!(install.launcher, PathBuf::from("wt"));
+///
+/// // `Preview` registered under `App Paths` is launched through its own alias..
+/// let preview_alias =
+///     PathBuf::from(r"C:\Users\me\AppData\Local\Microsoft\WindowsApps\Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe\wt.exe");
+/// let preview = HashMap::from([("wt.exe".to_string(), preview_alias.clone())]);
+/// let install = WindowsTerminalInstall::find_in(&preview, &env).unwrap();
+/// assert_eq!(install.channel, WindowsTerminalChannel::Preview);
+/// assert_eq!(install.launcher, preview_alias);
+///
+/// // A `Preview` only install without a registration is found from its alias directory..
+/// let alias_dir = local_app_data.join("Microsoft").join("WindowsApps").join(WINDOWS_TERMINAL_PREVIEW_PACKAGE);
+/// std::fs::create_dir_all(&alias_dir).unwrap();
+/// std::fs::write(alias_dir.join("wt.exe"), "").unwrap();
+/// let install = WindowsTerminalInstall::find_in(&no_registry, &env).unwrap();
+/// assert_eq!(install.channel, WindowsTerminalChannel::Preview);
+/// assert_eq!(install.launcher, alias_dir.join("wt.exe"));
+/// assert_eq!(install.channel.to_string(), "Windows Terminal Preview");
+///
+/// // A registration wins over the alias directories..
+/// assert_eq!(WindowsTerminalInstall::find_in(&stable, &env).unwrap().channel, WindowsTerminalChannel::Stable);
+///
+/// std::fs::remove_dir_all(&local_app_data).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WindowsTerminalInstall {
+    /// The release channel installed.
+    pub channel: WindowsTerminalChannel,
+    /// The executable to launch it with, e.g. the `wt` alias.
+    pub launcher: PathBuf,
+}
+
+impl WindowsTerminalInstall {
+    /// Finds the installed `Windows Terminal`, see `find_in()`.
+    #[inline]
+    #[must_use]
+    pub fn find() -> Option<Self> {
+        Self::find_in(&SystemAppPathRegistry, &SystemEnvironment)
+    }
+
+    /// Finds the installed `Windows Terminal` from the given `App Paths` registry and environment.
+    ///
+    /// # Returns
+    /// *   The release registered for `wt.exe` under `App Paths`, telling `Preview` apart by its package in the
+    ///     registered path. The stable release is launched through the `wt` alias, and `Preview` through the
+    ///     registered path.
+    /// *   The first release with a `wt.exe` alias in its directory of `%LOCALAPPDATA%\Microsoft\WindowsApps`
+    ///     otherwise, stable first, launched through that alias.
+    /// *   `None` otherwise.
+    #[must_use]
+    pub fn find_in(
+        registry: &(impl AppPathRegistry + ?Sized),
+        env: &(impl Environment + ?Sized),
+    ) -> Option<Self> {
+        if let Some(path) = registry.app_path("wt.exe") {
+            let is_preview = path
+                .to_string_lossy()
+                .to_ascii_lowercase()
+                .contains(&WINDOWS_TERMINAL_PREVIEW_PACKAGE.to_ascii_lowercase());
+            return Some(if is_preview {
+                Self {
+                    channel: WindowsTerminalChannel::Preview,
+                    launcher: path,
+                }
+            } else {
+                Self {
+                    channel: WindowsTerminalChannel::Stable,
+                    launcher: PathBuf::from("wt"),
+                }
+            });
+        }
+
+        let aliases = PathBuf::from(env.var_os("LOCALAPPDATA")?)
+            .join("Microsoft")
+            .join("WindowsApps");
+        [
+            WindowsTerminalChannel::Stable,
+            WindowsTerminalChannel::Preview,
+        ]
+        .into_iter()
+        .map(|channel| Self {
+            channel,
+            launcher: aliases.join(channel.package()).join("wt.exe"),
+        })
+        // App execution aliases are reparse points that can't be followed, so aren't checked with `is_file()`..
+        .find(|install| std::fs::symlink_metadata(&install.launcher).is_ok())
+    }
+}
+
+/// Terminal provider for `Windows Terminal`.
+#[derive(Debug, Default)]
+pub struct WindowsTerminalProvider {
+    install: Resolved<WindowsTerminalInstall>,
+}
+
+impl WindowsTerminalProvider {
+    /// Returns the installation found when first checked, see `Resolved`.
+    fn install(&self) -> Option<&WindowsTerminalInstall> {
+        self.install
+            .get_or_resolve(|| for_target!("windows", { WindowsTerminalInstall::find() }))
+    }
+}
+
+impl TerminalProvider for WindowsTerminalProvider {
     fn terminal_type(&self) -> TerminalType {
-        TerminalType::WezTerm
+        TerminalType::WindowsTerminal
+    }
+
+    fn supports_window_mode(&self, mode: WindowMode) -> bool {
+        // Splitting requires a current pane, i.e. that we are running inside `Windows Terminal`..
+        !mode.is_split() || std::env::var_os("WT_SESSION").is_some()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.install().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        // The stable release is launched through the `wt` alias, which has no path to check..
+        self.install()
+            .map(|install| install.launcher.clone())
+            .filter(|launcher| launcher.is_absolute())
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        for_target!(self, "windows", {
+            let (program, curr_wd) = get_launch_params();
+            let program = program.map(|(curr_exe, args)| (strip_verbatim_prefix(&curr_exe), args));
+            let curr_wd = strip_verbatim_prefix(&curr_wd);
+
+            let mode = resolve_window_mode(self)?;
+            let target = wt_window_target(
+                WINDOW_MODE_OVERRIDE.with(std::cell::Cell::get).is_some(),
+                relaunch_config().windows_terminal_window_target,
+                running_process_names,
+            );
+
+            let install = self
+                .install()
+                .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+            wt_command(
+                &install.launcher,
+                (mode, target, relaunch_config().window_geometry),
+                &curr_wd,
+                program.as_ref(),
+                &crate::environment::propagated_env(),
+            )?
+            .spawn()?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Returns the command relaunching the given program, or opening an interactive shell, in `Windows Terminal` with
+/// the given launcher, window placement and working directory.
+///
+/// # Errors
+/// `RelaunchError::UnrepresentablePath` if the working directory is a network share `cmd` can't change into, see
+/// `pushd_command_line()`.
+#[cfg(any(target_os = "windows", test))]
+fn wt_command(
+    launcher: &Path,
+    (mode, target, geometry): (WindowMode, WtWindowTarget, Option<WindowGeometry>),
+    curr_wd: &Path,
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> TermResult<Command> {
+    let mut command = Command::new(launcher);
+    command
+        .args(wt_geometry_args(mode, target, geometry))
+        .args(wt_window_args(mode, target));
+
+    // Some `Windows Terminal` versions reject network share starting directories..
+    match cwd_argument(TerminalType::WindowsTerminal, curr_wd)? {
+        CwdArg::Pushd(dir) => {
+            let command_line = pushd_command_line(&dir, program).ok_or_else(|| {
+                RelaunchError::UnrepresentablePath(TerminalType::WindowsTerminal, dir.clone())
+            })?;
+            // Without a program, `cmd` is kept open as the interactive shell..
+            let run = if program.is_some() { "/c" } else { "/k" };
+            command.args(["--", "cmd.exe", "/d", run]);
+
+            #[cfg(target_os = "windows")]
+            {
+                use std::os::windows::process::CommandExt;
+
+                command.raw_arg(command_line);
+            }
+            // Only built elsewhere by the tests, which check the command line as a single argument..
+            #[cfg(not(target_os = "windows"))]
+            command.arg(command_line);
+        }
+        cwd => {
+            command
+                .args(cwd.flag("--startingDirectory"))
+                .args(program_args(&["--"], program));
+        }
+    }
+
+    command.envs(vars.iter().cloned());
+    Ok(command)
+}
+
+/// A POSIX shell command, or part of one, with every value derived from the environment (paths, arguments,
+/// variables) quoted, see `ShellQuoted::quote()`.
+///
+/// # Notes
+/// *   Providers only pass shell commands to terminals as this type, e.g. `CwdArg::Shell`, and it can only be
+///     constructed from quoted values, so an unquoted value never reaches a shell.
+/// *   The quoting keeps every byte of the value, control characters included, so values that are typed into a
+///     terminal (e.g. for `ITerm2`) must be checked for them first, see `cwd_argument()`.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::terminal_providers::ShellQuoted;
+///
+/// assert_eq!(ShellQuoted::quote("$HOME; it's").as_str(), r"'$HOME; it'\''s'");
+/// assert_eq!(ShellQuoted::quote_args(&["a b".to_string(), String::new()]).to_string(), "'a b' ''");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShellQuoted(String);
+
+impl ShellQuoted {
+    /// Quotes a value as a single shell word, in single quotes.
+    ///
+    /// Only `'` is replaced, so multi-byte characters are copied through unchanged.
+    #[must_use]
+    pub fn quote(value: &str) -> Self {
+        Self(format!("'{}'", value.replace('\'', r"'\''")))
+    }
+
+    /// Quotes each argument as a single shell word, separated by spaces.
+    #[must_use]
+    pub fn quote_args(args: &[String]) -> Self {
+        Self(
+            args.iter()
+                .map(|arg| Self::quote(arg).0)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /// Returns the quoted command.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ShellQuoted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Name of the `Windows Terminal` process.
+pub const WINDOWS_TERMINAL_PROCESS: &str = "WindowsTerminal.exe";
+
+/// Returns the `wt` arguments selecting the window and action for the given window mode and window target.
+///
+/// # Notes
+/// Tabs always open in an existing window (or the quake window), and splits always split the current window.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::config::{WindowMode, WtWindowTarget};
+/// use terminal_relaunch::terminal_providers::wt_window_args;
+///
+/// let golden: [(WindowMode, WtWindowTarget, &[&str]); 12] = [
+///     (WindowMode::Window, WtWindowTarget::New, &["new-tab"]),
+///     (WindowMode::Window, WtWindowTarget::Existing, &["-w", "0", "new-tab"]),
+///     (WindowMode::Window, WtWindowTarget::Quake, &["-w", "_quake", "new-tab"]),
+///     (WindowMode::Tab, WtWindowTarget::New, &["-w", "0", "new-tab"]),
+///     (WindowMode::Tab, WtWindowTarget::Existing, &["-w", "0", "new-tab"]),
+///     (WindowMode::Tab, WtWindowTarget::Quake, &["-w", "_quake", "new-tab"]),
+///     (WindowMode::SplitHorizontal, WtWindowTarget::New, &["-w", "0", "split-pane", "-H"]),
+///     (WindowMode::SplitHorizontal, WtWindowTarget::Existing, &["-w", "0", "split-pane", "-H"]),
+///     (WindowMode::SplitHorizontal, WtWindowTarget::Quake, &["-w", "0", "split-pane", "-H"]),
+///     (WindowMode::SplitVertical, WtWindowTarget::New, &["-w", "0", "split-pane", "-V"]),
+///     (WindowMode::SplitVertical, WtWindowTarget::Existing, &["-w", "0", "split-pane", "-V"]),
+///     (WindowMode::SplitVertical, WtWindowTarget::Quake, &["-w", "0", "split-pane", "-V"]),
+/// ];
+/// for (mode, target, args) in golden {
+///     assert_eq!(wt_window_args(mode, target), args, "{mode} in {target}");
+/// }
+/// ```
+#[must_use]
+pub fn wt_window_args(mode: WindowMode, target: WtWindowTarget) -> &'static [&'static str] {
+    match (mode, target) {
+        (WindowMode::SplitHorizontal, _) => &["-w", "0", "split-pane", "-H"],
+        (WindowMode::SplitVertical, _) => &["-w", "0", "split-pane", "-V"],
+        (_, WtWindowTarget::Quake) => &["-w", "_quake", "new-tab"],
+        (WindowMode::Window, WtWindowTarget::New) => &["new-tab"],
+        (WindowMode::Window, WtWindowTarget::Existing) | (WindowMode::Tab, _) => {
+            &["-w", "0", "new-tab"]
+        }
+    }
+}
+
+/// Returns the `wt` arguments positioning and sizing the window for the given window mode, window target and
+/// geometry.
+///
+/// # Notes
+/// The geometry only applies when a new window is opened, existing windows keep their position and size.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::config::{WindowGeometry, WindowMode, WtWindowTarget};
+/// use terminal_relaunch::terminal_providers::wt_geometry_args;
+///
+/// let geometry = Some(WindowGeometry { x: -1920, y: 120, cols: 129, rows: 38 });
+/// assert_eq!(
+///     wt_geometry_args(WindowMode::Window, WtWindowTarget::New, geometry),
+///     ["--pos", "-1920,120", "--size", "129,38"],
+/// );
+///
+/// // Without hints, or when opening in an existing window, no arguments are added..
+/// assert!(wt_geometry_args(WindowMode::Window, WtWindowTarget::New, None).is_empty());
+/// assert!(wt_geometry_args(WindowMode::Window, WtWindowTarget::Existing, geometry).is_empty());
+/// assert!(wt_geometry_args(WindowMode::Tab, WtWindowTarget::New, geometry).is_empty());
+/// ```
+#[must_use]
+pub fn wt_geometry_args(
+    mode: WindowMode,
+    target: WtWindowTarget,
+    geometry: Option<WindowGeometry>,
+) -> Vec<String> {
+    match geometry {
+        Some(geometry) if mode == WindowMode::Window && target == WtWindowTarget::New => vec![
+            "--pos".to_string(),
+            format!("{},{}", geometry.x, geometry.y),
+            "--size".to_string(),
+            format!("{},{}", geometry.cols, geometry.rows),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the window target to use when none is configured, given the names of the running processes.
+///
+/// # Returns
+/// *   `WtWindowTarget::Existing` if `Windows Terminal` is already running, so no second instance is opened.
+/// *   `WtWindowTarget::New` otherwise.
+#[must_use]
+pub fn default_wt_window_target(process_names: &[String]) -> WtWindowTarget {
+    if process_names
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(WINDOWS_TERMINAL_PROCESS))
+    {
+        WtWindowTarget::Existing
+    } else {
+        WtWindowTarget::New
+    }
+}
+
+/// Returns the window `wt` opens the relaunch in, listing the running processes with `running_process_names` only
+/// if the target is neither requested nor configured.
+///
+/// # Returns
+/// *   `WtWindowTarget::New` if a new window was explicitly requested, e.g. by
+///     `open_in_new_window_of_current_terminal()`.
+/// *   The configured target, see `RelaunchConfig::windows_terminal_window_target`.
+/// *   Otherwise the default target for the running processes, see `default_wt_window_target()`.
+#[cfg(any(target_os = "windows", test))]
+fn wt_window_target(
+    new_window_requested: bool,
+    configured: Option<WtWindowTarget>,
+    running_process_names: impl FnOnce() -> Vec<String>,
+) -> WtWindowTarget {
+    if new_window_requested {
+        return WtWindowTarget::New;
+    }
+
+    configured.unwrap_or_else(|| default_wt_window_target(&running_process_names()))
+}
+
+/// Returns the executable names of all running processes, empty if they could not be listed.
+#[cfg(target_os = "windows")]
+fn running_process_names() -> Vec<String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
+        TH32CS_SNAPPROCESS,
+    };
+
+    // SAFETY: `CreateToolhelp32Snapshot` has no preconditions.
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Vec::new();
+    }
+
+    let mut names = Vec::new();
+    // SAFETY: `PROCESSENTRY32W` is a plain C struct, `dwSize` is set before use as required.
+    let mut entry: PROCESSENTRY32W = unsafe { std::mem::zeroed() };
+    entry.dwSize = u32::try_from(size_of::<PROCESSENTRY32W>()).unwrap_or_default();
+
+    // SAFETY: `snapshot` is a valid snapshot handle and `entry` is a valid pointer, for all calls below.
+    unsafe {
+        let mut found = Process32FirstW(snapshot, &raw mut entry);
+        while found != 0 {
+            let len = entry
+                .szExeFile
+                .iter()
+                .position(|c| *c == 0)
+                .unwrap_or(entry.szExeFile.len());
+            names.push(String::from_utf16_lossy(&entry.szExeFile[..len]));
+            found = Process32NextW(snapshot, &raw mut entry);
+        }
+        CloseHandle(snapshot);
+    }
+
+    names
+}
+
+/// Returns the `export` statement setting the given variables in a POSIX shell, skipping names that aren't valid
+/// shell identifiers, or `None` if there are none.
+fn export_statement(vars: &[(&str, String)]) -> Option<ShellQuoted> {
+    let assignments = vars
+        .iter()
+        .filter(|(name, _)| {
+            name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .map(|(name, value)| format!("{name}={}", ShellQuoted::quote(value)))
+        .collect::<Vec<_>>();
+
+    (!assignments.is_empty()).then(|| ShellQuoted(format!("export {}", assignments.join(" "))))
+}
+
+/// Returns the arguments running a program through a POSIX shell that exports the given variables first, to be
+/// followed by the program and its arguments, or nothing if there are no variables.
+///
+/// Launchers like `open` start the terminal with `launchd`'s environment, and `kitten @ launch` and `wezterm cli`
+/// start the program with the running terminal's, so this is how the relaunched program keeps the variables of
+/// `environment::propagated_env()`. The program and its arguments are passed to the shell as arguments, so they
+/// are never parsed by it.
+///
+/// # Example
+/// ```rust
+/// # #[cfg(unix)] {
+/// use std::ffi::OsString;
+/// use std::process::Command;
+///
+/// use terminal_relaunch::environment::propagated_env_in;
+/// use terminal_relaunch::terminal_providers::env_wrapper_args;
+///
+/// let parent = std::collections::HashMap::from([
+///     ("LANG".to_string(), "de_DE.UTF-8".to_string()),
+///     ("LC_ALL".to_string(), "it's'".to_string()),
+///     ("TZ".to_string(), "Europe/Berlin".to_string()),
+/// ]);
+/// let program = ["/bin/sh", "-c", r#"printf '%s|%s|%s|%s' "$LANG" "$LC_ALL" "$TZ" "$1""#, "sh", "$HOME; x"];
+///
+/// // The arguments each launcher is given, the terminal's own first (e.g. `open -na Ghostty.app --args -e`)..
+/// for prefix in [&["-e"][..], &[], &["--"]] {
+///     let args: Vec<OsString> = prefix
+///         .iter()
+///         .map(OsString::from)
+///         .chain(env_wrapper_args(&propagated_env_in(&parent)))
+///         .chain(program.iter().map(OsString::from))
+///         .collect();
+///
+///     // ..run as the terminal would, with `launchd`'s environment rather than the parent's..
+///     let output = Command::new(&args[prefix.len()]).args(&args[prefix.len() + 1..]).env_clear().output().unwrap();
+///     assert_eq!(String::from_utf8_lossy(&output.stdout), "de_DE.UTF-8|it's'|Europe/Berlin|$HOME; x");
+/// }
+///
+/// // Nothing is wrapped without variables, nor are invalid names exported..
+/// assert!(env_wrapper_args(&[]).is_empty());
+/// assert!(env_wrapper_args(&[("LC ALL; rm", "C".to_string())]).is_empty());
+/// # }
+/// ```
+#[must_use]
+pub fn env_wrapper_args(vars: &[(&str, String)]) -> Vec<OsString> {
+    export_statement(vars).map_or_else(Vec::new, |exports| {
+        vec![
+            "/bin/sh".into(),
+            "-c".into(),
+            format!(r#"{exports}; exec "$0" "$@""#).into(),
+        ]
+    })
+}
+
+/// Returns the POSIX shell command line running the given arguments, as typed into terminals that only accept
+/// a shell command, e.g. for fuzzing the quoting against a reference shell tokenizer.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::terminal_providers::posix_shell_command_line;
+///
+/// let args = ["/opt/my app/bin".to_string(), "it's".to_string(), String::new()];
+/// assert_eq!(posix_shell_command_line(&args), r"'/opt/my app/bin' 'it'\''s' ''");
+/// ```
+#[cfg(feature = "test-util")]
+#[must_use]
+pub fn posix_shell_command_line(args: &[String]) -> String {
+    ShellQuoted::quote_args(args).to_string()
+}
+
+/// Returns the `cmd` command line changing into the given directory and running the given program, as used for
+/// network share working directories, e.g. for fuzzing the quoting against a reference `CommandLineToArgvW`
+/// tokenizer.
+///
+/// # Returns
+/// `None` if the directory is verbatim, or anything contains a character `cmd` would interpret even when quoted
+/// (`"`, `%` or a line break).
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+///
+/// use terminal_relaunch::terminal_providers::cmd_pushd_command_line;
+///
+/// let args = [r"C:\out dir\".to_string(), "50%".to_string()];
+/// assert_eq!(
+///     cmd_pushd_command_line(Path::new(r"\\srv\share"), Path::new(r"C:\app.exe"), &args[..1]).as_deref(),
+///     Some(r#"pushd "\\srv\share" && "C:\app.exe" "C:\out dir\\""#),
+/// );
+/// assert_eq!(cmd_pushd_command_line(Path::new(r"\\srv\share"), Path::new(r"C:\app.exe"), &args), None);
+/// ```
+#[cfg(feature = "test-util")]
+#[must_use]
+pub fn cmd_pushd_command_line(dir: &Path, exe: &Path, args: &[String]) -> Option<String> {
+    pushd_command_line(dir, Some(&(exe.to_path_buf(), args.to_vec())))
+}
+
+/// `AppleScript` run by `osascript` to relaunch in `ITerm2`, the shell command is passed as its only argument.
+///
+/// # Notes
+/// The command is never embedded in the script source, `osascript` decodes arguments as `UTF-8`, so non-ASCII paths
+/// (e.g. `~/Проекты/демо`) reach `ITerm2` unchanged, and quotes in them need no `AppleScript` escaping.
+#[cfg(any(target_os = "macos", test))]
+const ITERM2_SCRIPT: &str = r#"
+on run argv
+    set wasRunning to application "iTerm" is running
+    tell application "iTerm"
+        activate
+        if wasRunning then
+            create window with default profile
+        end if
+        repeat 50 times
+            if (count of windows) > 0 then exit repeat
+            delay 0.1
+        end repeat
+        if (count of windows) > 0 then
+            tell current session of current window
+                write text (item 1 of argv)
+            end tell
+        else
+            error "no window" number 20
+        end if
+    end tell
+end run
+"#;
+
+/// Returns the shell command typed into a new `ITerm2` session, changing into the quoted working directory, and
+/// running the given program with the given variables exported, if any.
+#[cfg(any(target_os = "macos", test))]
+fn iterm2_command(
+    quoted_wd: &ShellQuoted,
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> ShellQuoted {
+    let Some((exe, args)) = program else {
+        return ShellQuoted(format!("cd {quoted_wd}"));
+    };
+
+    let quoted_exe = ShellQuoted::quote(&exe.to_string_lossy());
+    let quoted_args = ShellQuoted::quote_args(args);
+    let exports = export_statement(vars)
+        .map(|exports| format!("{exports}; "))
+        .unwrap_or_default();
+    ShellQuoted(format!(
+        "cd {quoted_wd}; {exports}exec {quoted_exe} {quoted_args}"
+    ))
+}
+
+/// Returns the `osascript` command typing the given shell command into a new `ITerm2` session.
+#[cfg(any(target_os = "macos", test))]
+fn iterm2_osascript_command(cmd: &ShellQuoted) -> Command {
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(ITERM2_SCRIPT).arg(cmd.as_str());
+    command
+}
+
+/// Terminal provider for `ITerm2`.
+#[derive(Debug, Default)]
+pub struct ITerm2Provider {
+    path: Resolved<PathBuf>,
+}
+
+/// Returns `"osascript"` if it can't be found in the `PATH` of the given environment, e.g. in minimal `MacOS`
+/// environments, see `TerminalProvider::missing_dependency()`.
+#[cfg(any(target_os = "macos", test))]
+fn missing_osascript_in(env: &(impl Environment + ?Sized)) -> Option<&'static str> {
+    find_executable_in("osascript", env)
+        .is_none()
+        .then_some("osascript")
+}
+
+impl TerminalProvider for ITerm2Provider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::ITerm2
+    }
+
+    fn is_installed(&self) -> bool {
+        self.installed_path().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.path
+            .get_or_resolve(|| for_target!("macos", { find_macos_app(TerminalType::ITerm2) }))
+            .cloned()
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.installed_path()
+            .as_deref()
+            .and_then(read_installed_version)
+    }
+
+    fn missing_dependency(&self) -> Option<&'static str> {
+        for_target!("macos", { missing_osascript_in(&SystemEnvironment) })
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        for_target!(self, "macos", {
+            if let Some(dependency) = self.missing_dependency() {
+                return Err(RelaunchError::MissingDependency(
+                    self.terminal_type(),
+                    dependency,
+                ));
+            }
+
+            let (program, curr_wd) = get_launch_params();
+
+            let CwdArg::Shell(quoted_wd) = cwd_argument(self.terminal_type(), &curr_wd)? else {
+                unreachable!("`ITerm2` working directories are always quoted for the shell");
+            };
+            // `iTerm2` sessions start with its own environment, so the propagated variables are exported..
+            let cmd = iterm2_command(
+                &quoted_wd,
+                program.as_ref(),
+                &crate::environment::propagated_env(),
+            );
+
+            let res = iterm2_osascript_command(&cmd).spawn()?.wait()?;
+
+            if res.success() {
+                Ok(())
+            } else {
+                crate::logging::error!("ITerm2 launch exited unsuccessfully!");
+                Err(RelaunchError::FailedToLaunchTerminal(
+                    self.terminal_type(),
+                    res.clone(),
+                ))
+            }
+        })
+    }
+}
+
+/// Terminal provider for `Ghostty`.
+#[derive(Debug, Default)]
+pub struct GhosttyProvider {
+    path: Resolved<PathBuf>,
+}
+
+impl TerminalProvider for GhosttyProvider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::Ghostty
     }
 
     fn is_installed(&self) -> bool {
-        for_target!("macos", {
-            std::path::Path::new("/Applications/WezTerm.app").exists()
+        self.installed_path().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.path
+            .get_or_resolve(|| for_target!("macos", { find_macos_app(TerminalType::Ghostty) }))
+            .cloned()
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.installed_path()
+            .as_deref()
+            .and_then(read_installed_version)
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        for_target!(self, "macos", {
+            let (program, curr_wd) = get_launch_params();
+            let ghostty = self
+                .installed_path()
+                .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+
+            open_app_command(
+                &ghostty,
+                &curr_wd,
+                program.as_ref(),
+                &crate::environment::propagated_env(),
+            )
+            .spawn()?;
+
+            Ok(())
         })
     }
+}
+
+/// Returns the `open` command relaunching the given program, or opening an interactive shell, in the given
+/// application bundle of `Ghostty` or `Alacritty`, which run programs given after `-e`.
+#[cfg(any(target_os = "macos", test))]
+fn open_app_command(
+    app: &Path,
+    curr_wd: &Path,
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> Command {
+    // `open` doesn't pass on its working directory, so a shell is told where to start..
+    let shell_cwd = CwdArg::Value(curr_wd.as_os_str().to_owned())
+        .flag("--working-directory")
+        .filter(|_| program.is_none());
+
+    let mut command = Command::new("open");
+    command
+        .arg("-na")
+        .arg(app)
+        .arg("--args")
+        .args(shell_cwd)
+        .args(program_args_with_env(&["-e"], program, vars))
+        .current_dir(curr_wd);
+    command
+}
+
+/// Returns the `kitten @ launch` arguments opening the given window mode in the current `Kitty` window, or `None`
+/// for a new window, which is opened by launching `Kitty` itself.
+#[cfg(any(target_os = "macos", test))]
+fn kitty_launch_args(mode: WindowMode) -> Option<&'static [&'static str]> {
+    match mode {
+        WindowMode::Window => None,
+        WindowMode::Tab => Some(&["--type=tab"]),
+        WindowMode::SplitHorizontal => Some(&["--type=window", "--location=hsplit"]),
+        WindowMode::SplitVertical => Some(&["--type=window", "--location=vsplit"]),
+    }
+}
+
+/// Terminal provider for `Kitty`.
+#[derive(Debug, Default)]
+pub struct KittyProvider {
+    path: Resolved<PathBuf>,
+}
+
+impl TerminalProvider for KittyProvider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::Kitty
+    }
+
+    fn supports_window_mode(&self, mode: WindowMode) -> bool {
+        // Tabs and splits are opened via remote control (`kitten @`), which must be run from inside `Kitty`..
+        mode == WindowMode::Window || std::env::var_os("KITTY_WINDOW_ID").is_some()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.installed_path().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.path
+            .get_or_resolve(|| {
+                for_target!("macos", {
+                    find_macos_app(TerminalType::Kitty).or_else(|| find_homebrew_binary("kitty"))
+                })
+            })
+            .cloned()
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.installed_path()
+            .as_deref()
+            .and_then(read_installed_version)
+    }
 
     fn relaunch_in_terminal(&self) -> TermResult<()> {
         for_target!(self, "macos", {
-            let (curr_exe, curr_wd, args) = get_relaunch_params();
-
-            Command::new("open")
-                .arg("-na")
-                .arg("WezTerm")
-                .arg("--args")
-                .arg("-e")
-                .arg(curr_exe)
-                .args(args)
-                .current_dir(curr_wd)
-                .spawn()?;
+            let (program, curr_wd) = get_launch_params();
+            let kitty = self
+                .installed_path()
+                .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+
+            let is_binary = kitty.is_file();
+            let mode = resolve_window_mode(self)?;
+            let cwd = cwd_argument(self.terminal_type(), &curr_wd)?;
+            kitty_command(
+                (&kitty, is_binary),
+                mode,
+                (&cwd, &curr_wd),
+                program.as_ref(),
+                &crate::environment::propagated_env(),
+            )
+            .spawn()?;
 
             Ok(())
         })
     }
 }
+
+/// Returns the command relaunching the given program, or opening an interactive shell, in `Kitty`: through
+/// `kitten @ launch` for a tab or split, by running the binary when it's installed as a formula rather than a
+/// cask (`is_binary`), or through `open` otherwise.
+#[cfg(any(target_os = "macos", test))]
+fn kitty_command(
+    (kitty, is_binary): (&Path, bool),
+    mode: WindowMode,
+    (cwd, curr_wd): (&CwdArg, &Path),
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> Command {
+    if let Some(launch_args) = kitty_launch_args(mode) {
+        let mut command = Command::new("kitten");
+        command
+            .args(["@", "launch"])
+            .args(launch_args)
+            .args(cwd.flag("--cwd"))
+            .args(program_args_with_env(&[], program, vars));
+        return command;
+    }
+
+    if is_binary {
+        let mut command = Command::new(kitty);
+        command
+            .args(cwd.flag("--directory"))
+            .args(program_args(&[], program))
+            .envs(vars.iter().cloned())
+            .current_dir(curr_wd);
+        return command;
+    }
+
+    // `open` doesn't pass on its working directory, so a shell is told where to start..
+    let mut command = Command::new("open");
+    command
+        .arg("-na")
+        .arg(kitty)
+        .arg("--args")
+        .args(cwd.flag("--directory").filter(|_| program.is_none()))
+        .args(program_args_with_env(&[], program, vars))
+        .current_dir(curr_wd);
+    command
+}
+
+/// Terminal provider for `Alacritty`.
+#[derive(Debug, Default)]
+pub struct AlacrittyProvider {
+    path: Resolved<PathBuf>,
+}
+
+impl TerminalProvider for AlacrittyProvider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::Alacritty
+    }
+
+    fn is_installed(&self) -> bool {
+        #[cfg(target_os = "macos")]
+        {
+            self.installed_path().is_some()
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("alacritty")
+                .arg("--version")
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            false
+        }
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.path
+            .get_or_resolve(|| for_target!("macos", { find_macos_app(TerminalType::Alacritty) }))
+            .cloned()
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.installed_path()
+            .as_deref()
+            .and_then(read_installed_version)
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+
+            let (program, curr_wd) = get_launch_params();
+
+            let program = program.map(|(curr_exe, args)| (strip_verbatim_prefix(&curr_exe), args));
+            let curr_wd = strip_verbatim_prefix(&curr_wd);
+
+            let cwd = cwd_argument(self.terminal_type(), &curr_wd)?;
+            alacritty_windows_command(cwd, program.as_ref(), &crate::environment::propagated_env())
+                .creation_flags(0x8 | 0x200)
+                .spawn()?;
+
+            Ok(())
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let (program, curr_wd) = get_launch_params();
+            let alacritty = self
+                .installed_path()
+                .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+
+            open_app_command(
+                &alacritty,
+                &curr_wd,
+                program.as_ref(),
+                &crate::environment::propagated_env(),
+            )
+            .spawn()?;
+
+            Ok(())
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            Err(RelaunchError::UnsupportedTerminalProvider(
+                self.terminal_type(),
+            ))
+        }
+    }
+}
+
+/// Returns the `cmd` command relaunching the given program, or opening an interactive shell, in `Alacritty` on
+/// `Windows`, in the directory given by `cwd_argument()`.
+#[cfg(any(target_os = "windows", test))]
+fn alacritty_windows_command(
+    cwd: CwdArg,
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> Command {
+    let mut command = Command::new("cmd");
+    // `cmd` can't use a network share as its current directory, so `Alacritty` changes into it instead..
+    match cwd {
+        CwdArg::CurrentDir(dir) => {
+            command.current_dir(dir).arg("/C").arg("alacritty");
+        }
+        cwd => {
+            command
+                .arg("/C")
+                .arg("alacritty")
+                .args(cwd.flag("--working-directory"));
+        }
+    }
+
+    command
+        .args(program_args(&["-e"], program))
+        .envs(vars.iter().cloned());
+    command
+}
+
+/// Returns the `wezterm` arguments opening the given window mode next to the current `WezTerm` pane, or `None` for
+/// a new window, which is opened by launching `WezTerm` itself.
+#[cfg(any(target_os = "macos", test))]
+fn wezterm_cli_args(mode: WindowMode) -> Option<&'static [&'static str]> {
+    match mode {
+        WindowMode::Window => None,
+        WindowMode::Tab => Some(&["cli", "spawn"]),
+        WindowMode::SplitHorizontal => Some(&["cli", "split-pane", "--bottom"]),
+        WindowMode::SplitVertical => Some(&["cli", "split-pane", "--right"]),
+    }
+}
+
+/// Terminal provider for `WezTerm`.
+#[derive(Debug, Default)]
+pub struct WezTermProvider {
+    path: Resolved<PathBuf>,
+}
+
+impl TerminalProvider for WezTermProvider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::WezTerm
+    }
+
+    fn supports_window_mode(&self, mode: WindowMode) -> bool {
+        // Tabs and splits are opened via `wezterm cli`, which must be run from inside `WezTerm`..
+        mode == WindowMode::Window || std::env::var_os("WEZTERM_PANE").is_some()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.installed_path().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.path
+            .get_or_resolve(|| {
+                for_target!("macos", {
+                    find_macos_app(TerminalType::WezTerm)
+                        .or_else(|| find_homebrew_binary("wezterm"))
+                })
+            })
+            .cloned()
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.installed_path()
+            .as_deref()
+            .and_then(read_installed_version)
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        for_target!(self, "macos", {
+            let (program, curr_wd) = get_launch_params();
+            let wezterm = self
+                .installed_path()
+                .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+
+            let is_binary = wezterm.is_file();
+            let mode = resolve_window_mode(self)?;
+            let cwd = cwd_argument(self.terminal_type(), &curr_wd)?;
+            let wezterm_cli =
+                std::env::var_os("WEZTERM_EXECUTABLE").unwrap_or_else(|| "wezterm".into());
+            wezterm_command(
+                (&wezterm, is_binary, &wezterm_cli),
+                mode,
+                (&cwd, &curr_wd),
+                program.as_ref(),
+                &crate::environment::propagated_env(),
+            )
+            .spawn()?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Returns the command relaunching the given program, or opening an interactive shell, in `WezTerm`: through
+/// `wezterm cli` (run as `wezterm_cli`) for a tab or split, by running the binary when it's installed as a
+/// formula rather than a cask (`is_binary`), or through `open` otherwise.
+#[cfg(any(target_os = "macos", test))]
+fn wezterm_command(
+    (wezterm, is_binary, wezterm_cli): (&Path, bool, &std::ffi::OsStr),
+    mode: WindowMode,
+    (cwd, curr_wd): (&CwdArg, &Path),
+    program: Option<&LaunchProgram>,
+    vars: &[(&str, String)],
+) -> Command {
+    if let Some(cli_command) = wezterm_cli_args(mode) {
+        let mut command = Command::new(wezterm_cli);
+        command
+            .args(cli_command)
+            .args(cwd.flag("--cwd"))
+            .args(program_args_with_env(&["--"], program, vars));
+        return command;
+    }
+
+    if is_binary {
+        let mut command = Command::new(wezterm);
+        command
+            .arg("start")
+            .args(cwd.flag("--cwd"))
+            .args(program_args(&["--"], program))
+            .envs(vars.iter().cloned())
+            .current_dir(curr_wd);
+        return command;
+    }
+
+    // `open` doesn't pass on its working directory, so a shell is started with `start --cwd`..
+    let shell_start = program.is_none().then_some("start");
+    let mut command = Command::new("open");
+    command
+        .arg("-na")
+        .arg(wezterm)
+        .arg("--args")
+        .args(shell_start)
+        .args(cwd.flag("--cwd").filter(|_| program.is_none()))
+        .args(program_args_with_env(&["-e"], program, vars))
+        .current_dir(curr_wd);
+    command
+}
+
+/// `Flatpak` application id of `Ptyxis`, as published on `Flathub`.
+pub const PTYXIS_FLATPAK_ID: &str = "app.devsuite.Ptyxis";
+
+/// How `Ptyxis` is launched, see `PtyxisLauncher::find()`.
+///
+/// # Example
+/// ```rust
+/// # #[cfg(unix)] {
+/// use std::collections::HashMap;
+/// use std::ffi::OsString;
+/// use std::os::unix::fs::PermissionsExt;
+///
+/// use terminal_relaunch::config::WindowMode;
+/// use terminal_relaunch::terminal_providers::{CwdArg, PTYXIS_FLATPAK_ID, PtyxisLauncher};
+///
+/// let bin = std::env::temp_dir().join(format!("ptyxis-launcher-{}", std::process::id()));
+/// std::fs::create_dir_all(&bin).unwrap();
+/// let install = |name: &str, script: &str| {
+///     let path = bin.join(name);
+///     std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+///     std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+///     path
+/// };
+/// let env = HashMap::from([("PATH".to_string(), bin.display().to_string())]);
+///
+/// // Neither installed, then `flatpak` without `Ptyxis`..
+/// assert_eq!(PtyxisLauncher::find_in(&env), None);
+/// let flatpak = install("flatpak", "exit 1");
+/// assert_eq!(PtyxisLauncher::find_in(&env), None);
+///
+/// // The `Flathub` release, unless a native install is found..
+/// install("flatpak", &format!("[ \"$1\" = info ] && [ \"$2\" = {PTYXIS_FLATPAK_ID} ]"));
+/// let flatpak = PtyxisLauncher::Flatpak(flatpak);
+/// assert_eq!(PtyxisLauncher::find_in(&env), Some(flatpak.clone()));
+/// let native = PtyxisLauncher::Native(install("ptyxis", "exit 0"));
+/// assert_eq!(PtyxisLauncher::find_in(&env), Some(native.clone()));
+///
+/// // The planned command in each window mode..
+/// let cwd = CwdArg::Value(OsString::from("/home/me/my project"));
+/// let program = [OsString::from("/usr/bin/app"), OsString::from("--flag")];
+/// let args = |launcher: &PtyxisLauncher, mode, program: &[OsString]| {
+///     let command = launcher.command(mode, &cwd, program);
+///     command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect::<Vec<_>>()
+/// };
+/// for (mode, flag) in [
+///     (WindowMode::Window, "--new-window"),
+///     (WindowMode::Tab, "--tab"),
+///     (WindowMode::SplitHorizontal, "--new-window"),
+///     (WindowMode::SplitVertical, "--new-window"),
+/// ] {
+///     let expected = [flag, "--working-directory=/home/me/my project", "--", "/usr/bin/app", "--flag"];
+///     assert_eq!(args(&native, mode, &program), expected);
+///     assert_eq!(args(&flatpak, mode, &program)[..2], ["run", PTYXIS_FLATPAK_ID]);
+///     assert_eq!(args(&flatpak, mode, &program)[2..], expected);
+/// }
+/// assert_eq!(native.command(WindowMode::Window, &cwd, &program).get_program(), bin.join("ptyxis"));
+///
+/// // An interactive shell..
+/// assert_eq!(args(&native, WindowMode::Tab, &[]), ["--tab", "--working-directory=/home/me/my project"]);
+///
+/// std::fs::remove_dir_all(&bin).unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PtyxisLauncher {
+    /// The `ptyxis` executable, e.g. installed by the distribution.
+    Native(PathBuf),
+    /// `flatpak run`, with the path of the `flatpak` executable, see `PTYXIS_FLATPAK_ID`.
+    Flatpak(PathBuf),
+}
+
+impl PtyxisLauncher {
+    /// Finds how to launch `Ptyxis`, see `find_in()`.
+    #[inline]
+    #[must_use]
+    pub fn find() -> Option<Self> {
+        Self::find_in(&SystemEnvironment)
+    }
+
+    /// Finds how to launch `Ptyxis`, with the `PATH` of the given environment.
+    ///
+    /// # Returns
+    /// *   `PtyxisLauncher::Native` if `ptyxis` is found.
+    /// *   `PtyxisLauncher::Flatpak` if `flatpak` is found, and reports `PTYXIS_FLATPAK_ID` as installed.
+    /// *   `None` otherwise.
+    #[must_use]
+    pub fn find_in(env: &(impl Environment + ?Sized)) -> Option<Self> {
+        if let Some(ptyxis) = find_executable_in("ptyxis", env) {
+            return Some(Self::Native(ptyxis));
+        }
+
+        let flatpak = find_executable_in("flatpak", env)?;
+        Command::new(&flatpak)
+            .args(["info", PTYXIS_FLATPAK_ID])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+            .then_some(Self::Flatpak(flatpak))
+    }
+
+    /// Returns the command opening `Ptyxis` in the given window mode and working directory, running the given
+    /// program and its arguments, or an interactive shell if empty.
+    ///
+    /// # Notes
+    /// Tabs are opened in a running instance if there is one, `Ptyxis` has no splits, so they open a new window.
+    #[must_use]
+    pub fn command(&self, mode: WindowMode, cwd: &CwdArg, program: &[OsString]) -> Command {
+        let mut command = match self {
+            Self::Native(ptyxis) => Command::new(ptyxis),
+            Self::Flatpak(flatpak) => {
+                let mut command = Command::new(flatpak);
+                command.args(["run", PTYXIS_FLATPAK_ID]);
+                command
+            }
+        };
+
+        command.arg(if mode == WindowMode::Tab {
+            "--tab"
+        } else {
+            "--new-window"
+        });
+        command.args(cwd.flag("--working-directory"));
+        if !program.is_empty() {
+            command.arg("--").args(program);
+        }
+
+        command
+    }
+}
+
+/// Terminal provider for `Ptyxis`.
+#[derive(Debug, Default)]
+pub struct PtyxisProvider {
+    launcher: Resolved<PtyxisLauncher>,
+}
+
+impl PtyxisProvider {
+    /// Returns the launcher found when first checked, see `Resolved`.
+    fn launcher(&self) -> Option<&PtyxisLauncher> {
+        self.launcher
+            .get_or_resolve(|| for_target!("linux", { PtyxisLauncher::find() }))
+    }
+}
+
+impl TerminalProvider for PtyxisProvider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::Ptyxis
+    }
+
+    fn supports_window_mode(&self, mode: WindowMode) -> bool {
+        !mode.is_split()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.launcher().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        match self.launcher() {
+            Some(PtyxisLauncher::Native(ptyxis)) => Some(ptyxis.clone()),
+            _ => None,
+        }
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        self.installed_path()
+            .as_deref()
+            .and_then(read_installed_version)
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        for_target!(self, "linux", {
+            let (program, curr_wd) = get_launch_params();
+            let launcher = self
+                .launcher()
+                .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+
+            let cwd = cwd_argument(self.terminal_type(), &curr_wd)?;
+            launcher
+                .command(
+                    resolve_window_mode(self)?,
+                    &cwd,
+                    &program_args(&[], program.as_ref()),
+                )
+                .envs(crate::environment::propagated_env())
+                .current_dir(curr_wd)
+                .spawn()?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Path of the `Hyper` executable installed by its `Linux` packages.
+pub const HYPER_LINUX_PATH: &str = "/opt/Hyper/hyper";
+
+/// Terminal provider for `Hyper`.
+///
+/// # Notes
+/// `Hyper` opens a window in a directory, through its `hyper` CLI or its application, but can't be told to run a
+/// program in it, so it only opens interactive shells (see `open_terminal_here()`). Relaunches are reported as
+/// `UnfitReason::CannotHostCommand`, so the next preferred terminal is tried rather than opening an empty window.
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::TerminalProvider;
+/// use terminal_relaunch::pipeline::UnfitReason;
+/// use terminal_relaunch::terminal_providers::HyperProvider;
+///
+/// assert_eq!(HyperProvider::default().unfit_reason(), Some(UnfitReason::CannotHostCommand));
+/// ```
+#[derive(Debug, Default)]
+pub struct HyperProvider {
+    path: Resolved<PathBuf>,
+}
+
+impl HyperProvider {
+    /// Returns the path of the `hyper` CLI, if found on the `PATH`.
+    fn cli() -> Option<PathBuf> {
+        find_executable(if cfg!(target_os = "windows") {
+            "hyper.cmd"
+        } else {
+            "hyper"
+        })
+    }
+
+    /// Finds the installed `Hyper`, the application or its executable.
+    fn find() -> Option<PathBuf> {
+        #[cfg(target_os = "macos")]
+        {
+            find_macos_app(TerminalType::Hyper).or_else(Self::cli)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            std::env::var_os("LOCALAPPDATA")
+                .map(|dir| {
+                    PathBuf::from(dir)
+                        .join("Programs")
+                        .join("Hyper")
+                        .join("Hyper.exe")
+                })
+                .filter(|path| path.is_file())
+                .or_else(Self::cli)
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            Self::cli().or_else(|| {
+                Some(PathBuf::from(HYPER_LINUX_PATH)).filter(|path| is_executable(path))
+            })
+        }
+    }
+}
+
+impl TerminalProvider for HyperProvider {
+    fn terminal_type(&self) -> TerminalType {
+        TerminalType::Hyper
+    }
+
+    fn is_installed(&self) -> bool {
+        self.installed_path().is_some()
+    }
+
+    fn installed_path(&self) -> Option<PathBuf> {
+        self.path.get_or_resolve(Self::find).cloned()
+    }
+
+    fn installed_version(&self) -> Option<TerminalVersion> {
+        // Only read from the application bundle, as the executable opens a window rather than printing its version..
+        self.installed_path()
+            .filter(|path| path.is_dir())
+            .as_deref()
+            .and_then(read_installed_version)
+    }
+
+    fn unfit_reason(&self) -> Option<UnfitReason> {
+        launches_program().then_some(UnfitReason::CannotHostCommand)
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        let (program, curr_wd) = get_launch_params();
+        if program.is_some() {
+            return Err(RelaunchError::UnfitTerminal(
+                self.terminal_type(),
+                UnfitReason::CannotHostCommand,
+            ));
+        }
+        let hyper = self
+            .installed_path()
+            .ok_or_else(|| not_installed_error(self.terminal_type()))?;
+
+        hyper_command(&hyper, &curr_wd, &crate::environment::propagated_env()).spawn()?;
+
+        Ok(())
+    }
+}
+
+/// Returns the command opening an interactive shell in `Hyper`, through `open` for its application bundle or by
+/// running its executable otherwise.
+fn hyper_command(hyper: &Path, curr_wd: &Path, vars: &[(&str, String)]) -> Command {
+    // The CLI and the application both open their window in the directory they are given..
+    let mut command = if hyper.is_dir() {
+        let mut open = Command::new("open");
+        open.arg("-na").arg(hyper).arg("--args");
+        open
+    } else {
+        Command::new(hyper)
+    };
+    command
+        .arg(curr_wd)
+        .envs(vars.iter().cloned())
+        .current_dir(curr_wd);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::ffi::{OsStr, OsString};
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        APP_DIRS_VAR, CwdArg, LaunchProgram, PTYXIS_FLATPAK_ID, PtyxisLauncher, PtyxisProvider,
+        ShellQuoted, TERMINAL_IDENTIFIERS, WINDOWS_MAX_PATH, WINDOWS_TERMINAL_PREVIEW_PACKAGE,
+        WINDOWS_TERMINAL_PROCESS, WindowsTerminalChannel, WindowsTerminalInstall,
+        alacritty_windows_command, cwd_argument, find_app_in_dirs, find_binary_in_prefixes,
+        homebrew_prefixes_in, hyper_command, is_unc_path, iterm2_command, iterm2_osascript_command,
+        kitty_command, kitty_launch_args, macos_app_search_paths_in, macos_bundle_name,
+        missing_osascript_in, open_app_command, pushd_command_line, resolve_window_mode,
+        strip_verbatim_prefix, wezterm_cli_args, wezterm_command, with_window_mode, wt_command,
+        wt_geometry_args, wt_window_args, wt_window_target,
+    };
+    use crate::config::{
+        RelaunchConfig, WindowGeometry, WindowMode, WtWindowTarget, relaunch_config,
+        set_relaunch_config,
+    };
+    use crate::decision::{RelaunchDecision, SkipReason};
+    use crate::errors::{RelaunchError, TermResult};
+    use crate::simulation::{Scenario, SimulationOutcome, simulate};
+    use crate::test_support::{reading_globals, writing_globals};
+    use crate::{OperatingSystem, TerminalProvider, TerminalType};
+
+    /// Simulates the scenario, holding the global state steady for the capabilities.
+    fn outcome(scenario: &Scenario) -> SimulationOutcome {
+        let _globals = reading_globals();
+        simulate(scenario)
+    }
+
+    fn linux(vars: &[(&str, &str)]) -> Scenario {
+        vars.iter().fold(
+            Scenario::new(OperatingSystem::Linux),
+            |scenario, (name, value)| scenario.var(name, value),
+        )
+    }
+
+    #[test]
+    fn wezterm_is_found_without_term_program() {
+        assert_eq!(
+            outcome(&linux(&[("TERM_PROGRAM", "WezTerm")])).terminal,
+            TerminalType::WezTerm
+        );
+        assert_eq!(
+            outcome(&linux(&[("WEZTERM_PANE", "0")])).terminal,
+            TerminalType::WezTerm
+        );
+    }
+
+    #[test]
+    fn hyper_is_found_from_either_variable_on_every_platform() {
+        for os in [
+            OperatingSystem::Windows,
+            OperatingSystem::MacOS,
+            OperatingSystem::Linux,
+        ] {
+            let term_program = Scenario::new(os).var("TERM_PROGRAM", "Hyper");
+            let version_only = Scenario::new(os).var("HYPER_VERSION", "3.4.1");
+            assert_eq!(outcome(&term_program).terminal, TerminalType::Hyper, "{os}");
+            assert_eq!(outcome(&version_only).terminal, TerminalType::Hyper, "{os}");
+            assert!(!outcome(&version_only).decision.should_attempt());
+        }
+    }
+
+    #[test]
+    fn tabby_wins_over_platform_fallbacks() {
+        let tabby_windows = Scenario::windows_cmd_with_wt_installed().var(
+            "TABBY_CONFIG_DIRECTORY",
+            r"C:\Users\me\AppData\Roaming\tabby",
+        );
+        let tabby_macos =
+            Scenario::macos_apple_terminal_no_thirdparty().var("TERM_PROGRAM", "Tabby");
+        let tabby_other = Scenario::new(OperatingSystem::MacOS).var("TERM_PROGRAM", "Tabby");
+        for tabby in [tabby_windows, tabby_macos, tabby_other] {
+            let tabby = outcome(&tabby);
+            assert_eq!(tabby.terminal, TerminalType::Tabby);
+            assert!(tabby.full_unicode && tabby.rgb_ansi_colours);
+        }
+    }
+
+    #[test]
+    fn contour_is_found_from_any_of_its_variables() {
+        for (name, value) in [
+            ("TERMINAL_NAME", "contour"),
+            ("TERMINAL_VERSION_STRING", "0.4.3.6442"),
+            ("TERM", "contour"),
+        ] {
+            let contour = outcome(&linux(&[(name, value)]));
+            assert_eq!(contour.terminal, TerminalType::Contour, "{name}");
+            assert!(contour.full_unicode && contour.rgb_ansi_colours);
+        }
+        assert_eq!(
+            outcome(&linux(&[("TERMINAL_NAME", "other")])).terminal,
+            TerminalType::LinuxTerminal
+        );
+    }
+
+    #[test]
+    fn tmux_is_reported_only_without_inherited_terminal_variables() {
+        let tmux = linux(&[
+            ("TMUX", "/tmp/tmux-1000/default,1234,0"),
+            ("TERM", "tmux-256color"),
+        ]);
+        assert_eq!(
+            outcome(&tmux.clone().var("WEZTERM_PANE", "0")).terminal,
+            TerminalType::WezTerm
+        );
+        let tmux = outcome(&tmux);
+        assert_eq!(tmux.terminal, TerminalType::Tmux);
+        assert!(tmux.full_unicode && !tmux.rgb_ansi_colours);
+    }
+
+    #[test]
+    fn term_program_paths_and_padding_are_normalised() {
+        let iterm = Scenario::new(OperatingSystem::MacOS).var(
+            "TERM_PROGRAM",
+            "/Applications/iTerm.app/Contents/MacOS/iTerm2",
+        );
+        assert_eq!(outcome(&iterm).terminal, TerminalType::ITerm2);
+        let apple = Scenario::new(OperatingSystem::MacOS).var("TERM_PROGRAM", "Apple_Terminal ");
+        assert_eq!(outcome(&apple).terminal, TerminalType::MacOS);
+    }
+
+    #[test]
+    fn a_single_pair_of_matching_quotes_is_stripped() {
+        for (name, value, terminal) in [
+            ("TERM_PROGRAM", "\"vscode\"", TerminalType::VSCode),
+            ("TERM_PROGRAM", "'WezTerm'", TerminalType::WezTerm),
+            (
+                "TERMINAL_EMULATOR",
+                "\"JetBrains-JediTerm\"",
+                TerminalType::JetBrains,
+            ),
+            ("TERM", "'contour'", TerminalType::Contour),
+            ("TERM", "\"foot-extra\"", TerminalType::Foot),
+        ] {
+            assert_eq!(
+                outcome(&linux(&[(name, value)])).terminal,
+                terminal,
+                "{name}={value}"
+            );
+        }
+        for value in ["\"vscode'", "\"\"vscode\"\"", "\"vscode"] {
+            assert_eq!(
+                outcome(&linux(&[("TERM_PROGRAM", value)])).terminal,
+                TerminalType::LinuxTerminal,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn vte_terminals_win_over_the_generic_fallback() {
+        let gnome = linux(&[("GNOME_TERMINAL_SCREEN", "/org/gnome/Terminal/screen/0")]);
+        assert_eq!(outcome(&gnome).terminal, TerminalType::GnomeTerminal);
+        assert_eq!(outcome(&linux(&[])).terminal, TerminalType::LinuxTerminal);
+
+        let tilix = gnome
+            .clone()
+            .var("TILIX_ID", "7f0c2b9e-2d4b-4f0e-9b1e-2c8a5b3f6d10");
+        assert_eq!(outcome(&tilix).terminal, TerminalType::Tilix);
+
+        let terminator = gnome.var(
+            "TERMINATOR_UUID",
+            "urn:uuid:0b5e2c1a-7d4e-4f3a-9c2b-1e6d8f0a3b47",
+        );
+        assert_eq!(outcome(&terminator).terminal, TerminalType::Terminator);
+        let terminator = linux(&[("TERMINATOR_DBUS_NAME", "net.tenshu.Terminator2")]);
+        assert_eq!(outcome(&terminator).terminal, TerminalType::Terminator);
+    }
+
+    #[test]
+    fn xfce_terminal_is_found_from_its_defaults_on_xfce() {
+        let xfce = linux(&[("XDG_CURRENT_DESKTOP", "XFCE"), ("TERM", "xterm-256color")]);
+        assert_eq!(outcome(&xfce).terminal, TerminalType::LinuxTerminal);
+        assert_eq!(
+            outcome(&xfce.var("COLORTERM", "truecolor")).terminal,
+            TerminalType::XfceTerminal
+        );
+    }
+
+    #[test]
+    fn foot_is_matched_by_its_exact_term_or_prefix_only() {
+        for term in ["foot", "foot-extra"] {
+            assert_eq!(
+                outcome(&linux(&[("TERM", term)])).terminal,
+                TerminalType::Foot,
+                "{term}"
+            );
+        }
+        for term in ["footx", "football-256color"] {
+            assert_eq!(
+                outcome(&linux(&[("TERM", term)])).terminal,
+                TerminalType::LinuxTerminal,
+                "{term}"
+            );
+        }
+    }
+
+    #[test]
+    fn urxvt_is_not_the_fully_capable_fallback() {
+        let urxvt = outcome(&linux(&[("TERM", "rxvt-unicode-256color")]));
+        assert_eq!(urxvt.terminal, TerminalType::Urxvt);
+        assert!(!urxvt.full_unicode && !urxvt.rgb_ansi_colours);
+        assert!(urxvt.decision.should_attempt());
+    }
+
+    #[test]
+    fn st_is_matched_by_its_exact_term_or_prefix_only() {
+        for term in ["st", "st-256color"] {
+            let st = linux(&[("TERM", term)]);
+            assert_eq!(outcome(&st).terminal, TerminalType::St, "{term}");
+            assert!(!outcome(&st).rgb_ansi_colours);
+            assert!(outcome(&st.var("COLORTERM", "truecolor")).rgb_ansi_colours);
+        }
+        assert_eq!(
+            outcome(&linux(&[("TERM", "stterm-256color")])).terminal,
+            TerminalType::LinuxTerminal
+        );
+    }
+
+    #[test]
+    fn conemu_is_only_capable_with_ansi_processing() {
+        let conemu = Scenario::windows_cmd_with_wt_installed()
+            .var("ConEmuPID", "4242")
+            .var("ConEmuBuild", "230724");
+        let plain = outcome(&conemu);
+        assert_eq!(plain.terminal, TerminalType::ConEmu);
+        assert!(plain.full_unicode && !plain.rgb_ansi_colours);
+        assert!(plain.decision.should_attempt());
+
+        let ansi = outcome(
+            &conemu
+                .var("ConEmuANSI", "ON")
+                .var("CMDER_ROOT", r"C:\tools\cmder"),
+        );
+        assert!(ansi.rgb_ansi_colours);
+        assert!(!ansi.decision.should_attempt());
+    }
+
+    #[test]
+    fn mintty_keeps_its_msys_environment() {
+        let git_bash = Scenario::windows_cmd_with_wt_installed()
+            .var("MSYSTEM", "MINGW64")
+            .var("TERM", "xterm-256color");
+        let mintty = git_bash
+            .clone()
+            .var("TERM_PROGRAM", "mintty")
+            .var("TERM_PROGRAM_VERSION", "3.7.4");
+        for scenario in [&mintty, &git_bash] {
+            let mintty = outcome(scenario);
+            assert_eq!(mintty.terminal, TerminalType::Mintty);
+            assert!(mintty.full_unicode && mintty.rgb_ansi_colours);
+            assert!(!mintty.decision.should_attempt());
+        }
+        // `MSYS` shells in other terminals are still detected as those terminals..
+        assert_eq!(
+            outcome(&git_bash.clone().var("ConEmuPID", "4242")).terminal,
+            TerminalType::ConEmu
+        );
+        assert_eq!(
+            outcome(&git_bash.var("TERM_PROGRAM", "vscode")).terminal,
+            TerminalType::VSCode
+        );
+    }
+
+    #[test]
+    fn ptyxis_wins_over_vte_and_is_preferred_on_gnome() {
+        let ptyxis = linux(&[
+            ("PTYXIS_VERSION", "47.6"),
+            ("VTE_VERSION", "7800"),
+            ("GNOME_TERMINAL_SCREEN", "/org/gnome/Terminal/screen/0"),
+        ]);
+        assert_eq!(outcome(&ptyxis).terminal, TerminalType::Ptyxis);
+        assert!(!outcome(&ptyxis).decision.should_attempt());
+
+        let urxvt = linux(&[
+            ("TERM", "rxvt-unicode-256color"),
+            ("XDG_CURRENT_DESKTOP", "GNOME"),
+        ])
+        .installed(TerminalType::Alacritty)
+        .installed(TerminalType::Ptyxis);
+        assert_eq!(outcome(&urxvt).provider, Some(TerminalType::Ptyxis));
+    }
+
+    #[test]
+    fn gnu_screen_wins_over_its_terminal_only_with_its_term() {
+        let screen = outcome(&linux(&[
+            ("STY", "1234.pts-0.host"),
+            ("TERM", "screen.xterm-256color"),
+            ("COLORTERM", "truecolor"),
+            ("TERM_PROGRAM", "WezTerm"),
+        ]));
+        assert_eq!(screen.terminal, TerminalType::GnuScreen);
+        assert!(!screen.full_unicode && !screen.rgb_ansi_colours);
+        assert!(screen.decision.should_attempt());
+
+        let leaked = linux(&[("STY", "1234.pts-0.host"), ("TERM", "xterm-256color")]);
+        assert_eq!(outcome(&leaked).terminal, TerminalType::LinuxTerminal);
+    }
+
+    #[test]
+    fn emacs_wins_and_is_only_relaunched_from_when_allowed() {
+        let vterm = linux(&[
+            ("INSIDE_EMACS", "vterm"),
+            ("EMACS_VTERM_PATH", "/usr/share/emacs/site-lisp/vterm"),
+            ("TERM", "xterm-256color"),
+            ("TERM_PROGRAM", "WezTerm"),
+        ])
+        .installed(TerminalType::Alacritty);
+        let vterm = outcome(&vterm);
+        assert_eq!(vterm.terminal, TerminalType::Emacs);
+        assert!(vterm.full_unicode && vterm.rgb_ansi_colours);
+
+        let term = linux(&[("INSIDE_EMACS", "29.1,term:0.96"), ("TERM", "eterm-color")])
+            .installed(TerminalType::Alacritty);
+        assert!(!outcome(&term).rgb_ansi_colours);
+        assert_eq!(
+            outcome(&term).decision,
+            RelaunchDecision::Skip(SkipReason::InsideEmacs)
+        );
+        let allowed = term.config(RelaunchConfig::new().allow_emacs_relaunch(true));
+        assert_eq!(outcome(&allowed).provider, Some(TerminalType::Alacritty));
+
+        let eat = linux(&[("INSIDE_EMACS", "29.1,eat"), ("TERM", "screen")]);
+        assert_eq!(outcome(&eat).terminal, TerminalType::Emacs);
+    }
+
+    #[test]
+    fn jupyter_is_never_relaunched_from() {
+        for (name, value) in [
+            ("JPY_PARENT_PID", "4242"),
+            ("JUPYTER_SERVER_ROOT", "/home/me/notebooks"),
+        ] {
+            let jupyter = linux(&[
+                (name, value),
+                ("TERM", "xterm-256color"),
+                ("TERM_PROGRAM", "WezTerm"),
+            ])
+            .installed(TerminalType::Alacritty);
+            let jupyter = outcome(&jupyter);
+            assert_eq!(jupyter.terminal, TerminalType::Jupyter, "{name}");
+            assert!(!jupyter.full_unicode && jupyter.rgb_ansi_colours);
+            assert_eq!(
+                jupyter.decision,
+                RelaunchDecision::Skip(SkipReason::RemoteSession)
+            );
+            assert_eq!(jupyter.provider, None);
+        }
+    }
+
+    #[test]
+    fn jetbrains_is_capable_whatever_it_was_started_from() {
+        for value in ["JetBrains-JediTerm", "jetbrains-jediterm"] {
+            let jetbrains = Scenario::macos_apple_terminal_no_thirdparty()
+                .var("TERMINAL_EMULATOR", value)
+                .var("TERM", "xterm-256color")
+                .installed(TerminalType::Ghostty);
+            let jetbrains = outcome(&jetbrains);
+            assert_eq!(jetbrains.terminal, TerminalType::JetBrains);
+            assert!(jetbrains.full_unicode && jetbrains.rgb_ansi_colours);
+            assert!(!jetbrains.decision.should_attempt());
+        }
+    }
+
+    #[test]
+    fn zed_is_preferred_on_every_platform() {
+        assert!(TerminalType::Zed.is_preferred());
+        for os in [
+            OperatingSystem::Windows,
+            OperatingSystem::MacOS,
+            OperatingSystem::Linux,
+        ] {
+            let zed = Scenario::new(os)
+                .var("TERM_PROGRAM", "zed")
+                .var("TERM", "xterm-256color")
+                .installed(TerminalType::Alacritty);
+            let zed = outcome(&zed);
+            assert_eq!(zed.terminal, TerminalType::Zed, "{os}");
+            assert!(zed.full_unicode && zed.rgb_ansi_colours);
+            assert_eq!(
+                zed.decision,
+                RelaunchDecision::Skip(SkipReason::AlreadyPreferred)
+            );
+        }
+    }
+
+    #[test]
+    fn xterm_needs_colorterm_for_rgb_colours() {
+        let xterm = linux(&[("XTERM_VERSION", "XTerm(379)")]);
+        assert!(!outcome(&xterm).rgb_ansi_colours);
+        assert!(outcome(&xterm.var("COLORTERM", "truecolor")).rgb_ansi_colours);
+    }
+
+    /// The functions allowed to construct a `ShellQuoted` directly, each only from quoted values.
+    const SHELL_QUOTED_CONSTRUCTORS: &[&str] =
+        &["quote", "quote_args", "export_statement", "iterm2_command"];
+
+    #[test]
+    fn shell_quoted_is_only_constructed_by_the_allowed_functions() {
+        let source = include_str!("terminal_providers.rs");
+        let source = &source[..source.find("#[cfg(test)]\nmod tests").unwrap()];
+
+        let mut current_fn = None;
+        let mut constructors = std::collections::BTreeSet::new();
+        for line in source.lines().map(str::trim_start) {
+            if line.starts_with("//") {
+                continue;
+            }
+            if let Some(name) = line.split("fn ").nth(1).filter(|_| line.contains("fn ")) {
+                current_fn = name.split(['(', '<']).next();
+            }
+            let constructs = line.contains("ShellQuoted(") || line.contains("Self(");
+            if constructs && !line.starts_with("pub struct ShellQuoted(") {
+                if line.contains("Self(") && !matches!(current_fn, Some("quote" | "quote_args")) {
+                    continue;
+                }
+                constructors.insert(current_fn.expect("constructed outside of a function"));
+            }
+        }
+
+        assert_eq!(
+            constructors,
+            SHELL_QUOTED_CONSTRUCTORS.iter().copied().collect()
+        );
+    }
+
+    #[test]
+    fn iterm2_command_quotes_every_value() {
+        use std::path::PathBuf;
+
+        let wd = ShellQuoted::quote("/tmp/it's; rm -rf ~");
+        let program = (
+            PathBuf::from("/opt/my app/bin"),
+            vec!["$(id)".to_string(), "a'b".to_string(), String::new()],
+        );
+        assert_eq!(
+            iterm2_command(&wd, None, &[]).as_str(),
+            r"cd '/tmp/it'\''s; rm -rf ~'"
+        );
+        assert_eq!(
+            iterm2_command(
+                &wd,
+                Some(&program),
+                &[("LANG", "C".to_string()), ("BAD NAME", "x".to_string())]
+            )
+            .as_str(),
+            r"cd '/tmp/it'\''s; rm -rf ~'; export LANG='C'; exec '/opt/my app/bin' '$(id)' 'a'\''b' ''"
+        );
+    }
+
+    #[test]
+    fn homebrew_prefix_var_comes_first_followed_by_both_defaults() {
+        let defaults = [PathBuf::from("/opt/homebrew"), PathBuf::from("/usr/local")];
+        let env =
+            |prefix: &str| HashMap::from([("HOMEBREW_PREFIX".to_string(), prefix.to_string())]);
+
+        assert_eq!(
+            homebrew_prefixes_in(&HashMap::<String, String>::new()),
+            defaults
+        );
+        assert_eq!(homebrew_prefixes_in(&env("")), defaults);
+        assert_eq!(
+            homebrew_prefixes_in(&env("/Users/me/brew")),
+            [
+                PathBuf::from("/Users/me/brew"),
+                defaults[0].clone(),
+                defaults[1].clone()
+            ]
+        );
+        // A default isn't searched twice..
+        assert_eq!(
+            homebrew_prefixes_in(&env("/usr/local")),
+            [defaults[1].clone(), defaults[0].clone()]
+        );
+    }
+
+    #[test]
+    fn homebrew_binaries_are_found_in_whichever_prefix_has_them() {
+        let root = crate::test_support::temp_dir("homebrew_binaries");
+        let prefixes = [root.join("opt/homebrew"), root.join("usr/local")];
+        for prefix in &prefixes {
+            std::fs::create_dir_all(prefix.join("bin")).unwrap();
+        }
+        assert_eq!(find_binary_in_prefixes("kitty", &prefixes), None);
+
+        // Installed by either architecture's `Homebrew` only..
+        for (index, prefix) in prefixes.iter().enumerate() {
+            let kitty = prefix.join("bin/kitty");
+            std::fs::write(&kitty, "").unwrap();
+            assert_eq!(
+                find_binary_in_prefixes("kitty", &prefixes),
+                Some(kitty.clone()),
+                "{index}"
+            );
+            std::fs::remove_file(kitty).unwrap();
+        }
+
+        // ..or both, where the first prefix wins..
+        for prefix in &prefixes {
+            std::fs::write(prefix.join("bin/wezterm"), "").unwrap();
+        }
+        assert_eq!(
+            find_binary_in_prefixes("wezterm", &prefixes),
+            Some(prefixes[0].join("bin/wezterm"))
+        );
+        // ..and a directory of the same name isn't a binary..
+        std::fs::create_dir_all(prefixes[1].join("bin/alacritty")).unwrap();
+        assert_eq!(find_binary_in_prefixes("alacritty", &prefixes), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Joins arguments into a command line as older `Command` implementations did, quoting those containing
+    /// whitespace without escaping their trailing backslashes.
+    fn naive_command_line(args: &[&str]) -> String {
+        args.iter()
+            .map(|arg| {
+                if arg.contains([' ', '\t']) {
+                    format!("\"{arg}\"")
+                } else {
+                    (*arg).to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Splits a command line into arguments as `CommandLineToArgvW`, where `2n` backslashes before a quote
+    /// become `n` and toggle quoting, and `2n + 1` become `n` followed by a literal quote.
+    fn command_line_to_argv(command_line: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut current: Option<String> = None;
+        let mut quoted = false;
+        let mut chars = command_line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let mut backslashes = 1;
+                    while chars.next_if_eq(&'\\').is_some() {
+                        backslashes += 1;
+                    }
+                    let arg = current.get_or_insert_with(String::new);
+                    if chars.next_if_eq(&'"').is_some() {
+                        arg.push_str(&"\\".repeat(backslashes / 2));
+                        if backslashes % 2 == 1 {
+                            arg.push('"');
+                        } else {
+                            quoted = !quoted;
+                        }
+                    } else {
+                        arg.push_str(&"\\".repeat(backslashes));
+                    }
+                }
+                '"' => {
+                    current.get_or_insert_with(String::new);
+                    quoted = !quoted;
+                }
+                ' ' | '\t' if !quoted => args.extend(current.take()),
+                c => current.get_or_insert_with(String::new).push(c),
+            }
+        }
+        args.extend(current);
+        args
+    }
+
+    /// The `--startingDirectory` argument `Windows Terminal` is given for the directory.
+    fn wt_starting_directory_flag(dir: &str) -> Option<String> {
+        cwd_argument(TerminalType::WindowsTerminal, Path::new(dir))
+            .unwrap()
+            .flag("--startingDirectory")
+            .map(|flag| flag.into_string().unwrap())
+    }
+
+    #[test]
+    fn wt_starting_directory_survives_the_command_line() {
+        for (dir, expected) in [
+            // Drive roots keep a doubled separator..
+            (r"C:\", r"C:\\"),
+            (r"\\?\C:\", r"\\?\C:\\"),
+            // ..while anything else has a single trailing separator stripped..
+            (r"C:\Users\me\", r"C:\Users\me"),
+            (r"C:\Users\me", r"C:\Users\me"),
+            (r"C:\Program Files\", r"C:\Program Files"),
+            (r"C:\Program Files\My App\", r"C:\Program Files\My App"),
+            (r"C:\Users\me\with space", r"C:\Users\me\with space"),
+            (r"\\?\C:\Program Files\", r"\\?\C:\Program Files"),
+        ] {
+            let flag = wt_starting_directory_flag(dir).unwrap();
+            assert_eq!(flag, format!("--startingDirectory={expected}"), "{dir}");
+
+            // ..so the separator and program following the directory are still received as arguments..
+            let argv = [flag.as_str(), "--", r"C:\Tools\my app.exe", "--serve"];
+            assert_eq!(
+                command_line_to_argv(&naive_command_line(&argv)),
+                argv,
+                "{dir}"
+            );
+        }
+
+        // ..which they wouldn't be with the trailing separator kept..
+        let unstripped = [r"--startingDirectory=C:\Program Files\", "--", "myapp.exe"];
+        assert_eq!(
+            command_line_to_argv(&naive_command_line(&unstripped)),
+            [r#"--startingDirectory=C:\Program Files" -- myapp.exe"#]
+        );
+    }
+
+    #[test]
+    fn wt_network_share_starting_directories_use_pushd() {
+        for dir in [
+            r"\\srv\share",
+            r"\\srv\share\",
+            r"\\srv\share\with space\",
+            r"\\?\UNC\srv\share",
+        ] {
+            assert!(is_unc_path(Path::new(dir)), "{dir}");
+            assert_eq!(
+                cwd_argument(TerminalType::WindowsTerminal, Path::new(dir)).unwrap(),
+                CwdArg::Pushd(PathBuf::from(dir)),
+            );
+            assert_eq!(wt_starting_directory_flag(dir), None);
+        }
+        for dir in [
+            r"C:\share",
+            r"\\?\C:\share",
+            r"\\.\pipe\share",
+            "/srv/share",
+        ] {
+            assert!(!is_unc_path(Path::new(dir)), "{dir}");
+        }
+
+        let program = (
+            PathBuf::from(r"C:\Program Files\My App\myapp.exe"),
+            vec![r"C:\out dir\".to_string(), "--serve".to_string()],
+        );
+        let command_line =
+            pushd_command_line(Path::new(r"\\srv\share\with space"), Some(&program)).unwrap();
+        assert_eq!(
+            command_line,
+            r#"pushd "\\srv\share\with space" && "C:\Program Files\My App\myapp.exe" "C:\out dir\\" "--serve""#
+        );
+        // The program's arguments are received unchanged..
+        let after_program = command_line.split_once("myapp.exe\" ").unwrap().1;
+        assert_eq!(
+            command_line_to_argv(after_program),
+            [r"C:\out dir\", "--serve"]
+        );
+
+        // ..and what `cmd` can't represent is refused..
+        let pushd = |dir: &str| pushd_command_line(Path::new(dir), None);
+        assert_eq!(
+            pushd(r"\\srv\share").as_deref(),
+            Some(r#"pushd "\\srv\share""#)
+        );
+        assert_eq!(pushd(r"\\?\UNC\srv\share"), None);
+        assert_eq!(pushd(r"\\srv\100%"), None);
+        assert_eq!(pushd("\\\\srv\\a\"b"), None);
+    }
+
+    /// A provider supporting only the given window modes, for resolving the window mode against.
+    struct ModesProvider(&'static [WindowMode]);
+
+    impl TerminalProvider for ModesProvider {
+        fn terminal_type(&self) -> TerminalType {
+            TerminalType::Kitty
+        }
+
+        fn supports_window_mode(&self, mode: WindowMode) -> bool {
+            self.0.contains(&mode)
+        }
+
+        fn is_installed(&self) -> bool {
+            true
+        }
+
+        fn relaunch_in_terminal(&self) -> TermResult<()> {
+            Ok(())
+        }
+    }
+
+    const MODES: [WindowMode; 4] = [
+        WindowMode::Window,
+        WindowMode::Tab,
+        WindowMode::SplitHorizontal,
+        WindowMode::SplitVertical,
+    ];
+
+    #[test]
+    fn window_modes_plan_each_terminals_command() {
+        for (mode, wt, kitty, wezterm) in [
+            (WindowMode::Window, &["new-tab"][..], None, None),
+            (
+                WindowMode::Tab,
+                &["-w", "0", "new-tab"][..],
+                Some(&["--type=tab"][..]),
+                Some(&["cli", "spawn"][..]),
+            ),
+            (
+                WindowMode::SplitHorizontal,
+                &["-w", "0", "split-pane", "-H"][..],
+                Some(&["--type=window", "--location=hsplit"][..]),
+                Some(&["cli", "split-pane", "--bottom"][..]),
+            ),
+            (
+                WindowMode::SplitVertical,
+                &["-w", "0", "split-pane", "-V"][..],
+                Some(&["--type=window", "--location=vsplit"][..]),
+                Some(&["cli", "split-pane", "--right"][..]),
+            ),
+        ] {
+            assert_eq!(wt_window_args(mode, WtWindowTarget::New), wt, "{mode}");
+            assert_eq!(kitty_launch_args(mode), kitty, "{mode}");
+            assert_eq!(wezterm_cli_args(mode), wezterm, "{mode}");
+        }
+
+        // Splits always address the current window, whichever window new tabs would open in..
+        for target in [WtWindowTarget::Existing, WtWindowTarget::Quake] {
+            for mode in [WindowMode::SplitHorizontal, WindowMode::SplitVertical] {
+                assert_eq!(
+                    wt_window_args(mode, target),
+                    wt_window_args(mode, WtWindowTarget::New)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_window_modes_degrade_unless_strict() {
+        let _globals = writing_globals();
+        let previous = relaunch_config();
+
+        let resolve = |supported, mode| {
+            with_window_mode(mode, || resolve_window_mode(&ModesProvider(supported)))
+        };
+        set_relaunch_config(RelaunchConfig::new());
+        for mode in MODES {
+            // Supported modes are kept, splits degrade to tabs, and tabs to windows..
+            assert_eq!(resolve(&MODES, mode).unwrap(), mode);
+            let expected = if mode == WindowMode::Window {
+                WindowMode::Window
+            } else {
+                WindowMode::Tab
+            };
+            assert_eq!(
+                resolve(&[WindowMode::Window, WindowMode::Tab], mode).unwrap(),
+                expected
+            );
+            assert_eq!(
+                resolve(&[WindowMode::Window], mode).unwrap(),
+                WindowMode::Window
+            );
+        }
+
+        // ..unless strict, where unsupported modes fail..
+        set_relaunch_config(RelaunchConfig::new().strict_window_mode(true));
+        assert_eq!(
+            resolve(&[WindowMode::Window, WindowMode::Tab], WindowMode::Tab).unwrap(),
+            WindowMode::Tab
+        );
+        for mode in [WindowMode::SplitHorizontal, WindowMode::SplitVertical] {
+            assert!(matches!(
+                resolve(&[WindowMode::Window, WindowMode::Tab], mode),
+                Err(RelaunchError::UnsupportedWindowMode(TerminalType::Kitty, failed)) if failed == mode
+            ));
+        }
+
+        // ..and without an override, the configured mode is requested..
+        set_relaunch_config(RelaunchConfig::new().window_mode(WindowMode::SplitVertical));
+        assert_eq!(
+            resolve_window_mode(&ModesProvider(&[WindowMode::Window, WindowMode::Tab])).unwrap(),
+            WindowMode::Tab
+        );
+
+        set_relaunch_config(previous);
+    }
+
+    #[test]
+    fn app_search_paths_put_overrides_before_the_defaults() {
+        let _globals = writing_globals();
+        let previous = relaunch_config();
+        set_relaunch_config(RelaunchConfig::new().extra_app_search_paths(vec![
+            PathBuf::from("/Volumes/Apps"),
+            PathBuf::from("/Applications"),
+        ]));
+
+        let env = |dirs: &str| HashMap::from([(APP_DIRS_VAR.to_string(), dirs.to_string())]);
+        let defaults = [
+            PathBuf::from("/Applications"),
+            PathBuf::from("/System/Volumes/Data/Applications"),
+        ];
+        for (dirs, from_env) in [
+            ("/Users/me/Downloads", &["/Users/me/Downloads"][..]),
+            (
+                "/Users/me/Downloads:/Volumes/Second",
+                &["/Users/me/Downloads", "/Volumes/Second"][..],
+            ),
+            // Empty segments are ignored, rather than searching the current directory..
+            (
+                "::/Users/me/Downloads::/Volumes/Second:",
+                &["/Users/me/Downloads", "/Volumes/Second"][..],
+            ),
+            ("", &[][..]),
+            (":", &[][..]),
+        ] {
+            let paths = macos_app_search_paths_in(&env(dirs));
+            let expected: Vec<PathBuf> = from_env
+                .iter()
+                .map(PathBuf::from)
+                .chain([PathBuf::from("/Volumes/Apps")])
+                .chain(defaults.clone())
+                .collect();
+
+            // ..the environment goes first, then the configuration, then the defaults, each once..
+            assert_eq!(paths[..expected.len()], expected, "{dirs:?}");
+            assert!(
+                paths[expected.len()..]
+                    .iter()
+                    .all(|path| path.ends_with("Applications")),
+                "{dirs:?}"
+            );
+        }
+
+        set_relaunch_config(RelaunchConfig::new());
+        assert_eq!(
+            macos_app_search_paths_in(&HashMap::<String, String>::new())[..2],
+            defaults
+        );
+
+        set_relaunch_config(previous);
+    }
+
+    #[test]
+    fn osascript_is_missing_unless_an_executable_is_on_the_path() {
+        let root = crate::test_support::temp_dir("osascript");
+        let (empty, bin) = (root.join("empty"), root.join("bin"));
+        for dir in [&empty, &bin] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let with_path = |dirs: &[&Path]| {
+            let path = std::env::join_paths(dirs).unwrap().into_string().unwrap();
+            HashMap::from([("PATH".to_string(), path)])
+        };
+
+        // Without a `PATH`, or with none of its directories having it..
+        assert_eq!(
+            missing_osascript_in(&HashMap::<String, String>::new()),
+            Some("osascript")
+        );
+        assert_eq!(
+            missing_osascript_in(&with_path(&[&empty])),
+            Some("osascript")
+        );
+
+        // ..or with it there, but not executable..
+        let osascript = bin.join("osascript");
+        std::fs::write(&osascript, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&osascript, std::fs::Permissions::from_mode(0o644)).unwrap();
+            assert_eq!(
+                missing_osascript_in(&with_path(&[&empty, &bin])),
+                Some("osascript")
+            );
+            std::fs::set_permissions(&osascript, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        assert_eq!(missing_osascript_in(&with_path(&[&empty, &bin])), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn app_bundles_are_found_ignoring_case_with_their_name_on_disk() {
+        let root = crate::test_support::temp_dir("app_bundles");
+        let (system, data, user) = (
+            root.join("Applications"),
+            root.join("System/Volumes/Data/Applications"),
+            root.join("Users/me/Applications"),
+        );
+        let dirs = [
+            root.join("missing"),
+            system.clone(),
+            data.clone(),
+            user.clone(),
+        ];
+        for dir in &dirs[1..] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        for bundle in [
+            system.join("Alacritty.app"),
+            data.join("KITTY.app"),
+            user.join("kitty.app"),
+            user.join("WezTerm.app.bak"),
+            user.join("ghostty.APP"),
+        ] {
+            std::fs::create_dir_all(bundle).unwrap();
+        }
+
+        // `Alacritty` is found as it's named, whatever the casing of the lookup..
+        let alacritty = macos_bundle_name(TerminalType::Alacritty).unwrap();
+        assert_eq!(alacritty, "Alacritty.app");
+        for name in [alacritty, "alacritty.app", "ALACRITTY.APP"] {
+            assert_eq!(
+                find_app_in_dirs(name, &dirs),
+                Some(system.join("Alacritty.app")),
+                "{name}"
+            );
+        }
+
+        // ..other bundles too, from the first directory having them, with the name on disk..
+        let found = |terminal| find_app_in_dirs(macos_bundle_name(terminal).unwrap(), &dirs);
+        assert_eq!(found(TerminalType::Kitty), Some(data.join("KITTY.app")));
+        assert_eq!(found(TerminalType::Ghostty), Some(user.join("ghostty.APP")));
+        assert_eq!(found(TerminalType::WezTerm), None);
+        assert_eq!(found(TerminalType::ITerm2), None);
+
+        // ..and terminals that aren't bundles aren't looked up..
+        assert_eq!(macos_bundle_name(TerminalType::MacOS), None);
+        assert_eq!(macos_bundle_name(TerminalType::Xterm), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Working directory names in other scripts, with emoji, combining characters, and quotes next to multi-byte
+    /// characters.
+    const UNICODE_DIRS: &[&str] = &[
+        "Проекты/демо",
+        "プロジェクト/デモ",
+        "项目 '演示'",
+        "emoji \u{1f680}\u{2728}",
+        "cafe\u{301} o\u{308}",
+        "д'е'м'о",
+        "'\u{1f680}'",
+    ];
+
+    #[cfg(unix)]
+    #[test]
+    fn iterm2_commands_keep_unicode_paths_recoverable() {
+        use std::path::PathBuf;
+
+        let root = crate::test_support::temp_dir("iterm2_unicode")
+            .canonicalize()
+            .unwrap();
+        for name in UNICODE_DIRS {
+            let dir = root.join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            let CwdArg::Shell(quoted_wd) = cwd_argument(TerminalType::ITerm2, &dir).unwrap() else {
+                panic!("`ITerm2` working directories are quoted for the shell");
+            };
+
+            // The generated command is valid `UTF-8`, with every character of the path kept..
+            let arg = format!("{name}|{name}");
+            let program = (
+                PathBuf::from("/bin/sh"),
+                vec![
+                    "-c".to_string(),
+                    r#"pwd -P; printf '%s\n' "$1""#.to_string(),
+                    "sh".to_string(),
+                    arg.clone(),
+                ],
+            );
+            let command =
+                iterm2_command(&quoted_wd, Some(&program), &[("LC_ALL", "C".to_string())]);
+            assert!(
+                command.as_str().contains(&name.replace('\'', r"'\''")),
+                "{command}"
+            );
+
+            // ..and the shell the command is typed into gets the original directory and argument back..
+            let output = std::process::Command::new("/bin/sh")
+                .args(["-c", command.as_str()])
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{command}: {output:?}");
+            assert_eq!(
+                String::from_utf8(output.stdout).unwrap(),
+                format!("{}\n{arg}\n", dir.display()),
+                "{command}"
+            );
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn wt_window_target_prefers_a_running_instance() {
+        let names = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        // Without a configured target, a running `Windows Terminal` is reused, such as a quake window..
+        for (running, target) in [
+            (names(&[]), WtWindowTarget::New),
+            (
+                names(&["explorer.exe", "conhost.exe", "cmd.exe"]),
+                WtWindowTarget::New,
+            ),
+            (
+                names(&["explorer.exe", WINDOWS_TERMINAL_PROCESS]),
+                WtWindowTarget::Existing,
+            ),
+            (names(&["windowsterminal.EXE"]), WtWindowTarget::Existing),
+            (names(&["WindowsTerminal"]), WtWindowTarget::New),
+            (names(&["wt.exe"]), WtWindowTarget::New),
+        ] {
+            assert_eq!(
+                wt_window_target(false, None, || running.clone()),
+                target,
+                "{running:?}"
+            );
+        }
+
+        // ..a configured target wins without listing the processes, and a requested new window wins over both..
+        for configured in [
+            WtWindowTarget::New,
+            WtWindowTarget::Existing,
+            WtWindowTarget::Quake,
+        ] {
+            assert_eq!(
+                wt_window_target(false, Some(configured), || panic!("processes listed")),
+                configured
+            );
+            assert_eq!(
+                wt_window_target(true, Some(configured), || panic!("processes listed")),
+                WtWindowTarget::New
+            );
+        }
+        assert_eq!(
+            wt_window_target(true, None, || panic!("processes listed")),
+            WtWindowTarget::New
+        );
+    }
+
+    #[test]
+    fn wt_commands_per_window_target() {
+        let geometry = Some(WindowGeometry {
+            x: 10,
+            y: 20,
+            cols: 120,
+            rows: 30,
+        });
+        let command = |target| {
+            let mut args = wt_geometry_args(WindowMode::Window, target, geometry);
+            args.extend(
+                wt_window_args(WindowMode::Window, target)
+                    .iter()
+                    .map(ToString::to_string),
+            );
+            args
+        };
+
+        // Only a new window is positioned, existing and quake windows keep their place..
+        assert_eq!(
+            command(WtWindowTarget::New),
+            ["--pos", "10,20", "--size", "120,30", "new-tab"]
+        );
+        assert_eq!(command(WtWindowTarget::Existing), ["-w", "0", "new-tab"]);
+        assert_eq!(command(WtWindowTarget::Quake), ["-w", "_quake", "new-tab"]);
+    }
+
+    /// The working directory part of the `wt` command planned for the directory, as in
+    /// `WindowsTerminalProvider::relaunch_in_terminal()`, or `None` if it's unrepresentable.
+    fn wt_directory_plan(dir: &str, program: &LaunchProgram) -> Option<String> {
+        let dir = strip_verbatim_prefix(Path::new(dir));
+        match cwd_argument(TerminalType::WindowsTerminal, &dir).unwrap() {
+            CwdArg::Pushd(dir) => pushd_command_line(&dir, Some(program)),
+            cwd => cwd
+                .flag("--startingDirectory")
+                .map(|flag| flag.into_string().unwrap()),
+        }
+    }
+
+    #[test]
+    fn verbatim_unc_and_long_paths_in_wt_commands() {
+        let program = (
+            strip_verbatim_prefix(Path::new(r"\\?\C:\Tools\app.exe")),
+            vec!["--serve".to_string()],
+        );
+        assert_eq!(program.0, PathBuf::from(r"C:\Tools\app.exe"));
+
+        let long_local = format!(r"\\?\C:\{}", "a".repeat(300));
+        let long_share = format!(r"\\?\UNC\srv\share\{}", "a".repeat(300));
+        for (dir, plan) in [
+            // Verbatim paths fitting in `MAX_PATH` lose their prefix..
+            (
+                r"\\?\C:\Users\me".to_string(),
+                Some(r"--startingDirectory=C:\Users\me".to_string()),
+            ),
+            (
+                r"\\?\UNC\srv\share\dir".to_string(),
+                Some(r#"pushd "\\srv\share\dir" && "C:\Tools\app.exe" "--serve""#.to_string()),
+            ),
+            // ..longer ones keep it, which `wt` accepts for local directories..
+            (
+                long_local.clone(),
+                Some(format!("--startingDirectory={long_local}")),
+            ),
+            // ..but `cmd` can't change into, so nothing can work..
+            (long_share, None),
+            // ..as for shares with characters `cmd` interprets even when quoted..
+            (r"\\srv\share\100%".to_string(), None),
+        ] {
+            assert_eq!(wt_directory_plan(&dir, &program), plan, "{dir}");
+        }
+
+        // The prefix is only stripped while the path fits in `MAX_PATH`, counted in `UTF-16` units..
+        let fits = format!(r"C:\{}", "a".repeat(WINDOWS_MAX_PATH - 4));
+        let too_long = format!(r"C:\{}", "a".repeat(WINDOWS_MAX_PATH - 3));
+        assert_eq!(
+            strip_verbatim_prefix(Path::new(&format!(r"\\?\{fits}"))),
+            PathBuf::from(&fits)
+        );
+        assert_eq!(
+            strip_verbatim_prefix(Path::new(&format!(r"\\?\{too_long}"))),
+            PathBuf::from(format!(r"\\?\{too_long}"))
+        );
+        let emoji = format!(r"C:\{}", "\u{1f680}".repeat(WINDOWS_MAX_PATH / 2));
+        assert_eq!(
+            strip_verbatim_prefix(Path::new(&format!(r"\\?\{emoji}"))),
+            PathBuf::from(format!(r"\\?\{emoji}"))
+        );
+    }
+
+    /// Renders the program, arguments, working directory and variables of the given command, one per line.
+    fn render_command(command: &std::process::Command) -> String {
+        use std::fmt::Write;
+
+        let mut rendered = String::new();
+        let _ = writeln!(rendered, "program {:?}", command.get_program());
+        for arg in command.get_args() {
+            let _ = writeln!(rendered, "arg {arg:?}");
+        }
+        if let Some(dir) = command.get_current_dir() {
+            let _ = writeln!(rendered, "cwd {dir:?}");
+        }
+        for (name, value) in command.get_envs() {
+            let _ = writeln!(rendered, "env {name:?}={value:?}");
+        }
+        rendered
+    }
+
+    /// Compares the rendered commands of each scenario with `tests/fixtures/argv/<name>.txt`, see
+    /// `assert_golden_text()`.
+    fn assert_golden(name: &str, scenarios: &[(&str, std::process::Command)]) {
+        use std::fmt::Write;
+
+        let mut rendered = String::new();
+        for (scenario, command) in scenarios {
+            let _ = writeln!(rendered, "# {scenario}\n{}", render_command(command));
+        }
+        assert_golden_text(name, &rendered);
+    }
+
+    /// Compares the given text with `tests/fixtures/argv/<name>.txt`, or rewrites the file when
+    /// `TERMINAL_RELAUNCH_BLESS` is set.
+    fn assert_golden_text(name: &str, rendered: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("argv")
+            .join(format!("{name}.txt"));
+
+        if std::env::var_os("TERMINAL_RELAUNCH_BLESS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, rendered).unwrap();
+            return;
+        }
+        let golden = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+            panic!(
+                "{}: {err}, bless it with `TERMINAL_RELAUNCH_BLESS=1`",
+                path.display()
+            )
+        });
+        assert_eq!(
+            rendered, golden,
+            "{name}, bless changes with `TERMINAL_RELAUNCH_BLESS=1`"
+        );
+    }
+
+    fn golden_program() -> LaunchProgram {
+        (
+            PathBuf::from("/opt/my app/bin/tool"),
+            vec!["--flag".to_string(), "two words".to_string(), String::new()],
+        )
+    }
+
+    fn golden_vars() -> Vec<(&'static str, String)> {
+        vec![
+            ("COLORTERM", "truecolor".to_string()),
+            ("LANG", "C.UTF-8".to_string()),
+        ]
+    }
+
+    #[test]
+    fn windows_terminal_argv_is_pinned() {
+        let launcher = Path::new(r"C:\Users\me\AppData\Local\Microsoft\WindowsApps\wt.exe");
+        let program = (
+            PathBuf::from(r"C:\Program Files\app\tool.exe"),
+            vec!["--flag".to_string(), "two words".to_string()],
+        );
+        let geometry = Some(WindowGeometry {
+            x: 10,
+            y: 20,
+            cols: 120,
+            rows: 30,
+        });
+        let vars = golden_vars();
+        let command = |placement, dir: &str, program: Option<&LaunchProgram>| {
+            wt_command(launcher, placement, Path::new(dir), program, &vars).unwrap()
+        };
+
+        assert_golden(
+            "windows_terminal",
+            &[
+                (
+                    "shell in a new window",
+                    command(
+                        (WindowMode::Window, WtWindowTarget::New, None),
+                        r"C:\work",
+                        None,
+                    ),
+                ),
+                (
+                    "program in a new window",
+                    command(
+                        (WindowMode::Window, WtWindowTarget::New, None),
+                        r"C:\work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "program in a placed new window",
+                    command(
+                        (WindowMode::Window, WtWindowTarget::New, geometry),
+                        r"C:\work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "program in a tab of the existing window",
+                    command(
+                        (WindowMode::Tab, WtWindowTarget::Existing, geometry),
+                        r"C:\-work\",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "program in a horizontal split",
+                    command(
+                        (WindowMode::SplitHorizontal, WtWindowTarget::Existing, None),
+                        r"C:\work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "program in a vertical split of the quake window",
+                    command(
+                        (WindowMode::SplitVertical, WtWindowTarget::Quake, None),
+                        r"C:\work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "shell on a network share",
+                    command(
+                        (WindowMode::Window, WtWindowTarget::New, None),
+                        r"\\srv\share",
+                        None,
+                    ),
+                ),
+                (
+                    "program on a network share",
+                    command(
+                        (WindowMode::Window, WtWindowTarget::New, None),
+                        r"\\srv\share\dir",
+                        Some(&program),
+                    ),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn iterm2_argv_is_pinned() {
+        let wd = ShellQuoted::quote("/Users/me/it's here");
+        let program = golden_program();
+        let vars = golden_vars();
+
+        assert_golden(
+            "iterm2",
+            &[
+                (
+                    "shell",
+                    iterm2_osascript_command(&iterm2_command(&wd, None, &vars)),
+                ),
+                (
+                    "program",
+                    iterm2_osascript_command(&iterm2_command(&wd, Some(&program), &vars)),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn open_app_argv_is_pinned() {
+        let program = golden_program();
+        let vars = golden_vars();
+        let command = |app: &str, dir: &str, program| {
+            open_app_command(Path::new(app), Path::new(dir), program, &vars)
+        };
+
+        assert_golden(
+            "open_app",
+            &[
+                (
+                    "Ghostty shell",
+                    command("/Applications/Ghostty.app", "/Users/me/work", None),
+                ),
+                (
+                    "Ghostty program",
+                    command(
+                        "/Applications/Ghostty.app",
+                        "/Users/me/work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "Alacritty shell in a directory starting with a dash",
+                    command("/Applications/Alacritty.app", "-work", None),
+                ),
+                (
+                    "Alacritty program",
+                    command(
+                        "/Applications/Alacritty.app",
+                        "/Users/me/work",
+                        Some(&program),
+                    ),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn kitty_argv_is_pinned() {
+        let program = golden_program();
+        let vars = golden_vars();
+        let command = |is_binary, mode, dir: &str, program| {
+            let kitty = if is_binary {
+                "/opt/homebrew/bin/kitty"
+            } else {
+                "/Applications/kitty.app"
+            };
+            let cwd = cwd_argument(TerminalType::Kitty, Path::new(dir)).unwrap();
+            kitty_command(
+                (Path::new(kitty), is_binary),
+                mode,
+                (&cwd, Path::new(dir)),
+                program,
+                &vars,
+            )
+        };
+
+        assert_golden(
+            "kitty",
+            &[
+                (
+                    "program in a tab",
+                    command(false, WindowMode::Tab, "/Users/me/work", Some(&program)),
+                ),
+                (
+                    "shell in a horizontal split",
+                    command(false, WindowMode::SplitHorizontal, "/Users/me/work", None),
+                ),
+                (
+                    "program in a vertical split",
+                    command(
+                        false,
+                        WindowMode::SplitVertical,
+                        "/Users/me/work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "shell from the binary",
+                    command(true, WindowMode::Window, "-work", None),
+                ),
+                (
+                    "program from the binary",
+                    command(true, WindowMode::Window, "/Users/me/work", Some(&program)),
+                ),
+                (
+                    "shell from the application",
+                    command(false, WindowMode::Window, "/Users/me/work", None),
+                ),
+                (
+                    "program from the application",
+                    command(false, WindowMode::Window, "/Users/me/work", Some(&program)),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn wezterm_argv_is_pinned() {
+        let program = golden_program();
+        let vars = golden_vars();
+        let command = |is_binary, mode, dir: &str, program| {
+            let wezterm = if is_binary {
+                "/opt/homebrew/bin/wezterm"
+            } else {
+                "/Applications/WezTerm.app"
+            };
+            let cwd = cwd_argument(TerminalType::WezTerm, Path::new(dir)).unwrap();
+            wezterm_command(
+                (
+                    Path::new(wezterm),
+                    is_binary,
+                    "/Applications/WezTerm.app/Contents/MacOS/wezterm".as_ref(),
+                ),
+                mode,
+                (&cwd, Path::new(dir)),
+                program,
+                &vars,
+            )
+        };
+
+        assert_golden(
+            "wezterm",
+            &[
+                (
+                    "program in a tab",
+                    command(false, WindowMode::Tab, "/Users/me/work", Some(&program)),
+                ),
+                (
+                    "shell in a horizontal split",
+                    command(false, WindowMode::SplitHorizontal, "/Users/me/work", None),
+                ),
+                (
+                    "program in a vertical split",
+                    command(
+                        false,
+                        WindowMode::SplitVertical,
+                        "/Users/me/work",
+                        Some(&program),
+                    ),
+                ),
+                (
+                    "shell from the binary",
+                    command(true, WindowMode::Window, "-work", None),
+                ),
+                (
+                    "program from the binary",
+                    command(true, WindowMode::Window, "/Users/me/work", Some(&program)),
+                ),
+                (
+                    "shell from the application",
+                    command(false, WindowMode::Window, "/Users/me/work", None),
+                ),
+                (
+                    "program from the application",
+                    command(false, WindowMode::Window, "/Users/me/work", Some(&program)),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn alacritty_windows_argv_is_pinned() {
+        let program = (
+            PathBuf::from(r"C:\Program Files\app\tool.exe"),
+            vec!["--flag".to_string(), "two words".to_string()],
+        );
+        let vars = golden_vars();
+        let command = |dir: &str, program| {
+            let cwd = cwd_argument(TerminalType::Alacritty, Path::new(dir)).unwrap();
+            alacritty_windows_command(cwd, program, &vars)
+        };
+
+        assert_golden(
+            "alacritty_windows",
+            &[
+                ("shell", command(r"C:\work", None)),
+                ("program", command(r"C:\work", Some(&program))),
+                ("shell on a network share", command(r"\\srv\share", None)),
+                (
+                    "program on a network share",
+                    command(r"\\srv\share\dir", Some(&program)),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn ptyxis_argv_is_pinned() {
+        let (exe, args) = golden_program();
+        let program: Vec<OsString> = std::iter::once(exe.into_os_string())
+            .chain(args.into_iter().map(OsString::from))
+            .collect();
+        let cwd = cwd_argument(TerminalType::Ptyxis, Path::new("/home/me/-work")).unwrap();
+        let native = PtyxisLauncher::Native(PathBuf::from("/usr/bin/ptyxis"));
+        let flatpak = PtyxisLauncher::Flatpak(PathBuf::from("/usr/bin/flatpak"));
+
+        assert_golden(
+            "ptyxis",
+            &[
+                (
+                    "native shell",
+                    native.command(WindowMode::Window, &cwd, &[]),
+                ),
+                (
+                    "native program in a tab",
+                    native.command(WindowMode::Tab, &cwd, &program),
+                ),
+                (
+                    "native program in a split, opened as a window",
+                    native.command(WindowMode::SplitVertical, &cwd, &program),
+                ),
+                (
+                    "flatpak program",
+                    flatpak.command(WindowMode::Window, &cwd, &program),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn hyper_argv_is_pinned() {
+        let dir = crate::test_support::temp_dir("hyper_argv");
+        let app = dir.join("Hyper.app");
+        std::fs::create_dir_all(&app).unwrap();
+        let vars = golden_vars();
+
+        // The application bundle is looked up on disk, so it's renamed to a fixed path for the golden file..
+        let bundled = render_command(&hyper_command(&app, Path::new("/Users/me/work"), &vars))
+            .replace(
+                &format!("{:?}", app.display().to_string()),
+                "\"/Applications/Hyper.app\"",
+            );
+        let executable = render_command(&hyper_command(
+            Path::new("/usr/local/bin/hyper"),
+            Path::new("/home/me/work"),
+            &vars,
+        ));
+        assert_golden_text(
+            "hyper",
+            &format!("# application\n{bundled}\n# executable\n{executable}\n"),
+        );
+    }
+
+    #[test]
+    fn tilix_is_identified_before_the_vte_terminals_it_inherits() {
+        let position = |kind| {
+            TERMINAL_IDENTIFIERS
+                .iter()
+                .position(|identifier| identifier.kind == kind)
+                .unwrap()
+        };
+        assert!(position(TerminalType::Tilix) < position(TerminalType::GnomeTerminal));
+        assert!(position(TerminalType::Tilix) < position(TerminalType::XfceTerminal));
+
+        // A `Tilix` session started from `GNOME Terminal` inherits all of its variables..
+        let vte = [
+            ("VTE_VERSION", "6800"),
+            ("COLORTERM", "truecolor"),
+            ("TERM", "xterm-256color"),
+            ("GNOME_TERMINAL_SCREEN", "/org/gnome/Terminal/screen/0"),
+            ("GNOME_TERMINAL_SERVICE", ":1.95"),
+            ("XDG_CURRENT_DESKTOP", "XFCE"),
+        ];
+        let tilix = linux(&vte).var("TILIX_ID", "7f0c2b9e-2d4b-4f0e-9b1e-2c8a5b3f6d10");
+        assert_eq!(outcome(&tilix).terminal, TerminalType::Tilix);
+        assert_eq!(outcome(&linux(&vte)).terminal, TerminalType::GnomeTerminal);
+
+        // ..while an empty `TILIX_ID` still counts, as only its presence is checked..
+        assert_eq!(
+            outcome(&linux(&vte).var("TILIX_ID", "")).terminal,
+            TerminalType::Tilix
+        );
+    }
+
+    fn command_args(command: &std::process::Command) -> Vec<OsString> {
+        command.get_args().map(OsStr::to_owned).collect()
+    }
+
+    /// Asserts the directory is passed as exactly one `flag=value` argument, which can't be parsed as a flag.
+    fn assert_cwd_flag(command: &std::process::Command, flag: &str, expected: &str) {
+        let value = OsString::from(format!("{flag}={expected}"));
+        let args = command_args(command);
+        assert_eq!(
+            args.iter().filter(|arg| **arg == value).count(),
+            1,
+            "{args:?}"
+        );
+        assert!(!args.iter().any(|arg| arg == expected), "{args:?}");
+    }
+
+    #[test]
+    fn awkward_working_directories_in_every_provider_command() {
+        let program = golden_program();
+        let vars = golden_vars();
+        for (dir, expected) in [
+            ("-foo", "./-foo"),
+            ("/tmp/-foo", "/tmp/-foo"),
+            ("/tmp/a\nb", "/tmp/a\nb"),
+            ("/tmp/with space", "/tmp/with space"),
+        ] {
+            let path = Path::new(dir);
+
+            let cwd = cwd_argument(TerminalType::Kitty, path).unwrap();
+            for (is_binary, mode, program, flag) in [
+                (false, WindowMode::Tab, Some(&program), "--cwd"),
+                (true, WindowMode::Window, Some(&program), "--directory"),
+                (false, WindowMode::Window, None, "--directory"),
+            ] {
+                let kitty = Path::new("/Applications/kitty.app");
+                let command = kitty_command((kitty, is_binary), mode, (&cwd, path), program, &vars);
+                assert_cwd_flag(&command, flag, expected);
+            }
+
+            let cwd = cwd_argument(TerminalType::WezTerm, path).unwrap();
+            for (is_binary, mode, program) in [
+                (false, WindowMode::SplitVertical, Some(&program)),
+                (true, WindowMode::Window, Some(&program)),
+                (false, WindowMode::Window, None),
+            ] {
+                let wezterm = (
+                    Path::new("/Applications/WezTerm.app"),
+                    is_binary,
+                    OsStr::new("wezterm"),
+                );
+                let command = wezterm_command(wezterm, mode, (&cwd, path), program, &vars);
+                assert_cwd_flag(&command, "--cwd", expected);
+            }
+
+            let cwd = cwd_argument(TerminalType::Ptyxis, path).unwrap();
+            let ptyxis = PtyxisLauncher::Native(PathBuf::from("/usr/bin/ptyxis"));
+            assert_cwd_flag(
+                &ptyxis.command(WindowMode::Window, &cwd, &[]),
+                "--working-directory",
+                expected,
+            );
+
+            // `Ghostty` and `Alacritty` are given the directory as their working directory, and a shell is told..
+            for program in [None, Some(&program)] {
+                let command =
+                    open_app_command(Path::new("/Applications/Ghostty.app"), path, program, &vars);
+                assert_eq!(command.get_current_dir(), Some(path));
+                if program.is_none() {
+                    assert_cwd_flag(&command, "--working-directory", dir);
+                }
+            }
+
+            // ..as is `Hyper`, also given it as its only argument, which is absolute when relaunching..
+            let command = hyper_command(Path::new("/usr/local/bin/hyper"), path, &vars);
+            assert_eq!(command.get_current_dir(), Some(path));
+            if path.is_absolute() {
+                assert_eq!(command_args(&command), [OsString::from(dir)]);
+            }
+
+            // ..while `ITerm2` types it into a shell, quoted, unless it contains a line break..
+            match cwd_argument(TerminalType::ITerm2, path) {
+                Ok(CwdArg::Shell(quoted)) => {
+                    assert!(!dir.contains('\n'));
+                    assert_eq!(quoted, ShellQuoted::quote(dir));
+                    let typed = iterm2_command(&quoted, Some(&program), &vars);
+                    assert!(
+                        typed.as_str().starts_with(&format!("cd '{dir}'; ")),
+                        "{typed}"
+                    );
+                }
+                result => assert!(dir.contains('\n') && result.is_err(), "{result:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn awkward_working_directories_in_windows_provider_commands() {
+        let program = golden_program();
+        let vars = golden_vars();
+
+        for dir in [r"C:\-foo", "C:\\a\nb", r"C:\with space"] {
+            let placement = (WindowMode::Tab, WtWindowTarget::Existing, None);
+            let command = wt_command(
+                Path::new("wt.exe"),
+                placement,
+                Path::new(dir),
+                Some(&program),
+                &vars,
+            )
+            .unwrap();
+            assert_cwd_flag(&command, "--startingDirectory", dir);
+
+            let cwd = cwd_argument(TerminalType::Alacritty, Path::new(dir)).unwrap();
+            let command = alacritty_windows_command(cwd, Some(&program), &vars);
+            assert_eq!(command.get_current_dir(), Some(Path::new(dir)));
+        }
+        let share = Path::new(r"\\srv\-share\with space");
+        let cwd = cwd_argument(TerminalType::Alacritty, share).unwrap();
+        let command = alacritty_windows_command(cwd, Some(&program), &vars);
+        assert_cwd_flag(&command, "--working-directory", r"\\srv\-share\with space");
+    }
+
+    #[test]
+    fn geometry_hints_in_planned_wt_commands() {
+        let geometry = WindowGeometry {
+            x: -1920,
+            y: 120,
+            cols: 129,
+            rows: 38,
+        };
+        let vars = golden_vars();
+        let args = |mode, target, geometry| {
+            let placement = (mode, target, geometry);
+            let command = wt_command(
+                Path::new("wt.exe"),
+                placement,
+                Path::new(r"C:\work"),
+                None,
+                &vars,
+            )
+            .unwrap();
+            command_args(&command)
+        };
+
+        for mode in [
+            WindowMode::Window,
+            WindowMode::Tab,
+            WindowMode::SplitHorizontal,
+            WindowMode::SplitVertical,
+        ] {
+            for target in [
+                WtWindowTarget::New,
+                WtWindowTarget::Existing,
+                WtWindowTarget::Quake,
+            ] {
+                let hinted = args(mode, target, Some(geometry));
+                let unhinted = args(mode, target, None);
+
+                // Only a new window is placed, with the hints ahead of the subcommand..
+                if mode == WindowMode::Window && target == WtWindowTarget::New {
+                    let expected = ["--pos", "-1920,120", "--size", "129,38"].map(OsString::from);
+                    assert_eq!(hinted[..4], expected, "{mode:?} {target:?}");
+                    assert_eq!(hinted[4..], unhinted, "{mode:?} {target:?}");
+                } else {
+                    assert_eq!(hinted, unhinted, "{mode:?} {target:?}");
+                }
+
+                // ..and without hints, nothing is placed..
+                assert!(
+                    !unhinted.iter().any(|arg| arg == "--pos" || arg == "--size"),
+                    "{mode:?} {target:?}"
+                );
+            }
+        }
+
+        // The default configuration gives no hints..
+        let _globals = reading_globals();
+        assert_eq!(relaunch_config().window_geometry, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ptyxis_is_found_natively_then_through_flatpak() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = crate::test_support::temp_dir("ptyxis_launcher");
+        let script = |dir: &Path, name: &str, body: &str| {
+            std::fs::create_dir_all(dir).unwrap();
+            let path = dir.join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let env = |dirs: &[&Path]| {
+            let path = std::env::join_paths(dirs).unwrap();
+            HashMap::from([("PATH".to_string(), path.into_string().unwrap())])
+        };
+
+        // `flatpak info` only succeeds for installed applications..
+        let installed = root.join("installed");
+        let flatpak = script(
+            &installed,
+            "flatpak",
+            &format!(r#"[ "$1 $2" = "info {PTYXIS_FLATPAK_ID}" ]"#),
+        );
+        let missing = root.join("missing");
+        script(&missing, "flatpak", "exit 1");
+        let native = root.join("native");
+        let ptyxis = script(&native, "ptyxis", "exit 0");
+
+        assert_eq!(
+            PtyxisLauncher::find_in(&env(&[&installed])),
+            Some(PtyxisLauncher::Flatpak(flatpak))
+        );
+        assert_eq!(PtyxisLauncher::find_in(&env(&[&missing])), None);
+        assert_eq!(PtyxisLauncher::find_in(&env(&[&root.join("empty")])), None);
+
+        // ..and the native executable is preferred, wherever it is on the `PATH`..
+        for dirs in [[&*installed, &*native], [&*native, &*installed]] {
+            assert_eq!(
+                PtyxisLauncher::find_in(&env(&dirs)),
+                Some(PtyxisLauncher::Native(ptyxis.clone()))
+            );
+        }
+
+        // ..but only if it can be run..
+        std::fs::set_permissions(&ptyxis, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(PtyxisLauncher::find_in(&env(&[&native])), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn ptyxis_commands_across_window_modes() {
+        let provider = PtyxisProvider::default();
+        let cwd = cwd_argument(TerminalType::Ptyxis, Path::new("/home/me/work")).unwrap();
+        let program = [OsString::from("/usr/bin/app"), OsString::from("--flag")];
+
+        for launcher in [
+            PtyxisLauncher::Native(PathBuf::from("/usr/bin/ptyxis")),
+            PtyxisLauncher::Flatpak(PathBuf::from("/usr/bin/flatpak")),
+        ] {
+            let prefix: &[&str] = match launcher {
+                PtyxisLauncher::Native(_) => &[],
+                PtyxisLauncher::Flatpak(_) => &["run", PTYXIS_FLATPAK_ID],
+            };
+            for (mode, resolved, flag) in [
+                (WindowMode::Window, WindowMode::Window, "--new-window"),
+                (WindowMode::Tab, WindowMode::Tab, "--tab"),
+                // `Ptyxis` has no splits, which fall back to a tab, or open a new window if asked for directly..
+                (WindowMode::SplitHorizontal, WindowMode::Tab, "--new-window"),
+                (WindowMode::SplitVertical, WindowMode::Tab, "--new-window"),
+            ] {
+                assert_eq!(provider.supports_window_mode(mode), !mode.is_split());
+                let resolved_mode = {
+                    let _globals = reading_globals();
+                    with_window_mode(mode, || resolve_window_mode(&provider)).unwrap()
+                };
+                assert_eq!(resolved_mode, resolved, "{mode:?}");
+
+                let expected: Vec<OsString> = prefix
+                    .iter()
+                    .chain(&[flag, "--working-directory=/home/me/work", "--"])
+                    .map(OsString::from)
+                    .chain(program.iter().cloned())
+                    .collect();
+                let command = launcher.command(mode, &cwd, &program);
+                assert_eq!(command_args(&command), expected, "{launcher:?} {mode:?}");
+
+                // ..and without a program, a shell is opened..
+                let shell = launcher.command(mode, &cwd, &[]);
+                assert_eq!(
+                    command_args(&shell),
+                    expected[..prefix.len() + 2],
+                    "{launcher:?} {mode:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn preview_only_installs_are_found_and_launched_through_their_alias() {
+        let root = crate::test_support::temp_dir("wt_preview");
+        let env = HashMap::from([("LOCALAPPDATA".to_string(), root.display().to_string())]);
+        let aliases = root.join("Microsoft").join("WindowsApps");
+        let alias =
+            |channel: WindowsTerminalChannel| aliases.join(channel.package()).join("wt.exe");
+        let no_registry = HashMap::<String, PathBuf>::new();
+        let registry = |path: &str| HashMap::from([("wt.exe".to_string(), PathBuf::from(path))]);
+
+        // `Preview` registered under `App Paths`, whatever the case of its package..
+        let registered = format!(
+            r"C:\Users\me\AppData\Local\Microsoft\WindowsApps\{}\wt.exe",
+            WINDOWS_TERMINAL_PREVIEW_PACKAGE.to_ascii_lowercase()
+        );
+        let install = WindowsTerminalInstall::find_in(&registry(&registered), &env).unwrap();
+        assert_eq!(
+            install,
+            WindowsTerminalInstall {
+                channel: WindowsTerminalChannel::Preview,
+                launcher: PathBuf::from(&registered),
+            }
+        );
+
+        // ..is relaunched through that alias, not `wt`..
+        let placement = (WindowMode::Window, WtWindowTarget::New, None);
+        let command = wt_command(
+            &install.launcher,
+            placement,
+            Path::new(r"C:\work"),
+            None,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(command.get_program(), OsStr::new(&registered));
+
+        // ..while the stable release, or a registration without a path, is launched through `wt`..
+        for path in [
+            r"C:\Users\me\AppData\Local\Microsoft\WindowsApps\Microsoft.WindowsTerminal_8wekyb3d8bbwe\wt.exe",
+            "wt.exe",
+        ] {
+            assert_eq!(
+                WindowsTerminalInstall::find_in(&registry(path), &env),
+                Some(WindowsTerminalInstall {
+                    channel: WindowsTerminalChannel::Stable,
+                    launcher: PathBuf::from("wt"),
+                }),
+                "{path}"
+            );
+        }
+
+        // Without a registration, the alias directories are looked in, stable first..
+        assert_eq!(WindowsTerminalInstall::find_in(&no_registry, &env), None);
+        for channel in [
+            WindowsTerminalChannel::Preview,
+            WindowsTerminalChannel::Stable,
+        ] {
+            std::fs::create_dir_all(alias(channel).parent().unwrap()).unwrap();
+            std::fs::write(alias(channel), "").unwrap();
+            assert_eq!(
+                WindowsTerminalInstall::find_in(&no_registry, &env),
+                Some(WindowsTerminalInstall {
+                    channel,
+                    launcher: alias(channel),
+                })
+            );
+        }
+
+        // ..which need `LOCALAPPDATA`, and only a directory without the alias doesn't count..
+        let empty = HashMap::<String, String>::new();
+        assert_eq!(WindowsTerminalInstall::find_in(&no_registry, &empty), None);
+        std::fs::remove_file(alias(WindowsTerminalChannel::Stable)).unwrap();
+        std::fs::remove_file(alias(WindowsTerminalChannel::Preview)).unwrap();
+        assert_eq!(WindowsTerminalInstall::find_in(&no_registry, &env), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Runs the program relaunched by the planned command the way a terminal started by `launchd` would: from its
+    /// first `/bin/sh` argument (the wrapper, or the program itself), with only the variables set on the command.
+    fn run_relaunched(command: &std::process::Command) -> String {
+        let args = command_args(command);
+        let start = args
+            .iter()
+            .position(|arg| arg == "/bin/sh")
+            .unwrap_or_else(|| panic!("{args:?}"));
+        let output = std::process::Command::new(&args[start])
+            .args(&args[start + 1..])
+            .env_clear()
+            .envs(
+                command
+                    .get_envs()
+                    .filter_map(|(name, value)| value.map(|value| (name, value))),
+            )
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn locale_and_time_zone_reach_the_program_under_every_provider_command() {
+        let parent = HashMap::from([
+            ("LANG".to_string(), "de_DE.UTF-8".to_string()),
+            ("LC_TIME".to_string(), "en_GB.UTF-8".to_string()),
+            ("TZ".to_string(), "Europe/Berlin".to_string()),
+            ("HOME".to_string(), "/home/me".to_string()),
+        ]);
+        let program: LaunchProgram = (
+            PathBuf::from("/bin/sh"),
+            vec![
+                "-c".to_string(),
+                r#"printf '%s|%s|%s|%s' "$LANG" "$LC_TIME" "$TZ" "$HOME""#.to_string(),
+            ],
+        );
+        let curr_wd = Path::new("/home/me/work");
+        let cwd = CwdArg::Value(curr_wd.as_os_str().to_owned());
+
+        let commands = |vars: &[(&str, String)]| {
+            let ptyxis_program: Vec<OsString> = std::iter::once(program.0.clone().into_os_string())
+                .chain(program.1.iter().map(OsString::from))
+                .collect();
+            let mut ptyxis = PtyxisLauncher::Native(PathBuf::from("/usr/bin/ptyxis")).command(
+                WindowMode::Window,
+                &cwd,
+                &ptyxis_program,
+            );
+            ptyxis.envs(vars.iter().cloned());
+
+            let mut commands = vec![
+                open_app_command(
+                    Path::new("/Applications/Ghostty.app"),
+                    curr_wd,
+                    Some(&program),
+                    vars,
+                ),
+                alacritty_windows_command(cwd.clone(), Some(&program), vars),
+                wt_command(
+                    Path::new("wt.exe"),
+                    (WindowMode::Window, WtWindowTarget::New, None),
+                    curr_wd,
+                    Some(&program),
+                    vars,
+                )
+                .unwrap(),
+                ptyxis,
+            ];
+            for mode in [
+                WindowMode::Window,
+                WindowMode::Tab,
+                WindowMode::SplitVertical,
+            ] {
+                for is_binary in [false, true] {
+                    commands.push(kitty_command(
+                        (Path::new("/Applications/kitty.app"), is_binary),
+                        mode,
+                        (&cwd, curr_wd),
+                        Some(&program),
+                        vars,
+                    ));
+                    commands.push(wezterm_command(
+                        (
+                            Path::new("/Applications/WezTerm.app"),
+                            is_binary,
+                            OsStr::new("wezterm"),
+                        ),
+                        mode,
+                        (&cwd, curr_wd),
+                        Some(&program),
+                        vars,
+                    ));
+                }
+            }
+            commands
+        };
+
+        // Neither the locale nor the time zone of the parent are lost, while the rest of its environment is..
+        let vars = crate::environment::propagated_env_in(&parent);
+        for command in commands(&vars) {
+            assert_eq!(
+                run_relaunched(&command),
+                "de_DE.UTF-8|en_GB.UTF-8|Europe/Berlin|",
+                "{command:?}"
+            );
+        }
+        // ..and the same goes for the command typed into `ITerm2`..
+        let typed = iterm2_command(&ShellQuoted::quote("/"), Some(&program), &vars);
+        let output = std::process::Command::new("/bin/sh")
+            .args(["-c", typed.as_str()])
+            .env_clear()
+            .output()
+            .unwrap();
+        assert_eq!(output.stdout, b"de_DE.UTF-8|en_GB.UTF-8|Europe/Berlin|");
+
+        // ..while nothing is made up when the parent has neither set..
+        let vars = crate::environment::propagated_env_in(&HashMap::<String, String>::new());
+        for command in commands(&vars) {
+            assert_eq!(run_relaunched(&command), "|||", "{command:?}");
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Helpers shared by the unit tests.
+
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Serialises the unit tests reading or changing process-wide state, e.g. the capability overrides and rules,
+/// which the test harness would otherwise race across threads.
+static GLOBAL_STATE: RwLock<()> = RwLock::new(());
+
+/// Held by tests reading process-wide state, e.g. resolving capabilities.
+pub(crate) fn reading_globals() -> RwLockReadGuard<'static, ()> {
+    GLOBAL_STATE
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Held by tests changing process-wide state, which must restore it before releasing the guard.
+pub(crate) fn writing_globals() -> RwLockWriteGuard<'static, ()> {
+    GLOBAL_STATE
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Creates an empty directory for the calling test, under the system's temporary directory, named after the test so
+/// tests running at once don't share it.
+pub(crate) fn temp_dir(test: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("terminal_relaunch-{}-{test}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
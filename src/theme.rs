@@ -0,0 +1,196 @@
+//! Detection of whether the terminal uses a dark or light colour scheme, so programs can pick readable colours.
+//!
+//! # Reliability
+//! In order of preference:
+//! *   The background colour reported by the terminal (`OSC 11`), with the `tty-query` feature and
+//!     `CapabilityDetection::Probe` set, see `capabilities::set_capability_detection()`.
+//! *   The `COLORFGBG` environment variable, exported by e.g. `rxvt`, `Konsole` and `iTerm2`. It only
+//!     reflects the palette indices at startup, and is not updated if the theme changes.
+
+use crate::environment::{Environment, SystemEnvironment};
+
+/// Environment variable containing the default foreground and background palette indices, e.g. `15;0`.
+pub const COLORFGBG_VAR: &str = "COLORFGBG";
+
+/// Returns the default `(foreground, background)` palette indices from `COLORFGBG`, if set and valid.
+#[inline]
+#[must_use]
+pub fn colorfgbg() -> Option<(u8, u8)> {
+    colorfgbg_in(&SystemEnvironment)
+}
+
+/// Returns the default `(foreground, background)` palette indices from `COLORFGBG` in the given environment.
+#[inline]
+#[must_use]
+pub fn colorfgbg_in(env: &(impl Environment + ?Sized)) -> Option<(u8, u8)> {
+    env.var(COLORFGBG_VAR).as_deref().and_then(parse_colorfgbg)
+}
+
+/// Parses a `COLORFGBG` value into `(foreground, background)` palette indices.
+///
+/// Both the 2 field (`fg;bg`, e.g. `Konsole`'s `15;0`) and 3 field (`fg;xpm;bg`, e.g. `rxvt`'s `0;default;15`)
+/// forms are supported, the middle field is ignored.
+///
+/// # Returns
+/// `None` if the value has another number of fields, or the foreground or background isn't a palette index
+/// (e.g. `default;default`, or empty fields).
+///
+/// # Example
+/// ```
+/// use terminal_relaunch::theme::parse_colorfgbg;
+///
+/// assert_eq!(parse_colorfgbg("15;0"), Some((15, 0)));
+/// assert_eq!(parse_colorfgbg("0;default;15"), Some((0, 15)));
+/// assert_eq!(parse_colorfgbg("default;default"), None);
+/// ```
+#[must_use]
+pub fn parse_colorfgbg(value: &str) -> Option<(u8, u8)> {
+    let fields = value.trim().split(';').collect::<Vec<_>>();
+    let ([foreground, background] | [foreground, _, background]) = fields.as_slice() else {
+        return None;
+    };
+
+    Some((foreground.parse().ok()?, background.parse().ok()?))
+}
+
+/// Returns `true` if the given palette index is a dark colour in the standard 16 colour palette.
+///
+/// # Notes
+/// Black (`0`), the non-bright colours except white (`1`-`6`), and bright black (`8`) are dark, as are indices
+/// outside the 16 colour palette, which can't be classified without knowing the palette.
+#[inline]
+#[must_use]
+pub fn is_dark_palette_index(index: u8) -> bool {
+    !matches!(index, 7 | 9..=15)
+}
+
+/// Returns `true` if the given 8-bit RGB colour is dark, i.e. its relative luminance is below half.
+#[inline]
+#[must_use]
+pub fn is_dark_colour((red, green, blue): (u8, u8, u8)) -> bool {
+    let luminance = 0.2126 * f64::from(red) + 0.7152 * f64::from(green) + 0.0722 * f64::from(blue);
+    luminance < 127.5
+}
+
+/// Returns `true` if the terminal uses a dark background, `false` for a light background.
+///
+/// # Notes
+/// See the module documentation for the order sources are tried in. Returns `None` if no source is available.
+#[must_use]
+pub fn prefers_dark_theme() -> Option<bool> {
+    prefers_dark_theme_with(query_background_colour, &SystemEnvironment)
+}
+
+/// Returns `true` if the terminal uses a dark background, from the background colour queried with `query`, or the
+/// given environment, see `prefers_dark_theme()`.
+fn prefers_dark_theme_with(
+    query: impl FnOnce() -> Option<(u8, u8, u8)>,
+    env: &(impl Environment + ?Sized),
+) -> Option<bool> {
+    if let Some(colour) = query() {
+        return Some(is_dark_colour(colour));
+    }
+
+    colorfgbg_in(env).map(|(_, background)| is_dark_palette_index(background))
+}
+
+/// Queries the terminal's background colour, if probing is enabled.
+fn query_background_colour() -> Option<(u8, u8, u8)> {
+    #[cfg(feature = "tty-query")]
+    if crate::capabilities::capability_detection()
+        == crate::capabilities::CapabilityDetection::Probe
+    {
+        return crate::query::query_background_colour(crate::capabilities::PROBE_TIMEOUT)
+            .ok()
+            .flatten();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        COLORFGBG_VAR, colorfgbg_in, is_dark_colour, is_dark_palette_index, parse_colorfgbg,
+        prefers_dark_theme_with,
+    };
+
+    fn colorfgbg(value: &str) -> HashMap<String, String> {
+        HashMap::from([(COLORFGBG_VAR.to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn real_world_colorfgbg_values_are_parsed() {
+        for (source, value, parsed) in [
+            ("Konsole, dark", "15;0", Some((15, 0))),
+            ("Konsole, light", "0;15", Some((0, 15))),
+            ("rxvt, light", "0;default;15", Some((0, 15))),
+            ("rxvt, dark", "7;default;0", Some((7, 0))),
+            ("iTerm2, dark", "7;0", Some((7, 0))),
+            ("iTerm2, light", "12;15", Some((12, 15))),
+            ("256 colour palette", "252;234", Some((252, 234))),
+            ("trailing line break", "15;0\n", Some((15, 0))),
+            ("rxvt without a palette", "default;default", None),
+            ("rxvt without a background", "0;default;default", None),
+            ("empty fields", ";", None),
+            ("empty background", "15;", None),
+            ("empty", "", None),
+            ("one field", "15", None),
+            ("four fields", "15;0;0;0", None),
+            ("out of range", "15;256", None),
+            ("negative", "15;-1", None),
+        ] {
+            assert_eq!(parse_colorfgbg(value), parsed, "{source}: {value:?}");
+            assert_eq!(
+                colorfgbg_in(&colorfgbg(value)),
+                parsed,
+                "{source}: {value:?}"
+            );
+        }
+        assert_eq!(colorfgbg_in(&HashMap::<String, String>::new()), None);
+    }
+
+    #[test]
+    fn palette_indices_and_colours_are_classified() {
+        let dark: Vec<u8> = (0..=15)
+            .filter(|index| is_dark_palette_index(*index))
+            .collect();
+        assert_eq!(dark, [0, 1, 2, 3, 4, 5, 6, 8]);
+        assert!(is_dark_palette_index(234));
+
+        assert!(is_dark_colour((0, 0, 0)));
+        assert!(is_dark_colour((0x1e, 0x1e, 0x2e)));
+        assert!(!is_dark_colour((0xff, 0xff, 0xff)));
+        assert!(!is_dark_colour((0xfd, 0xf6, 0xe3)));
+        // Green counts for far more than blue..
+        assert!(!is_dark_colour((0, 0xc0, 0)));
+        assert!(is_dark_colour((0, 0, 0xff)));
+    }
+
+    #[test]
+    fn queried_background_takes_precedence_over_colorfgbg() {
+        let light = colorfgbg("0;15");
+        assert_eq!(
+            prefers_dark_theme_with(|| Some((0, 0, 0)), &light),
+            Some(true)
+        );
+        assert_eq!(prefers_dark_theme_with(|| None, &light), Some(false));
+        assert_eq!(
+            prefers_dark_theme_with(|| None, &colorfgbg("15;0")),
+            Some(true)
+        );
+        assert_eq!(
+            prefers_dark_theme_with(|| None, &colorfgbg("default;default")),
+            None
+        );
+        assert_eq!(
+            prefers_dark_theme_with(
+                || Some((0xff, 0xff, 0xff)),
+                &HashMap::<String, String>::new()
+            ),
+            Some(false)
+        );
+    }
+}
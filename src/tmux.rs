@@ -0,0 +1,591 @@
+//! Detection of terminal multiplexers, the outer terminal of `tmux` clients, and the features `tmux` actually
+//! passes through to its client terminal.
+//!
+//! Inside `tmux`, `TERM` and `TERM_PROGRAM` describe `tmux` itself, and other variables are inherited from
+//! whichever terminal started the `tmux` server, not the terminal attached to it now. The outer terminal is
+//! instead identified from the client `tmux` reports (see `client_terminal()`), and `TerminalType::Tmux` is
+//! reported when neither it nor any inherited variable identifies a terminal.
+//!
+//! Even when the outer terminal supports RGB colours, `tmux` only passes them through if its
+//! `terminal-features` (or `terminal-overrides` before `tmux 3.2`) enable RGB for the outer `TERM`.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use terminal_relaunch::tmux::{ClientTerminal, Multiplexer, multiplexer_in, parse_client_terminal};
+//! use terminal_relaunch::version::TerminalVersion;
+//! use terminal_relaunch::{OperatingSystem, TerminalType};
+//!
+//! let env = HashMap::from([
+//!     ("TMUX".to_string(), "/tmp/tmux-1000/default,1234,0".to_string()),
+//!     ("TERM".to_string(), "tmux-256color".to_string()),
+//! ]);
+//! assert_eq!(multiplexer_in(&env), Some(Multiplexer::Tmux));
+//!
+//! let screen = HashMap::from([("STY".to_string(), "1234.pts-0.host".to_string())]);
+//! assert_eq!(multiplexer_in(&screen), Some(Multiplexer::Screen));
+//! assert_eq!(multiplexer_in(&HashMap::<String, String>::new()), None);
+//!
+//! // Identified from the client's `XTVERSION` reply, with its version..
+//! assert_eq!(
+//!     parse_client_terminal("WezTerm 20240203-110809-5046fc22", "xterm-256color", OperatingSystem::Linux),
+//!     Some(ClientTerminal {
+//!         terminal: TerminalType::WezTerm,
+//!         version: Some(TerminalVersion::new(20240203, 110809, 0)),
+//!     }),
+//! );
+//! // Or from its `TERM`, when older clients or terminals don't report one..
+//! assert_eq!(
+//!     parse_client_terminal("", "foot", OperatingSystem::Linux),
+//!     Some(ClientTerminal { terminal: TerminalType::Foot, version: None }),
+//! );
+//! // Terminals that can't run on the operating system, or generic replies, aren't identified..
+//! assert_eq!(parse_client_terminal("iTerm2 3.5.10", "xterm-256color", OperatingSystem::Linux), None);
+//! assert_eq!(parse_client_terminal("VTE(7600)", "xterm-256color", OperatingSystem::Linux), None);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::process::Command;
+use std::sync::LazyLock;
+
+use strum::IntoEnumIterator;
+
+use crate::capabilities::ColourDepth;
+use crate::environment::{Environment, SystemEnvironment};
+use crate::terminal_providers::{TERM_PROGRAM_VAR, TERM_VAR};
+use crate::version::TerminalVersion;
+use crate::{OperatingSystem, TerminalSignature, TerminalType};
+
+/// Environment variable set by `tmux` inside its panes.
+pub const TMUX_VAR: &str = "TMUX";
+
+/// Environment variable set by `GNU Screen` inside its windows.
+pub const STY_VAR: &str = "STY";
+
+/// Environment variable set by `Zellij` inside its panes.
+pub const ZELLIJ_VAR: &str = "ZELLIJ";
+
+/// Represents a terminal multiplexer the program is running inside, see `multiplexer()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Multiplexer {
+    /// `tmux`.
+    Tmux,
+    /// `GNU Screen`.
+    Screen,
+    /// `Zellij`.
+    Zellij,
+}
+
+impl Multiplexer {
+    /// Returns the name of the multiplexer.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tmux => "tmux",
+            Self::Screen => "GNU Screen",
+            Self::Zellij => "Zellij",
+        }
+    }
+}
+
+impl Display for Multiplexer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The outer terminal of a `tmux` client, see `client_terminal()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientTerminal {
+    /// The terminal type.
+    pub terminal: TerminalType,
+    /// The version of the terminal, if it reported one.
+    pub version: Option<TerminalVersion>,
+}
+
+/// The features `tmux` passes through to its client terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TmuxFeatures {
+    /// If RGB (24-bit) colours are passed through.
+    pub rgb: bool,
+    /// If `OSC 8` hyperlinks are passed through.
+    pub hyperlinks: bool,
+    /// If `allow-passthrough` is enabled, allowing e.g. image protocols to reach the outer terminal.
+    pub passthrough: bool,
+}
+
+impl TmuxFeatures {
+    /// Returns the highest colour depth `tmux` passes through.
+    #[inline]
+    #[must_use]
+    pub fn max_colour_depth(&self) -> ColourDepth {
+        if self.rgb {
+            ColourDepth::TrueColour
+        } else {
+            ColourDepth::Ansi256
+        }
+    }
+}
+
+/// Returns `true` if the program is running inside `tmux`.
+#[inline]
+#[must_use]
+pub fn is_inside_tmux() -> bool {
+    is_inside_tmux_in(&SystemEnvironment)
+}
+
+/// Returns `true` if the given environment is inside `tmux`.
+#[inline]
+#[must_use]
+pub fn is_inside_tmux_in(env: &(impl Environment + ?Sized)) -> bool {
+    env.var(TMUX_VAR).is_some_and(|value| !value.is_empty())
+}
+
+/// Returns the terminal multiplexer the program is running inside, if any.
+#[inline]
+#[must_use]
+pub fn multiplexer() -> Option<Multiplexer> {
+    multiplexer_in(&SystemEnvironment)
+}
+
+/// Returns the terminal multiplexer the given environment is inside, if any.
+///
+/// # Notes
+/// `tmux` is detected from `TMUX_VAR`, or `TERM_PROGRAM` being `tmux` (e.g. when `TMUX` was removed to allow
+/// nesting), and is checked first, as it's often run inside the other multiplexers' panes.
+#[must_use]
+pub fn multiplexer_in(env: &(impl Environment + ?Sized)) -> Option<Multiplexer> {
+    let set = |name| env.var(name).is_some_and(|value| !value.is_empty());
+
+    if is_inside_tmux_in(env)
+        || env
+            .var(TERM_PROGRAM_VAR)
+            .is_some_and(|program| program.trim().eq_ignore_ascii_case("tmux"))
+    {
+        Some(Multiplexer::Tmux)
+    } else if set(ZELLIJ_VAR) {
+        Some(Multiplexer::Zellij)
+    } else if set(STY_VAR) {
+        Some(Multiplexer::Screen)
+    } else {
+        None
+    }
+}
+
+/// The outer terminal of the `tmux` client the program is displayed in, queried once per process.
+static CLIENT_TERMINAL: LazyLock<Option<ClientTerminal>> = LazyLock::new(query_client_terminal);
+
+/// Returns the outer terminal of the `tmux` client the program is displayed in, if running inside `tmux` and it
+/// could be identified, see `parse_client_terminal()`.
+///
+/// # Notes
+/// Queries the `tmux` server once per process, for the client's `client_termtype` (`tmux 3.3` and later) and
+/// `client_termname`.
+#[inline]
+#[must_use]
+pub fn client_terminal() -> Option<ClientTerminal> {
+    *CLIENT_TERMINAL
+}
+
+/// Queries the running `tmux` server for its client's outer terminal.
+fn query_client_terminal() -> Option<ClientTerminal> {
+    if !is_inside_tmux() {
+        return None;
+    }
+
+    let output = run_tmux(&[
+        "display-message",
+        "-p",
+        "#{client_termtype}\t#{client_termname}",
+    ])?;
+    let (termtype, termname) = output.trim_end().split_once('\t')?;
+    let client = parse_client_terminal(termtype, termname, OperatingSystem::current());
+    if let Some(client) = client {
+        crate::logging::info!(
+            "Identified the tmux client terminal as {}.",
+            client.terminal
+        );
+    }
+
+    client
+}
+
+/// Identifies the outer terminal of a `tmux` client from its `client_termtype`, e.g. `"kitty(0.39.1)"` or
+/// `"WezTerm 20240203-110809-5046fc22"`, and its `client_termname` (the outer `TERM`), on the given operating
+/// system.
+///
+/// # Returns
+/// *   The terminal whose `TerminalType::id()` is the name in `termtype`, with the version following it, if any.
+/// *   Otherwise the terminal identified from `termname` alone, as detection from `TERM` would, without a version.
+/// *   `None` if neither identifies a terminal that runs on the operating system, e.g. for generic names such as
+///     `VTE` or `xterm-256color`.
+#[must_use]
+pub fn parse_client_terminal(
+    termtype: &str,
+    termname: &str,
+    os: OperatingSystem,
+) -> Option<ClientTerminal> {
+    let termtype = termtype.trim();
+    let (name, version) = termtype
+        .split_once(['(', ' '])
+        .map_or((termtype, ""), |(name, rest)| {
+            (name, rest.trim_end_matches(')'))
+        });
+
+    let reported = (!name.is_empty())
+        .then(|| {
+            TerminalType::iter().find(|terminal| {
+                terminal.id().eq_ignore_ascii_case(name)
+                    && os.compatible_with_target(terminal.target_os())
+            })
+        })
+        .flatten()
+        .map(|terminal| ClientTerminal {
+            terminal,
+            version: TerminalVersion::parse(version),
+        });
+
+    reported.or_else(|| {
+        let env = HashMap::from([(TERM_VAR.to_string(), termname.trim().to_string())]);
+        crate::get_possible_terminal_identifiers_for(os)
+            .filter(|identifier| {
+                !identifier
+                    .signatures
+                    .iter()
+                    .any(TerminalSignature::accesses_system)
+            })
+            .find(|identifier| identifier.matches_in(&env))
+            .map(|identifier| ClientTerminal {
+                terminal: identifier.kind,
+                version: None,
+            })
+    })
+}
+
+/// The features of the `tmux` server the program is running in, queried once per process.
+static TMUX_FEATURES: LazyLock<Option<TmuxFeatures>> = LazyLock::new(query_tmux_features);
+
+/// Returns the features `tmux` passes through, if running inside `tmux`.
+///
+/// # Notes
+/// Queries the `tmux` server once per process. If the query fails, conservative features are returned,
+/// i.e. no RGB colours, hyperlinks or passthrough.
+#[inline]
+#[must_use]
+pub fn tmux_features() -> Option<TmuxFeatures> {
+    *TMUX_FEATURES
+}
+
+/// Queries the running `tmux` server for its client's features.
+fn query_tmux_features() -> Option<TmuxFeatures> {
+    if !is_inside_tmux() {
+        return None;
+    }
+
+    let term = std::env::var("TERM").unwrap_or_default();
+    Some(features_from_tmux(run_tmux, &term))
+}
+
+/// Returns the features `tmux` passes through to a client with the given `TERM`, running `tmux` commands
+/// with `run`, see `tmux_features()`.
+fn features_from_tmux(run: impl Fn(&[&str]) -> Option<String>, term: &str) -> TmuxFeatures {
+    let passthrough =
+        run(&["show", "-gv", "allow-passthrough"]).is_some_and(|value| parse_passthrough(&value));
+
+    // `client_termfeatures` is available from `tmux 3.2`, older versions only have `terminal-overrides`..
+    let features = run(&["display-message", "-p", "#{client_termfeatures}"])
+        .filter(|features| !features.trim().is_empty())
+        .map(|features| parse_client_termfeatures(&features))
+        .or_else(|| {
+            run(&["show", "-g", "terminal-overrides"])
+                .map(|overrides| parse_terminal_overrides(&overrides, term))
+        });
+
+    TmuxFeatures {
+        passthrough,
+        ..features.unwrap_or_else(|| {
+            crate::logging::warning!("Failed to query tmux features, assuming no RGB colours.");
+            TmuxFeatures::default()
+        })
+    }
+}
+
+/// Runs `tmux` with the given arguments, returning its output if successful.
+fn run_tmux(args: &[&str]) -> Option<String> {
+    let output = Command::new("tmux").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses the output of `display-message -p '#{client_termfeatures}'`, e.g. `"256,RGB,title,hyperlinks"`.
+#[must_use]
+pub fn parse_client_termfeatures(output: &str) -> TmuxFeatures {
+    let mut features = TmuxFeatures::default();
+
+    for feature in output.trim().split(',').map(str::trim) {
+        match feature {
+            "RGB" => features.rgb = true,
+            "hyperlinks" => features.hyperlinks = true,
+            _ => {}
+        }
+    }
+
+    features
+}
+
+/// Parses the output of `show -g terminal-overrides`, used before `tmux 3.2`, for the given outer `TERM`.
+///
+/// Each line has the form `terminal-overrides[0] "xterm*:Tc"`, where RGB colours are enabled by
+/// the `Tc` or `RGB` capabilities for a pattern matching `TERM`.
+#[must_use]
+pub fn parse_terminal_overrides(output: &str, term: &str) -> TmuxFeatures {
+    let rgb = output
+        .lines()
+        .filter_map(|line| {
+            line.split_once(' ')
+                .map(|(_, value)| value.trim().trim_matches('"'))
+        })
+        .filter_map(|value| value.split_once(':'))
+        .any(|(pattern, capabilities)| {
+            glob_matches(pattern, term)
+                && capabilities
+                    .split(':')
+                    .any(|capability| matches!(capability, "Tc" | "RGB"))
+        });
+
+    TmuxFeatures {
+        rgb,
+        ..TmuxFeatures::default()
+    }
+}
+
+/// Parses the value of the `allow-passthrough` option, `on` or `all` enable passthrough.
+#[inline]
+#[must_use]
+pub fn parse_passthrough(value: &str) -> bool {
+    matches!(value.trim(), "on" | "all")
+}
+
+/// Matches a `tmux` terminal pattern, which only supports `*` wildcards.
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return true;
+    };
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TmuxFeatures, features_from_tmux, parse_client_termfeatures, parse_passthrough,
+        parse_terminal_overrides,
+    };
+    use crate::capabilities::ColourDepth;
+
+    /// `#{client_termfeatures}` as printed by `tmux 3.2` for a `TERM=xterm-256color` client, whose default
+    /// `terminal-features` don't enable RGB colours.
+    const TMUX_3_2_XTERM: &str =
+        "256,bpaste,ccolour,clipboard,cstyle,extkeys,focus,margins,mouse,title\n";
+
+    /// As `TMUX_3_2_XTERM`, with `set -as terminal-features ',xterm-256color:RGB'`.
+    const TMUX_3_2_XTERM_RGB: &str =
+        "256,bpaste,ccolour,clipboard,cstyle,extkeys,focus,margins,mouse,RGB,title\n";
+
+    /// `#{client_termfeatures}` as printed by `tmux 3.3` for a `kitty` client, which `tmux` recognises.
+    const TMUX_3_3_KITTY: &str = "256,bpaste,ccolour,clipboard,cstyle,extkeys,focus,margins,mouse,\
+        overline,rectfill,RGB,strikethrough,sync,title,usstyle\n";
+
+    /// `#{client_termfeatures}` as printed by `tmux 3.4`, which added `hyperlinks`, for a `WezTerm` client.
+    const TMUX_3_4_WEZTERM: &str = "256,bpaste,ccolour,clipboard,cstyle,extkeys,focus,hyperlinks,\
+        ignorefkeys,margins,mouse,osc7,overline,rectfill,RGB,sixel,strikethrough,sync,title,usstyle\n";
+
+    /// `show -g terminal-overrides` as printed by `tmux 3.1`, with the default overrides and one added with
+    /// `set -ga terminal-overrides ',xterm-256color:Tc'`.
+    const TMUX_3_1_OVERRIDES: &str = "terminal-overrides[0] \"xterm*:XT:Ms=\\\\E]52;%p1%s;%p2%s\\\\007:\
+        Cs=\\\\E]12;%p1%s\\\\007:Cr=\\\\E]112\\\\007:Ss=\\\\E[%p1%d q:Se=\\\\E[2 q\"\n\
+        terminal-overrides[1] screen*:XT\n\
+        terminal-overrides[2] xterm-256color:Tc\n";
+
+    const fn features(rgb: bool, hyperlinks: bool) -> TmuxFeatures {
+        TmuxFeatures {
+            rgb,
+            hyperlinks,
+            passthrough: false,
+        }
+    }
+
+    #[test]
+    fn client_termfeatures_of_each_version_are_parsed() {
+        for (version, output, expected) in [
+            ("3.2", TMUX_3_2_XTERM, features(false, false)),
+            ("3.2", TMUX_3_2_XTERM_RGB, features(true, false)),
+            ("3.3", TMUX_3_3_KITTY, features(true, false)),
+            ("3.4", TMUX_3_4_WEZTERM, features(true, true)),
+        ] {
+            assert_eq!(
+                parse_client_termfeatures(output),
+                expected,
+                "{version}: {output}"
+            );
+        }
+    }
+
+    #[test]
+    fn client_termfeatures_need_exact_feature_names() {
+        assert_eq!(parse_client_termfeatures(""), TmuxFeatures::default());
+        assert_eq!(
+            parse_client_termfeatures(" RGB , hyperlinks "),
+            features(true, true)
+        );
+        assert_eq!(
+            parse_client_termfeatures("rgb,Tc,hyperlink"),
+            TmuxFeatures::default()
+        );
+    }
+
+    #[test]
+    fn terminal_overrides_before_3_2_are_parsed_for_the_outer_term() {
+        assert!(parse_terminal_overrides(TMUX_3_1_OVERRIDES, "xterm-256color").rgb);
+        // Only the default overrides match other terminals, which don't enable RGB colours..
+        assert!(!parse_terminal_overrides(TMUX_3_1_OVERRIDES, "xterm").rgb);
+        assert!(!parse_terminal_overrides(TMUX_3_1_OVERRIDES, "screen-256color").rgb);
+        assert!(!parse_terminal_overrides(TMUX_3_1_OVERRIDES, "xterm-256color").hyperlinks);
+
+        for (overrides, term, rgb) in [
+            ("terminal-overrides[0] \"*:RGB\"\n", "alacritty", true),
+            ("terminal-overrides[0] \"xterm*:Tc\"\n", "xterm-kitty", true),
+            (
+                "terminal-overrides[0] \"*256col*:Tc\"\n",
+                "rxvt-unicode-256color",
+                true,
+            ),
+            (
+                "terminal-overrides[0] \"*256col*:Tc\"\n",
+                "rxvt-unicode",
+                false,
+            ),
+            (
+                "terminal-overrides[0] \"xterm*:Tcx\"\n",
+                "xterm-256color",
+                false,
+            ),
+            ("", "xterm-256color", false),
+        ] {
+            assert_eq!(
+                parse_terminal_overrides(overrides, term).rgb,
+                rgb,
+                "{overrides:?} {term}"
+            );
+        }
+    }
+
+    #[test]
+    fn passthrough_is_only_enabled_by_on_or_all() {
+        for (value, enabled) in [
+            ("on\n", true),
+            ("all\n", true),
+            ("off\n", false),
+            ("", false),
+        ] {
+            assert_eq!(parse_passthrough(value), enabled, "{value:?}");
+        }
+    }
+
+    /// Runs `tmux` commands against captured outputs, failing the commands without one.
+    fn captured<'a>(outputs: &'a [(&str, &str)]) -> impl Fn(&[&str]) -> Option<String> + 'a {
+        |args| {
+            let command = args.join(" ");
+            outputs
+                .iter()
+                .find(|(captured, _)| *captured == command)
+                .map(|(_, output)| (*output).to_string())
+        }
+    }
+
+    #[test]
+    fn features_are_queried_from_each_version() {
+        const TERMFEATURES: &str = "display-message -p #{client_termfeatures}";
+        const OVERRIDES: &str = "show -g terminal-overrides";
+        const PASSTHROUGH: &str = "show -gv allow-passthrough";
+
+        for (version, outputs, expected) in [
+            (
+                "3.4",
+                &[(TERMFEATURES, TMUX_3_4_WEZTERM), (PASSTHROUGH, "on\n")][..],
+                TmuxFeatures {
+                    passthrough: true,
+                    ..features(true, true)
+                },
+            ),
+            (
+                "3.3",
+                &[(TERMFEATURES, TMUX_3_3_KITTY), (PASSTHROUGH, "off\n")][..],
+                features(true, false),
+            ),
+            (
+                "3.2",
+                &[
+                    (TERMFEATURES, TMUX_3_2_XTERM),
+                    (OVERRIDES, TMUX_3_1_OVERRIDES),
+                ][..],
+                features(false, false),
+            ),
+            // `client_termfeatures` is empty before `tmux 3.2`, where `allow-passthrough` doesn't exist..
+            (
+                "3.1",
+                &[(TERMFEATURES, "\n"), (OVERRIDES, TMUX_3_1_OVERRIDES)][..],
+                features(true, false),
+            ),
+            (
+                "3.1",
+                &[(OVERRIDES, TMUX_3_1_OVERRIDES)][..],
+                features(true, false),
+            ),
+            // ..and a failed query is conservative, capping colours to 256..
+            ("none", &[][..], TmuxFeatures::default()),
+            (
+                "none",
+                &[(PASSTHROUGH, "all\n")][..],
+                TmuxFeatures {
+                    passthrough: true,
+                    ..TmuxFeatures::default()
+                },
+            ),
+        ] {
+            let queried = features_from_tmux(captured(outputs), "xterm-256color");
+            assert_eq!(queried, expected, "{version}: {outputs:?}");
+        }
+
+        assert_eq!(
+            TmuxFeatures::default().max_colour_depth(),
+            ColourDepth::Ansi256
+        );
+        assert_eq!(
+            features(true, false).max_colour_depth(),
+            ColourDepth::TrueColour
+        );
+    }
+}
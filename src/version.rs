@@ -0,0 +1,407 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::environment::{Environment, SystemEnvironment};
+use crate::{TerminalSignature, TerminalType};
+
+/// Common environment variable `TERM_PROGRAM_VERSION` used for terminal version detection.
+pub const TERM_PROGRAM_VERSION_VAR: &str = "TERM_PROGRAM_VERSION";
+/// Environment variable `WEZTERM_VERSION`, which may contain the version of `WezTerm` when `TERM_PROGRAM_VERSION`
+/// has been replaced, e.g. by `tmux`.
+pub const WEZTERM_VERSION_VAR: &str = "WEZTERM_VERSION";
+/// Environment variable `TERMINAL_VERSION_STRING`, containing the version of `Contour`, e.g. `0.4.3.6442`.
+pub const CONTOUR_VERSION_VAR: &str = "TERMINAL_VERSION_STRING";
+
+/// Represents the version of a terminal, as reported by the terminal itself.
+///
+/// Versions are compared component wise, `major` first, missing components are treated as `0`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerminalVersion {
+    /// The major version component.
+    pub major: u32,
+    /// The minor version component.
+    pub minor: u32,
+    /// The patch version component.
+    pub patch: u32,
+}
+
+impl TerminalVersion {
+    /// Creates a new terminal version from the given components.
+    #[inline]
+    #[must_use]
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses a dotted version string, e.g. `"0.21.2"` or `"3.4"`, or a date based version, see `parse_dated()`.
+    ///
+    /// Any trailing non-numeric characters in a component are ignored, e.g. `"3.4a"` => `3.4.0`.
+    /// # Returns
+    /// *   `Some(version)` if at least a major version component could be parsed.
+    /// *   `None` if the string does not start with a version number.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Some(version) = Self::parse_dated(value) {
+            return Some(version);
+        }
+
+        let mut components = value.trim().split('.').map(leading_number);
+
+        let major = components.next().flatten()?;
+        let minor = components.next().flatten().unwrap_or(0);
+        let patch = components.next().flatten().unwrap_or(0);
+
+        Some(Self::new(major, minor, patch))
+    }
+
+    /// Parses a date based version string as used by `WezTerm`, e.g. `"20240203-110809-5046fc22"`.
+    ///
+    /// The date (`YYYYMMDD`) becomes the major component and the time (`HHMMSS`) the minor component,
+    /// so dated versions order chronologically, the trailing commit hash is ignored.
+    /// # Returns
+    /// *   `Some(version)` if the string starts with an 8 digit date followed by a 6 digit time.
+    /// *   `None` otherwise.
+    #[must_use]
+    pub fn parse_dated(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+
+        let date = parts.next()?;
+        let time = parts.next()?;
+        if date.len() != 8 || time.len() != 6 {
+            return None;
+        }
+        if !date.bytes().chain(time.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(Self::new(date.parse().ok()?, time.parse().ok()?, 0))
+    }
+}
+
+/// Parses the leading decimal digits of the given string, if there are any.
+#[inline]
+fn leading_number(value: &str) -> Option<u32> {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+impl Display for TerminalVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for TerminalVersion {
+    type Err = InvalidVersion;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or_else(|| InvalidVersion(s.to_string()))
+    }
+}
+
+/// Error returned when a version or version requirement string could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid version string `{0}`.")]
+pub struct InvalidVersion(pub String);
+
+/// Represents a requirement that a terminal version must meet.
+///
+/// # Notes
+/// Serialized as its requirement string, e.g. `"<0.22"`, see `VersionReq::from_str()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum VersionReq {
+    /// The version must be strictly lower than the given version (`<`).
+    Less(TerminalVersion),
+    /// The version must be greater than or equal to the given version (`>=`).
+    AtLeast(TerminalVersion),
+    /// The version must be exactly the given version.
+    Exact(TerminalVersion),
+}
+
+impl VersionReq {
+    /// Returns `true` if the given version meets this requirement.
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, version: &TerminalVersion) -> bool {
+        match self {
+            Self::Less(req) => version.cmp(req) == Ordering::Less,
+            Self::AtLeast(req) => version.cmp(req) != Ordering::Less,
+            Self::Exact(req) => version == req,
+        }
+    }
+}
+
+impl Display for VersionReq {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Less(version) => write!(f, "<{version}"),
+            Self::AtLeast(version) => write!(f, ">={version}"),
+            Self::Exact(version) => write!(f, "{version}"),
+        }
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = InvalidVersion;
+
+    /// Parses a requirement string, e.g. `"<0.21"`, `">=0.30.1"` or `"0.25"` (exact).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let invalid = || InvalidVersion(s.to_string());
+
+        if let Some(rest) = trimmed.strip_prefix(">=") {
+            rest.parse().map(Self::AtLeast).map_err(|_| invalid())
+        } else if let Some(rest) = trimmed.strip_prefix('<') {
+            rest.parse().map(Self::Less).map_err(|_| invalid())
+        } else {
+            let rest = trimmed.strip_prefix('=').unwrap_or(trimmed);
+            rest.parse().map(Self::Exact).map_err(|_| invalid())
+        }
+    }
+}
+
+impl TryFrom<String> for VersionReq {
+    type Error = InvalidVersion;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<VersionReq> for String {
+    fn from(req: VersionReq) -> Self {
+        req.to_string()
+    }
+}
+
+/// Returns the version of `Terminal.app` from the given environment, i.e. `TERM_PROGRAM_VERSION` if `TERM_PROGRAM`
+/// is `Apple_Terminal`, e.g. `453` on `macOS 14`.
+///
+/// # Notes
+/// Full unicode rendering is only supported since `capabilities::MIN_APPLE_TERMINAL_UNICODE_VERSION`, RGB colours
+/// aren't supported by any version.
+///
+/// # Example
+/// ```rust
+/// use terminal_relaunch::TerminalType;
+/// use terminal_relaunch::capabilities::{resolve_full_unicode_support, resolve_rgb_ansi_colour_support};
+/// use terminal_relaunch::decision::RelaunchDecision;
+/// use terminal_relaunch::simulation::{Scenario, simulate};
+/// use terminal_relaunch::version::{TerminalVersion, apple_terminal_version_in};
+///
+/// let apple = |version: Option<&str>| {
+///     let scenario = Scenario::macos_apple_terminal_no_thirdparty();
+///     match version {
+///         Some(version) => scenario.var("TERM_PROGRAM_VERSION", version),
+///         None => scenario,
+///     }
+/// };
+///
+/// // A modern `Terminal.app` renders emojis, an old one or one of unknown version doesn't..
+/// for (version, parsed, full_unicode) in [
+///     (Some("455"), Some(TerminalVersion::new(455, 0, 0)), true),
+///     (Some("440"), Some(TerminalVersion::new(440, 0, 0)), true),
+///     (Some("421.2"), Some(TerminalVersion::new(421, 2, 0)), false),
+///     (Some("unknown"), None, false),
+///     (None, None, false),
+/// ] {
+///     let scenario = apple(version);
+///     assert_eq!(apple_terminal_version_in(&scenario.env), parsed, "{version:?}");
+///     assert_eq!(resolve_full_unicode_support(TerminalType::MacOS, parsed.as_ref()), full_unicode, "{version:?}");
+///     assert!(!resolve_rgb_ansi_colour_support(TerminalType::MacOS, parsed.as_ref()));
+///
+///     // ..but neither supports RGB colours, so relaunching is still attempted..
+///     let outcome = simulate(&scenario);
+///     assert_eq!((outcome.full_unicode, outcome.rgb_ansi_colours), (full_unicode, false), "{version:?}");
+///     assert_eq!(outcome.decision, RelaunchDecision::Attempt, "{version:?}");
+/// }
+///
+/// // Only the version of `Terminal.app` itself is used..
+/// let env = std::collections::HashMap::from([
+///     ("TERM_PROGRAM".to_string(), "iTerm.app".to_string()),
+///     ("TERM_PROGRAM_VERSION".to_string(), "3.5.0".to_string()),
+/// ]);
+/// assert_eq!(apple_terminal_version_in(&env), None);
+/// ```
+#[must_use]
+pub fn apple_terminal_version_in(env: &(impl Environment + ?Sized)) -> Option<TerminalVersion> {
+    // Matched as detection does, so a `Terminal.app` detected from a padded or quoted value keeps its version..
+    if !TerminalSignature::TermProgram("Apple_Terminal").check_in(env) {
+        return None;
+    }
+    env.var(TERM_PROGRAM_VERSION_VAR)
+        .as_deref()
+        .and_then(TerminalVersion::parse)
+}
+
+/// Returns the version of `WezTerm` from the given environment, from `WEZTERM_VERSION`, or `TERM_PROGRAM_VERSION` if
+/// `TERM_PROGRAM` is still `WezTerm`, as it is replaced by multiplexers such as `tmux`.
+#[must_use]
+pub fn wezterm_version_in(env: &(impl Environment + ?Sized)) -> Option<TerminalVersion> {
+    env.var(WEZTERM_VERSION_VAR)
+        .or_else(|| {
+            env.var(crate::terminal_providers::TERM_PROGRAM_VAR)
+                .is_some_and(|program| program.eq_ignore_ascii_case("WezTerm"))
+                .then(|| env.var(TERM_PROGRAM_VERSION_VAR))
+                .flatten()
+        })
+        .as_deref()
+        .and_then(TerminalVersion::parse)
+}
+
+/// Attempts to find the version of the current terminal from the `TERM_PROGRAM_VERSION` environment variable.
+///
+/// # Notes
+/// For `WezTerm`, `WEZTERM_VERSION` is checked first, and `TERM_PROGRAM_VERSION` is only used if `TERM_PROGRAM`
+/// is still `WezTerm`, as it is replaced by multiplexers such as `tmux`. For `Contour`, `TERMINAL_VERSION_STRING`
+/// is checked first. Inside `tmux`, the version reported by the client is used for its outer terminal, see
+/// `tmux::client_terminal()`, and `TERM_PROGRAM_VERSION` only for `TerminalType::Tmux`, as it's the version of
+/// `tmux`. For `Terminal.app`, `TERM_PROGRAM_VERSION` is only used if `TERM_PROGRAM` is still `Apple_Terminal`,
+/// see `apple_terminal_version_in()`.
+#[inline]
+#[must_use]
+pub fn find_current_terminal_version() -> Option<TerminalVersion> {
+    if let Some(client) = crate::tmux::client_terminal()
+        && client.terminal == *crate::CURRENT_TERMINAL
+    {
+        return client.version;
+    }
+
+    let version = match *crate::CURRENT_TERMINAL {
+        TerminalType::WezTerm => return wezterm_version_in(&SystemEnvironment),
+        TerminalType::Contour => std::env::var(CONTOUR_VERSION_VAR)
+            .or_else(|_| std::env::var(TERM_PROGRAM_VERSION_VAR))
+            .ok(),
+        TerminalType::Tmux => std::env::var(TERM_PROGRAM_VERSION_VAR).ok(),
+        TerminalType::MacOS => return apple_terminal_version_in(&SystemEnvironment),
+        _ if crate::tmux::multiplexer() == Some(crate::tmux::Multiplexer::Tmux) => None,
+        _ => std::env::var(TERM_PROGRAM_VERSION_VAR).ok(),
+    };
+
+    version.as_deref().and_then(TerminalVersion::parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TerminalVersion, apple_terminal_version_in, wezterm_version_in};
+    use crate::simulation::{Scenario, simulate};
+    use crate::{OperatingSystem, TerminalType};
+
+    #[test]
+    fn apple_terminal_version_is_kept_for_every_value_detected_as_terminal_app() {
+        for term_program in [
+            "Apple_Terminal",
+            "Apple_Terminal ",
+            "\"Apple_Terminal\"",
+            "apple_terminal",
+        ] {
+            let scenario = Scenario::new(OperatingSystem::MacOS)
+                .var("TERM_PROGRAM", term_program)
+                .var("TERM_PROGRAM_VERSION", "455");
+            assert_eq!(
+                apple_terminal_version_in(&scenario.env),
+                Some(TerminalVersion::new(455, 0, 0)),
+                "{term_program:?}"
+            );
+
+            let outcome = {
+                let _globals = crate::test_support::reading_globals();
+                simulate(&scenario)
+            };
+            assert_eq!(outcome.terminal, TerminalType::MacOS, "{term_program:?}");
+            assert!(outcome.full_unicode, "{term_program:?}");
+        }
+    }
+
+    #[test]
+    fn wezterm_dated_versions_order_by_date_then_time() {
+        let versions = [
+            "20210314-114017-04b7cedd",
+            "20220319-142410-0fcdea07",
+            "20230712-072601-f4abf8fd",
+            "20240127-113634-bbcac864",
+            "20240203-110809-5046fc22",
+            "20240203-110810-00000000",
+        ]
+        .map(|version| TerminalVersion::parse(version).unwrap());
+        assert!(versions.is_sorted());
+        assert!(versions.windows(2).all(|pair| pair[0] != pair[1]));
+        assert_eq!(versions[4], TerminalVersion::new(20_240_203, 110_809, 0));
+
+        // The commit hash is ignored, and anything not starting with a date and time isn't dated..
+        assert_eq!(
+            TerminalVersion::parse_dated("20240203-110809-ffffffff"),
+            TerminalVersion::parse_dated("20240203-110809")
+        );
+        for value in [
+            "0.21.2",
+            "2024020-110809-5046fc22",
+            "20240203-11080-5046fc22",
+            "20240203_110809",
+            "2024020a-110809",
+            "20240203",
+            "",
+        ] {
+            assert_eq!(TerminalVersion::parse_dated(value), None, "{value:?}");
+        }
+        // ..so dotted versions still parse as such, and order before any dated version..
+        let dotted = TerminalVersion::parse("0.21.2").unwrap();
+        assert_eq!(dotted, TerminalVersion::new(0, 21, 2));
+        assert!(dotted < versions[0]);
+    }
+
+    #[test]
+    fn wezterm_wrapped_in_tmux_is_found_with_its_version() {
+        let tmux = Scenario::new(OperatingSystem::Linux)
+            .var("TMUX", "/tmp/tmux-1000/default,1234,0")
+            .var("TERM", "tmux-256color")
+            .var("TERM_PROGRAM", "tmux")
+            .var("TERM_PROGRAM_VERSION", "3.4");
+
+        // Any of the variables surviving `tmux` identifies `WezTerm`..
+        for (name, value) in [
+            ("WEZTERM_PANE", "3"),
+            (
+                "WEZTERM_UNIX_SOCKET",
+                "/run/user/1000/wezterm/gui-sock-1234",
+            ),
+            ("WEZTERM_EXECUTABLE", "/usr/bin/wezterm-gui"),
+        ] {
+            let outcome = {
+                let _globals = crate::test_support::reading_globals();
+                simulate(&tmux.clone().var(name, value))
+            };
+            assert_eq!(outcome.terminal, TerminalType::WezTerm, "{name}");
+        }
+
+        // ..and its version is only taken from `WEZTERM_VERSION`, as `TERM_PROGRAM_VERSION` is the version of `tmux`..
+        assert_eq!(wezterm_version_in(&tmux.env), None);
+        let versioned = tmux.var("WEZTERM_VERSION", "20240203-110809-5046fc22");
+        assert_eq!(
+            wezterm_version_in(&versioned.env),
+            Some(TerminalVersion::new(20_240_203, 110_809, 0))
+        );
+
+        // ..unless `TERM_PROGRAM` is still `WezTerm`..
+        let direct = Scenario::new(OperatingSystem::Linux)
+            .var("TERM_PROGRAM", "WezTerm")
+            .var("TERM_PROGRAM_VERSION", "20230712-072601-f4abf8fd");
+        assert_eq!(
+            wezterm_version_in(&direct.env),
+            Some(TerminalVersion::new(20_230_712, 72_601, 0))
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Classification of the `VS Code` family of editors, whose integrated terminals all match the `VSCode`
+//! terminal identifier.
+//!
+//! Browser based and remote sessions (e.g. `GitHub Codespaces`, `Gitpod`, `code-server`) have no local
+//! terminal to relaunch in, even though the identifier matches.
+
+use std::fmt::Display;
+
+use crate::TerminalType;
+use crate::environment::{Environment, SystemEnvironment};
+
+/// Represents the variant of `VS Code` the integrated terminal belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VSCodeFlavor {
+    /// Desktop `VS Code`, running locally.
+    Desktop,
+    /// The `Cursor` editor, running locally.
+    Cursor,
+    /// `VSCodium`, running locally.
+    VSCodium,
+    /// A `code-server` session, used from a browser.
+    CodeServer,
+    /// A `GitHub Codespaces` session.
+    Codespaces,
+    /// A `Gitpod` workspace.
+    Gitpod,
+    /// A `VS Code` remote server session, e.g. over SSH or in a container.
+    RemoteServer,
+}
+
+impl VSCodeFlavor {
+    /// Returns the name of the flavor.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Desktop => "VS Code",
+            Self::Cursor => "Cursor",
+            Self::VSCodium => "VSCodium",
+            Self::CodeServer => "code-server",
+            Self::Codespaces => "GitHub Codespaces",
+            Self::Gitpod => "Gitpod",
+            Self::RemoteServer => "VS Code Remote",
+        }
+    }
+
+    /// Returns `true` if the session is remote or browser based, so there is no local terminal to relaunch in.
+    #[inline]
+    #[must_use]
+    pub fn is_remote(&self) -> bool {
+        matches!(
+            self,
+            Self::CodeServer | Self::Codespaces | Self::Gitpod | Self::RemoteServer
+        )
+    }
+}
+
+impl Display for VSCodeFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Returns the flavor of `VS Code` the current terminal belongs to, if the current terminal is `VSCode`.
+#[inline]
+#[must_use]
+pub fn vscode_flavor() -> Option<VSCodeFlavor> {
+    (*crate::CURRENT_TERMINAL == TerminalType::VSCode)
+        .then(|| detect_vscode_flavor_in(&SystemEnvironment))
+}
+
+/// Classifies the `VS Code` flavor from the given environment, assuming it is a `VS Code` integrated terminal.
+///
+/// Remote sessions are checked first, as e.g. a `Cursor` window connected to a remote server is remote.
+#[must_use]
+pub fn detect_vscode_flavor_in(env: &(impl Environment + ?Sized)) -> VSCodeFlavor {
+    let ipc_hook = env.var("VSCODE_IPC_HOOK_CLI").unwrap_or_default();
+    let askpass = env
+        .var("VSCODE_GIT_ASKPASS_NODE")
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    if env.var("CODESPACES").is_some_and(|value| value == "true") {
+        VSCodeFlavor::Codespaces
+    } else if env.var("GITPOD_WORKSPACE_ID").is_some() {
+        VSCodeFlavor::Gitpod
+    } else if is_code_server_path(&ipc_hook) || is_code_server_path(&askpass) {
+        VSCodeFlavor::CodeServer
+    } else if env.var("VSCODE_AGENT_FOLDER").is_some()
+        || ipc_hook.contains(".vscode-server")
+        || askpass.contains(".vscode-server")
+    {
+        VSCodeFlavor::RemoteServer
+    } else if env.var("CURSOR_TRACE_ID").is_some() || askpass.contains("cursor") {
+        VSCodeFlavor::Cursor
+    } else if askpass.contains("codium") {
+        VSCodeFlavor::VSCodium
+    } else {
+        VSCodeFlavor::Desktop
+    }
+}
+
+/// Returns `true` if the given path belongs to `code-server`, and not to the `.vscode-server` of a remote session,
+/// whose name contains it.
+#[inline]
+fn is_code_server_path(path: &str) -> bool {
+    path.replace(".vscode-server", "").contains("code-server")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VSCodeFlavor, detect_vscode_flavor_in};
+    use crate::decision::{RelaunchDecision, SkipReason};
+    use crate::simulation::{Scenario, simulate};
+    use crate::{OperatingSystem, TerminalType};
+
+    /// A `VS Code` integrated terminal on `Linux`, with the given variables set.
+    fn vscode(vars: &[(&str, &str)]) -> Scenario {
+        vars.iter().fold(
+            Scenario::new(OperatingSystem::Linux)
+                .var("TERM_PROGRAM", "vscode")
+                .var("TERM", "xterm-256color")
+                .installed(TerminalType::WezTerm),
+            |scenario, (name, value)| scenario.var(name, value),
+        )
+    }
+
+    #[test]
+    fn codespaces_gitpod_and_local_sessions_are_told_apart() {
+        let codespaces = vscode(&[
+            ("CODESPACES", "true"),
+            ("VSCODE_IPC_HOOK_CLI", "/tmp/vscode-ipc-1234.sock"),
+        ]);
+        let gitpod = vscode(&[("GITPOD_WORKSPACE_ID", "myorg-myrepo-abc123")]);
+        let local = vscode(&[
+            ("VSCODE_IPC_HOOK_CLI", "/run/user/1000/vscode-ipc-1234.sock"),
+            ("VSCODE_GIT_ASKPASS_NODE", "/usr/share/code/code"),
+        ]);
+
+        for (scenario, flavor, remote) in [
+            (&codespaces, VSCodeFlavor::Codespaces, true),
+            (&gitpod, VSCodeFlavor::Gitpod, true),
+            (&local, VSCodeFlavor::Desktop, false),
+        ] {
+            assert_eq!(detect_vscode_flavor_in(&scenario.env), flavor);
+            assert_eq!(flavor.is_remote(), remote, "{flavor}");
+
+            let outcome = {
+                let _globals = crate::test_support::reading_globals();
+                simulate(scenario)
+            };
+            assert_eq!(outcome.terminal, TerminalType::VSCode, "{flavor}");
+            // Remote sessions are skipped before any terminal is probed..
+            if remote {
+                assert_eq!(
+                    outcome.decision,
+                    RelaunchDecision::Skip(SkipReason::RemoteSession),
+                    "{flavor}"
+                );
+                assert_eq!(
+                    (outcome.provider, outcome.command),
+                    (None, None),
+                    "{flavor}"
+                );
+            } else {
+                assert_ne!(
+                    outcome.decision,
+                    RelaunchDecision::Skip(SkipReason::RemoteSession),
+                    "{flavor}"
+                );
+            }
+        }
+
+        // `CODESPACES` must be `true`..
+        let not_codespaces = vscode(&[("CODESPACES", "false")]);
+        assert_eq!(
+            detect_vscode_flavor_in(&not_codespaces.env),
+            VSCodeFlavor::Desktop
+        );
+    }
+
+    #[test]
+    fn every_flavor_is_classified_from_its_variables() {
+        for (vars, flavor) in [
+            (
+                &[("VSCODE_IPC_HOOK_CLI", "/tmp/code-server-ipc-1234.sock")][..],
+                VSCodeFlavor::CodeServer,
+            ),
+            (
+                &[("VSCODE_GIT_ASKPASS_NODE", "/usr/lib/code-server/lib/node")][..],
+                VSCodeFlavor::CodeServer,
+            ),
+            (
+                &[("VSCODE_AGENT_FOLDER", "/home/me/.vscode-server")][..],
+                VSCodeFlavor::RemoteServer,
+            ),
+            (
+                &[(
+                    "VSCODE_GIT_ASKPASS_NODE",
+                    "/home/me/.vscode-server/bin/abc/node",
+                )][..],
+                VSCodeFlavor::RemoteServer,
+            ),
+            (&[("CURSOR_TRACE_ID", "abc")][..], VSCodeFlavor::Cursor),
+            (
+                &[(
+                    "VSCODE_GIT_ASKPASS_NODE",
+                    "/Applications/Cursor.app/Contents/MacOS/Cursor",
+                )][..],
+                VSCodeFlavor::Cursor,
+            ),
+            (
+                &[("VSCODE_GIT_ASKPASS_NODE", "/usr/share/codium/codium")][..],
+                VSCodeFlavor::VSCodium,
+            ),
+            // A remote `Cursor` window is remote..
+            (
+                &[
+                    ("CURSOR_TRACE_ID", "abc"),
+                    ("VSCODE_AGENT_FOLDER", "/home/me/.cursor-server"),
+                ][..],
+                VSCodeFlavor::RemoteServer,
+            ),
+            (&[][..], VSCodeFlavor::Desktop),
+        ] {
+            assert_eq!(
+                detect_vscode_flavor_in(&vscode(vars).env),
+                flavor,
+                "{vars:?}"
+            );
+        }
+    }
+}
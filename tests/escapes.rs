@@ -0,0 +1,129 @@
+//! Checks that escape sequences and line breaks set in the environment never reach the doctor report, the logs, or
+//! the relaunch that would be planned from it, whatever is detected from them.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use terminal_relaunch::OperatingSystem;
+use terminal_relaunch::TerminalType;
+use terminal_relaunch::doctor::DoctorReport;
+use terminal_relaunch::environment::propagated_env_in;
+use terminal_relaunch::info::TerminalInfo;
+use terminal_relaunch::simulation::{Scenario, simulate};
+use terminal_relaunch::terminal_providers::{CwdArg, cwd_argument, env_wrapper_args};
+
+/// Values setting the window title, clearing the screen, forging a line, and a `C1` `CSI`.
+const ESCAPES: &[&str] = &["\x1b]0;pwned\x07", "\x1b[2J", "\r\nfake=1", "\u{9b}31m"];
+
+/// A `Linux` scenario with every reported and propagated variable set to an escape-laden value, with `TERM`
+/// quoted so it's still detected as `foot` (logging the quoted value).
+fn escape_laden_scenario() -> Scenario {
+    let payload: String = ESCAPES.concat();
+    Scenario::new(OperatingSystem::Linux)
+        .var("TERM", &format!("'foot-{payload}'"))
+        .var("TERM_PROGRAM", &format!("evil{payload}"))
+        .var("TERM_PROGRAM_VERSION", &format!("1.0{payload}"))
+        .var("COLORTERM", &format!("truecolor{payload}"))
+        .var("LANG", &format!("de_DE.UTF-8{payload}"))
+        .var("LC_TIME", "en_GB.UTF-8")
+        .var("TZ", &format!("Europe/Berlin{payload}"))
+}
+
+fn assert_no_control_bytes(what: &str, text: &str) {
+    assert!(
+        !text.chars().any(|c| c.is_control() && c != '\n'),
+        "{what} contains control bytes: {text:?}"
+    );
+}
+
+#[test]
+fn doctor_report_strips_control_bytes() {
+    let scenario = escape_laden_scenario();
+    let outcome = simulate(&scenario);
+    assert_eq!(outcome.terminal, TerminalType::Foot);
+
+    let report = DoctorReport::for_info_in(TerminalInfo::new(outcome.terminal), &scenario.env);
+    let text = report.to_string();
+    assert_no_control_bytes("the report", &text);
+    // No line was forged, every variable is still on its own line..
+    assert!(!text.lines().any(|line| line.starts_with("fake=")));
+    assert!(
+        text.contains("TERM='foot-]0;pwned[2Jfake=131m' (compared as foot-]0;pwned[2Jfake=131m)\n")
+    );
+}
+
+#[test]
+fn planned_relaunch_drops_escape_laden_values() {
+    let scenario = escape_laden_scenario();
+
+    // Only the clean variable is propagated, and so exported by the shell wrapper..
+    let propagated = propagated_env_in(&scenario.env);
+    assert_eq!(propagated, [("LC_TIME", "en_GB.UTF-8".to_string())]);
+    let wrapper: Vec<String> = env_wrapper_args(&propagated)
+        .into_iter()
+        .map(|arg| arg.into_string().unwrap())
+        .collect();
+    assert_eq!(
+        wrapper[2],
+        r#"export LC_TIME='en_GB.UTF-8'; exec "$0" "$@""#
+    );
+
+    // ..and working directories typed into `ITerm2` are rejected rather than quoted..
+    for escape in ESCAPES {
+        let dir = format!("/tmp/project{escape}");
+        assert!(cwd_argument(TerminalType::ITerm2, Path::new(&dir)).is_err());
+    }
+    assert!(matches!(
+        cwd_argument(TerminalType::ITerm2, Path::new("/tmp/project")),
+        Ok(CwdArg::Shell(_))
+    ));
+
+    let unchanged = HashMap::from([("TZ".to_string(), "UTC".to_string())]);
+    assert_eq!(propagated_env_in(&unchanged), [("TZ", "UTC".to_string())]);
+}
+
+#[cfg(feature = "logging")]
+mod logs {
+    use std::sync::Mutex;
+
+    use terminal_relaunch::simulation::simulate;
+
+    use super::{assert_no_control_bytes, escape_laden_scenario};
+
+    /// Every message logged by the test binary.
+    static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            LOGGED
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn logged_values_strip_control_bytes() {
+        log::set_logger(&CapturingLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let _ = simulate(&escape_laden_scenario());
+
+        let logged = LOGGED
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // The quoted `TERM` is logged, so the check covers at least one escape-laden value..
+        assert!(logged.iter().any(|message| message.contains("pwned")));
+        for message in logged.iter() {
+            assert_no_control_bytes("a log message", message);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Checks that the capability getters, and everything deciding from them, follow the overrides set at any time,
+//! while the legacy statics stay latched at their first read.
+//!
+//! # Notes
+//! The overrides are process-wide, so the tests serialise on `OVERRIDES`.
+
+use std::sync::{Barrier, Mutex};
+
+use terminal_relaunch::{
+    SUPPORTS_FULL_UNICODE, SUPPORTS_RGB_ANSI_COLOURS, set_rgb_ansi_override,
+    set_unicode_support_override, supports_full_unicode, supports_rgb_ansi_colours,
+};
+
+/// Threads reading the capabilities at once.
+const READERS: usize = 4;
+
+/// Held by tests changing the overrides.
+static OVERRIDES: Mutex<()> = Mutex::new(());
+
+/// Sets both capability overrides.
+fn set_overrides(supports: Option<bool>) {
+    set_unicode_support_override(supports);
+    set_rgb_ansi_override(supports);
+}
+
+#[test]
+fn getters_follow_overrides_across_threads_while_statics_stay_latched() {
+    let _lock = OVERRIDES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let latched = (*SUPPORTS_FULL_UNICODE, *SUPPORTS_RGB_ANSI_COLOURS);
+
+    for supports in [true, false, true] {
+        set_overrides(Some(supports));
+
+        // Every thread sees the override, and none of them unlatches the statics..
+        let barrier = Barrier::new(READERS);
+        std::thread::scope(|scope| {
+            for _ in 0..READERS {
+                scope.spawn(|| {
+                    barrier.wait();
+                    assert_eq!(supports_full_unicode(), supports);
+                    assert_eq!(supports_rgb_ansi_colours(), supports);
+                    assert_eq!(
+                        (*SUPPORTS_FULL_UNICODE, *SUPPORTS_RGB_ANSI_COLOURS),
+                        latched
+                    );
+                });
+            }
+        });
+    }
+
+    set_overrides(None);
+    assert_eq!(
+        (*SUPPORTS_FULL_UNICODE, *SUPPORTS_RGB_ANSI_COLOURS),
+        latched
+    );
+}
+
+#[test]
+fn relaunch_summary_reports_current_overrides() {
+    let _lock = OVERRIDES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let _ = (*SUPPORTS_FULL_UNICODE, *SUPPORTS_RGB_ANSI_COLOURS);
+
+    // Supporting every preferred feature skips the relaunch, so this never exits the test..
+    set_overrides(Some(true));
+    let summary = terminal_relaunch::relaunch_if_available_or_continue();
+    set_overrides(None);
+
+    assert_eq!(
+        (summary.full_unicode, summary.rgb_ansi_colours),
+        (true, true)
+    );
+    assert!(!summary.attempted);
+}
+
+#[test]
+fn overrides_set_after_detection_reach_the_decision() {
+    use terminal_relaunch::config::RelaunchConfig;
+    use terminal_relaunch::decision::{
+        DecisionInputs, FeatureRequirements, RelaunchDecision, SkipReason, decide,
+        relaunch_decision,
+    };
+
+    let _lock = OVERRIDES
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    // Detection has happened, and the statics are latched..
+    let _ = (
+        terminal_relaunch::current_terminal(),
+        *SUPPORTS_FULL_UNICODE,
+        *SUPPORTS_RGB_ANSI_COLOURS,
+    );
+
+    set_overrides(Some(false));
+    let missing = DecisionInputs::current();
+    assert!(!missing.full_unicode && !missing.rgb_ansi_colours);
+
+    set_overrides(Some(true));
+    let supported = DecisionInputs::current();
+    let decision = relaunch_decision();
+    set_overrides(None);
+
+    assert!(supported.full_unicode && supported.rgb_ansi_colours);
+    assert!(!decision.should_attempt());
+    // Only the reasons checked before the capabilities can win over them..
+    let without_earlier_reasons = DecisionInputs {
+        full_unicode: supported.full_unicode,
+        rgb_ansi_colours: supported.rgb_ansi_colours,
+        ..DecisionInputs::new(terminal_relaunch::TerminalType::Unknown)
+    };
+    assert_eq!(
+        decide(
+            &without_earlier_reasons,
+            &RelaunchConfig::new(),
+            &FeatureRequirements::PREFERRED
+        ),
+        RelaunchDecision::Skip(SkipReason::AlreadyPreferred)
+    );
+}
@@ -0,0 +1,257 @@
+//! End-to-end tests relaunching a helper program (`examples/relaunch_probe.rs`) in the real terminals, to catch
+//! changes in how the terminals' command lines (e.g. `wt`, `kitty` or `open`) are handled.
+//!
+//! # Notes
+//! *   These open windows, so only run when `TERMINAL_RELAUNCH_E2E=1` is set, e.g.
+//!     `TERMINAL_RELAUNCH_E2E=1 cargo test --test real_relaunch`.
+//! *   The test of each terminal is skipped when it isn't installed.
+//! *   The probe exits once it has written its result, which closes the window in the default configuration of
+//!     every terminal. Windows left open by `Kitty` and `WezTerm` are also closed through their CLIs (`Kitty` needs
+//!     `allow_remote_control` for this). Other terminals configured to keep windows open after their program exits
+//!     (e.g. `closeOnExit` set to `never` in `Windows Terminal`, or `wait-after-command` in `Ghostty`) leave a
+//!     window to close manually per run.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use terminal_relaunch::{TerminalType, get_provider_for_terminal};
+
+/// Environment variable opting in to these tests.
+const E2E_VAR: &str = "TERMINAL_RELAUNCH_E2E";
+
+/// Environment variable passed to the probe, identifying the run.
+const TOKEN_VAR: &str = "TERMINAL_RELAUNCH_E2E_TOKEN";
+
+/// Arguments passed to the probe, which must arrive unchanged, whatever quoting each terminal needs.
+const PROBE_ARGS: &[&str] = &[
+    "plain",
+    "with space",
+    "it's \"quoted\"",
+    "$HOME and %PATH%",
+    r"trailing\",
+    "",
+];
+
+/// Locale and time zone the probe is started with, which must reach it in every terminal, see
+/// `environment::propagated_env()`. Unlikely to be any terminal's default, so they can't arrive by accident.
+const PROBE_LOCALE: &[(&str, &str)] = &[
+    ("LANG", "en_DK.UTF-8"),
+    ("LC_TIME", "en_IE.UTF-8"),
+    ("TZ", "Pacific/Chatham"),
+];
+
+/// How long to wait for the probe to write its result, including the terminal starting up.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the result file is checked for.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The result written by the relaunched probe.
+#[derive(Debug, Default)]
+struct ProbeResult {
+    /// The terminal the probe detected.
+    terminal: String,
+    /// The working directory of the probe.
+    cwd: PathBuf,
+    /// The arguments of the probe, after the terminal and result file.
+    args: Vec<String>,
+    /// The recorded environment variables that were set.
+    env: HashMap<String, String>,
+}
+
+impl ProbeResult {
+    /// Parses the result file contents, see `examples/relaunch_probe.rs`.
+    fn parse(contents: &str) -> Self {
+        let mut result = Self::default();
+        for line in contents.lines() {
+            match line.split_once('\t') {
+                Some(("terminal", terminal)) => result.terminal = terminal.to_string(),
+                Some(("cwd", cwd)) => result.cwd = PathBuf::from(cwd),
+                Some(("arg", arg)) => result.args.push(arg.to_string()),
+                Some(("env", var)) => {
+                    let (name, value) = var.split_once('\t').unwrap_or((var, ""));
+                    result.env.insert(name.to_string(), value.to_string());
+                }
+                _ => panic!("Unexpected line in the probe result: {line:?}"),
+            }
+        }
+        result
+    }
+}
+
+/// A directory for a single run, removed on drop.
+struct RunDirectory(PathBuf);
+
+impl Drop for RunDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns `true` if the end-to-end tests are enabled, see `E2E_VAR`.
+fn enabled() -> bool {
+    std::env::var(E2E_VAR).is_ok_and(|value| value == "1")
+}
+
+/// Returns the path of the probe, built by `cargo test` with the other examples.
+fn probe_path() -> PathBuf {
+    // Tests are built into `target/<profile>/deps`, and examples into `target/<profile>/examples`..
+    let test_exe = std::env::current_exe().expect("Failed to get the test executable path");
+    let probe = test_exe
+        .parent()
+        .and_then(Path::parent)
+        .expect("Test executable is not in a target directory")
+        .join("examples")
+        .join(format!("relaunch_probe{}", std::env::consts::EXE_SUFFIX));
+
+    assert!(
+        probe.is_file(),
+        "The probe was not found at {}, build it with `cargo test` or `cargo build --examples`.",
+        probe.display()
+    );
+    probe
+}
+
+/// Returns a token unique to this run.
+fn unique_token(terminal: TerminalType) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    format!("{}-{}-{nanos:08x}", terminal.id(), std::process::id())
+}
+
+/// Waits for the probe to write its result file, returning `None` on timeout.
+fn wait_for_result(path: &Path, timeout: Duration) -> Option<ProbeResult> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        // The probe renames the file into place once written..
+        if let Ok(contents) = std::fs::read_to_string(path) {
+            return Some(ProbeResult::parse(&contents));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    None
+}
+
+/// Closes the window the probe ran in, where the terminal has a CLI to do so.
+fn close_window(terminal: TerminalType, result: &ProbeResult) {
+    // Best effort, the window has usually closed with the probe already..
+    match terminal {
+        TerminalType::Kitty => {
+            if let Some(id) = result.env.get("KITTY_WINDOW_ID") {
+                let to = result
+                    .env
+                    .get("KITTY_LISTEN_ON")
+                    .map(|listen_on| format!("--to={listen_on}"));
+                let _ = Command::new("kitten")
+                    .args(["@", "close-window", "--ignore-no-match"])
+                    .args(to)
+                    .arg(format!("--match=id:{id}"))
+                    .status();
+            }
+        }
+        TerminalType::WezTerm => {
+            if let Some(pane) = result.env.get("WEZTERM_PANE") {
+                let _ = Command::new("wezterm")
+                    .args(["cli", "kill-pane", "--pane-id", pane])
+                    .status();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Relaunches the probe in the given terminal, and checks what it received.
+fn relaunch_in(terminal: TerminalType) {
+    if !enabled() {
+        eprintln!("Skipping {terminal}, set {E2E_VAR}=1 to run the end-to-end tests.");
+        return;
+    }
+    let provider = get_provider_for_terminal(terminal)
+        .unwrap_or_else(|| panic!("{terminal} has no terminal provider"));
+    if !provider.is_installed() {
+        eprintln!("Skipping {terminal}, as it is not installed.");
+        return;
+    }
+
+    let token = unique_token(terminal);
+    // A space in the working directory, as it's passed to each terminal differently..
+    let run_dir = RunDirectory(std::env::temp_dir().join(format!("terminal relaunch e2e {token}")));
+    std::fs::create_dir_all(&run_dir.0).expect("Failed to create the run directory");
+    let result_file = run_dir.0.join("result.txt");
+
+    let status = Command::new(probe_path())
+        .arg(terminal.id())
+        .arg(&result_file)
+        .args(PROBE_ARGS)
+        .current_dir(&run_dir.0)
+        .env(TOKEN_VAR, &token)
+        .envs(PROBE_LOCALE.iter().copied())
+        .status()
+        .expect("Failed to start the probe");
+    assert!(
+        status.success(),
+        "The probe failed to relaunch in {terminal}: {status}"
+    );
+
+    let result = wait_for_result(&result_file, TIMEOUT)
+        .unwrap_or_else(|| panic!("{terminal} did not run the probe within {TIMEOUT:?}"));
+    close_window(terminal, &result);
+
+    assert_eq!(result.terminal, terminal.id(), "Detected terminal");
+    assert_eq!(result.args, PROBE_ARGS, "Arguments");
+    assert_eq!(
+        result.cwd.canonicalize().ok(),
+        run_dir.0.canonicalize().ok(),
+        "Working directory"
+    );
+    for (name, value) in PROBE_LOCALE {
+        assert_eq!(
+            result.env.get(*name).map(String::as_str),
+            Some(*value),
+            "{name}"
+        );
+    }
+    // Not passed on through `open` on `MacOS`, but must never be another run's..
+    if let Some(received) = result.env.get(TOKEN_VAR) {
+        assert_eq!(received, &token, "{TOKEN_VAR}");
+    }
+}
+
+#[test]
+fn windows_terminal() {
+    relaunch_in(TerminalType::WindowsTerminal);
+}
+
+#[test]
+fn iterm2() {
+    relaunch_in(TerminalType::ITerm2);
+}
+
+#[test]
+fn ghostty() {
+    relaunch_in(TerminalType::Ghostty);
+}
+
+#[test]
+fn kitty() {
+    relaunch_in(TerminalType::Kitty);
+}
+
+#[test]
+fn alacritty() {
+    relaunch_in(TerminalType::Alacritty);
+}
+
+#[test]
+fn wezterm() {
+    relaunch_in(TerminalType::WezTerm);
+}
+
+#[test]
+fn ptyxis() {
+    relaunch_in(TerminalType::Ptyxis);
+}
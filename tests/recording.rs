@@ -0,0 +1,111 @@
+//! Checks the pattern applications use to assert, in their own tests, which terminal they would relaunch in and
+//! how, with `RecordingProvider`s registered through a `ScopedRegistry` rather than any terminal installed.
+//!
+//! # Notes
+//! Registrations are process-wide, so the tests serialise on `REGISTRY`.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use terminal_relaunch::config::{RelaunchConfig, WindowMode, relaunch_config, set_relaunch_config};
+use terminal_relaunch::pipeline::RelaunchPipeline;
+use terminal_relaunch::recording::{RecordedLaunch, RecordingProvider};
+use terminal_relaunch::registry::{ScopedRegistry, registered_provider};
+use terminal_relaunch::{
+    OperatingSystem, TerminalType, get_preferred_terminals_for_os, open_terminal_here,
+};
+
+/// Held by tests registering providers.
+static REGISTRY: Mutex<()> = Mutex::new(());
+
+/// Registers a recorder for every terminal a relaunch may select, for the lifetime of the returned scope.
+fn record_preferred_terminals(
+    recorder: impl Fn(TerminalType) -> RecordingProvider,
+) -> (ScopedRegistry, Vec<RecordingProvider>) {
+    let recorders: Vec<RecordingProvider> =
+        get_preferred_terminals_for_os(OperatingSystem::current())
+            .map(recorder)
+            .collect();
+    let mut scope = ScopedRegistry::new();
+    for recorder in &recorders {
+        scope.register(recorder.clone());
+    }
+    (scope, recorders)
+}
+
+fn launches(recorders: &[RecordingProvider]) -> Vec<RecordedLaunch> {
+    recorders
+        .iter()
+        .flat_map(RecordingProvider::launches)
+        .collect()
+}
+
+#[test]
+fn a_relaunch_is_recorded_with_the_program_and_its_arguments() {
+    let _lock = REGISTRY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let config = relaunch_config();
+    set_relaunch_config(RelaunchConfig::new().window_mode(WindowMode::Tab));
+    let (scope, recorders) = record_preferred_terminals(RecordingProvider::new);
+
+    let relaunched = RelaunchPipeline::without_decision()
+        .with_current_terminal(TerminalType::Unknown)
+        .into_result()
+        .unwrap();
+    assert!(relaunched);
+
+    let launches = launches(&recorders);
+    assert_eq!(launches.len(), 1, "{launches:?}");
+    let launch = &launches[0];
+    assert_eq!(launch.window_mode, WindowMode::Tab);
+    assert_eq!(launch.program, Some(std::env::current_exe().unwrap()));
+    assert_eq!(
+        launch.args.first(),
+        Some(&RelaunchConfig::new().relaunched_argument())
+    );
+    assert_eq!(launch.working_directory, std::env::current_dir().unwrap());
+
+    drop(scope);
+    assert!(registered_provider(launch.terminal).is_none());
+    set_relaunch_config(config);
+}
+
+#[test]
+fn terminals_missing_a_dependency_are_never_launched() {
+    let _lock = REGISTRY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let (_scope, recorders) = record_preferred_terminals(|terminal| {
+        RecordingProvider::new(terminal).with_missing_dependency("osascript")
+    });
+
+    assert!(
+        RelaunchPipeline::without_decision()
+            .with_current_terminal(TerminalType::Unknown)
+            .into_result()
+            .is_err()
+    );
+    assert!(open_terminal_here(Path::new("/")).is_err());
+    assert!(launches(&recorders).is_empty());
+}
+
+#[test]
+fn a_shell_is_recorded_in_a_new_window_of_the_given_directory() {
+    let _lock = REGISTRY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let (_scope, recorders) = record_preferred_terminals(RecordingProvider::new);
+    let dir = std::env::temp_dir();
+
+    let terminal = open_terminal_here(&dir).unwrap();
+
+    let launches = launches(&recorders);
+    assert_eq!(launches.len(), 1, "{launches:?}");
+    let launch = &launches[0];
+    assert_eq!(launch.terminal, terminal);
+    assert_eq!(launch.window_mode, WindowMode::Window);
+    assert_eq!(launch.program, None);
+    assert!(launch.args.is_empty());
+    assert_eq!(launch.working_directory, std::path::absolute(&dir).unwrap());
+}
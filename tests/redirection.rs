@@ -0,0 +1,153 @@
+//! Checks the stream user-facing output is written to, and the links formatted for it, in a child process with its
+//! standard output and error redirected in every combination.
+//!
+//! # Notes
+//! *   The child is this test binary, running only `child_reports_its_output`.
+//! *   Terminals are pseudo-terminals, which need `libc`, so with the `tty-query` feature on unix only. Otherwise
+//!     only fully piped output is checked.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use terminal_relaunch::output::{format_link, user_channel};
+
+/// Environment variable telling the child where to write its report.
+const REPORT_VAR: &str = "TERMINAL_RELAUNCH_REDIRECTION_REPORT";
+
+/// The link formatted by the child.
+const URL: &str = "https://example.com";
+const LABEL: &str = "Example";
+
+/// Writes the user channel and a formatted link to the report file, when run as the child.
+#[test]
+fn child_reports_its_output() {
+    let Some(report) = std::env::var_os(REPORT_VAR) else {
+        return;
+    };
+
+    let text = format!("{:?}\n{}", user_channel(), format_link(URL, LABEL));
+    std::fs::write(report, text).unwrap();
+}
+
+/// A standard stream of the child, piped or a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Redirect {
+    Piped,
+    #[cfg_attr(not(all(unix, feature = "tty-query")), allow(dead_code))]
+    Terminal,
+}
+
+/// Runs the child with the given streams, in `WezTerm` (supporting hyperlinks), returning its report.
+fn run_child(stdout: Redirect, stderr: Redirect, test: &str) -> String {
+    let report = std::env::temp_dir().join(format!(
+        "terminal_relaunch-{}-redirection-{test}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&report);
+
+    // Held open until the child exits, so writing to the terminal never fails..
+    #[cfg(all(unix, feature = "tty-query"))]
+    let terminal = pty::open();
+    let stream = |redirect| match redirect {
+        Redirect::Piped => Stdio::piped(),
+        #[cfg(all(unix, feature = "tty-query"))]
+        Redirect::Terminal => Stdio::from(terminal.1.try_clone().unwrap()),
+        #[cfg(not(all(unix, feature = "tty-query")))]
+        Redirect::Terminal => unreachable!("terminals need `libc`"),
+    };
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "child_reports_its_output", "--test-threads=1"])
+        .env_clear()
+        .env("TERM_PROGRAM", "WezTerm")
+        .env("TERM", "xterm-256color")
+        .env(REPORT_VAR, &report)
+        .stdin(Stdio::null())
+        .stdout(stream(stdout))
+        .stderr(stream(stderr))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let text = std::fs::read_to_string(&report).unwrap();
+    let _ = std::fs::remove_file(PathBuf::from(&report));
+    text
+}
+
+/// Returns the expected report for the given user channel.
+fn expected(channel: Option<&str>) -> String {
+    let link = if channel.is_some() {
+        format!("\x1b]8;;{URL}\x1b\\{LABEL}\x1b]8;;\x1b\\")
+    } else {
+        format!("{LABEL} ({URL})")
+    };
+    let channel = channel.map_or("None".to_string(), |stream| format!("Some({stream})"));
+    format!("{channel}\n{link}")
+}
+
+#[test]
+fn piped_output_has_no_user_channel_or_hyperlinks() {
+    if std::env::var_os(REPORT_VAR).is_some() {
+        return;
+    }
+
+    assert_eq!(
+        run_child(Redirect::Piped, Redirect::Piped, "piped"),
+        expected(None)
+    );
+}
+
+#[cfg(all(unix, feature = "tty-query"))]
+#[test]
+fn links_follow_the_user_channel_in_every_redirection() {
+    if std::env::var_os(REPORT_VAR).is_some() {
+        return;
+    }
+
+    // (stdout, stderr, user channel)..
+    for (stdout, stderr, channel) in [
+        (Redirect::Terminal, Redirect::Terminal, Some("Stdout")),
+        (Redirect::Terminal, Redirect::Piped, Some("Stdout")),
+        // e.g. `myapp | jq`, where links are written to the terminal on standard error..
+        (Redirect::Piped, Redirect::Terminal, Some("Stderr")),
+        (Redirect::Piped, Redirect::Piped, None),
+    ] {
+        assert_eq!(
+            run_child(stdout, stderr, "combinations"),
+            expected(channel),
+            "stdout: {stdout:?}, stderr: {stderr:?}"
+        );
+    }
+}
+
+/// Opening pseudo-terminals for the child.
+#[cfg(all(unix, feature = "tty-query"))]
+mod pty {
+    use std::ffi::CStr;
+    use std::fs::File;
+    use std::os::fd::FromRawFd;
+
+    /// Opens a pseudo-terminal, returning its controlling and terminal ends.
+    pub(crate) fn open() -> (File, File) {
+        // SAFETY: The controlling end is a new descriptor owned by the returned file, and `ptsname()` is only
+        // called from the thread running the test..
+        unsafe {
+            let controlling = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(controlling >= 0, "{}", std::io::Error::last_os_error());
+            let controlling_file = File::from_raw_fd(controlling);
+            assert_eq!(libc::grantpt(controlling), 0);
+            assert_eq!(libc::unlockpt(controlling), 0);
+
+            let name = libc::ptsname(controlling);
+            assert!(!name.is_null());
+            let path = CStr::from_ptr(name).to_str().unwrap().to_string();
+            let terminal = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .unwrap();
+
+            (controlling_file, terminal)
+        }
+    }
+}
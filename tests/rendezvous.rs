@@ -0,0 +1,149 @@
+//! Checks the full rendezvous handshake: a launcher relaunches this program through a provider running the relaunch
+//! command directly, and waits until the relaunched program reads its handoff.
+//!
+//! # Notes
+//! This test has no harness (see `Cargo.toml`), as the relaunched program is this binary, started with the relaunch
+//! arguments rather than the harness's.
+
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use terminal_relaunch::config::WindowMode;
+use terminal_relaunch::errors::TermResult;
+use terminal_relaunch::handoff::read_relaunch_handoff;
+use terminal_relaunch::pipeline::RelaunchPipeline;
+use terminal_relaunch::recording::RecordingProvider;
+use terminal_relaunch::rendezvous::{Rendezvous, signal_relaunch_ready, with_rendezvous};
+use terminal_relaunch::{
+    OperatingSystem, TerminalProvider, TerminalType, get_preferred_terminals_for_os,
+    has_been_relaunched,
+};
+
+/// Environment variable telling the relaunched program to exit without reading its handoff, as if it crashed.
+const SILENT_VAR: &str = "TERMINAL_RELAUNCH_RENDEZVOUS_SILENT";
+
+/// How long the launcher waits for a relaunched program that signals.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A provider running the relaunch command (`{program} {args}`) itself, as a terminal started with it would.
+#[derive(Debug, Clone)]
+struct CommandTemplateProvider {
+    /// Works out the command, as the provider of a real terminal would.
+    recorder: RecordingProvider,
+    /// Whether the relaunched program exits without signalling.
+    silent: bool,
+    /// The relaunched programs.
+    children: Arc<Mutex<Vec<Child>>>,
+}
+
+impl TerminalProvider for CommandTemplateProvider {
+    fn terminal_type(&self) -> TerminalType {
+        self.recorder.terminal_type()
+    }
+
+    fn is_installed(&self) -> bool {
+        true
+    }
+
+    fn supports_window_mode(&self, _mode: WindowMode) -> bool {
+        true
+    }
+
+    fn relaunch_in_terminal(&self) -> TermResult<()> {
+        self.recorder.relaunch_in_terminal()?;
+        let launch = self.recorder.last_launch().expect("the launch is recorded");
+
+        let mut command = Command::new(launch.program.expect("a program is relaunched"));
+        command
+            .args(&launch.args)
+            .current_dir(&launch.working_directory)
+            .stdin(Stdio::null());
+        if self.silent {
+            command.env(SILENT_VAR, "1");
+        }
+        self.children.lock().unwrap().push(command.spawn()?);
+
+        Ok(())
+    }
+}
+
+/// Relaunches this program through a `CommandTemplateProvider`, returning the rendezvous and the relaunched program.
+fn relaunch(silent: bool) -> (Rendezvous, Child) {
+    let terminal = get_preferred_terminals_for_os(OperatingSystem::current())
+        .next()
+        .expect("a preferred terminal");
+    let provider = CommandTemplateProvider {
+        recorder: RecordingProvider::new(terminal),
+        silent,
+        children: Arc::default(),
+    };
+    let pipeline = RelaunchPipeline::without_decision()
+        .with_current_terminal(TerminalType::Unknown)
+        .with_providers({
+            let provider = provider.clone();
+            move |candidate| {
+                (candidate == terminal)
+                    .then(|| Box::new(provider.clone()) as Box<dyn TerminalProvider>)
+            }
+        });
+
+    let rendezvous =
+        with_rendezvous(|| pipeline.into_result().map(|relaunched| assert!(relaunched))).unwrap();
+    let mut children = provider.children.lock().unwrap();
+    assert_eq!(children.len(), 1);
+    (rendezvous, children.pop().unwrap())
+}
+
+/// The relaunched program: signals by reading its handoff, unless told to stay silent.
+fn relaunched() {
+    if std::env::var_os(SILENT_VAR).is_some() {
+        return;
+    }
+
+    let handoff = read_relaunch_handoff().expect("the handoff is passed");
+    assert!(handoff.rendezvous.is_some());
+    // ..and signalling again doesn't reach the launcher twice..
+    assert!(signal_relaunch_ready().unwrap());
+}
+
+fn the_launcher_waits_until_the_relaunched_program_reads_its_handoff() {
+    let (rendezvous, mut child) = relaunch(false);
+    assert!(rendezvous.wait_ready(TIMEOUT).unwrap());
+    assert!(child.wait().unwrap().success());
+
+    let path = rendezvous.path().to_path_buf();
+    drop(rendezvous);
+    assert!(!path.exists());
+}
+
+fn a_relaunched_program_that_never_signals_times_out() {
+    let (rendezvous, mut child) = relaunch(true);
+    assert!(child.wait().unwrap().success());
+    assert!(!rendezvous.wait_ready(Duration::from_millis(200)).unwrap());
+
+    let path = rendezvous.path().to_path_buf();
+    drop(rendezvous);
+    assert!(!path.exists());
+}
+
+fn main() {
+    if has_been_relaunched() {
+        relaunched();
+        return;
+    }
+
+    for (name, test) in [
+        (
+            "the_launcher_waits_until_the_relaunched_program_reads_its_handoff",
+            the_launcher_waits_until_the_relaunched_program_reads_its_handoff as fn(),
+        ),
+        (
+            "a_relaunched_program_that_never_signals_times_out",
+            a_relaunched_program_that_never_signals_times_out,
+        ),
+    ] {
+        test();
+        println!("test {name} ... ok");
+    }
+}
@@ -0,0 +1,133 @@
+//! Checks that `runtime::init()` racing the first detection on many threads applies its configuration to every
+//! detection or not at all, and that setters follow the mutability matrix either way.
+//!
+//! # Notes
+//! The first detection only happens once per process, so the race runs in fresh child processes of this test
+//! binary, marked by `CHILD_VAR`.
+
+use std::process::Command;
+use std::sync::Barrier;
+
+use terminal_relaunch::capabilities::{CapabilityDetection, set_capability_detection};
+use terminal_relaunch::config::{RelaunchConfig, relaunch_config, set_relaunch_config};
+use terminal_relaunch::output::{OutputStyle, output_style, set_output_style};
+use terminal_relaunch::runtime::{AlreadyInitialized, RuntimeConfig, init, is_initialized};
+use terminal_relaunch::{SUPPORTS_FULL_UNICODE, current_terminal, is_unicode_overridden};
+
+/// Environment variable set in the child processes running the race.
+const CHILD_VAR: &str = "TERMINAL_RELAUNCH_INIT_RACE_CHILD";
+
+/// Child processes racing, each a fresh first detection.
+const RUNS: usize = 8;
+
+/// Threads racing in each child, alternately initializing and detecting.
+const THREADS: usize = 8;
+
+/// The full unicode support the racing `init()` calls override.
+const OVERRIDE: bool = false;
+
+/// The configuration the racing `init()` calls apply.
+fn racing_config() -> RuntimeConfig {
+    RuntimeConfig {
+        unicode_override: Some(OVERRIDE),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn init_racing_detection_applies_all_or_nothing() {
+    if std::env::var_os(CHILD_VAR).is_some() {
+        race();
+        return;
+    }
+
+    for _ in 0..RUNS {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "init_racing_detection_applies_all_or_nothing",
+                "--test-threads=1",
+            ])
+            .env(CHILD_VAR, "1")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+}
+
+/// Races `init()` against the first detection, then checks the outcome, in a fresh process.
+fn race() {
+    // Staged before anything is initialized..
+    set_relaunch_config(RelaunchConfig::new().marker_namespace("staged"));
+    assert_eq!(
+        set_capability_detection(CapabilityDetection::Environment),
+        Ok(())
+    );
+    assert!(!is_initialized());
+
+    let barrier = Barrier::new(THREADS);
+    let initialized = std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..THREADS)
+            .map(|index| {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    barrier.wait();
+                    if index % 2 == 0 {
+                        init(racing_config()).is_ok()
+                    } else {
+                        let _ = (current_terminal(), *SUPPORTS_FULL_UNICODE);
+                        false
+                    }
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .filter(|initialized| *initialized)
+            .count()
+    });
+
+    // At most one `init()` won, and if one did, every detection saw its configuration..
+    assert!(initialized <= 1, "{initialized} calls initialized");
+    assert!(is_initialized());
+    if initialized == 1 {
+        assert_eq!(is_unicode_overridden(), Some(OVERRIDE));
+        assert_eq!(*SUPPORTS_FULL_UNICODE, OVERRIDE);
+    } else {
+        assert_eq!(is_unicode_overridden(), None);
+    }
+    assert_eq!(init(racing_config()).err(), Some(AlreadyInitialized));
+
+    // ..the staged values it didn't replace were kept, and the mutability matrix holds after..
+    assert_eq!(
+        relaunch_config().marker_namespace.as_deref(),
+        Some("staged")
+    );
+    set_relaunch_config(RelaunchConfig::new());
+    assert_eq!(relaunch_config(), RelaunchConfig::new());
+    set_output_style(OutputStyle::PlainAscii);
+    assert_eq!(output_style(), OutputStyle::PlainAscii);
+    assert_eq!(
+        set_capability_detection(CapabilityDetection::Probe),
+        Err(AlreadyInitialized)
+    );
+
+    #[cfg(feature = "test-util")]
+    {
+        // ..and tearing down allows initializing again, with detection still latched..
+        let terminal = current_terminal();
+        terminal_relaunch::runtime::reset_all_global_state();
+        assert!(!is_initialized());
+        let guard = init(racing_config()).unwrap();
+        assert_eq!(is_unicode_overridden(), Some(OVERRIDE));
+        guard.tear_down();
+        assert!(!is_initialized());
+        assert_eq!(is_unicode_overridden(), None);
+        assert_eq!(current_terminal(), terminal);
+    }
+}
@@ -0,0 +1,80 @@
+//! Checks `SUPPORT_MATRIX.md` against the support matrix rendered from the live tables, see `support`.
+//!
+//! # Notes
+//! After changing a detection or capability table, regenerate the matrix with
+//! `UPDATE_SUPPORT_MATRIX=1 cargo test --test support_matrix -- --ignored regenerate_matrix`, and review the
+//! changes to it with the table change.
+
+use std::path::PathBuf;
+
+use terminal_relaunch::support::render_support_matrix;
+
+/// Environment variable allowing `regenerate_matrix` to write the matrix.
+const UPDATE_VAR: &str = "UPDATE_SUPPORT_MATRIX";
+
+/// Returns the path of the checked-in matrix.
+fn matrix_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("SUPPORT_MATRIX.md")
+}
+
+/// Returns the lines that differ between the checked-in and rendered matrices, as a minimal line diff.
+fn line_diff(checked_in: &str, rendered: &str) -> String {
+    let old: Vec<&str> = checked_in.lines().collect();
+    let new: Vec<&str> = rendered.lines().collect();
+
+    // Longest common subsequence table, the matrix is small enough for the quadratic approach..
+    let mut lcs = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[test]
+fn support_matrix_is_current() {
+    let path = matrix_path();
+    let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+    let rendered = render_support_matrix();
+
+    // Compared by lines, so checkouts converting line endings still pass..
+    if !checked_in.lines().eq(rendered.lines()) {
+        panic!(
+            "{} is out of date with the detection and capability tables:\n\n{}\n\
+             Regenerate it with `{UPDATE_VAR}=1 cargo test --test support_matrix -- --ignored regenerate_matrix`.",
+            path.display(),
+            line_diff(&checked_in, &rendered),
+        );
+    }
+}
+
+#[test]
+#[ignore = "writes SUPPORT_MATRIX.md, run explicitly to regenerate it"]
+fn regenerate_matrix() {
+    assert!(
+        std::env::var(UPDATE_VAR).is_ok_and(|value| value == "1"),
+        "Set {UPDATE_VAR}=1 to regenerate the support matrix."
+    );
+    std::fs::write(matrix_path(), render_support_matrix())
+        .expect("Failed to write the support matrix");
+}
@@ -0,0 +1,113 @@
+//! Checks the font family read from sample `Windows Terminal` `settings.json` files, across the schema versions in
+//! `tests/fixtures/wt_settings`, and the emoji rendering risk it results in.
+#![cfg(feature = "wt-settings")]
+
+use std::path::PathBuf;
+
+use terminal_relaunch::TerminalType;
+use terminal_relaunch::fonts::{
+    EmojiRisk, WT_DEFAULT_FONT, emoji_rendering_risk_for, wt_font_family_from_settings,
+};
+
+/// GUID of the built-in `Windows PowerShell` profile.
+const WINDOWS_POWERSHELL: &str = "{61c54bbd-c2c6-5271-96e7-009a87ff44bf}";
+/// GUID of the `PowerShell 7` profile.
+const POWERSHELL: &str = "{574e775e-4f2a-5b96-ac1e-a2962a402336}";
+/// GUID of the built-in `Command Prompt` profile.
+const COMMAND_PROMPT: &str = "{0caa0dad-35be-5f56-a8ff-afceeeaa6101}";
+/// GUID of a `WSL` `Ubuntu` profile.
+const UBUNTU: &str = "{2c4de342-38b7-51cf-b940-2309a097f518}";
+
+/// Returns the contents of the given fixture.
+fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("wt_settings")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+/// Returns the font of the given profile in the given fixture.
+fn font(name: &str, profile: Option<&str>) -> Option<String> {
+    wt_font_family_from_settings(&fixture(name), profile)
+}
+
+#[test]
+fn fonts_follow_the_defaults_and_profile_overrides() {
+    for (name, profile, expected) in [
+        // Current schema, `font.face` in `profiles.defaults` and the profile list..
+        ("v1_current.json", None, "CaskaydiaCove Nerd Font"),
+        (
+            "v1_current.json",
+            Some(POWERSHELL),
+            "CaskaydiaCove Nerd Font",
+        ),
+        ("v1_current.json", Some(WINDOWS_POWERSHELL), "Cascadia Code"),
+        (
+            "v1_current.json",
+            Some(COMMAND_PROMPT),
+            "CaskaydiaCove Nerd Font",
+        ),
+        (
+            "v1_current.json",
+            Some("{00000000-0000-0000-0000-000000000000}"),
+            "CaskaydiaCove Nerd Font",
+        ),
+        // ..`fontFace` in the same structure..
+        ("v1_font_face.json", None, "Consolas"),
+        ("v1_font_face.json", Some(UBUNTU), "Hack NF"),
+        // ..a plain profile array, matching GUIDs ignoring case..
+        ("v0_profiles_array.json", None, "Fira Code"),
+        (
+            "v0_profiles_array.json",
+            Some(WINDOWS_POWERSHELL),
+            "Consolas",
+        ),
+        // ..and no font configured at all..
+        ("no_font.json", None, WT_DEFAULT_FONT),
+        ("no_font.json", Some(WINDOWS_POWERSHELL), WT_DEFAULT_FONT),
+    ] {
+        assert_eq!(
+            font(name, profile).as_deref(),
+            Some(expected),
+            "{name} {profile:?}"
+        );
+    }
+
+    // ..while settings that can't be parsed give no font..
+    assert_eq!(font("truncated.json", None), None);
+    assert_eq!(wt_font_family_from_settings("", None), None);
+}
+
+#[test]
+fn fixture_fonts_give_the_expected_emoji_risk() {
+    let risk = |name, profile| {
+        emoji_rendering_risk_for(
+            TerminalType::WindowsTerminal,
+            font(name, profile).as_deref(),
+        )
+    };
+
+    assert_eq!(risk("v1_current.json", None), EmojiRisk::High);
+    assert_eq!(
+        risk("v1_current.json", Some(WINDOWS_POWERSHELL)),
+        EmojiRisk::Low
+    );
+    assert_eq!(risk("v1_font_face.json", None), EmojiRisk::Low);
+    assert_eq!(risk("v1_font_face.json", Some(UBUNTU)), EmojiRisk::High);
+    assert_eq!(risk("v0_profiles_array.json", None), EmojiRisk::Unknown);
+    assert_eq!(risk("no_font.json", None), EmojiRisk::Low);
+    assert_eq!(risk("truncated.json", None), EmojiRisk::Unknown);
+
+    // The font only matters in `Windows Terminal`..
+    let nerd_font = font("v1_current.json", None);
+    assert_eq!(
+        emoji_rendering_risk_for(TerminalType::WezTerm, nerd_font.as_deref()),
+        EmojiRisk::Low
+    );
+    assert_eq!(
+        emoji_rendering_risk_for(TerminalType::WindowsCMD, nerd_font.as_deref()),
+        EmojiRisk::High
+    );
+}